@@ -0,0 +1,18 @@
+//Stores the hash of the current commit for `lunar_engine::build_info`
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+
+    let hash = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8(o.stdout).ok());
+
+    if let Some(hash) = hash {
+        println!("cargo:rustc-env=LUNAR_ENGINE_GIT_HASH={}", hash.trim());
+    }
+}
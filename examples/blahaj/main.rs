@@ -9,6 +9,7 @@ use lunar_engine::{
         mesh::Mesh,
         transform::Transform,
     },
+    determinism::random_vec3,
     ecs::{Component, ComponentReference, EntityBuilder, World},
     input,
     math::Vec3,
@@ -129,9 +130,9 @@ fn run(state: &mut MyState) {
         state.world.add_entity(
             EntityBuilder::new()
                 .create_component(|| Transform {
-                    // scale: Vec3::random(0.3, 3.0),
-                    rotation: Vec3::random(0.0, 360.0),
-                    position: Vec3::random(-5.0, 5.0),
+                    // scale: random_vec3(0.3, 3.0),
+                    rotation: random_vec3(0.0, 360.0),
+                    position: random_vec3(-5.0, 5.0),
                     ..Default::default()
                 })
                 .create_component(|| Mesh::new(state.blahaj_mesh, state.blahaj_mat))
@@ -4,6 +4,7 @@ use lunar_engine::{
     assets::{self, materials::ColorUnlit, mesh::SphereData},
     components::{camera::MainCamera, mesh::Mesh, transform::Transform},
     delta_time,
+    determinism::random_vec3,
     ecs::{Component, ComponentReference, EntityBuilder, World},
     input::{self, CursorLock, CursorVisibily, KeyState},
     math::{lerp, Mat4x4, Vec3, Vector},
@@ -120,7 +121,7 @@ fn generate_scene(world: &mut World, assets: &mut AssetStore, num_objects: u32,
     let mut rng = rand::thread_rng();
 
     for _ in 0..num_colors {
-        // colors.push(assets.register(ColorUnlit::new(Vec3::random(0.0, 1.0).into())));
+        // colors.push(assets.register(ColorUnlit::new(random_vec3(0.0, 1.0).into())));
         colors.push(assets.register(ColorUnlit::new(Color::from_hsl(
             rng.gen_range(0.0..360.0),
             rng.gen_range(0.5..1.0),
@@ -135,9 +136,9 @@ fn generate_scene(world: &mut World, assets: &mut AssetStore, num_objects: u32,
         world.add_entity(
             EntityBuilder::new()
                 .create_component(|| Transform {
-                    position: Vec3::random(-20.0, 20.0),
-                    rotation: Vec3::random(-180.0, 180.0),
-                    scale: Vec3::random(0.3, 1.5),
+                    position: random_vec3(-20.0, 20.0),
+                    rotation: random_vec3(-180.0, 180.0),
+                    scale: random_vec3(0.3, 1.5),
                     ..Default::default()
                 })
                 .create_component(|| Mesh::new(obj_id, mat_id))
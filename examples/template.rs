@@ -0,0 +1,3 @@
+//! The `main.rs` of the projects generated by `lunar_engine::template`, built as an example so
+//! changes to the engine that break the template fail the build
+include!("../src/template/main.rs.in");
@@ -209,6 +209,12 @@ pub fn alias(attr: TokenStream, item: TokenStream) -> TokenStream {
         fn as_any_mut(&mut self) -> &mut dyn std::any::Any {{
             self as &mut dyn std::any::Any
         }}
+        fn as_reflect(&self) -> Option<&dyn lunar_engine::ecs::reflect::Reflect> {{
+            self.inner.as_reflect()
+        }}
+        fn as_reflect_mut(&mut self) -> Option<&mut dyn lunar_engine::ecs::reflect::Reflect> {{
+            self.inner.as_reflect_mut()
+        }}
         fn check_dependencies(entity: &lunar_engine::ecs::Entity) -> Result<(), &'static str> {{
             {base}::check_dependencies(entity)
         }}
@@ -426,3 +432,199 @@ pub fn as_any(_: TokenStream, item: TokenStream) -> TokenStream {
         .chain(item)
         .collect()
 }
+
+#[proc_macro_attribute]
+///Implements `as_reflect` and `as_reflect_mut` functions for Components implementing `Reflect`
+///
+///# Examples
+///```ignore
+///#[derive(Reflect)]
+///struct TestComponent{
+/// ...
+///}
+///
+///impl Component for TestComponent{
+///#[as_any]
+///#[as_reflect]
+/// ...
+///}
+///```
+pub fn as_reflect(_: TokenStream, item: TokenStream) -> TokenStream {
+    let as_reflect =
+        " fn as_reflect(&self) -> Option<&dyn lunar_engine::ecs::reflect::Reflect> { Some(self) } "
+            .to_string();
+    let as_reflect_mut = " fn as_reflect_mut(&mut self) -> Option<&mut dyn lunar_engine::ecs::reflect::Reflect> { Some(self) } ";
+
+    (as_reflect + as_reflect_mut)
+        .parse::<TokenStream>()
+        .unwrap()
+        .into_iter()
+        .chain(item)
+        .collect()
+}
+
+///Returns `true` if the token is the given punctuation character
+fn is_punct(token: &TokenTree, c: char) -> bool {
+    matches!(token, TokenTree::Punct(p) if p.as_char() == c)
+}
+
+///Splits the contents of a struct body into fields, separated by commas outside of generic
+///arguments, returns the names of the fields and whether they are marked with `#[reflect(skip)]`
+fn parse_fields(body: TokenStream, named: bool) -> Result<Vec<(String, bool)>, String> {
+    let mut fields = Vec::new();
+    let mut tokens = body.into_iter().peekable();
+
+    while tokens.peek().is_some() {
+        //Attributes
+        let mut skip = false;
+        while tokens.peek().is_some_and(|t| is_punct(t, '#')) {
+            tokens.next();
+            let Some(TokenTree::Group(attribute)) = tokens.next() else {
+                return Err("Invalid attribute".to_owned());
+            };
+            let attribute = attribute.stream().to_string().replace(' ', "");
+            if attribute == "reflect(skip)" {
+                skip = true;
+            } else if attribute.starts_with("reflect") {
+                return Err(format!("Unknown reflect attribute {attribute}"));
+            }
+        }
+
+        //Visibility
+        if matches!(tokens.peek(), Some(TokenTree::Ident(i)) if i.to_string() == "pub") {
+            tokens.next();
+            if matches!(tokens.peek(), Some(TokenTree::Group(g)) if g.delimiter() == proc_macro::Delimiter::Parenthesis)
+            {
+                tokens.next();
+            }
+        }
+
+        let name = if named {
+            let Some(TokenTree::Ident(name)) = tokens.next() else {
+                return Err("Expected a field name".to_owned());
+            };
+            if !tokens.next().is_some_and(|t| is_punct(&t, ':')) {
+                return Err("Expected a colon after the field name".to_owned());
+            }
+            name.to_string()
+        } else {
+            fields.len().to_string()
+        };
+
+        //Type, until a comma outside of angle brackets
+        let mut depth = 0usize;
+        let mut previous = ' ';
+        for t in tokens.by_ref() {
+            if let TokenTree::Punct(p) = &t {
+                match p.as_char() {
+                    ',' if depth == 0 => break,
+                    '<' => depth += 1,
+                    //Not a part of an arrow of a function pointer
+                    '>' if previous != '-' => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+                previous = p.as_char();
+            } else {
+                previous = ' ';
+            }
+        }
+
+        fields.push((name, skip));
+    }
+
+    Ok(fields)
+}
+
+#[proc_macro_derive(Reflect, attributes(reflect))]
+///Implements `Reflect` for a struct, exposing all of its fields, fields marked with
+///`#[reflect(skip)]` are not exposed. Generic structs are not supported.
+///
+///# Examples
+///```ignore
+///#[derive(Reflect)]
+///struct Health {
+/// current: f32,
+/// max: f32,
+/// #[reflect(skip)]
+/// timer: f32,
+///}
+///
+///#[derive(Reflect)]
+///struct Position(f32, f32);
+///```
+pub fn derive_reflect(item: TokenStream) -> TokenStream {
+    let error = |e: &str| format!("compile_error!(\"{e}\");").parse().unwrap();
+
+    let mut tokens = item
+        .into_iter()
+        .skip_while(|t| !matches!(t, TokenTree::Ident(i) if i.to_string() == "struct"));
+    if tokens.next().is_none() {
+        return error("Reflect can only be derived for structs");
+    }
+    let Some(TokenTree::Ident(name)) = tokens.next() else {
+        return error("No struct name found");
+    };
+
+    let fields = match tokens.next() {
+        Some(t) if is_punct(&t, '<') => return error("Generic structs not supported"),
+        Some(TokenTree::Group(g)) if g.delimiter() == proc_macro::Delimiter::Brace => {
+            parse_fields(g.stream(), true)
+        }
+        Some(TokenTree::Group(g)) if g.delimiter() == proc_macro::Delimiter::Parenthesis => {
+            parse_fields(g.stream(), false)
+        }
+        //Empty struct
+        _ => Ok(Vec::new()),
+    };
+    let fields = match fields {
+        Ok(f) => f
+            .into_iter()
+            .filter(|f| !f.1)
+            .map(|f| f.0)
+            .collect::<Vec<_>>(),
+        Err(e) => return error(&e),
+    };
+
+    let names = fields
+        .iter()
+        .map(|f| format!("\"{f}\","))
+        .collect::<Vec<_>>()
+        .concat();
+    let arms = fields
+        .iter()
+        .map(|f| format!("\"{f}\" => Some(&self.{f}),"))
+        .collect::<Vec<_>>()
+        .concat();
+    let arms_mut = fields
+        .iter()
+        .map(|f| format!("\"{f}\" => Some(&mut self.{f}),"))
+        .collect::<Vec<_>>()
+        .concat();
+
+    format!(
+        "
+    impl lunar_engine::ecs::reflect::Reflect for {name} {{
+        fn type_name(&self) -> &'static str {{
+            \"{name}\"
+        }}
+        fn field_names(&self) -> Vec<&str> {{
+            vec![{names}]
+        }}
+        fn field(&self, name: &str) -> Option<&dyn std::any::Any> {{
+            match name {{
+                {arms}
+                _ => None,
+            }}
+        }}
+        fn field_mut(&mut self, name: &str) -> Option<&mut dyn std::any::Any> {{
+            match name {{
+                {arms_mut}
+                _ => None,
+            }}
+        }}
+    }}
+    "
+    )
+    .parse::<TokenStream>()
+    .unwrap()
+}
@@ -0,0 +1,405 @@
+//! Bounding volume hierarchy for culling and spatial queries
+//!
+//! [`Bvh`] is a dynamic tree of axis aligned boxes, that is updated incrementally as the objects
+//! move. Every object is stored with a box enlarged by [`Bvh::margin`], the tree is only changed
+//! when the object leaves that box, so objects moving by small amounts are cheap to update. The
+//! tree is kept balanced with rotations.
+use alloc::{vec, vec::Vec};
+
+use super::geometry::{Aabb, Frustum};
+
+const NULL: usize = usize::MAX;
+
+///Handle of an object stored in a [`Bvh`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LeafId(usize);
+
+#[derive(Debug, Clone)]
+struct Node<T> {
+    //Enlarged box of a leaf, or the union of the boxes of the children
+    aabb: Aabb,
+    //Exact box of a leaf
+    bounds: Aabb,
+    //Parent of the node, or the next free node
+    parent: usize,
+    children: [usize; 2],
+    //Height of the subtree, leaves have the height 0 and free nodes -1
+    height: i32,
+    value: Option<T>,
+}
+
+impl<T> Node<T> {
+    const fn is_leaf(&self) -> bool {
+        self.children[0] == NULL
+    }
+}
+
+///Dynamic bounding volume hierarchy, see [`self`]
+#[derive(Debug, Clone)]
+pub struct Bvh<T> {
+    ///Distance the boxes of the objects are enlarged by, larger margins make the updates cheaper,
+    ///but the hierarchy less tight
+    pub margin: f32,
+    nodes: Vec<Node<T>>,
+    root: usize,
+    free: usize,
+    len: usize,
+}
+
+impl<T> Default for Bvh<T> {
+    fn default() -> Self {
+        Self::new(0.1)
+    }
+}
+
+impl<T> Bvh<T> {
+    ///Creates an empty hierarchy
+    #[must_use]
+    pub const fn new(margin: f32) -> Self {
+        Self {
+            margin,
+            nodes: Vec::new(),
+            root: NULL,
+            free: NULL,
+            len: 0,
+        }
+    }
+
+    ///Returns the number of objects in the hierarchy
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    ///Returns `true` if the hierarchy contains no objects
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    ///Returns the height of the tree, 0 if it's empty or contains a single object
+    #[must_use]
+    pub fn height(&self) -> u32 {
+        if self.root == NULL {
+            0
+        } else {
+            self.nodes[self.root].height.unsigned_abs()
+        }
+    }
+
+    ///Removes all the objects
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.root = NULL;
+        self.free = NULL;
+        self.len = 0;
+    }
+
+    ///Adds an object with the given bounds, returns the handle used to update and remove it
+    pub fn insert(&mut self, bounds: Aabb, value: T) -> LeafId {
+        let leaf = self.allocate(Node {
+            aabb: bounds.expanded(self.margin),
+            bounds,
+            parent: NULL,
+            children: [NULL; 2],
+            height: 0,
+            value: Some(value),
+        });
+        self.insert_leaf(leaf);
+        self.len += 1;
+        LeafId(leaf)
+    }
+
+    ///Removes the object and returns its value
+    ///
+    ///# Panics
+    ///Panics if the object was already removed
+    pub fn remove(&mut self, id: LeafId) -> T {
+        let value = self.nodes[id.0]
+            .value
+            .take()
+            .expect("The object was already removed");
+        self.remove_leaf(id.0);
+        self.deallocate(id.0);
+        self.len -= 1;
+        value
+    }
+
+    ///Sets the bounds of the object, returns `true` if the object left its enlarged box and the
+    ///tree had to be changed
+    ///
+    ///# Panics
+    ///Panics if the object was removed
+    pub fn update(&mut self, id: LeafId, bounds: Aabb) -> bool {
+        let node = &mut self.nodes[id.0];
+        assert!(node.value.is_some(), "The object was removed");
+        node.bounds = bounds;
+        if node.aabb.contains_aabb(&bounds) {
+            return false;
+        }
+
+        self.remove_leaf(id.0);
+        self.nodes[id.0].aabb = bounds.expanded(self.margin);
+        self.insert_leaf(id.0);
+        true
+    }
+
+    ///Returns the value of the object, `None` if it was removed
+    #[must_use]
+    pub fn get(&self, id: LeafId) -> Option<&T> {
+        self.nodes.get(id.0).and_then(|n| n.value.as_ref())
+    }
+
+    ///Returns the bounds of the object, `None` if it was removed
+    #[must_use]
+    pub fn bounds(&self, id: LeafId) -> Option<Aabb> {
+        self.nodes
+            .get(id.0)
+            .filter(|n| n.value.is_some())
+            .map(|n| n.bounds)
+    }
+
+    ///Calls `f` with every object whose bounds are at least partially inside the frustum
+    ///
+    ///Subtrees outside of the frustum are rejected, and subtrees entirely inside of it are
+    ///accepted, with a single test. Returns the number of boxes tested against the frustum.
+    pub fn query_frustum(&self, frustum: &Frustum, mut f: impl FnMut(LeafId, &T)) -> usize {
+        let mut tests = 0;
+        let mut stack = Vec::new();
+        if self.root != NULL {
+            stack.push(self.root);
+        }
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            tests += 1;
+            if !frustum.intersects_aabb(&node.aabb) {
+                continue;
+            }
+            if node.is_leaf() {
+                //The enlarged box may be visible while the object is not
+                tests += 1;
+                if frustum.intersects_aabb(&node.bounds) {
+                    f(LeafId(index), node.value.as_ref().unwrap());
+                }
+            } else if frustum.contains_aabb(&node.aabb) {
+                self.for_each_leaf(index, &mut f);
+            } else {
+                stack.extend(node.children);
+            }
+        }
+        tests
+    }
+
+    ///Calls `f` with every object whose bounds overlap the box
+    pub fn query_aabb(&self, aabb: &Aabb, mut f: impl FnMut(LeafId, &T)) {
+        let mut stack = Vec::new();
+        if self.root != NULL {
+            stack.push(self.root);
+        }
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.aabb.intersects_aabb(aabb) {
+                continue;
+            }
+            if !node.is_leaf() {
+                stack.extend(node.children);
+            } else if node.bounds.intersects_aabb(aabb) {
+                f(LeafId(index), node.value.as_ref().unwrap());
+            }
+        }
+    }
+
+    fn for_each_leaf(&self, index: usize, f: &mut impl FnMut(LeafId, &T)) {
+        let mut stack = vec![index];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node.is_leaf() {
+                f(LeafId(index), node.value.as_ref().unwrap());
+            } else {
+                stack.extend(node.children);
+            }
+        }
+    }
+
+    fn allocate(&mut self, node: Node<T>) -> usize {
+        if self.free == NULL {
+            self.nodes.push(node);
+            return self.nodes.len() - 1;
+        }
+        let index = self.free;
+        self.free = self.nodes[index].parent;
+        self.nodes[index] = node;
+        index
+    }
+
+    fn deallocate(&mut self, index: usize) {
+        let node = &mut self.nodes[index];
+        node.parent = self.free;
+        node.children = [NULL; 2];
+        node.height = -1;
+        node.value = None;
+        self.free = index;
+    }
+
+    //Replaces the child of the parent of `old`, or the root, with `new`
+    fn replace_child(&mut self, parent: usize, old: usize, new: usize) {
+        if parent == NULL {
+            self.root = new;
+        } else if self.nodes[parent].children[0] == old {
+            self.nodes[parent].children[0] = new;
+        } else {
+            self.nodes[parent].children[1] = new;
+        }
+    }
+
+    fn insert_leaf(&mut self, leaf: usize) {
+        if self.root == NULL {
+            self.root = leaf;
+            self.nodes[leaf].parent = NULL;
+            return;
+        }
+
+        //Find the sibling that increases the surface area of the tree the least
+        let aabb = self.nodes[leaf].aabb;
+        let mut index = self.root;
+        while !self.nodes[index].is_leaf() {
+            let node = &self.nodes[index];
+            let area = node.aabb.surface_area();
+            let combined = node.aabb.union(&aabb).surface_area();
+
+            //Cost of making a new parent for this node and the leaf
+            let cost = 2.0 * combined;
+            //Cost of pushing the leaf further down the tree
+            let inheritance = 2.0 * (combined - area);
+
+            let child_cost = |child: usize| {
+                let child = &self.nodes[child];
+                let area = child.aabb.union(&aabb).surface_area();
+                if child.is_leaf() {
+                    area + inheritance
+                } else {
+                    area - child.aabb.surface_area() + inheritance
+                }
+            };
+            let [a, b] = node.children;
+            let (cost_a, cost_b) = (child_cost(a), child_cost(b));
+
+            if cost < cost_a && cost < cost_b {
+                break;
+            }
+            index = if cost_a < cost_b { a } else { b };
+        }
+
+        let sibling = index;
+        let old_parent = self.nodes[sibling].parent;
+        let parent = self.allocate(Node {
+            aabb: aabb.union(&self.nodes[sibling].aabb),
+            bounds: Aabb::default(),
+            parent: old_parent,
+            children: [sibling, leaf],
+            height: self.nodes[sibling].height + 1,
+            value: None,
+        });
+        self.replace_child(old_parent, sibling, parent);
+        self.nodes[sibling].parent = parent;
+        self.nodes[leaf].parent = parent;
+
+        self.refit(parent);
+    }
+
+    fn remove_leaf(&mut self, leaf: usize) {
+        if leaf == self.root {
+            self.root = NULL;
+            return;
+        }
+
+        let parent = self.nodes[leaf].parent;
+        let grandparent = self.nodes[parent].parent;
+        let [a, b] = self.nodes[parent].children;
+        let sibling = if a == leaf { b } else { a };
+
+        self.replace_child(grandparent, parent, sibling);
+        self.nodes[sibling].parent = grandparent;
+        self.deallocate(parent);
+
+        self.refit(grandparent);
+    }
+
+    //Rebalances the ancestors of a changed node and recomputes their boxes
+    fn refit(&mut self, mut index: usize) {
+        while index != NULL {
+            index = self.balance(index);
+
+            let [a, b] = self.nodes[index].children;
+            let (a, b) = (&self.nodes[a], &self.nodes[b]);
+            let height = 1 + a.height.max(b.height);
+            let aabb = a.aabb.union(&b.aabb);
+
+            let node = &mut self.nodes[index];
+            node.height = height;
+            node.aabb = aabb;
+            index = node.parent;
+        }
+    }
+
+    //Rotates the node if its subtrees differ in height by more than 1, returns the node that took
+    //its place
+    fn balance(&mut self, a: usize) -> usize {
+        if self.nodes[a].is_leaf() || self.nodes[a].height < 2 {
+            return a;
+        }
+
+        let [b, c] = self.nodes[a].children;
+        let balance = self.nodes[c].height - self.nodes[b].height;
+
+        if balance > 1 {
+            self.rotate(a, 1)
+        } else if balance < -1 {
+            self.rotate(a, 0)
+        } else {
+            a
+        }
+    }
+
+    //Moves the child of `a` in the `side` slot up to the place of `a`
+    fn rotate(&mut self, a: usize, side: usize) -> usize {
+        let up = self.nodes[a].children[side];
+        let other = self.nodes[a].children[1 - side];
+        let [f, g] = self.nodes[up].children;
+
+        let parent = self.nodes[a].parent;
+        self.nodes[up].children[0] = a;
+        self.nodes[up].parent = parent;
+        self.nodes[a].parent = up;
+        self.replace_child(parent, a, up);
+
+        //The taller grandchild stays with the node moved up
+        let (keep, give) = if self.nodes[f].height > self.nodes[g].height {
+            (f, g)
+        } else {
+            (g, f)
+        };
+        self.nodes[up].children[1] = keep;
+        self.nodes[a].children[side] = give;
+        self.nodes[give].parent = a;
+
+        let (a_aabb, a_height) = {
+            let (other, give) = (&self.nodes[other], &self.nodes[give]);
+            (
+                other.aabb.union(&give.aabb),
+                1 + other.height.max(give.height),
+            )
+        };
+        self.nodes[a].aabb = a_aabb;
+        self.nodes[a].height = a_height;
+
+        let keep = &self.nodes[keep];
+        let (up_aabb, up_height) = (a_aabb.union(&keep.aabb), 1 + a_height.max(keep.height));
+        self.nodes[up].aabb = up_aabb;
+        self.nodes[up].height = up_height;
+
+        up
+    }
+}
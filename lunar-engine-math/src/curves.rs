@@ -0,0 +1,303 @@
+//! Splines and curves
+//!
+//! All the curves are parameterized by `t` in the range [0, 1] over the whole curve, regardless
+//! of the number of segments. The speed along a curve is generally not constant, use
+//! [`ArcLength`] to move along it with a constant speed.
+use alloc::{vec, vec::Vec};
+
+use super::{Vec3, Vector};
+#[cfg(not(feature = "std"))]
+use crate::float::Float;
+
+///A curve in 3D space
+pub trait Curve {
+    ///Returns the point on the curve at `t`, between 0 and 1
+    fn evaluate(&self, t: f32) -> Vec3;
+
+    ///Returns the derivative of the curve at `t`, between 0 and 1
+    ///
+    ///The tangent is not normalized, its length is the speed of the curve at that point
+    fn tangent(&self, t: f32) -> Vec3;
+}
+
+///Splits `t` in the [0, 1] range of a curve with `segments` segments into the index of the
+///segment and the local parameter within it
+#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+fn segment(t: f32, segments: usize) -> (usize, f32) {
+    let t = t.clamp(0.0, 1.0) * segments as f32;
+    let i = (t.floor() as usize).min(segments - 1);
+    (i, t - i as f32)
+}
+
+///A Bezier curve of any degree, defined by its control points
+///
+///The curve passes through the first and the last control points
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Bezier {
+    ///Control points of the curve
+    pub points: Vec<Vec3>,
+}
+
+impl Bezier {
+    ///Creates a new Bezier curve
+    #[must_use]
+    pub const fn new(points: Vec<Vec3>) -> Self {
+        Self { points }
+    }
+
+    ///Creates a cubic Bezier curve
+    #[must_use]
+    pub fn cubic(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3) -> Self {
+        Self::new(vec![p0, p1, p2, p3])
+    }
+}
+
+///Evaluates a Bezier curve using De Casteljau's algorithm
+fn de_casteljau(points: &mut [Vec3], t: f32) -> Vec3 {
+    for n in (1..points.len()).rev() {
+        for i in 0..n {
+            points[i] = points[i] + (points[i + 1] - points[i]) * t;
+        }
+    }
+    points[0]
+}
+
+impl Curve for Bezier {
+    ///# Panics
+    ///Panics if the curve has no control points
+    fn evaluate(&self, t: f32) -> Vec3 {
+        de_casteljau(&mut self.points.clone(), t.clamp(0.0, 1.0))
+    }
+
+    fn tangent(&self, t: f32) -> Vec3 {
+        let n = self.points.len();
+        if n < 2 {
+            return Vec3::default();
+        }
+        //The derivative is a curve of a lower degree defined by the differences of the points
+        let mut derivative = self
+            .points
+            .windows(2)
+            .map(|w| (w[1] - w[0]) * (n - 1) as f32)
+            .collect::<Vec<_>>();
+        de_casteljau(&mut derivative, t.clamp(0.0, 1.0))
+    }
+}
+
+///A uniform Catmull-Rom spline
+///
+///The spline passes through all the points, the tangent at every point is parallel to the line
+///between its neighbours
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CatmullRom {
+    ///Points the spline passes through
+    pub points: Vec<Vec3>,
+}
+
+impl CatmullRom {
+    ///Creates a new Catmull-Rom spline
+    #[must_use]
+    pub const fn new(points: Vec<Vec3>) -> Self {
+        Self { points }
+    }
+
+    ///Returns the 4 control points of the segment, the end points are duplicated
+    fn segment_points(&self, i: usize) -> [Vec3; 4] {
+        let last = self.points.len() - 1;
+        let p = |i: usize| self.points[i.min(last)];
+        [p(i.saturating_sub(1)), p(i), p(i + 1), p(i + 2)]
+    }
+}
+
+impl Curve for CatmullRom {
+    ///# Panics
+    ///Panics if the spline has no points
+    fn evaluate(&self, t: f32) -> Vec3 {
+        let segments = self.points.len() - 1;
+        if segments == 0 {
+            return self.points[0];
+        }
+
+        let (i, t) = segment(t, segments);
+        let [p0, p1, p2, p3] = self.segment_points(i);
+
+        let t2 = t * t;
+        let t3 = t2 * t;
+
+        (p1 * 2.0
+            + (p2 - p0) * t
+            + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+            + (p3 - p0 + (p1 - p2) * 3.0) * t3)
+            * 0.5
+    }
+
+    fn tangent(&self, t: f32) -> Vec3 {
+        if self.points.len() < 2 {
+            return Vec3::default();
+        }
+        let segments = self.points.len() - 1;
+
+        let (i, t) = segment(t, segments);
+        let [p0, p1, p2, p3] = self.segment_points(i);
+
+        //Derivative with respect to the local parameter, scaled to the global one
+        ((p2 - p0)
+            + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * (2.0 * t)
+            + (p3 - p0 + (p1 - p2) * 3.0) * (3.0 * t * t))
+            * (0.5 * segments as f32)
+    }
+}
+
+///A point of a [`Hermite`] spline
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HermitePoint {
+    ///Position of the point
+    pub position: Vec3,
+    ///Tangent of the spline at the point
+    pub tangent: Vec3,
+}
+
+impl HermitePoint {
+    ///Creates a new point
+    #[must_use]
+    pub const fn new(position: Vec3, tangent: Vec3) -> Self {
+        Self { position, tangent }
+    }
+}
+
+///A cubic Hermite spline, defined by points and tangents at them
+///
+///Tangents are specified per segment, i.e. a tangent of the length 1 means moving by 1 unit over
+///the whole segment
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Hermite {
+    ///Points of the spline
+    pub points: Vec<HermitePoint>,
+}
+
+impl Hermite {
+    ///Creates a new Hermite spline
+    #[must_use]
+    pub const fn new(points: Vec<HermitePoint>) -> Self {
+        Self { points }
+    }
+}
+
+impl Curve for Hermite {
+    ///# Panics
+    ///Panics if the spline has no points
+    fn evaluate(&self, t: f32) -> Vec3 {
+        let segments = self.points.len() - 1;
+        if segments == 0 {
+            return self.points[0].position;
+        }
+
+        let (i, t) = segment(t, segments);
+        let (a, b) = (self.points[i], self.points[i + 1]);
+
+        let t2 = t * t;
+        let t3 = t2 * t;
+
+        a.position * 2.0f32.mul_add(t3, (-3.0f32).mul_add(t2, 1.0))
+            + a.tangent * (-2.0f32).mul_add(t2, t3 + t)
+            + b.position * (-2.0f32).mul_add(t3, 3.0 * t2)
+            + b.tangent * (t3 - t2)
+    }
+
+    fn tangent(&self, t: f32) -> Vec3 {
+        if self.points.len() < 2 {
+            return Vec3::default();
+        }
+        let segments = self.points.len() - 1;
+
+        let (i, t) = segment(t, segments);
+        let (a, b) = (self.points[i], self.points[i + 1]);
+
+        let t2 = t * t;
+
+        (a.position * (6.0 * (t2 - t))
+            + a.tangent * 3.0f32.mul_add(t2, (-4.0f32).mul_add(t, 1.0))
+            + b.position * (6.0 * (t - t2))
+            + b.tangent * 3.0f32.mul_add(t2, -2.0 * t))
+            * segments as f32
+    }
+}
+
+///Arc length parameterization of a curve
+///
+///Maps distances along the curve to curve parameters, allowing for movement with a constant
+///speed. The curve is approximated by line segments, so the accuracy depends on the number of
+///samples
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArcLength {
+    //Length of the curve from the start to every sample
+    lengths: Vec<f32>,
+}
+
+impl ArcLength {
+    ///Creates the parameterization of the `curve` using `samples` line segments
+    ///
+    ///# Panics
+    ///Panics if `samples` is 0
+    #[must_use]
+    pub fn new(curve: &impl Curve, samples: usize) -> Self {
+        assert!(samples > 0, "Number of samples must be positive");
+
+        let mut lengths = Vec::with_capacity(samples + 1);
+        let mut total = 0.0;
+        let mut previous = curve.evaluate(0.0);
+        lengths.push(0.0);
+
+        for i in 1..=samples {
+            let point = curve.evaluate(i as f32 / samples as f32);
+            total += (point - previous).length();
+            lengths.push(total);
+            previous = point;
+        }
+
+        Self { lengths }
+    }
+
+    ///Returns the length of the curve
+    #[must_use]
+    pub fn length(&self) -> f32 {
+        *self.lengths.last().unwrap()
+    }
+
+    ///Returns the curve parameter of the point that is `distance` away from the start along the
+    ///curve
+    #[must_use]
+    pub fn parameter(&self, distance: f32) -> f32 {
+        let samples = (self.lengths.len() - 1) as f32;
+        let distance = distance.clamp(0.0, self.length());
+
+        let i = self.lengths.partition_point(|l| *l < distance);
+        if i == 0 {
+            return 0.0;
+        }
+
+        let (start, end) = (self.lengths[i - 1], self.lengths[i]);
+        let local = if end > start {
+            (distance - start) / (end - start)
+        } else {
+            0.0
+        };
+
+        ((i - 1) as f32 + local) / samples
+    }
+
+    ///Returns the curve parameter of the point at `fraction` of the length of the curve, between
+    ///0 and 1
+    #[must_use]
+    pub fn parameter_normalized(&self, fraction: f32) -> f32 {
+        self.parameter(fraction * self.length())
+    }
+
+    ///Returns the point of the `curve` that is `distance` away from the start along it
+    ///
+    ///The curve must be the one used for creating the parameterization
+    #[must_use]
+    pub fn evaluate(&self, curve: &impl Curve, distance: f32) -> Vec3 {
+        curve.evaluate(self.parameter(distance))
+    }
+}
@@ -0,0 +1,469 @@
+//! Geometric primitives and intersection tests between them
+//!
+//! Used for culling, picking and physics
+use super::{Mat4x4, Vec3, Vec4, Vector};
+#[cfg(not(feature = "std"))]
+use crate::float::Float;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+///A plane defined by `normal · p + distance = 0`
+pub struct Plane {
+    ///Normal of the plane
+    pub normal: Vec3,
+    ///Signed distance from the origin along the normal
+    pub distance: f32,
+}
+
+impl Plane {
+    ///Creates a new plane
+    #[must_use]
+    pub const fn new(normal: Vec3, distance: f32) -> Self {
+        Self { normal, distance }
+    }
+
+    ///Creates a plane that passes through `point`, with the given normal
+    #[must_use]
+    pub fn from_point_normal(point: Vec3, normal: Vec3) -> Self {
+        let normal = normal.normalize();
+        Self::new(normal, -normal.dot_product(&point))
+    }
+
+    ///Returns the plane with a normal of length 1
+    #[must_use]
+    pub fn normalized(self) -> Self {
+        let len = self.normal.length();
+        if len == 0.0 {
+            return self;
+        }
+        Self::new(self.normal / len, self.distance / len)
+    }
+
+    ///Returns the signed distance from the plane to the point, positive if the point is in front
+    ///of the plane
+    ///
+    ///The plane must be normalized
+    #[must_use]
+    pub fn signed_distance(&self, point: Vec3) -> f32 {
+        self.normal.dot_product(&point) + self.distance
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+///A half line starting at `origin` and going in `direction`
+pub struct Ray {
+    ///Origin of the ray
+    pub origin: Vec3,
+    ///Direction of the ray
+    pub direction: Vec3,
+}
+
+impl Ray {
+    ///Creates a new ray, normalizing the direction
+    #[must_use]
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    ///Returns the point at the distance `t` along the ray
+    #[must_use]
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.origin + self.direction * t
+    }
+
+    ///Returns the distance along the ray to the closest intersection with the box, or `None` if
+    ///they don't intersect
+    ///
+    ///Returns 0 if the origin is inside the box
+    #[must_use]
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let mut t_min = 0.0f32;
+        let mut t_max = f32::INFINITY;
+
+        for (origin, direction, min, max) in [
+            (self.origin.x, self.direction.x, aabb.min.x, aabb.max.x),
+            (self.origin.y, self.direction.y, aabb.min.y, aabb.max.y),
+            (self.origin.z, self.direction.z, aabb.min.z, aabb.max.z),
+        ] {
+            if direction == 0.0 {
+                //Parallel to the slab
+                if origin < min || origin > max {
+                    return None;
+                }
+                continue;
+            }
+
+            let inv = 1.0 / direction;
+            let t0 = (min - origin) * inv;
+            let t1 = (max - origin) * inv;
+
+            t_min = t_min.max(t0.min(t1));
+            t_max = t_max.min(t0.max(t1));
+
+            if t_min > t_max {
+                return None;
+            }
+        }
+
+        Some(t_min)
+    }
+
+    ///Returns the distance along the ray to the closest intersection with the sphere, or `None`
+    ///if they don't intersect
+    ///
+    ///Returns 0 if the origin is inside the sphere
+    #[must_use]
+    pub fn intersect_sphere(&self, sphere: &Sphere) -> Option<f32> {
+        let offset = self.origin - sphere.center;
+        let b = offset.dot_product(&self.direction);
+        let c = sphere
+            .radius
+            .mul_add(-sphere.radius, offset.square_length());
+
+        if c <= 0.0 {
+            return Some(0.0);
+        }
+
+        let discriminant = b.mul_add(b, -c);
+        if discriminant < 0.0 || b > 0.0 {
+            return None;
+        }
+
+        Some(-b - discriminant.sqrt())
+    }
+
+    ///Returns the distance along the ray to the intersection with the plane, or `None` if they
+    ///don't intersect
+    #[must_use]
+    pub fn intersect_plane(&self, plane: &Plane) -> Option<f32> {
+        let denominator = plane.normal.dot_product(&self.direction);
+        if denominator == 0.0 {
+            return None;
+        }
+
+        let t = -plane.signed_distance(self.origin) / denominator;
+        if t < 0.0 {
+            return None;
+        }
+        Some(t)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+///Axis aligned bounding box
+pub struct Aabb {
+    ///Minimum corner of the box
+    pub min: Vec3,
+    ///Maximum corner of the box
+    pub max: Vec3,
+}
+
+impl Aabb {
+    ///Creates a new box from the minimum and maximum corners
+    #[must_use]
+    pub const fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    ///Creates a box with the given center and half extents
+    #[must_use]
+    pub fn from_center_extents(center: Vec3, extents: Vec3) -> Self {
+        Self::new(center - extents, center + extents)
+    }
+
+    ///Creates the smallest box containing all the points, returns `None` if there are no points
+    #[must_use]
+    pub fn from_points(points: &[Vec3]) -> Option<Self> {
+        let first = *points.first()?;
+
+        Some(points.iter().fold(Self::new(first, first), |acc, p| {
+            Self::new(acc.min.min(*p), acc.max.max(*p))
+        }))
+    }
+
+    ///Returns the center of the box
+    #[must_use]
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    ///Returns the half extents of the box
+    #[must_use]
+    pub fn extents(&self) -> Vec3 {
+        (self.max - self.min) * 0.5
+    }
+
+    ///Returns the 8 corners of the box
+    #[must_use]
+    pub const fn corners(&self) -> [Vec3; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            Vec3::new(a.x, a.y, a.z),
+            Vec3::new(b.x, a.y, a.z),
+            Vec3::new(a.x, b.y, a.z),
+            Vec3::new(b.x, b.y, a.z),
+            Vec3::new(a.x, a.y, b.z),
+            Vec3::new(b.x, a.y, b.z),
+            Vec3::new(a.x, b.y, b.z),
+            Vec3::new(b.x, b.y, b.z),
+        ]
+    }
+
+    ///Returns `true` if the point is inside the box
+    #[must_use]
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        point.x >= self.min.x
+            && point.y >= self.min.y
+            && point.z >= self.min.z
+            && point.x <= self.max.x
+            && point.y <= self.max.y
+            && point.z <= self.max.z
+    }
+
+    ///Returns `true` if the boxes overlap
+    #[must_use]
+    pub fn intersects_aabb(&self, other: &Self) -> bool {
+        self.min.x <= other.max.x
+            && self.max.x >= other.min.x
+            && self.min.y <= other.max.y
+            && self.max.y >= other.min.y
+            && self.min.z <= other.max.z
+            && self.max.z >= other.min.z
+    }
+
+    ///Returns `true` if the other box is entirely inside this box
+    #[must_use]
+    pub fn contains_aabb(&self, other: &Self) -> bool {
+        self.contains_point(other.min) && self.contains_point(other.max)
+    }
+
+    ///Returns the smallest box containing both boxes
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self {
+        Self::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    ///Returns the box grown by `margin` in every direction
+    #[must_use]
+    pub fn expanded(&self, margin: f32) -> Self {
+        let margin = Vec3::new(margin, margin, margin);
+        Self::new(self.min - margin, self.max + margin)
+    }
+
+    ///Returns the surface area of the box
+    #[must_use]
+    pub fn surface_area(&self) -> f32 {
+        let size = self.max - self.min;
+        2.0 * size
+            .x
+            .mul_add(size.y, size.z.mul_add(size.x, size.y * size.z))
+    }
+
+    ///Returns the point of the box closest to `point`
+    #[must_use]
+    pub fn closest_point(&self, point: Vec3) -> Vec3 {
+        point.max(self.min).min(self.max)
+    }
+
+    ///Returns the axis aligned box containing this box transformed by `transform`
+    #[must_use]
+    pub fn transformed(&self, transform: &Mat4x4) -> Self {
+        //Can't fail, since there are always 8 corners
+        Self::from_points(&self.corners().map(|c| transform.transform3(c))).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+///A sphere
+pub struct Sphere {
+    ///Center of the sphere
+    pub center: Vec3,
+    ///Radius of the sphere
+    pub radius: f32,
+}
+
+impl Sphere {
+    ///Creates a new sphere
+    #[must_use]
+    pub const fn new(center: Vec3, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    ///Returns `true` if the point is inside the sphere
+    #[must_use]
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        (point - self.center).square_length() <= self.radius * self.radius
+    }
+
+    ///Returns `true` if the spheres overlap
+    #[must_use]
+    pub fn intersects_sphere(&self, other: &Self) -> bool {
+        let radius = self.radius + other.radius;
+        (self.center - other.center).square_length() <= radius * radius
+    }
+
+    ///Returns `true` if the sphere overlaps the box
+    #[must_use]
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.contains_point(aabb.closest_point(self.center))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+///Oriented bounding box
+pub struct Obb {
+    ///Center of the box
+    pub center: Vec3,
+    ///Local axes of the box, must be normalized and perpendicular to each other
+    pub axes: [Vec3; 3],
+    ///Half extents of the box along each of the axes
+    pub extents: Vec3,
+}
+
+impl Obb {
+    ///Creates an oriented box from an axis aligned box in local space, transformed by `transform`
+    #[must_use]
+    pub fn from_aabb(aabb: &Aabb, transform: &Mat4x4) -> Self {
+        let center = transform.transform3(aabb.center());
+        let extents = aabb.extents();
+
+        let x = (*transform * Vec4::new(1.0, 0.0, 0.0, 0.0)).xyz();
+        let y = (*transform * Vec4::new(0.0, 1.0, 0.0, 0.0)).xyz();
+        let z = (*transform * Vec4::new(0.0, 0.0, 1.0, 0.0)).xyz();
+
+        Self {
+            center,
+            axes: [x.normalize(), y.normalize(), z.normalize()],
+            extents: Vec3::new(
+                extents.x * x.length(),
+                extents.y * y.length(),
+                extents.z * z.length(),
+            ),
+        }
+    }
+
+    ///Returns the radius of the projection of the box onto `direction`
+    #[must_use]
+    pub fn projected_radius(&self, direction: Vec3) -> f32 {
+        self.extents.z.mul_add(
+            self.axes[2].dot_product(&direction).abs(),
+            self.extents.x.mul_add(
+                self.axes[0].dot_product(&direction).abs(),
+                self.extents.y * self.axes[1].dot_product(&direction).abs(),
+            ),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+///A view frustum defined by 6 planes facing inwards
+pub struct Frustum {
+    ///Planes of the frustum, in the order: left, right, bottom, top, near, far
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    ///Extracts the frustum from a view projection matrix, that transforms points as
+    ///`matrix * point`, with the depth range of 0 to 1
+    #[must_use]
+    pub fn from_matrix(matrix: &Mat4x4) -> Self {
+        let row = |x: f32, y: f32, z: f32, w: f32| Vec4::new(x, y, z, w);
+        let r0 = row(matrix.m00, matrix.m01, matrix.m02, matrix.m03);
+        let r1 = row(matrix.m10, matrix.m11, matrix.m12, matrix.m13);
+        let r2 = row(matrix.m20, matrix.m21, matrix.m22, matrix.m23);
+        let r3 = row(matrix.m30, matrix.m31, matrix.m32, matrix.m33);
+
+        let plane = |v: Vec4| Plane::new(v.xyz(), v.w).normalized();
+
+        Self {
+            planes: [
+                plane(r3 + r0),
+                plane(r3 - r0),
+                plane(r3 + r1),
+                plane(r3 - r1),
+                plane(r2),
+                plane(r3 - r2),
+            ],
+        }
+    }
+
+    ///Returns `true` if the point is inside the frustum
+    #[must_use]
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        self.planes.iter().all(|p| p.signed_distance(point) >= 0.0)
+    }
+
+    ///Returns `true` if the sphere is at least partially inside the frustum
+    #[must_use]
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        self.planes
+            .iter()
+            .all(|p| p.signed_distance(sphere.center) >= -sphere.radius)
+    }
+
+    ///Returns `true` if the box is at least partially inside the frustum
+    ///
+    ///May return `true` for some boxes that are just outside of the frustum corners
+    #[must_use]
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|p| {
+            //The corner furthest along the normal
+            let positive = Vec3::new(
+                if p.normal.x >= 0.0 {
+                    aabb.max.x
+                } else {
+                    aabb.min.x
+                },
+                if p.normal.y >= 0.0 {
+                    aabb.max.y
+                } else {
+                    aabb.min.y
+                },
+                if p.normal.z >= 0.0 {
+                    aabb.max.z
+                } else {
+                    aabb.min.z
+                },
+            );
+            p.signed_distance(positive) >= 0.0
+        })
+    }
+
+    ///Returns `true` if the box is entirely inside the frustum
+    #[must_use]
+    pub fn contains_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|p| {
+            //The corner furthest against the normal
+            let negative = Vec3::new(
+                if p.normal.x >= 0.0 {
+                    aabb.min.x
+                } else {
+                    aabb.max.x
+                },
+                if p.normal.y >= 0.0 {
+                    aabb.min.y
+                } else {
+                    aabb.max.y
+                },
+                if p.normal.z >= 0.0 {
+                    aabb.min.z
+                } else {
+                    aabb.max.z
+                },
+            );
+            p.signed_distance(negative) >= 0.0
+        })
+    }
+
+    ///Returns `true` if the box is at least partially inside the frustum
+    ///
+    ///May return `true` for some boxes that are just outside of the frustum corners
+    #[must_use]
+    pub fn intersects_obb(&self, obb: &Obb) -> bool {
+        self.planes
+            .iter()
+            .all(|p| p.signed_distance(obb.center) >= -obb.projected_radius(p.normal))
+    }
+}
@@ -0,0 +1,65 @@
+//Conversions between the engine and glam types
+use super::{Mat4x4, Quaternion, Vec2, Vec3, Vec4};
+
+impl From<glam::Vec2> for Vec2 {
+    fn from(value: glam::Vec2) -> Self {
+        Self::new(value.x, value.y)
+    }
+}
+
+impl From<Vec2> for glam::Vec2 {
+    fn from(value: Vec2) -> Self {
+        Self::new(value.x, value.y)
+    }
+}
+
+impl From<glam::Vec3> for Vec3 {
+    fn from(value: glam::Vec3) -> Self {
+        Self::new(value.x, value.y, value.z)
+    }
+}
+
+impl From<Vec3> for glam::Vec3 {
+    fn from(value: Vec3) -> Self {
+        Self::new(value.x, value.y, value.z)
+    }
+}
+
+impl From<glam::Vec4> for Vec4 {
+    fn from(value: glam::Vec4) -> Self {
+        let [x, y, z, w] = value.to_array();
+        Self::new(x, y, z, w)
+    }
+}
+
+impl From<Vec4> for glam::Vec4 {
+    fn from(value: Vec4) -> Self {
+        Self::new(value.x, value.y, value.z, value.w)
+    }
+}
+
+impl From<glam::Quat> for Quaternion {
+    fn from(value: glam::Quat) -> Self {
+        let [x, y, z, w] = value.to_array();
+        Self::new(x, y, z, w)
+    }
+}
+
+impl From<Quaternion> for glam::Quat {
+    fn from(value: Quaternion) -> Self {
+        Self::from_xyzw(value.x, value.y, value.z, value.w)
+    }
+}
+
+//glam matrices are column major, while the engine ones are row major
+impl From<glam::Mat4> for Mat4x4 {
+    fn from(value: glam::Mat4) -> Self {
+        bytemuck::cast::<[f32; 16], Self>(value.to_cols_array()).transpose()
+    }
+}
+
+impl From<Mat4x4> for glam::Mat4 {
+    fn from(value: Mat4x4) -> Self {
+        Self::from_cols_array(&bytemuck::cast(value.transpose()))
+    }
+}
@@ -0,0 +1,148 @@
+//! The math library
+//!
+//! Contains implementations of vectors with length 2,3,4, 3x3 and 4x4 matrices and quaternions
+//! as well as curves, geometric primitives and a bounding volume hierarchy
+//!
+//! With the `glam` and `mint` features enabled, the types can be converted to and from the
+//! respective types of those crates, with the `serde` feature they can be serialized.
+//!
+//! The crate is re-exported by the engine as `lunar_engine::math`, tools and servers can depend on
+//! it directly to share the types without the renderer. Without the default `std` feature it's
+//! `no_std`, the `libm` feature must then be enabled for the float functions.
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+#![deny(missing_docs)]
+#![allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::missing_panics_doc
+)]
+
+#[cfg(not(any(feature = "std", feature = "libm")))]
+compile_error!("Either the `std` or the `libm` feature must be enabled");
+
+extern crate alloc;
+
+pub mod bvh;
+pub mod curves;
+#[cfg(not(feature = "std"))]
+mod float;
+pub mod geometry;
+#[cfg(feature = "glam")]
+mod glam_interop;
+mod mat3x3;
+mod mat4x4;
+#[cfg(feature = "mint")]
+mod mint_interop;
+mod quaternion;
+mod swizzle;
+#[cfg(test)]
+mod tests;
+mod traits;
+mod vec2;
+mod vec3;
+mod vec4;
+
+use core::ops::{Add, Mul, Sub};
+
+pub use mat3x3::Mat3x3;
+pub use mat4x4::Mat4x4;
+pub use quaternion::Quaternion;
+pub use traits::{ApproxEq, Vector};
+pub use vec2::Vec2;
+pub use vec3::Vec3;
+pub use vec4::Vec4;
+
+//The types are uploaded to the gpu as is, so their layout must match the one used in the shaders
+const _: () = {
+    use core::mem::{align_of, size_of};
+
+    assert!(size_of::<Vec2>() == 8);
+    assert!(size_of::<Vec3>() == 12);
+    assert!(size_of::<Vec4>() == 16);
+    assert!(size_of::<Quaternion>() == 16);
+    assert!(size_of::<Mat3x3>() == 36);
+    assert!(size_of::<Mat4x4>() == 64);
+
+    assert!(align_of::<Vec2>() == 4);
+    assert!(align_of::<Vec3>() == 4);
+    assert!(align_of::<Vec4>() == 4);
+    assert!(align_of::<Quaternion>() == 4);
+    assert!(align_of::<Mat3x3>() == 4);
+    assert!(align_of::<Mat4x4>() == 4);
+};
+
+///Perform linear interpolation between a and b, using t
+///
+///t MUST be a value between 0 and 1
+///
+///# Panics
+///
+///The function will panic if t is not in the [0, 1] range
+pub fn lerp<T>(a: T, b: T, t: f32) -> T
+where
+    T: Mul<f32, Output = T> + Add<T, Output = T> + Sub<T, Output = T> + Clone,
+{
+    //Check if the value is within bounds
+    assert!(t >= 0.0);
+    assert!(t <= 1.0);
+
+    a.clone() + ((b - a) * t)
+}
+
+///Formats the matrix with every row on a separate line and the columns aligned
+fn fmt_matrix<const N: usize>(
+    f: &mut core::fmt::Formatter<'_>,
+    name: &str,
+    rows: &[[f32; N]; N],
+) -> core::fmt::Result {
+    let cells = rows.map(|r| {
+        r.map(|v| {
+            f.precision()
+                .map_or_else(|| alloc::format!("{v:?}"), |p| alloc::format!("{v:.p$}"))
+        })
+    });
+    let width = cells.iter().flatten().map(alloc::string::String::len).max().unwrap_or(0);
+
+    writeln!(f, "{name} [")?;
+    for row in &cells {
+        write!(f, "    [")?;
+        for (i, c) in row.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{c:>width$}")?;
+        }
+        writeln!(f, "],")?;
+    }
+    write!(f, "]")
+}
+
+///Asserts that two values are approximately equal, using [`ApproxEq`]
+///
+///The default epsilon is `1e-5`, a different one can be provided as the third argument
+///
+///# Examples
+///```
+///# use lunar_engine_math::{assert_approx_eq, Vec3};
+///assert_approx_eq!(Vec3::new(0.1 + 0.2, 0.0, 0.0), Vec3::new(0.3, 0.0, 0.0));
+///assert_approx_eq!(1.0, 1.01, 0.1);
+///```
+#[macro_export]
+macro_rules! assert_approx_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_approx_eq!($left, $right, 1e-5)
+    };
+    ($left:expr, $right:expr, $epsilon:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                assert!(
+                    $crate::ApproxEq::approx_eq(left, right, $epsilon),
+                    "assertion `left ≈ right` failed (epsilon: {})\n  left: {:?}\n right: {:?}",
+                    $epsilon,
+                    left,
+                    right
+                );
+            }
+        }
+    };
+}
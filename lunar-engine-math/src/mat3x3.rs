@@ -0,0 +1,265 @@
+use core::ops::{Mul, Sub};
+
+use bytemuck::{Pod, Zeroable};
+
+use super::{mat4x4::Mat4x4, traits::ApproxEq, vec3::Vec3};
+#[cfg(not(feature = "std"))]
+use crate::float::Float;
+
+#[allow(missing_docs)]
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, PartialOrd, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+///A 3 by 3 matrix of `f32`
+pub struct Mat3x3 {
+    pub m00: f32,
+    pub m01: f32,
+    pub m02: f32,
+    pub m10: f32,
+    pub m11: f32,
+    pub m12: f32,
+    pub m20: f32,
+    pub m21: f32,
+    pub m22: f32,
+}
+
+impl Default for Mat3x3 {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Mat3x3 {
+    ///Identity matrix
+    #[must_use]
+    pub const fn identity() -> Self {
+        Self::new(1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0)
+    }
+
+    #[must_use]
+    #[allow(clippy::too_many_arguments)]
+    ///Creates a new matrix with the given data
+    pub const fn new(
+        m00: f32,
+        m01: f32,
+        m02: f32,
+        m10: f32,
+        m11: f32,
+        m12: f32,
+        m20: f32,
+        m21: f32,
+        m22: f32,
+    ) -> Self {
+        Self {
+            m00,
+            m01,
+            m02,
+            m10,
+            m11,
+            m12,
+            m20,
+            m21,
+            m22,
+        }
+    }
+
+    #[must_use]
+    ///Transposes the matrix, consuming it in the process
+    pub const fn transpose(self) -> Self {
+        Self::new(
+            self.m00, self.m10, self.m20, self.m01, self.m11, self.m21, self.m02, self.m12,
+            self.m22,
+        )
+    }
+
+    #[must_use]
+    ///Returns the determinant of the matrix
+    pub fn determinant(&self) -> f32 {
+        self.m02.mul_add(
+            self.m10.mul_add(self.m21, -self.m11 * self.m20),
+            self.m00.mul_add(
+                self.m11.mul_add(self.m22, -self.m12 * self.m21),
+                -self.m01 * self.m10.mul_add(self.m22, -self.m12 * self.m20),
+            ),
+        )
+    }
+
+    #[must_use]
+    ///Inverts the matrix
+    ///Returns None if the Matrix can not be inverted i.e. if the determenant is equal to zero
+    pub fn inverted(&self) -> Option<Self> {
+        let det = self.determinant();
+        if det == 0.0 {
+            return None;
+        }
+
+        Some(
+            Self::new(
+                self.m11.mul_add(self.m22, -self.m12 * self.m21),
+                self.m02.mul_add(self.m21, -self.m01 * self.m22),
+                self.m01.mul_add(self.m12, -self.m02 * self.m11),
+                self.m12.mul_add(self.m20, -self.m10 * self.m22),
+                self.m00.mul_add(self.m22, -self.m02 * self.m20),
+                self.m02.mul_add(self.m10, -self.m00 * self.m12),
+                self.m10.mul_add(self.m21, -self.m11 * self.m20),
+                self.m01.mul_add(self.m20, -self.m00 * self.m21),
+                self.m00.mul_add(self.m11, -self.m01 * self.m10),
+            ) * (1.0 / det),
+        )
+    }
+
+    #[must_use]
+    ///Creates the normal matrix of the transformation, the inverse transpose of its upper 3x3
+    ///part
+    ///
+    ///Used for transforming normals of non uniformly scaled meshes, if the transformation can not
+    ///be inverted, returns its upper 3x3 part
+    pub fn normal_matrix(transform: &Mat4x4) -> Self {
+        let mat = Self::from(*transform);
+        mat.inverted().map_or(mat, Self::transpose)
+    }
+
+    ///Transforms `other` using `self` matrix
+    #[must_use]
+    pub fn transform(&self, other: Vec3) -> Vec3 {
+        Vec3 {
+            x: self
+                .m02
+                .mul_add(other.z, self.m00.mul_add(other.x, self.m01 * other.y)),
+            y: self
+                .m12
+                .mul_add(other.z, self.m10.mul_add(other.x, self.m11 * other.y)),
+            z: self
+                .m22
+                .mul_add(other.z, self.m20.mul_add(other.x, self.m21 * other.y)),
+        }
+    }
+
+    ///Performs matrix multiplication `self` * `other`
+    #[must_use]
+    pub fn multiply(&self, other: Self) -> Self {
+        let row =
+            |a: f32, b: f32, c: f32, x: f32, y: f32, z: f32| c.mul_add(z, a.mul_add(x, b * y));
+
+        Self::new(
+            row(
+                self.m00, self.m01, self.m02, other.m00, other.m10, other.m20,
+            ),
+            row(
+                self.m00, self.m01, self.m02, other.m01, other.m11, other.m21,
+            ),
+            row(
+                self.m00, self.m01, self.m02, other.m02, other.m12, other.m22,
+            ),
+            row(
+                self.m10, self.m11, self.m12, other.m00, other.m10, other.m20,
+            ),
+            row(
+                self.m10, self.m11, self.m12, other.m01, other.m11, other.m21,
+            ),
+            row(
+                self.m10, self.m11, self.m12, other.m02, other.m12, other.m22,
+            ),
+            row(
+                self.m20, self.m21, self.m22, other.m00, other.m10, other.m20,
+            ),
+            row(
+                self.m20, self.m21, self.m22, other.m01, other.m11, other.m21,
+            ),
+            row(
+                self.m20, self.m21, self.m22, other.m02, other.m12, other.m22,
+            ),
+        )
+    }
+}
+
+impl From<Mat4x4> for Mat3x3 {
+    ///Takes the upper 3x3 part of the matrix
+    fn from(value: Mat4x4) -> Self {
+        Self::new(
+            value.m00, value.m01, value.m02, value.m10, value.m11, value.m12, value.m20, value.m21,
+            value.m22,
+        )
+    }
+}
+
+impl From<Mat3x3> for Mat4x4 {
+    fn from(value: Mat3x3) -> Self {
+        Self {
+            m00: value.m00,
+            m01: value.m01,
+            m02: value.m02,
+            m10: value.m10,
+            m11: value.m11,
+            m12: value.m12,
+            m20: value.m20,
+            m21: value.m21,
+            m22: value.m22,
+            ..Default::default()
+        }
+    }
+}
+
+impl Mul<f32> for Mat3x3 {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        Self::new(
+            self.m00 * rhs,
+            self.m01 * rhs,
+            self.m02 * rhs,
+            self.m10 * rhs,
+            self.m11 * rhs,
+            self.m12 * rhs,
+            self.m20 * rhs,
+            self.m21 * rhs,
+            self.m22 * rhs,
+        )
+    }
+}
+
+impl Sub<Self> for Mat3x3 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.m00 - rhs.m00,
+            self.m01 - rhs.m01,
+            self.m02 - rhs.m02,
+            self.m10 - rhs.m10,
+            self.m11 - rhs.m11,
+            self.m12 - rhs.m12,
+            self.m20 - rhs.m20,
+            self.m21 - rhs.m21,
+            self.m22 - rhs.m22,
+        )
+    }
+}
+
+impl Mul<Self> for Mat3x3 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.multiply(rhs)
+    }
+}
+
+impl Mul<Vec3> for Mat3x3 {
+    type Output = Vec3;
+
+    fn mul(self, rhs: Vec3) -> Self::Output {
+        self.transform(rhs)
+    }
+}
+
+impl ApproxEq for Mat3x3 {
+    fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        bytemuck::cast_ref::<Self, [f32; 9]>(self).approx_eq(bytemuck::cast_ref(other), epsilon)
+    }
+}
+
+impl core::fmt::Debug for Mat3x3 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        super::fmt_matrix(f, "Mat3x3", &bytemuck::cast::<Self, [[f32; 3]; 3]>(*self))
+    }
+}
@@ -1,14 +1,17 @@
 #![allow(clippy::too_many_arguments, dead_code)]
-use std::ops::{Add, Mul, Sub};
+use core::ops::{Add, Mul, Sub};
 
-use crate::math::vec4::Vec4;
-use crate::math::vec3::Vec3;
+use crate::vec4::Vec4;
+use crate::vec3::Vec3;
 
-use super::traits::Vector;
+use super::traits::{ApproxEq, Vector};
+#[cfg(not(feature = "std"))]
+use crate::float::Float;
 
 #[allow(missing_docs)]
 #[repr(C)]
-#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Clone, Copy, PartialEq, PartialOrd, bytemuck::Pod, bytemuck::Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 ///A 4 by 4 matrix of `f32`
 pub struct Mat4x4 {
     pub m00: f32,
@@ -129,7 +132,7 @@ pub const fn identity() -> Self {
     //TODO: SIMD THE SHIT OUT THIS
     #[must_use]
     pub fn transform(&self, other: Vec4) -> Vec4 {
-        Vec4 {
+        let out = Vec4 {
             x: self.m03.mul_add(
                 other.w,
                 self.m02
@@ -150,7 +153,9 @@ pub const fn identity() -> Self {
                 self.m32
                     .mul_add(other.z, self.m30.mul_add(other.x, self.m31 * other.y)),
             ),
-        }
+        };
+        debug_assert!(out.is_finite(), "Transformation produced a non finite vector");
+        out
     }
 
     ///Transforms `other` using `self` matrix
@@ -164,7 +169,7 @@ pub const fn identity() -> Self {
     ///Performs matrix multiplication `self` * `other`
     #[must_use]
     pub fn multiply(&self, other: Self) -> Self {
-        Self {
+        let out = Self {
             m00: self.m03.mul_add(
                 other.m30,
                 self.m02
@@ -245,7 +250,30 @@ pub const fn identity() -> Self {
                 self.m32
                     .mul_add(other.m23, self.m30.mul_add(other.m03, self.m31 * other.m13)),
             ),
-        }
+        };
+        debug_assert!(out.is_finite(), "Matrix multiplication produced a non finite matrix");
+        out
+    }
+
+    ///Returns `true` if none of the elements are NaN or infinite
+    #[must_use]
+    pub fn is_finite(&self) -> bool {
+        bytemuck::cast_ref::<Self, [f32; 16]>(self)
+            .iter()
+            .all(|i| i.is_finite())
+    }
+
+    ///Returns `true` if the matrix is a valid affine transformation, meaning that all the elements
+    ///are finite, the last row is `[0, 0, 0, 1]` and the matrix can be inverted, i.e. it doesn't
+    ///have a scale of 0
+    #[must_use]
+    pub fn is_valid_transform(&self) -> bool {
+        self.is_finite()
+            && self.m30 == 0.0
+            && self.m31 == 0.0
+            && self.m32 == 0.0
+            && (self.m33 - 1.0).abs() < f32::EPSILON
+            && self.determinant() != 0.0
     }
 
     #[must_use]
@@ -354,7 +382,7 @@ pub const fn identity() -> Self {
         screen_near: f32,
         screen_far: f32,
     ) -> Self {
-        let (sin_fov, cos_fov) = f32::sin_cos(0.5 * fov);
+        let (sin_fov, cos_fov) = (0.5 * fov).sin_cos();
         // 1/ tan(FOV / 2 ) = cot(FOV / 2)
         let h = cos_fov / sin_fov;
         let w = h / screen_aspect;
@@ -371,6 +399,28 @@ pub const fn identity() -> Self {
         }
     }
 
+    #[must_use]
+    ///Creates a perspective projection matrix with the far plane at infinity
+    ///
+    ///Points infinitely far away are mapped to a depth of 1, combine with
+    ///[`Mat4x4::reverse_depth`] for better depth precision
+    pub fn perspective_infinite(fov: f32, aspect: f32, near: f32) -> Self {
+        let (sin_fov, cos_fov) = (0.5 * fov).sin_cos();
+        let h = cos_fov / sin_fov;
+        let w = h / aspect;
+
+        //Limit of the regular projection as far approaches infinity
+        Self {
+            m00: w,
+            m11: h,
+            m22: -1.0,
+            m23: -1.0,
+            m32: -near,
+            m33: 0.0,
+            ..Default::default()
+        }
+    }
+
     ///TODO
     #[must_use]
     pub fn orth_aspect_projection(size: f32, aspect: f32, near:f32, far: f32) -> Self{
@@ -394,6 +444,20 @@ pub const fn identity() -> Self {
         }
     }
 
+    ///Reverses the depth range of a projection matrix, mapping the near plane to a depth of 1 and
+    ///the far plane to 0
+    #[must_use]
+    pub fn reverse_depth(self) -> Self {
+        //z' = w - z
+        Self {
+            m02: self.m03 - self.m02,
+            m12: self.m13 - self.m12,
+            m22: self.m23 - self.m22,
+            m32: self.m33 - self.m32,
+            ..self
+        }
+    }
+
     #[must_use]
     ///Creates a scale matrix for the given vector
     pub fn scale_matrix(scale: &Vec3) -> Self {
@@ -452,6 +516,10 @@ pub const fn identity() -> Self {
     ///2. Rotation 
     ///3. Translation
     pub fn transform_matrix_euler(translation: &Vec3, scale: &Vec3, rotation: &Vec3) -> Self {
+        debug_assert!(
+            translation.is_finite() && scale.is_finite() && rotation.is_finite(),
+            "Creating a transformation matrix from non finite values"
+        );
         Self::translation_matrix(translation)
             * Self::rotation_matrix_euler(rotation)
             * Self::scale_matrix(scale)
@@ -582,3 +650,16 @@ impl Mul<Mat4x4> for Vec4{
         rhs.transform(self)
     }
 }
+
+impl ApproxEq for Mat4x4 {
+    fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        bytemuck::cast_ref::<Self, [f32; 16]>(self)
+            .approx_eq(bytemuck::cast_ref(other), epsilon)
+    }
+}
+
+impl core::fmt::Debug for Mat4x4 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        super::fmt_matrix(f, "Mat4x4", &bytemuck::cast::<Self, [[f32; 4]; 4]>(*self))
+    }
+}
@@ -0,0 +1,93 @@
+//Conversions between the engine and mint types
+use super::{Mat4x4, Quaternion, Vec2, Vec3, Vec4};
+
+impl From<mint::Vector2<f32>> for Vec2 {
+    fn from(value: mint::Vector2<f32>) -> Self {
+        Self::new(value.x, value.y)
+    }
+}
+
+impl From<Vec2> for mint::Vector2<f32> {
+    fn from(value: Vec2) -> Self {
+        Self {
+            x: value.x,
+            y: value.y,
+        }
+    }
+}
+
+impl From<mint::Vector3<f32>> for Vec3 {
+    fn from(value: mint::Vector3<f32>) -> Self {
+        Self::new(value.x, value.y, value.z)
+    }
+}
+
+impl From<Vec3> for mint::Vector3<f32> {
+    fn from(value: Vec3) -> Self {
+        Self {
+            x: value.x,
+            y: value.y,
+            z: value.z,
+        }
+    }
+}
+
+impl From<mint::Vector4<f32>> for Vec4 {
+    fn from(value: mint::Vector4<f32>) -> Self {
+        Self::new(value.x, value.y, value.z, value.w)
+    }
+}
+
+impl From<Vec4> for mint::Vector4<f32> {
+    fn from(value: Vec4) -> Self {
+        Self {
+            x: value.x,
+            y: value.y,
+            z: value.z,
+            w: value.w,
+        }
+    }
+}
+
+impl From<mint::Quaternion<f32>> for Quaternion {
+    fn from(value: mint::Quaternion<f32>) -> Self {
+        Self::new(value.v.x, value.v.y, value.v.z, value.s)
+    }
+}
+
+impl From<Quaternion> for mint::Quaternion<f32> {
+    fn from(value: Quaternion) -> Self {
+        Self {
+            v: mint::Vector3 {
+                x: value.x,
+                y: value.y,
+                z: value.z,
+            },
+            s: value.w,
+        }
+    }
+}
+
+impl From<mint::RowMatrix4<f32>> for Mat4x4 {
+    fn from(value: mint::RowMatrix4<f32>) -> Self {
+        bytemuck::cast::<[[f32; 4]; 4], Self>(value.into())
+    }
+}
+
+impl From<Mat4x4> for mint::RowMatrix4<f32> {
+    fn from(value: Mat4x4) -> Self {
+        bytemuck::cast::<Mat4x4, [[f32; 4]; 4]>(value).into()
+    }
+}
+
+impl From<mint::ColumnMatrix4<f32>> for Mat4x4 {
+    fn from(value: mint::ColumnMatrix4<f32>) -> Self {
+        bytemuck::cast::<[[f32; 4]; 4], Self>(value.into()).transpose()
+    }
+}
+
+impl From<Mat4x4> for mint::ColumnMatrix4<f32> {
+    fn from(value: Mat4x4) -> Self {
+        bytemuck::cast::<Mat4x4, [[f32; 4]; 4]>(value.transpose()).into()
+    }
+}
@@ -0,0 +1,241 @@
+use core::ops::{Mul, MulAssign};
+
+use bytemuck::{Pod, Zeroable};
+
+use super::{
+    mat4x4::Mat4x4,
+    traits::{ApproxEq, Vector},
+    vec3::Vec3,
+};
+#[cfg(not(feature = "std"))]
+use crate::float::Float;
+
+#[repr(C)]
+#[allow(missing_docs)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+///A quaternion representing a rotation
+pub struct Quaternion {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Quaternion {
+    #[must_use]
+    ///Creates a new quaternion
+    pub const fn new(x: f32, y: f32, z: f32, w: f32) -> Self {
+        Self { x, y, z, w }
+    }
+
+    #[must_use]
+    ///Quaternion that represents no rotation
+    pub const fn identity() -> Self {
+        Self::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    #[must_use]
+    ///Creates a rotation of `angle` degrees around the `axis`
+    pub fn from_axis_angle(axis: Vec3, angle: f32) -> Self {
+        let (sin, cos) = (angle.to_radians() * 0.5).sin_cos();
+        let axis = axis.normalize() * sin;
+        Self::new(axis.x, axis.y, axis.z, cos)
+    }
+
+    #[must_use]
+    ///Creates a rotation from euler angles in degrees, with the same order of rotations as
+    ///[`Mat4x4::rotation_matrix_euler`]
+    pub fn from_euler(rotation: &Vec3) -> Self {
+        Self::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), rotation.z)
+            * Self::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), rotation.y)
+            * Self::from_axis_angle(Vec3::new(1.0, 0.0, 0.0), rotation.x)
+    }
+
+    #[must_use]
+    ///Returns the squared length of the quaternion
+    pub fn square_length(&self) -> f32 {
+        self.w.mul_add(
+            self.w,
+            self.z
+                .mul_add(self.z, self.x.mul_add(self.x, self.y * self.y)),
+        )
+    }
+
+    #[must_use]
+    ///Returns the quaternion with the length of 1
+    pub fn normalized(self) -> Self {
+        let len = self.square_length().sqrt();
+        if len == 0.0 {
+            return Self::identity();
+        }
+        Self::new(self.x / len, self.y / len, self.z / len, self.w / len)
+    }
+
+    #[must_use]
+    ///Returns the conjugate of the quaternion, which for unit quaternions is the inverse rotation
+    pub const fn conjugate(self) -> Self {
+        Self::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    #[must_use]
+    ///Rotates the vector by the quaternion
+    pub fn rotate(&self, vector: Vec3) -> Vec3 {
+        let q = Vec3::new(self.x, self.y, self.z);
+        let t = q.cross(&vector) * 2.0;
+        vector + t * self.w + q.cross(&t)
+    }
+
+    #[must_use]
+    ///Creates a rotation matrix from the quaternion
+    pub fn matrix(&self) -> Mat4x4 {
+        let Self { x, y, z, w } = *self;
+
+        Mat4x4 {
+            m00: 2.0f32.mul_add(-y.mul_add(y, z * z), 1.0),
+            m01: 2.0 * x.mul_add(y, -z * w),
+            m02: 2.0 * x.mul_add(z, y * w),
+            m10: 2.0 * x.mul_add(y, z * w),
+            m11: 2.0f32.mul_add(-x.mul_add(x, z * z), 1.0),
+            m12: 2.0 * y.mul_add(z, -x * w),
+            m20: 2.0 * x.mul_add(z, -y * w),
+            m21: 2.0 * y.mul_add(z, x * w),
+            m22: 2.0f32.mul_add(-x.mul_add(x, y * y), 1.0),
+            ..Default::default()
+        }
+    }
+
+    #[must_use]
+    ///Creates a quaternion from the rotation part of the matrix, the matrix must not contain any
+    ///scale
+    pub fn from_matrix(matrix: &Mat4x4) -> Self {
+        let m = matrix;
+        let trace = m.m00 + m.m11 + m.m22;
+        let q = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Self::new(
+                (m.m21 - m.m12) / s,
+                (m.m02 - m.m20) / s,
+                (m.m10 - m.m01) / s,
+                0.25 * s,
+            )
+        } else if m.m00 > m.m11 && m.m00 > m.m22 {
+            let s = (1.0 + m.m00 - m.m11 - m.m22).sqrt() * 2.0;
+            Self::new(
+                0.25 * s,
+                (m.m01 + m.m10) / s,
+                (m.m02 + m.m20) / s,
+                (m.m21 - m.m12) / s,
+            )
+        } else if m.m11 > m.m22 {
+            let s = (1.0 + m.m11 - m.m00 - m.m22).sqrt() * 2.0;
+            Self::new(
+                (m.m01 + m.m10) / s,
+                0.25 * s,
+                (m.m12 + m.m21) / s,
+                (m.m02 - m.m20) / s,
+            )
+        } else {
+            let s = (1.0 + m.m22 - m.m00 - m.m11).sqrt() * 2.0;
+            Self::new(
+                (m.m02 + m.m20) / s,
+                (m.m12 + m.m21) / s,
+                0.25 * s,
+                (m.m10 - m.m01) / s,
+            )
+        };
+        q.normalized()
+    }
+
+    #[must_use]
+    ///Returns the dot product of the quaternions
+    pub fn dot(&self, other: &Self) -> f32 {
+        self.w.mul_add(
+            other.w,
+            self.z
+                .mul_add(other.z, self.x.mul_add(other.x, self.y * other.y)),
+        )
+    }
+
+    #[must_use]
+    ///Spherically interpolates between the rotations, taking the shortest path
+    ///
+    ///`t` of 0 returns `self`, `t` of 1 returns `other`
+    pub fn slerp(self, other: Self, t: f32) -> Self {
+        let mut cos = self.dot(&other);
+        //Both quaternions represent the same rotation, go the short way around
+        let other = if cos < 0.0 {
+            cos = -cos;
+            Self::new(-other.x, -other.y, -other.z, -other.w)
+        } else {
+            other
+        };
+
+        //Nearly parallel, fall back to linear interpolation to avoid dividing by 0
+        let (a, b) = if cos > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let angle = cos.acos();
+            let sin = angle.sin();
+            (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+
+        Self::new(
+            self.x.mul_add(a, other.x * b),
+            self.y.mul_add(a, other.y * b),
+            self.z.mul_add(a, other.z * b),
+            self.w.mul_add(a, other.w * b),
+        )
+        .normalized()
+    }
+
+    #[must_use]
+    ///Returns `true` if none of the components are NaN or infinite
+    pub fn is_finite(&self) -> bool {
+        self.x.is_finite() && self.y.is_finite() && self.z.is_finite() && self.w.is_finite()
+    }
+}
+
+impl Mul<Self> for Quaternion {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let a = Vec3::new(self.x, self.y, self.z);
+        let b = Vec3::new(rhs.x, rhs.y, rhs.z);
+
+        let v = b * self.w + a * rhs.w + a.cross(&b);
+        Self::new(v.x, v.y, v.z, self.w.mul_add(rhs.w, -a.dot_product(&b)))
+    }
+}
+
+impl MulAssign<Self> for Quaternion {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl Mul<Vec3> for Quaternion {
+    type Output = Vec3;
+
+    fn mul(self, rhs: Vec3) -> Self::Output {
+        self.rotate(rhs)
+    }
+}
+
+impl core::fmt::Display for Quaternion {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "({}, {}, {}, {})", self.x, self.y, self.z, self.w)
+    }
+}
+
+impl ApproxEq for Quaternion {
+    fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        bytemuck::cast_ref::<Self, [f32; 4]>(self).approx_eq(bytemuck::cast_ref(other), epsilon)
+    }
+}
@@ -0,0 +1,148 @@
+//Swizzle accessors of the vectors, e.g. `v.xz()`, `v.zyx()`
+use super::{Vec2, Vec3, Vec4};
+
+macro_rules! swizzle {
+    ($type:ty => $out:ident { $($name:ident: $($c:ident),+;)+ }) => {
+        impl $type {
+            $(
+                #[must_use]
+                #[doc = concat!("Returns a [`", stringify!($out), "`] made of the `", stringify!($name), "` components")]
+                pub const fn $name(self) -> $out {
+                    $out::new($(self.$c),+)
+                }
+            )+
+        }
+    };
+}
+
+swizzle!(Vec2 => Vec2 {
+    xx: x, x;
+    yx: y, x;
+    yy: y, y;
+});
+
+swizzle!(Vec3 => Vec2 {
+    xx: x, x;
+    xy: x, y;
+    xz: x, z;
+    yx: y, x;
+    yy: y, y;
+    yz: y, z;
+    zx: z, x;
+    zy: z, y;
+    zz: z, z;
+});
+
+swizzle!(Vec3 => Vec3 {
+    xxx: x, x, x;
+    xxy: x, x, y;
+    xxz: x, x, z;
+    xyx: x, y, x;
+    xyy: x, y, y;
+    xzx: x, z, x;
+    xzy: x, z, y;
+    xzz: x, z, z;
+    yxx: y, x, x;
+    yxy: y, x, y;
+    yxz: y, x, z;
+    yyx: y, y, x;
+    yyy: y, y, y;
+    yyz: y, y, z;
+    yzx: y, z, x;
+    yzy: y, z, y;
+    yzz: y, z, z;
+    zxx: z, x, x;
+    zxy: z, x, y;
+    zxz: z, x, z;
+    zyx: z, y, x;
+    zyy: z, y, y;
+    zyz: z, y, z;
+    zzx: z, z, x;
+    zzy: z, z, y;
+    zzz: z, z, z;
+});
+
+swizzle!(Vec4 => Vec2 {
+    xx: x, x;
+    xy: x, y;
+    xz: x, z;
+    xw: x, w;
+    yx: y, x;
+    yy: y, y;
+    yz: y, z;
+    yw: y, w;
+    zx: z, x;
+    zy: z, y;
+    zz: z, z;
+    zw: z, w;
+    wx: w, x;
+    wy: w, y;
+    wz: w, z;
+    ww: w, w;
+});
+
+swizzle!(Vec4 => Vec3 {
+    xxx: x, x, x;
+    xxy: x, x, y;
+    xxz: x, x, z;
+    xxw: x, x, w;
+    xyx: x, y, x;
+    xyy: x, y, y;
+    xyw: x, y, w;
+    xzx: x, z, x;
+    xzy: x, z, y;
+    xzz: x, z, z;
+    xzw: x, z, w;
+    xwx: x, w, x;
+    xwy: x, w, y;
+    xwz: x, w, z;
+    xww: x, w, w;
+    yxx: y, x, x;
+    yxy: y, x, y;
+    yxz: y, x, z;
+    yxw: y, x, w;
+    yyx: y, y, x;
+    yyy: y, y, y;
+    yyz: y, y, z;
+    yyw: y, y, w;
+    yzx: y, z, x;
+    yzy: y, z, y;
+    yzz: y, z, z;
+    yzw: y, z, w;
+    ywx: y, w, x;
+    ywy: y, w, y;
+    ywz: y, w, z;
+    yww: y, w, w;
+    zxx: z, x, x;
+    zxy: z, x, y;
+    zxz: z, x, z;
+    zxw: z, x, w;
+    zyx: z, y, x;
+    zyy: z, y, y;
+    zyz: z, y, z;
+    zyw: z, y, w;
+    zzx: z, z, x;
+    zzy: z, z, y;
+    zzz: z, z, z;
+    zzw: z, z, w;
+    zwx: z, w, x;
+    zwy: z, w, y;
+    zwz: z, w, z;
+    zww: z, w, w;
+    wxx: w, x, x;
+    wxy: w, x, y;
+    wxz: w, x, z;
+    wxw: w, x, w;
+    wyx: w, y, x;
+    wyy: w, y, y;
+    wyz: w, y, z;
+    wyw: w, y, w;
+    wzx: w, z, x;
+    wzy: w, z, y;
+    wzz: w, z, z;
+    wzw: w, z, w;
+    wwx: w, w, x;
+    wwy: w, w, y;
+    wwz: w, w, z;
+    www: w, w, w;
+});
@@ -0,0 +1,680 @@
+use super::*;
+use crate::assert_approx_eq;
+
+#[test]
+fn test_matrix_float_mul() {
+    let a = Mat4x4::new(
+        1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0,
+    );
+    let b = 2.0;
+
+    let c = a * b;
+
+    assert_eq!(
+        c,
+        Mat4x4::new(
+            2.0, 4.0, 6.0, 8.0, 10.0, 12.0, 14.0, 16.0, 18.0, 20.0, 22.0, 24.0, 26.0, 28.0, 30.0,
+            32.0
+        )
+    );
+}
+
+#[test]
+fn test_transformation() {
+    let a = Mat4x4::identity();
+    let b = Vec4::new(1.0, 2.0, 3.0, 4.0);
+    let c = a.transform(b);
+
+    assert_eq!(c, b);
+
+    let a = Mat4x4::new(
+        1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0,
+    );
+
+    let c = a.transform(b);
+
+    assert_eq!(c, Vec4::new(30.0, 70.0, 110.0, 150.0));
+}
+
+#[test]
+fn test_mat_mul_1() {
+    let a = Mat4x4::new(
+        1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0,
+    );
+    let b = Mat4x4::new(
+        2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0, 17.0,
+    );
+
+    let o = a.multiply(b);
+    let expected = Mat4x4::new(
+        100.0, 110.0, 120.0, 130.0, 228.0, 254.0, 280.0, 306.0, 356.0, 398.0, 440.0, 482.0, 484.0,
+        542.0, 600.0, 658.0,
+    );
+    assert_eq!(o, expected);
+}
+
+#[test]
+fn test_mat_identity_mul() {
+    let a = Mat4x4::new(
+        1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0,
+    );
+    let o = a.multiply(Mat4x4::default());
+    let expected = Mat4x4::new(
+        1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0,
+    );
+    assert_eq!(o, expected);
+}
+
+#[test]
+fn test_mat_mat_mul() {
+    let a = Mat4x4::new(
+        1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0,
+    );
+    let o = a.multiply(a);
+    let expected = Mat4x4::new(
+        90.0, 100.0, 110.0, 120.0, 202.0, 228.0, 254.0, 280.0, 314.0, 356.0, 398.0, 440.0, 426.0,
+        484.0, 542.0, 600.0,
+    );
+    assert_eq!(o, expected);
+}
+
+#[test]
+fn test_determinant() {
+    let a = Mat4x4::new(
+        1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0,
+    );
+
+    let o = a.determinant();
+    let expected = 0.0;
+
+    assert_approx_eq!(o, expected);
+
+    let a = Mat4x4::new(
+        1.0, 0.0, 0.0, 0.0, 5.0, 6.0, 7.0, 8.0, 0.0, 0.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0,
+    );
+
+    let o = a.determinant();
+    let expected = -80.0;
+    assert_approx_eq!(o, expected);
+}
+
+#[test]
+fn test_transpose() {
+    let a = Mat4x4::new(
+        1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0,
+    );
+
+    let o = a.transpose();
+    let expected = Mat4x4 {
+        m00: 1.0,
+        m01: 5.0,
+        m02: 9.0,
+        m03: 13.0,
+        m10: 2.0,
+        m11: 6.0,
+        m12: 10.0,
+        m13: 14.0,
+        m20: 3.0,
+        m21: 7.0,
+        m22: 11.0,
+        m23: 15.0,
+        m30: 4.0,
+        m31: 8.0,
+        m32: 12.0,
+        m33: 16.0,
+    };
+    assert_eq!(o, expected);
+
+    let a = Mat4x4::new(
+        1.0, 0.0, 0.0, 0.0, 5.0, 6.0, 7.0, 8.0, 0.0, 0.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0,
+    );
+
+    let o = a.determinant();
+    let expected = -80.0;
+    assert_approx_eq!(o, expected);
+}
+
+#[test]
+fn test_vec2_dot_product() {
+    let a = Vec2::new(1.0, 0.0);
+    let b = Vec2::new(0.0, 1.0);
+
+    let expected = 0.0;
+    let result = a.dot_product(&b);
+
+    assert_approx_eq!(expected, result);
+    let a = Vec2::new(1.0, 0.0);
+    let b = Vec2::new(1.0, 0.0);
+
+    let expected = 1.0;
+    let result = a.dot_product(&b);
+
+    assert_approx_eq!(expected, result);
+}
+
+#[test]
+fn test_vec2_length() {
+    let a = Vec2::new(1.0, 2.0);
+    assert_approx_eq!(a.square_length(), 5.0);
+}
+
+#[test]
+fn test_vec3_dot_product() {
+    let a = Vec3::new(1.0, 0.0, 0.0);
+    let b = Vec3::new(0.0, 1.0, 0.0);
+
+    let expected = 0.0;
+    let result = a.dot_product(&b);
+
+    assert_approx_eq!(expected, result);
+    let a = Vec3::new(1.0, 0.0, 0.0);
+    let b = Vec3::new(1.0, 0.0, 0.0);
+
+    let expected = 1.0;
+    let result = a.dot_product(&b);
+
+    assert_approx_eq!(expected, result);
+}
+
+#[test]
+fn test_vec3_length() {
+    let a = Vec3::new(1.0, 2.0, 2.0);
+    assert_approx_eq!(a.square_length(), 9.0);
+    assert_approx_eq!(a.length(), 3.0);
+}
+
+#[test]
+fn test_vec4_dot_product() {
+    let a = Vec4::new(1.0, 0.0, 0.0, 0.0);
+    let b = Vec4::new(0.0, 1.0, 0.0, 0.0);
+
+    let expected = 0.0;
+    let result = a.dot_product(&b);
+
+    assert_approx_eq!(expected, result);
+    let a = Vec4::new(1.0, 0.0, 0.0, 0.0);
+    let b = Vec4::new(1.0, 0.0, 0.0, 0.0);
+
+    let expected = 1.0;
+    let result = a.dot_product(&b);
+
+    assert_approx_eq!(expected, result);
+}
+
+#[test]
+fn test_vec4_length() {
+    let a = Vec4::new(1.0, 2.0, 2.0, 0.0);
+    assert_approx_eq!(a.square_length(), 9.0);
+    assert_approx_eq!(a.length(), 3.0);
+}
+
+#[test]
+fn test_lerp() {
+    let a = 0.0;
+    let b = 1.0;
+    let t = 0.5;
+    let expected = 0.5;
+
+    let o = lerp(a, b, t);
+
+    assert_approx_eq!(o, expected);
+
+    let a = Vec2::new(0.0, 1.0);
+    let b = Vec2::new(1.0, 0.0);
+    let t = 0.5;
+    let expected = Vec2::new(0.5, 0.5);
+
+    let o = lerp(a, b, t);
+
+    assert_approx_eq!(o, expected);
+
+    let a = Vec3::new(0.0, 1.0, 0.0);
+    let b = Vec3::new(1.0, 0.0, 1.0);
+    let t = 0.5;
+    let expected = Vec3::new(0.5, 0.5, 0.5);
+
+    let o = lerp(a, b, t);
+
+    assert_approx_eq!(o, expected);
+
+    let a = Vec4::new(0.0, 1.0, 0.0, 1.0);
+    let b = Vec4::new(1.0, 0.0, 1.0, 0.0);
+    let t = 0.5;
+    let expected = Vec4::new(0.5, 0.5, 0.5, 0.5);
+
+    let o = lerp(a, b, t);
+
+    assert_approx_eq!(o, expected);
+}
+
+#[test]
+fn test_is_finite() {
+    assert!(Vec3::new(1.0, 2.0, 3.0).is_finite());
+    assert!(!Vec3::new(1.0, f32::NAN, 3.0).is_finite());
+    assert!(!Vec4::new(1.0, 2.0, 3.0, f32::INFINITY).is_finite());
+
+    let mut mat = Mat4x4::identity();
+    assert!(mat.is_finite());
+    mat.m12 = f32::NAN;
+    assert!(!mat.is_finite());
+}
+
+#[test]
+fn test_valid_transform() {
+    let mat = Mat4x4::transform_matrix_euler(
+        &Vec3::new(1.0, 2.0, 3.0),
+        &Vec3::new(1.0, 2.0, 1.0),
+        &Vec3::new(45.0, 0.0, 10.0),
+    );
+    assert!(mat.is_valid_transform());
+
+    let mat = Mat4x4::scale_matrix(&Vec3::new(1.0, 0.0, 1.0));
+    assert!(!mat.is_valid_transform());
+
+    let mat = Mat4x4::perspercive_projection(1.0, 1.0, 0.1, 100.0);
+    assert!(!mat.is_valid_transform());
+}
+
+#[test]
+fn test_quaternion_euler() {
+    let rotation = Vec3::new(30.0, 45.0, 60.0);
+    let a = Quaternion::from_euler(&rotation).matrix();
+    let b = Mat4x4::rotation_matrix_euler(&rotation);
+
+    assert_approx_eq!(a, b);
+}
+
+#[test]
+fn test_quaternion_rotate() {
+    let q = Quaternion::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), 90.0);
+    let v = q * Vec3::new(1.0, 0.0, 0.0);
+
+    assert_approx_eq!(v, Vec3::new(0.0, 1.0, 0.0));
+
+    let v = q.conjugate() * v;
+    assert_approx_eq!(v, Vec3::new(1.0, 0.0, 0.0));
+}
+
+#[test]
+fn test_quaternion_slerp() {
+    let a = Quaternion::identity();
+    let b = Quaternion::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), 90.0);
+
+    assert_approx_eq!(a.slerp(b, 0.0), a);
+    assert_approx_eq!(a.slerp(b, 1.0), b);
+    assert_approx_eq!(
+        a.slerp(b, 0.5),
+        Quaternion::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), 45.0)
+    );
+
+    //The negated quaternion is the same rotation, the short path must be taken
+    let b = Quaternion::new(-b.x, -b.y, -b.z, -b.w);
+    assert_approx_eq!(
+        a.slerp(b, 0.5).matrix(),
+        Quaternion::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), 45.0).matrix()
+    );
+}
+
+#[test]
+fn test_quaternion_from_matrix() {
+    for rotation in [
+        Vec3::new(30.0, 45.0, 60.0),
+        Vec3::new(170.0, 0.0, 0.0),
+        Vec3::new(0.0, 175.0, 10.0),
+        Vec3::new(0.0, 0.0, 179.0),
+    ] {
+        let mat = Mat4x4::rotation_matrix_euler(&rotation);
+        assert_approx_eq!(Quaternion::from_matrix(&mat).matrix(), mat);
+    }
+}
+
+#[test]
+fn test_mat3_inverse() {
+    let mat = Mat3x3::new(2.0, 0.0, 1.0, 1.0, 3.0, 0.0, 0.0, 1.0, 4.0);
+    let inv = mat.inverted().unwrap();
+
+    assert_approx_eq!(mat * inv, Mat3x3::identity());
+
+    assert_eq!(
+        Mat3x3::new(1.0, 2.0, 3.0, 2.0, 4.0, 6.0, 0.0, 0.0, 1.0).inverted(),
+        None
+    );
+}
+
+#[test]
+fn test_normal_matrix() {
+    //Non uniform scale must keep the normal perpendicular to the surface
+    let transform = Mat4x4::scale_matrix(&Vec3::new(2.0, 1.0, 1.0));
+    let normal_mat = Mat3x3::normal_matrix(&transform);
+
+    let tangent = Mat3x3::from(transform) * Vec3::new(1.0, 1.0, 0.0);
+    let normal = normal_mat * Vec3::new(1.0, -1.0, 0.0);
+
+    assert_approx_eq!(tangent.dot_product(&normal), 0.0);
+}
+
+#[test]
+fn test_ray_intersections() {
+    use super::geometry::{Aabb, Plane, Ray, Sphere};
+
+    let ray = Ray::new(Vec3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+    let aabb = Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+
+    assert_eq!(ray.intersect_aabb(&aabb), Some(4.0));
+    assert_eq!(
+        Ray::new(Vec3::new(0.0, 2.0, -5.0), Vec3::new(0.0, 0.0, 1.0)).intersect_aabb(&aabb),
+        None
+    );
+    assert_eq!(
+        Ray::new(Vec3::default(), Vec3::new(0.0, 0.0, 1.0)).intersect_aabb(&aabb),
+        Some(0.0)
+    );
+
+    let sphere = Sphere::new(Vec3::default(), 2.0);
+    assert_eq!(ray.intersect_sphere(&sphere), Some(3.0));
+    assert_eq!(
+        Ray::new(Vec3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, -1.0)).intersect_sphere(&sphere),
+        None
+    );
+
+    let plane = Plane::from_point_normal(Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+    assert_eq!(ray.intersect_plane(&plane), Some(6.0));
+}
+
+#[test]
+fn test_volume_intersections() {
+    use super::geometry::{Aabb, Sphere};
+
+    let a = Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+    let b = Aabb::from_center_extents(Vec3::new(1.5, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0));
+    let c = Aabb::from_center_extents(Vec3::new(3.5, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0));
+
+    assert!(a.intersects_aabb(&b));
+    assert!(!a.intersects_aabb(&c));
+
+    let s = Sphere::new(Vec3::new(2.5, 0.0, 0.0), 1.0);
+    assert!(!s.intersects_aabb(&a));
+    assert!(s.intersects_aabb(&b));
+    assert!(s.intersects_sphere(&Sphere::new(Vec3::new(4.0, 0.0, 0.0), 0.5)));
+
+    let moved = a.transformed(&Mat4x4::transform_matrix_euler(
+        &Vec3::new(10.0, 0.0, 0.0),
+        &Vec3::new(2.0, 1.0, 1.0),
+        &Vec3::default(),
+    ));
+    assert_eq!(moved.min, Vec3::new(8.0, -1.0, -1.0));
+    assert_eq!(moved.max, Vec3::new(12.0, 1.0, 1.0));
+}
+
+#[test]
+fn test_frustum() {
+    use super::geometry::{Aabb, Frustum, Obb, Sphere};
+
+    //Projection matrices are stored for row vectors
+    let frustum = Frustum::from_matrix(
+        &Mat4x4::perspercive_projection(std::f32::consts::FRAC_PI_2, 1.0, 0.1, 100.0).transpose(),
+    );
+
+    assert!(frustum.contains_point(Vec3::new(0.0, 0.0, -5.0)));
+    assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, 5.0)));
+    assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, -200.0)));
+    assert!(!frustum.contains_point(Vec3::new(10.0, 0.0, -5.0)));
+
+    assert!(frustum.intersects_sphere(&Sphere::new(Vec3::new(0.0, 0.0, 5.0), 6.0)));
+    assert!(!frustum.intersects_sphere(&Sphere::new(Vec3::new(0.0, 0.0, 5.0), 1.0)));
+
+    let aabb = Aabb::from_center_extents(Vec3::new(8.0, 0.0, -5.0), Vec3::new(1.0, 1.0, 1.0));
+    assert!(!frustum.intersects_aabb(&aabb));
+    let aabb = Aabb::from_center_extents(Vec3::new(5.5, 0.0, -5.0), Vec3::new(1.0, 1.0, 1.0));
+    assert!(frustum.intersects_aabb(&aabb));
+
+    let obb = Obb::from_aabb(
+        &Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0)),
+        &Mat4x4::transform_matrix_euler(
+            &Vec3::new(9.0, 0.0, -5.0),
+            &Vec3::new(1.0, 1.0, 1.0),
+            &Vec3::new(0.0, 45.0, 0.0),
+        ),
+    );
+    assert!(!frustum.intersects_obb(&obb));
+}
+
+#[test]
+fn test_swizzle() {
+    let v = Vec4::new(1.0, 2.0, 3.0, 4.0);
+    assert_eq!(v.xz(), Vec2::new(1.0, 3.0));
+    assert_eq!(v.wzy(), Vec3::new(4.0, 3.0, 2.0));
+    assert_eq!(Vec2::new(1.0, 2.0).yx(), Vec2::new(2.0, 1.0));
+    assert_eq!(Vec3::new(1.0, 2.0, 3.0).zzx(), Vec3::new(3.0, 3.0, 1.0));
+}
+
+#[test]
+fn test_component_wise() {
+    let a = Vec3::new(1.0, -2.5, 3.0);
+    let b = Vec3::new(-1.0, 2.0, 5.0);
+
+    assert_eq!(a.min(b), Vec3::new(-1.0, -2.5, 3.0));
+    assert_eq!(a.max(b), Vec3::new(1.0, 2.0, 5.0));
+    assert_eq!(a.floor(), Vec3::new(1.0, -3.0, 3.0));
+    assert_eq!(a.ceil(), Vec3::new(1.0, -2.0, 3.0));
+    assert_eq!(a * b, Vec3::new(-1.0, -5.0, 15.0));
+    assert_eq!(2.0 * a, a * 2.0);
+    assert_eq!(-a, Vec3::new(-1.0, 2.5, -3.0));
+
+    let mut c = a;
+    c += 1.0;
+    assert_eq!(c, Vec3::new(2.0, -1.5, 4.0));
+}
+
+#[test]
+fn test_approx_eq() {
+    assert!(Vec3::new(0.1 + 0.2, 1.0, 1.0).approx_eq(&Vec3::new(0.3, 1.0, 1.0), 1e-6));
+    assert!(!Vec3::new(0.1, 1.0, 1.0).approx_eq(&Vec3::new(0.2, 1.0, 1.0), 1e-6));
+
+    let a = Mat4x4::rotation_matrix_euler(&Vec3::new(90.0, 0.0, 0.0));
+    let b = a.inverted().unwrap().inverted().unwrap();
+    assert_approx_eq!(a, b, 1e-4);
+}
+
+#[test]
+fn test_matrix_debug() {
+    let a = Mat3x3::new(1.0, 20.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, -9.5);
+
+    assert_eq!(
+        format!("{a:?}"),
+        "Mat3x3 [\n    [ 1.0, 20.0,  3.0],\n    [ 4.0,  5.0,  6.0],\n    [ 7.0,  8.0, -9.5],\n]"
+    );
+    assert_eq!(
+        format!("{:.1?}", Mat3x3::identity()),
+        "Mat3x3 [\n    [1.0, 0.0, 0.0],\n    [0.0, 1.0, 0.0],\n    [0.0, 0.0, 1.0],\n]"
+    );
+}
+
+#[test]
+fn test_curves() {
+    use curves::*;
+
+    let p = [
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(1.0, 2.0, 0.0),
+        Vec3::new(3.0, 2.0, 1.0),
+        Vec3::new(4.0, 0.0, 1.0),
+    ];
+
+    let bezier = Bezier::cubic(p[0], p[1], p[2], p[3]);
+    let catmull_rom = CatmullRom::new(p.to_vec());
+    let hermite = Hermite::new(
+        p.iter()
+            .map(|p| HermitePoint::new(*p, Vec3::new(1.0, 0.0, 0.0)))
+            .collect(),
+    );
+
+    //End points
+    assert_approx_eq!(bezier.evaluate(0.0), p[0]);
+    assert_approx_eq!(bezier.evaluate(1.0), p[3]);
+    assert_approx_eq!(bezier.tangent(0.0), (p[1] - p[0]) * 3.0);
+    assert_approx_eq!(bezier.tangent(1.0), (p[3] - p[2]) * 3.0);
+
+    //Interpolating splines pass through all the points
+    for (i, p) in p.iter().enumerate() {
+        let t = i as f32 / 3.0;
+        assert_approx_eq!(catmull_rom.evaluate(t), *p, 1e-4);
+        assert_approx_eq!(hermite.evaluate(t), *p, 1e-4);
+    }
+    assert_approx_eq!(hermite.tangent(0.0), Vec3::new(3.0, 0.0, 0.0));
+    assert_approx_eq!(catmull_rom.tangent(1.0 / 3.0), (p[2] - p[0]) * 1.5, 1e-4);
+
+    //Tangents match finite differences
+    let curves: [&dyn Curve; 3] = [&bezier, &catmull_rom, &hermite];
+    for c in curves {
+        for t in [0.1, 0.4, 0.8] {
+            let h = 0.001;
+            let numeric = (c.evaluate(t + h) - c.evaluate(t - h)) / (2.0 * h);
+            assert_approx_eq!(c.tangent(t), numeric, 0.05);
+        }
+    }
+}
+
+#[test]
+fn test_arc_length() {
+    use curves::*;
+
+    //A straight line with a non uniform speed
+    let line = Bezier::cubic(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(4.0, 0.0, 0.0),
+    );
+    let arc = ArcLength::new(&line, 256);
+
+    assert_approx_eq!(arc.length(), 4.0, 1e-3);
+    assert_approx_eq!(arc.parameter(0.0), 0.0);
+    assert_approx_eq!(arc.parameter(4.0), 1.0);
+    assert_approx_eq!(arc.evaluate(&line, 1.0), Vec3::new(1.0, 0.0, 0.0), 1e-2);
+    assert_approx_eq!(
+        line.evaluate(arc.parameter_normalized(0.5)),
+        Vec3::new(2.0, 0.0, 0.0),
+        1e-2
+    );
+}
+
+#[test]
+fn test_reverse_depth() {
+    //Projection matrices are stored for row vectors
+    let depth = |m: Mat4x4, z: f32| {
+        let v = m.transpose().transform(Vec4::new(0.0, 0.0, z, 1.0));
+        v.z / v.w
+    };
+
+    let perspective = Mat4x4::perspercive_projection(1.0, 1.0, 0.1, 100.0);
+    assert_approx_eq!(depth(perspective, -0.1), 0.0);
+    assert_approx_eq!(depth(perspective, -100.0), 1.0);
+
+    let reversed = perspective.reverse_depth();
+    assert_approx_eq!(depth(reversed, -0.1), 1.0);
+    assert_approx_eq!(depth(reversed, -100.0), 0.0);
+    assert_approx_eq!(depth(reversed, -50.0), 1.0 - depth(perspective, -50.0));
+}
+
+#[test]
+fn test_perspective_infinite() {
+    use super::geometry::Frustum;
+
+    let depth = |m: Mat4x4, z: f32| {
+        let v = m.transpose().transform(Vec4::new(0.0, 0.0, z, 1.0));
+        v.z / v.w
+    };
+
+    let infinite = Mat4x4::perspective_infinite(1.0, 1.0, 0.1);
+    assert_approx_eq!(depth(infinite, -0.1), 0.0);
+    assert_approx_eq!(depth(infinite, -1.0e6), 1.0);
+    //Approaches the finite projection with a distant far plane
+    assert_approx_eq!(
+        depth(infinite, -50.0),
+        depth(Mat4x4::perspercive_projection(1.0, 1.0, 0.1, 1.0e6), -50.0)
+    );
+
+    let reversed = infinite.reverse_depth();
+    assert_approx_eq!(depth(reversed, -0.1), 1.0);
+    assert_approx_eq!(depth(reversed, -1.0e6), 0.0);
+
+    //Nothing is too far away
+    for m in [infinite, reversed] {
+        let frustum = Frustum::from_matrix(&m.transpose());
+        assert!(frustum.contains_point(Vec3::new(0.0, 0.0, -1.0e6)));
+        assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, 5.0)));
+        assert!(!frustum.contains_point(Vec3::new(1.0e3, 0.0, -5.0)));
+    }
+}
+
+#[test]
+fn test_bvh() {
+    use super::{
+        bvh::Bvh,
+        geometry::{Aabb, Frustum},
+    };
+
+    let frustum = Frustum::from_matrix(
+        &Mat4x4::perspercive_projection(std::f32::consts::FRAC_PI_2, 1.0, 0.1, 100.0).transpose(),
+    );
+    let unit = Vec3::new(0.5, 0.5, 0.5);
+
+    let mut bvh = Bvh::new(0.5);
+    let mut boxes = Vec::new();
+    for x in -20..20 {
+        for z in -20..20 {
+            let aabb = Aabb::from_center_extents(Vec3::new(x as f32, 0.0, z as f32) * 5.0, unit);
+            boxes.push((bvh.insert(aabb, boxes.len()), aabb));
+        }
+    }
+    assert_eq!(bvh.len(), 1600);
+    //Balanced
+    assert!(bvh.height() < 24);
+
+    let query = |bvh: &Bvh<usize>| {
+        let mut found = Vec::new();
+        let tests = bvh.query_frustum(&frustum, |_, i| found.push(*i));
+        found.sort_unstable();
+        (found, tests)
+    };
+    let expected = |boxes: &[(_, Aabb)]| {
+        (0..boxes.len())
+            .filter(|i| frustum.intersects_aabb(&boxes[*i].1))
+            .collect::<Vec<_>>()
+    };
+    let (found, tests) = query(&bvh);
+    assert_eq!(found, expected(&boxes));
+    //Most of the boxes are rejected with their subtrees
+    assert!(tests < boxes.len());
+
+    //Small movements stay inside of the enlarged box
+    let (id, aabb) = boxes[0];
+    let moved = Aabb::new(aabb.min + unit * 0.5, aabb.max + unit * 0.5);
+    assert!(!bvh.update(id, moved));
+    assert_eq!(bvh.bounds(id), Some(moved));
+
+    //Moving a box into the view
+    let moved = Aabb::from_center_extents(Vec3::new(0.0, 0.0, -5.0), unit);
+    assert!(bvh.update(id, moved));
+    boxes[0].1 = moved;
+    let (found, _) = query(&bvh);
+    assert!(found.contains(&0));
+    assert_eq!(found, expected(&boxes));
+
+    assert_eq!(bvh.remove(id), 0);
+    assert_eq!(bvh.get(id), None);
+    assert!(!query(&bvh).0.contains(&0));
+
+    let mut overlapping = Vec::new();
+    bvh.query_aabb(
+        &Aabb::from_center_extents(Vec3::new(5.0, 0.0, 5.0), unit),
+        |_, i| {
+            overlapping.push(*i);
+        },
+    );
+    assert_eq!(overlapping, vec![21 * 40 + 21]);
+
+    assert!(frustum.contains_aabb(&Aabb::from_center_extents(Vec3::new(0.0, 0.0, -5.0), unit)));
+    assert!(!frustum.contains_aabb(&Aabb::from_center_extents(Vec3::new(5.0, 0.0, -5.0), unit)));
+}
@@ -1,4 +1,6 @@
-use std::ops::Div;
+use core::ops::Div;
+#[cfg(not(feature = "std"))]
+use crate::float::Float;
 
 ///Trait all vectors must implement
 pub trait Vector: Div<f32> + Sized + Copy + PartialEq + PartialOrd {
@@ -6,6 +8,8 @@ pub trait Vector: Div<f32> + Sized + Copy + PartialEq + PartialOrd {
     fn square_length(&self) -> f32;
     ///Returns dot product between the `self` vector and the `other` vector
     fn dot_product(&self, other: &Self) -> f32;
+    ///Returns `true` if none of the components are NaN or infinite
+    fn is_finite(&self) -> bool;
 
     ///Returns length of the vector
     #[must_use]
@@ -18,6 +22,7 @@ pub trait Vector: Div<f32> + Sized + Copy + PartialEq + PartialOrd {
     where
         Self: From<<Self as Div<f32>>::Output>,
     {
+        debug_assert!(self.length() != 0.0, "Normalizing a vector of length 0");
         (*self / self.length()).into()
     }
     ///Returns vector normalized
@@ -47,3 +52,23 @@ pub trait Vector: Div<f32> + Sized + Copy + PartialEq + PartialOrd {
         }
     }
 }
+
+///Trait for comparing floating point values, allowing for rounding errors
+pub trait ApproxEq {
+    ///Returns `true` if all the components of `self` and `other` differ by at most `epsilon`
+    fn approx_eq(&self, other: &Self, epsilon: f32) -> bool;
+}
+
+impl ApproxEq for f32 {
+    fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        (self - other).abs() <= epsilon
+    }
+}
+
+impl<T: ApproxEq, const N: usize> ApproxEq for [T; N] {
+    fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        self.iter()
+            .zip(other.iter())
+            .all(|(a, b)| a.approx_eq(b, epsilon))
+    }
+}
@@ -15,19 +15,17 @@
 //! Assets are only initialized when first needed (or perhaps on "scene load"?)
 // Oh god, is this just the entity system but with assets!?!?
 
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
-#[cfg(not(target_arch = "wasm32"))]
-use std::thread;
-
-use rand::Rng;
 use vec_key_value_pair::map::VecMap;
 
-#[cfg(not(target_arch = "wasm32"))]
-use crate::grimoire;
+use crate::timeline;
 
 #[cfg(test)]
 mod tests;
+mod watcher;
+
+pub use watcher::FileWatcher;
 
 #[derive(Debug)]
 ///Error type for asset management
@@ -91,6 +89,16 @@ pub trait Asset: Send + Sync + std::any::Any {
     ///# Errors
     ///May return an error if the initialization of an asset fails
     fn initialize(&mut self) -> Result<(), Box<dyn std::error::Error + Send>>;
+    ///Performs the cpu side of the initialization, such as reading and decoding files
+    ///
+    ///May be called from any thread before [`Asset::initialize`], which then only needs to upload
+    ///the prepared data to the gpu. Does nothing by default
+    ///
+    ///# Errors
+    ///May return an error if reading or decoding of the asset data fails
+    fn prepare(&mut self) -> Result<(), Box<dyn std::error::Error + Send>> {
+        Ok(())
+    }
     ///Disposes of all all the resources used by the asset
     fn dispose(&mut self);
     ///Sets the id for the asset, may only be called internally, and only once
@@ -100,6 +108,20 @@ pub trait Asset: Send + Sync + std::any::Any {
     fn set_id(&mut self, id: UUID) -> Result<(), Error>;
     ///Returns whether or not the asset is initialized
     fn is_initialized(&self) -> bool;
+    ///Returns the files the asset is read from, [`Asset::reload`] is called when they change,
+    ///see [`AssetStore::reload_changed`]. None by default
+    fn watched_files(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+    ///Reloads the initialized asset after some of its watched files changed, the asset must stay
+    ///usable if it fails. Does nothing by default
+    ///
+    ///# Errors
+    ///May return an error if the new contents of the files are invalid
+    fn reload(&mut self, changed: &[PathBuf]) -> Result<(), Box<dyn std::error::Error + Send>> {
+        _ = changed;
+        Ok(())
+    }
     //Will not be needed after Rust 1.75.0
     //Cannot be implemented automatically, well... likely can be, but i can't be bothered
     ///Converts trait object to a `std::any::Any` reference
@@ -183,18 +205,56 @@ impl<T> AssetReference<T> {
 
 type RwLock<T> = lock_api::RwLock<parking_lot::RawRwLock, T>;
 
+//(asset, type id, type name)
+type Entry = (Arc<RwLock<Box<dyn Asset>>>, std::any::TypeId, &'static str);
+
+///Time it took to initialize a single asset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssetTiming {
+    ///Id of the asset
+    pub id: UUID,
+    ///Name of the type of the asset
+    pub type_name: &'static str,
+    ///Time spent in [`Asset::prepare`], zero if the asset was initialized lazily
+    pub prepare: Duration,
+    ///Time spent in [`Asset::initialize`]
+    pub initialize: Duration,
+}
+
+impl AssetTiming {
+    ///Returns the total initialization time of the asset
+    #[must_use]
+    pub fn total(&self) -> Duration {
+        self.prepare + self.initialize
+    }
+}
+
+///Initialization times of all the assets of a single type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeTiming {
+    ///Name of the type
+    pub type_name: &'static str,
+    ///Sum of the initialization times of all the assets of the type
+    pub total: Duration,
+    ///Initialization times of the individual assets, from the slowest to the fastest
+    pub assets: Vec<AssetTiming>,
+}
+
 ///Asset manager
 ///
 ///Manages the initialization of assets, borrowing of assets and disposal of assets
-#[allow(clippy::type_complexity)]
 pub struct AssetStore {
-    assets: VecMap<UUID, (Arc<RwLock<Box<dyn Asset>>>, std::any::TypeId)>,
+    assets: VecMap<UUID, Entry>,
+    timings: parking_lot::Mutex<Vec<AssetTiming>>,
+    watchers: parking_lot::Mutex<VecMap<UUID, FileWatcher>>,
 }
 
 impl Default for AssetStore {
     fn default() -> Self {
         Self {
             assets: VecMap::new(),
+            timings: parking_lot::Mutex::new(Vec::new()),
+            watchers: parking_lot::Mutex::new(VecMap::new()),
         }
     }
 }
@@ -214,7 +274,7 @@ impl AssetStore {
     where
         T: Asset + 'static,
     {
-        let id = rand::thread_rng().gen();
+        let id = crate::determinism::random();
         let mut asset = asset;
         asset.set_id(id).unwrap();
         self.assets.insert(
@@ -222,63 +282,63 @@ impl AssetStore {
             (
                 Arc::new(RwLock::new(Box::new(asset))),
                 std::any::TypeId::of::<T>(),
+                std::any::type_name::<T>(),
             ),
         );
         id
     }
 
+    ///Returns `true` if an asset with the given id is registered in the store
+    #[must_use]
+    pub fn contains(&self, id: UUID) -> bool {
+        self.assets.contains_key(&id)
+    }
+
+    ///Returns `true` if an asset of type `T` with the id is registered in the store
+    #[must_use]
+    pub fn contains_type<T: Asset + 'static>(&self, id: UUID) -> bool {
+        self.assets
+            .get(&id)
+            .is_some_and(|a| a.1 == std::any::TypeId::of::<T>())
+    }
+
     ///Initializes all of the assets in the assetstore
     ///
-    ///Utilizes threads to initialize assets in parallel
+    ///Decodes the assets in parallel, then uploads them to the gpu
     ///
     ///# Errors
     ///Returns an error if one of the assets fails to initialize
     pub fn intialize_all(&self) -> Result<(), Error> {
-        let binding = self.assets.values().collect::<Vec<_>>();
-
-        #[cfg(not(target_arch = "wasm32"))]
-        let mut chunk_size = self.assets.len() / grimoire::NUM_THREADS;
-        #[cfg(not(target_arch = "wasm32"))]
-        if chunk_size == 0 {
-            chunk_size = 1;
-        }
+        self.initialize_assets(&self.assets.iter().collect::<Vec<_>>())
+    }
 
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            let handles = binding
-                .chunks(chunk_size)
-                .map(|c| c.iter().map(|i| (*i).clone()).collect::<Vec<_>>())
-                .collect::<Vec<_>>()
-                .into_iter()
-                .map(|c| {
-                    thread::spawn(move || {
-                        c.clone()
-                            .iter()
-                            .map(move |i| i.0.write().initialize())
-                            .collect::<Vec<_>>()
-                    })
-                })
-                .collect::<Vec<_>>();
+    ///Initializes all of the assets of type `T` in the assetstore
+    ///
+    ///Decodes the assets in parallel, then uploads them to the gpu
+    ///
+    ///# Errors
+    ///Returns an error if one of the assets fails to initialize
+    pub fn intialize_by_type<T: Asset + 'static>(&self) -> Result<(), Error> {
+        let type_id = std::any::TypeId::of::<T>();
 
-            for h in handles {
-                for r in unsafe { h.join().unwrap_unchecked() } {
-                    if let Err(r) = r {
-                        return Err(Error::InitializationError(r));
-                    }
-                }
-            }
-        }
+        self.initialize_assets(
+            &self
+                .assets
+                .iter()
+                .filter(|i| i.1 .1 == type_id)
+                .collect::<Vec<_>>(),
+        )
+    }
 
-        #[cfg(target_arch = "wasm32")]
-        {
-            for r in binding.iter().map(|c| c.0.write().initialize()) {
-                if let Err(r) = r {
-                    return Err(Error::InitializationError(r));
-                }
-            }
-        }
+    ///Returns all the assets of type `T`, without initializing them
+    pub(crate) fn get_all_raw<T: Asset + 'static>(&self) -> Vec<Arc<RwLock<Box<dyn Asset>>>> {
+        let type_id = std::any::TypeId::of::<T>();
 
-        Ok(())
+        self.assets
+            .values()
+            .filter(|i| i.1 == type_id)
+            .map(|i| i.0.clone())
+            .collect()
     }
 
     ///Returns the [`AssetReference`] to an asset inside the `AssetStore` by id
@@ -289,16 +349,7 @@ impl AssetStore {
         let this = self.assets.get(&id);
         match this {
             Some(x) => {
-                {
-                    let mut x = x.0.write();
-                    if !x.is_initialized() {
-                        let r = x.initialize();
-                        drop(x);
-                        if let Err(r) = r {
-                            return Err(Error::InitializationError(r));
-                        }
-                    }
-                }
+                self.initialize_lazy(id, x)?;
                 Ok(AssetReference {
                     refernce: x.0.clone(),
                     phantom: std::marker::PhantomData,
@@ -315,16 +366,9 @@ impl AssetStore {
     pub fn get_by_type<T: Asset + 'static>(&self) -> Result<AssetReference<T>, Error> {
         let type_id = std::any::TypeId::of::<T>();
 
-        for i in self.assets.values() {
+        for (id, i) in &self.assets {
             if i.1 == type_id {
-                let mut x = i.0.write();
-                if !x.is_initialized() {
-                    let r = x.initialize();
-                    drop(x);
-                    if let Err(r) = r {
-                        return Err(Error::InitializationError(r));
-                    }
-                }
+                self.initialize_lazy(*id, i)?;
                 return Ok(AssetReference {
                     refernce: i.0.clone(),
                     phantom: std::marker::PhantomData,
@@ -335,6 +379,23 @@ impl AssetStore {
         Err(Error::DoesNotExist)
     }
 
+    ///Returns all the assets of type T, initializing them if needed
+    ///
+    ///# Errors
+    ///Returns an error if one of the assets fails to initialize
+    pub fn get_all_by_type<T: Asset + 'static>(&self) -> Result<Vec<AssetReference<T>>, Error> {
+        self.intialize_by_type::<T>()?;
+
+        Ok(self
+            .get_all_raw::<T>()
+            .into_iter()
+            .map(|refernce| AssetReference {
+                refernce,
+                phantom: std::marker::PhantomData,
+            })
+            .collect())
+    }
+
     ///Disposes of the asset with id
     ///
     ///# Errors
@@ -347,10 +408,178 @@ impl AssetStore {
         Ok(())
     }
 
+    ///Reloads the initialized assets whose watched files changed since the last call, see
+    ///[`Asset::watched_files`]
+    ///
+    ///Called by [`crate::rendering::render`] every frame in debug builds, so the changes are
+    ///visible in the next frame. Assets that fail to reload are logged and stay as they were
+    pub fn reload_changed(&self) {
+        let mut watchers = self.watchers.lock();
+
+        for (id, (asset, _, type_name)) in &self.assets {
+            let files = {
+                let asset = asset.read();
+                if !asset.is_initialized() {
+                    continue;
+                }
+                asset.watched_files()
+            };
+            if files.is_empty() {
+                continue;
+            }
+
+            let watcher = watchers.entry(*id).or_insert(FileWatcher::new());
+            for file in &files {
+                watcher.watch(file);
+            }
+            let changed = watcher.changed();
+            if changed.is_empty() {
+                continue;
+            }
+
+            match asset.write().reload(&changed) {
+                Ok(()) => log::info!("Reloaded {type_name} from {}", changed[0].display()),
+                Err(e) => log::error!("Failed to reload {}: {e}", changed[0].display()),
+            }
+        }
+    }
+
     ///Disposes of all assets
     pub fn dispose_all(&self) {
         for a in self.assets.values().map(|v| v.0.clone()) {
             a.write().dispose();
         }
     }
+
+    ///Returns the initialization times of all the assets initialized so far, in the order they
+    ///were initialized
+    ///
+    ///Assets that are initialized multiple times, e.g. after being disposed, appear multiple times
+    #[must_use]
+    pub fn get_init_timings(&self) -> Vec<AssetTiming> {
+        self.timings.lock().clone()
+    }
+
+    ///Returns the initialization times grouped by the type of the assets, from the slowest type to
+    ///the fastest
+    #[must_use]
+    pub fn get_init_profile(&self) -> Vec<TypeTiming> {
+        let mut types: Vec<TypeTiming> = Vec::new();
+
+        for t in self.timings.lock().iter() {
+            match types.iter_mut().find(|i| i.type_name == t.type_name) {
+                Some(i) => {
+                    i.total += t.total();
+                    i.assets.push(*t);
+                }
+                None => types.push(TypeTiming {
+                    type_name: t.type_name,
+                    total: t.total(),
+                    assets: vec![*t],
+                }),
+            }
+        }
+
+        for t in &mut types {
+            t.assets.sort_by_key(|i| std::cmp::Reverse(i.total()));
+        }
+        types.sort_by_key(|i| std::cmp::Reverse(i.total));
+        types
+    }
+
+    ///Clears the recorded initialization times
+    pub fn clear_init_timings(&self) {
+        self.timings.lock().clear();
+    }
+
+    ///Initializes the asset if it's not initialized yet
+    fn initialize_lazy(&self, id: UUID, entry: &Entry) -> Result<(), Error> {
+        let mut x = entry.0.write();
+        if x.is_initialized() {
+            return Ok(());
+        }
+
+        let type_span = timeline::span_with_category(entry.2, "asset");
+        let asset_span = asset_span("", id, entry.2);
+        let start = crate::time::now();
+        let r = x.initialize();
+        drop(x);
+        drop(asset_span);
+        drop(type_span);
+        self.timings.lock().push(AssetTiming {
+            id,
+            type_name: entry.2,
+            prepare: Duration::ZERO,
+            initialize: Duration::from_nanos(crate::time::now() - start),
+        });
+
+        r.map_err(|e| Error::InitializationError(e))
+    }
+
+    ///Initializes the assets, decoding them in parallel and uploading them to the gpu
+    ///sequentially
+    fn initialize_assets(&self, binding: &[(&UUID, &Entry)]) -> Result<(), Error> {
+        //Time spent preparing every asset
+        #[allow(unused_mut)]
+        let mut prepare = vec![Duration::ZERO; binding.len()];
+
+        //Decode all the assets in parallel
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            use rayon::prelude::*;
+
+            binding
+                .par_iter()
+                .zip(prepare.par_iter_mut())
+                .map(|(i, time)| {
+                    let _span = asset_span("Prepare ", *i.0, i.1 .2);
+                    let start = crate::time::now();
+                    let r = i.1 .0.write().prepare();
+                    *time = Duration::from_nanos(crate::time::now() - start);
+                    r
+                })
+                .collect::<Result<(), _>>()
+                .map_err(|e| Error::InitializationError(e))?;
+        }
+
+        //Upload them one by one
+        let mut type_span = None;
+        let mut previous = None;
+        for ((id, entry), prepare) in binding.iter().zip(prepare) {
+            //Consecutive assets of the same type share a span
+            if previous != Some(entry.1) {
+                drop(type_span.take());
+                type_span = Some(timeline::span_with_category(entry.2, "asset"));
+                previous = Some(entry.1);
+            }
+
+            let span = asset_span("", **id, entry.2);
+            let start = crate::time::now();
+            let r = entry.0.write().initialize();
+            let initialize = Duration::from_nanos(crate::time::now() - start);
+            drop(span);
+
+            log::trace!("Initialized {} {id} in {:?}", entry.2, prepare + initialize);
+            self.timings.lock().push(AssetTiming {
+                id: **id,
+                type_name: entry.2,
+                prepare,
+                initialize,
+            });
+
+            r.map_err(|e| Error::InitializationError(e))?;
+        }
+
+        Ok(())
+    }
+}
+
+///Starts the timeline span of a single asset, the name is only formatted during a capture
+fn asset_span(prefix: &str, id: UUID, type_name: &str) -> timeline::Span {
+    let name = if timeline::is_capturing() {
+        format!("{prefix}{type_name} {id}")
+    } else {
+        String::new()
+    };
+    timeline::span_with_category(name, "asset")
 }
@@ -85,3 +85,138 @@ fn test_asset_borrowing() {
 
     assert_eq!(borrow.data, -20);
 }
+
+#[test]
+fn test_get_all_by_type() {
+    let mut store = AssetStore::new();
+
+    for _ in 0..10 {
+        store.register(TestAsset::new());
+    }
+
+    let assets = store.get_all_by_type::<TestAsset>().unwrap();
+    assert_eq!(assets.len(), 10);
+    for a in assets {
+        assert_eq!(a.borrow().data, 20);
+    }
+}
+
+#[test]
+fn test_init_profile() {
+    let mut store = AssetStore::new();
+
+    for _ in 0..10 {
+        store.register(TestAsset::new());
+    }
+    store.intialize_all().unwrap();
+
+    let timings = store.get_init_timings();
+    assert_eq!(timings.len(), 10);
+    assert!(timings
+        .iter()
+        .all(|t| t.type_name == std::any::type_name::<TestAsset>()));
+
+    //Lazily initialized assets are recorded as well
+    let id = store.register(TestAsset::new());
+    store.get_by_id::<TestAsset>(id).unwrap();
+    //Already initialized assets are not
+    store.get_by_id::<TestAsset>(id).unwrap();
+
+    let profile = store.get_init_profile();
+    assert_eq!(profile.len(), 1);
+    assert_eq!(profile[0].assets.len(), 11);
+    assert_eq!(
+        profile[0].total,
+        profile[0].assets.iter().map(AssetTiming::total).sum()
+    );
+    assert!(profile[0]
+        .assets
+        .windows(2)
+        .all(|w| w[0].total() >= w[1].total()));
+
+    store.clear_init_timings();
+    assert!(store.get_init_timings().is_empty());
+}
+
+struct FileAsset {
+    id: Option<UUID>,
+    path: PathBuf,
+    contents: Option<String>,
+}
+
+impl Asset for FileAsset {
+    #[as_any]
+    fn get_id(&self) -> UUID {
+        self.id.unwrap()
+    }
+
+    fn initialize(&mut self) -> Result<(), Box<dyn std::error::Error + Send>> {
+        self.reload(&[])
+    }
+
+    fn dispose(&mut self) {
+        self.contents = None;
+    }
+
+    fn set_id(&mut self, id: UUID) -> Result<(), Error> {
+        self.id = Some(id);
+        Ok(())
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.contents.is_some()
+    }
+
+    fn watched_files(&self) -> Vec<PathBuf> {
+        vec![self.path.clone()]
+    }
+
+    fn reload(&mut self, _: &[PathBuf]) -> Result<(), Box<dyn std::error::Error + Send>> {
+        let contents = std::fs::read_to_string(&self.path)
+            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+        if contents.is_empty() {
+            return Err(Box::new(std::io::Error::other("Empty file")));
+        }
+        self.contents = Some(contents);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_asset_reloading() {
+    let path = std::env::temp_dir().join(format!("lunar-engine-reload-{}", std::process::id()));
+    //Modification times may be too coarse to notice quick changes, so they're set explicitly
+    let write = |contents: &str, seconds: u64| {
+        std::fs::write(&path, contents).unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+            .unwrap();
+    };
+    write("first", 1);
+
+    let mut store = AssetStore::new();
+    let id = store.register(FileAsset {
+        id: None,
+        path: path.clone(),
+        contents: None,
+    });
+    let contents = || store.get_by_id::<FileAsset>(id).unwrap().borrow().contents.clone();
+
+    assert_eq!(contents().as_deref(), Some("first"));
+    store.reload_changed();
+    assert_eq!(contents().as_deref(), Some("first"));
+
+    write("second", 2);
+    store.reload_changed();
+    assert_eq!(contents().as_deref(), Some("second"));
+
+    //Failed reloads keep the previous contents
+    write("", 3);
+    store.reload_changed();
+    assert_eq!(contents().as_deref(), Some("second"));
+
+    std::fs::remove_file(&path).unwrap();
+}
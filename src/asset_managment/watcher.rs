@@ -0,0 +1,59 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+///Polls files for changes by comparing their modification times
+///
+///Used by [`super::AssetStore::reload_changed`], platforms without a file system never report any
+///changes
+#[derive(Debug, Default)]
+pub struct FileWatcher {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl FileWatcher {
+    ///Creates a watcher without any files
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { files: Vec::new() }
+    }
+
+    ///Starts watching the file, changes before this call are not reported
+    pub fn watch(&mut self, path: &Path) {
+        if self.is_watching(path) {
+            return;
+        }
+        self.files.push((path.to_path_buf(), modified(path)));
+    }
+
+    ///Stops watching the file
+    pub fn unwatch(&mut self, path: &Path) {
+        self.files.retain(|(p, _)| p != path);
+    }
+
+    ///Returns `true` if the file is watched
+    #[must_use]
+    pub fn is_watching(&self, path: &Path) -> bool {
+        self.files.iter().any(|(p, _)| p == path)
+    }
+
+    ///Returns the files that changed since the last call, files that were removed are reported
+    ///once they're created again
+    pub fn changed(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for (path, time) in &mut self.files {
+            let modified = modified(path);
+            if modified.is_some() && modified != *time {
+                changed.push(path.clone());
+            }
+            *time = modified;
+        }
+        changed
+    }
+}
+
+///Returns the modification time of the file, `None` if it can't be read
+pub(crate) fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
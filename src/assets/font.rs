@@ -0,0 +1,410 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use ab_glyph::{point, FontVec, OutlineCurve, Point};
+use lunar_engine_derive::as_any;
+use wgpu::util::DeviceExt;
+
+use crate::{
+    asset_managment::{Asset, UUID},
+    math::Vec2,
+    rendering::extensions::canvas::{Glyph, SdfFont},
+};
+
+//Width of the atlas, the height grows to fit all the glyphs
+const ATLAS_WIDTH: u32 = 1024;
+//Number of line segments a curve is split into
+const CURVE_STEPS: usize = 8;
+
+///Where the font data is read from
+enum FontSource {
+    File(PathBuf),
+    Static(&'static [u8]),
+}
+
+///Glyphs baked into a signed distance field atlas, see [`SdfFont`]
+pub(crate) struct BakedFont {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    ///Distance field, one byte per pixel, stored flipped like the textures
+    pub(crate) pixels: Vec<u8>,
+    pub(crate) font: SdfFont,
+}
+
+///Asset that loads a TrueType or OpenType font and bakes its glyphs into a signed distance field
+///atlas
+///
+///The baked font is used like any other [`SdfFont`], by the
+///[`Canvas`](crate::rendering::extensions::Canvas), the [`WorldText`](crate::components::WorldText)
+///and the [`Text`](crate::components::text::Text) components. The distance field stays sharp at
+///any size, so a single size is baked.
+pub struct Font {
+    id: Option<UUID>,
+    initialized: bool,
+    source: FontSource,
+    characters: Vec<char>,
+    glyph_size: u32,
+    //Atlas baked by `prepare`, waiting to be uploaded to the gpu
+    baked: Option<BakedFont>,
+    font: Option<Arc<SdfFont>>,
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) texture: Option<crate::wrappers::WgpuWrapper<wgpu::Texture>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) texture: Option<wgpu::Texture>,
+}
+
+impl Font {
+    const fn new(source: FontSource) -> Self {
+        Self {
+            id: None,
+            initialized: false,
+            source,
+            characters: Vec::new(),
+            glyph_size: 48,
+            baked: None,
+            font: None,
+            texture: None,
+        }
+    }
+
+    ///Initializes a font to load a ttf or otf file in runtime
+    ///
+    ///By default the printable ASCII and Latin-1 characters are baked, 48 pixels tall
+    #[must_use]
+    pub fn new_ttf(path: &Path) -> Self {
+        Self::new(FontSource::File(path.to_owned()))
+    }
+
+    ///Initializes a font to parse the font in runtime, but being loaded at comp time
+    #[must_use]
+    pub const fn static_ttf(data: &'static [u8]) -> Self {
+        Self::new(FontSource::Static(data))
+    }
+
+    ///Sets the characters that are baked into the atlas, the characters missing in the font are
+    ///skipped
+    #[must_use]
+    pub fn with_characters(mut self, characters: impl IntoIterator<Item = char>) -> Self {
+        self.characters = characters.into_iter().collect();
+        self
+    }
+
+    ///Sets the height of a line of the glyphs in the atlas, in pixels
+    ///
+    ///Larger sizes preserve more detail at the cost of memory
+    #[must_use]
+    pub fn with_glyph_size(mut self, size: u32) -> Self {
+        self.glyph_size = size.max(8);
+        self
+    }
+
+    ///Returns the font used for laying out and rendering text
+    ///
+    ///# Panics
+    ///Panics if the asset was not initialized
+    #[must_use]
+    pub fn get_font(&self) -> Arc<SdfFont> {
+        self.font.clone().unwrap()
+    }
+
+    fn characters(&self) -> Vec<char> {
+        if self.characters.is_empty() {
+            (' '..='~').chain('\u{a0}'..='\u{ff}').collect()
+        } else {
+            self.characters.clone()
+        }
+    }
+
+    ///Reads the font and bakes the atlas
+    fn bake(&self) -> Result<BakedFont, Box<dyn std::error::Error + Send>> {
+        let data = match &self.source {
+            FontSource::File(path) => match std::fs::read(path) {
+                Ok(it) => it,
+                Err(err) => return Err(Box::new(err)),
+            },
+            FontSource::Static(data) => data.to_vec(),
+        };
+        let font = match FontVec::try_from_vec(data) {
+            Ok(it) => it,
+            Err(err) => return Err(Box::new(err)),
+        };
+        Ok(bake(
+            &font,
+            &self.characters(),
+            self.glyph_size,
+            self.get_id(),
+        ))
+    }
+}
+
+///Bakes the `characters` of the font into an atlas with the lines `glyph_size` pixels tall
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub(crate) fn bake(
+    font: &impl ab_glyph::Font,
+    characters: &[char],
+    glyph_size: u32,
+    atlas: UUID,
+) -> BakedFont {
+    let ascent = font.ascent_unscaled();
+    let units = ascent - font.descent_unscaled();
+    //Pixels per font unit
+    let scale = glyph_size as f32 / units;
+    //Distance covered by the field, in pixels
+    let spread = (glyph_size / 8).max(2);
+
+    let mut sdf = SdfFont::new(atlas, (units + font.line_gap_unscaled()) / units);
+
+    //Pack the glyphs into rows
+    let mut cells = Vec::new();
+    let (mut x, mut y, mut row) = (0, 0, 0);
+    for &c in characters {
+        let id = font.glyph_id(c);
+        if id.0 == 0 {
+            continue;
+        }
+        let advance = font.h_advance_unscaled(id) / units;
+
+        let Some(outline) = font.outline(id) else {
+            //Whitespace
+            sdf.add_glyph(
+                c,
+                Glyph {
+                    uv: (Vec2::new(0.0, 0.0), Vec2::new(0.0, 0.0)),
+                    size: Vec2::new(0.0, 0.0),
+                    offset: Vec2::new(0.0, 0.0),
+                    advance,
+                },
+            );
+            continue;
+        };
+
+        //Bounds have y pointing up, min is the top left corner
+        let bounds = outline.bounds;
+        let width = ((bounds.max.x - bounds.min.x) * scale).ceil() as u32 + spread * 2;
+        let height = ((bounds.min.y - bounds.max.y) * scale).ceil() as u32 + spread * 2;
+        if x + width > ATLAS_WIDTH {
+            (x, y, row) = (0, y + row, 0);
+        }
+        cells.push((c, (x, y), (width, height), advance, outline));
+        x += width;
+        row = row.max(height);
+    }
+    let atlas_height = (y + row).max(1).next_power_of_two();
+
+    let mut pixels = vec![0; (ATLAS_WIDTH * atlas_height) as usize];
+    let atlas_size = Vec2::new(ATLAS_WIDTH as f32, atlas_height as f32);
+    for (c, (x, y), (width, height), advance, outline) in cells {
+        let segments = flatten(&outline.curves);
+        //Font units of the top left corner of the cell
+        let origin = point(
+            outline.bounds.min.x - spread as f32 / scale,
+            outline.bounds.min.y + spread as f32 / scale,
+        );
+
+        for py in 0..height {
+            for px in 0..width {
+                let p = point(
+                    origin.x + (px as f32 + 0.5) / scale,
+                    origin.y - (py as f32 + 0.5) / scale,
+                );
+                let distance = signed_distance(&segments, p) * scale;
+                let value = (0.5 + distance / (2.0 * spread as f32)).clamp(0.0, 1.0);
+
+                //Flipped vertically
+                let row = atlas_height - 1 - (y + py);
+                pixels[(row * ATLAS_WIDTH + x + px) as usize] = (value * 255.0).round() as u8;
+            }
+        }
+
+        let min = Vec2::new(x as f32, y as f32);
+        let size = Vec2::new(width as f32, height as f32);
+        sdf.add_glyph(
+            c,
+            Glyph {
+                uv: (min / atlas_size, (min + size) / atlas_size),
+                size: size / glyph_size as f32,
+                offset: Vec2::new(
+                    origin.x * scale / glyph_size as f32,
+                    (ascent - origin.y) * scale / glyph_size as f32,
+                ),
+                advance,
+            },
+        );
+    }
+
+    for &first in characters {
+        for &second in characters {
+            let kerning = font.kern_unscaled(font.glyph_id(first), font.glyph_id(second));
+            if kerning != 0.0 {
+                sdf.add_kerning(first, second, kerning / units);
+            }
+        }
+    }
+
+    BakedFont {
+        width: ATLAS_WIDTH,
+        height: atlas_height,
+        pixels,
+        font: sdf,
+    }
+}
+
+///Splits the curves of an outline into line segments
+pub(crate) fn flatten(curves: &[OutlineCurve]) -> Vec<(Point, Point)> {
+    let mut segments = Vec::new();
+
+    for curve in curves {
+        match *curve {
+            OutlineCurve::Line(a, b) => {
+                segments.push((a, b));
+            }
+            OutlineCurve::Quad(a, b, c) => push_curve(&mut segments, &|t| {
+                let u = 1.0 - t;
+                weighted(&[(a, u * u), (b, 2.0 * u * t), (c, t * t)])
+            }),
+            OutlineCurve::Cubic(a, b, c, d) => push_curve(&mut segments, &|t| {
+                let u = 1.0 - t;
+                weighted(&[
+                    (a, u * u * u),
+                    (b, 3.0 * u * u * t),
+                    (c, 3.0 * u * t * t),
+                    (d, t * t * t),
+                ])
+            }),
+        }
+    }
+    segments
+}
+
+fn push_curve(segments: &mut Vec<(Point, Point)>, curve: &dyn Fn(f32) -> Point) {
+    let mut previous = curve(0.0);
+    for i in 1..=CURVE_STEPS {
+        let next = curve(i as f32 / CURVE_STEPS as f32);
+        segments.push((previous, next));
+        previous = next;
+    }
+}
+
+//Sum of the points multiplied by their weights
+fn weighted(points: &[(Point, f32)]) -> Point {
+    points.iter().fold(point(0.0, 0.0), |sum, (p, w)| {
+        point(p.x.mul_add(*w, sum.x), p.y.mul_add(*w, sum.y))
+    })
+}
+
+///Returns the distance from the point to the outline made of the segments, positive inside of
+///the outline, using the non-zero winding rule
+pub(crate) fn signed_distance(segments: &[(Point, Point)], p: Point) -> f32 {
+    let mut distance = f32::INFINITY;
+    let mut winding = 0;
+
+    for &(a, b) in segments {
+        let ab = b - a;
+        let ap = p - a;
+        let length = ab.x.mul_add(ab.x, ab.y * ab.y);
+        let t = if length > 0.0 {
+            (ap.x.mul_add(ab.x, ap.y * ab.y) / length).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let d = point(ab.x.mul_add(-t, ap.x), ab.y.mul_add(-t, ap.y));
+        distance = distance.min(d.x.mul_add(d.x, d.y * d.y));
+
+        //Crossings of a ray going to the right of the point
+        let cross = ab.x.mul_add(ap.y, -(ab.y * ap.x));
+        if a.y <= p.y {
+            if b.y > p.y && cross > 0.0 {
+                winding += 1;
+            }
+        } else if b.y <= p.y && cross < 0.0 {
+            winding -= 1;
+        }
+    }
+
+    let distance = distance.sqrt();
+    if winding == 0 {
+        -distance
+    } else {
+        distance
+    }
+}
+
+impl Asset for Font {
+    #[as_any]
+
+    fn get_id(&self) -> UUID {
+        self.id.unwrap()
+    }
+
+    fn initialize(&mut self) -> Result<(), Box<dyn std::error::Error + Send>> {
+        let baked = match self.baked.take() {
+            Some(it) => it,
+            None => self.bake()?,
+        };
+
+        let device = crate::DEVICE.get().unwrap();
+        let queue = crate::QUEUE.get().unwrap();
+        let name = format!("Font {}", self.get_id());
+
+        let texture = crate::errors::scoped(&name, || {
+            device.create_texture_with_data(
+                queue,
+                &wgpu::TextureDescriptor {
+                    label: Some(&name),
+                    size: wgpu::Extent3d {
+                        width: baked.width,
+                        height: baked.height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: wgpu::TextureFormat::R8Unorm,
+                    usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                    view_formats: &[],
+                },
+                wgpu::util::TextureDataOrder::LayerMajor,
+                &baked.pixels,
+            )
+        });
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.texture = Some(crate::wrappers::WgpuWrapper::new(texture));
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.texture = Some(texture);
+        }
+        self.font = Some(Arc::new(baked.font));
+        self.initialized = true;
+        Ok(())
+    }
+
+    fn prepare(&mut self) -> Result<(), Box<dyn std::error::Error + Send>> {
+        if self.baked.is_none() {
+            self.baked = Some(self.bake()?);
+        }
+        Ok(())
+    }
+
+    fn dispose(&mut self) {
+        self.texture = None;
+        self.initialized = false;
+    }
+
+    fn set_id(&mut self, id: UUID) -> Result<(), crate::asset_managment::Error> {
+        if self.id.is_some() {
+            Err(crate::asset_managment::Error::IdAlreadySet)
+        } else {
+            self.id = Some(id);
+            Ok(())
+        }
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
@@ -1,8 +1,13 @@
+use std::{collections::HashMap, path::PathBuf, sync::Mutex};
+
 use lunar_engine_derive::as_any;
 
 use crate::asset_managment::{Asset, AssetStore, UUID};
 
-use super::BindgroupState;
+use super::{
+    parameters::{ParameterError, ParameterValue, Parameters},
+    BindgroupState,
+};
 
 ///Trait for implementing materials
 #[allow(clippy::module_name_repetitions)]
@@ -17,8 +22,57 @@ pub trait MaterialTrait {
     fn set_bindgroups(&mut self, asset_store: &AssetStore);
     ///State of the bindgroups of the material
     fn bindgroup_sate(&self) -> BindgroupState;
+    ///Index of the texture of the material in the shared texture array, `None` if the material
+    ///binds its own textures, see [`crate::rendering::bindless`]
+    fn texture_index(&self) -> Option<u32> {
+        None
+    }
+    ///Identifies the materials using the shared texture array that set the same pipeline and bind
+    ///groups in [`MaterialTrait::render`], the meshes using such materials are drawn together
+    fn batch_key(&self) -> Option<&'static str> {
+        None
+    }
+    ///Whether or not the material can draw skinned meshes with [`MaterialTrait::render_skinned`],
+    ///skinned meshes using other materials are drawn in their bind pose
+    fn supports_skinning(&self) -> bool {
+        false
+    }
+    ///Render function of the material for skinned meshes, sets the pipeline skinning the vertices,
+    ///which expects the skin buffer of the mesh in the vertex buffer slot 2 and the joint matrices
+    ///of the [`Animator`](crate::components::animator::Animator) in the bind group 3
+    fn render_skinned(&self, render_pass: &mut wgpu::RenderPass) {
+        self.render(render_pass);
+    }
+    ///Parameters of the material that can be changed at runtime, see
+    ///[`super::parameters`]
+    ///
+    ///They are updated before [`MaterialTrait::set_bindgroups`] is called
+    fn parameters(&self) -> Option<&Parameters> {
+        None
+    }
+    ///Mutable access to the parameters of the material, see [`MaterialTrait::parameters`]
+    fn parameters_mut(&mut self) -> Option<&mut Parameters> {
+        None
+    }
+    ///Files the shaders of the material are read from, when they change
+    ///[`MaterialTrait::reload_shaders`] is called, see
+    ///[`AssetStore::reload_changed`]
+    fn shader_files(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+    ///Recreates the pipeline from the changed shader files, if it fails the current pipeline must
+    ///be kept
+    ///
+    ///# Errors
+    ///May return an error if the new shaders fail to compile
+    fn reload_shaders(&mut self) -> Result<(), Box<dyn std::error::Error + Send>> {
+        Ok(())
+    }
 }
 
+//First material of every batch key, used to draw all the materials with that key
+static BATCHES: Mutex<Option<HashMap<&'static str, UUID>>> = Mutex::new(None);
+
 ///Stores material data, wrapper around the material trait object
 pub struct Material {
     id: Option<UUID>,
@@ -34,6 +88,7 @@ impl Asset for Material {
     }
 
     fn initialize(&mut self) -> Result<(), Box<dyn std::error::Error + Send>> {
+        crate::rendering::material_initialized();
         self.material.intialize();
         self.initialized = true;
         Ok(())
@@ -56,24 +111,120 @@ impl Asset for Material {
     fn is_initialized(&self) -> bool {
         self.initialized
     }
+
+    fn watched_files(&self) -> Vec<PathBuf> {
+        self.material.shader_files()
+    }
+
+    fn reload(&mut self, _changed: &[PathBuf]) -> Result<(), Box<dyn std::error::Error + Send>> {
+        self.material.reload_shaders()
+    }
 }
 
 impl Material {
     #[must_use]
     ///Get the bindgroup state of the material
     pub fn get_bindgroup_state(&self) -> BindgroupState {
+        //Parameters changed since the bind groups were created
+        if self
+            .material
+            .parameters()
+            .is_some_and(Parameters::is_outdated)
+        {
+            return BindgroupState::Uninitialized;
+        }
         self.material.bindgroup_sate()
     }
 
     ///Initialize bindgroups of the material
     pub fn initialize_bindgroups(&mut self, asset_store: &AssetStore) {
+        if let Some(parameters) = self.material.parameters_mut() {
+            if let Err(e) = parameters.update(asset_store) {
+                log::error!(
+                    "Failed to update the parameters of material {:?}: {e:?}",
+                    self.id
+                );
+            }
+        }
         self.material.set_bindgroups(asset_store);
     }
 
+    ///Sets the value of a parameter of the material, the bind groups are updated before the
+    ///material is used next, see [`super::parameters`]
+    ///
+    ///# Errors
+    ///Returns an error if the material has no such parameter or it has a different type
+    pub fn set_parameter(
+        &mut self,
+        name: &str,
+        value: impl Into<ParameterValue>,
+    ) -> Result<(), ParameterError> {
+        self.material
+            .parameters_mut()
+            .ok_or_else(|| ParameterError::Unknown(name.to_owned()))?
+            .set(name, value)
+    }
+
+    ///Returns the value of a parameter of the material, `None` if it has no such parameter
+    #[must_use]
+    pub fn get_parameter(&self, name: &str) -> Option<ParameterValue> {
+        self.material.parameters()?.get(name)
+    }
+
     ///Call the render function of the material
     pub fn render(&self, render_pass: &mut wgpu::RenderPass) {
         self.material.render(render_pass);
     }
+
+    ///Returns the index of the texture of the material in the shared texture array, see
+    ///[`MaterialTrait::texture_index`]
+    #[must_use]
+    pub fn texture_index(&self) -> Option<u32> {
+        self.material.texture_index()
+    }
+
+    ///Call the render function of the material for skinned meshes
+    pub fn render_skinned(&self, render_pass: &mut wgpu::RenderPass) {
+        self.material.render_skinned(render_pass);
+    }
+
+    ///Returns `true` if the material can draw skinned meshes, see
+    ///[`MaterialTrait::supports_skinning`]
+    #[must_use]
+    pub fn supports_skinning(&self) -> bool {
+        self.material.supports_skinning()
+    }
+}
+
+///Returns the material the meshes using the material `id` are drawn with and the index of its
+///texture in the shared texture array
+///
+///Materials using the shared texture array with the same [`MaterialTrait::batch_key`] are drawn
+///with the first such material, other materials are drawn with themselves
+pub(crate) fn batch(assets: &AssetStore, id: UUID) -> (UUID, u32) {
+    let Ok(material) = assets.get_by_id::<Material>(id) else {
+        return (id, 0);
+    };
+    let material = material.borrow();
+    let (Some(key), Some(index)) = (material.material.batch_key(), material.texture_index()) else {
+        return (id, 0);
+    };
+    drop(material);
+
+    let mut batches = BATCHES.lock().unwrap();
+    let first = batches
+        .get_or_insert_with(HashMap::new)
+        .entry(key)
+        .or_insert(id);
+    //The first material was removed from the store, or the store changed
+    if *first != id
+        && assets
+            .get_by_id::<Material>(*first)
+            .map_or(true, |m| m.borrow().material.batch_key() != Some(key))
+    {
+        *first = id;
+    }
+    (*first, index)
 }
 
 impl<T> From<T> for Material
@@ -1,52 +1,39 @@
 #![allow(clippy::too_many_lines)]
-use std::num::NonZeroU64;
 use std::sync::Arc;
 
-use wgpu::util::DeviceExt;
-use wgpu::BufferUsages;
-
-use crate::assets::Material;
+use crate::assets::{shader, Material, Parameters};
 use crate::structures::Color;
-use crate::{grimoire, DEVICE, FORMAT};
+use crate::{
+    grimoire,
+    rendering::{depth, hdr, msaa},
+    DEVICE,
+};
 
 use crate::{assets::material::MaterialTrait, assets::BindgroupState};
 
 use super::helpers::vertex_binding;
 
-///Basic material that renders an object with a given texture, without lighting
+///Basic material that renders an object with a given color, without lighting
+///
+///The color can be changed with the `color` parameter, see [`crate::assets::parameters`]
 pub struct ColorUnlit {
     #[cfg(target_arch = "wasm32")]
     pipeline: Option<Arc<crate::wrappers::WgpuWrapper<wgpu::RenderPipeline>>>,
     #[cfg(not(target_arch = "wasm32"))]
     pipeline: Option<Arc<wgpu::RenderPipeline>>,
-    #[cfg(target_arch = "wasm32")]
-    bind_group: Option<Arc<crate::wrappers::WgpuWrapper<wgpu::BindGroup>>>,
-    #[cfg(not(target_arch = "wasm32"))]
-    bind_group: Option<Arc<wgpu::BindGroup>>,
-    #[cfg(target_arch = "wasm32")]
-    bind_group_layout_f: Option<crate::wrappers::WgpuWrapper<wgpu::BindGroupLayout>>,
-    #[cfg(not(target_arch = "wasm32"))]
-    bind_group_layout_f: Option<wgpu::BindGroupLayout>,
-    #[cfg(target_arch = "wasm32")]
-    uniform: Option<crate::wrappers::WgpuWrapper<wgpu::Buffer>>,
-    #[cfg(not(target_arch = "wasm32"))]
-    uniform: Option<wgpu::Buffer>,
-    color: Color,
+    parameters: Parameters,
     bindgroup_sate: BindgroupState,
 }
 
 impl ColorUnlit {
     #[allow(clippy::new_ret_no_self)]
     #[must_use]
-    ///Creates a new material with a give texture id
+    ///Creates a new material with a given color
     pub fn new(color: Color) -> Material {
         Self {
-            color,
             pipeline: None,
-            bind_group: None,
-            bind_group_layout_f: None,
+            parameters: Parameters::new().with("color", color),
             bindgroup_sate: BindgroupState::Uninitialized,
-            uniform: None,
         }
         .into()
     }
@@ -64,113 +51,67 @@ impl MaterialTrait for ColorUnlit {
         };
 
         render_pass.set_pipeline(pipeline);
-        let b = unsafe {
-            Arc::as_ptr(&self.bind_group.clone().unwrap())
-                .as_ref()
-                .unwrap()
-        };
-        render_pass.set_bind_group(1, b, &[]);
+        self.parameters.bind(render_pass, 1);
     }
 
     fn intialize(&mut self) {
         let device = DEVICE.get().unwrap();
 
-        let v_shader =
-            device.create_shader_module(wgpu::include_wgsl!("../../shaders/vertex.wgsl"));
-        let f_shader =
-            device.create_shader_module(wgpu::include_wgsl!("../../shaders/color_unlit.wgsl"));
-
-        let bind_group_layout_f =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Fragment binding"),
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: Some(NonZeroU64::new(4 * 4).unwrap()),
-                    },
-                    count: None,
-                }],
-            });
+        let v_shader = shader::compile_vertex(false).expect("Failed to compile the vertex shader");
+        let f_shader = shader::compile(
+            "color_unlit.wgsl",
+            include_str!("../../shaders/color_unlit.wgsl"),
+        )
+        .expect("Failed to compile the fragment shader");
 
         let cam_bind_group_layout =
             device.create_bind_group_layout(&grimoire::CAMERA_BIND_GROUP_LAYOUT_DESCRIPTOR);
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: None,
-            bind_group_layouts: &[&cam_bind_group_layout, &bind_group_layout_f],
+            label: Some("Color unlit pipeline layout"),
+            bind_group_layouts: &[&cam_bind_group_layout, self.parameters.bind_group_layout()],
             push_constant_ranges: &[],
         });
 
-        #[cfg(target_arch = "wasm32")]
-        {
-            self.bind_group_layout_f = Some(crate::wrappers::WgpuWrapper::new(bind_group_layout_f));
-        }
-
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            self.bind_group_layout_f = Some(bind_group_layout_f);
-        }
-
-        #[cfg(target_arch = "wasm32")]
-        {
-            self.uniform = Some(crate::wrappers::WgpuWrapper::new(
-                device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: None,
-                    contents: bytemuck::bytes_of(&self.color),
-                    usage: BufferUsages::UNIFORM,
+        let pipeline = crate::errors::scoped("Color unlit pipeline", || {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Color unlit pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &v_shader,
+                    entry_point: "main",
+                    buffers: &vertex_binding(),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: Some(wgpu::Face::Back),
+                    unclipped_depth: false,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    conservative: false,
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: depth::format(),
+                    depth_write_enabled: true,
+                    depth_compare: depth::compare_function(),
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
                 }),
-            ));
-        }
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            self.uniform = Some(
-                device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: None,
-                    contents: bytemuck::bytes_of(&self.color),
-                    usage: BufferUsages::UNIFORM,
+                multisample: msaa::multisample_state(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &f_shader,
+                    entry_point: "main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: hdr::color_format(),
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
                 }),
-            );
-        }
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: None,
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &v_shader,
-                entry_point: "main",
-                buffers: &vertex_binding(),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                unclipped_depth: false,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
-            },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth32Float,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState::default(),
-            }),
-            multisample: wgpu::MultisampleState::default(),
-            fragment: Some(wgpu::FragmentState {
-                module: &f_shader,
-                entry_point: "main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: *FORMAT.get().unwrap(),
-                    blend: None,
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }),
-            multiview: None,
+                multiview: None,
+            })
         });
 
         #[cfg(target_arch = "wasm32")]
@@ -184,37 +125,25 @@ impl MaterialTrait for ColorUnlit {
     }
 
     fn dispose(&mut self) {
-        self.bind_group = None;
         self.pipeline = None;
         self.bindgroup_sate = BindgroupState::Uninitialized;
-        self.uniform = None;
+        self.parameters.dispose();
     }
 
     fn set_bindgroups(&mut self, _asset_store: &crate::asset_managment::AssetStore) {
-        let device = DEVICE.get().unwrap();
-
-        let bind_group_f = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Fragment bind group"),
-            layout: self.bind_group_layout_f.as_ref().unwrap(),
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::Buffer(
-                    self.uniform.as_ref().unwrap().as_entire_buffer_binding(),
-                ),
-            }],
-        });
-        #[cfg(target_arch = "wasm32")]
-        {
-            self.bind_group = Some(Arc::new(crate::wrappers::WgpuWrapper::new(bind_group_f)));
-        }
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            self.bind_group = Some(Arc::new(bind_group_f));
-        }
+        //The bind group is created by the parameters
         self.bindgroup_sate = BindgroupState::Initialized;
     }
 
     fn bindgroup_sate(&self) -> crate::assets::BindgroupState {
         self.bindgroup_sate
     }
+
+    fn parameters(&self) -> Option<&Parameters> {
+        Some(&self.parameters)
+    }
+
+    fn parameters_mut(&mut self) -> Option<&mut Parameters> {
+        Some(&mut self.parameters)
+    }
 }
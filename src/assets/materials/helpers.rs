@@ -1,34 +1,38 @@
+use std::mem::{offset_of, size_of};
+
 use wgpu::VertexBufferLayout;
 
+use crate::structures::{InstanceData, SkinVertex, Vertex};
+
 ///Returns the default vertex buffer bindings
 #[must_use]
 pub const fn vertex_binding() -> [VertexBufferLayout<'static>; 2] {
     [
         //Vertex data
         wgpu::VertexBufferLayout {
-            array_stride: 36,
+            array_stride: size_of::<Vertex>() as u64,
             step_mode: wgpu::VertexStepMode::Vertex,
             attributes: &[
                 wgpu::VertexAttribute {
                     format: wgpu::VertexFormat::Float32x4,
-                    offset: 0,
+                    offset: offset_of!(Vertex, coords) as u64,
                     shader_location: 0,
                 },
                 wgpu::VertexAttribute {
                     format: wgpu::VertexFormat::Float32x2,
-                    offset: 16,
+                    offset: offset_of!(Vertex, texture) as u64,
                     shader_location: 1,
                 },
                 wgpu::VertexAttribute {
                     format: wgpu::VertexFormat::Float32x3,
-                    offset: 24,
+                    offset: offset_of!(Vertex, normal) as u64,
                     shader_location: 2,
                 },
             ],
         },
-        //Transform data
+        //Instance data
         wgpu::VertexBufferLayout {
-            array_stride: 64,
+            array_stride: size_of::<InstanceData>() as u64,
             step_mode: wgpu::VertexStepMode::Instance,
             attributes: &[
                 wgpu::VertexAttribute {
@@ -51,6 +55,59 @@ pub const fn vertex_binding() -> [VertexBufferLayout<'static>; 2] {
                     offset: 48,
                     shader_location: 6,
                 },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x3,
+                    offset: offset_of!(InstanceData, normal) as u64,
+                    shader_location: 7,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x3,
+                    offset: offset_of!(InstanceData, normal) as u64 + 12,
+                    shader_location: 8,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x3,
+                    offset: offset_of!(InstanceData, normal) as u64 + 24,
+                    shader_location: 9,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32,
+                    offset: offset_of!(InstanceData, receive_shadows) as u64,
+                    shader_location: 11,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Uint32,
+                    offset: offset_of!(InstanceData, texture_index) as u64,
+                    shader_location: 12,
+                },
+            ],
+        },
+    ]
+}
+
+///Returns the vertex buffer bindings of skinned meshes, the default bindings followed by the
+///joints and weights of the vertices
+#[must_use]
+pub const fn skinned_vertex_binding() -> [VertexBufferLayout<'static>; 3] {
+    let [vertices, instances] = vertex_binding();
+    [
+        vertices,
+        instances,
+        //Skin data
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<SkinVertex>() as u64,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Uint32x4,
+                    offset: offset_of!(SkinVertex, joints) as u64,
+                    shader_location: 13,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x4,
+                    offset: offset_of!(SkinVertex, weights) as u64,
+                    shader_location: 14,
+                },
             ],
         },
     ]
@@ -0,0 +1,300 @@
+#![allow(clippy::too_many_lines)]
+use std::num::NonZeroU64;
+use std::sync::Arc;
+
+use wgpu::util::DeviceExt;
+use wgpu::BufferUsages;
+
+use crate::assets::{shader, Material};
+use crate::structures::Color;
+use crate::{
+    grimoire,
+    rendering::{depth, hdr, lighting, msaa},
+    DEVICE,
+};
+
+use crate::{assets::material::MaterialTrait, assets::BindgroupState};
+
+use super::helpers::skinned_vertex_binding;
+
+///Material that renders an object with a given color, lit by the directional light, the point
+///and spot lights and the ambient light, see [`crate::rendering::lighting`]
+///
+///Receives the shadows of the [`crate::rendering::extensions::shadows::ShadowMap`] extension
+pub struct Lit {
+    #[cfg(target_arch = "wasm32")]
+    pipeline: Option<Arc<crate::wrappers::WgpuWrapper<wgpu::RenderPipeline>>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pipeline: Option<Arc<wgpu::RenderPipeline>>,
+    #[cfg(target_arch = "wasm32")]
+    skinned_pipeline: Option<Arc<crate::wrappers::WgpuWrapper<wgpu::RenderPipeline>>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    skinned_pipeline: Option<Arc<wgpu::RenderPipeline>>,
+    #[cfg(target_arch = "wasm32")]
+    bind_group: Option<Arc<crate::wrappers::WgpuWrapper<wgpu::BindGroup>>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    bind_group: Option<Arc<wgpu::BindGroup>>,
+    #[cfg(target_arch = "wasm32")]
+    bind_group_layout_f: Option<crate::wrappers::WgpuWrapper<wgpu::BindGroupLayout>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    bind_group_layout_f: Option<wgpu::BindGroupLayout>,
+    #[cfg(target_arch = "wasm32")]
+    uniform: Option<crate::wrappers::WgpuWrapper<wgpu::Buffer>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    uniform: Option<wgpu::Buffer>,
+    color: Color,
+    bindgroup_sate: BindgroupState,
+}
+
+impl Lit {
+    #[allow(clippy::new_ret_no_self)]
+    #[must_use]
+    ///Creates a new material with a given color
+    pub fn new(color: Color) -> Material {
+        Self {
+            color,
+            pipeline: None,
+            skinned_pipeline: None,
+            bind_group: None,
+            bind_group_layout_f: None,
+            bindgroup_sate: BindgroupState::Uninitialized,
+            uniform: None,
+        }
+        .into()
+    }
+
+    ///Sets the bind groups of the material and of the lighting
+    fn set_bind_groups(&self, render_pass: &mut wgpu::RenderPass) {
+        let b = unsafe {
+            Arc::as_ptr(&self.bind_group.clone().unwrap())
+                .as_ref()
+                .unwrap()
+        };
+        render_pass.set_bind_group(1, b, &[]);
+
+        //The lighting keeps the bind group alive until the shadow map changes, which doesn't
+        //happen while a pass is being recorded
+        let lighting = lighting::bind_group();
+        let lighting = unsafe { Arc::as_ptr(&lighting).as_ref().unwrap() };
+        render_pass.set_bind_group(grimoire::LIGHTING_BIND_GROUP_INDEX, lighting, &[]);
+    }
+}
+
+impl MaterialTrait for Lit {
+    fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        //SHOULD BE FINE
+        //TODO: FIND A BETTER SOLUTION
+        //This is a big FUCK OFF to the borrow checker
+        let pipeline = unsafe {
+            Arc::as_ptr(self.pipeline.as_ref().unwrap())
+                .as_ref()
+                .unwrap()
+        };
+
+        render_pass.set_pipeline(pipeline);
+        self.set_bind_groups(render_pass);
+    }
+
+    fn render_skinned(&self, render_pass: &mut wgpu::RenderPass) {
+        let pipeline = unsafe {
+            Arc::as_ptr(self.skinned_pipeline.as_ref().unwrap())
+                .as_ref()
+                .unwrap()
+        };
+
+        render_pass.set_pipeline(pipeline);
+        self.set_bind_groups(render_pass);
+    }
+
+    fn intialize(&mut self) {
+        let device = DEVICE.get().unwrap();
+
+        let v_shader = shader::compile_vertex(false).expect("Failed to compile the vertex shader");
+        let v_shader_skinned = shader::compile_vertex(true)
+            .expect("Failed to compile the skinned vertex shader");
+        let f_shader = lighting::compile_shader("lit.wgsl", include_str!("../../shaders/lit.wgsl"))
+            .expect("Failed to compile the fragment shader");
+
+        let bind_group_layout_f =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Lit fragment binding"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: Some(NonZeroU64::new(4 * 4).unwrap()),
+                    },
+                    count: None,
+                }],
+            });
+
+        let cam_bind_group_layout =
+            device.create_bind_group_layout(&grimoire::CAMERA_BIND_GROUP_LAYOUT_DESCRIPTOR);
+        let lighting_bind_group_layout = lighting::create_bind_group_layout();
+        let joints_bind_group_layout =
+            device.create_bind_group_layout(&grimoire::JOINTS_BIND_GROUP_LAYOUT_DESCRIPTOR);
+
+        let bind_group_layouts = [
+            &cam_bind_group_layout,
+            &bind_group_layout_f,
+            &lighting_bind_group_layout,
+            &joints_bind_group_layout,
+        ];
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Lit pipeline layout"),
+            bind_group_layouts: &bind_group_layouts[..3],
+            push_constant_ranges: &[],
+        });
+        //The joints are only bound for skinned meshes
+        let skinned_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Lit skinned pipeline layout"),
+                bind_group_layouts: &bind_group_layouts,
+                push_constant_ranges: &[],
+            });
+        let vertex_buffers = skinned_vertex_binding();
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.bind_group_layout_f = Some(crate::wrappers::WgpuWrapper::new(bind_group_layout_f));
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.bind_group_layout_f = Some(bind_group_layout_f);
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.uniform = Some(crate::wrappers::WgpuWrapper::new(
+                device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Lit uniform"),
+                    contents: bytemuck::bytes_of(&self.color),
+                    usage: BufferUsages::UNIFORM,
+                }),
+            ));
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.uniform = Some(
+                device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Lit uniform"),
+                    contents: bytemuck::bytes_of(&self.color),
+                    usage: BufferUsages::UNIFORM,
+                }),
+            );
+        }
+        let create_pipeline = |label: &str,
+                               layout: &wgpu::PipelineLayout,
+                               module: &wgpu::ShaderModule,
+                               buffers: &[wgpu::VertexBufferLayout]| {
+            crate::errors::scoped(label, || {
+                device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some(label),
+                    layout: Some(layout),
+                    vertex: wgpu::VertexState {
+                        module,
+                        entry_point: "main",
+                        buffers,
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    },
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        cull_mode: Some(wgpu::Face::Back),
+                        unclipped_depth: false,
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        conservative: false,
+                    },
+                    depth_stencil: Some(wgpu::DepthStencilState {
+                        format: depth::format(),
+                        depth_write_enabled: true,
+                        depth_compare: depth::compare_function(),
+                        stencil: wgpu::StencilState::default(),
+                        bias: wgpu::DepthBiasState::default(),
+                    }),
+                    multisample: msaa::multisample_state(),
+                    fragment: Some(wgpu::FragmentState {
+                        module: &f_shader,
+                        entry_point: "main",
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: hdr::color_format(),
+                            blend: None,
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    }),
+                    multiview: None,
+                })
+            })
+        };
+        let pipeline = create_pipeline(
+            "Lit pipeline",
+            &pipeline_layout,
+            &v_shader,
+            &vertex_buffers[..2],
+        );
+        let skinned_pipeline = create_pipeline(
+            "Lit skinned pipeline",
+            &skinned_pipeline_layout,
+            &v_shader_skinned,
+            &vertex_buffers,
+        );
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.pipeline = Some(Arc::new(crate::wrappers::WgpuWrapper::new(pipeline)));
+            self.skinned_pipeline = Some(Arc::new(crate::wrappers::WgpuWrapper::new(
+                skinned_pipeline,
+            )));
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.pipeline = Some(Arc::new(pipeline));
+            self.skinned_pipeline = Some(Arc::new(skinned_pipeline));
+        }
+    }
+
+    fn dispose(&mut self) {
+        self.bind_group = None;
+        self.pipeline = None;
+        self.skinned_pipeline = None;
+        self.bindgroup_sate = BindgroupState::Uninitialized;
+        self.uniform = None;
+    }
+
+    fn set_bindgroups(&mut self, _asset_store: &crate::asset_managment::AssetStore) {
+        let device = DEVICE.get().unwrap();
+
+        let bind_group_f = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Lit fragment bind group"),
+            layout: self.bind_group_layout_f.as_ref().unwrap(),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(
+                    self.uniform.as_ref().unwrap().as_entire_buffer_binding(),
+                ),
+            }],
+        });
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.bind_group = Some(Arc::new(crate::wrappers::WgpuWrapper::new(bind_group_f)));
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.bind_group = Some(Arc::new(bind_group_f));
+        }
+        self.bindgroup_sate = BindgroupState::Initialized;
+    }
+
+    fn bindgroup_sate(&self) -> crate::assets::BindgroupState {
+        self.bindgroup_sate
+    }
+
+    fn supports_skinning(&self) -> bool {
+        true
+    }
+}
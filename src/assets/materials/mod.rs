@@ -1,7 +1,13 @@
 pub use color_unlit::ColorUnlit;
+pub use lit::Lit;
+pub use pbr::{Pbr, PbrParameters};
+pub use shader_material::{ShaderLayout, ShaderMaterial, ShaderMaterialError};
 pub use texture_unlit::TextureUnlit;
 
 mod color_unlit;
+mod lit;
+mod pbr;
+mod shader_material;
 mod texture_unlit;
 
 ///Helper functions for implementing materials
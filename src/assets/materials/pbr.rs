@@ -0,0 +1,421 @@
+#![allow(clippy::too_many_lines)]
+use std::num::NonZeroU64;
+use std::sync::Arc;
+
+use wgpu::util::DeviceExt;
+use wgpu::BufferUsages;
+
+use crate::assets::{shader, Material};
+use crate::structures::Color;
+use crate::{
+    asset_managment::{AssetStore, UUID},
+    grimoire,
+    rendering::{depth, hdr, lighting, msaa},
+    DEVICE, QUEUE,
+};
+
+use crate::{assets::material::MaterialTrait, assets::BindgroupState, assets::Texture};
+
+use super::helpers::vertex_binding;
+
+///Parameters of the [`Pbr`] material
+///
+///Follows the metallic roughness model of glTF, the factors are multiplied with the values of the
+///maps, missing maps are treated as white, or as a flat normal for the normal map
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PbrParameters {
+    ///Base color of the surface, the alpha is used as the alpha of the output
+    pub albedo: Color,
+    ///Metalness of the surface, from 0 to 1
+    pub metallic: f32,
+    ///Roughness of the surface, from 0 to 1
+    pub roughness: f32,
+    ///Light emitted by the surface, the alpha is ignored
+    pub emissive: Color,
+    ///Strength of the normal map
+    pub normal_scale: f32,
+    ///Strength of the ambient occlusion map, 0 disables it
+    pub occlusion_strength: f32,
+    ///Texture with the base color of the surface, in srgb
+    pub albedo_map: Option<UUID>,
+    ///Tangent space normal map
+    pub normal_map: Option<UUID>,
+    ///Texture with the roughness in the green channel and the metalness in the blue channel
+    pub metallic_roughness_map: Option<UUID>,
+    ///Texture with the emitted light, in srgb
+    pub emissive_map: Option<UUID>,
+    ///Texture with the ambient occlusion in the red channel
+    pub occlusion_map: Option<UUID>,
+}
+
+impl Default for PbrParameters {
+    fn default() -> Self {
+        Self {
+            albedo: Color::white(),
+            metallic: 0.0,
+            roughness: 0.5,
+            emissive: Color::black(),
+            normal_scale: 1.0,
+            occlusion_strength: 1.0,
+            albedo_map: None,
+            normal_map: None,
+            metallic_roughness_map: None,
+            emissive_map: None,
+            occlusion_map: None,
+        }
+    }
+}
+
+///Parameters as used by the shader
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct PbrUniform {
+    albedo: Color,
+    emissive: Color,
+    ///Metallic, roughness, normal scale, occlusion strength
+    factors: [f32; 4],
+}
+
+///Physically based material, using the metallic roughness model, so that the materials of glTF
+///assets render as intended
+///
+///Lit by the same lights as the [`super::Lit`] material, see [`crate::rendering::lighting`]
+pub struct Pbr {
+    #[cfg(target_arch = "wasm32")]
+    pipeline: Option<Arc<crate::wrappers::WgpuWrapper<wgpu::RenderPipeline>>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pipeline: Option<Arc<wgpu::RenderPipeline>>,
+    #[cfg(target_arch = "wasm32")]
+    bind_group: Option<Arc<crate::wrappers::WgpuWrapper<wgpu::BindGroup>>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    bind_group: Option<Arc<wgpu::BindGroup>>,
+    #[cfg(target_arch = "wasm32")]
+    bind_group_layout_f: Option<crate::wrappers::WgpuWrapper<wgpu::BindGroupLayout>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    bind_group_layout_f: Option<wgpu::BindGroupLayout>,
+    #[cfg(target_arch = "wasm32")]
+    uniform: Option<crate::wrappers::WgpuWrapper<wgpu::Buffer>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    uniform: Option<wgpu::Buffer>,
+    parameters: PbrParameters,
+    bindgroup_sate: BindgroupState,
+}
+
+impl Pbr {
+    #[allow(clippy::new_ret_no_self)]
+    #[must_use]
+    ///Creates a new material with the given parameters
+    pub fn new(parameters: PbrParameters) -> Material {
+        Self {
+            parameters,
+            pipeline: None,
+            bind_group: None,
+            bind_group_layout_f: None,
+            bindgroup_sate: BindgroupState::Uninitialized,
+            uniform: None,
+        }
+        .into()
+    }
+
+    fn uniform(&self) -> PbrUniform {
+        let p = &self.parameters;
+        PbrUniform {
+            albedo: p.albedo,
+            emissive: p.emissive,
+            factors: [
+                p.metallic.clamp(0.0, 1.0),
+                p.roughness.clamp(0.0, 1.0),
+                p.normal_scale,
+                p.occlusion_strength.clamp(0.0, 1.0),
+            ],
+        }
+    }
+}
+
+///Returns a view of the texture `id`, or of a 1x1 texture with the given `color` if there is no
+///texture
+fn map_view(id: Option<UUID>, color: [u8; 4], asset_store: &AssetStore) -> wgpu::TextureView {
+    let descriptor = wgpu::TextureViewDescriptor {
+        label: Some("Pbr map view"),
+        format: Some(wgpu::TextureFormat::Rgba8Unorm),
+        dimension: None,
+        aspect: wgpu::TextureAspect::All,
+        base_mip_level: 0,
+        mip_level_count: Some(1),
+        base_array_layer: 0,
+        array_layer_count: None,
+    };
+
+    if let Some(texture) = id.and_then(|id| asset_store.get_by_id::<Texture>(id).ok()) {
+        return texture
+            .borrow()
+            .texture
+            .as_ref()
+            .unwrap()
+            .create_view(&descriptor);
+    }
+
+    DEVICE
+        .get()
+        .unwrap()
+        .create_texture_with_data(
+            QUEUE.get().unwrap(),
+            &wgpu::TextureDescriptor {
+                label: Some("Pbr default map"),
+                size: wgpu::Extent3d {
+                    width: 1,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &color,
+        )
+        .create_view(&descriptor)
+}
+
+impl MaterialTrait for Pbr {
+    fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        //SHOULD BE FINE
+        //TODO: FIND A BETTER SOLUTION
+        let pipeline = unsafe {
+            Arc::as_ptr(self.pipeline.as_ref().unwrap())
+                .as_ref()
+                .unwrap()
+        };
+
+        render_pass.set_pipeline(pipeline);
+        let b = unsafe {
+            Arc::as_ptr(&self.bind_group.clone().unwrap())
+                .as_ref()
+                .unwrap()
+        };
+        render_pass.set_bind_group(1, b, &[]);
+
+        let lighting = lighting::bind_group();
+        let lighting = unsafe { Arc::as_ptr(&lighting).as_ref().unwrap() };
+        render_pass.set_bind_group(grimoire::LIGHTING_BIND_GROUP_INDEX, lighting, &[]);
+    }
+
+    fn intialize(&mut self) {
+        let device = DEVICE.get().unwrap();
+
+        let v_shader = shader::compile_vertex(false).expect("Failed to compile the vertex shader");
+        let f_shader = lighting::compile_shader("pbr.wgsl", include_str!("../../shaders/pbr.wgsl"))
+            .expect("Failed to compile the fragment shader");
+
+        let map = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+
+        let bind_group_layout_f =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Pbr fragment binding"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: Some(
+                                NonZeroU64::new(std::mem::size_of::<PbrUniform>() as u64).unwrap(),
+                            ),
+                        },
+                        count: None,
+                    },
+                    map(1),
+                    map(2),
+                    map(3),
+                    map(4),
+                    map(5),
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 6,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        let cam_bind_group_layout =
+            device.create_bind_group_layout(&grimoire::CAMERA_BIND_GROUP_LAYOUT_DESCRIPTOR);
+        let lighting_bind_group_layout = lighting::create_bind_group_layout();
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Pbr pipeline layout"),
+            bind_group_layouts: &[
+                &cam_bind_group_layout,
+                &bind_group_layout_f,
+                &lighting_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.bind_group_layout_f = Some(crate::wrappers::WgpuWrapper::new(bind_group_layout_f));
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.bind_group_layout_f = Some(bind_group_layout_f);
+        }
+
+        let uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Pbr uniform"),
+            contents: bytemuck::bytes_of(&self.uniform()),
+            usage: BufferUsages::UNIFORM,
+        });
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.uniform = Some(crate::wrappers::WgpuWrapper::new(uniform));
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.uniform = Some(uniform);
+        }
+
+        let pipeline = crate::errors::scoped("Pbr pipeline", || {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Pbr pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &v_shader,
+                    entry_point: "main",
+                    buffers: &vertex_binding(),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: Some(wgpu::Face::Back),
+                    unclipped_depth: false,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    conservative: false,
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: depth::format(),
+                    depth_write_enabled: true,
+                    depth_compare: depth::compare_function(),
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: msaa::multisample_state(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &f_shader,
+                    entry_point: "main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: hdr::color_format(),
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                }),
+                multiview: None,
+            })
+        });
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.pipeline = Some(Arc::new(crate::wrappers::WgpuWrapper::new(pipeline)));
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.pipeline = Some(Arc::new(pipeline));
+        }
+    }
+
+    fn dispose(&mut self) {
+        self.bind_group = None;
+        self.pipeline = None;
+        self.bindgroup_sate = BindgroupState::Uninitialized;
+        self.uniform = None;
+    }
+
+    fn set_bindgroups(&mut self, asset_store: &AssetStore) {
+        let device = DEVICE.get().unwrap();
+        let p = &self.parameters;
+
+        let albedo = map_view(p.albedo_map, [255; 4], asset_store);
+        let normal = map_view(p.normal_map, [128, 128, 255, 255], asset_store);
+        let metallic_roughness = map_view(p.metallic_roughness_map, [255; 4], asset_store);
+        let emissive = map_view(p.emissive_map, [255; 4], asset_store);
+        let occlusion = map_view(p.occlusion_map, [255; 4], asset_store);
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Pbr sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            address_mode_w: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let bind_group_f = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Pbr fragment bind group"),
+            layout: self.bind_group_layout_f.as_ref().unwrap(),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(
+                        self.uniform.as_ref().unwrap().as_entire_buffer_binding(),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&albedo),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&normal),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&metallic_roughness),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(&emissive),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: wgpu::BindingResource::TextureView(&occlusion),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.bind_group = Some(Arc::new(crate::wrappers::WgpuWrapper::new(bind_group_f)));
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.bind_group = Some(Arc::new(bind_group_f));
+        }
+        self.bindgroup_sate = BindgroupState::Initialized;
+    }
+
+    fn bindgroup_sate(&self) -> crate::assets::BindgroupState {
+        self.bindgroup_sate
+    }
+}
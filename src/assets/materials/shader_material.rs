@@ -0,0 +1,371 @@
+#![allow(clippy::too_many_lines)]
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::assets::{shader, Material, Parameters, Preprocessor};
+use crate::{
+    grimoire,
+    rendering::{depth, hdr, msaa},
+    DEVICE,
+};
+
+use crate::{assets::material::MaterialTrait, assets::BindgroupState};
+
+use super::helpers::vertex_binding;
+
+///Layout of a [`ShaderMaterial`], describes what the shader provides and how it's rendered
+pub struct ShaderLayout {
+    ///Name of the shader, used in the error messages and labels
+    pub name: String,
+    ///Parameters of the material, bound in group 1, their declarations are added to the shader
+    pub parameters: Parameters,
+    ///Whether the shader provides its own vertex shader `vs_main`, the engine one is used
+    ///otherwise
+    pub vertex: bool,
+    ///Blending of the output with the frame
+    pub blend: Option<wgpu::BlendState>,
+    ///Culled faces
+    pub cull_mode: Option<wgpu::Face>,
+    ///Whether the material writes to the depth buffer
+    pub depth_write: bool,
+    ///Flags and included files of the shader, see [`crate::assets::preprocessor`]
+    pub preprocessor: Preprocessor,
+}
+
+impl Default for ShaderLayout {
+    fn default() -> Self {
+        Self {
+            name: "shader material".to_owned(),
+            parameters: Parameters::new(),
+            vertex: false,
+            blend: None,
+            cull_mode: Some(wgpu::Face::Back),
+            depth_write: true,
+            preprocessor: Preprocessor::new(),
+        }
+    }
+}
+
+///Error while creating a [`ShaderMaterial`]
+#[derive(Debug, Clone)]
+pub enum ShaderMaterialError {
+    ///The shader failed to parse or validate
+    Compilation(crate::errors::Error),
+    ///The file of the shader could not be read
+    Read {
+        ///Path of the file
+        path: PathBuf,
+        ///Description of the error
+        message: String,
+    },
+    ///The shader does not have a required entry point
+    MissingEntryPoint {
+        ///Name of the entry point
+        name: &'static str,
+        ///Stage of the entry point
+        stage: naga::ShaderStage,
+    },
+}
+
+impl std::fmt::Display for ShaderMaterialError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Compilation(e) => write!(f, "{e}"),
+            Self::Read { path, message } => {
+                write!(f, "Failed to read the shader {}: {message}", path.display())
+            }
+            Self::MissingEntryPoint { name, stage } => {
+                write!(f, "The shader has no {stage:?} entry point \"{name}\"")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ShaderMaterialError {}
+
+impl From<crate::errors::Error> for ShaderMaterialError {
+    fn from(value: crate::errors::Error) -> Self {
+        Self::Compilation(value)
+    }
+}
+
+///Material rendered with a user provided WGSL shader
+///
+///The engine prepends its declarations to the source, `common.wgsl` and the parameters:
+///- `VertexInput`, the per vertex and per instance data of the mesh
+///- `VertexOutput`, the output of the vertex shader and the input of the fragment shader
+///- `camera`, the view projection matrix in group 0, only visible to the vertex shader
+///- the declarations of the parameters in group 1, see [`Parameters::wgsl_declaration`]
+///
+///The source must contain a fragment entry point `fs_main` and, if [`ShaderLayout::vertex`] is
+///set, a vertex entry point `vs_main`. It's processed with [`ShaderLayout::preprocessor`], so
+///variants of the material can be created from the same file with different flags.
+///
+///```no_run
+///# use lunar_engine::assets::{ShaderMaterial, materials::ShaderLayout, Parameters};
+///# use lunar_engine::structures::Color;
+///let material = ShaderMaterial::new(
+///    "@fragment
+///     fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+///         return parameters.tint * max(dot(in.normal, vec3(0.0, 1.0, 0.0)), 0.1);
+///     }",
+///    ShaderLayout {
+///        parameters: Parameters::new().with("tint", Color::rgb(1.0, 0.5, 0.0)),
+///        ..Default::default()
+///    },
+///)
+///.unwrap();
+///```
+///
+///Materials created with [`Self::from_file`] are recreated when the file changes in debug builds,
+///see [`AssetStore::reload_changed`](crate::asset_managment::AssetStore::reload_changed)
+pub struct ShaderMaterial {
+    #[cfg(target_arch = "wasm32")]
+    pipeline: Option<Arc<crate::wrappers::WgpuWrapper<wgpu::RenderPipeline>>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pipeline: Option<Arc<wgpu::RenderPipeline>>,
+    source: String,
+    map: shader::SourceMap,
+    file: Option<PathBuf>,
+    layout: ShaderLayout,
+    bindgroup_sate: BindgroupState,
+}
+
+impl ShaderMaterial {
+    #[allow(clippy::new_ret_no_self)]
+    ///Creates a new material from the WGSL source, the shader is validated immediately
+    ///
+    ///# Errors
+    ///Returns an error if the shader fails to parse or validate, or misses an entry point
+    pub fn new(wgsl_source: &str, layout: ShaderLayout) -> Result<Material, ShaderMaterialError> {
+        let (source, map) = assemble(wgsl_source, &layout)?;
+
+        Ok(Self {
+            pipeline: None,
+            source,
+            map,
+            file: None,
+            layout,
+            bindgroup_sate: BindgroupState::Uninitialized,
+        }
+        .into())
+    }
+
+    ///Creates a new material from the WGSL file, the name of the layout is replaced by the path
+    ///
+    ///# Errors
+    ///Returns an error if the file can't be read, or the shader fails to validate
+    pub fn from_file(
+        path: &Path,
+        mut layout: ShaderLayout,
+    ) -> Result<Material, ShaderMaterialError> {
+        layout.name = path.display().to_string();
+        let (source, map) = assemble(&read(path)?, &layout)?;
+
+        Ok(Self {
+            pipeline: None,
+            source,
+            map,
+            file: Some(path.to_path_buf()),
+            layout,
+            bindgroup_sate: BindgroupState::Uninitialized,
+        }
+        .into())
+    }
+
+    fn create_pipeline(&mut self) -> Result<wgpu::RenderPipeline, crate::errors::Error> {
+        let device = DEVICE.get().unwrap();
+        let label = self.layout.name.clone();
+
+        let shader = shader::compile_mapped(&self.source, &self.map)?;
+        let engine_vertex = if self.layout.vertex {
+            None
+        } else {
+            Some(shader::compile_vertex(false).expect("Failed to compile the vertex shader"))
+        };
+        let (v_shader, v_entry) = engine_vertex
+            .as_ref()
+            .map_or((&shader, "vs_main"), |s| (s, "main"));
+
+        let cam_bind_group_layout =
+            device.create_bind_group_layout(&grimoire::CAMERA_BIND_GROUP_LAYOUT_DESCRIPTOR);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(&label),
+            bind_group_layouts: &[
+                &cam_bind_group_layout,
+                self.layout.parameters.bind_group_layout(),
+            ],
+            push_constant_ranges: &[],
+        });
+
+        crate::errors::try_scoped(&label, || {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(&label),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: v_shader,
+                    entry_point: v_entry,
+                    buffers: &vertex_binding(),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: self.layout.cull_mode,
+                    unclipped_depth: false,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    conservative: false,
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: depth::format(),
+                    depth_write_enabled: self.layout.depth_write,
+                    depth_compare: depth::compare_function(),
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: msaa::multisample_state(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: hdr::color_format(),
+                        blend: self.layout.blend,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                }),
+                multiview: None,
+            })
+        })
+    }
+
+    fn set_pipeline(&mut self, pipeline: wgpu::RenderPipeline) {
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.pipeline = Some(Arc::new(crate::wrappers::WgpuWrapper::new(pipeline)));
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.pipeline = Some(Arc::new(pipeline));
+        }
+    }
+}
+
+fn read(path: &Path) -> Result<String, ShaderMaterialError> {
+    std::fs::read_to_string(path).map_err(|e| ShaderMaterialError::Read {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    })
+}
+
+//Preprocesses the source with the engine declarations and validates it
+fn assemble(
+    wgsl_source: &str,
+    layout: &ShaderLayout,
+) -> Result<(String, shader::SourceMap), ShaderMaterialError> {
+    let (source, map) = layout
+        .preprocessor
+        .clone()
+        .with_include("parameters", &layout.parameters.wgsl_declaration(1))
+        .with_prelude("common.wgsl")
+        .with_prelude("parameters")
+        .process(&layout.name, wgsl_source)?;
+
+    let module = shader::validate_mapped(&source, &map)?;
+
+    let mut required = vec![("fs_main", naga::ShaderStage::Fragment)];
+    if layout.vertex {
+        required.push(("vs_main", naga::ShaderStage::Vertex));
+    }
+    for (name, stage) in required {
+        if !module
+            .entry_points
+            .iter()
+            .any(|e| e.name == name && e.stage == stage)
+        {
+            return Err(ShaderMaterialError::MissingEntryPoint { name, stage });
+        }
+    }
+
+    Ok((source, map))
+}
+
+impl MaterialTrait for ShaderMaterial {
+    fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        //Same as the other materials, the pipeline lives as long as the material
+        let pipeline = unsafe {
+            Arc::as_ptr(self.pipeline.as_ref().unwrap())
+                .as_ref()
+                .unwrap()
+        };
+
+        render_pass.set_pipeline(pipeline);
+        self.layout.parameters.bind(render_pass, 1);
+    }
+
+    fn intialize(&mut self) {
+        let pipeline = self
+            .create_pipeline()
+            .expect("The shader was validated when the material was created");
+        self.set_pipeline(pipeline);
+    }
+
+    fn dispose(&mut self) {
+        self.pipeline = None;
+        self.bindgroup_sate = BindgroupState::Uninitialized;
+        self.layout.parameters.dispose();
+    }
+
+    fn set_bindgroups(&mut self, _asset_store: &crate::asset_managment::AssetStore) {
+        //The bind group is created by the parameters
+        self.bindgroup_sate = BindgroupState::Initialized;
+    }
+
+    fn bindgroup_sate(&self) -> BindgroupState {
+        self.bindgroup_sate
+    }
+
+    fn shader_files(&self) -> Vec<PathBuf> {
+        self.file.iter().cloned().collect()
+    }
+
+    fn reload_shaders(&mut self) -> Result<(), Box<dyn std::error::Error + Send>> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        let (source, map) = read(file)
+            .and_then(|s| assemble(&s, &self.layout))
+            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+        let previous = (
+            std::mem::replace(&mut self.source, source),
+            std::mem::replace(&mut self.map, map),
+        );
+        if self.pipeline.is_none() {
+            return Ok(());
+        }
+        match self.create_pipeline() {
+            Ok(pipeline) => {
+                self.set_pipeline(pipeline);
+                Ok(())
+            }
+            //Keeps the source matching the pipeline
+            Err(e) => {
+                (self.source, self.map) = previous;
+                Err(Box::new(ShaderMaterialError::Compilation(e)))
+            }
+        }
+    }
+
+    fn parameters(&self) -> Option<&Parameters> {
+        Some(&self.layout.parameters)
+    }
+
+    fn parameters_mut(&mut self) -> Option<&mut Parameters> {
+        Some(&mut self.layout.parameters)
+    }
+}
@@ -1,14 +1,21 @@
 #![allow(clippy::too_many_lines)]
 use std::sync::Arc;
 
-use crate::assets::Material;
-use crate::{asset_managment::UUID, grimoire, DEVICE, FORMAT};
+use crate::assets::{shader, Material};
+use crate::{
+    asset_managment::UUID,
+    grimoire,
+    rendering::{bindless, depth, hdr, msaa},
+    DEVICE,
+};
 
 use crate::{assets::material::MaterialTrait, assets::BindgroupState, assets::Texture};
 
 use super::helpers;
 
 ///Basic material that renders an object with a given texture, without lighting
+///
+///Uses the shared texture array where it's supported, see [`crate::rendering::bindless`]
 pub struct TextureUnlit {
     #[cfg(target_arch = "wasm32")]
     pipeline: Option<Arc<crate::wrappers::WgpuWrapper<wgpu::RenderPipeline>>>,
@@ -23,6 +30,8 @@ pub struct TextureUnlit {
     #[cfg(not(target_arch = "wasm32"))]
     bind_group_layout_f: Option<wgpu::BindGroupLayout>,
     texture_id: UUID,
+    //Index of the texture in the shared texture array, if it's used
+    texture_index: Option<u32>,
     bindgroup_sate: BindgroupState,
 }
 
@@ -36,6 +45,7 @@ impl TextureUnlit {
             bind_group: None,
             bind_group_layout_f: None,
             texture_id,
+            texture_index: None,
             bindgroup_sate: BindgroupState::Uninitialized,
         }
         .into()
@@ -53,6 +63,13 @@ impl MaterialTrait for TextureUnlit {
         };
 
         render_pass.set_pipeline(pipeline);
+        if self.texture_index.is_some() {
+            //Kept alive by the texture array until it's recreated before the next pass
+            let b = bindless::current().unwrap();
+            let b = unsafe { Arc::as_ptr(&b).as_ref().unwrap() };
+            render_pass.set_bind_group(1, b, &[]);
+            return;
+        }
         let b = unsafe {
             Arc::as_ptr(&self.bind_group.clone().unwrap())
                 .as_ref()
@@ -63,15 +80,29 @@ impl MaterialTrait for TextureUnlit {
 
     fn intialize(&mut self) {
         let device = DEVICE.get().unwrap();
+        self.texture_index = if bindless::supported() {
+            bindless::texture_index(self.texture_id)
+        } else {
+            None
+        };
 
-        let v_shader =
-            device.create_shader_module(wgpu::include_wgsl!("../../shaders/vertex.wgsl"));
-        let f_shader =
-            device.create_shader_module(wgpu::include_wgsl!("../../shaders/texture_unlit.wgsl"));
+        let v_shader = shader::compile_vertex(false).expect("Failed to compile the vertex shader");
+        let f_shader = if self.texture_index.is_some() {
+            shader::compile(
+                "texture_unlit_bindless.wgsl",
+                include_str!("../../shaders/texture_unlit_bindless.wgsl"),
+            )
+        } else {
+            shader::compile(
+                "texture_unlit.wgsl",
+                include_str!("../../shaders/texture_unlit.wgsl"),
+            )
+        }
+        .expect("Failed to compile the fragment shader");
 
         let bind_group_layout_f =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Fragment binding"),
+                label: Some("Texture unlit fragment binding"),
                 entries: &[
                     wgpu::BindGroupLayoutEntry {
                         binding: 0,
@@ -95,9 +126,13 @@ impl MaterialTrait for TextureUnlit {
         let cam_bind_group_layout =
             device.create_bind_group_layout(&grimoire::CAMERA_BIND_GROUP_LAYOUT_DESCRIPTOR);
 
+        let shared_layout = self.texture_index.map(|_| bindless::bind_group_layout());
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: None,
-            bind_group_layouts: &[&cam_bind_group_layout, &bind_group_layout_f],
+            label: Some("Texture unlit pipeline layout"),
+            bind_group_layouts: &[
+                &cam_bind_group_layout,
+                shared_layout.as_deref().unwrap_or(&bind_group_layout_f),
+            ],
             push_constant_ranges: &[],
         });
 
@@ -111,43 +146,45 @@ impl MaterialTrait for TextureUnlit {
             self.bind_group_layout_f = Some(bind_group_layout_f);
         }
 
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: None,
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &v_shader,
-                entry_point: "main",
-                buffers: &helpers::vertex_binding(),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                unclipped_depth: false,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
-            },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth32Float,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState::default(),
-            }),
-            multisample: wgpu::MultisampleState::default(),
-            fragment: Some(wgpu::FragmentState {
-                module: &f_shader,
-                entry_point: "main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: *FORMAT.get().unwrap(),
-                    blend: None,
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }),
-            multiview: None,
+        let pipeline = crate::errors::scoped("Texture unlit pipeline", || {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Texture unlit pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &v_shader,
+                    entry_point: "main",
+                    buffers: &helpers::vertex_binding(),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: Some(wgpu::Face::Back),
+                    unclipped_depth: false,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    conservative: false,
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: depth::format(),
+                    depth_write_enabled: true,
+                    depth_compare: depth::compare_function(),
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: msaa::multisample_state(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &f_shader,
+                    entry_point: "main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: hdr::color_format(),
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                }),
+                multiview: None,
+            })
         });
 
         #[cfg(target_arch = "wasm32")]
@@ -167,13 +204,18 @@ impl MaterialTrait for TextureUnlit {
     }
 
     fn set_bindgroups(&mut self, asset_store: &crate::asset_managment::AssetStore) {
+        if self.texture_index.is_some() {
+            bindless::bind_group(asset_store);
+            self.bindgroup_sate = BindgroupState::Initialized;
+            return;
+        }
         let device = DEVICE.get().unwrap();
 
         let texture = asset_store.get_by_id::<Texture>(self.texture_id).unwrap();
         let texture = texture.borrow();
 
         let bind_group_f = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Fragment bind group"),
+            label: Some("Texture unlit fragment bind group"),
             layout: self.bind_group_layout_f.as_ref().unwrap(),
             entries: &[
                 wgpu::BindGroupEntry {
@@ -181,7 +223,7 @@ impl MaterialTrait for TextureUnlit {
                     resource: wgpu::BindingResource::TextureView(
                         &texture.texture.as_ref().unwrap().create_view(
                             &wgpu::TextureViewDescriptor {
-                                label: None,
+                                label: Some("Texture unlit view"),
                                 format: Some(wgpu::TextureFormat::Rgba8Unorm),
                                 dimension: None,
                                 aspect: wgpu::TextureAspect::All,
@@ -213,6 +255,18 @@ impl MaterialTrait for TextureUnlit {
     }
 
     fn bindgroup_sate(&self) -> crate::assets::BindgroupState {
+        //Textures were added to the array since its bind group was created
+        if self.texture_index.is_some() && !bindless::is_current() {
+            return BindgroupState::Uninitialized;
+        }
         self.bindgroup_sate
     }
+
+    fn texture_index(&self) -> Option<u32> {
+        self.texture_index
+    }
+
+    fn batch_key(&self) -> Option<&'static str> {
+        self.texture_index.map(|_| "texture_unlit")
+    }
 }
@@ -13,8 +13,10 @@
 //This sounds interesting
 
 use std::{
+    collections::BTreeMap,
+    hash::{Hash, Hasher},
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, Mutex, Weak},
 };
 
 use lunar_engine_derive::as_any;
@@ -22,13 +24,31 @@ use mesh_generator::generate_mesh;
 use wgpu::util::DeviceExt;
 
 use crate::{
-    asset_managment::{Asset, UUID},
-    math::{Vec3, Vector},
+    asset_managment::{Asset, AssetStore, UUID},
+    components::animator::{AnimationClip, Armature},
+    import::gltf::Model,
+    math::{geometry::Aabb, Vec3, Vector},
     DEVICE,
 };
 
 mod mesh_generator;
 
+#[cfg(target_arch = "wasm32")]
+type MeshBuffer = crate::wrappers::WgpuWrapper<wgpu::Buffer>;
+#[cfg(not(target_arch = "wasm32"))]
+type MeshBuffer = wgpu::Buffer;
+
+//Buffers of the initialized meshes, keyed by the hash of their vertices and indices
+static GEOMETRY: Mutex<BTreeMap<u64, SharedGeometry>> = Mutex::new(BTreeMap::new());
+
+struct SharedGeometry {
+    //Mesh that created the buffers
+    id: UUID,
+    vertices: Weak<MeshBuffer>,
+    indices: Weak<MeshBuffer>,
+    skin: Option<Weak<MeshBuffer>>,
+}
+
 ///Asset that stores mesh data
 pub struct Mesh {
     id: Option<UUID>,
@@ -42,11 +62,22 @@ pub struct Mesh {
     vertex_buffer: Option<Arc<wgpu::Buffer>>,
     #[cfg(not(target_arch = "wasm32"))]
     index_buffer: Option<Arc<wgpu::Buffer>>,
+    //Joints and weights of the vertices of a skinned mesh
+    skin_buffer: Option<Arc<MeshBuffer>>,
     vert_count: Option<u32>,
     tris_count: Option<u32>,
     index_count: Option<u32>,
     ///distance to the vertex furthest from the origin
     extent: Option<f32>,
+    //Bounding box of the vertices in local space
+    bounds: Option<Aabb>,
+    //Mesh data loaded by `prepare`, waiting to be uploaded to the gpu
+    loaded: Option<Model>,
+    armature: Option<Arc<Armature>>,
+    animations: Vec<Arc<AnimationClip>>,
+    skeleton: Option<UUID>,
+    //Mesh the buffers are shared with
+    geometry: Option<UUID>,
 }
 
 ///Description of a uv sphere
@@ -72,7 +103,13 @@ enum MeshMode {
     ///An obj file that contains a single mesh
     SingleObjectOBJ(PathBuf),
     StaticSingleObjectOBJ(&'static str),
+    ///The first triangle primitive in a gltf or a glb file, with the first skin and the
+    ///animations
+    Gltf(PathBuf),
+    StaticGltf(&'static [u8]),
     GeneratedModel(ModelType),
+    ///Vertices and indices in memory
+    Data(crate::structures::Mesh),
 }
 
 impl Mesh {
@@ -83,13 +120,20 @@ impl Mesh {
         Self {
             id: None,
             initialized: false,
+            bounds: None,
             mode: MeshMode::StaticSingleObjectOBJ(mesh),
             vertex_buffer: None,
             index_buffer: None,
+            skin_buffer: None,
             vert_count: None,
             tris_count: None,
             index_count: None,
             extent: None,
+            loaded: None,
+            armature: None,
+            animations: Vec::new(),
+            skeleton: None,
+            geometry: None,
         }
     }
 
@@ -105,22 +149,156 @@ impl Mesh {
         Ok(Self {
             id: None,
             initialized: false,
+            bounds: None,
             mode: MeshMode::SingleObjectOBJ(path.to_owned()),
             vertex_buffer: None,
             index_buffer: None,
+            skin_buffer: None,
             tris_count: None,
             vert_count: None,
             index_count: None,
             extent: None,
+            loaded: None,
+            armature: None,
+            animations: Vec::new(),
+            skeleton: None,
+            geometry: None,
+        })
+    }
+
+    ///Creates a new asset that will load the first triangle primitive in a gltf or a glb file,
+    ///along with the armature of the first skin and the animations, see
+    ///[`crate::import::gltf::parse`]
+    ///
+    ///Currently unsupported on the web target
+    ///
+    ///# Errors
+    ///Returns an error if the file does not exist
+    pub fn new_from_gltf(path: &Path) -> Result<Self, std::io::Error> {
+        //Verify that file exists
+        std::fs::File::options().read(true).open(path)?;
+        Ok(Self {
+            mode: MeshMode::Gltf(path.to_owned()),
+            ..Self::new_from_static_gltf(&[])
         })
     }
 
+    ///Creates a new asset that will load the first triangle primitive in a statically loaded gltf
+    ///or glb file, along with the armature of the first skin and the animations
+    ///
+    ///All the buffers must be embedded in the file
+    #[must_use]
+    pub const fn new_from_static_gltf(data: &'static [u8]) -> Self {
+        Self {
+            id: None,
+            initialized: false,
+            bounds: None,
+            mode: MeshMode::StaticGltf(data),
+            vertex_buffer: None,
+            index_buffer: None,
+            skin_buffer: None,
+            vert_count: None,
+            tris_count: None,
+            index_count: None,
+            extent: None,
+            loaded: None,
+            armature: None,
+            animations: Vec::new(),
+            skeleton: None,
+            geometry: None,
+        }
+    }
+
+    ///Creates a new asset from the vertices and the indices in memory
+    #[must_use]
+    pub const fn new_from_data(mesh: crate::structures::Mesh) -> Self {
+        Self {
+            id: None,
+            initialized: false,
+            bounds: None,
+            mode: MeshMode::Data(mesh),
+            vertex_buffer: None,
+            index_buffer: None,
+            skin_buffer: None,
+            vert_count: None,
+            tris_count: None,
+            index_count: None,
+            extent: None,
+            loaded: None,
+            armature: None,
+            animations: Vec::new(),
+            skeleton: None,
+            geometry: None,
+        }
+    }
+
+    ///Returns the armature of a skinned mesh loaded from a gltf file, shared with the
+    ///[`Animator`](crate::components::animator::Animator)s animating the mesh
+    ///
+    ///Returns `None` if the mesh is not skinned or if it was not loaded yet
+    #[must_use]
+    pub fn get_armature(&self) -> Option<Arc<Armature>> {
+        self.armature.clone()
+    }
+
+    ///Returns the animation clips loaded from a gltf file
+    #[must_use]
+    pub fn get_animations(&self) -> &[Arc<AnimationClip>] {
+        &self.animations
+    }
+
+    ///Returns the animation clip with the given name
+    #[must_use]
+    pub fn get_animation(&self, name: &str) -> Option<Arc<AnimationClip>> {
+        self.animations.iter().find(|a| a.name == name).cloned()
+    }
+
+    ///Sets the [`Skeleton`](super::Skeleton) the joint indices of the vertices index into,
+    ///multiple meshes can share the same skeleton
+    pub fn set_skeleton(&mut self, skeleton: Option<UUID>) {
+        self.skeleton = skeleton;
+    }
+
+    ///Returns the id of the skeleton of the mesh
+    #[must_use]
+    pub const fn get_skeleton(&self) -> Option<UUID> {
+        self.skeleton
+    }
+
+    ///Returns `true` if the mesh has joints and weights
+    #[must_use]
+    pub const fn is_skinned(&self) -> bool {
+        self.skin_buffer.is_some()
+    }
+
+    ///Returns the id of the mesh whose geometry this mesh uses
+    ///
+    ///Meshes with identical vertices and indices share the buffers of the first one of them that
+    ///was initialized and are rendered as instances of that mesh. Returns the id of the mesh
+    ///itself if it was not initialized
+    ///
+    ///# Panics
+    ///Panics if the asset was not registered
+    #[must_use]
+    pub fn get_geometry_id(&self) -> UUID {
+        self.geometry.or(self.id).unwrap()
+    }
+
     ///Returns extent of the mesh
     #[must_use]
     pub const fn get_extent(&self) -> f32 {
         self.extent.unwrap()
     }
 
+    ///Returns the bounding box of the mesh in local space
+    ///
+    ///# Panics
+    ///Panics if the asset was not initialized
+    #[must_use]
+    pub const fn get_bounds(&self) -> Aabb {
+        self.bounds.unwrap()
+    }
+
     ///Returns the vertex buffer of the mesh
     ///
     ///# Panics
@@ -142,6 +320,15 @@ impl Mesh {
         self.vertex_buffer.clone().unwrap()
     }
 
+    ///Returns the buffer containing the joints and weights of the vertices, see
+    ///[`crate::structures::SkinVertex`]
+    ///
+    ///Returns `None` if the mesh is not skinned
+    #[must_use]
+    pub fn get_skin_buffer(&self) -> Option<Arc<MeshBuffer>> {
+        self.skin_buffer.clone()
+    }
+
     ///Returns the index buffer of the mesh
     ///
     ///# Panics
@@ -195,15 +382,22 @@ impl Mesh {
         Self {
             id: None,
             initialized: false,
+            bounds: None,
             extent: Some(
                 (f32::abs(dimensions.x) + f32::abs(dimensions.y) + f32::abs(dimensions.z)) / 2.0,
             ),
             mode: MeshMode::GeneratedModel(ModelType::Box(dimensions)),
             vertex_buffer: None,
             index_buffer: None,
+            skin_buffer: None,
             vert_count: None,
             tris_count: None,
             index_count: None,
+            loaded: None,
+            armature: None,
+            animations: Vec::new(),
+            skeleton: None,
+            geometry: None,
         }
     }
 
@@ -213,6 +407,7 @@ impl Mesh {
         Self {
             id: None,
             initialized: false,
+            bounds: None,
             extent: Some(desc.radius * 2.0),
             mode: MeshMode::GeneratedModel(ModelType::Sphere(desc)),
             vertex_buffer: None,
@@ -220,19 +415,17 @@ impl Mesh {
             vert_count: None,
             tris_count: None,
             index_buffer: None,
+            skin_buffer: None,
+            loaded: None,
+            armature: None,
+            animations: Vec::new(),
+            skeleton: None,
+            geometry: None,
         }
     }
-}
 
-impl Asset for Mesh {
-    #[as_any]
-
-    fn get_id(&self) -> UUID {
-        self.id.unwrap()
-    }
-
-    #[allow(clippy::cast_possible_truncation)]
-    fn initialize(&mut self) -> Result<(), Box<dyn std::error::Error + Send>> {
+    ///Loads or generates the mesh data
+    fn load(&self) -> Result<Model, Box<dyn std::error::Error + Send>> {
         //This is horrific, but i LOVE this :3
         let mesh = match &self.mode {
             MeshMode::SingleObjectOBJ(path) => {
@@ -267,8 +460,71 @@ impl Asset for Mesh {
                     }
                 }
             }
+            MeshMode::Gltf(path) => return with_meshes(crate::import::gltf::parse_file(path)?),
+            MeshMode::StaticGltf(data) => {
+                return with_meshes(crate::import::gltf::parse(data, |_| None)?)
+            }
             MeshMode::GeneratedModel(mdl_type) => generate_mesh(mdl_type),
+            MeshMode::Data(mesh) => mesh.clone(),
+        };
+        Ok(Model {
+            meshes: vec![mesh],
+            ..Default::default()
+        })
+    }
+
+    ///Loads or generates the vertices and the indices of the mesh, without uploading them
+    pub(crate) fn load_data(
+        &self,
+    ) -> Result<crate::structures::Mesh, Box<dyn std::error::Error + Send>> {
+        Ok(self.load()?.meshes.swap_remove(0))
+    }
+}
+
+//Checks that the loaded model contains a mesh
+fn with_meshes(model: Model) -> Result<Model, Box<dyn std::error::Error + Send>> {
+    if model.meshes.is_empty() {
+        return Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "The file does not contain any triangle meshes",
+        )));
+    }
+    Ok(model)
+}
+
+///Returns the geometry id of the mesh asset, see [`Mesh::get_geometry_id`], or the `id` itself
+///if the asset does not exist or shares the geometry of a mesh from a different store
+pub(crate) fn geometry_id(assets: &AssetStore, id: UUID) -> UUID {
+    assets
+        .get_by_id::<Mesh>(id)
+        .map(|m| m.borrow().get_geometry_id())
+        .ok()
+        .filter(|g| assets.contains(*g))
+        .unwrap_or(id)
+}
+
+impl Asset for Mesh {
+    #[as_any]
+
+    fn get_id(&self) -> UUID {
+        self.id.unwrap()
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn initialize(&mut self) -> Result<(), Box<dyn std::error::Error + Send>> {
+        let model = match self.loaded.take() {
+            Some(it) => it,
+            None => self.load()?,
         };
+        self.armature = model.armature.map(Arc::new);
+        self.animations = model.animations.into_iter().map(Arc::new).collect();
+        let mesh = model.meshes.into_iter().next().unwrap();
+        if !mesh.skin.is_empty() && mesh.skin.len() != mesh.vertices.len() {
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Every vertex of a skinned mesh must have joints and weights",
+            )));
+        }
 
         if self.extent.is_none() {
             let mut e = 0.0;
@@ -280,32 +536,92 @@ impl Asset for Mesh {
             }
             self.extent = Some(e.sqrt());
         }
+        self.bounds = Some(
+            Aabb::from_points(
+                &mesh
+                    .vertices
+                    .iter()
+                    .map(|v| v.coords.xyz())
+                    .collect::<Vec<_>>(),
+            )
+            .unwrap_or_default(),
+        );
+
+        let id = self.get_id();
+        let hash = {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            bytemuck::cast_slice::<_, u8>(mesh.vertices.as_slice()).hash(&mut hasher);
+            mesh.indices.hash(&mut hasher);
+            if !mesh.skin.is_empty() {
+                bytemuck::cast_slice::<_, u8>(mesh.skin.as_slice()).hash(&mut hasher);
+            }
+            hasher.finish()
+        };
 
-        let device = DEVICE.get().unwrap();
-        let name = format!("Mesh {}", self.get_id());
-
-        let vb = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some(&name),
-            contents: bytemuck::cast_slice(mesh.vertices.as_slice()),
-            usage: wgpu::BufferUsages::VERTEX,
+        let mut geometry = GEOMETRY.lock().unwrap();
+        let shared = geometry.get(&hash).and_then(|g| {
+            let skin = match &g.skin {
+                Some(skin) => Some(skin.upgrade()?),
+                None => None,
+            };
+            Some((g.id, g.vertices.upgrade()?, g.indices.upgrade()?, skin))
         });
 
-        let ib = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some(&name),
-            contents: bytemuck::cast_slice(mesh.indices.as_slice()),
-            usage: wgpu::BufferUsages::INDEX,
-        });
+        let (vertices, indices, skin) = if let Some((shared_id, vertices, indices, skin)) = shared {
+            self.geometry = Some(shared_id);
+            (vertices, indices, skin)
+        } else {
+            let device = DEVICE.get().unwrap();
+            let name = format!("Mesh {id}");
+
+            let (vb, ib) = crate::errors::scoped(&name, || {
+                (
+                    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some(&format!("{name} vertices")),
+                        contents: bytemuck::cast_slice(mesh.vertices.as_slice()),
+                        usage: wgpu::BufferUsages::VERTEX,
+                    }),
+                    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some(&format!("{name} indices")),
+                        contents: bytemuck::cast_slice(mesh.indices.as_slice()),
+                        usage: wgpu::BufferUsages::INDEX,
+                    }),
+                )
+            });
+
+            let sb = (!mesh.skin.is_empty()).then(|| {
+                device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(&format!("{name} skin")),
+                    contents: bytemuck::cast_slice(mesh.skin.as_slice()),
+                    usage: wgpu::BufferUsages::VERTEX,
+                })
+            });
+
+            #[cfg(target_arch = "wasm32")]
+            let (vb, ib, sb) = (
+                crate::wrappers::WgpuWrapper::new(vb),
+                crate::wrappers::WgpuWrapper::new(ib),
+                sb.map(crate::wrappers::WgpuWrapper::new),
+            );
+            let (vertices, indices, skin) = (Arc::new(vb), Arc::new(ib), sb.map(Arc::new));
+
+            geometry.insert(
+                hash,
+                SharedGeometry {
+                    id,
+                    vertices: Arc::downgrade(&vertices),
+                    indices: Arc::downgrade(&indices),
+                    skin: skin.as_ref().map(Arc::downgrade),
+                },
+            );
+            self.geometry = Some(id);
+            (vertices, indices, skin)
+        };
+        drop(geometry);
 
-        #[cfg(target_arch = "wasm32")]
-        {
-            self.vertex_buffer = Some(Arc::new(crate::wrappers::WgpuWrapper::new(vb)));
-            self.index_buffer = Some(Arc::new(crate::wrappers::WgpuWrapper::new(ib)));
-        }
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            self.vertex_buffer = Some(Arc::new(vb));
-            self.index_buffer = Some(Arc::new(ib));
-        }
+        self.vertex_buffer = Some(vertices);
+        self.index_buffer = Some(indices);
+        self.skin_buffer = skin;
         self.vert_count = Some(mesh.vertices.len() as u32);
         self.tris_count = Some((mesh.indices.len() as u32) / 3u32);
         self.index_count = Some(mesh.indices.len() as u32);
@@ -314,10 +630,19 @@ impl Asset for Mesh {
         Ok(())
     }
 
+    fn prepare(&mut self) -> Result<(), Box<dyn std::error::Error + Send>> {
+        if self.loaded.is_none() {
+            self.loaded = Some(self.load()?);
+        }
+        Ok(())
+    }
+
     fn dispose(&mut self) {
         //Unload index and vertex buffers, clearing memory
         self.vertex_buffer = None;
         self.index_buffer = None;
+        self.skin_buffer = None;
+        self.geometry = None;
         self.initialized = false;
     }
 
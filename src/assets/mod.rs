@@ -1,18 +1,31 @@
 //! Implemented assets
 
+///Font asset
+pub mod font;
 ///Material struct
 pub mod material;
 ///Contains implemented materials
 pub mod materials;
 ///Mesh asset
 pub mod mesh;
+pub mod parameters;
+pub mod preprocessor;
+///Shader compilation with error reporting
+pub mod shader;
+///Skeleton asset
+pub mod skeleton;
 #[cfg(test)]
 mod tests;
 ///Texture asset
 pub mod texture;
 
+pub use font::Font;
 pub use material::Material;
+pub use materials::ShaderMaterial;
 pub use mesh::Mesh;
+pub use parameters::{ParameterValue, Parameters};
+pub use preprocessor::{Preprocessor, ShaderInclude};
+pub use skeleton::Skeleton;
 pub use texture::Texture;
 
 #[derive(Clone, Copy)]
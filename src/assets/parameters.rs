@@ -0,0 +1,544 @@
+//! Typed uniform parameters of materials
+//!
+//! A material declares its parameters once, they are laid out in a uniform buffer following the
+//! WGSL alignment rules, with the textures bound after it. The values can then be changed at any
+//! time with [`Material::set_parameter`], the buffer is written and the bind group recreated
+//! before the material is used next.
+//!
+//!```no_run
+//! # use lunar_engine::{asset_managment::AssetStore, assets::materials::ColorUnlit, structures::Color};
+//! # let mut assets = AssetStore::new();
+//! let id = assets.register(ColorUnlit::new(Color::rgb(1.0, 0.0, 0.0)));
+//! let material = assets.get_by_id::<lunar_engine::assets::Material>(id).unwrap();
+//! material
+//!     .borrow_mut()
+//!     .set_parameter("color", Color::rgb(0.0, 1.0, 0.0))
+//!     .unwrap();
+//!```
+//!
+//! Custom materials store a [`Parameters`] block, return it from
+//! [`MaterialTrait::parameters`](super::material::MaterialTrait::parameters), create their
+//! pipeline layout with [`Parameters::bind_group_layout`] and bind it with [`Parameters::bind`].
+//! [`Parameters::wgsl_declaration`] returns the matching declarations for the shader.
+//!
+//! [`Material::set_parameter`]: super::Material::set_parameter
+use std::{num::NonZeroU64, sync::Arc};
+
+use crate::{
+    asset_managment::{self, AssetStore, UUID},
+    math::{Vec2, Vec3, Vec4},
+    structures::Color,
+    DEVICE, QUEUE,
+};
+
+use super::Texture;
+
+///Type of a material parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterType {
+    ///`f32`
+    Float,
+    ///`vec2<f32>`
+    Vec2,
+    ///`vec3<f32>`
+    Vec3,
+    ///`vec4<f32>`
+    Vec4,
+    ///`vec4<f32>`
+    Color,
+    ///`texture_2d<f32>` with a sampler
+    Texture,
+}
+
+impl ParameterType {
+    //Alignment and size in the uniform buffer
+    const fn layout(self) -> Option<(u64, u64)> {
+        match self {
+            Self::Float => Some((4, 4)),
+            Self::Vec2 => Some((8, 8)),
+            Self::Vec3 => Some((16, 12)),
+            Self::Vec4 | Self::Color => Some((16, 16)),
+            Self::Texture => None,
+        }
+    }
+
+    const fn wgsl(self) -> &'static str {
+        match self {
+            Self::Float => "f32",
+            Self::Vec2 => "vec2<f32>",
+            Self::Vec3 => "vec3<f32>",
+            Self::Vec4 | Self::Color => "vec4<f32>",
+            Self::Texture => "texture_2d<f32>",
+        }
+    }
+}
+
+///Value of a material parameter
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParameterValue {
+    ///Single float
+    Float(f32),
+    ///2 component vector
+    Vec2(Vec2),
+    ///3 component vector
+    Vec3(Vec3),
+    ///4 component vector
+    Vec4(Vec4),
+    ///Color
+    Color(Color),
+    ///Id of a [`Texture`] asset
+    Texture(UUID),
+}
+
+impl ParameterValue {
+    ///Returns the type of the value
+    #[must_use]
+    pub const fn get_type(&self) -> ParameterType {
+        match self {
+            Self::Float(_) => ParameterType::Float,
+            Self::Vec2(_) => ParameterType::Vec2,
+            Self::Vec3(_) => ParameterType::Vec3,
+            Self::Vec4(_) => ParameterType::Vec4,
+            Self::Color(_) => ParameterType::Color,
+            Self::Texture(_) => ParameterType::Texture,
+        }
+    }
+
+    //Bytes of the value in the uniform buffer
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Self::Float(v) => bytemuck::bytes_of(v),
+            Self::Vec2(v) => bytemuck::bytes_of(v),
+            Self::Vec3(v) => bytemuck::bytes_of(v),
+            Self::Vec4(v) => bytemuck::bytes_of(v),
+            Self::Color(v) => bytemuck::bytes_of(v),
+            Self::Texture(_) => &[],
+        }
+    }
+}
+
+impl From<f32> for ParameterValue {
+    fn from(value: f32) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<Vec2> for ParameterValue {
+    fn from(value: Vec2) -> Self {
+        Self::Vec2(value)
+    }
+}
+
+impl From<Vec3> for ParameterValue {
+    fn from(value: Vec3) -> Self {
+        Self::Vec3(value)
+    }
+}
+
+impl From<Vec4> for ParameterValue {
+    fn from(value: Vec4) -> Self {
+        Self::Vec4(value)
+    }
+}
+
+impl From<Color> for ParameterValue {
+    fn from(value: Color) -> Self {
+        Self::Color(value)
+    }
+}
+
+///Error while setting a material parameter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParameterError {
+    ///The material has no parameter with the name
+    Unknown(String),
+    ///The value has a different type than the parameter
+    WrongType {
+        ///Name of the parameter
+        name: String,
+        ///Type of the parameter
+        expected: ParameterType,
+        ///Type of the value
+        found: ParameterType,
+    },
+}
+
+impl std::fmt::Display for ParameterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unknown(name) => write!(f, "The material has no parameter \"{name}\""),
+            Self::WrongType {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Parameter \"{name}\" is of type {expected:?}, but the value is {found:?}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParameterError {}
+
+struct Parameter {
+    name: String,
+    value: ParameterValue,
+    //Offset in the uniform buffer, or the binding of the texture
+    offset: u64,
+}
+
+///Parameters of a material, stored in a uniform buffer and a bind group
+///
+///The uniform buffer is bound at binding 0, if there are any non texture parameters. The textures
+///are bound from binding 1 in the order of declaration, each followed by its sampler.
+pub struct Parameters {
+    parameters: Vec<Parameter>,
+    size: u64,
+    //Values changed since the buffer was written
+    values_changed: bool,
+    //Textures changed since the bind group was created
+    textures_changed: bool,
+    #[cfg(target_arch = "wasm32")]
+    layout: Option<crate::wrappers::WgpuWrapper<wgpu::BindGroupLayout>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    layout: Option<wgpu::BindGroupLayout>,
+    #[cfg(target_arch = "wasm32")]
+    buffer: Option<crate::wrappers::WgpuWrapper<wgpu::Buffer>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    buffer: Option<wgpu::Buffer>,
+    #[cfg(target_arch = "wasm32")]
+    bind_group: Option<Arc<crate::wrappers::WgpuWrapper<wgpu::BindGroup>>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    bind_group: Option<Arc<wgpu::BindGroup>>,
+}
+
+impl Default for Parameters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parameters {
+    ///Creates an empty parameter block
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            parameters: Vec::new(),
+            size: 0,
+            values_changed: true,
+            textures_changed: true,
+            layout: None,
+            buffer: None,
+            bind_group: None,
+        }
+    }
+
+    ///Declares a parameter with its initial value, the name must be a valid WGSL identifier
+    ///
+    ///# Panics
+    ///Panics if a parameter with the same name was already declared
+    #[must_use]
+    pub fn with(mut self, name: &str, value: impl Into<ParameterValue>) -> Self {
+        assert!(
+            self.parameters.iter().all(|p| p.name != name),
+            "Parameter \"{name}\" is already declared"
+        );
+        let value = value.into();
+
+        let offset = if let Some((align, size)) = value.get_type().layout() {
+            let offset = self.size.next_multiple_of(align);
+            self.size = offset + size;
+            offset
+        } else {
+            1 + 2 * self.textures().count() as u64
+        };
+        self.parameters.push(Parameter {
+            name: name.to_owned(),
+            value,
+            offset,
+        });
+        self
+    }
+
+    fn textures(&self) -> impl Iterator<Item = &Parameter> {
+        self.parameters
+            .iter()
+            .filter(|p| p.value.get_type() == ParameterType::Texture)
+    }
+
+    fn uniforms(&self) -> impl Iterator<Item = &Parameter> {
+        self.parameters
+            .iter()
+            .filter(|p| p.value.get_type() != ParameterType::Texture)
+    }
+
+    ///Returns the value of the parameter, `None` if it's not declared
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<ParameterValue> {
+        self.parameters
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| p.value)
+    }
+
+    ///Sets the value of the parameter, the bind group is updated before it's used next
+    ///
+    ///# Errors
+    ///Returns an error if the parameter is not declared or has a different type
+    pub fn set(
+        &mut self,
+        name: &str,
+        value: impl Into<ParameterValue>,
+    ) -> Result<(), ParameterError> {
+        let value = value.into();
+        let parameter = self
+            .parameters
+            .iter_mut()
+            .find(|p| p.name == name)
+            .ok_or_else(|| ParameterError::Unknown(name.to_owned()))?;
+
+        if parameter.value.get_type() != value.get_type() {
+            return Err(ParameterError::WrongType {
+                name: name.to_owned(),
+                expected: parameter.value.get_type(),
+                found: value.get_type(),
+            });
+        }
+        if parameter.value != value {
+            parameter.value = value;
+            if value.get_type() == ParameterType::Texture {
+                self.textures_changed = true;
+            } else {
+                self.values_changed = true;
+            }
+        }
+        Ok(())
+    }
+
+    ///Returns the size of the uniform buffer, 0 if there are only textures
+    #[must_use]
+    pub fn uniform_size(&self) -> u64 {
+        self.size.next_multiple_of(16)
+    }
+
+    ///Returns the contents of the uniform buffer
+    #[must_use]
+    pub fn uniform_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0; self.uniform_size() as usize];
+        for p in self.uniforms() {
+            let value = p.value.bytes();
+            let offset = p.offset as usize;
+            bytes[offset..offset + value.len()].copy_from_slice(value);
+        }
+        bytes
+    }
+
+    ///Returns the WGSL declarations of the parameters in the bind group `group`, the uniforms
+    ///are fields of the `parameters` variable, the textures are variables named after them,
+    ///followed by `<name>_sampler`
+    #[must_use]
+    pub fn wgsl_declaration(&self, group: u32) -> String {
+        let mut wgsl = String::new();
+        if self.size != 0 {
+            wgsl.push_str("struct Parameters {\n");
+            for p in self.uniforms() {
+                wgsl.push_str(&format!("    {}: {},\n", p.name, p.value.get_type().wgsl()));
+            }
+            wgsl.push_str(&format!(
+                "}}\n\n@group({group}) @binding(0)\nvar<uniform> parameters: Parameters;\n"
+            ));
+        }
+        for p in self.textures() {
+            wgsl.push_str(&format!(
+                "@group({group}) @binding({})\nvar {}: texture_2d<f32>;\n@group({group}) @binding({})\nvar {}_sampler: sampler;\n",
+                p.offset,
+                p.name,
+                p.offset + 1,
+                p.name
+            ));
+        }
+        wgsl
+    }
+
+    ///Returns the layout of the bind group, creating it if needed
+    pub fn bind_group_layout(&mut self) -> &wgpu::BindGroupLayout {
+        if self.layout.is_none() {
+            let visibility = wgpu::ShaderStages::VERTEX_FRAGMENT;
+            let mut entries = Vec::new();
+            if self.size != 0 {
+                entries.push(wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: NonZeroU64::new(self.uniform_size()),
+                    },
+                    count: None,
+                });
+            }
+            for p in self.textures() {
+                entries.push(wgpu::BindGroupLayoutEntry {
+                    binding: p.offset as u32,
+                    visibility,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                });
+                entries.push(wgpu::BindGroupLayoutEntry {
+                    binding: p.offset as u32 + 1,
+                    visibility,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                });
+            }
+
+            let layout = DEVICE.get().unwrap().create_bind_group_layout(
+                &wgpu::BindGroupLayoutDescriptor {
+                    label: Some("Material parameters layout"),
+                    entries: &entries,
+                },
+            );
+            #[cfg(target_arch = "wasm32")]
+            {
+                self.layout = Some(crate::wrappers::WgpuWrapper::new(layout));
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                self.layout = Some(layout);
+            }
+        }
+        self.layout.as_ref().unwrap()
+    }
+
+    ///Returns `true` if the parameters changed since the bind group was last updated
+    #[must_use]
+    pub const fn is_outdated(&self) -> bool {
+        self.values_changed || self.textures_changed
+    }
+
+    ///Writes the changed values into the uniform buffer and recreates the bind group if a texture
+    ///changed
+    ///
+    ///# Errors
+    ///Returns an error if one of the textures does not exist
+    pub fn update(&mut self, assets: &AssetStore) -> Result<(), asset_managment::Error> {
+        let device = DEVICE.get().unwrap();
+
+        if self.size != 0 && self.buffer.is_none() {
+            let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Material parameters"),
+                size: self.uniform_size(),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            #[cfg(target_arch = "wasm32")]
+            {
+                self.buffer = Some(crate::wrappers::WgpuWrapper::new(buffer));
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                self.buffer = Some(buffer);
+            }
+            self.values_changed = true;
+            self.textures_changed = true;
+        }
+        if self.values_changed {
+            if let Some(buffer) = &self.buffer {
+                QUEUE
+                    .get()
+                    .unwrap()
+                    .write_buffer(buffer, 0, &self.uniform_bytes());
+            }
+            self.values_changed = false;
+        }
+        if !self.textures_changed && self.bind_group.is_some() {
+            return Ok(());
+        }
+
+        let textures = self
+            .textures()
+            .map(|p| match p.value {
+                ParameterValue::Texture(id) => assets
+                    .get_by_id::<Texture>(id)
+                    .map(|t| (p.offset as u32, t)),
+                _ => unreachable!(),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let textures = textures
+            .iter()
+            .map(|(binding, t)| (*binding, t.borrow()))
+            .collect::<Vec<_>>();
+        let views = textures
+            .iter()
+            .map(|(_, t)| {
+                t.texture
+                    .as_ref()
+                    .unwrap()
+                    .create_view(&wgpu::TextureViewDescriptor::default())
+            })
+            .collect::<Vec<_>>();
+
+        self.bind_group_layout();
+        let mut entries = Vec::new();
+        if let Some(buffer) = &self.buffer {
+            entries.push(wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            });
+        }
+        for ((binding, texture), view) in textures.iter().zip(&views) {
+            entries.push(wgpu::BindGroupEntry {
+                binding: *binding,
+                resource: wgpu::BindingResource::TextureView(view),
+            });
+            entries.push(wgpu::BindGroupEntry {
+                binding: binding + 1,
+                resource: wgpu::BindingResource::Sampler(texture.sampler.as_ref().unwrap()),
+            });
+        }
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Material parameters bind group"),
+            layout: self.layout.as_ref().unwrap(),
+            entries: &entries,
+        });
+        drop(entries);
+        drop(textures);
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.bind_group = Some(Arc::new(crate::wrappers::WgpuWrapper::new(bind_group)));
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.bind_group = Some(Arc::new(bind_group));
+        }
+        self.textures_changed = false;
+        Ok(())
+    }
+
+    ///Sets the bind group of the parameters at `index`, must be called after [`Self::update`]
+    pub fn bind(&self, render_pass: &mut wgpu::RenderPass, index: u32) {
+        //Kept alive until the bind group is recreated, which only happens between the passes
+        let b = unsafe {
+            Arc::as_ptr(self.bind_group.as_ref().unwrap())
+                .as_ref()
+                .unwrap()
+        };
+        render_pass.set_bind_group(index, b, &[]);
+    }
+
+    ///Releases the gpu resources of the parameters, they are recreated by [`Self::update`]
+    pub fn dispose(&mut self) {
+        self.layout = None;
+        self.buffer = None;
+        self.bind_group = None;
+        self.values_changed = true;
+        self.textures_changed = true;
+    }
+}
@@ -0,0 +1,327 @@
+//! Preprocessing of WGSL shaders
+//!
+//! Shaders can share declarations with `#include "file.wgsl"` and produce multiple variants from
+//! one file with conditional compilation. Directives must be the only thing on their line:
+//!- `#include "name"` inserts the file, every file is included at most once
+//!- `#define NAME` defines a flag for the rest of the shader
+//!- `#ifdef NAME` and `#ifndef NAME` keep the lines until the matching `#else` or `#endif` only
+//!  if the flag is defined or not
+//!
+//! Included files are looked up in the files added with [`Preprocessor::with_include`], the
+//! [`ShaderInclude`] assets of the store and the files provided by the engine, in that order.
+//! The engine provides `common.wgsl`, with the input and output of the vertex shader and the
+//! camera, see [`ShaderMaterial`](super::ShaderMaterial).
+//!
+//!```no_run
+//! # use lunar_engine::assets::Preprocessor;
+//! let (source, map) = Preprocessor::new()
+//!     .with_define("SKINNED")
+//!     .process(
+//!         "shader.wgsl",
+//!         "#include \"common.wgsl\"
+//!          #ifdef SKINNED
+//!          @group(3) @binding(0) var<uniform> joints: array<mat4x4<f32>, 128>;
+//!          #endif",
+//!     )
+//!     .unwrap();
+//!```
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use lunar_engine_derive::as_any;
+
+use crate::{
+    asset_managment::{Asset, AssetStore, UUID},
+    errors,
+};
+
+use super::shader::{snippet, SourceMap};
+
+//Files provided by the engine
+const BUILTIN: [(&str, &str); 1] = [("common.wgsl", include_str!("../shaders/common.wgsl"))];
+
+///WGSL source that can be included by shaders, see [`Preprocessor::with_assets`]
+pub struct ShaderInclude {
+    id: Option<UUID>,
+    name: String,
+    source: String,
+}
+
+impl ShaderInclude {
+    ///Creates an include with the `name` used in the `#include` directives
+    #[must_use]
+    pub fn new(name: &str, source: &str) -> Self {
+        Self {
+            id: None,
+            name: name.to_owned(),
+            source: source.to_owned(),
+        }
+    }
+
+    ///Reads the include from a file, it's named after the file
+    ///
+    ///# Errors
+    ///Returns an error if the file can't be read
+    pub fn from_file(path: &Path) -> std::io::Result<Self> {
+        let name = path
+            .file_name()
+            .map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into());
+        Ok(Self::new(&name, &std::fs::read_to_string(path)?))
+    }
+
+    ///Returns the name of the include
+    #[must_use]
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    ///Returns the source of the include
+    #[must_use]
+    pub fn get_source(&self) -> &str {
+        &self.source
+    }
+}
+
+impl Asset for ShaderInclude {
+    #[as_any]
+
+    fn get_id(&self) -> UUID {
+        self.id.unwrap()
+    }
+
+    fn initialize(&mut self) -> Result<(), Box<dyn std::error::Error + Send>> {
+        Ok(())
+    }
+
+    fn dispose(&mut self) {}
+
+    fn set_id(&mut self, id: UUID) -> Result<(), crate::asset_managment::Error> {
+        if self.id.is_some() {
+            Err(crate::asset_managment::Error::IdAlreadySet)
+        } else {
+            self.id = Some(id);
+            Ok(())
+        }
+    }
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+
+///Resolves the directives of WGSL shaders
+#[derive(Debug, Clone, Default)]
+pub struct Preprocessor {
+    defines: HashSet<String>,
+    includes: HashMap<String, String>,
+    prelude: Vec<String>,
+}
+
+//Conditional block being processed
+struct Condition {
+    active: bool,
+    has_else: bool,
+}
+
+//State shared by the processed file and the files it includes
+struct State {
+    defines: HashSet<String>,
+    included: HashSet<String>,
+    source: String,
+    map: SourceMap,
+}
+
+impl Preprocessor {
+    ///Creates a preprocessor without any flags
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///Defines the flag for the processed shaders
+    #[must_use]
+    pub fn with_define(mut self, name: &str) -> Self {
+        self.defines.insert(name.to_owned());
+        self
+    }
+
+    ///Defines all the flags
+    #[must_use]
+    pub fn with_defines<S: AsRef<str>>(mut self, names: &[S]) -> Self {
+        self.defines.extend(names.iter().map(|n| n.as_ref().to_owned()));
+        self
+    }
+
+    ///Adds a file that can be included by the processed shaders
+    #[must_use]
+    pub fn with_include(mut self, name: &str, source: &str) -> Self {
+        self.includes.insert(name.to_owned(), source.to_owned());
+        self
+    }
+
+    ///Adds all the [`ShaderInclude`] assets of the store, the files added with
+    ///[`Self::with_include`] take precedence
+    #[must_use]
+    pub fn with_assets(mut self, assets: &AssetStore) -> Self {
+        for include in assets.get_all_by_type::<ShaderInclude>().unwrap_or_default() {
+            let include = include.borrow();
+            self.includes
+                .entry(include.name.clone())
+                .or_insert_with(|| include.source.clone());
+        }
+        self
+    }
+
+    ///Includes the file before the source of the processed shaders
+    #[must_use]
+    pub fn with_prelude(mut self, name: &str) -> Self {
+        self.prelude.push(name.to_owned());
+        self
+    }
+
+    ///Processes the shader, returns the resulting source and the map of its lines to the files
+    ///they originate from, which can be passed to [`super::shader::compile_mapped`]
+    ///
+    ///# Errors
+    ///Returns an error if a directive is invalid, an included file does not exist or a
+    ///conditional block is not closed
+    pub fn process(&self, file: &str, source: &str) -> Result<(String, SourceMap), errors::Error> {
+        let mut state = State {
+            defines: self.defines.clone(),
+            included: HashSet::new(),
+            source: String::new(),
+            map: SourceMap::new(),
+        };
+
+        for name in &self.prelude {
+            if !self.include(&mut state, name)? {
+                return Err(error(file, "", 0, format!("Unknown include \"{name}\"")));
+            }
+        }
+        self.process_file(&mut state, file, source)?;
+
+        Ok((state.source, state.map))
+    }
+
+    fn resolve(&self, name: &str) -> Option<&str> {
+        self.includes
+            .get(name)
+            .map(String::as_str)
+            .or_else(|| BUILTIN.iter().find(|(n, _)| *n == name).map(|(_, s)| *s))
+    }
+
+    //Returns `false` if the file does not exist
+    fn include(&self, state: &mut State, name: &str) -> Result<bool, errors::Error> {
+        if state.included.contains(name) {
+            return Ok(true);
+        }
+        let Some(source) = self.resolve(name) else {
+            return Ok(false);
+        };
+        state.included.insert(name.to_owned());
+
+        self.process_file(state, name, source)?;
+        Ok(true)
+    }
+
+    fn process_file(
+        &self,
+        state: &mut State,
+        file: &str,
+        source: &str,
+    ) -> Result<(), errors::Error> {
+        let mut conditions = Vec::<Condition>::new();
+        let mut last = 0;
+
+        for (i, text) in source.lines().enumerate() {
+            let line = i as u32 + 1;
+            last = line;
+            let active = conditions.iter().all(|c| c.active);
+
+            let Some(directive) = text.trim().strip_prefix('#') else {
+                if active {
+                    state.source.push_str(text);
+                    state.source.push('\n');
+                    state.map.push(file, line);
+                }
+                continue;
+            };
+            let error = |message: String| error(file, source, line, message);
+
+            let (name, argument) = directive
+                .split_once(char::is_whitespace)
+                .map_or((directive, ""), |(n, a)| (n, a.trim()));
+            let flag = || {
+                if argument.is_empty() || argument.contains(char::is_whitespace) {
+                    Err(error(format!("Expected a flag after #{name}")))
+                } else {
+                    Ok(argument)
+                }
+            };
+
+            match name {
+                "ifdef" | "ifndef" => {
+                    let defined = state.defines.contains(flag()?);
+                    conditions.push(Condition {
+                        active: defined == (name == "ifdef"),
+                        has_else: false,
+                    });
+                }
+                "else" => {
+                    let condition = conditions
+                        .last_mut()
+                        .ok_or_else(|| error("#else without #ifdef".to_owned()))?;
+                    if condition.has_else {
+                        return Err(error("Duplicate #else".to_owned()));
+                    }
+                    condition.active = !condition.active;
+                    condition.has_else = true;
+                }
+                "endif" => {
+                    conditions
+                        .pop()
+                        .ok_or_else(|| error("#endif without #ifdef".to_owned()))?;
+                }
+                //The directives of skipped blocks are ignored
+                _ if !active => {}
+                "define" => {
+                    state.defines.insert(flag()?.to_owned());
+                }
+                "include" => {
+                    let name = argument
+                        .strip_prefix('"')
+                        .and_then(|a| a.strip_suffix('"'))
+                        .ok_or_else(|| error("Expected a quoted file name".to_owned()))?;
+                    if !self.include(state, name)? {
+                        return Err(error(format!("Unknown include \"{name}\"")));
+                    }
+                }
+                _ => return Err(error(format!("Unknown directive #{name}"))),
+            }
+        }
+
+        if conditions.is_empty() {
+            Ok(())
+        } else {
+            Err(error(file, source, last, "Missing #endif".to_owned()))
+        }
+    }
+}
+
+//Creates the error at the `line` of the file
+fn error(file: &str, source: &str, line: u32, message: String) -> errors::Error {
+    let length = source
+        .lines()
+        .nth(line.saturating_sub(1) as usize)
+        .map_or(0, |l| l.trim_end().len() as u32);
+
+    errors::Error::ShaderCompilation {
+        file: file.to_owned(),
+        line,
+        column: 1,
+        message,
+        snippet: snippet(source, line, line, 1, length),
+    }
+}
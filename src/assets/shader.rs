@@ -0,0 +1,158 @@
+use crate::{errors, DEVICE};
+
+///Maps lines of a processed shader back to the files and lines they originate from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceMap {
+    //Index is the line of the processed source, value is (file, line in that file)
+    lines: Vec<(String, u32)>,
+}
+
+impl SourceMap {
+    ///Creates a map where every line of `source` maps to the same line in `file`
+    #[must_use]
+    pub fn identity(file: &str, source: &str) -> Self {
+        Self {
+            lines: (1..=source.lines().count() as u32)
+                .map(|l| (file.to_owned(), l))
+                .collect(),
+        }
+    }
+
+    ///Creates an empty map
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { lines: Vec::new() }
+    }
+
+    ///Adds the next line of the processed source, which originates from `line` of `file`
+    pub fn push(&mut self, file: &str, line: u32) {
+        self.lines.push((file.to_owned(), line));
+    }
+
+    ///Returns the file and the line in that file the `line` of the processed source originates
+    ///from
+    ///
+    ///Lines start from 1, returns `None` if the line is not in the map
+    #[must_use]
+    pub fn locate(&self, line: u32) -> Option<(&str, u32)> {
+        self.lines
+            .get((line as usize).checked_sub(1)?)
+            .map(|(f, l)| (f.as_str(), *l))
+    }
+}
+
+impl Default for SourceMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+///Returns the `line` of the source with the error highlighted, labeled with `file_line`
+pub(crate) fn snippet(source: &str, line: u32, file_line: u32, column: u32, length: u32) -> String {
+    let Some(text) = source.lines().nth(line.saturating_sub(1) as usize) else {
+        return String::new();
+    };
+    let number = file_line.to_string();
+    let padding = " ".repeat(number.len());
+
+    format!(
+        "{number} | {text}\n{padding} | {}{}",
+        " ".repeat(column.saturating_sub(1) as usize),
+        "^".repeat(length.max(1) as usize)
+    )
+}
+
+///Creates the compilation error at `location` of the processed source
+fn located(
+    source: &str,
+    map: &SourceMap,
+    location: Option<naga::SourceLocation>,
+    message: &str,
+) -> errors::Error {
+    let label = map.locate(1).map_or("shader", |i| i.0);
+    let (line, column, length) =
+        location.map_or((0, 0, 0), |l| (l.line_number, l.line_position, l.length));
+    let (file, file_line) = map.locate(line).unwrap_or((label, line));
+
+    errors::Error::ShaderCompilation {
+        file: file.to_owned(),
+        line: file_line,
+        column,
+        message: message.to_owned(),
+        snippet: snippet(source, line, file_line, column, length),
+    }
+}
+
+///Parses and validates a WGSL shader that was produced from multiple files, without the gpu
+///
+///Unlike [`compile_mapped`] the errors are only returned, not reported
+///
+///# Errors
+///Returns the first parsing or validation error
+pub fn validate_mapped(source: &str, map: &SourceMap) -> Result<naga::Module, errors::Error> {
+    let module = naga::front::wgsl::parse_str(source)
+        .map_err(|e| located(source, map, e.location(source), e.message()))?;
+
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    )
+    .validate(&module)
+    .map_err(|e| located(source, map, e.location(source), &e.as_inner().to_string()))?;
+
+    Ok(module)
+}
+
+///Compiles a WGSL shader
+///
+///Compilation errors are reported via [`crate::errors`] with the location of the error in the
+///original file
+///
+///# Errors
+///Returns the first compilation error if the shader failed to compile
+pub fn compile(file: &str, source: &str) -> Result<wgpu::ShaderModule, errors::Error> {
+    compile_mapped(source, &SourceMap::identity(file, source))
+}
+
+///Compiles a WGSL shader that was produced from multiple files, using `map` to locate the errors
+///
+///# Errors
+///Returns the first compilation error if the shader failed to compile
+pub fn compile_mapped(source: &str, map: &SourceMap) -> Result<wgpu::ShaderModule, errors::Error> {
+    let device = DEVICE.get().unwrap();
+    let label = map.locate(1).map_or("shader", |i| i.0);
+
+    //The compilation messages are not exposed by wgpu, so the source is parsed first to locate
+    //the errors
+    if let Err(e) = naga::front::wgsl::parse_str(source) {
+        let e = located(source, map, e.location(source), e.message());
+        errors::report(e.clone());
+        return Err(e);
+    }
+
+    let (module, error) = errors::capture(|| {
+        device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        })
+    });
+
+    //Failed for a reason other than compilation
+    if let Some(e) = error {
+        let e = errors::Error::from_wgpu(label, &e);
+        errors::report(e.clone());
+        return Err(e);
+    }
+
+    Ok(module)
+}
+
+///Compiles the vertex shader of the engine materials, the `skinned` variant transforms the
+///vertices by the joints of the armature
+pub(crate) fn compile_vertex(skinned: bool) -> Result<wgpu::ShaderModule, errors::Error> {
+    let defines: &[&str] = if skinned { &["SKINNED"] } else { &[] };
+    let (source, map) = super::Preprocessor::new()
+        .with_defines(defines)
+        .process("vertex.wgsl", include_str!("../shaders/vertex.wgsl"))?;
+    compile_mapped(&source, &map)
+}
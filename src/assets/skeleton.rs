@@ -0,0 +1,175 @@
+use std::sync::Arc;
+
+use lunar_engine_derive::as_any;
+
+use crate::{
+    asset_managment::{Asset, AssetStore, UUID},
+    components::animator::{AnimationClip, Armature},
+};
+
+///Joint hierarchy and inverse bind matrices shared by skinned meshes and their animation clips
+///
+///Meshes using the skeleton reference it with [`Mesh::set_skeleton`](super::Mesh::set_skeleton),
+///clips with [`AnimationClip::skeleton`], so the joint indices of the meshes and the channels of
+///the clips are known to index the same joints
+pub struct Skeleton {
+    id: Option<UUID>,
+    name: String,
+    armature: Arc<Armature>,
+}
+
+///Reason an animation clip can't be played on a skeleton
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ClipError {
+    ///The clip is not bound to a skeleton
+    Unbound,
+    ///The skeleton of the clip is not registered in the asset store
+    UnknownSkeleton(UUID),
+    ///The clip is bound to a different skeleton
+    WrongSkeleton {
+        ///Id of the skeleton the clip was checked against
+        expected: UUID,
+        ///Id of the skeleton of the clip
+        found: UUID,
+    },
+    ///A channel of the clip targets a joint the skeleton does not have
+    UnknownJoint {
+        ///Index of the channel in the clip
+        channel: usize,
+        ///Index of the joint
+        joint: usize,
+    },
+}
+
+impl std::fmt::Display for ClipError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unbound => write!(f, "The clip is not bound to a skeleton"),
+            Self::UnknownSkeleton(id) => {
+                write!(f, "Skeleton {id} is not registered in the asset store")
+            }
+            Self::WrongSkeleton { expected, found } => write!(
+                f,
+                "The clip targets skeleton {found}, but is played on skeleton {expected}"
+            ),
+            Self::UnknownJoint { channel, joint } => write!(
+                f,
+                "Channel {channel} targets joint {joint}, which does not exist in the skeleton"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ClipError {}
+
+impl Skeleton {
+    ///Creates a new skeleton from the armature
+    #[must_use]
+    pub fn new(name: &str, armature: Arc<Armature>) -> Self {
+        Self {
+            id: None,
+            name: name.to_owned(),
+            armature,
+        }
+    }
+
+    ///Returns the name of the skeleton
+    #[must_use]
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    ///Returns the armature of the skeleton, shared with the
+    ///[`Animator`](crate::components::animator::Animator)s animating it
+    #[must_use]
+    pub fn get_armature(&self) -> Arc<Armature> {
+        self.armature.clone()
+    }
+
+    ///Binds the clip to the skeleton
+    ///
+    ///# Errors
+    ///Returns an error if the clip targets joints the skeleton does not have, the clip is not
+    ///modified in that case
+    pub fn bind_clip(&self, clip: &mut AnimationClip) -> Result<(), ClipError> {
+        self.validate_joints(clip)?;
+        clip.skeleton = Some(self.get_id());
+        Ok(())
+    }
+
+    ///Checks that the clip is bound to the skeleton and only targets its joints
+    ///
+    ///# Errors
+    ///Returns the first problem found
+    pub fn validate_clip(&self, clip: &AnimationClip) -> Result<(), ClipError> {
+        match clip.skeleton {
+            None => return Err(ClipError::Unbound),
+            Some(found) if found != self.get_id() => {
+                return Err(ClipError::WrongSkeleton {
+                    expected: self.get_id(),
+                    found,
+                })
+            }
+            Some(_) => {}
+        }
+        self.validate_joints(clip)
+    }
+
+    fn validate_joints(&self, clip: &AnimationClip) -> Result<(), ClipError> {
+        let joints = self.armature.joints().len();
+        clip.channels
+            .iter()
+            .enumerate()
+            .find(|(_, c)| c.joint >= joints)
+            .map_or(Ok(()), |(channel, c)| {
+                Err(ClipError::UnknownJoint {
+                    channel,
+                    joint: c.joint,
+                })
+            })
+    }
+}
+
+///Checks that the skeleton the clip is bound to exists in the store and has all the joints the
+///clip targets
+///
+///# Errors
+///Returns the first problem found
+pub fn validate_clip(clip: &AnimationClip, assets: &AssetStore) -> Result<(), ClipError> {
+    let id = clip.skeleton.ok_or(ClipError::Unbound)?;
+    if !assets.contains_type::<Skeleton>(id) {
+        return Err(ClipError::UnknownSkeleton(id));
+    }
+    let skeleton = assets
+        .get_by_id::<Skeleton>(id)
+        .map_err(|_| ClipError::UnknownSkeleton(id))?;
+    let result = skeleton.borrow().validate_clip(clip);
+    result
+}
+
+impl Asset for Skeleton {
+    #[as_any]
+
+    fn get_id(&self) -> UUID {
+        self.id.unwrap()
+    }
+
+    fn initialize(&mut self) -> Result<(), Box<dyn std::error::Error + Send>> {
+        Ok(())
+    }
+
+    fn dispose(&mut self) {}
+
+    fn set_id(&mut self, id: UUID) -> Result<(), crate::asset_managment::Error> {
+        if self.id.is_some() {
+            Err(crate::asset_managment::Error::IdAlreadySet)
+        } else {
+            self.id = Some(id);
+            Ok(())
+        }
+    }
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
@@ -25,3 +25,320 @@ fn test_mesh_load() {
     mesh.dispose();
     mesh.initialize().unwrap();
 }
+
+#[test]
+fn test_mesh_geometry_sharing() {
+    use crate::math::Vec3;
+    use std::sync::Arc;
+
+    crate::test_utils::generate_gpu();
+    let dimensions = Vec3::new(1.25, 2.5, 3.75);
+    let mut meshes = [
+        super::Mesh::new_box(dimensions),
+        super::Mesh::new_box(dimensions),
+        super::Mesh::new_box(Vec3::new(3.75, 2.5, 1.25)),
+    ];
+    for (id, mesh) in (1001..).zip(&mut meshes) {
+        mesh.set_id(id).unwrap();
+        mesh.initialize().unwrap();
+    }
+
+    //Identical geometry shares the buffers of the first mesh
+    assert_eq!(meshes[0].get_geometry_id(), 1001);
+    assert_eq!(meshes[1].get_geometry_id(), 1001);
+    assert_eq!(meshes[2].get_geometry_id(), 1003);
+    assert!(Arc::ptr_eq(
+        &meshes[0].get_vertex_buffer(),
+        &meshes[1].get_vertex_buffer()
+    ));
+    assert!(!Arc::ptr_eq(
+        &meshes[0].get_vertex_buffer(),
+        &meshes[2].get_vertex_buffer()
+    ));
+
+    //The buffers are released once all the meshes using them are disposed
+    meshes[0].dispose();
+    meshes[1].dispose();
+    meshes[1].initialize().unwrap();
+    assert_eq!(meshes[1].get_geometry_id(), 1002);
+}
+
+#[test]
+fn test_mesh_bounds() {
+    use crate::math::Vec3;
+
+    crate::test_utils::generate_gpu();
+    let mut mesh = super::Mesh::new_box(Vec3::new(1.0, 2.0, 4.0));
+    mesh.set_id(1).unwrap();
+    mesh.initialize().unwrap();
+
+    let bounds = mesh.get_bounds();
+    assert_eq!(bounds.min, Vec3::new(-0.5, -1.0, -2.0));
+    assert_eq!(bounds.max, Vec3::new(0.5, 1.0, 2.0));
+}
+
+#[test]
+fn test_source_map() {
+    let map = super::shader::SourceMap::identity("test.wgsl", "a\nb\nc");
+    assert_eq!(map.locate(2), Some(("test.wgsl", 2)));
+    assert_eq!(map.locate(0), None);
+    assert_eq!(map.locate(4), None);
+
+    let mut map = super::shader::SourceMap::new();
+    map.push("a.wgsl", 10);
+    map.push("b.wgsl", 1);
+    assert_eq!(map.locate(2), Some(("b.wgsl", 1)));
+}
+
+#[test]
+fn test_shader_snippet() {
+    let source = "fn main() {\n    let a = b;\n}";
+    let snippet = super::shader::snippet(source, 2, 2, 13, 1);
+    assert_eq!(snippet, "2 |     let a = b;\n  |             ^");
+}
+
+#[test]
+fn test_font_signed_distance() {
+    use ab_glyph::{point, OutlineCurve};
+
+    use super::font::{flatten, signed_distance};
+
+    //Counter clockwise square from 0 to 10
+    let corners = [
+        point(0.0, 0.0),
+        point(10.0, 0.0),
+        point(10.0, 10.0),
+        point(0.0, 10.0),
+    ];
+    let curves = (0..4)
+        .map(|i| OutlineCurve::Line(corners[i], corners[(i + 1) % 4]))
+        .collect::<Vec<_>>();
+    let segments = flatten(&curves);
+
+    assert!((signed_distance(&segments, point(5.0, 5.0)) - 5.0).abs() < 0.0001);
+    assert!((signed_distance(&segments, point(5.0, 8.0)) - 2.0).abs() < 0.0001);
+    assert!((signed_distance(&segments, point(13.0, 5.0)) + 3.0).abs() < 0.0001);
+    assert!((signed_distance(&segments, point(-1.0, 12.0)) + 5.0f32.sqrt()).abs() < 0.0001);
+
+    //Curves are split into segments
+    let curve = [OutlineCurve::Quad(
+        point(0.0, 0.0),
+        point(5.0, 10.0),
+        point(10.0, 0.0),
+    )];
+    assert_eq!(flatten(&curve).len(), 8);
+}
+
+#[test]
+fn test_parameters_layout() {
+    use super::parameters::{ParameterError, ParameterType, ParameterValue, Parameters};
+    use crate::math::{Vec2, Vec3};
+
+    let mut parameters = Parameters::new()
+        .with("roughness", 0.5)
+        .with("normal", ParameterValue::Texture(7))
+        .with("offset", Vec2::new(1.0, 2.0))
+        .with("tint", Vec3::new(3.0, 4.0, 5.0));
+
+    //f32 at 0, vec2 aligned to 8, vec3 aligned to 16, rounded up to 16
+    assert_eq!(parameters.uniform_size(), 32);
+    let bytes = parameters.uniform_bytes();
+    let floats = bytes
+        .chunks_exact(4)
+        .map(bytemuck::pod_read_unaligned::<f32>)
+        .collect::<Vec<_>>();
+    assert_eq!(floats, [0.5, 0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 0.0]);
+
+    let wgsl = parameters.wgsl_declaration(1);
+    assert!(wgsl.contains("    offset: vec2<f32>,\n"));
+    assert!(wgsl.contains("@group(1) @binding(1)\nvar normal: texture_2d<f32>;"));
+    assert!(wgsl.contains("@group(1) @binding(2)\nvar normal_sampler: sampler;"));
+
+    parameters.set("roughness", 0.25).unwrap();
+    assert_eq!(
+        parameters.get("roughness"),
+        Some(ParameterValue::Float(0.25))
+    );
+    assert_eq!(
+        parameters.set("metallic", 1.0),
+        Err(ParameterError::Unknown("metallic".to_owned()))
+    );
+    assert_eq!(
+        parameters.set("tint", 1.0),
+        Err(ParameterError::WrongType {
+            name: "tint".to_owned(),
+            expected: ParameterType::Vec3,
+            found: ParameterType::Float,
+        })
+    );
+}
+
+#[test]
+fn test_shader_material() {
+    use super::materials::{ShaderLayout, ShaderMaterial, ShaderMaterialError};
+    use crate::{errors::Error, structures::Color};
+
+    let layout = || ShaderLayout {
+        name: "test.wgsl".to_owned(),
+        parameters: super::Parameters::new().with("tint", Color::rgb(1.0, 0.5, 0.0)),
+        ..Default::default()
+    };
+
+    crate::test_utils::generate_headless();
+    let mut material = ShaderMaterial::new(
+        "@fragment\nfn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {\n    return parameters.tint;\n}",
+        layout(),
+    )
+    .unwrap();
+    material.set_id(1).unwrap();
+    material.initialize().unwrap();
+    material.dispose();
+    material.initialize().unwrap();
+
+    //Errors are located in the user source, not the engine declarations
+    let Err(ShaderMaterialError::Compilation(Error::ShaderCompilation { file, line, .. })) =
+        ShaderMaterial::new(
+            "@fragment\nfn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {\n    return parameters.color;\n}",
+            layout(),
+        )
+    else {
+        panic!("Expected a compilation error");
+    };
+    assert_eq!(file, "test.wgsl");
+    assert_eq!(line, 3);
+
+    assert!(matches!(
+        ShaderMaterial::new(
+            "@fragment\nfn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {\n    return parameters.tint;\n}",
+            ShaderLayout {
+                vertex: true,
+                ..layout()
+            },
+        ),
+        Err(ShaderMaterialError::MissingEntryPoint { name: "vs_main", .. })
+    ));
+}
+
+#[test]
+fn test_preprocessor() {
+    use super::Preprocessor;
+    use crate::errors::Error;
+
+    let source = "#include \"a.wgsl\"\n#include \"b.wgsl\"\n#ifdef SKINNED\nskinned\n#else\nstatic\n#endif\nend";
+    let preprocessor = Preprocessor::new()
+        .with_include("a.wgsl", "a")
+        .with_include("b.wgsl", "#include \"a.wgsl\"\nb");
+
+    //Every file is included once
+    let (processed, map) = preprocessor.process("test.wgsl", source).unwrap();
+    assert_eq!(processed, "a\nb\nstatic\nend\n");
+    assert_eq!(map.locate(1), Some(("a.wgsl", 1)));
+    assert_eq!(map.locate(2), Some(("b.wgsl", 2)));
+    assert_eq!(map.locate(4), Some(("test.wgsl", 8)));
+
+    let (processed, _) = preprocessor
+        .clone()
+        .with_define("SKINNED")
+        .process("test.wgsl", source)
+        .unwrap();
+    assert_eq!(processed, "a\nb\nskinned\nend\n");
+
+    //Flags defined by the shader apply to the rest of it, skipped blocks are ignored
+    let (processed, _) = preprocessor
+        .process("test.wgsl", "#define A\n#ifdef A\nx\n#else\n#pragma\n#endif")
+        .unwrap();
+    assert_eq!(processed, "x\n");
+
+    //Errors are located in the file with the directive
+    let Err(Error::ShaderCompilation { file, line, .. }) =
+        preprocessor.process("test.wgsl", "#include \"b.wgsl\"\n#include \"c.wgsl\"")
+    else {
+        panic!("Expected an unknown include");
+    };
+    assert_eq!(file, "test.wgsl");
+    assert_eq!(line, 2);
+
+    assert!(preprocessor.process("test.wgsl", "#ifdef A\nx").is_err());
+    assert!(preprocessor.process("test.wgsl", "#endif").is_err());
+    assert!(preprocessor.process("test.wgsl", "#pragma once").is_err());
+
+    //The engine declarations can be included by any shader
+    let (processed, _) = Preprocessor::new()
+        .process("test.wgsl", "#include \"common.wgsl\"")
+        .unwrap();
+    assert!(processed.contains("struct VertexInput"));
+}
+
+#[test]
+fn test_skeleton() {
+    use std::sync::Arc;
+
+    use super::{skeleton::ClipError, Skeleton};
+    use crate::{
+        asset_managment::AssetStore,
+        components::animator::{
+            AnimationClip, Armature, Channel, ChannelValues, Interpolation, Joint,
+        },
+        math::{Mat4x4, Quaternion, Vec3},
+    };
+
+    let joint = |name: &str, parent| Joint {
+        name: name.to_owned(),
+        parent,
+        translation: Vec3::new(0.0, 0.0, 0.0),
+        rotation: Quaternion::identity(),
+        scale: Vec3::new(1.0, 1.0, 1.0),
+        inverse_bind: Mat4x4::identity(),
+    };
+    let clip = |joint| {
+        AnimationClip::new(
+            "clip".to_owned(),
+            vec![Channel {
+                joint,
+                times: vec![0.0],
+                values: ChannelValues::Scale(vec![Vec3::new(1.0, 1.0, 1.0)]),
+                interpolation: Interpolation::Step,
+            }],
+        )
+    };
+
+    let mut assets = AssetStore::new();
+    let armature = Arc::new(Armature::new(vec![joint("root", None), joint("arm", Some(0))]));
+    let id = assets.register(Skeleton::new("skeleton", armature));
+    let other = assets.register(Skeleton::new("other", Arc::new(Armature::default())));
+    //Validating a clip looks the skeleton up again, so it can't stay borrowed
+    let skeleton = assets.get_by_id::<Skeleton>(id).unwrap();
+
+    let mut valid = clip(1);
+    assert_eq!(super::skeleton::validate_clip(&valid, &assets), Err(ClipError::Unbound));
+    skeleton.borrow().bind_clip(&mut valid).unwrap();
+    assert_eq!(valid.skeleton, Some(id));
+    assert_eq!(super::skeleton::validate_clip(&valid, &assets), Ok(()));
+
+    //Clips are only bound if the skeleton has all their joints
+    let mut invalid = clip(2);
+    assert_eq!(
+        skeleton.borrow().bind_clip(&mut invalid),
+        Err(ClipError::UnknownJoint {
+            channel: 0,
+            joint: 2
+        })
+    );
+    assert_eq!(invalid.skeleton, None);
+
+    let other = assets.get_by_id::<Skeleton>(other).unwrap();
+    let other_id = other.borrow().get_id();
+    assert_eq!(
+        other.borrow().validate_clip(&valid),
+        Err(ClipError::WrongSkeleton {
+            expected: other_id,
+            found: id
+        })
+    );
+
+    valid.skeleton = Some(10);
+    assert_eq!(
+        super::skeleton::validate_clip(&valid, &assets),
+        Err(ClipError::UnknownSkeleton(10))
+    );
+}
@@ -24,6 +24,8 @@ pub struct Texture {
     sample_count: u8,
     adress_mode: wgpu::AddressMode,
     filter: wgpu::FilterMode,
+    //Image decoded by `prepare`, waiting to be uploaded to the gpu
+    decoded: Option<Image>,
     #[cfg(target_arch = "wasm32")]
     pub(crate) sampler: Option<crate::wrappers::WgpuWrapper<wgpu::Sampler>>,
     #[cfg(not(target_arch = "wasm32"))]
@@ -49,6 +51,8 @@ enum ImageFormat {
     Bmp,
     ///.png image
     Png,
+    ///Texture rendered into by a camera, with the width and the height
+    Target(u32, u32),
 }
 
 #[allow(unused_variables)]
@@ -69,6 +73,7 @@ impl Texture {
             sample_count: 1,
             adress_mode: wgpu::AddressMode::ClampToEdge,
             filter: wgpu::FilterMode::Linear,
+            decoded: None,
             sampler: None,
             texture: None,
         }
@@ -89,6 +94,7 @@ impl Texture {
             sample_count: 1,
             adress_mode: wgpu::AddressMode::ClampToEdge,
             filter: wgpu::FilterMode::Linear,
+            decoded: None,
             sampler: None,
             texture: None,
         }
@@ -110,6 +116,7 @@ impl Texture {
             sample_count: 1,
             adress_mode: wgpu::AddressMode::ClampToEdge,
             filter: wgpu::FilterMode::Linear,
+            decoded: None,
             sampler: None,
             texture: None,
         }
@@ -130,39 +137,122 @@ impl Texture {
             sample_count: 1,
             adress_mode: wgpu::AddressMode::ClampToEdge,
             filter: wgpu::FilterMode::Linear,
+            decoded: None,
             sampler: None,
             texture: None,
         }
     }
 
+    ///Initializes a texture rendered into by a [`Camera`](crate::components::camera::Camera),
+    ///see [`Camera::target`](crate::components::camera::Camera::target)
+    ///
+    ///The texture is oriented like the loaded textures, so it can be used by any material
+    #[must_use]
+    pub fn render_target(width: u32, height: u32) -> Self {
+        Self {
+            id: None,
+            initialized: false,
+            image_format: ImageFormat::Target(width.max(1), height.max(1)),
+            filepath: None,
+            r#static: Static::No,
+            mip_count: 1,
+            sample_count: 1,
+            adress_mode: wgpu::AddressMode::ClampToEdge,
+            filter: wgpu::FilterMode::Linear,
+            decoded: None,
+            sampler: None,
+            texture: None,
+        }
+    }
+
+    ///Returns `true` if the texture is rendered into by a camera
+    #[must_use]
+    pub const fn is_render_target(&self) -> bool {
+        matches!(self.image_format, ImageFormat::Target(..))
+    }
+
+    ///Reads and decodes the image
+    fn decode(&self) -> Result<Image, Box<dyn std::error::Error + Send>> {
+        //Render targets start out transparent
+        if let ImageFormat::Target(width, height) = self.image_format {
+            return Ok(Image {
+                width,
+                height,
+                img_type: lunar_png::ImageType::Rgba8,
+                data: vec![0; width as usize * height as usize * 4],
+            });
+        }
+
+        let image = match &self.r#static {
+            Static::Yes(d, _) => d.clone(),
+            Static::No => {
+                if let Some(file) = &self.filepath {
+                    match std::fs::read(file) {
+                        Ok(it) => it,
+                        Err(err) => return Err(Box::new(err)),
+                    }
+                } else {
+                    return Err(Box::new(std::io::Error::new(
+                        std::io::ErrorKind::NotFound,
+                        "File not found",
+                    )));
+                }
+            }
+        };
+
+        match self.image_format {
+            ImageFormat::Bmp => crate::import::bmp::parse(&image),
+            ImageFormat::Png => match lunar_png::read_png(&mut image.into_iter()) {
+                Ok(mut img) => {
+                    flip_texture(&mut img);
+                    img.add_alpha();
+                    img.add_channels();
+
+                    Ok(img)
+                }
+                Err(err) => Err(Box::new(err)),
+            },
+            //Returned before reading the data
+            ImageFormat::Target(..) => unreachable!(),
+        }
+    }
+
     /// Loads image data into `wgpu::Texture`
     fn load_into_gpu(&mut self, image: &Arc<RwLock<Image>>) {
         let device = crate::DEVICE.get().unwrap();
         let queue = crate::QUEUE.get().unwrap();
         let image = image.read().unwrap();
 
-        let var_name = &format!("{}", self.get_id());
+        let var_name = &format!("Texture {}", self.get_id());
         let label = Some(var_name.as_str());
 
-        let texture = device.create_texture_with_data(
-            queue,
-            &wgpu::TextureDescriptor {
-                label,
-                size: wgpu::Extent3d {
-                    width: image.width,
-                    height: image.height,
-                    depth_or_array_layers: 1,
+        let texture = crate::errors::scoped(var_name, || {
+            device.create_texture_with_data(
+                queue,
+                &wgpu::TextureDescriptor {
+                    label,
+                    size: wgpu::Extent3d {
+                        width: image.width,
+                        height: image.height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: self.mip_count.into(),
+                    sample_count: self.sample_count.into(),
+                    dimension: wgpu::TextureDimension::D2,
+                    format: wgpu::TextureFormat::Rgba8Unorm,
+                    usage: if self.is_render_target() {
+                        wgpu::TextureUsages::TEXTURE_BINDING
+                            | wgpu::TextureUsages::COPY_DST
+                            | wgpu::TextureUsages::RENDER_ATTACHMENT
+                    } else {
+                        wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST
+                    },
+                    view_formats: &[wgpu::TextureFormat::Rgba8Unorm],
                 },
-                mip_level_count: self.mip_count.into(),
-                sample_count: self.sample_count.into(),
-                dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Rgba8Unorm,
-                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-                view_formats: &[wgpu::TextureFormat::Rgba8Unorm],
-            },
-            wgpu::util::TextureDataOrder::LayerMajor,
-            &image.data,
-        );
+                wgpu::util::TextureDataOrder::LayerMajor,
+                &image.data,
+            )
+        });
 
         drop(image);
 
@@ -230,38 +320,9 @@ impl Asset for Texture {
             return Ok(());
         }
 
-        let image = match &self.r#static {
-            Static::Yes(d, _) => d.clone(),
-            Static::No => {
-                if let Some(file) = &self.filepath {
-                    match std::fs::read(file) {
-                        Ok(it) => it,
-                        Err(err) => return Err(Box::new(err)),
-                    }
-                } else {
-                    return Err(Box::new(std::io::Error::new(
-                        std::io::ErrorKind::NotFound,
-                        "File not found",
-                    )));
-                }
-            }
-        };
-
-        let image = match self.image_format {
-            ImageFormat::Bmp => match crate::import::bmp::parse(&image) {
-                Ok(it) => it,
-                Err(err) => return Err(err),
-            },
-            ImageFormat::Png => match lunar_png::read_png(&mut image.into_iter()) {
-                Ok(mut img) => {
-                    flip_texture(&mut img);
-                    img.add_alpha();
-                    img.add_channels();
-
-                    img
-                }
-                Err(err) => return Err(Box::new(err)),
-            },
+        let image = match self.decoded.take() {
+            Some(it) => it,
+            None => self.decode()?,
         };
 
         //This is so trash
@@ -276,6 +337,15 @@ impl Asset for Texture {
         Ok(())
     }
 
+    fn prepare(&mut self) -> Result<(), Box<dyn std::error::Error + Send>> {
+        if self.decoded.is_some() || matches!(self.r#static, Static::Yes(_, Some(_))) {
+            return Ok(());
+        }
+
+        self.decoded = Some(self.decode()?);
+        Ok(())
+    }
+
     fn dispose(&mut self) {
         //Don't dispose of static textures
         if let Static::Yes(..) = self.r#static {
@@ -0,0 +1,604 @@
+use std::{num::NonZeroU64, sync::Arc};
+
+use lunar_engine_derive::as_any;
+
+use crate::{
+    asset_managment::{Asset, UUID},
+    ecs::Component,
+    grimoire::JOINTS_BIND_GROUP_LAYOUT_DESCRIPTOR,
+    math::{Mat4x4, Quaternion, Vec3},
+    time, DEVICE, STAGING_BELT,
+};
+
+pub use super::sprite_animator::PlaybackMode;
+
+///Maximum number of joints of a skinned mesh, must match the size of the array in the skinning
+///shader
+pub const MAX_JOINTS: usize = 128;
+
+///Joint of an armature
+#[derive(Debug, Clone, PartialEq)]
+pub struct Joint {
+    ///Name of the joint
+    pub name: String,
+    ///Index of the parent joint in the armature, `None` for the root joints
+    pub parent: Option<usize>,
+    ///Translation of the joint relative to its parent in the rest pose
+    pub translation: Vec3,
+    ///Rotation of the joint relative to its parent in the rest pose
+    pub rotation: Quaternion,
+    ///Scale of the joint relative to its parent in the rest pose
+    pub scale: Vec3,
+    ///Matrix transforming the vertices of the mesh into the space of the joint
+    pub inverse_bind: Mat4x4,
+}
+
+impl Joint {
+    ///Returns the transformation of the joint relative to its parent in the rest pose
+    #[must_use]
+    pub const fn rest_pose(&self) -> Pose {
+        Pose {
+            translation: self.translation,
+            rotation: self.rotation,
+            scale: self.scale,
+        }
+    }
+}
+
+///Transformation of a joint relative to its parent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pose {
+    ///Translation
+    pub translation: Vec3,
+    ///Rotation
+    pub rotation: Quaternion,
+    ///Scale
+    pub scale: Vec3,
+}
+
+impl Pose {
+    ///Returns the transformation matrix of the pose
+    #[must_use]
+    pub fn matrix(&self) -> Mat4x4 {
+        Mat4x4::translation_matrix(&self.translation)
+            * self.rotation.matrix()
+            * Mat4x4::scale_matrix(&self.scale)
+    }
+}
+
+///Joint hierarchy of a skinned mesh
+///
+///The joint indices of the vertices of the mesh index into the joints of the armature
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Armature {
+    joints: Vec<Joint>,
+    //Joints ordered so that parents come before their children
+    order: Vec<usize>,
+}
+
+impl Armature {
+    ///Creates a new armature from the joints
+    ///
+    ///Joints with a parent that doesn't exist, or that are their own ancestors, are treated as
+    ///root joints
+    #[must_use]
+    pub fn new(mut joints: Vec<Joint>) -> Self {
+        for i in 0..joints.len() {
+            //Walk up the hierarchy, breaking cycles and invalid references
+            let mut current = i;
+            let mut depth = 0;
+            while let Some(parent) = joints[current].parent {
+                if parent >= joints.len() || depth > joints.len() {
+                    joints[current].parent = None;
+                    break;
+                }
+                current = parent;
+                depth += 1;
+            }
+        }
+
+        let depth = |mut joint: usize| {
+            let mut depth = 0;
+            while let Some(parent) = joints[joint].parent {
+                joint = parent;
+                depth += 1;
+            }
+            depth
+        };
+        let mut order = (0..joints.len()).collect::<Vec<_>>();
+        order.sort_by_key(|i| depth(*i));
+
+        Self { joints, order }
+    }
+
+    ///Returns the joints of the armature
+    #[must_use]
+    pub fn joints(&self) -> &[Joint] {
+        &self.joints
+    }
+
+    ///Returns the index of the joint with the given name
+    #[must_use]
+    pub fn find_joint(&self, name: &str) -> Option<usize> {
+        self.joints.iter().position(|j| j.name == name)
+    }
+
+    ///Returns the poses of the joints in the rest pose
+    #[must_use]
+    pub fn rest_pose(&self) -> Vec<Pose> {
+        self.joints.iter().map(Joint::rest_pose).collect()
+    }
+
+    ///Returns the matrices transforming the vertices of the mesh from the rest pose into the
+    ///`poses` of the joints, relative to the root of the armature
+    ///
+    ///# Panics
+    ///Panics if the number of poses is different from the number of joints
+    #[must_use]
+    pub fn joint_matrices(&self, poses: &[Pose]) -> Vec<Mat4x4> {
+        assert_eq!(
+            poses.len(),
+            self.joints.len(),
+            "Every joint must have a pose"
+        );
+
+        let mut global = vec![Mat4x4::identity(); self.joints.len()];
+        for i in &self.order {
+            let local = poses[*i].matrix();
+            global[*i] = self.joints[*i]
+                .parent
+                .map_or(local, |parent| global[parent] * local);
+        }
+
+        global
+            .into_iter()
+            .zip(&self.joints)
+            .map(|(g, j)| g * j.inverse_bind)
+            .collect()
+    }
+}
+
+///How the values between the keyframes are computed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    ///The value of the previous keyframe is used
+    Step,
+    ///Values are interpolated linearly, rotations are interpolated spherically
+    #[default]
+    Linear,
+    ///Values are interpolated with a cubic hermite spline, every keyframe stores an in tangent, a
+    ///value and an out tangent in this order
+    CubicSpline,
+}
+
+///Keyframe values of a channel
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChannelValues {
+    ///Translations of the joint
+    Translation(Vec<Vec3>),
+    ///Rotations of the joint
+    Rotation(Vec<Quaternion>),
+    ///Scales of the joint
+    Scale(Vec<Vec3>),
+}
+
+///Animation of one property of a joint
+#[derive(Debug, Clone, PartialEq)]
+pub struct Channel {
+    ///Index of the animated joint in the armature
+    pub joint: usize,
+    ///Times of the keyframes in seconds, in ascending order
+    pub times: Vec<f32>,
+    ///Values of the keyframes
+    pub values: ChannelValues,
+    ///How the values between the keyframes are computed
+    pub interpolation: Interpolation,
+}
+
+///Animation of the joints of an armature
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AnimationClip {
+    ///Name of the clip
+    pub name: String,
+    ///Channels of the clip
+    pub channels: Vec<Channel>,
+    ///[`Skeleton`](crate::assets::Skeleton) the channels target, `None` if the clip is not bound
+    ///to one, see [`Skeleton::bind_clip`](crate::assets::Skeleton::bind_clip)
+    pub skeleton: Option<UUID>,
+}
+
+impl AnimationClip {
+    ///Creates a new clip
+    #[must_use]
+    pub const fn new(name: String, channels: Vec<Channel>) -> Self {
+        Self {
+            name,
+            channels,
+            skeleton: None,
+        }
+    }
+
+    ///Returns the length of the clip in seconds, the time of the last keyframe
+    #[must_use]
+    pub fn duration(&self) -> f32 {
+        self.channels
+            .iter()
+            .filter_map(|c| c.times.last().copied())
+            .fold(0.0, f32::max)
+    }
+
+    ///Samples the poses of the joints at the `time` in seconds
+    ///
+    ///Joints not animated by the clip keep their rest pose, channels targeting joints that don't
+    ///exist in the armature are ignored
+    #[must_use]
+    pub fn sample(&self, armature: &Armature, time: f32) -> Vec<Pose> {
+        let mut poses = armature.rest_pose();
+        for c in &self.channels {
+            let Some(pose) = poses.get_mut(c.joint) else {
+                continue;
+            };
+            match &c.values {
+                ChannelValues::Translation(v) => {
+                    if let Some(v) = sample(&c.times, v, c.interpolation, time) {
+                        pose.translation = v;
+                    }
+                }
+                ChannelValues::Rotation(v) => {
+                    if let Some(v) = sample(&c.times, v, c.interpolation, time) {
+                        pose.rotation = v.normalized();
+                    }
+                }
+                ChannelValues::Scale(v) => {
+                    if let Some(v) = sample(&c.times, v, c.interpolation, time) {
+                        pose.scale = v;
+                    }
+                }
+            }
+        }
+        poses
+    }
+}
+
+//Value that can be animated
+trait Keyframe: Copy {
+    fn interpolate(self, other: Self, t: f32) -> Self;
+    //Weighted sum of the components
+    fn combine(values: [(Self, f32); 4]) -> Self;
+}
+
+impl Keyframe for Vec3 {
+    fn interpolate(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+
+    fn combine(values: [(Self, f32); 4]) -> Self {
+        values
+            .into_iter()
+            .fold(Self::new(0.0, 0.0, 0.0), |acc, (v, w)| acc + v * w)
+    }
+}
+
+impl Keyframe for Quaternion {
+    fn interpolate(self, other: Self, t: f32) -> Self {
+        self.slerp(other, t)
+    }
+
+    fn combine(values: [(Self, f32); 4]) -> Self {
+        values
+            .into_iter()
+            .fold(Self::new(0.0, 0.0, 0.0, 0.0), |acc, (v, w)| {
+                Self::new(
+                    v.x.mul_add(w, acc.x),
+                    v.y.mul_add(w, acc.y),
+                    v.z.mul_add(w, acc.z),
+                    v.w.mul_add(w, acc.w),
+                )
+            })
+    }
+}
+
+//Returns the value of the keyframes at the time, `None` if there are not enough values
+fn sample<T: Keyframe>(
+    times: &[f32],
+    values: &[T],
+    interpolation: Interpolation,
+    time: f32,
+) -> Option<T> {
+    let cubic = interpolation == Interpolation::CubicSpline;
+    let stride = if cubic { 3 } else { 1 };
+    if times.is_empty() || values.len() < times.len() * stride {
+        return None;
+    }
+    //The value itself is between the tangents
+    let value = |i: usize| values[i * stride + usize::from(cubic)];
+
+    //Index of the first keyframe after the time
+    let next = times.partition_point(|t| *t <= time);
+    if next == 0 {
+        return Some(value(0));
+    }
+    if next == times.len() {
+        return Some(value(times.len() - 1));
+    }
+    let previous = next - 1;
+
+    let delta = times[next] - times[previous];
+    let t = if delta > 0.0 {
+        (time - times[previous]) / delta
+    } else {
+        0.0
+    };
+
+    Some(match interpolation {
+        Interpolation::Step => value(previous),
+        Interpolation::Linear => value(previous).interpolate(value(next), t),
+        Interpolation::CubicSpline => {
+            let out_tangent = values[previous * 3 + 2];
+            let in_tangent = values[next * 3];
+            let (t2, t3) = (t * t, t * t * t);
+            T::combine([
+                (value(previous), 2.0f32.mul_add(t3, -3.0 * t2) + 1.0),
+                (out_tangent, delta * (2.0f32.mul_add(-t2, t3) + t)),
+                (value(next), (-2.0f32).mul_add(t3, 3.0 * t2)),
+                (in_tangent, delta * (t3 - t2)),
+            ])
+        }
+    })
+}
+
+///Plays animation clips on an armature, skinning the meshes on the same entity
+///
+///The joint matrices are recomputed every frame and uploaded to the gpu by the renderer. Skinned
+///meshes must use a material supporting skinning, e.g. [`Lit`](crate::assets::materials::Lit),
+///otherwise they are drawn in their bind pose
+#[derive(Debug)]
+pub struct Animator {
+    ///Playback speed multiplier
+    pub speed: f32,
+    ///How the clip is played
+    pub mode: PlaybackMode,
+    armature: Arc<Armature>,
+    skeleton: Option<UUID>,
+    clip: Option<Arc<AnimationClip>>,
+    playing: bool,
+    //Position in the clip in seconds
+    time: f32,
+    //Whether or not a ping pong animation is playing backwards
+    reverse: bool,
+    matrices: Vec<Mat4x4>,
+    buffer: Option<wgpu::Buffer>,
+    bind_group: Option<Arc<wgpu::BindGroup>>,
+}
+
+impl Component for Animator {
+    #[as_any]
+    fn mew() -> Self
+    where
+        Self: Sized,
+    {
+        Self::new(Arc::new(Armature::default()))
+    }
+
+    fn update(&mut self) {
+        self.advance(time::delta_time());
+    }
+}
+
+impl Animator {
+    ///Creates a new animator for the armature, in the rest pose
+    #[must_use]
+    pub fn new(armature: Arc<Armature>) -> Self {
+        let matrices = armature.joint_matrices(&armature.rest_pose());
+        Self {
+            speed: 1.0,
+            mode: PlaybackMode::Loop,
+            armature,
+            skeleton: None,
+            clip: None,
+            playing: false,
+            time: 0.0,
+            reverse: false,
+            matrices,
+            buffer: None,
+            bind_group: None,
+        }
+    }
+
+    ///Creates a new animator for the armature of the skeleton, in the rest pose
+    #[must_use]
+    pub fn from_skeleton(skeleton: &crate::assets::Skeleton) -> Self {
+        Self {
+            skeleton: Some(skeleton.get_id()),
+            ..Self::new(skeleton.get_armature())
+        }
+    }
+
+    ///Returns the armature animated by the animator
+    #[must_use]
+    pub fn get_armature(&self) -> Arc<Armature> {
+        self.armature.clone()
+    }
+
+    ///Replaces the armature, stopping the current clip
+    pub fn set_armature(&mut self, armature: Arc<Armature>) {
+        self.armature = armature;
+        self.skeleton = None;
+        self.stop();
+    }
+
+    ///Returns the id of the skeleton animated by the animator, `None` if it was created from an
+    ///armature
+    #[must_use]
+    pub const fn get_skeleton(&self) -> Option<UUID> {
+        self.skeleton
+    }
+
+    ///Starts playing the clip from the beginning
+    pub fn play(&mut self, clip: Arc<AnimationClip>) {
+        self.clip = Some(clip);
+        self.playing = true;
+        self.time = 0.0;
+        self.reverse = false;
+        self.update_pose();
+    }
+
+    ///Pauses the clip
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    ///Resumes the paused clip
+    pub fn resume(&mut self) {
+        self.playing = self.clip.is_some();
+    }
+
+    ///Stops the clip and returns to the rest pose
+    pub fn stop(&mut self) {
+        self.clip = None;
+        self.playing = false;
+        self.time = 0.0;
+        self.update_pose();
+    }
+
+    ///Returns `true` if a clip is playing
+    #[must_use]
+    pub const fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    ///Returns the current clip
+    #[must_use]
+    pub const fn get_clip(&self) -> Option<&Arc<AnimationClip>> {
+        self.clip.as_ref()
+    }
+
+    ///Returns the position in the current clip in seconds
+    #[must_use]
+    pub const fn get_time(&self) -> f32 {
+        self.time
+    }
+
+    ///Moves to the `time` in seconds in the current clip
+    pub fn seek(&mut self, time: f32) {
+        self.time = time;
+        self.update_pose();
+    }
+
+    ///Returns the matrices of the joints in the current pose, see [`Armature::joint_matrices`]
+    #[must_use]
+    pub fn get_joint_matrices(&self) -> &[Mat4x4] {
+        &self.matrices
+    }
+
+    ///Advances the clip by `delta` seconds
+    ///
+    ///Called automatically every frame
+    pub fn advance(&mut self, delta: f32) {
+        let Some(clip) = &self.clip else {
+            return;
+        };
+        if !self.playing {
+            return;
+        }
+        let duration = clip.duration();
+        let delta = delta * self.speed;
+
+        match self.mode {
+            PlaybackMode::Once => {
+                self.time += delta;
+                if self.time >= duration {
+                    self.time = duration;
+                    self.playing = false;
+                }
+            }
+            PlaybackMode::Loop => {
+                self.time = if duration > 0.0 {
+                    (self.time + delta).rem_euclid(duration)
+                } else {
+                    0.0
+                };
+            }
+            PlaybackMode::PingPong => {
+                if duration > 0.0 {
+                    //Position in a loop going forward and then backwards
+                    let position = (if self.reverse {
+                        2.0f32.mul_add(duration, -self.time)
+                    } else {
+                        self.time
+                    } + delta)
+                        .rem_euclid(duration * 2.0);
+                    self.reverse = position > duration;
+                    self.time = if self.reverse {
+                        2.0f32.mul_add(duration, -position)
+                    } else {
+                        position
+                    };
+                }
+            }
+        }
+        self.update_pose();
+    }
+
+    fn update_pose(&mut self) {
+        let poses = self.clip.as_ref().map_or_else(
+            || self.armature.rest_pose(),
+            |c| c.sample(&self.armature, self.time),
+        );
+        self.matrices = self.armature.joint_matrices(&poses);
+    }
+
+    ///Uploads the joint matrices and returns the bind group containing them
+    pub(crate) fn update_gpu(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+    ) -> Arc<wgpu::BindGroup> {
+        let device = DEVICE.get().unwrap();
+        let size = (MAX_JOINTS * std::mem::size_of::<Mat4x4>()) as u64;
+
+        let buffer = self.buffer.get_or_insert_with(|| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Joint matrices"),
+                size,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        });
+
+        if self.matrices.len() > MAX_JOINTS {
+            log::warn!(
+                "Armature has {} joints, only the first {MAX_JOINTS} are used",
+                self.matrices.len()
+            );
+        }
+
+        let mut staging_belt = STAGING_BELT.get().unwrap().write().unwrap();
+        let mut view =
+            staging_belt.write_buffer(encoder, buffer, 0, NonZeroU64::new(size).unwrap(), device);
+        for (m, data) in self
+            .matrices
+            .iter()
+            .chain(std::iter::repeat(&Mat4x4::identity()))
+            .zip(view.chunks_exact_mut(std::mem::size_of::<Mat4x4>()))
+        {
+            //Shaders expect column major matrices
+            data.copy_from_slice(bytemuck::bytes_of(&m.transpose()));
+        }
+        drop(view);
+        drop(staging_belt);
+
+        self.bind_group
+            .get_or_insert_with(|| {
+                let layout = device.create_bind_group_layout(&JOINTS_BIND_GROUP_LAYOUT_DESCRIPTOR);
+                Arc::new(device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Joint matrices bind group"),
+                    layout: &layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: buffer.as_entire_binding(),
+                    }],
+                }))
+            })
+            .clone()
+    }
+}
@@ -5,10 +5,19 @@ use lunar_engine_derive::{alias, as_any, dependencies};
 use crate as lunar_engine;
 
 use crate::{
+    asset_managment::UUID,
     ecs::{Component, ComponentReference},
     grimoire::{CAMERA_BIND_GROUP_INDEX, CAMERA_BIND_GROUP_LAYOUT_DESCRIPTOR},
-    math::{Mat4x4, Vec4},
-    DEVICE, RESOLUTION, STAGING_BELT,
+    math::{
+        curves::{CatmullRom, Curve},
+        Mat4x4, Vec3, Vec4, Vector,
+    },
+    rendering::{
+        background::Background,
+        depth,
+        viewport::{self, Rect},
+    },
+    time, DEVICE, STAGING_BELT,
 };
 
 use super::transform::Transform;
@@ -48,6 +57,19 @@ impl ProjectionType {
     }
 }
 
+///What happens to the rectangle of a [`Camera`] before it's rendered into
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CameraClear {
+    ///The color and the depth are cleared
+    #[default]
+    Color,
+    ///Only the depth is cleared, the camera is drawn over the image of the previous cameras
+    Depth,
+    ///Nothing is cleared, the meshes of the camera are hidden by the ones of the previous
+    ///cameras
+    Nothing,
+}
+
 #[derive(Debug)]
 ///Camera used for rendering of the objects
 pub struct Camera {
@@ -56,7 +78,27 @@ pub struct Camera {
     ///Near plane of the camera
     pub near: f32,
     ///Far plane of the camera
+    ///
+    ///Perspective cameras may use [`f32::INFINITY`] for a far plane at infinity
     pub far: f32,
+    ///Whether or not the camera is rendered, only used by cameras with a [`Self::target`]
+    pub enabled: bool,
+    ///Id of the render target [`Texture`](crate::assets::Texture) the camera renders into,
+    ///created with [`Texture::render_target`](crate::assets::Texture::render_target)
+    ///
+    ///Cameras with a target are rendered by the
+    ///[`RenderTargets`](crate::rendering::extensions::RenderTargets) extension, the target of the
+    ///[`MainCamera`] is ignored
+    pub target: Option<UUID>,
+    ///Part of the viewport the camera renders into, ignored by cameras with a [`Self::target`],
+    ///the rectangle of the [`MainCamera`] must cover the whole viewport
+    pub rect: Rect,
+    ///Priority of the camera, cameras with smaller priorities are rendered first
+    pub priority: u32,
+    ///What happens to the rectangle of the camera before it's rendered into
+    pub clear: CameraClear,
+    ///What the parts of the rectangle or the target not occupied by a mesh are filled with
+    pub background: Background,
     transorm_reference: Option<ComponentReference<Transform>>,
     buffer: Option<wgpu::Buffer>,
     bind_group: Option<wgpu::BindGroup>,
@@ -67,6 +109,8 @@ impl Default for Camera {
     /// - Fov: 60
     /// - Near plane: 0.1
     /// - Far plane: 100
+    /// - Enabled, without a target
+    /// - Covering the whole viewport, inheriting the background
     fn default() -> Self {
         Self {
             projection_type: ProjectionType::Perspective {
@@ -74,6 +118,12 @@ impl Default for Camera {
             },
             near: 0.1,
             far: 100.0,
+            enabled: true,
+            target: None,
+            rect: Rect::FULL,
+            priority: 0,
+            clear: CameraClear::Color,
+            background: Background::Inherit,
             transorm_reference: None,
             buffer: None,
             bind_group: None,
@@ -121,6 +171,13 @@ impl Camera {
     #[must_use]
     ///Returns the transformation matrix of the camera multiplied by the projection matrix
     pub fn matrix(&self) -> Mat4x4 {
+        self.matrix_with_aspect(viewport::current().sub(self.rect).aspect())
+    }
+
+    #[must_use]
+    ///Returns the transformation matrix of the camera multiplied by the projection matrix with the
+    ///given aspect ratio
+    pub fn matrix_with_aspect(&self, aspect: f32) -> Mat4x4 {
         let binding = self.transorm_reference.as_ref().unwrap();
         let transform = binding.borrow();
         let rotation_matrix = Mat4x4::rotation_matrix_euler(&transform.rotation);
@@ -130,12 +187,10 @@ impl Camera {
 
         let camera_matrix = Mat4x4::look_at_matrix(transform.position, up, forward);
 
-        let resolution = RESOLUTION.read().unwrap();
-        let aspect = resolution.width as f32 / resolution.height as f32;
-
-        drop(resolution);
-
         let projection_matrix = match self.projection_type {
+            ProjectionType::Perspective { fov } if self.far.is_infinite() => {
+                Mat4x4::perspective_infinite(fov, aspect, self.near)
+            }
             ProjectionType::Perspective { fov } => {
                 Mat4x4::perspercive_projection(fov, aspect, self.near, self.far)
             }
@@ -143,6 +198,11 @@ impl Camera {
                 Mat4x4::orth_aspect_projection(size, aspect, self.near, self.far)
             }
         };
+        let projection_matrix = if depth::is_reversed() {
+            projection_matrix.reverse_depth()
+        } else {
+            projection_matrix
+        };
 
         camera_matrix * projection_matrix
     }
@@ -174,6 +234,11 @@ impl Camera {
 
     ///Updates the buffer of the camera with the new camera matrix
     pub(crate) fn update_gpu(&self, encoder: &mut wgpu::CommandEncoder) {
+        self.write_matrix(encoder, &self.matrix());
+    }
+
+    ///Updates the buffer of the camera with the `matrix`
+    pub(crate) fn write_matrix(&self, encoder: &mut wgpu::CommandEncoder, matrix: &Mat4x4) {
         let mut staging_belt = STAGING_BELT.get().unwrap().write().unwrap();
 
         staging_belt
@@ -184,7 +249,12 @@ impl Camera {
                 NonZeroU64::new(std::mem::size_of::<Mat4x4>() as u64).unwrap(),
                 DEVICE.get().unwrap(),
             )
-            .copy_from_slice(bytemuck::bytes_of(&self.matrix()));
+            .copy_from_slice(bytemuck::bytes_of(matrix));
+    }
+
+    ///Returns the bind group of the camera
+    pub(crate) fn get_bind_group(&self) -> &wgpu::BindGroup {
+        self.bind_group.as_ref().unwrap()
     }
 
     ///Sets bindgroups of the camera for rendering
@@ -203,3 +273,342 @@ impl Camera {
 // #[derive(Debug, Default)]
 #[alias(Camera)]
 pub struct MainCamera;
+
+///Trauma based camera shake
+///
+///Offsets the position and the rotation of the [`Transform`] every frame using smooth noise. The
+///strength of the shake is the square of the trauma, which decays over time, so small amounts
+///of trauma produce barely noticeable shakes, while large amounts produce violent ones.
+///
+///Other components may still move the transform, the shake is applied on top of their changes
+#[derive(Debug)]
+pub struct CameraShake {
+    ///Maximum offset of the position
+    pub max_offset: Vec3,
+    ///Maximum offset of the rotation in degrees
+    pub max_rotation: Vec3,
+    ///Amount of trauma removed every second
+    pub decay: f32,
+    ///Speed of the shake, in noise samples per second
+    pub frequency: f32,
+    trauma: f32,
+    seed: u32,
+    time: f32,
+    //Offsets applied in the last frame and the resulting position and rotation
+    applied: Option<ShakeState>,
+    transform_reference: Option<ComponentReference<Transform>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ShakeState {
+    offset: Vec3,
+    rotation_offset: Vec3,
+    position: Vec3,
+    rotation: Vec3,
+}
+
+impl Default for CameraShake {
+    ///The default shake has the following settings:
+    /// - Max offset: 0.5 on every axis
+    /// - Max rotation: 5 degrees on every axis
+    /// - Decay: 1 per second
+    /// - Frequency: 25
+    fn default() -> Self {
+        Self {
+            max_offset: Vec3::new(0.5, 0.5, 0.5),
+            max_rotation: Vec3::new(5.0, 5.0, 5.0),
+            decay: 1.0,
+            frequency: 25.0,
+            trauma: 0.0,
+            seed: crate::determinism::random(),
+            time: 0.0,
+            applied: None,
+            transform_reference: None,
+        }
+    }
+}
+
+impl Component for CameraShake {
+    #[as_any]
+    #[dependencies(Transform)]
+    fn mew() -> Self
+    where
+        Self: Sized,
+    {
+        Self::default()
+    }
+
+    fn set_self_reference(&mut self, reference: crate::ecs::SelfReferenceGuard) {
+        self.transform_reference = Some(reference.get_component().unwrap());
+    }
+
+    fn update(&mut self) {
+        let delta = time::delta_time();
+        self.time += delta * self.frequency;
+        let shake = self.trauma * self.trauma;
+        self.trauma = (self.trauma - self.decay * delta).max(0.0);
+
+        let offset = Vec3::new(
+            self.max_offset.x * noise(self.seed, self.time),
+            self.max_offset.y * noise(self.seed.wrapping_add(1), self.time),
+            self.max_offset.z * noise(self.seed.wrapping_add(2), self.time),
+        ) * shake;
+        let rotation_offset = Vec3::new(
+            self.max_rotation.x * noise(self.seed.wrapping_add(3), self.time),
+            self.max_rotation.y * noise(self.seed.wrapping_add(4), self.time),
+            self.max_rotation.z * noise(self.seed.wrapping_add(5), self.time),
+        ) * shake;
+
+        let binding = self.transform_reference.as_ref().unwrap();
+        let mut transform = binding.borrow_mut();
+
+        //Remove the offsets of the last frame, unless something else has moved the transform
+        let (mut position, mut rotation) = (transform.position, transform.rotation);
+        if let Some(a) = self.applied {
+            if a.position == position {
+                position -= a.offset;
+            }
+            if a.rotation == rotation {
+                rotation -= a.rotation_offset;
+            }
+        }
+
+        transform.position = position + offset;
+        transform.rotation = rotation + rotation_offset;
+
+        self.applied = Some(ShakeState {
+            offset,
+            rotation_offset,
+            position: transform.position,
+            rotation: transform.rotation,
+        });
+    }
+}
+
+impl CameraShake {
+    ///Creates a new camera shake with the given maximum offsets
+    #[must_use]
+    pub fn new(max_offset: Vec3, max_rotation: Vec3) -> Self {
+        Self {
+            max_offset,
+            max_rotation,
+            ..Default::default()
+        }
+    }
+
+    ///Adds trauma, the total trauma is clamped between 0 and 1
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    ///Returns the current trauma
+    #[must_use]
+    pub const fn get_trauma(&self) -> f32 {
+        self.trauma
+    }
+
+    ///Sets the current trauma, clamped between 0 and 1
+    pub fn set_trauma(&mut self, value: f32) {
+        self.trauma = value.clamp(0.0, 1.0);
+    }
+}
+
+///Smooth 1D value noise in the range [-1, 1]
+#[allow(clippy::cast_sign_loss, clippy::cast_possible_wrap)]
+fn noise(seed: u32, t: f32) -> f32 {
+    let hash = |i: i32| {
+        let mut x = (i as u32) ^ seed.wrapping_mul(0x9E37_79B9);
+        x ^= x >> 16;
+        x = x.wrapping_mul(0x7FEB_352D);
+        x ^= x >> 15;
+        x = x.wrapping_mul(0x846C_A68B);
+        x ^= x >> 16;
+        (x as f32 / u32::MAX as f32).mul_add(2.0, -1.0)
+    };
+
+    let i = t.floor();
+    let f = t - i;
+    let a = hash(i as i32);
+    let b = hash(i as i32 + 1);
+    //Smoothstep
+    let f = f * f * 2.0f32.mul_add(-f, 3.0);
+    (b - a).mul_add(f, a)
+}
+
+///Where the [`CinematicCamera`] looks
+#[derive(Debug, Default)]
+pub enum LookTarget {
+    ///Look along the path
+    #[default]
+    Path,
+    ///Look at a fixed point
+    Point(Vec3),
+    ///Look at a transform
+    Transform(ComponentReference<Transform>),
+}
+
+///Moves the [`Transform`] along a smooth path going through all the points
+///
+///The path is a Catmull-Rom spline, the camera travels it in `duration` seconds, looking at the
+///[`LookTarget`]
+#[derive(Debug)]
+pub struct CinematicCamera {
+    ///Path of the camera
+    pub path: CatmullRom,
+    ///Time it takes to travel the whole path in seconds
+    pub duration: f32,
+    ///Whether or not to restart the path after reaching its end
+    pub looping: bool,
+    ///Where the camera looks
+    pub target: LookTarget,
+    playing: bool,
+    time: f32,
+    transform_reference: Option<ComponentReference<Transform>>,
+}
+
+impl Default for CinematicCamera {
+    fn default() -> Self {
+        Self {
+            path: CatmullRom::default(),
+            duration: 1.0,
+            looping: false,
+            target: LookTarget::Path,
+            playing: false,
+            time: 0.0,
+            transform_reference: None,
+        }
+    }
+}
+
+impl Component for CinematicCamera {
+    #[as_any]
+    #[dependencies(Transform)]
+    fn mew() -> Self
+    where
+        Self: Sized,
+    {
+        Self::default()
+    }
+
+    fn set_self_reference(&mut self, reference: crate::ecs::SelfReferenceGuard) {
+        self.transform_reference = Some(reference.get_component().unwrap());
+    }
+
+    fn update(&mut self) {
+        if !self.playing || self.path.points.is_empty() {
+            return;
+        }
+
+        self.time += time::delta_time();
+        if self.time >= self.duration {
+            if self.looping && self.duration > 0.0 {
+                self.time %= self.duration;
+            } else {
+                self.time = self.duration;
+                self.playing = false;
+            }
+        }
+
+        let progress = self.get_progress();
+        let position = self.position_at(progress);
+
+        let look_at = match &self.target {
+            LookTarget::Path => Some(position + self.path.tangent(progress)),
+            LookTarget::Point(p) => Some(*p),
+            LookTarget::Transform(t) => {
+                let m = t.borrow().matrix();
+                Some(Vec3::new(m.m03, m.m13, m.m23))
+            }
+        };
+
+        let binding = self.transform_reference.as_ref().unwrap();
+        let mut transform = binding.borrow_mut();
+        transform.position = position;
+        if let Some(rotation) = look_at.and_then(|t| look_rotation(t - position)) {
+            transform.rotation = rotation;
+        }
+    }
+}
+
+impl CinematicCamera {
+    ///Creates a new cinematic camera that travels the `path` in `duration` seconds
+    #[must_use]
+    pub fn new(path: Vec<Vec3>, duration: f32, target: LookTarget) -> Self {
+        Self {
+            path: CatmullRom::new(path),
+            duration,
+            target,
+            ..Default::default()
+        }
+    }
+
+    ///Starts or resumes moving along the path
+    pub fn play(&mut self) {
+        if self.time >= self.duration {
+            self.time = 0.0;
+        }
+        self.playing = true;
+    }
+
+    ///Pauses the movement
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    ///Stops the movement and returns to the start of the path
+    pub fn stop(&mut self) {
+        self.playing = false;
+        self.time = 0.0;
+    }
+
+    ///Returns `true` if the camera is moving along the path
+    #[must_use]
+    pub const fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    ///Returns the time since the start of the path in seconds
+    #[must_use]
+    pub const fn get_time(&self) -> f32 {
+        self.time
+    }
+
+    ///Sets the time since the start of the path in seconds
+    pub fn set_time(&mut self, time: f32) {
+        self.time = time.clamp(0.0, self.duration);
+    }
+
+    ///Returns how much of the path was traveled, between 0 and 1
+    #[must_use]
+    pub fn get_progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return 1.0;
+        }
+        (self.time / self.duration).clamp(0.0, 1.0)
+    }
+
+    ///Returns the position on the path, `progress` is between 0 and 1
+    ///
+    ///# Panics
+    ///Panics if the path is empty
+    #[must_use]
+    pub fn position_at(&self, progress: f32) -> Vec3 {
+        self.path.evaluate(progress)
+    }
+}
+
+///Returns euler angles in degrees that rotate the camera to look in the `direction`
+///
+///Returns `None` if the direction has no length
+pub(crate) fn look_rotation(direction: Vec3) -> Option<Vec3> {
+    if direction.square_length() == 0.0 {
+        return None;
+    }
+    let d = direction.normalized();
+
+    Some(Vec3::new(
+        -d.y.clamp(-1.0, 1.0).asin().to_degrees(),
+        d.x.atan2(d.z).to_degrees(),
+        0.0,
+    ))
+}
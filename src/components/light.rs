@@ -0,0 +1,236 @@
+use lunar_engine_derive::{as_any, dependencies};
+
+use crate as lunar_engine;
+
+use crate::{
+    ecs::{Component, ComponentReference},
+    math::{Vec3, Vec4, Vector},
+    structures::Color,
+};
+
+use super::transform::Transform;
+
+///Light shining in a single direction from infinitely far away, like the sun
+///
+///The light shines along the forward (+Z) axis of the [`Transform`]. Only the first directional
+///light in the world is used, it's shadows are rendered by the
+///[`crate::rendering::extensions::shadows::ShadowMap`] extension and used by the
+///[`crate::assets::materials::Lit`] material
+#[derive(Debug)]
+pub struct DirectionalLight {
+    ///Color of the light
+    pub color: Color,
+    ///Intensity of the light, the color is multiplied by it
+    pub intensity: f32,
+    ///Whether or not the light casts shadows
+    pub cast_shadows: bool,
+    ///Size of the area around the camera covered by the shadows
+    pub shadow_size: f32,
+    ///Depth of the area covered by the shadows, centered on the camera
+    pub shadow_depth: f32,
+    ///Offset of the depth used when comparing it with the shadow map, removes shadow acne
+    pub shadow_bias: f32,
+    transform_reference: Option<ComponentReference<Transform>>,
+}
+
+impl Default for DirectionalLight {
+    ///The default light has the following settings:
+    /// - Color: white
+    /// - Intensity: 1
+    /// - Shadows: enabled, covering 50x50x100 units
+    /// - Shadow bias: 0.002
+    fn default() -> Self {
+        Self {
+            color: Color::white(),
+            intensity: 1.0,
+            cast_shadows: true,
+            shadow_size: 50.0,
+            shadow_depth: 100.0,
+            shadow_bias: 0.002,
+            transform_reference: None,
+        }
+    }
+}
+
+impl Component for DirectionalLight {
+    #[as_any]
+    #[dependencies(Transform)]
+    fn mew() -> Self
+    where
+        Self: Sized,
+    {
+        Self::default()
+    }
+
+    fn set_self_reference(&mut self, reference: crate::ecs::SelfReferenceGuard) {
+        self.transform_reference = Some(reference.get_component().unwrap());
+    }
+}
+
+impl DirectionalLight {
+    ///Creates a new light with the given color and intensity
+    #[must_use]
+    pub fn new(color: Color, intensity: f32) -> Self {
+        Self {
+            color,
+            intensity,
+            ..Default::default()
+        }
+    }
+
+    ///Returns the direction the light shines in
+    #[must_use]
+    pub fn direction(&self) -> Vec3 {
+        let matrix = self.transform_reference.as_ref().unwrap().borrow().matrix();
+        (matrix * Vec4::new(0.0, 0.0, 1.0, 0.0)).xyz().normalized()
+    }
+}
+
+///Light shining in all directions from a single point, like a light bulb
+///
+///The light only affects surfaces closer than the range. Point lights are assigned to clusters
+///of the view by the [`crate::rendering::extensions::clustered::LightCulling`] extension, which
+///needs to be used for them to light the [`crate::assets::materials::Lit`] material
+#[derive(Debug)]
+pub struct PointLight {
+    ///Color of the light
+    pub color: Color,
+    ///Intensity of the light, the color is multiplied by it
+    pub intensity: f32,
+    ///Distance at which the light fades out completely
+    pub range: f32,
+    transform_reference: Option<ComponentReference<Transform>>,
+}
+
+impl Default for PointLight {
+    ///The default light has the following settings:
+    /// - Color: white
+    /// - Intensity: 1
+    /// - Range: 10
+    fn default() -> Self {
+        Self {
+            color: Color::white(),
+            intensity: 1.0,
+            range: 10.0,
+            transform_reference: None,
+        }
+    }
+}
+
+impl Component for PointLight {
+    #[as_any]
+    #[dependencies(Transform)]
+    fn mew() -> Self
+    where
+        Self: Sized,
+    {
+        Self::default()
+    }
+
+    fn set_self_reference(&mut self, reference: crate::ecs::SelfReferenceGuard) {
+        self.transform_reference = Some(reference.get_component().unwrap());
+    }
+}
+
+impl PointLight {
+    ///Creates a new light with the given color, intensity and range
+    #[must_use]
+    pub fn new(color: Color, intensity: f32, range: f32) -> Self {
+        Self {
+            color,
+            intensity,
+            range,
+            ..Default::default()
+        }
+    }
+
+    ///Returns the position of the light in the world
+    #[must_use]
+    pub fn position(&self) -> Vec3 {
+        let matrix = self.transform_reference.as_ref().unwrap().borrow().matrix();
+        Vec3::new(matrix.m03, matrix.m13, matrix.m23)
+    }
+}
+
+///Light shining in a cone from a single point, like a flashlight
+///
+///The cone points along the forward (+Z) axis of the [`Transform`]. Like the [`PointLight`], spot
+///lights require the [`crate::rendering::extensions::clustered::LightCulling`] extension
+#[derive(Debug)]
+pub struct SpotLight {
+    ///Color of the light
+    pub color: Color,
+    ///Intensity of the light, the color is multiplied by it
+    pub intensity: f32,
+    ///Distance at which the light fades out completely
+    pub range: f32,
+    ///Angle of the cone in degrees, inside of which the light has the full intensity
+    pub inner_angle: f32,
+    ///Angle of the cone in degrees, outside of which there is no light
+    pub outer_angle: f32,
+    transform_reference: Option<ComponentReference<Transform>>,
+}
+
+impl Default for SpotLight {
+    ///The default light has the following settings:
+    /// - Color: white
+    /// - Intensity: 1
+    /// - Range: 10
+    /// - Inner angle: 20 degrees
+    /// - Outer angle: 30 degrees
+    fn default() -> Self {
+        Self {
+            color: Color::white(),
+            intensity: 1.0,
+            range: 10.0,
+            inner_angle: 20.0,
+            outer_angle: 30.0,
+            transform_reference: None,
+        }
+    }
+}
+
+impl Component for SpotLight {
+    #[as_any]
+    #[dependencies(Transform)]
+    fn mew() -> Self
+    where
+        Self: Sized,
+    {
+        Self::default()
+    }
+
+    fn set_self_reference(&mut self, reference: crate::ecs::SelfReferenceGuard) {
+        self.transform_reference = Some(reference.get_component().unwrap());
+    }
+}
+
+impl SpotLight {
+    ///Creates a new light with the given color, intensity, range and outer angle of the cone in
+    ///degrees, the inner angle is two thirds of the outer one
+    #[must_use]
+    pub fn new(color: Color, intensity: f32, range: f32, angle: f32) -> Self {
+        Self {
+            color,
+            intensity,
+            range,
+            inner_angle: angle * 2.0 / 3.0,
+            outer_angle: angle,
+            ..Default::default()
+        }
+    }
+
+    ///Returns the position of the light in the world
+    #[must_use]
+    pub fn position(&self) -> Vec3 {
+        let matrix = self.transform_reference.as_ref().unwrap().borrow().matrix();
+        Vec3::new(matrix.m03, matrix.m13, matrix.m23)
+    }
+
+    ///Returns the direction the light shines in
+    #[must_use]
+    pub fn direction(&self) -> Vec3 {
+        let matrix = self.transform_reference.as_ref().unwrap().borrow().matrix();
+        (matrix * Vec4::new(0.0, 0.0, 1.0, 0.0)).xyz().normalized()
+    }
+}
@@ -0,0 +1,175 @@
+use lunar_engine_derive::as_any;
+
+use crate::{
+    asset_managment::{AssetStore, UUID},
+    assets,
+    ecs::{Component, ComponentReference, World},
+    math::{Vec3, Vector},
+    rendering::extensions::frustum_culling::screen_size,
+};
+
+use super::{camera::ProjectionType, mesh::Mesh};
+
+///Mesh used by a [`LodGroup`] while the mesh covers enough of the screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LodLevel {
+    ///Id of the mesh asset
+    pub mesh: UUID,
+    ///Fraction of the height of the screen the bounding sphere of the mesh must cover for the
+    ///level to be used
+    pub min_screen_size: f32,
+}
+
+impl LodLevel {
+    ///Creates a new level
+    #[must_use]
+    pub const fn new(mesh: UUID, min_screen_size: f32) -> Self {
+        Self {
+            mesh,
+            min_screen_size,
+        }
+    }
+}
+
+///Level of detail component, swaps the mesh asset of the [`Mesh`] component of the entity for
+///simpler ones as it gets smaller on the screen
+///
+///The levels are picked for the main camera by the
+///[`Base`](crate::rendering::extensions::Base) extensions before the meshes are batched, the
+///extensions rendering with their own cameras draw the levels picked for the main one. The size
+///on the screen is measured with the bounds of the mesh of the first level
+///
+///```
+///# use lunar_engine::components::lod::{LodGroup, LodLevel};
+///let lod = LodGroup::new(vec![
+///    LodLevel::new(1, 0.5),
+///    LodLevel::new(2, 0.1),
+///    LodLevel::new(3, 0.0),
+///]);
+///assert_eq!(lod.level_for_size(0.75), Some(0));
+///assert_eq!(lod.level_for_size(0.25), Some(1));
+///assert_eq!(lod.level_for_size(0.01), Some(2));
+///```
+#[derive(Debug, Default)]
+pub struct LodGroup {
+    //Sorted from the largest screen size to the smallest
+    levels: Vec<LodLevel>,
+    mesh_reference: Option<ComponentReference<Mesh>>,
+    current: Option<usize>,
+}
+
+impl Component for LodGroup {
+    #[as_any]
+
+    fn mew() -> Self
+    where
+        Self: Sized,
+    {
+        Self::default()
+    }
+
+    fn set_self_reference(&mut self, reference: crate::ecs::SelfReferenceGuard) {
+        self.mesh_reference = reference.get_component().ok();
+    }
+}
+
+impl LodGroup {
+    ///Creates a new group with the levels, in any order
+    #[must_use]
+    pub fn new(levels: Vec<LodLevel>) -> Self {
+        let mut levels = levels;
+        levels.sort_by(|a, b| b.min_screen_size.total_cmp(&a.min_screen_size));
+        Self {
+            levels,
+            mesh_reference: None,
+            current: None,
+        }
+    }
+
+    ///Adds a level using the `mesh` while the mesh covers at least `min_screen_size` of the
+    ///height of the screen
+    pub fn add_level(&mut self, mesh: UUID, min_screen_size: f32) {
+        let index = self
+            .levels
+            .partition_point(|l| l.min_screen_size >= min_screen_size);
+        self.levels
+            .insert(index, LodLevel::new(mesh, min_screen_size));
+        self.current = None;
+    }
+
+    ///Returns the levels, from the most detailed one to the least detailed one
+    #[must_use]
+    pub fn get_levels(&self) -> &[LodLevel] {
+        &self.levels
+    }
+
+    ///Returns the index of the level that was picked last
+    ///
+    ///Returns none if no level was picked yet
+    #[must_use]
+    pub const fn get_current_level(&self) -> Option<usize> {
+        self.current
+    }
+
+    ///Returns the index of the level used when the mesh covers `screen_size` of the height of the
+    ///screen, the least detailed level if it's smaller than all of the thresholds
+    ///
+    ///Returns none if the group has no levels
+    #[must_use]
+    pub fn level_for_size(&self, screen_size: f32) -> Option<usize> {
+        self.levels
+            .iter()
+            .position(|l| screen_size >= l.min_screen_size)
+            .or_else(|| self.levels.len().checked_sub(1))
+    }
+
+    ///Picks the level for the `screen_size` and sets its mesh on the mesh component
+    fn select(&mut self, screen_size: f32) {
+        let Some(level) = self.level_for_size(screen_size) else {
+            return;
+        };
+        self.current = Some(level);
+
+        let Some(mesh) = &self.mesh_reference else {
+            return;
+        };
+        let id = self.levels[level].mesh;
+        let mut mesh = mesh.borrow_mut();
+        if mesh.get_mesh_id() != Some(id) {
+            mesh.set_mesh(id);
+        }
+    }
+}
+
+///Picks the levels of all the groups in the world for a camera at the `position` with the
+///`projection`
+pub(crate) fn select_levels(
+    world: &World,
+    assets: &AssetStore,
+    position: Vec3,
+    projection: &ProjectionType,
+) {
+    let Some(groups) = world.get_all_components::<LodGroup>() else {
+        return;
+    };
+
+    for group in groups {
+        let mut group = group.borrow_mut();
+        let (Some(mesh), Some(first)) = (group.mesh_reference.clone(), group.levels.first()) else {
+            continue;
+        };
+        let Ok(asset) = assets.get_by_id::<assets::Mesh>(first.mesh) else {
+            continue;
+        };
+        let extent = asset.borrow().get_extent();
+
+        let size = {
+            let mesh = mesh.borrow();
+            let transform = mesh.get_transform();
+            let t = transform.borrow();
+            let radius = extent * f32::max(t.scale.x, f32::max(t.scale.y, t.scale.z));
+            screen_size(radius, (t.position - position).length(), projection)
+        };
+        group.select(size);
+    }
+}
@@ -1,15 +1,43 @@
 #![allow(dead_code)]
 
+use std::{
+    cell::Cell,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
 use lunar_engine_derive::as_any;
 
 use crate::{
     asset_managment::UUID,
-    ecs::{Component, ComponentReference},
-    math::Mat4x4,
+    ecs::{self, Component, ComponentReference},
+    math::Mat3x3,
+    structures::InstanceData,
 };
 
 use super::transform::Transform;
 
+///Render layer of the new meshes
+pub const DEFAULT_LAYER: u32 = 1;
+///Layer mask including all the render layers
+pub const ALL_LAYERS: u32 = u32::MAX;
+
+//Incremented every time a static mesh changes, tells the renderer to update the static instances
+static STATIC_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+///Returns the number of times static meshes were changed
+pub(crate) fn static_generation() -> u64 {
+    STATIC_GENERATION.load(Ordering::Relaxed)
+}
+
+//Incremented every time the material of a mesh is swapped, tells the renderer to move the mesh to
+//the instances of its new material
+static MATERIAL_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+///Returns the number of times the materials of meshes were swapped
+pub(crate) fn material_generation() -> u64 {
+    MATERIAL_GENERATION.load(Ordering::Relaxed)
+}
+
 #[derive(Debug)]
 ///Mesh component used for rendering
 pub struct Mesh {
@@ -18,6 +46,15 @@ pub struct Mesh {
 
     material_id: Option<UUID>,
     transform_reference: Option<ComponentReference<Transform>>,
+    entity: Option<ecs::UUID>,
+    r#static: bool,
+    layers: u32,
+    cast_shadows: bool,
+    receive_shadows: bool,
+    max_draw_distance: Option<f32>,
+    min_screen_size: Option<f32>,
+    //Instance data of a static mesh, computed on first use
+    frozen: Cell<Option<InstanceData>>,
 }
 
 impl Default for Mesh {
@@ -27,6 +64,14 @@ impl Default for Mesh {
             mesh_id: None,
             material_id: None,
             transform_reference: None,
+            entity: None,
+            r#static: false,
+            layers: DEFAULT_LAYER,
+            cast_shadows: true,
+            receive_shadows: true,
+            max_draw_distance: None,
+            min_screen_size: None,
+            frozen: Cell::new(None),
         }
     }
 }
@@ -44,6 +89,7 @@ impl Component for Mesh {
     #[allow(unused_variables)]
     fn set_self_reference(&mut self, reference: crate::ecs::SelfReferenceGuard) {
         self.transform_reference = Some(reference.get_component().unwrap());
+        self.entity = reference.get_entity_id().ok();
     }
 }
 
@@ -56,6 +102,14 @@ impl Mesh {
             mesh_id: Some(mesh),
             material_id: Some(material),
             transform_reference: None,
+            entity: None,
+            r#static: false,
+            layers: DEFAULT_LAYER,
+            cast_shadows: true,
+            receive_shadows: true,
+            max_draw_distance: None,
+            min_screen_size: None,
+            frozen: Cell::new(None),
         }
     }
     ///Whether or not this mesh is rendered
@@ -68,6 +122,83 @@ impl Mesh {
         self.visible = value;
     }
 
+    ///Returns the bit mask of the render layers the mesh is on
+    #[must_use]
+    pub const fn get_layers(&self) -> u32 {
+        self.layers
+    }
+
+    ///Sets the bit mask of the render layers the mesh is on, extensions only render the meshes
+    ///on the layers included in their layer mask, see
+    ///[`Base::layers`](crate::rendering::extensions::Base::layers)
+    pub fn set_layers(&mut self, layers: u32) {
+        self.layers = layers;
+    }
+
+    ///Returns `true` if the mesh is on any of the layers in the mask
+    #[must_use]
+    pub const fn is_on_layers(&self, mask: u32) -> bool {
+        self.layers & mask != 0
+    }
+
+    ///Whether or not this mesh is rendered into the shadow map
+    #[must_use]
+    pub const fn get_cast_shadows(&self) -> bool {
+        self.cast_shadows
+    }
+
+    ///Sets whether or not this mesh is rendered into the shadow map, e.g. large ground planes
+    ///that nothing is below of don't need to cast shadows
+    pub fn set_cast_shadows(&mut self, value: bool) {
+        self.cast_shadows = value;
+    }
+
+    ///Whether or not the shadows are applied to this mesh by the lit materials
+    #[must_use]
+    pub const fn get_receive_shadows(&self) -> bool {
+        self.receive_shadows
+    }
+
+    ///Sets whether or not the shadows are applied to this mesh by the lit materials, thin objects
+    ///prone to shadow acne can opt out
+    pub fn set_receive_shadows(&mut self, value: bool) {
+        self.receive_shadows = value;
+        if self.r#static {
+            self.invalidate_static();
+        }
+    }
+
+    ///Returns the distance from the camera beyond which the mesh is culled
+    ///
+    ///Returns none if the default of the culling extension is used
+    #[must_use]
+    pub const fn get_max_draw_distance(&self) -> Option<f32> {
+        self.max_draw_distance
+    }
+
+    ///Sets the distance from the camera beyond which the mesh is culled, overriding
+    ///[`Base::max_draw_distance`](crate::rendering::extensions::frustum_culling::Base::max_draw_distance),
+    ///`None` uses the default of the extension
+    pub fn set_max_draw_distance(&mut self, value: Option<f32>) {
+        self.max_draw_distance = value;
+    }
+
+    ///Returns the fraction of the height of the screen the mesh must cover to be rendered
+    ///
+    ///Returns none if the default of the culling extension is used
+    #[must_use]
+    pub const fn get_min_screen_size(&self) -> Option<f32> {
+        self.min_screen_size
+    }
+
+    ///Sets the fraction of the height of the screen the bounding sphere of the mesh must cover
+    ///to be rendered, overriding
+    ///[`Base::min_screen_size`](crate::rendering::extensions::frustum_culling::Base::min_screen_size),
+    ///`None` uses the default of the extension
+    pub fn set_min_screen_size(&mut self, value: Option<f32>) {
+        self.min_screen_size = value;
+    }
+
     ///Changes the asset used by the component
     ///Does not chedk if the provided id is valid
     pub fn set_mesh(&mut self, id: UUID) {
@@ -84,8 +215,14 @@ impl Mesh {
 
     ///Changes the asset used by the component
     ///Does not check if the provided id is valid
+    ///
+    ///Swapping the material at runtime, e.g. for damage states, only moves the mesh to the
+    ///instances of the new material, the instance buffers of the other meshes are not rebuilt
     pub fn set_material(&mut self, id: UUID) {
-        self.material_id = Some(id);
+        if self.material_id != Some(id) {
+            self.material_id = Some(id);
+            MATERIAL_GENERATION.fetch_add(1, Ordering::Relaxed);
+        }
     }
 
     ///Returns asset id of the component
@@ -102,13 +239,59 @@ impl Mesh {
         self.transform_reference.clone().unwrap()
     }
 
+    ///Returns the id of the entity the component is attached to
+    ///
+    ///Returns none if it was not added to an entity in a world
     #[must_use]
-    pub(crate) fn get_matrix(&self) -> Mat4x4 {
-        self.transform_reference
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .matrix()
-            .transpose()
+    pub const fn get_entity_id(&self) -> Option<ecs::UUID> {
+        self.entity
+    }
+
+    ///Whether or not this mesh is static
+    #[must_use]
+    pub const fn get_static(&self) -> bool {
+        self.r#static
+    }
+
+    ///Sets whether or not this mesh is static
+    ///
+    ///The transformation of a static mesh, including the transformations of all of its parents, is
+    ///computed once and then frozen, the renderer skips static meshes when updating the instance
+    ///data every frame. Changes to the transform of a static mesh have no effect until
+    ///[`Mesh::invalidate_static`] is called
+    pub fn set_static(&mut self, value: bool) {
+        self.r#static = value;
+        self.invalidate_static();
+    }
+
+    ///Recomputes the frozen transformation of a static mesh
+    ///
+    ///Must be called after moving a static mesh or any of its parents
+    pub fn invalidate_static(&mut self) {
+        self.frozen.set(None);
+        STATIC_GENERATION.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub(crate) fn get_instance_data(&self) -> InstanceData {
+        if self.r#static {
+            if let Some(data) = self.frozen.get() {
+                return data;
+            }
+        }
+
+        let matrix = self.transform_reference.as_ref().unwrap().borrow().matrix();
+        let data = InstanceData {
+            transform: matrix.transpose(),
+            normal: Mat3x3::normal_matrix(&matrix).transpose(),
+            receive_shadows: f32::from(u8::from(self.receive_shadows)),
+            //Set by the renderers from the material
+            texture_index: 0,
+        };
+
+        if self.r#static {
+            self.frozen.set(Some(data));
+        }
+        data
     }
 }
@@ -0,0 +1,220 @@
+use std::{any::Any, collections::BTreeMap};
+
+use lunar_engine_derive::{as_any, as_reflect};
+
+use crate::ecs::{reflect::Reflect, Component};
+
+use crate as lunar_engine;
+
+///Value stored in [`Metadata`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Value {
+    ///Boolean value
+    Bool(bool),
+    ///Integer value
+    Int(i64),
+    ///Floating point value
+    Float(f64),
+    ///String value
+    String(String),
+}
+
+impl Value {
+    ///Returns the value if it's a boolean
+    #[must_use]
+    pub const fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    ///Returns the value if it's an integer
+    #[must_use]
+    pub const fn as_int(&self) -> Option<i64> {
+        match self {
+            Self::Int(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    ///Returns the value if it's a number, integers are converted to floating point
+    #[must_use]
+    pub const fn as_float(&self) -> Option<f64> {
+        match self {
+            Self::Float(v) => Some(*v),
+            Self::Int(v) => Some(*v as f64),
+            _ => None,
+        }
+    }
+
+    ///Returns the value if it's a string
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Self::Int(value.into())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<u32> for Value {
+    fn from(value: u32) -> Self {
+        Self::Int(value.into())
+    }
+}
+
+impl From<f32> for Value {
+    fn from(value: f32) -> Self {
+        Self::Float(value.into())
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_owned())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+///Component storing arbitrary user data on an entity, as a map of string keys to values
+///
+///The entries are exposed as fields through [`Reflect`], sorted by their keys
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Metadata {
+    values: BTreeMap<String, Value>,
+}
+
+impl Component for Metadata {
+    #[as_any]
+    #[as_reflect]
+
+    fn mew() -> Self
+    where
+        Self: Sized,
+    {
+        Self::default()
+    }
+}
+
+impl Metadata {
+    ///Creates empty metadata
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            values: BTreeMap::new(),
+        }
+    }
+
+    ///Sets the value of the key, returns the previous value
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<Value>) -> Option<Value> {
+        self.values.insert(key.into(), value.into())
+    }
+
+    ///Returns the value of the key
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.values.get(key)
+    }
+
+    ///Returns the value of the key, `None` if it doesn't exist or if it's not a boolean
+    #[must_use]
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        self.get(key)?.as_bool()
+    }
+
+    ///Returns the value of the key, `None` if it doesn't exist or if it's not an integer
+    #[must_use]
+    pub fn get_int(&self, key: &str) -> Option<i64> {
+        self.get(key)?.as_int()
+    }
+
+    ///Returns the value of the key, `None` if it doesn't exist or if it's not a number
+    #[must_use]
+    pub fn get_float(&self, key: &str) -> Option<f64> {
+        self.get(key)?.as_float()
+    }
+
+    ///Returns the value of the key, `None` if it doesn't exist or if it's not a string
+    #[must_use]
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key)?.as_str()
+    }
+
+    ///Removes the key, returns its value
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        self.values.remove(key)
+    }
+
+    ///Returns `true` if the key exists
+    #[must_use]
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.values.contains_key(key)
+    }
+
+    ///Returns an iterator over the keys and their values, sorted by the keys
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.values.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    ///Returns the number of keys
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    ///Returns `true` if there are no keys
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl Reflect for Metadata {
+    fn type_name(&self) -> &'static str {
+        "Metadata"
+    }
+
+    fn field_names(&self) -> Vec<&str> {
+        self.values.keys().map(String::as_str).collect()
+    }
+
+    fn field(&self, name: &str) -> Option<&dyn Any> {
+        self.values.get(name).map(|v| v as &dyn Any)
+    }
+
+    fn field_mut(&mut self, name: &str) -> Option<&mut dyn Any> {
+        self.values.get_mut(name).map(|v| v as &mut dyn Any)
+    }
+}
@@ -1,9 +1,28 @@
 //!Implemented components
+///Skeletal animation component
+pub mod animator;
 ///Camera component
 pub mod camera;
+///Light components
+pub mod light;
+///Level of detail component
+pub mod lod;
 ///Mesh component
 pub mod mesh;
+///User data component
+pub mod metadata;
+///Sprite component
+pub mod sprite;
+///Sprite sheet animation component
+pub mod sprite_animator;
 #[cfg(test)]
 mod tests;
+///Screen and world space text component
+pub mod text;
 ///Transformation component
 pub mod transform;
+///World space text component
+pub mod world_text;
+
+pub use metadata::Metadata;
+pub use world_text::WorldText;
@@ -0,0 +1,118 @@
+use lunar_engine_derive::{as_any, dependencies};
+
+use crate as lunar_engine;
+
+use crate::{
+    asset_managment::UUID,
+    ecs::{Component, ComponentReference},
+    math::{Mat4x4, Vec2},
+    structures::Color,
+};
+
+use super::{mesh::DEFAULT_LAYER, sprite_animator::SpriteAnimator, transform::Transform};
+
+///Textured quad rendered by the [`SpriteRenderer`](crate::rendering::extensions::SpriteRenderer)
+///extension
+///
+///The quad is 1 unit wide and tall, centered on the position of the entity and lying in its
+///local xy plane, the size is set with the scale of the transform.
+#[derive(Debug)]
+pub struct Sprite {
+    ///Texture of the sprite, usually an atlas shared by many sprites
+    pub texture: Option<UUID>,
+    ///Texture coordinates of the top left corner of the sprite in the texture and its size in
+    ///texture coordinates, see [`SpriteSheet::frame_uv`](super::sprite_animator::SpriteSheet::frame_uv)
+    pub uv: (Vec2, Vec2),
+    ///Color the texture is multiplied by
+    pub color: Color,
+    ///Sprites with a lower order are drawn first, sprites with the same order are batched by
+    ///their texture
+    pub order: i32,
+    ///Whether or not the sprite is rendered
+    pub visible: bool,
+    layers: u32,
+    transform_reference: Option<ComponentReference<Transform>>,
+}
+
+impl Component for Sprite {
+    #[as_any]
+    #[dependencies(Transform)]
+    fn mew() -> Self
+    where
+        Self: Sized,
+    {
+        Self::default()
+    }
+
+    fn set_self_reference(&mut self, reference: crate::ecs::SelfReferenceGuard) {
+        self.transform_reference = Some(reference.get_component().unwrap());
+    }
+}
+
+impl Default for Sprite {
+    fn default() -> Self {
+        Self {
+            texture: None,
+            uv: (Vec2::new(0.0, 0.0), Vec2::new(1.0, 1.0)),
+            color: Color::white(),
+            order: 0,
+            visible: true,
+            layers: DEFAULT_LAYER,
+            transform_reference: None,
+        }
+    }
+}
+
+impl Sprite {
+    ///Creates a new sprite showing the whole `texture`
+    #[must_use]
+    pub fn new(texture: UUID) -> Self {
+        Self {
+            texture: Some(texture),
+            ..Default::default()
+        }
+    }
+
+    ///Creates a new sprite showing a region of the `texture`, starting at `offset` with the size
+    ///of `size`, both in texture coordinates
+    #[must_use]
+    pub fn from_region(texture: UUID, offset: Vec2, size: Vec2) -> Self {
+        Self {
+            texture: Some(texture),
+            uv: (offset, size),
+            ..Default::default()
+        }
+    }
+
+    ///Returns the bit mask of the render layers the sprite is on
+    #[must_use]
+    pub const fn get_layers(&self) -> u32 {
+        self.layers
+    }
+
+    ///Sets the bit mask of the render layers the sprite is on, see
+    ///[`Mesh::set_layers`](super::mesh::Mesh::set_layers)
+    pub fn set_layers(&mut self, layers: u32) {
+        self.layers = layers;
+    }
+
+    ///Returns `true` if the sprite is on any of the layers in the mask
+    #[must_use]
+    pub const fn is_on_layers(&self, mask: u32) -> bool {
+        self.layers & mask != 0
+    }
+
+    ///Shows the current frame of the `animator`
+    pub fn set_frame(&mut self, animator: &SpriteAnimator) {
+        self.uv = animator.get_uv();
+    }
+
+    ///Returns the transformation matrix of the sprite
+    ///
+    ///# Panics
+    ///Panics if the component is not attached to an entity
+    #[must_use]
+    pub fn matrix(&self) -> Mat4x4 {
+        self.transform_reference.as_ref().unwrap().borrow().matrix()
+    }
+}
@@ -0,0 +1,300 @@
+use lunar_engine_derive::as_any;
+
+use crate::{ecs::Component, math::Vec2, time};
+
+///Sprite sheet made of a grid of equally sized frames
+///
+///Frames are numbered left to right, top to bottom, starting at 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpriteSheet {
+    ///Number of frames in a row
+    pub columns: u32,
+    ///Number of frames in a column
+    pub rows: u32,
+}
+
+impl SpriteSheet {
+    ///Creates a new sprite sheet
+    #[must_use]
+    pub const fn new(columns: u32, rows: u32) -> Self {
+        Self { columns, rows }
+    }
+
+    ///Returns the number of frames in the sheet
+    #[must_use]
+    pub const fn frame_count(&self) -> u32 {
+        self.columns * self.rows
+    }
+
+    ///Returns the texture coordinates of the top left corner of the frame and the size of the
+    ///frame in texture coordinates
+    #[must_use]
+    pub fn frame_uv(&self, frame: u32) -> (Vec2, Vec2) {
+        let size = Vec2::new(1.0 / self.columns as f32, 1.0 / self.rows as f32);
+        let offset = Vec2::new(
+            (frame % self.columns) as f32 * size.x,
+            (frame / self.columns) as f32 * size.y,
+        );
+        (offset, size)
+    }
+}
+
+impl Default for SpriteSheet {
+    fn default() -> Self {
+        Self::new(1, 1)
+    }
+}
+
+///How the animation is played
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PlaybackMode {
+    ///The animation stops at the last frame
+    #[default]
+    Once,
+    ///The animation restarts after the last frame
+    Loop,
+    ///The animation is played forward and then backwards
+    PingPong,
+}
+
+///Event emitted when the animation reaches a frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnimationEvent {
+    ///Index of the frame in the animation, not in the sprite sheet
+    pub frame: usize,
+    ///Name of the event
+    pub name: String,
+}
+
+///Sequence of sprite sheet frames
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteAnimation {
+    ///Frames of the sprite sheet, in the order they are shown
+    pub frames: Vec<u32>,
+    ///Frames per second
+    pub fps: f32,
+    ///How the animation is played
+    pub mode: PlaybackMode,
+    ///Events emitted when the animation reaches their frame
+    pub events: Vec<AnimationEvent>,
+}
+
+impl SpriteAnimation {
+    ///Creates a new animation without any events
+    #[must_use]
+    pub const fn new(frames: Vec<u32>, fps: f32, mode: PlaybackMode) -> Self {
+        Self {
+            frames,
+            fps,
+            mode,
+            events: Vec::new(),
+        }
+    }
+
+    ///Creates a new animation showing frames of the sprite sheet from `first` to `last`
+    ///inclusive
+    #[must_use]
+    pub fn from_range(first: u32, last: u32, fps: f32, mode: PlaybackMode) -> Self {
+        Self::new((first..=last).collect(), fps, mode)
+    }
+
+    ///Adds an event emitted when the animation reaches the `frame`
+    #[must_use]
+    pub fn with_event(mut self, frame: usize, name: &str) -> Self {
+        self.events.push(AnimationEvent {
+            frame,
+            name: name.to_owned(),
+        });
+        self
+    }
+}
+
+///Steps through the frames of a sprite sheet
+///
+///The animator only keeps track of the current frame, the renderer uses [`SpriteAnimator::get_uv`]
+///to display it. Events reached by the animation are queued and can be retrieved with
+///[`SpriteAnimator::take_events`]
+#[derive(Debug)]
+pub struct SpriteAnimator {
+    ///Sprite sheet the frames are taken from
+    pub sheet: SpriteSheet,
+    ///Playback speed multiplier
+    pub speed: f32,
+    animation: Option<SpriteAnimation>,
+    playing: bool,
+    //Index of the current frame in the animation
+    position: usize,
+    //Fraction of the current frame that has elapsed
+    time: f32,
+    //Whether or not a ping pong animation is playing backwards
+    reverse: bool,
+    events: Vec<AnimationEvent>,
+}
+
+impl Component for SpriteAnimator {
+    #[as_any]
+    fn mew() -> Self
+    where
+        Self: Sized,
+    {
+        Self::new(SpriteSheet::default())
+    }
+
+    fn update(&mut self) {
+        self.advance(time::delta_time());
+    }
+}
+
+impl SpriteAnimator {
+    ///Creates a new animator for the sprite sheet
+    #[must_use]
+    pub const fn new(sheet: SpriteSheet) -> Self {
+        Self {
+            sheet,
+            speed: 1.0,
+            animation: None,
+            playing: false,
+            position: 0,
+            time: 0.0,
+            reverse: false,
+            events: Vec::new(),
+        }
+    }
+
+    ///Starts playing the animation from the first frame
+    pub fn play(&mut self, animation: SpriteAnimation) {
+        self.animation = Some(animation);
+        self.playing = true;
+        self.position = 0;
+        self.time = 0.0;
+        self.reverse = false;
+        self.emit_events();
+    }
+
+    ///Pauses the animation
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    ///Resumes the paused animation
+    pub fn resume(&mut self) {
+        self.playing = self.animation.is_some();
+    }
+
+    ///Stops the animation and removes it
+    pub fn stop(&mut self) {
+        self.animation = None;
+        self.playing = false;
+        self.position = 0;
+    }
+
+    ///Returns `true` if an animation is playing
+    #[must_use]
+    pub const fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    ///Returns the current animation
+    #[must_use]
+    pub const fn get_animation(&self) -> Option<&SpriteAnimation> {
+        self.animation.as_ref()
+    }
+
+    ///Returns the index of the current frame in the animation
+    #[must_use]
+    pub const fn get_position(&self) -> usize {
+        self.position
+    }
+
+    ///Returns the current frame of the sprite sheet
+    #[must_use]
+    pub fn get_frame(&self) -> u32 {
+        self.animation
+            .as_ref()
+            .and_then(|a| a.frames.get(self.position).copied())
+            .unwrap_or_default()
+    }
+
+    ///Returns the texture coordinates of the current frame, see [`SpriteSheet::frame_uv`]
+    #[must_use]
+    pub fn get_uv(&self) -> (Vec2, Vec2) {
+        self.sheet.frame_uv(self.get_frame())
+    }
+
+    ///Returns all the events emitted since the last call, clearing the queue
+    #[must_use]
+    pub fn take_events(&mut self) -> Vec<AnimationEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    ///Advances the animation by `delta` seconds
+    ///
+    ///Called automatically every frame
+    pub fn advance(&mut self, delta: f32) {
+        let Some(animation) = &self.animation else {
+            return;
+        };
+        if !self.playing || animation.frames.is_empty() {
+            return;
+        }
+
+        self.time += delta * animation.fps * self.speed;
+        while self.time >= 1.0 && self.playing {
+            self.time -= 1.0;
+            self.step();
+        }
+    }
+
+    ///Moves to the next frame
+    fn step(&mut self) {
+        let animation = self.animation.as_ref().unwrap();
+        let last = animation.frames.len() - 1;
+
+        match animation.mode {
+            PlaybackMode::Once => {
+                if self.position == last {
+                    self.playing = false;
+                    return;
+                }
+                self.position += 1;
+            }
+            PlaybackMode::Loop => {
+                self.position = if self.position == last {
+                    0
+                } else {
+                    self.position + 1
+                };
+            }
+            PlaybackMode::PingPong => {
+                if last == 0 {
+                    return;
+                }
+                if (self.reverse && self.position == 0) || (!self.reverse && self.position == last)
+                {
+                    self.reverse = !self.reverse;
+                }
+                if self.reverse {
+                    self.position -= 1;
+                } else {
+                    self.position += 1;
+                }
+            }
+        }
+
+        self.emit_events();
+    }
+
+    ///Queues the events of the current frame
+    fn emit_events(&mut self) {
+        let Some(animation) = &self.animation else {
+            return;
+        };
+        self.events.extend(
+            animation
+                .events
+                .iter()
+                .filter(|e| e.frame == self.position)
+                .cloned(),
+        );
+    }
+}
@@ -1,5 +1,15 @@
-use super::{mesh::Mesh, transform::Transform};
+use super::{
+    animator::{AnimationClip, Animator, Armature, Channel, ChannelValues, Interpolation, Joint},
+    camera::{look_rotation, CinematicCamera, LookTarget},
+    lod::{LodGroup, LodLevel},
+    mesh::Mesh,
+    metadata::{Metadata, Value},
+    sprite_animator::{PlaybackMode, SpriteAnimation, SpriteAnimator, SpriteSheet},
+    transform::Transform,
+    world_text::{TextOrientation, WorldText},
+};
 use crate::ecs::*;
+use crate::math::{ApproxEq, Mat4x4, Quaternion, Vec2, Vec3, Vec4, Vector};
 
 #[test]
 fn test_mesh() {
@@ -13,6 +23,25 @@ fn test_mesh() {
     m.borrow_mut().set_mesh(123);
 }
 
+#[test]
+fn lod_levels() {
+    let mut lod = LodGroup::new(vec![LodLevel::new(3, 0.0), LodLevel::new(1, 0.5)]);
+    assert_eq!(lod.level_for_size(0.1), Some(1));
+    assert_eq!(lod.get_current_level(), None);
+
+    lod.add_level(2, 0.25);
+    let meshes = lod.get_levels().iter().map(|l| l.mesh).collect::<Vec<_>>();
+    assert_eq!(meshes, [1, 2, 3]);
+    assert_eq!(lod.level_for_size(1.0), Some(0));
+    assert_eq!(lod.level_for_size(0.25), Some(1));
+    assert_eq!(lod.level_for_size(0.1), Some(2));
+
+    //Without a level for the size the least detailed one is used
+    let lod = LodGroup::new(vec![LodLevel::new(1, 0.5)]);
+    assert_eq!(lod.level_for_size(0.1), Some(0));
+    assert_eq!(LodGroup::default().level_for_size(0.1), None);
+}
+
 #[test]
 fn test_transform() {
     let mut e = Entity::new();
@@ -22,3 +51,324 @@ fn test_transform() {
     let t = e.get_component::<Transform>().unwrap();
     _ = t.borrow_mut().matrix();
 }
+
+#[test]
+fn look_rotation_test() {
+    for d in [
+        Vec3::new(0.0, 0.0, 1.0),
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(-1.0, 2.0, 3.0),
+        Vec3::new(0.5, -1.0, -2.0),
+    ] {
+        let rotation = look_rotation(d).unwrap();
+        let forward =
+            (Mat4x4::rotation_matrix_euler(&rotation) * Vec4::new(0.0, 0.0, 1.0, 1.0)).xyz();
+
+        assert!(forward.approx_eq(&d.normalized(), 0.0001));
+    }
+
+    assert!(look_rotation(Vec3::default()).is_none());
+}
+
+#[test]
+fn cinematic_path_test() {
+    let path = vec![
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(1.0, 1.0, 0.0),
+    ];
+    let camera = CinematicCamera::new(path.clone(), 2.0, LookTarget::Path);
+
+    //The spline goes through all the points
+    assert_eq!(camera.position_at(0.0), path[0]);
+    assert!(camera.position_at(0.5).approx_eq(&path[1], 0.0001));
+    assert!(camera.position_at(1.0).approx_eq(&path[2], 0.0001));
+}
+
+#[test]
+fn sprite_animator_test() {
+    let mut animator = SpriteAnimator::new(SpriteSheet::new(4, 2));
+
+    animator.play(
+        SpriteAnimation::from_range(4, 6, 10.0, PlaybackMode::PingPong).with_event(2, "step"),
+    );
+    assert_eq!(animator.get_frame(), 4);
+    assert_eq!(
+        animator.get_uv(),
+        (Vec2::new(0.0, 0.5), Vec2::new(0.25, 0.5))
+    );
+
+    let mut frames = Vec::new();
+    for _ in 0..6 {
+        animator.advance(0.1);
+        frames.push(animator.get_frame());
+    }
+    assert_eq!(frames, [5, 6, 5, 4, 5, 6]);
+
+    let events = animator.take_events();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].name, "step");
+    assert!(animator.take_events().is_empty());
+
+    //Stops at the last frame
+    animator.play(SpriteAnimation::from_range(0, 2, 10.0, PlaybackMode::Once));
+    animator.advance(1.0);
+    assert_eq!(animator.get_frame(), 2);
+    assert!(!animator.is_playing());
+}
+
+#[test]
+fn world_text_test() {
+    use crate::rendering::extensions::SdfFont;
+    use std::sync::Arc;
+
+    let mut world = World::new();
+    world.add_entity(
+        EntityBuilder::new()
+            .create_component(|| Transform {
+                position: Vec3::new(0.0, 2.0, 0.0),
+                ..Default::default()
+            })
+            .add_component::<WorldText>()
+            .create()
+            .unwrap(),
+    );
+
+    let text = world.get_all_components::<WorldText>().unwrap().remove(0);
+    let mut text = text.borrow_mut();
+    text.text = "AB".to_owned();
+    text.font = Arc::new(SdfFont::grid(0, 16, 6, ' ', 0.5));
+    text.size = 2.0;
+    text.offset = Vec3::new(0.0, 1.0, 0.0);
+    assert_eq!(text.position(), Vec3::new(0.0, 3.0, 0.0));
+
+    //Centered on the position, facing the camera
+    let right = Vec3::new(0.0, 0.0, 1.0);
+    let up = Vec3::new(0.0, 1.0, 0.0);
+    let quads = text.glyph_quads(right, up);
+    assert_eq!(quads.len(), 2);
+    assert!(quads[0].0[0].approx_eq(&Vec3::new(0.0, 4.0, -1.0), 0.0001));
+    assert!(quads[1].0[3].approx_eq(&Vec3::new(0.0, 2.0, 1.0), 0.0001));
+
+    //In the xy plane of the entity
+    text.orientation = TextOrientation::Fixed;
+    let quads = text.glyph_quads(right, up);
+    assert!(quads[0].0[0].approx_eq(&Vec3::new(-1.0, 4.0, 0.0), 0.0001));
+
+    text.fade = Some((10.0, 20.0));
+    assert!((text.opacity(Vec3::new(0.0, 3.0, 5.0)) - 1.0).abs() < 0.0001);
+    assert!((text.opacity(Vec3::new(0.0, 3.0, 15.0)) - 0.5).abs() < 0.0001);
+    assert!(text.opacity(Vec3::new(0.0, 3.0, 25.0)).abs() < 0.0001);
+}
+
+#[test]
+fn mesh_shadow_flags_test() {
+    let mut world = World::new();
+    world.add_entity(
+        EntityBuilder::new()
+            .add_component::<Transform>()
+            .add_component::<Mesh>()
+            .create()
+            .unwrap(),
+    );
+
+    let mesh = world.get_all_components::<Mesh>().unwrap().remove(0);
+    let mut mesh = mesh.borrow_mut();
+    assert!(mesh.get_cast_shadows());
+    assert!(mesh.get_receive_shadows());
+    assert!((mesh.get_instance_data().receive_shadows - 1.0).abs() < 0.0001);
+
+    mesh.set_cast_shadows(false);
+    mesh.set_receive_shadows(false);
+    assert!(!mesh.get_cast_shadows());
+    assert!(mesh.get_instance_data().receive_shadows.abs() < 0.0001);
+
+    //The frozen instance data of a static mesh is updated
+    mesh.set_static(true);
+    assert!(mesh.get_instance_data().receive_shadows.abs() < 0.0001);
+    mesh.set_receive_shadows(true);
+    assert!((mesh.get_instance_data().receive_shadows - 1.0).abs() < 0.0001);
+}
+
+//Two joints 1 unit above each other, the child is listed first
+fn test_armature() -> Armature {
+    let joint = |name: &str, parent, height: f32| Joint {
+        name: name.to_owned(),
+        parent,
+        translation: Vec3::new(0.0, if parent.is_some() { 1.0 } else { 0.0 }, 0.0),
+        rotation: Quaternion::identity(),
+        scale: Vec3::new(1.0, 1.0, 1.0),
+        inverse_bind: Mat4x4::translation_matrix(&Vec3::new(0.0, -height, 0.0)),
+    };
+    Armature::new(vec![joint("child", Some(1), 1.0), joint("root", None, 0.0)])
+}
+
+#[test]
+fn armature_test() {
+    let armature = test_armature();
+
+    for m in armature.joint_matrices(&armature.rest_pose()) {
+        assert!(m.approx_eq(&Mat4x4::identity(), 1e-5));
+    }
+
+    //Rotating the root moves the child with it
+    let mut pose = armature.rest_pose();
+    pose[1].rotation = Quaternion::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), 90.0);
+    let matrices = armature.joint_matrices(&pose);
+    let tip = matrices[0].transform3(Vec3::new(0.0, 2.0, 0.0));
+    assert!(tip.approx_eq(&Vec3::new(-2.0, 0.0, 0.0), 1e-5));
+
+    //Cycles are broken instead of looping forever
+    let mut joints = armature.joints().to_vec();
+    joints[1].parent = Some(0);
+    let armature = Armature::new(joints);
+    assert_eq!(armature.joint_matrices(&armature.rest_pose()).len(), 2);
+}
+
+#[test]
+fn animation_clip_test() {
+    let armature = test_armature();
+    let channel = |interpolation, values| Channel {
+        joint: 0,
+        times: vec![1.0, 2.0],
+        values: ChannelValues::Translation(values),
+        interpolation,
+    };
+    let up = |y| Vec3::new(0.0, y, 0.0);
+
+    let clip = AnimationClip::new(
+        "linear".to_owned(),
+        vec![channel(Interpolation::Linear, vec![up(1.0), up(3.0)])],
+    );
+    assert!((clip.duration() - 2.0).abs() < 1e-5);
+    //Clamped before the first and after the last keyframe
+    assert!(clip.sample(&armature, 0.0)[0]
+        .translation
+        .approx_eq(&up(1.0), 1e-5));
+    assert!(clip.sample(&armature, 1.5)[0]
+        .translation
+        .approx_eq(&up(2.0), 1e-5));
+    assert!(clip.sample(&armature, 5.0)[0]
+        .translation
+        .approx_eq(&up(3.0), 1e-5));
+    //Not animated
+    assert!(clip.sample(&armature, 1.5)[1]
+        .translation
+        .approx_eq(&up(0.0), 1e-5));
+
+    let clip = AnimationClip::new(
+        "step".to_owned(),
+        vec![channel(Interpolation::Step, vec![up(1.0), up(3.0)])],
+    );
+    assert!(clip.sample(&armature, 1.9)[0]
+        .translation
+        .approx_eq(&up(1.0), 1e-5));
+
+    //Flat tangents, eases in and out
+    let clip = AnimationClip::new(
+        "cubic".to_owned(),
+        vec![channel(
+            Interpolation::CubicSpline,
+            vec![up(0.0), up(1.0), up(0.0), up(0.0), up(3.0), up(0.0)],
+        )],
+    );
+    assert!(clip.sample(&armature, 1.5)[0]
+        .translation
+        .approx_eq(&up(2.0), 1e-5));
+    assert!(clip.sample(&armature, 1.25)[0].translation.y < 1.5);
+    assert!(clip.sample(&armature, 2.0)[0]
+        .translation
+        .approx_eq(&up(3.0), 1e-5));
+}
+
+#[test]
+fn animator_test() {
+    let armature = std::sync::Arc::new(test_armature());
+    let clip = std::sync::Arc::new(AnimationClip::new(
+        "rise".to_owned(),
+        vec![Channel {
+            joint: 1,
+            times: vec![0.0, 2.0],
+            values: ChannelValues::Translation(vec![
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(0.0, 2.0, 0.0),
+            ]),
+            interpolation: Interpolation::Linear,
+        }],
+    ));
+    let height = |a: &Animator| a.get_joint_matrices()[1].m13;
+
+    let mut animator = Animator::new(armature);
+    assert!(height(&animator).abs() < 1e-5);
+
+    animator.play(clip.clone());
+    animator.advance(0.5);
+    assert!((height(&animator) - 0.5).abs() < 1e-5);
+    //The child follows the root
+    assert!((animator.get_joint_matrices()[0].m13 - 0.5).abs() < 1e-5);
+
+    //Loops by default
+    animator.advance(2.0);
+    assert!((animator.get_time() - 0.5).abs() < 1e-5);
+
+    animator.mode = PlaybackMode::PingPong;
+    animator.advance(2.0);
+    assert!((animator.get_time() - 1.5).abs() < 1e-5);
+    animator.advance(0.75);
+    assert!((animator.get_time() - 0.75).abs() < 1e-5);
+
+    animator.mode = PlaybackMode::Once;
+    animator.play(clip);
+    animator.speed = 2.0;
+    animator.advance(5.0);
+    assert!(!animator.is_playing());
+    assert!((height(&animator) - 2.0).abs() < 1e-5);
+
+    animator.stop();
+    assert!(height(&animator).abs() < 1e-5);
+}
+
+#[test]
+fn metadata_test() {
+    use crate::ecs::reflect::Reflect;
+
+    let mut m = Metadata::new();
+    assert!(m.is_empty());
+
+    assert_eq!(m.set("name", "blahaj"), None);
+    m.set("health", 100);
+    m.set("speed", 2.5f32);
+    m.set("hostile", false);
+    assert_eq!(m.set("health", 90), Some(Value::Int(100)));
+
+    assert_eq!(m.len(), 4);
+    assert_eq!(m.get_str("name"), Some("blahaj"));
+    assert_eq!(m.get_int("health"), Some(90));
+    assert_eq!(m.get_float("health"), Some(90.0));
+    assert_eq!(m.get_float("speed"), Some(2.5));
+    assert_eq!(m.get_bool("hostile"), Some(false));
+    assert_eq!(m.get_int("name"), None);
+    assert_eq!(m.get("missing"), None);
+
+    //Entries are exposed as fields
+    let r: &mut dyn Reflect = &mut m;
+    assert_eq!(r.field_names(), vec!["health", "hostile", "name", "speed"]);
+    r.set("hostile", Value::Bool(true)).unwrap();
+    assert_eq!(m.get_bool("hostile"), Some(true));
+
+    assert_eq!(m.remove("name"), Some(Value::String("blahaj".to_owned())));
+    assert!(!m.contains_key("name"));
+    assert_eq!(
+        m.iter().map(|(k, _)| k).collect::<Vec<_>>(),
+        vec!["health", "hostile", "speed"]
+    );
+
+    let e = EntityBuilder::new()
+        .add_component::<Transform>()
+        .add_component::<Metadata>()
+        .create()
+        .unwrap();
+    let mut names = Vec::new();
+    e.for_each_reflect(|r| names.push((r.type_name(), r.field_names().len())));
+    assert_eq!(names, vec![("Transform", 3), ("Metadata", 0)]);
+}
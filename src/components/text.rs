@@ -0,0 +1,123 @@
+use lunar_engine_derive::{as_any, dependencies};
+
+use crate as lunar_engine;
+
+use crate::{
+    asset_managment::UUID,
+    ecs::{Component, ComponentReference},
+    math::{Vec2, Vec3},
+    structures::Color,
+};
+
+use super::transform::Transform;
+
+///Where the text is placed on the screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextPlacement {
+    ///The top left corner of the text is placed at a fixed position, in pixels from the top left
+    ///corner of the viewport
+    Screen(Vec2),
+    ///The text is centered on the position of the entity in the world, projected onto the screen,
+    ///and it keeps its size at any distance
+    World {
+        ///Offset of the anchor from the position of the entity, in world space
+        offset: Vec3,
+    },
+}
+
+impl Default for TextPlacement {
+    fn default() -> Self {
+        Self::Screen(Vec2::new(0.0, 0.0))
+    }
+}
+
+///Text drawn on top of the scene using a [`Font`](crate::assets::Font) asset
+///
+///Rendered by the [`TextRenderer`](crate::rendering::extensions::TextRenderer) extension, the
+///text may contain multiple lines and any of the characters baked into the font.
+#[derive(Debug)]
+pub struct Text {
+    ///Text to render
+    pub text: String,
+    ///Font asset used by the text
+    pub font: Option<UUID>,
+    ///Font size in pixels
+    pub size: f32,
+    ///Color of the text
+    pub color: Color,
+    ///Where the text is placed
+    pub placement: TextPlacement,
+    ///Whether or not the text is rendered
+    pub visible: bool,
+    transform_reference: Option<ComponentReference<Transform>>,
+}
+
+impl Component for Text {
+    #[as_any]
+    #[dependencies(Transform)]
+    fn mew() -> Self
+    where
+        Self: Sized,
+    {
+        Self::default()
+    }
+
+    fn set_self_reference(&mut self, reference: crate::ecs::SelfReferenceGuard) {
+        self.transform_reference = Some(reference.get_component().unwrap());
+    }
+}
+
+impl Default for Text {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            font: None,
+            size: 16.0,
+            color: Color::white(),
+            placement: TextPlacement::default(),
+            visible: true,
+            transform_reference: None,
+        }
+    }
+}
+
+impl Text {
+    ///Creates a new white text placed at the top left corner of the screen
+    #[must_use]
+    pub fn new(text: &str, font: UUID, size: f32) -> Self {
+        Self {
+            text: text.to_owned(),
+            font: Some(font),
+            size,
+            ..Default::default()
+        }
+    }
+
+    ///Sets where the text is placed
+    #[must_use]
+    pub const fn with_placement(mut self, placement: TextPlacement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    ///Sets the color of the text
+    #[must_use]
+    pub const fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    ///Returns the position in the world the text is anchored to, `None` if the text is placed
+    ///in screen space
+    ///
+    ///# Panics
+    ///Panics if the component is not attached to an entity
+    #[must_use]
+    pub fn anchor(&self) -> Option<Vec3> {
+        let TextPlacement::World { offset } = self.placement else {
+            return None;
+        };
+        let matrix = self.transform_reference.as_ref().unwrap().borrow().matrix();
+        Some(Vec3::new(matrix.m03, matrix.m13, matrix.m23) + offset)
+    }
+}
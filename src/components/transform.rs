@@ -1,13 +1,19 @@
-use lunar_engine_derive::as_any;
+use lunar_engine_derive::{as_any, as_reflect, Reflect};
 
 use crate::math::{Mat4x4, Vec3};
 
 use crate::ecs::{Component, ComponentReference};
 
+use crate as lunar_engine;
+
 ///Transform  component contains function and data to determine the position of the entity
 ///
 ///Note: rotation is represented as Euler angles using degrees
-#[derive(Debug)]
+///
+///With the `serde` feature, only the position, rotation and scale are serialized, same as the
+///fields exposed through [`Reflect`](crate::ecs::reflect::Reflect)
+#[derive(Debug, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Transform {
     ///Position of the object
     pub position: Vec3,
@@ -16,6 +22,8 @@ pub struct Transform {
     ///Scale of the object
     pub scale: Vec3,
     ///Parent transform of the object
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[reflect(skip)]
     pub parent: Option<ComponentReference<Self>>,
 }
 
@@ -36,6 +44,7 @@ impl Default for Transform {
 
 impl Component for Transform {
     #[as_any]
+    #[as_reflect]
 
     fn mew() -> Self
     where
@@ -0,0 +1,154 @@
+use std::sync::Arc;
+
+use lunar_engine_derive::{as_any, dependencies};
+
+use crate as lunar_engine;
+
+use crate::{
+    ecs::{Component, ComponentReference},
+    math::{Vec2, Vec3, Vec4, Vector},
+    rendering::extensions::canvas::SdfFont,
+    structures::Color,
+};
+
+use super::transform::Transform;
+
+///How the text is oriented in the world
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextOrientation {
+    ///The text always faces the camera, the scale and the rotation of the entity are ignored
+    #[default]
+    Billboard,
+    ///The text lies in the local xy plane of the entity, reading along the x axis, with the y
+    ///axis pointing up
+    Fixed,
+}
+
+///Text rendered in the world at the position of the entity, centered on it
+///
+///Used for nameplates or debug labels, rendered by the
+///[`WorldTextRenderer`](crate::rendering::extensions::WorldTextRenderer) extension. The text is
+///hidden by the geometry in front of it.
+#[derive(Debug)]
+pub struct WorldText {
+    ///Text to render, may contain multiple lines
+    pub text: String,
+    ///Font of the text
+    pub font: Arc<SdfFont>,
+    ///Font size in world units
+    pub size: f32,
+    ///Color of the text
+    pub color: Color,
+    ///How the text is oriented
+    pub orientation: TextOrientation,
+    ///Offset of the center of the text from the position of the entity, in world space
+    pub offset: Vec3,
+    ///Distances from the camera at which the text starts fading out and at which it's fully
+    ///transparent, `None` disables the fading
+    pub fade: Option<(f32, f32)>,
+    ///Whether or not the text is rendered
+    pub visible: bool,
+    transform_reference: Option<ComponentReference<Transform>>,
+}
+
+impl Component for WorldText {
+    #[as_any]
+    #[dependencies(Transform)]
+    fn mew() -> Self
+    where
+        Self: Sized,
+    {
+        Self::new("", Arc::new(SdfFont::new(0, 1.0)), 1.0)
+    }
+
+    fn set_self_reference(&mut self, reference: crate::ecs::SelfReferenceGuard) {
+        self.transform_reference = Some(reference.get_component().unwrap());
+    }
+}
+
+impl WorldText {
+    ///Creates a new white billboarded text
+    #[must_use]
+    pub fn new(text: &str, font: Arc<SdfFont>, size: f32) -> Self {
+        Self {
+            text: text.to_owned(),
+            font,
+            size,
+            color: Color::white(),
+            orientation: TextOrientation::Billboard,
+            offset: Vec3::default(),
+            fade: None,
+            visible: true,
+            transform_reference: None,
+        }
+    }
+
+    ///Returns the position of the center of the text in world space
+    ///
+    ///# Panics
+    ///Panics if the component is not attached to an entity
+    #[must_use]
+    pub fn position(&self) -> Vec3 {
+        let matrix = self.transform_reference.as_ref().unwrap().borrow().matrix();
+        Vec3::new(matrix.m03, matrix.m13, matrix.m23) + self.offset
+    }
+
+    ///Returns the opacity of the text seen from the `camera`, from 0 to 1
+    #[must_use]
+    pub fn opacity(&self, camera: Vec3) -> f32 {
+        let Some((start, end)) = self.fade else {
+            return 1.0;
+        };
+        let distance = (self.position() - camera).length();
+        if distance <= start {
+            return 1.0;
+        }
+        if distance >= end {
+            return 0.0;
+        }
+        let t = (distance - start) / (end - start);
+        //Smoothstep
+        1.0 - t * t * 2.0f32.mul_add(-t, 3.0)
+    }
+
+    ///Returns the corners of the glyphs in world space, top left, top right, bottom left, bottom
+    ///right, and their top left and bottom right texture coordinates in the atlas
+    ///
+    ///`right` and `up` are the directions of the screen in world space, used for billboarded
+    ///text
+    ///
+    ///# Panics
+    ///Panics if the component is not attached to an entity
+    #[must_use]
+    pub fn glyph_quads(&self, right: Vec3, up: Vec3) -> Vec<([Vec3; 4], (Vec2, Vec2))> {
+        let center = self.font.measure(&self.text, self.size) / 2.0;
+        let matrix = self.transform_reference.as_ref().unwrap().borrow().matrix();
+
+        //Layout coordinates have y pointing down
+        let place = |p: Vec2| {
+            let p = Vec2::new(p.x - center.x, center.y - p.y);
+            match self.orientation {
+                TextOrientation::Billboard => self.position() + right * p.x + up * p.y,
+                TextOrientation::Fixed => {
+                    (matrix * Vec4::new(p.x, p.y, 0.0, 1.0)).xyz() + self.offset
+                }
+            }
+        };
+
+        self.font
+            .layout(&self.text, Vec2::new(0.0, 0.0), self.size)
+            .into_iter()
+            .map(|(position, size, glyph)| {
+                (
+                    [
+                        place(position),
+                        place(position + Vec2::new(size.x, 0.0)),
+                        place(position + Vec2::new(0.0, size.y)),
+                        place(position + size),
+                    ],
+                    glyph.uv,
+                )
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,150 @@
+//! Crash reports
+//!
+//! When the application panics a report is written to a `crash-<timestamp>.txt` file next to the
+//! executable, or into the working directory if that is not possible. The report contains:
+//! - The build info of the engine, see [`crate::build_info`]
+//! - The gpu, see [`crate::rendering::capabilities`]
+//! - The recent log messages
+//! - The panic message and the backtrace
+//!
+//! The handler is installed by [`crate::State::run`], it can be installed manually with
+//! [`install`] when the engine is used without the state
+use std::{
+    backtrace::Backtrace,
+    fmt::Write as _,
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+static MESSAGE_BOX: AtomicBool = AtomicBool::new(false);
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+///Installs the panic hook that writes crash reports, the previous hook is still called afterwards
+///
+///Installing the hook more than once has no effect
+pub fn install() {
+    if INSTALLED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let report = report(info);
+        match write_report(&report) {
+            Ok(path) => {
+                log::error!("Crash report written to {}", path.display());
+                if MESSAGE_BOX.load(Ordering::Relaxed) {
+                    show_message_box(&format!(
+                        "The application has crashed.\nA crash report was written to {}",
+                        path.display()
+                    ));
+                }
+            }
+            Err(e) => log::error!("Failed to write a crash report: {e}"),
+        }
+        previous(info);
+    }));
+}
+
+///Sets whether or not a message box with the location of the report is shown on a crash, `false`
+///by default
+///
+///The message box uses `zenity` on linux, `osascript` on macos and powershell on windows, if they
+///are not available no message box is shown
+pub fn set_show_message_box(show: bool) {
+    MESSAGE_BOX.store(show, Ordering::Relaxed);
+}
+
+///Creates the contents of a crash report
+fn report(info: &dyn std::fmt::Display) -> String {
+    let mut o = String::new();
+    let build = crate::build_info();
+
+    _ = writeln!(o, "Lunar engine crash report\n");
+    _ = writeln!(o, "Engine version: {}", build.version);
+    _ = writeln!(o, "Commit: {}", build.git_hash.unwrap_or("unknown"));
+    _ = writeln!(o, "Features: {}", build.features.join(", "));
+    _ = writeln!(
+        o,
+        "Platform: {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+
+    match crate::rendering::capabilities() {
+        Some(c) => _ = writeln!(o, "Gpu: {} ({:?})", c.adapter_name, c.backend),
+        None => _ = writeln!(o, "Gpu: not initialized"),
+    }
+    _ = writeln!(o, "Frame: {}", crate::time::frame());
+
+    _ = writeln!(o, "\nPanic: {info}");
+    _ = writeln!(o, "\nBacktrace:\n{}", Backtrace::force_capture());
+
+    let messages = crate::logging::recent_messages();
+    _ = writeln!(o, "Recent log messages:");
+    if messages.is_empty() {
+        _ = writeln!(o, "None recorded");
+    }
+    for m in messages {
+        _ = writeln!(o, "{m}");
+    }
+    o
+}
+
+fn write_report(report: &str) -> std::io::Result<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let name = format!("crash-{timestamp}.txt");
+
+    let next_to_executable = std::env::current_exe()
+        .ok()
+        .and_then(|e| e.parent().map(|p| p.join(&name)));
+
+    if let Some(path) = next_to_executable {
+        if std::fs::write(&path, report).is_ok() {
+            return Ok(path);
+        }
+    }
+
+    let path = std::env::current_dir()?.join(name);
+    std::fs::write(&path, report)?;
+    Ok(path)
+}
+
+fn show_message_box(message: &str) {
+    use std::process::Command;
+
+    let result = if cfg!(target_os = "windows") {
+        Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-Command",
+                &format!(
+                    "Add-Type -AssemblyName PresentationFramework; [System.Windows.MessageBox]::Show('{}', 'Crash')",
+                    message.replace('\'', "''")
+                ),
+            ])
+            .status()
+    } else if cfg!(target_os = "macos") {
+        Command::new("osascript")
+            .args([
+                "-e",
+                &format!(
+                    "display alert \"Crash\" message \"{}\"",
+                    message.replace('"', "\\\"")
+                ),
+            ])
+            .status()
+    } else {
+        Command::new("zenity")
+            .args(["--error", "--title=Crash", &format!("--text={message}")])
+            .status()
+    };
+
+    if let Err(e) = result {
+        log::warn!("Failed to show a message box: {e}");
+    }
+}
@@ -0,0 +1,86 @@
+//! Deterministic mode for automated tests
+//!
+//! When enabled, [`crate::time::delta_time`] returns a fixed value and all the randomness of the
+//! engine, including the ids of entities and assets, comes from a seeded generator, so every run
+//! produces identical results.
+//!
+//! The generator is per thread, so tests running in parallel don't affect each other, while the
+//! fixed delta time is shared by all the threads.
+//!
+//!```
+//! # use lunar_engine::{determinism, ecs::Entity};
+//! determinism::enable(42, std::time::Duration::from_millis(16));
+//! let a = Entity::new().get_id();
+//!
+//! determinism::enable(42, std::time::Duration::from_millis(16));
+//! let b = Entity::new().get_id();
+//!
+//! assert_eq!(a, b);
+//! determinism::disable();
+//!```
+use std::{cell::RefCell, time::Duration};
+
+use rand::{
+    distributions::{uniform::SampleRange, uniform::SampleUniform, Distribution, Standard},
+    rngs::StdRng,
+    Rng, SeedableRng,
+};
+
+thread_local! {
+    static RNG: RefCell<Option<StdRng>> = const { RefCell::new(None) };
+}
+
+///Enables the deterministic mode, seeding the generator of the current thread and fixing the
+///delta time
+///
+///Calling it again with the same seed restarts the sequence of random values
+pub fn enable(seed: u64, delta: Duration) {
+    RNG.with_borrow_mut(|r| *r = Some(StdRng::seed_from_u64(seed)));
+    crate::time::set_fixed_delta(Some(delta));
+}
+
+///Disables the deterministic mode
+pub fn disable() {
+    RNG.with_borrow_mut(|r| *r = None);
+    crate::time::set_fixed_delta(None);
+}
+
+///Returns `true` if the generator of the current thread is seeded
+#[must_use]
+pub fn is_enabled() -> bool {
+    RNG.with_borrow(Option::is_some)
+}
+
+///Returns a random value
+///
+///Uses the seeded generator in the deterministic mode, and the thread local generator of `rand`
+///otherwise
+#[must_use]
+pub fn random<T>() -> T
+where
+    Standard: Distribution<T>,
+{
+    RNG.with_borrow_mut(|r| {
+        r.as_mut()
+            .map_or_else(|| rand::thread_rng().gen(), Rng::gen)
+    })
+}
+
+///Returns a random value in the range, see [`random`]
+#[must_use]
+pub fn random_range<T, R>(range: R) -> T
+where
+    T: SampleUniform,
+    R: SampleRange<T>,
+{
+    RNG.with_borrow_mut(|r| match r {
+        Some(r) => r.gen_range(range),
+        None => rand::thread_rng().gen_range(range),
+    })
+}
+
+///Returns a vector with the components in the given range, see [`random`]
+#[must_use]
+pub fn random_vec3(min: f32, max: f32) -> crate::math::Vec3 {
+    crate::math::Vec3::random_with(min, max, random_range)
+}
@@ -2,9 +2,12 @@
 //!
 //! Implements a simple ECS(like) system, heavily inspired by the Unity component system
 //! implementation
+pub mod reflect;
 #[cfg(test)]
 mod tests;
 
+use reflect::Reflect;
+
 ///The trait all components that are used within the ECS must implement
 pub trait Component: std::any::Any {
     ///Creates a new instance of the component
@@ -67,6 +70,23 @@ pub trait Component: std::any::Any {
     ///```
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
 
+    ///Converts trait object to a [`Reflect`] reference, `None` if the component doesn't implement
+    ///[`Reflect`]
+    ///
+    ///Please use [`lunar_engine_derive::as_reflect`] to implement this function for components
+    ///implementing [`Reflect`]
+    fn as_reflect(&self) -> Option<&dyn Reflect> {
+        None
+    }
+    ///Converts trait object to a mutable [`Reflect`] reference, `None` if the component doesn't
+    ///implement [`Reflect`]
+    ///
+    ///Please use [`lunar_engine_derive::as_reflect`] to implement this function for components
+    ///implementing [`Reflect`]
+    fn as_reflect_mut(&mut self) -> Option<&mut dyn Reflect> {
+        None
+    }
+
     #[allow(clippy::missing_errors_doc)]
     ///Checks if the specified entity contains all the dependencies of this `Component`
     ///
@@ -94,7 +114,6 @@ pub trait Component: std::any::Any {
     }
 }
 
-use rand::Rng;
 use std::cell::{Ref, RefMut};
 
 ///Id type [Entity] uses
@@ -139,6 +158,18 @@ impl SelfReferenceGuard {
             },
         )
     }
+
+    ///Returns the id of this entity
+    ///
+    ///# Errors
+    ///Returns an error if the entity has been deleted
+    pub fn get_entity_id(&self) -> Result<UUID, Error> {
+        self.weak
+            .upgrade()
+            .map_or(Err(Error::EntityDoesNotExist), |it| {
+                Ok(it.borrow().get_id())
+            })
+    }
 }
 
 ///ECS errors
@@ -197,6 +228,12 @@ impl<T: 'static> ComponentReference<T> {
             |c| unsafe { c.as_any_mut().downcast_mut::<T>().unwrap_unchecked() },
         )
     }
+
+    ///Returns `true` if both of the references point to the same component
+    #[must_use]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        self.cell.ptr_eq(&other.cell)
+    }
 }
 
 impl Entity {
@@ -204,7 +241,7 @@ impl Entity {
     #[must_use]
     pub fn new() -> Self {
         Self {
-            id: rand::thread_rng().gen(),
+            id: crate::determinism::random(),
             ..Default::default()
         }
     }
@@ -296,6 +333,31 @@ impl Entity {
         None
     }
 
+    ///Calls `f` with every component of the entity that implements [`Reflect`]
+    ///
+    ///# Panics
+    ///Panics if any of the components is mutably borrowed
+    pub fn for_each_reflect(&self, mut f: impl FnMut(&dyn Reflect)) {
+        for c in &self.components {
+            if let Some(r) = c.borrow().as_reflect() {
+                f(r);
+            }
+        }
+    }
+
+    ///Calls `f` with a mutable reference to every component of the entity that implements
+    ///[`Reflect`]
+    ///
+    ///# Panics
+    ///Panics if any of the components is borrowed
+    pub fn for_each_reflect_mut(&self, mut f: impl FnMut(&mut dyn Reflect)) {
+        for c in &self.components {
+            if let Some(r) = c.borrow_mut().as_reflect_mut() {
+                f(r);
+            }
+        }
+    }
+
     ///Performs update on all components of the entity
     pub fn update(&mut self) {
         for c in &mut self.components {
@@ -395,7 +457,7 @@ impl EntityBuilder {
     ///Note: component addition order matters in the builder, dependencies MUST be added first
     pub fn create(self) -> Result<Entity, Error> {
         let mut e = Entity {
-            id: rand::thread_rng().gen(),
+            id: crate::determinism::random(),
             ..Default::default()
         };
 
@@ -416,7 +478,10 @@ impl EntityBuilder {
 }
 
 use std::rc::Weak;
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
 
 use vec_key_value_pair::map::VecMap;
 
@@ -424,27 +489,49 @@ use vec_key_value_pair::map::VecMap;
 #[derive(Debug, Default)]
 pub(crate) struct ComponentsModified {
     modified_components: Vec<std::any::TypeId>,
-    entity_modified: bool,
 }
 
 impl ComponentsModified {
     ///Sets all caches modified to false
     pub fn reset(&mut self) {
         self.modified_components.clear();
-        self.entity_modified = false;
     }
 
     ///Must be called upon component addition or removal
     pub fn component_changed<T: Component>(&mut self) {
-        self.modified_components.push(std::any::TypeId::of::<T>());
+        self.type_changed(std::any::TypeId::of::<T>());
     }
 
-    ///Must be called upon new entity creation or entity delition
-    pub fn entity_changed(&mut self) {
-        self.entity_modified = true;
+    ///Must be called upon new entity creation or entity deletion, with the types of all the
+    ///components of the entity
+    pub fn entity_changed(&mut self, types: &[std::any::TypeId]) {
+        for t in types {
+            self.type_changed(*t);
+        }
+    }
+
+    fn type_changed(&mut self, id: std::any::TypeId) {
+        if !self.modified_components.contains(&id) {
+            self.modified_components.push(id);
+        }
     }
 }
 
+///Statistics of the [`World`] query cache
+///
+///Queries made using [`World::get_all_components`] and [`World::get_all_entities_with_component`]
+///are cached per component type. A cached query is invalidated when a component of that type is
+///added or removed, either directly or by adding or removing an entity that has it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct QueryCacheStats {
+    ///Number of queries that were answered from the cache
+    pub hits: u64,
+    ///Number of queries that had to iterate over all the entities
+    pub misses: u64,
+    ///Number of cached queries that were invalidated
+    pub invalidations: u64,
+}
+
 ///Manages all the entities
 pub struct World {
     entities: Vec<EntityRefence>,
@@ -452,6 +539,7 @@ pub struct World {
     //Gotta box it, this is so stupid
     component_cache: RefCell<VecMap<std::any::TypeId, Box<dyn std::any::Any>>>,
     entity_cache: RefCell<VecMap<std::any::TypeId, Box<dyn std::any::Any>>>,
+    cache_stats: Cell<QueryCacheStats>,
 }
 
 impl Default for World {
@@ -461,6 +549,7 @@ impl Default for World {
             modified: Rc::new(RefCell::new(ComponentsModified::default())),
             component_cache: RefCell::new(VecMap::new()),
             entity_cache: RefCell::new(VecMap::new()),
+            cache_stats: Cell::new(QueryCacheStats::default()),
         }
     }
 }
@@ -488,9 +577,11 @@ impl World {
             });
         }
         let weak = Rc::downgrade(&rc);
-        self.entities.push(rc);
 
-        (*self.modified).borrow_mut().entity_changed();
+        (*self.modified)
+            .borrow_mut()
+            .entity_changed(&rc.borrow().comoponent_types);
+        self.entities.push(rc);
 
         weak
     }
@@ -510,8 +601,12 @@ impl World {
         }
 
         if let Some(id) = id {
-            self.entities.remove(id).take().decatify();
-            (*self.modified).borrow_mut().entity_changed();
+            let e = self.entities.remove(id).take();
+            (*self.modified)
+                .borrow_mut()
+                .entity_changed(&e.comoponent_types);
+            crate::validation::forget_entity(e.get_id());
+            e.decatify();
 
             Ok(())
         } else {
@@ -533,8 +628,12 @@ impl World {
         }
 
         if let Some(id) = id {
-            self.entities.remove(id).take().decatify();
-            (*self.modified).borrow_mut().entity_changed();
+            let e = self.entities.remove(id).take();
+            (*self.modified)
+                .borrow_mut()
+                .entity_changed(&e.comoponent_types);
+            crate::validation::forget_entity(entity_id);
+            e.decatify();
             Ok(())
         } else {
             Err(Error::EntityDoesNotExist)
@@ -550,6 +649,12 @@ impl World {
         self.entities.len()
     }
 
+    ///Returns the ids of all the entities, in the order they were added in
+    #[must_use]
+    pub fn get_entity_ids(&self) -> Vec<UUID> {
+        self.entities.iter().map(|e| e.borrow().get_id()).collect()
+    }
+
     ///Returns the entity with the requested id
     #[must_use]
     pub fn get_entity_by_id(&self, id: UUID) -> Option<EntityRefence> {
@@ -561,26 +666,51 @@ impl World {
     ///Checks the modified data and deletes all modified caches;
     fn upate_caches(&self) {
         let mut modified = (*self.modified).borrow_mut();
-        if modified.entity_modified {
-            modified.reset();
-            self.component_cache.borrow_mut().clear();
-            self.entity_cache.borrow_mut().clear();
+        if modified.modified_components.is_empty() {
             return;
         }
+
         let mut c_cache = self.component_cache.borrow_mut();
         let mut e_cache = self.entity_cache.borrow_mut();
-        if !modified.modified_components.is_empty() {
-            //Remove caches for all modified components
-            for i in &modified.modified_components {
-                c_cache.remove(i);
-                e_cache.remove(i);
-            }
-            modified.reset();
+        let mut stats = self.cache_stats.get();
+
+        //Remove caches for all modified components
+        for i in &modified.modified_components {
+            stats.invalidations += u64::from(c_cache.remove(i).is_some());
+            stats.invalidations += u64::from(e_cache.remove(i).is_some());
+        }
+        modified.reset();
+
+        self.cache_stats.set(stats);
+    }
+
+    ///Records a query cache hit or miss
+    fn record_query(&self, hit: bool) {
+        let mut stats = self.cache_stats.get();
+        if hit {
+            stats.hits += 1;
+        } else {
+            stats.misses += 1;
         }
+        self.cache_stats.set(stats);
+    }
+
+    ///Returns the statistics of the query cache
+    #[must_use]
+    pub fn get_cache_stats(&self) -> QueryCacheStats {
+        self.cache_stats.get()
+    }
+
+    ///Resets the statistics of the query cache, does not affect the cache itself
+    pub fn reset_cache_stats(&self) {
+        self.cache_stats.set(QueryCacheStats::default());
     }
 
     /// Returns a vector of all components of type T
     ///
+    /// The result is cached until a component of type T is added or removed, see
+    /// [`QueryCacheStats`]
+    ///
     /// Will return None if no components are found
     #[allow(clippy::missing_panics_doc)]
     #[must_use]
@@ -591,17 +721,18 @@ impl World {
         self.upate_caches();
 
         let mut binding = self.component_cache.borrow_mut();
-        let entry = binding
-            .entry(std::any::TypeId::of::<T>())
-            .or_insert_with(|| {
-                log::warn!("Cache miss");
-                Box::new(
-                    self.entities
-                        .iter()
-                        .filter_map(|e| e.borrow().get_component::<T>())
-                        .collect::<Vec<_>>(),
-                )
-            });
+        let id = std::any::TypeId::of::<T>();
+        self.record_query(binding.contains_key(&id));
+
+        let entry = binding.entry(id).or_insert_with(|| {
+            log::trace!("Cache miss");
+            Box::new(
+                self.entities
+                    .iter()
+                    .filter_map(|e| e.borrow().get_component::<T>())
+                    .collect::<Vec<_>>(),
+            )
+        });
 
         let vec = entry.downcast_ref::<Vec<ComponentReference<T>>>().unwrap();
 
@@ -612,7 +743,10 @@ impl World {
         }
     }
 
-    /// Returns a vector of all components of type T
+    /// Returns a vector of all entities that have a component of type T
+    ///
+    /// The result is cached until a component of type T is added or removed, see
+    /// [`QueryCacheStats`]
     ///
     /// Will return None, if no entities are found
     #[allow(clippy::missing_panics_doc)]
@@ -624,8 +758,11 @@ impl World {
         self.upate_caches();
 
         let mut entry = self.entity_cache.borrow_mut();
-        let entry = entry.entry(std::any::TypeId::of::<T>()).or_insert_with(|| {
-            log::warn!("Cache miss");
+        let id = std::any::TypeId::of::<T>();
+        self.record_query(entry.contains_key(&id));
+
+        let entry = entry.entry(id).or_insert_with(|| {
+            log::trace!("Cache miss");
             Box::new(
                 self.entities
                     .iter()
@@ -645,6 +782,7 @@ impl World {
 
     ///Calls update on all containing entities
     pub fn update(&self) {
+        let _span = crate::timeline::span_with_category("World update", "engine");
         for e in &self.entities {
             e.borrow_mut().update();
         }
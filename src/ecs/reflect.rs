@@ -0,0 +1,74 @@
+//! Generic access to the fields of components
+//!
+//! Components implementing [`Reflect`] expose their fields by name, so that tools like an
+//! inspector, a scene format or network replication can read and write them without knowing the
+//! concrete type of the component. The trait is implemented with the
+//! [`lunar_engine_derive::Reflect`] derive macro and exposed to the ECS by implementing
+//! [`Component::as_reflect`](super::Component::as_reflect) with [`lunar_engine_derive::as_reflect`].
+//!
+//! # Examples
+//!```ignore
+//!#[derive(Reflect)]
+//!struct Health {
+//!    current: f32,
+//!    max: f32,
+//!    #[reflect(skip)]
+//!    regeneration_timer: f32,
+//!}
+//!
+//!impl Component for Health {
+//!    #[as_any]
+//!    #[as_reflect]
+//!
+//!    fn mew() -> Self {
+//!        ...
+//!    }
+//!}
+//!```
+use std::any::Any;
+
+///Reflection errors
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    ///The type does not have a field with that name
+    FieldDoesNotExist,
+    ///The field is of a different type
+    TypeMismatch,
+}
+
+///Trait for accessing the fields of a type by their names
+pub trait Reflect: Any {
+    ///Returns the name of the type
+    fn type_name(&self) -> &'static str;
+    ///Returns the names of the fields, in the order they are declared in
+    fn field_names(&self) -> Vec<&str>;
+    ///Returns a reference to the field with the given name, `None` if it doesn't exist
+    fn field(&self, name: &str) -> Option<&dyn Any>;
+    ///Returns a mutable reference to the field with the given name, `None` if it doesn't exist
+    fn field_mut(&mut self, name: &str) -> Option<&mut dyn Any>;
+}
+
+impl dyn Reflect {
+    ///Returns the value of the field with the given name, `None` if it doesn't exist or if it's not
+    ///of type `T`
+    #[must_use]
+    pub fn get<T: Any>(&self, name: &str) -> Option<&T> {
+        self.field(name)?.downcast_ref()
+    }
+
+    ///Returns a mutable reference to the field with the given name, `None` if it doesn't exist or
+    ///if it's not of type `T`
+    pub fn get_mut<T: Any>(&mut self, name: &str) -> Option<&mut T> {
+        self.field_mut(name)?.downcast_mut()
+    }
+
+    ///Sets the value of the field with the given name
+    ///
+    ///# Errors
+    ///Returns an error if the field doesn't exist or if it's not of type `T`
+    pub fn set<T: Any>(&mut self, name: &str, value: T) -> Result<(), Error> {
+        let field = self.field_mut(name).ok_or(Error::FieldDoesNotExist)?;
+        *field.downcast_mut().ok_or(Error::TypeMismatch)? = value;
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use lunar_engine_derive::{alias, as_any, dependencies};
+use lunar_engine_derive::{alias, as_any, as_reflect, dependencies, Reflect};
 
 use crate as lunar_engine;
 
@@ -204,6 +204,59 @@ fn get_all_componenents_test() {
     assert_eq!(o.unwrap().len(), 200);
 }
 
+#[test]
+fn query_cache_stats_test() {
+    let mut w = World::new();
+
+    for _ in 0..10 {
+        w.add_entity(
+            EntityBuilder::new()
+                .add_component::<TestComponent>()
+                .add_component::<TestComponent1>()
+                .create()
+                .unwrap(),
+        );
+    }
+
+    _ = w.get_all_components::<TestComponent>();
+    _ = w.get_all_components::<TestComponent>();
+    _ = w.get_all_components::<TestComponent1>();
+    _ = w.get_all_entities_with_component::<TestComponent>();
+
+    assert_eq!(
+        w.get_cache_stats(),
+        QueryCacheStats {
+            hits: 1,
+            misses: 3,
+            invalidations: 0
+        }
+    );
+
+    //Only invalidates the queries of the types of the entity
+    let mut e = Entity::new();
+    e.add_component::<TestComponent>().unwrap();
+    let id = e.get_id();
+    w.add_entity(e);
+
+    assert_eq!(w.get_all_components::<TestComponent>().unwrap().len(), 11);
+    assert_eq!(w.get_all_components::<TestComponent1>().unwrap().len(), 10);
+    assert_eq!(
+        w.get_cache_stats(),
+        QueryCacheStats {
+            hits: 2,
+            misses: 4,
+            invalidations: 2
+        }
+    );
+
+    w.remove_entity_by_id(id).unwrap();
+    assert_eq!(w.get_all_components::<TestComponent>().unwrap().len(), 10);
+    assert_eq!(w.get_cache_stats().invalidations, 3);
+
+    w.reset_cache_stats();
+    assert_eq!(w.get_cache_stats(), QueryCacheStats::default());
+}
+
 #[test]
 fn component_add_test() {
     let mut entity = Entity::new();
@@ -350,3 +403,95 @@ fn alias_test() {
     let binding = world.get_all_components::<Alias>().unwrap();
     assert_eq!(binding.len(), 1);
 }
+
+#[test]
+fn deterministic_ids_test() {
+    let ids = || {
+        crate::determinism::enable(1234, std::time::Duration::from_millis(10));
+        assert_eq!(crate::time::delta(), std::time::Duration::from_millis(10));
+        let ids = (0..4).map(|_| Entity::new().get_id()).collect::<Vec<_>>();
+        crate::determinism::disable();
+        ids
+    };
+
+    assert_eq!(ids(), ids());
+}
+
+#[derive(Debug, Reflect)]
+struct ReflectComponent {
+    health: f32,
+    name: String,
+    #[reflect(skip)]
+    #[allow(dead_code)]
+    hidden: Vec<Option<(u8, u8)>>,
+}
+
+impl Component for ReflectComponent {
+    #[as_any]
+    #[as_reflect]
+
+    fn mew() -> Self
+    where
+        Self: Sized,
+    {
+        Self {
+            health: 100.0,
+            name: "cat".to_owned(),
+            hidden: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+#[alias(ReflectComponent)]
+struct ReflectAlias;
+
+#[test]
+fn reflect_test() {
+    use reflect::Error;
+
+    let mut c = ReflectComponent::mew();
+    let r: &mut dyn Reflect = &mut c;
+
+    assert_eq!(r.type_name(), "ReflectComponent");
+    assert_eq!(r.field_names(), vec!["health", "name"]);
+    assert_eq!(r.get::<f32>("health"), Some(&100.0));
+    assert_eq!(r.get::<String>("name").map(String::as_str), Some("cat"));
+    assert_eq!(r.get::<f64>("health"), None);
+    assert!(r.field("hidden").is_none());
+
+    *r.get_mut::<f32>("health").unwrap() -= 10.0;
+    r.set("name", "dog".to_owned()).unwrap();
+    assert_eq!(r.set("health", 1), Err(Error::TypeMismatch));
+    assert_eq!(r.set("hidden", 1), Err(Error::FieldDoesNotExist));
+    assert_eq!(c.health, 90.0);
+    assert_eq!(c.name, "dog");
+
+    //Only the components implementing reflect are visited
+    let e = EntityBuilder::new()
+        .add_component::<TestComponent>()
+        .add_component::<ReflectComponent>()
+        .create()
+        .unwrap();
+    let mut names = Vec::new();
+    e.for_each_reflect(|r| names.push(r.type_name()));
+    assert_eq!(names, vec!["ReflectComponent"]);
+
+    e.for_each_reflect_mut(|r| r.set("health", 5.0f32).unwrap());
+    assert_eq!(
+        e.get_component::<ReflectComponent>()
+            .unwrap()
+            .borrow()
+            .health,
+        5.0
+    );
+
+    //Aliases expose the aliased component
+    let e = EntityBuilder::new()
+        .add_component::<ReflectAlias>()
+        .create()
+        .unwrap();
+    let mut names = Vec::new();
+    e.for_each_reflect(|r| names.push(r.type_name()));
+    assert_eq!(names, vec!["ReflectComponent"]);
+}
@@ -0,0 +1,152 @@
+//! Engine error reporting
+//!
+//! Some errors can not be returned to the caller directly, for example gpu validation errors,
+//! which wgpu reports asynchronously. These errors are logged and stored in a global queue, from
+//! which they can be retrieved using [`take_errors`].
+use std::sync::RwLock;
+
+///Errors reported by the engine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    ///A gpu object failed validation
+    Validation {
+        ///Label of the object or the operation that caused the error
+        label: String,
+        ///Description of the error provided by wgpu
+        description: String,
+    },
+    ///The gpu ran out of memory while performing an operation
+    OutOfMemory {
+        ///Label of the object or the operation that caused the error
+        label: String,
+    },
+    ///A shader failed to compile
+    ShaderCompilation {
+        ///File the error originates from
+        file: String,
+        ///Line of the error in the file, starting from 1
+        line: u32,
+        ///Column of the error in the line, starting from 1
+        column: u32,
+        ///Error message
+        message: String,
+        ///Offending part of the source code
+        snippet: String,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Validation { label, description } => {
+                write!(f, "Validation error in {label}: {description}")
+            }
+            Self::OutOfMemory { label } => write!(f, "Out of memory in {label}"),
+            Self::ShaderCompilation {
+                file,
+                line,
+                column,
+                message,
+                snippet,
+            } => write!(f, "{file}:{line}:{column}: {message}\n{snippet}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Error {
+    ///Converts a wgpu error into an engine error with the given label
+    pub(crate) fn from_wgpu(label: &str, error: &wgpu::Error) -> Self {
+        if let wgpu::Error::OutOfMemory { .. } = error {
+            return Self::OutOfMemory {
+                label: label.to_owned(),
+            };
+        }
+        Self::Validation {
+            label: label.to_owned(),
+            description: error.to_string(),
+        }
+    }
+}
+
+static ERRORS: RwLock<Vec<Error>> = RwLock::new(Vec::new());
+//The error scopes are shared by all the threads using the device, so only one thread can have
+//scopes open at a time. Reentrant, as scopes can be nested on the same thread
+static SCOPES: parking_lot::ReentrantMutex<()> = parking_lot::const_reentrant_mutex(());
+
+///Logs the error and adds it to the error queue
+pub(crate) fn report(error: Error) {
+    log::error!("{error}");
+    ERRORS.write().unwrap().push(error);
+}
+
+///Returns all the errors reported since the last call, clearing the queue
+#[must_use]
+pub fn take_errors() -> Vec<Error> {
+    std::mem::take(&mut *ERRORS.write().unwrap())
+}
+
+///Returns `true` if there are any errors in the queue
+#[must_use]
+pub fn has_errors() -> bool {
+    !ERRORS.read().unwrap().is_empty()
+}
+
+///Installs the handler that reports all the gpu errors that were not caught by an error scope
+pub(crate) fn set_uncaptured_error_handler(device: &wgpu::Device) {
+    device.on_uncaptured_error(Box::new(|e| {
+        report(Error::from_wgpu("uncaptured", &e));
+    }));
+}
+
+///Runs `f` inside of wgpu error scopes, returns the result of `f` and the error that occurred
+///without reporting it
+///
+///Other threads opening scopes wait until `f` returns, so their errors are not attributed to it
+pub(crate) fn capture<T>(f: impl FnOnce() -> T) -> (T, Option<wgpu::Error>) {
+    let device = crate::DEVICE.get().unwrap();
+    let _scopes = SCOPES.lock();
+
+    device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+    let out = f();
+
+    let validation = futures::executor::block_on(device.pop_error_scope());
+    let memory = futures::executor::block_on(device.pop_error_scope());
+
+    (out, validation.or(memory))
+}
+
+///Runs `f` inside of wgpu error scopes, returns the result of `f` and the error that occurred
+fn run_scoped<T>(label: &str, f: impl FnOnce() -> T) -> (T, Option<Error>) {
+    let (out, error) = capture(f);
+
+    let error = error.map(|e| Error::from_wgpu(label, &e));
+    if let Some(e) = &error {
+        report(e.clone());
+    }
+
+    (out, error)
+}
+
+///Runs `f` inside of wgpu error scopes, reporting any error that occurred
+///
+///Objects created inside of `f` are returned even if they are invalid, wgpu will report an error
+///upon using them
+pub(crate) fn scoped<T>(label: &str, f: impl FnOnce() -> T) -> T {
+    run_scoped(label, f).0
+}
+
+///Runs `f` inside of wgpu error scopes, reporting any error that occurred
+///
+///# Errors
+///Returns the error if creation of any of the objects inside `f` has failed, the error is also
+///added to the error queue
+pub(crate) fn try_scoped<T>(label: &str, f: impl FnOnce() -> T) -> Result<T, Error> {
+    match run_scoped(label, f) {
+        (out, None) => Ok(out),
+        (_, Some(e)) => Err(e),
+    }
+}
@@ -18,4 +18,83 @@ pub const CAMERA_BIND_GROUP_LAYOUT_DESCRIPTOR: wgpu::BindGroupLayoutDescriptor =
     };
 
 pub const CAMERA_BIND_GROUP_INDEX: u32 = 0;
-pub const NUM_THREADS: usize = 8;
+
+pub const JOINTS_BIND_GROUP_LAYOUT_DESCRIPTOR: wgpu::BindGroupLayoutDescriptor =
+    wgpu::BindGroupLayoutDescriptor {
+        label: Some("Joints binding"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    };
+
+//After the camera, the material and the lighting
+pub const JOINTS_BIND_GROUP_INDEX: u32 = 3;
+//Number of frames the cpu may prepare while the gpu is still rendering the previous ones
+pub const FRAMES_IN_FLIGHT: usize = 2;
+
+//Entries of the lighting bind group, the local lights are stored in buffers of the given type
+const fn lighting_entries(lights: wgpu::BufferBindingType) -> [wgpu::BindGroupLayoutEntry; 6] {
+    const fn buffer(binding: u32, ty: wgpu::BufferBindingType) -> wgpu::BindGroupLayoutEntry {
+        wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }
+    }
+
+    [
+        buffer(0, wgpu::BufferBindingType::Uniform),
+        wgpu::BindGroupLayoutEntry {
+            binding: 1,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Depth,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        },
+        wgpu::BindGroupLayoutEntry {
+            binding: 2,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+            count: None,
+        },
+        //Local lights, clusters and light indices
+        buffer(3, lights),
+        buffer(4, lights),
+        buffer(5, lights),
+    ]
+}
+
+const LIGHTING_STORAGE_ENTRIES: [wgpu::BindGroupLayoutEntry; 6] =
+    lighting_entries(wgpu::BufferBindingType::Storage { read_only: true });
+const LIGHTING_UNIFORM_ENTRIES: [wgpu::BindGroupLayoutEntry; 6] =
+    lighting_entries(wgpu::BufferBindingType::Uniform);
+
+pub const LIGHTING_BIND_GROUP_LAYOUT_DESCRIPTOR: wgpu::BindGroupLayoutDescriptor =
+    wgpu::BindGroupLayoutDescriptor {
+        label: Some("Lighting binding"),
+        entries: &LIGHTING_STORAGE_ENTRIES,
+    };
+
+//Used when storage buffers are not available in fragment shaders, e.g. on WebGL
+pub const LIGHTING_UNIFORM_BIND_GROUP_LAYOUT_DESCRIPTOR: wgpu::BindGroupLayoutDescriptor =
+    wgpu::BindGroupLayoutDescriptor {
+        label: Some("Lighting binding"),
+        entries: &LIGHTING_UNIFORM_ENTRIES,
+    };
+
+pub const LIGHTING_BIND_GROUP_INDEX: u32 = 2;
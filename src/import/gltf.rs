@@ -0,0 +1,713 @@
+#![allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_possible_wrap,
+    clippy::cast_sign_loss
+)]
+use std::{collections::BTreeMap, error::Error, path::Path};
+
+use super::json::{self, Value};
+use crate::{
+    components::animator::{AnimationClip, Armature, Channel, ChannelValues, Interpolation, Joint},
+    math::{Mat4x4, Quaternion, Vec2, Vec3, Vector},
+    structures::{Mesh, SkinVertex, Vertex},
+};
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_JSON: u32 = 0x4E4F_534A;
+const GLB_BIN: u32 = 0x004E_4942;
+
+///Contents of a gltf file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Model {
+    ///Triangle primitives of all the meshes in the file, in order
+    pub meshes: Vec<Mesh>,
+    ///Joint hierarchy of the first skin in the file, the joint indices of the skinned meshes index
+    ///into it
+    pub armature: Option<Armature>,
+    ///Animations of the joints of the armature
+    pub animations: Vec<AnimationClip>,
+}
+
+fn invalid(message: &str) -> Box<dyn Error + Send> {
+    Box::new(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        message.to_owned(),
+    ))
+}
+
+///Loads a .gltf or a .glb file, external buffers are loaded relative to the file
+///
+///# Errors
+///Returns an error if the file or any of its buffers can not be read, or if the file is not valid
+pub fn parse_file(path: &Path) -> Result<Model, Box<dyn Error + Send>> {
+    let data = std::fs::read(path).map_err(|e| Box::new(e) as Box<dyn Error + Send>)?;
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    parse(&data, |uri| std::fs::read(directory.join(uri)).ok())
+}
+
+///Parses a .gltf or a .glb file
+///
+///Buffers embedded as base64 data uris and the binary chunk of .glb files are loaded directly,
+///other buffers are loaded with `load_buffer`, which is given the uri of the buffer.
+///
+///Only the data needed for skinned meshes is imported, the transforms of the nodes that are not
+///joints are ignored, as are materials, textures, cameras and morph targets
+///
+///# Errors
+///Returns an error if a buffer can not be loaded or if the file is not valid
+pub fn parse(
+    data: &[u8],
+    load_buffer: impl Fn(&str) -> Option<Vec<u8>>,
+) -> Result<Model, Box<dyn Error + Send>> {
+    let (json, binary) = if data.get(0..4) == Some(&GLB_MAGIC.to_le_bytes()) {
+        split_glb(data).ok_or_else(|| invalid("Invalid glb container"))?
+    } else {
+        (data, None)
+    };
+    let document = std::str::from_utf8(json)
+        .ok()
+        .and_then(json::parse)
+        .ok_or_else(|| invalid("Invalid gltf json"))?;
+
+    let mut buffers = Vec::new();
+    for (i, b) in array(&document, "buffers").iter().enumerate() {
+        let buffer = match b.get("uri").and_then(Value::as_str) {
+            Some(uri) => uri.strip_prefix("data:").map_or_else(
+                || load_buffer(uri),
+                |data| {
+                    data.split_once(";base64,")
+                        .and_then(|(_, data)| decode_base64(data))
+                },
+            ),
+            //The binary chunk of a glb file
+            None if i == 0 => binary.map(<[u8]>::to_vec),
+            None => None,
+        };
+        buffers.push(buffer.ok_or_else(|| invalid(&format!("Could not load buffer {i}")))?);
+    }
+    let reader = Reader {
+        document: &document,
+        buffers: &buffers,
+    };
+
+    let mut meshes = Vec::new();
+    for m in array(&document, "meshes") {
+        for p in m
+            .get("primitives")
+            .and_then(Value::as_array)
+            .unwrap_or_default()
+        {
+            //Only triangle lists are supported
+            if p.get("mode").and_then(Value::as_usize).unwrap_or(4) != 4 {
+                log::warn!("Skipping a gltf primitive that is not a triangle list");
+                continue;
+            }
+            meshes.push(reader.primitive(p)?);
+        }
+    }
+
+    let (armature, joints) = match array(&document, "skins").first() {
+        Some(skin) => {
+            let (armature, joints) = reader.armature(skin)?;
+            (Some(armature), joints)
+        }
+        None => (None, Vec::new()),
+    };
+
+    let mut animations = Vec::new();
+    for (i, a) in array(&document, "animations").iter().enumerate() {
+        let name = a
+            .get("name")
+            .and_then(Value::as_str)
+            .map_or_else(|| format!("Animation {i}"), str::to_owned);
+        animations.push(AnimationClip::new(name, reader.channels(a, &joints)?));
+    }
+
+    Ok(Model {
+        meshes,
+        armature,
+        animations,
+    })
+}
+
+//Returns the json and the binary chunks of a glb file
+fn split_glb(data: &[u8]) -> Option<(&[u8], Option<&[u8]>)> {
+    let read = |offset: usize| {
+        data.get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()) as usize)
+    };
+    let length = read(8)?.min(data.len());
+
+    let mut json = None;
+    let mut binary = None;
+    let mut offset = 12;
+    while offset + 8 <= length {
+        let chunk_length = read(offset)?;
+        let chunk_type = read(offset + 4)? as u32;
+        let chunk = data.get(offset + 8..offset + 8 + chunk_length)?;
+        match chunk_type {
+            GLB_JSON => json = Some(chunk),
+            GLB_BIN => binary = Some(chunk),
+            _ => {}
+        }
+        //Chunks are aligned to 4 bytes
+        offset += 8 + chunk_length.next_multiple_of(4);
+    }
+    Some((json?, binary))
+}
+
+fn decode_base64(input: &str) -> Option<Vec<u8>> {
+    let value = |c: u8| match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' | b'-' => Some(62),
+        b'/' | b'_' => Some(63),
+        _ => None,
+    };
+
+    let mut out = Vec::with_capacity(input.len() / 4 * 3);
+    let mut accumulator = 0u32;
+    let mut bits = 0;
+    for c in input.bytes().take_while(|c| *c != b'=') {
+        accumulator = (accumulator << 6) | u32::from(value(c)?);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((accumulator >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+//Returns the array member of the object, or an empty slice
+fn array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value.get(key).and_then(Value::as_array).unwrap_or_default()
+}
+
+struct Reader<'a> {
+    document: &'a Value,
+    buffers: &'a [Vec<u8>],
+}
+
+impl Reader<'_> {
+    //Reads all the components of the elements of the accessor, returns the number of components
+    //per element and the values
+    fn accessor(&self, index: usize) -> Result<(usize, Vec<f64>), Box<dyn Error + Send>> {
+        let error = || invalid(&format!("Invalid accessor {index}"));
+        let accessor = array(self.document, "accessors")
+            .get(index)
+            .ok_or_else(error)?;
+
+        let components = match accessor.get("type").and_then(Value::as_str) {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4" | "MAT2") => 4,
+            Some("MAT3") => 9,
+            Some("MAT4") => 16,
+            _ => return Err(error()),
+        };
+        let component_type = accessor
+            .get("componentType")
+            .and_then(Value::as_usize)
+            .ok_or_else(error)?;
+        let size = match component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            _ => return Err(error()),
+        };
+        let normalized = accessor.get("normalized") == Some(&Value::Bool(true));
+        let count = accessor
+            .get("count")
+            .and_then(Value::as_usize)
+            .ok_or_else(error)?;
+
+        if accessor.get("sparse").is_some() {
+            return Err(invalid("Sparse gltf accessors are not supported"));
+        }
+        //Accessors without a buffer view are filled with zeros
+        let Some(view) = accessor.get("bufferView").and_then(Value::as_usize) else {
+            return Ok((components, vec![0.0; count * components]));
+        };
+        let view = array(self.document, "bufferViews")
+            .get(view)
+            .ok_or_else(error)?;
+        let buffer = view
+            .get("buffer")
+            .and_then(Value::as_usize)
+            .and_then(|b| self.buffers.get(b))
+            .ok_or_else(error)?;
+        let offset = view
+            .get("byteOffset")
+            .and_then(Value::as_usize)
+            .unwrap_or(0)
+            + accessor
+                .get("byteOffset")
+                .and_then(Value::as_usize)
+                .unwrap_or(0);
+        let stride = view
+            .get("byteStride")
+            .and_then(Value::as_usize)
+            .unwrap_or(components * size);
+
+        let mut values = Vec::with_capacity(count * components);
+        for e in 0..count {
+            for c in 0..components {
+                let start = offset + e * stride + c * size;
+                let bytes = buffer.get(start..start + size).ok_or_else(error)?;
+                let value = match component_type {
+                    5120 => f64::from(bytes[0] as i8),
+                    5121 => f64::from(bytes[0]),
+                    5122 => f64::from(i16::from_le_bytes([bytes[0], bytes[1]])),
+                    5123 => f64::from(u16::from_le_bytes([bytes[0], bytes[1]])),
+                    5125 => f64::from(u32::from_le_bytes(bytes.try_into().unwrap())),
+                    _ => f64::from(f32::from_le_bytes(bytes.try_into().unwrap())),
+                };
+                values.push(if normalized {
+                    match component_type {
+                        5120 => (value / 127.0).max(-1.0),
+                        5121 => value / 255.0,
+                        5122 => (value / 32767.0).max(-1.0),
+                        5123 => value / 65535.0,
+                        _ => value,
+                    }
+                } else {
+                    value
+                });
+            }
+        }
+        Ok((components, values))
+    }
+
+    //Reads the accessor as elements of `N` floats
+    fn elements<const N: usize>(
+        &self,
+        index: usize,
+    ) -> Result<Vec<[f32; N]>, Box<dyn Error + Send>> {
+        let (components, values) = self.accessor(index)?;
+        if components != N {
+            return Err(invalid(&format!(
+                "Accessor {index} has {components} components, expected {N}"
+            )));
+        }
+        Ok(values
+            .chunks_exact(N)
+            .map(|c| std::array::from_fn(|i| c[i] as f32))
+            .collect())
+    }
+
+    fn primitive(&self, primitive: &Value) -> Result<Mesh, Box<dyn Error + Send>> {
+        let attribute = |name: &str| {
+            primitive
+                .get("attributes")
+                .and_then(|a| a.get(name))
+                .and_then(Value::as_usize)
+        };
+
+        let positions = self.elements::<3>(
+            attribute("POSITION").ok_or_else(|| invalid("Gltf primitive without positions"))?,
+        )?;
+        let normals = attribute("NORMAL")
+            .map(|a| self.elements::<3>(a))
+            .transpose()?;
+        let uvs = attribute("TEXCOORD_0")
+            .map(|a| self.elements::<2>(a))
+            .transpose()?
+            .unwrap_or_default();
+
+        let indices = match primitive.get("indices").and_then(Value::as_usize) {
+            Some(i) => self.accessor(i)?.1.into_iter().map(|i| i as u32).collect(),
+            None => (0..positions.len() as u32).collect::<Vec<_>>(),
+        };
+        if indices.iter().any(|i| *i as usize >= positions.len()) {
+            return Err(invalid("Gltf primitive has indices out of range"));
+        }
+
+        let mut vertices = positions
+            .iter()
+            .enumerate()
+            .map(|(i, p)| Vertex {
+                coords: (Vec3::new(p[0], p[1], p[2]), 1.0).into(),
+                texture: uvs
+                    .get(i)
+                    .map_or_else(Vec2::default, |u| Vec2::new(u[0], u[1])),
+                normal: normals
+                    .as_ref()
+                    .and_then(|n| n.get(i))
+                    .map_or_else(Vec3::default, |n| Vec3::new(n[0], n[1], n[2])),
+            })
+            .collect::<Vec<_>>();
+
+        //Smooth normals, computed from the faces the vertex is a part of
+        if normals.is_none() {
+            for t in indices.chunks_exact(3) {
+                let [a, b, c] = [0, 1, 2].map(|i| vertices[t[i] as usize].coords.xyz());
+                let normal = (b - a).cross(&(c - a));
+                for i in t {
+                    vertices[*i as usize].normal += normal;
+                }
+            }
+            for v in &mut vertices {
+                if v.normal.square_length() > 0.0 {
+                    v.normal = v.normal.normalize();
+                }
+            }
+        }
+
+        let skin = match (attribute("JOINTS_0"), attribute("WEIGHTS_0")) {
+            (Some(joints), Some(weights)) => {
+                let joints = self.elements::<4>(joints)?;
+                let weights = self.elements::<4>(weights)?;
+                if joints.len() != positions.len() || weights.len() != positions.len() {
+                    return Err(invalid("Gltf primitive has missing joints or weights"));
+                }
+                joints
+                    .into_iter()
+                    .zip(weights)
+                    .map(|(joints, weights)| {
+                        //Weights are not guaranteed to be normalized
+                        let sum = weights.iter().sum::<f32>();
+                        SkinVertex {
+                            joints: joints.map(|j| j as u32),
+                            weights: if sum > 0.0 {
+                                weights.map(|w| w / sum)
+                            } else {
+                                weights
+                            },
+                        }
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+
+        Ok(Mesh {
+            vertices,
+            indices,
+            skin,
+        })
+    }
+
+    //Returns the armature and the nodes of its joints
+    fn armature(&self, skin: &Value) -> Result<(Armature, Vec<usize>), Box<dyn Error + Send>> {
+        let nodes = array(self.document, "nodes");
+        let joints = array(skin, "joints")
+            .iter()
+            .map(|j| j.as_usize().filter(|j| *j < nodes.len()))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| invalid("Gltf skin references a node that does not exist"))?;
+
+        let inverse_bind = match skin.get("inverseBindMatrices").and_then(Value::as_usize) {
+            Some(a) => self
+                .elements::<16>(a)?
+                .into_iter()
+                //Gltf matrices are column major
+                .map(|m| bytemuck::cast::<_, Mat4x4>(m).transpose())
+                .collect(),
+            None => vec![Mat4x4::identity(); joints.len()],
+        };
+        if inverse_bind.len() < joints.len() {
+            return Err(invalid("Gltf skin is missing inverse bind matrices"));
+        }
+
+        //Parent node of every node
+        let mut parents = BTreeMap::new();
+        for (i, n) in nodes.iter().enumerate() {
+            for c in array(n, "children").iter().filter_map(Value::as_usize) {
+                parents.insert(c, i);
+            }
+        }
+
+        let armature = Armature::new(
+            joints
+                .iter()
+                .zip(inverse_bind)
+                .enumerate()
+                .map(|(i, (node_index, inverse_bind))| {
+                    let node = &nodes[*node_index];
+                    //Closest ancestor that is a joint
+                    let mut parent = parents.get(node_index);
+                    let mut depth = 0;
+                    while let Some(p) = parent {
+                        if let Some(j) = joints.iter().position(|j| j == p) {
+                            parent = Some(&joints[j]);
+                            break;
+                        }
+                        parent = parents.get(p);
+                        depth += 1;
+                        //Cyclic hierarchy
+                        if depth > nodes.len() {
+                            parent = None;
+                        }
+                    }
+                    let (translation, rotation, scale) = node_transform(node);
+                    Joint {
+                        name: node
+                            .get("name")
+                            .and_then(Value::as_str)
+                            .map_or_else(|| format!("Joint {i}"), str::to_owned),
+                        parent: parent.and_then(|p| joints.iter().position(|j| j == p)),
+                        translation,
+                        rotation,
+                        scale,
+                        inverse_bind,
+                    }
+                })
+                .collect(),
+        );
+        Ok((armature, joints))
+    }
+
+    fn channels(
+        &self,
+        animation: &Value,
+        joints: &[usize],
+    ) -> Result<Vec<Channel>, Box<dyn Error + Send>> {
+        let samplers = array(animation, "samplers");
+        let mut channels = Vec::new();
+        for c in array(animation, "channels") {
+            let target = c.get("target");
+            //Only the joints of the armature can be animated
+            let Some(joint) = target
+                .and_then(|t| t.get("node"))
+                .and_then(Value::as_usize)
+                .and_then(|n| joints.iter().position(|j| *j == n))
+            else {
+                continue;
+            };
+            let sampler = c
+                .get("sampler")
+                .and_then(Value::as_usize)
+                .and_then(|s| samplers.get(s))
+                .ok_or_else(|| invalid("Gltf animation channel without a sampler"))?;
+            let (Some(input), Some(output)) = (
+                sampler.get("input").and_then(Value::as_usize),
+                sampler.get("output").and_then(Value::as_usize),
+            ) else {
+                return Err(invalid("Invalid gltf animation sampler"));
+            };
+
+            let values = match target.and_then(|t| t.get("path")).and_then(Value::as_str) {
+                Some("translation") => ChannelValues::Translation(
+                    self.elements::<3>(output)?
+                        .into_iter()
+                        .map(|v| Vec3::new(v[0], v[1], v[2]))
+                        .collect(),
+                ),
+                Some("rotation") => ChannelValues::Rotation(
+                    self.elements::<4>(output)?
+                        .into_iter()
+                        .map(|v| Quaternion::new(v[0], v[1], v[2], v[3]))
+                        .collect(),
+                ),
+                Some("scale") => ChannelValues::Scale(
+                    self.elements::<3>(output)?
+                        .into_iter()
+                        .map(|v| Vec3::new(v[0], v[1], v[2]))
+                        .collect(),
+                ),
+                //Morph target weights
+                _ => continue,
+            };
+
+            channels.push(Channel {
+                joint,
+                times: self
+                    .elements::<1>(input)?
+                    .into_iter()
+                    .map(|t| t[0])
+                    .collect(),
+                values,
+                interpolation: match sampler.get("interpolation").and_then(Value::as_str) {
+                    Some("STEP") => Interpolation::Step,
+                    Some("CUBICSPLINE") => Interpolation::CubicSpline,
+                    _ => Interpolation::Linear,
+                },
+            });
+        }
+        Ok(channels)
+    }
+}
+
+//Returns the translation, rotation and scale of the node
+fn node_transform(node: &Value) -> (Vec3, Quaternion, Vec3) {
+    if let Some(m) = node
+        .get("matrix")
+        .and_then(Value::as_f32_array)
+        .and_then(|m| <[f32; 16]>::try_from(m).ok())
+    {
+        //Column major
+        let m = bytemuck::cast::<_, Mat4x4>(m).transpose();
+        let columns = [
+            Vec3::new(m.m00, m.m10, m.m20),
+            Vec3::new(m.m01, m.m11, m.m21),
+            Vec3::new(m.m02, m.m12, m.m22),
+        ];
+        let scale = Vec3::new(
+            columns[0].length(),
+            columns[1].length(),
+            columns[2].length(),
+        );
+        let rotation = if scale.x > 0.0 && scale.y > 0.0 && scale.z > 0.0 {
+            Quaternion::from_matrix(
+                &(m * Mat4x4::scale_matrix(&(Vec3::new(1.0, 1.0, 1.0) / scale))),
+            )
+        } else {
+            Quaternion::identity()
+        };
+        return (Vec3::new(m.m03, m.m13, m.m23), rotation, scale);
+    }
+
+    let vec3 = |key: &str, default: Vec3| {
+        node.get(key)
+            .and_then(Value::as_f32_array)
+            .filter(|v| v.len() == 3)
+            .map_or(default, |v| Vec3::new(v[0], v[1], v[2]))
+    };
+    let rotation = node
+        .get("rotation")
+        .and_then(Value::as_f32_array)
+        .filter(|v| v.len() == 4)
+        .map_or_else(Quaternion::identity, |v| {
+            Quaternion::new(v[0], v[1], v[2], v[3]).normalized()
+        });
+    (
+        vec3("translation", Vec3::new(0.0, 0.0, 0.0)),
+        rotation,
+        vec3("scale", Vec3::new(1.0, 1.0, 1.0)),
+    )
+}
+
+#[test]
+fn test_decode_base64() {
+    assert_eq!(decode_base64("").unwrap(), b"");
+    assert_eq!(decode_base64("Zg==").unwrap(), b"f");
+    assert_eq!(decode_base64("Zm8=").unwrap(), b"fo");
+    assert_eq!(decode_base64("Zm9vYmFy").unwrap(), b"foobar");
+    assert_eq!(decode_base64("Zm9v!"), None);
+}
+
+#[cfg(test)]
+pub(crate) const TEST_BUFFER: &str = "AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAAEAAAAAAAAAAAAABAAABAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAIA/AAAAAAAAAAAAAIA/AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAAAAAAIA/AAAAAAAAAAAAAAAAAAAAAAAAgD8AAAAAAAAAAAAAAAAAAAAAAACAPwAAgD8AAAAAAAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAAAAAAIA/AAAAAAAAAAAAAIC/AAAAAAAAgD8AAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAAAAPMENT/zBDU/";
+
+//Triangle skinned to a root joint and a child joint 1 unit above it, the child is rotated by 90
+//degrees around the z axis over 1 second
+#[cfg(test)]
+pub(crate) fn test_json(buffer: &str) -> String {
+    format!(
+        r#"{{
+    "asset": {{"version": "2.0"}},
+    "buffers": [{buffer}],
+    "bufferViews": [
+        {{"buffer": 0, "byteOffset": 0, "byteLength": 36}},
+        {{"buffer": 0, "byteOffset": 36, "byteLength": 12}},
+        {{"buffer": 0, "byteOffset": 48, "byteLength": 48}},
+        {{"buffer": 0, "byteOffset": 96, "byteLength": 128}},
+        {{"buffer": 0, "byteOffset": 224, "byteLength": 8}},
+        {{"buffer": 0, "byteOffset": 232, "byteLength": 32}}
+    ],
+    "accessors": [
+        {{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"}},
+        {{"bufferView": 1, "componentType": 5121, "count": 3, "type": "VEC4"}},
+        {{"bufferView": 2, "componentType": 5126, "count": 3, "type": "VEC4"}},
+        {{"bufferView": 3, "componentType": 5126, "count": 2, "type": "MAT4"}},
+        {{"bufferView": 4, "componentType": 5126, "count": 2, "type": "SCALAR"}},
+        {{"bufferView": 5, "componentType": 5126, "count": 2, "type": "VEC4"}}
+    ],
+    "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0, "JOINTS_0": 1, "WEIGHTS_0": 2}}}}]}}],
+    "nodes": [
+        {{"name": "mesh", "mesh": 0, "skin": 0}},
+        {{"name": "root", "children": [2]}},
+        {{"name": "child", "translation": [0, 1, 0]}}
+    ],
+    "skins": [{{"joints": [1, 2], "inverseBindMatrices": 3}}],
+    "animations": [{{
+        "name": "bend",
+        "samplers": [{{"input": 4, "output": 5}}],
+        "channels": [{{"sampler": 0, "target": {{"node": 2, "path": "rotation"}}}}]
+    }}]
+}}"#
+    )
+}
+
+#[cfg(test)]
+fn check_test_model(model: &Model) {
+    use crate::assert_approx_eq;
+
+    assert_eq!(model.meshes.len(), 1);
+    let mesh = &model.meshes[0];
+    assert_eq!(mesh.vertices.len(), 3);
+    assert_eq!(mesh.indices, vec![0, 1, 2]);
+    assert_approx_eq!(mesh.vertices[2].coords.xyz(), Vec3::new(0.0, 2.0, 0.0));
+    //Computed from the face
+    assert_approx_eq!(mesh.vertices[0].normal, Vec3::new(0.0, 0.0, 1.0));
+
+    assert_eq!(mesh.skin[1].joints, [0, 1, 0, 0]);
+    assert_approx_eq!(mesh.skin[1].weights[0], 0.5);
+    assert_approx_eq!(mesh.skin[1].weights[1], 0.5);
+
+    let armature = model.armature.as_ref().unwrap();
+    assert_eq!(armature.joints().len(), 2);
+    assert_eq!(armature.find_joint("child"), Some(1));
+    assert_eq!(armature.joints()[0].parent, None);
+    assert_eq!(armature.joints()[1].parent, Some(0));
+    assert_approx_eq!(armature.joints()[1].translation, Vec3::new(0.0, 1.0, 0.0));
+
+    //The rest pose doesn't move the vertices
+    for m in armature.joint_matrices(&armature.rest_pose()) {
+        assert_approx_eq!(m, Mat4x4::identity());
+    }
+
+    assert_eq!(model.animations.len(), 1);
+    let clip = &model.animations[0];
+    assert_eq!(clip.name, "bend");
+    assert_approx_eq!(clip.duration(), 1.0);
+
+    let pose = clip.sample(armature, 0.5);
+    assert_approx_eq!(
+        pose[1].rotation,
+        Quaternion::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), 45.0)
+    );
+    assert_approx_eq!(pose[0].rotation, Quaternion::identity());
+}
+
+#[test]
+fn test_parse_gltf() {
+    let json = test_json(&format!(
+        r#"{{"byteLength": 264, "uri": "data:application/octet-stream;base64,{TEST_BUFFER}"}}"#
+    ));
+    check_test_model(&parse(json.as_bytes(), |_| None).unwrap());
+
+    //External buffers are loaded with the callback
+    let json = test_json(r#"{"byteLength": 264, "uri": "test.bin"}"#);
+    let model = parse(json.as_bytes(), |uri| {
+        assert_eq!(uri, "test.bin");
+        decode_base64(TEST_BUFFER)
+    })
+    .unwrap();
+    check_test_model(&model);
+
+    assert!(parse(json.as_bytes(), |_| None).is_err());
+}
+
+#[test]
+fn test_parse_glb() {
+    let mut json = test_json(r#"{"byteLength": 264}"#).into_bytes();
+    json.resize(json.len().next_multiple_of(4), b' ');
+    let binary = decode_base64(TEST_BUFFER).unwrap();
+
+    let mut glb = Vec::new();
+    glb.extend_from_slice(&GLB_MAGIC.to_le_bytes());
+    glb.extend_from_slice(&2u32.to_le_bytes());
+    glb.extend_from_slice(&((12 + 8 + json.len() + 8 + binary.len()) as u32).to_le_bytes());
+    glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    glb.extend_from_slice(&GLB_JSON.to_le_bytes());
+    glb.extend_from_slice(&json);
+    glb.extend_from_slice(&(binary.len() as u32).to_le_bytes());
+    glb.extend_from_slice(&GLB_BIN.to_le_bytes());
+    glb.extend_from_slice(&binary);
+
+    check_test_model(&parse(&glb, |_| None).unwrap());
+}
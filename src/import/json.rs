@@ -0,0 +1,252 @@
+//!Minimal json parser, only supports what the importers need
+use std::collections::BTreeMap;
+
+///Json value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Self>),
+    Object(BTreeMap<String, Self>),
+}
+
+impl Value {
+    ///Returns the member of an object, `None` if the value is not an object or if the member
+    ///doesn't exist
+    pub fn get(&self, key: &str) -> Option<&Self> {
+        match self {
+            Self::Object(o) => o.get(key),
+            _ => None,
+        }
+    }
+
+    pub const fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn as_f32(&self) -> Option<f32> {
+        self.as_f64().map(|n| n as f32)
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn as_usize(&self) -> Option<usize> {
+        self.as_f64()
+            .filter(|n| *n >= 0.0 && n.fract() == 0.0)
+            .map(|n| n as usize)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Self]> {
+        match self {
+            Self::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    ///Returns the elements of an array as floats, `None` if any of them is not a number
+    pub fn as_f32_array(&self) -> Option<Vec<f32>> {
+        self.as_array()?.iter().map(Self::as_f32).collect()
+    }
+}
+
+///Parses the json document
+pub fn parse(input: &str) -> Option<Value> {
+    let mut parser = Parser {
+        input: input.as_bytes(),
+        position: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    //Trailing garbage
+    if parser.position != parser.input.len() {
+        return None;
+    }
+    Some(value)
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let c = self.peek()?;
+        self.position += 1;
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\n' | b'\r' | b'\t')) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, literal: &str) -> Option<()> {
+        let end = self.position + literal.len();
+        if self.input.get(self.position..end)? != literal.as_bytes() {
+            return None;
+        }
+        self.position = end;
+        Some(())
+    }
+
+    fn value(&mut self) -> Option<Value> {
+        self.skip_whitespace();
+        match self.peek()? {
+            b'{' => self.object(),
+            b'[' => self.array(),
+            b'"' => self.string().map(Value::String),
+            b't' => self.expect("true").map(|()| Value::Bool(true)),
+            b'f' => self.expect("false").map(|()| Value::Bool(false)),
+            b'n' => self.expect("null").map(|()| Value::Null),
+            _ => self.number(),
+        }
+    }
+
+    fn object(&mut self) -> Option<Value> {
+        self.next();
+        let mut object = BTreeMap::new();
+        self.skip_whitespace();
+        if self.peek()? == b'}' {
+            self.next();
+            return Some(Value::Object(object));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            if self.next()? != b':' {
+                return None;
+            }
+            let value = self.value()?;
+            object.insert(key, value);
+            self.skip_whitespace();
+            match self.next()? {
+                b',' => {}
+                b'}' => return Some(Value::Object(object)),
+                _ => return None,
+            }
+        }
+    }
+
+    fn array(&mut self) -> Option<Value> {
+        self.next();
+        let mut array = Vec::new();
+        self.skip_whitespace();
+        if self.peek()? == b']' {
+            self.next();
+            return Some(Value::Array(array));
+        }
+        loop {
+            array.push(self.value()?);
+            self.skip_whitespace();
+            match self.next()? {
+                b',' => {}
+                b']' => return Some(Value::Array(array)),
+                _ => return None,
+            }
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        if self.next()? != b'"' {
+            return None;
+        }
+        let mut out = Vec::new();
+        loop {
+            match self.next()? {
+                b'"' => return String::from_utf8(out).ok(),
+                b'\\' => {
+                    let c = match self.next()? {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode()?,
+                        _ => return None,
+                    };
+                    let mut buffer = [0; 4];
+                    out.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+                }
+                c => out.push(c),
+            }
+        }
+    }
+
+    //Escaped utf-16 code unit, or a surrogate pair
+    fn unicode(&mut self) -> Option<char> {
+        let high = self.hex()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high);
+        }
+        self.expect("\\u")?;
+        let low = self.hex()?;
+        char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low.checked_sub(0xDC00)?))
+    }
+
+    fn hex(&mut self) -> Option<u32> {
+        let digits = std::str::from_utf8(self.input.get(self.position..self.position + 4)?).ok()?;
+        self.position += 4;
+        u32::from_str_radix(digits, 16).ok()
+    }
+
+    fn number(&mut self) -> Option<Value> {
+        let start = self.position;
+        while matches!(
+            self.peek(),
+            Some(b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E')
+        ) {
+            self.position += 1;
+        }
+        std::str::from_utf8(&self.input[start..self.position])
+            .ok()?
+            .parse()
+            .ok()
+            .map(Value::Number)
+    }
+}
+
+#[test]
+fn test_parse_json() {
+    let value = parse(
+        r#" {"a": [1, -2.5, 3e2], "b": {"c": "d\"\u00e9\ud83d\ude00"}, "e": true, "f": null} "#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        value.get("a").unwrap().as_f32_array().unwrap(),
+        vec![1.0, -2.5, 300.0]
+    );
+    assert_eq!(
+        value.get("b").unwrap().get("c").unwrap().as_str(),
+        Some("d\"é😀")
+    );
+    assert_eq!(value.get("e"), Some(&Value::Bool(true)));
+    assert_eq!(value.get("f"), Some(&Value::Null));
+    assert_eq!(value.get("g"), None);
+
+    assert_eq!(parse("[]"), Some(Value::Array(Vec::new())));
+    assert_eq!(parse("[1,]"), None);
+    assert_eq!(parse("{} {}"), None);
+}
@@ -1,5 +1,8 @@
 //! Asset import
 ///.bmp image loading
 pub mod bmp;
+///.gltf and .glb model loading
+pub mod gltf;
+pub(crate) mod json;
 ///.obj mesh loading
 pub mod obj;
@@ -84,6 +84,7 @@ pub fn parse(file: &str) -> Option<Vec<Mesh>> {
             meshes.push(Mesh {
                 vertices,
                 indices: indecies,
+                skin: Vec::new(),
             });
 
             //Clear data
@@ -142,6 +143,7 @@ pub fn parse(file: &str) -> Option<Vec<Mesh>> {
     meshes.push(Mesh {
         vertices,
         indices: indecies,
+        skin: Vec::new(),
     });
 
     log::info!("Read {} meshes", meshes.len());
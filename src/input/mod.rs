@@ -33,6 +33,46 @@ pub(crate) struct InputState {
 
 pub(crate) static INPUT: OnceLock<InputState> = OnceLock::new();
 
+pub mod recording;
+#[cfg(test)]
+mod tests;
+
+pub use recording::InputEvent;
+
+///Initializes the input state, does nothing if it's already initialized
+pub(crate) fn initialize() {
+    INPUT.get_or_init(|| InputState {
+        key_map: RwLock::new(VecMap::new()),
+        mouse_button_map: RwLock::new(VecMap::new()),
+        cursor_position: RwLock::new(Vec2::default()),
+        previous_cursor_position: RwLock::new(Vec2::default()),
+        cursor_delta: RwLock::new(Vec2::default()),
+        raw_curosor_delta: RwLock::new(Vec2::default()),
+        delta_changed: RwLock::new(false),
+    });
+}
+
+///Applies the event to the input state, every input event of the window goes through here, see
+///[`recording`]
+pub(crate) fn process_event(event: &InputEvent) {
+    recording::record(event);
+
+    match *event {
+        InputEvent::Key { key, pressed } => {
+            set_key(key, if pressed { KeyState::Down } else { KeyState::Up });
+        }
+        InputEvent::MouseButton { button, pressed } => {
+            set_mouse_button(button, if pressed { KeyState::Down } else { KeyState::Up });
+        }
+        InputEvent::CursorMoved(position) => set_cursor_position(position),
+        InputEvent::MouseMotion(delta) => {
+            let i = INPUT.get().unwrap();
+            *i.raw_curosor_delta.write().unwrap() = delta;
+            *i.delta_changed.write().unwrap() = true;
+        }
+    }
+}
+
 ///Returns the state of the requested key
 pub fn key(key: KeyCode) -> KeyState {
     let mut i = INPUT.get().unwrap().key_map.write().unwrap();
@@ -61,6 +101,215 @@ pub(crate) fn set_cursor_position(pos: Vec2) {
     *i = pos;
 }
 
+///Returns the key with the same name as the variant of [`KeyCode`]
+#[allow(clippy::too_many_lines)]
+pub(crate) fn key_from_name(name: &str) -> Option<KeyCode> {
+    macro_rules! keys {
+        ($($key:ident),* $(,)?) => {
+            match name {
+                $(stringify!($key) => Some(KeyCode::$key),)*
+                _ => None,
+            }
+        };
+    }
+    keys!(
+        Backquote,
+        Backslash,
+        BracketLeft,
+        BracketRight,
+        Comma,
+        Digit0,
+        Digit1,
+        Digit2,
+        Digit3,
+        Digit4,
+        Digit5,
+        Digit6,
+        Digit7,
+        Digit8,
+        Digit9,
+        Equal,
+        IntlBackslash,
+        IntlRo,
+        IntlYen,
+        KeyA,
+        KeyB,
+        KeyC,
+        KeyD,
+        KeyE,
+        KeyF,
+        KeyG,
+        KeyH,
+        KeyI,
+        KeyJ,
+        KeyK,
+        KeyL,
+        KeyM,
+        KeyN,
+        KeyO,
+        KeyP,
+        KeyQ,
+        KeyR,
+        KeyS,
+        KeyT,
+        KeyU,
+        KeyV,
+        KeyW,
+        KeyX,
+        KeyY,
+        KeyZ,
+        Minus,
+        Period,
+        Quote,
+        Semicolon,
+        Slash,
+        AltLeft,
+        AltRight,
+        Backspace,
+        CapsLock,
+        ContextMenu,
+        ControlLeft,
+        ControlRight,
+        Enter,
+        SuperLeft,
+        SuperRight,
+        ShiftLeft,
+        ShiftRight,
+        Space,
+        Tab,
+        Convert,
+        KanaMode,
+        Lang1,
+        Lang2,
+        Lang3,
+        Lang4,
+        Lang5,
+        NonConvert,
+        Delete,
+        End,
+        Help,
+        Home,
+        Insert,
+        PageDown,
+        PageUp,
+        ArrowDown,
+        ArrowLeft,
+        ArrowRight,
+        ArrowUp,
+        NumLock,
+        Numpad0,
+        Numpad1,
+        Numpad2,
+        Numpad3,
+        Numpad4,
+        Numpad5,
+        Numpad6,
+        Numpad7,
+        Numpad8,
+        Numpad9,
+        NumpadAdd,
+        NumpadBackspace,
+        NumpadClear,
+        NumpadClearEntry,
+        NumpadComma,
+        NumpadDecimal,
+        NumpadDivide,
+        NumpadEnter,
+        NumpadEqual,
+        NumpadHash,
+        NumpadMemoryAdd,
+        NumpadMemoryClear,
+        NumpadMemoryRecall,
+        NumpadMemoryStore,
+        NumpadMemorySubtract,
+        NumpadMultiply,
+        NumpadParenLeft,
+        NumpadParenRight,
+        NumpadStar,
+        NumpadSubtract,
+        Escape,
+        Fn,
+        FnLock,
+        PrintScreen,
+        ScrollLock,
+        Pause,
+        BrowserBack,
+        BrowserFavorites,
+        BrowserForward,
+        BrowserHome,
+        BrowserRefresh,
+        BrowserSearch,
+        BrowserStop,
+        Eject,
+        LaunchApp1,
+        LaunchApp2,
+        LaunchMail,
+        MediaPlayPause,
+        MediaSelect,
+        MediaStop,
+        MediaTrackNext,
+        MediaTrackPrevious,
+        Power,
+        Sleep,
+        AudioVolumeDown,
+        AudioVolumeMute,
+        AudioVolumeUp,
+        WakeUp,
+        Meta,
+        Hyper,
+        Turbo,
+        Abort,
+        Resume,
+        Suspend,
+        Again,
+        Copy,
+        Cut,
+        Find,
+        Open,
+        Paste,
+        Props,
+        Select,
+        Undo,
+        Hiragana,
+        Katakana,
+        F1,
+        F2,
+        F3,
+        F4,
+        F5,
+        F6,
+        F7,
+        F8,
+        F9,
+        F10,
+        F11,
+        F12,
+        F13,
+        F14,
+        F15,
+        F16,
+        F17,
+        F18,
+        F19,
+        F20,
+        F21,
+        F22,
+        F23,
+        F24,
+        F25,
+        F26,
+        F27,
+        F28,
+        F29,
+        F30,
+        F31,
+        F32,
+        F33,
+        F34,
+        F35,
+    )
+}
+
 ///Returns the state of the requested mouse button
 pub fn mouse_btn(btn: MouseButton) -> KeyState {
     let mut i = INPUT.get().unwrap().mouse_button_map.write().unwrap();
@@ -73,6 +322,12 @@ pub fn cursor_position() -> Vec2 {
     *INPUT.get().unwrap().cursor_position.read().unwrap()
 }
 
+///Returns the cursor position relative to the top left corner of the viewport, or `None` if the
+///cursor is outside of it, see [`crate::rendering::viewport`]
+pub fn viewport_cursor_position() -> Option<Vec2> {
+    crate::rendering::viewport::current().to_viewport(cursor_position())
+}
+
 ///Returns the cursor movement delta
 pub fn cursor_delta() -> Vec2 {
     *INPUT.get().unwrap().cursor_delta.read().unwrap()
@@ -0,0 +1,356 @@
+//! Recording and replaying of the input
+//!
+//! Every input event of the window is converted into an [`InputEvent`] before it's applied to the
+//! input state. While recording, the events are stored along with the frame they arrived in, the
+//! [`Recording`] can then be saved to a file and replayed later, for example in integration tests
+//! of the game, without anyone at the keyboard.
+//!
+//!```no_run
+//! # use lunar_engine::input::recording;
+//! //In the game
+//! recording::start();
+//! //...
+//! recording::stop().unwrap().save("input.rec".as_ref()).unwrap();
+//!
+//! //In the test
+//! let mut replay = recording::Replay::new(recording::Recording::load("input.rec".as_ref()).unwrap());
+//! while !replay.is_finished() {
+//!     replay.next_frame();
+//!     //Update the game
+//! }
+//!```
+//!
+//! The recording is a text file, one event per line, prefixed with the frame it arrived in,
+//! relative to the start of the recording
+//!```text
+//! 0 key KeyW down
+//! 3 cursor 512.5 300
+//! 5 mouse Left down
+//! 12 key KeyW up
+//!```
+use std::{path::Path, sync::Mutex};
+
+use winit::{event::MouseButton, keyboard::KeyCode};
+
+use crate::math::Vec2;
+
+///Input event of the window, as it's applied to the input state
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputEvent {
+    ///A key was pressed or released
+    Key {
+        ///The key
+        key: KeyCode,
+        ///Whether the key was pressed
+        pressed: bool,
+    },
+    ///A mouse button was pressed or released
+    MouseButton {
+        ///The button
+        button: MouseButton,
+        ///Whether the button was pressed
+        pressed: bool,
+    },
+    ///The cursor moved to the position inside the window
+    CursorMoved(Vec2),
+    ///Raw movement of the mouse, used for [`super::cursor_delta`]
+    MouseMotion(Vec2),
+}
+
+impl InputEvent {
+    ///Converts the event of the window, returns `None` if it does not affect the input state
+    #[must_use]
+    pub fn from_window_event(event: &winit::event::WindowEvent) -> Option<Self> {
+        use winit::event::WindowEvent;
+
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => match event.physical_key {
+                winit::keyboard::PhysicalKey::Code(key) => Some(Self::Key {
+                    key,
+                    pressed: event.state.is_pressed(),
+                }),
+                winit::keyboard::PhysicalKey::Unidentified(_) => None,
+            },
+            WindowEvent::MouseInput { state, button, .. } => Some(Self::MouseButton {
+                button: *button,
+                pressed: state.is_pressed(),
+            }),
+            WindowEvent::CursorMoved { position, .. } => Some(Self::CursorMoved(Vec2::new(
+                position.x as f32,
+                position.y as f32,
+            ))),
+            _ => None,
+        }
+    }
+}
+
+const fn state(pressed: bool) -> &'static str {
+    if pressed {
+        "down"
+    } else {
+        "up"
+    }
+}
+
+impl std::fmt::Display for InputEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Key { key, pressed } => write!(f, "key {key:?} {}", state(*pressed)),
+            Self::MouseButton { button, pressed } => {
+                let state = state(*pressed);
+                match button {
+                    MouseButton::Other(b) => write!(f, "mouse {b} {state}"),
+                    b => write!(f, "mouse {b:?} {state}"),
+                }
+            }
+            Self::CursorMoved(p) => write!(f, "cursor {} {}", p.x, p.y),
+            Self::MouseMotion(d) => write!(f, "motion {} {}", d.x, d.y),
+        }
+    }
+}
+
+impl std::str::FromStr for InputEvent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words = s.split_whitespace().collect::<Vec<_>>();
+
+        let pressed = |w: &str| match w {
+            "down" => Ok(true),
+            "up" => Ok(false),
+            _ => Err(format!("Unknown state {w}")),
+        };
+        let vector = |x: &str, y: &str| {
+            Ok(Vec2::new(
+                x.parse().map_err(|_| format!("Invalid number {x}"))?,
+                y.parse().map_err(|_| format!("Invalid number {y}"))?,
+            ))
+        };
+
+        match words.as_slice() {
+            ["key", key, state] => Ok(Self::Key {
+                key: super::key_from_name(key).ok_or_else(|| format!("Unknown key {key}"))?,
+                pressed: pressed(state)?,
+            }),
+            ["mouse", button, state] => Ok(Self::MouseButton {
+                button: match *button {
+                    "Left" => MouseButton::Left,
+                    "Right" => MouseButton::Right,
+                    "Middle" => MouseButton::Middle,
+                    "Back" => MouseButton::Back,
+                    "Forward" => MouseButton::Forward,
+                    b => MouseButton::Other(
+                        b.parse()
+                            .map_err(|_| format!("Unknown mouse button {b}"))?,
+                    ),
+                },
+                pressed: pressed(state)?,
+            }),
+            ["cursor", x, y] => vector(x, y).map(Self::CursorMoved),
+            ["motion", x, y] => vector(x, y).map(Self::MouseMotion),
+            _ => Err(format!("Unknown event {s}")),
+        }
+    }
+}
+
+///Error while loading a recording
+#[derive(Debug)]
+pub enum LoadError {
+    ///The file could not be read
+    Io(std::io::Error),
+    ///A line of the file is not a valid event
+    Parse {
+        ///Line of the file, starting from 1
+        line: usize,
+        ///Description of the error
+        message: String,
+    },
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "Failed to read the recording: {e}"),
+            Self::Parse { line, message } => write!(f, "Invalid event on line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<std::io::Error> for LoadError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+///Input events along with the frames they arrived in
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    events: Vec<(u64, InputEvent)>,
+}
+
+impl Recording {
+    ///Creates an empty recording
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { events: Vec::new() }
+    }
+
+    ///Adds an event that arrived in `frame`, frames must not decrease
+    ///
+    ///# Panics
+    ///Panics if the frame is before the frame of the last event
+    pub fn push(&mut self, frame: u64, event: InputEvent) {
+        assert!(
+            self.events.last().is_none_or(|(f, _)| *f <= frame),
+            "Events must be added in order"
+        );
+        self.events.push((frame, event));
+    }
+
+    ///Returns the events along with their frames
+    #[must_use]
+    pub fn get_events(&self) -> &[(u64, InputEvent)] {
+        &self.events
+    }
+
+    ///Returns the number of frames the recording spans
+    #[must_use]
+    pub fn frames(&self) -> u64 {
+        self.events.last().map_or(0, |(f, _)| f + 1)
+    }
+
+    ///Parses a recording, empty lines and lines starting with `#` are ignored
+    ///
+    ///# Errors
+    ///Returns an error if a line is not a valid event
+    pub fn parse(text: &str) -> Result<Self, LoadError> {
+        let mut recording = Self::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message| LoadError::Parse {
+                line: i + 1,
+                message,
+            };
+
+            let (frame, event) = line
+                .split_once(' ')
+                .ok_or_else(|| error(format!("Missing event {line}")))?;
+            let frame = frame
+                .parse::<u64>()
+                .map_err(|_| error(format!("Invalid frame {frame}")))?;
+            if recording.frames() > frame + 1 {
+                return Err(error(format!("Frame {frame} is out of order")));
+            }
+            recording.push(frame, event.parse().map_err(error)?);
+        }
+
+        Ok(recording)
+    }
+
+    ///Loads a recording from a file
+    ///
+    ///# Errors
+    ///Returns an error if the file can't be read or contains an invalid event
+    pub fn load(path: &Path) -> Result<Self, LoadError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    ///Saves the recording to a file
+    ///
+    ///# Errors
+    ///Returns an error if the file can't be written
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+}
+
+impl std::fmt::Display for Recording {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (frame, event) in &self.events {
+            writeln!(f, "{frame} {event}")?;
+        }
+        Ok(())
+    }
+}
+
+//The recording and the frame it started in
+static RECORDER: Mutex<Option<(u64, Recording)>> = Mutex::new(None);
+
+///Starts recording the input, discarding the current recording
+pub fn start() {
+    *RECORDER.lock().unwrap() = Some((crate::time::frame(), Recording::new()));
+}
+
+///Stops recording the input, returns the recording or `None` if it wasn't recording
+pub fn stop() -> Option<Recording> {
+    RECORDER.lock().unwrap().take().map(|(_, r)| r)
+}
+
+///Returns `true` if the input is being recorded
+pub fn is_recording() -> bool {
+    RECORDER.lock().unwrap().is_some()
+}
+
+pub(crate) fn record(event: &InputEvent) {
+    if let Some((start, recording)) = RECORDER.lock().unwrap().as_mut() {
+        recording.push(crate::time::frame().saturating_sub(*start), *event);
+    }
+}
+
+///Replays a recording into the input state, one frame at a time
+///
+///The events go through the same path as the events of the window, so the input functions return
+///the same values as when the input was recorded
+pub struct Replay {
+    recording: Recording,
+    frame: u64,
+    next: usize,
+}
+
+impl Replay {
+    ///Creates a replay starting from the first frame of the recording
+    #[must_use]
+    pub fn new(recording: Recording) -> Self {
+        super::initialize();
+        Self {
+            recording,
+            frame: 0,
+            next: 0,
+        }
+    }
+
+    ///Advances the input state by a frame and applies the events of that frame, call before
+    ///updating the game each frame
+    pub fn next_frame(&mut self) {
+        //The engine updates the states at the end of every frame
+        if self.frame != 0 {
+            super::update();
+        }
+
+        let events = &self.recording.events[self.next..];
+        let count = events.iter().take_while(|(f, _)| *f == self.frame).count();
+        for (_, event) in &events[..count] {
+            super::process_event(event);
+        }
+        self.next += count;
+        self.frame += 1;
+    }
+
+    ///Returns the number of frames replayed so far
+    #[must_use]
+    pub const fn get_frame(&self) -> u64 {
+        self.frame
+    }
+
+    ///Returns `true` if all the frames of the recording were replayed
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.recording.frames()
+    }
+}
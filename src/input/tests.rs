@@ -0,0 +1,83 @@
+use winit::{event::MouseButton, keyboard::KeyCode};
+
+use super::{
+    recording::{InputEvent, Recording, Replay},
+    KeyState,
+};
+use crate::math::Vec2;
+
+#[test]
+fn test_recording_format() {
+    let mut recording = Recording::new();
+    recording.push(
+        0,
+        InputEvent::Key {
+            key: KeyCode::KeyW,
+            pressed: true,
+        },
+    );
+    recording.push(3, InputEvent::CursorMoved(Vec2::new(512.5, 300.0)));
+    recording.push(
+        3,
+        InputEvent::MouseButton {
+            button: MouseButton::Other(8),
+            pressed: false,
+        },
+    );
+    recording.push(4, InputEvent::MouseMotion(Vec2::new(-1.25, 0.1)));
+
+    let text = recording.to_string();
+    assert_eq!(
+        text,
+        "0 key KeyW down\n3 cursor 512.5 300\n3 mouse 8 up\n4 motion -1.25 0.1\n"
+    );
+    assert_eq!(Recording::parse(&text).unwrap(), recording);
+
+    assert!(Recording::parse("0 key NotAKey down").is_err());
+    assert!(Recording::parse("2 key KeyA down\n1 key KeyA up").is_err());
+}
+
+#[test]
+fn test_replay() {
+    let recording = Recording::parse(
+        "# Tap Q, then hold the left button while moving the cursor
+0 key KeyQ down
+1 key KeyQ up
+2 mouse Left down
+2 cursor 10 20
+4 mouse Left up",
+    )
+    .unwrap();
+    let mut replay = Replay::new(recording);
+
+    let mut keys = Vec::new();
+    let mut buttons = Vec::new();
+    while !replay.is_finished() {
+        replay.next_frame();
+        keys.push(super::key(KeyCode::KeyQ));
+        buttons.push(super::mouse_btn(MouseButton::Left));
+    }
+
+    assert_eq!(replay.get_frame(), 5);
+    assert_eq!(
+        keys,
+        [
+            KeyState::Down,
+            KeyState::Up,
+            KeyState::Neutral,
+            KeyState::Neutral,
+            KeyState::Neutral
+        ]
+    );
+    assert_eq!(
+        buttons,
+        [
+            KeyState::Neutral,
+            KeyState::Neutral,
+            KeyState::Down,
+            KeyState::Pressed,
+            KeyState::Up
+        ]
+    );
+    assert_eq!(super::cursor_position(), Vec2::new(10.0, 20.0));
+}
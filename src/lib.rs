@@ -49,8 +49,6 @@ use std::{
     sync::{OnceLock, RwLock},
 };
 
-use chrono::DateTime;
-use input::INPUT;
 #[allow(clippy::wildcard_imports)]
 use internal::*;
 use wgpu::SurfaceConfiguration;
@@ -62,47 +60,110 @@ use crate::wrappers::WgpuWrapper;
 pub mod asset_managment;
 pub mod assets;
 pub mod components;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod crash;
+pub mod determinism;
 pub mod ecs;
+pub mod errors;
 mod grimoire;
 mod helpers;
 pub mod import;
 pub mod input;
 pub mod internal;
 mod logging;
-pub mod math;
+pub use lunar_engine_math as math;
+pub use lunar_engine_math::assert_approx_eq;
+pub mod physics2d;
+pub mod platform;
+#[cfg(all(feature = "plugins", not(target_arch = "wasm32")))]
+pub mod plugins;
 pub mod rendering;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+pub mod streaming;
 ///Various structures
 pub mod structures;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod template;
 #[cfg(test)]
 mod test_utils;
-mod windowing;
+pub mod time;
+pub mod timeline;
+pub mod validation;
+#[cfg(target_arch = "wasm32")]
+pub mod web_cache;
+pub mod windowing;
 #[cfg(target_arch = "wasm32")]
 mod wrappers;
 
 //TODO find a better way than just staticing it
 static WINDOW: OnceLock<winit::window::Window> = OnceLock::new();
 
+//The surface and the depth attachment are `None` after the shutdown
 #[cfg(target_arch = "wasm32")]
-static SURFACE: OnceLock<RwLock<wrappers::WgpuWrapper<wgpu::Surface>>> = OnceLock::new();
+static SURFACE: OnceLock<RwLock<Option<wrappers::WgpuWrapper<wgpu::Surface>>>> = OnceLock::new();
 #[cfg(target_arch = "wasm32")]
-static DEPTH: OnceLock<RwLock<wrappers::WgpuWrapper<wgpu::Texture>>> = OnceLock::new();
+static DEPTH: OnceLock<RwLock<Option<wrappers::WgpuWrapper<wgpu::Texture>>>> = OnceLock::new();
 
 #[cfg(not(target_arch = "wasm32"))]
-static SURFACE: OnceLock<RwLock<wgpu::Surface>> = OnceLock::new();
+static SURFACE: OnceLock<RwLock<Option<wgpu::Surface>>> = OnceLock::new();
 #[cfg(not(target_arch = "wasm32"))]
-static DEPTH: OnceLock<RwLock<wgpu::Texture>> = OnceLock::new();
+static DEPTH: OnceLock<RwLock<Option<wgpu::Texture>>> = OnceLock::new();
 
 static QUIT: OnceLock<bool> = OnceLock::new();
-static DELTA_TIME: RwLock<f32> = RwLock::new(0.01);
 
 ///Exits the application and closes the window
 pub fn quit() {
     QUIT.set(true).unwrap();
 }
 
+///Information about the engine build
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuildInfo {
+    ///Version of the engine
+    pub version: &'static str,
+    ///Short hash of the commit the engine was built from, `None` if it was not built from a git
+    ///repository
+    pub git_hash: Option<&'static str>,
+    ///Enabled cargo features of the engine
+    pub features: &'static [&'static str],
+}
+
+const FEATURES: &[&str] = &[
+    #[cfg(feature = "webgl")]
+    "webgl",
+    #[cfg(feature = "glam")]
+    "glam",
+    #[cfg(feature = "mint")]
+    "mint",
+    #[cfg(feature = "serde")]
+    "serde",
+    #[cfg(feature = "dialogs")]
+    "dialogs",
+    #[cfg(feature = "plugins")]
+    "plugins",
+    #[cfg(feature = "scripting")]
+    "scripting",
+];
+
+///Returns the version, commit hash and enabled features of the engine
+///
+///See [`rendering::capabilities`] for the capabilities of the gpu
+#[must_use]
+pub const fn build_info() -> BuildInfo {
+    BuildInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        git_hash: option_env!("LUNAR_ENGINE_GIT_HASH"),
+        features: FEATURES,
+    }
+}
+
 ///Returns time between frames in seconds
+///
+///See [`time`] for more timing functions
+#[must_use]
 pub fn delta_time() -> f32 {
-    *DELTA_TIME.read().unwrap()
+    time::delta_time()
 }
 
 ///Contains main state of the app
@@ -110,9 +171,10 @@ pub fn delta_time() -> f32 {
 pub struct State<T> {
     first_resume: bool,
     surface_config: OnceCell<SurfaceConfiguration>,
-    contents: T,
+    //Dropped on shutdown, before the gpu resources of the engine
+    contents: Option<T>,
     closed: bool,
-    frame_start: Option<DateTime<chrono::Local>>,
+    frame_start: Option<u64>,
     init: Option<Box<dyn FnOnce(&mut T)>>,
     run: Option<Box<dyn Fn(&mut T)>>,
     end: Option<Box<dyn FnOnce(&mut T)>>,
@@ -123,7 +185,7 @@ impl<T: Default> Default for State<T> {
         Self {
             first_resume: false,
             surface_config: OnceCell::default(),
-            contents: Default::default(),
+            contents: Some(T::default()),
             closed: Default::default(),
             frame_start: None,
             init: None,
@@ -139,7 +201,7 @@ impl<T: 'static> State<T> {
         Self {
             first_resume: false,
             surface_config: OnceCell::new(),
-            contents,
+            contents: Some(contents),
             closed: false,
             frame_start: None,
             init: None,
@@ -170,6 +232,8 @@ impl<T: 'static> State<T> {
                 log::error!("{e}");
             }));
         }
+        #[cfg(not(target_arch = "wasm32"))]
+        crash::install();
 
         //Initialize logging first
 
@@ -196,14 +260,17 @@ impl<T: 'static> State<T> {
 impl<T> State<T> {
     fn initialize(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         #[cfg(not(target_arch = "wasm32"))]
-        let attributes = winit::window::Window::default_attributes();
+        let attributes = winit::window::Window::default_attributes()
+            .with_transparent(rendering::surface::is_transparent())
+            .with_fullscreen(windowing::monitor::initial_fullscreen());
         let window;
         #[cfg(target_arch = "wasm32")]
         {
             use wasm_bindgen::JsCast;
             use winit::platform::web::WindowAttributesExtWebSys;
 
-            let mut attributes = winit::window::Window::default_attributes();
+            let mut attributes = winit::window::Window::default_attributes()
+                .with_transparent(rendering::surface::is_transparent());
 
             //Acquire a canvas as a base for the window
             let canvas = web_sys::window()
@@ -242,6 +309,7 @@ impl<T> State<T> {
 
         WINDOW.set(window).unwrap();
         let window = WINDOW.get().unwrap();
+        windowing::monitor::initialize();
 
         let (surface, config, depth_stencil) = windowing::initialize_gpu(window);
 
@@ -251,18 +319,20 @@ impl<T> State<T> {
 
         #[cfg(not(target_arch = "wasm32"))]
         {
-            SURFACE.set(RwLock::new(surface)).unwrap();
-            DEPTH.set(RwLock::new(depth_stencil)).unwrap();
+            SURFACE.set(RwLock::new(Some(surface))).unwrap();
+            DEPTH.set(RwLock::new(Some(depth_stencil))).unwrap();
         }
         #[cfg(target_arch = "wasm32")]
         {
-            SURFACE.set(RwLock::new(WgpuWrapper::new(surface))).unwrap();
+            SURFACE
+                .set(RwLock::new(Some(WgpuWrapper::new(surface))))
+                .unwrap();
             DEPTH
-                .set(RwLock::new(WgpuWrapper::new(depth_stencil)))
+                .set(RwLock::new(Some(WgpuWrapper::new(depth_stencil))))
                 .unwrap();
         }
 
-        self.init.take().unwrap()(&mut self.contents);
+        self.init.take().unwrap()(self.contents.as_mut().unwrap());
 
         event_loop.set_control_flow(winit::event_loop::ControlFlow::Wait);
     }
@@ -274,49 +344,74 @@ impl<T> State<T> {
         self.surface_config.get_mut().unwrap().height = size.height;
         let device = DEVICE.get().unwrap();
 
-        SURFACE
-            .get()
-            .unwrap()
-            .write()
-            .unwrap()
-            .configure(device, self.surface_config.get().unwrap());
+        //Nothing is rendered after the shutdown
+        {
+            let surface = SURFACE.get().unwrap().read().unwrap();
+            let Some(surface) = surface.as_ref() else {
+                return;
+            };
+            surface.configure(device, self.surface_config.get().unwrap());
+        }
         let desc = windowing::get_depth_descriptor(size.width, size.height);
 
         #[cfg(target_arch = "wasm32")]
         {
-            **DEPTH.get().unwrap().write().unwrap() = device.create_texture(&desc);
+            *DEPTH.get().unwrap().write().unwrap() =
+                Some(WgpuWrapper::new(device.create_texture(&desc)));
         }
 
         #[cfg(not(target_arch = "wasm32"))]
         {
-            *DEPTH.get().unwrap().write().unwrap() = device.create_texture(&desc);
+            *DEPTH.get().unwrap().write().unwrap() = Some(device.create_texture(&desc));
         }
     }
 
     fn redraw(&mut self) {
         //Frame time includes the wait between frames
-        if let Some(start) = self.frame_start {
-            let finish = chrono::Local::now();
-
-            let delta = (finish - start).abs().num_microseconds().unwrap() as f32 / 1_000_000.0;
-
-            *DELTA_TIME.write().unwrap() = delta;
-        }
-        self.frame_start = Some(chrono::Local::now());
+        self.frame_start = Some(self.frame_start.map_or_else(time::now, time::end_frame));
+        let _frame = timeline::frame();
 
+        let input = timeline::span_with_category("Input", "engine");
         input::process_cursor();
+        drop(input);
 
         if self.closed {
-            //This should be fine but needs further testing
-            self.end.take().unwrap()(&mut self.contents);
-
+            self.shutdown();
             return;
         }
-        self.run.as_ref().unwrap()(&mut self.contents);
+        let run = timeline::span_with_category("Game loop", "engine");
+        self.run.as_ref().unwrap()(self.contents.as_mut().unwrap());
+        drop(run);
         input::update();
 
         WINDOW.get().unwrap().request_redraw();
     }
+
+    ///Calls the disposal function and releases everything in a fixed order, the contents of the
+    ///state first, as they own the world and the assets, then the resources of the renderer, and
+    ///finally the staging belt, the surface and the device
+    ///
+    ///Only the first call does anything
+    fn shutdown(&mut self) {
+        let Some(mut contents) = self.contents.take() else {
+            return;
+        };
+        log::info!("Shutting down");
+
+        //The disposal function is only called if the application was initialized
+        if self.init.is_none() {
+            if let Some(end) = self.end.take() {
+                end(&mut contents);
+            }
+        }
+        drop(contents);
+
+        if DEVICE.get().is_none() {
+            return;
+        }
+        rendering::release();
+        windowing::shutdown_gpu();
+    }
 }
 
 impl<T> ApplicationHandler for State<T> {
@@ -327,22 +422,22 @@ impl<T> ApplicationHandler for State<T> {
         self.initialize(event_loop);
     }
 
+    fn exiting(&mut self, _: &winit::event_loop::ActiveEventLoop) {
+        //The window may be closed without another frame being rendered
+        self.shutdown();
+    }
+
     fn device_event(
         &mut self,
         _: &winit::event_loop::ActiveEventLoop,
         _: event::DeviceId,
         event: event::DeviceEvent,
     ) {
-        #[allow(clippy::single_match)]
-        match event {
-            event::DeviceEvent::MouseMotion { delta } => {
-                let d = math::Vec2::new(delta.0 as f32, delta.1 as f32);
-
-                let i = INPUT.get().unwrap();
-                *i.raw_curosor_delta.write().unwrap() = d;
-                *i.delta_changed.write().unwrap() = true;
-            }
-            _ => {}
+        if let event::DeviceEvent::MouseMotion { delta } = event {
+            input::process_event(&input::InputEvent::MouseMotion(math::Vec2::new(
+                delta.0 as f32,
+                delta.1 as f32,
+            )));
         }
     }
 
@@ -358,6 +453,9 @@ impl<T> ApplicationHandler for State<T> {
                 event_loop.exit();
                 self.closed = true;
             }
+            event::WindowEvent::Moved(_) | event::WindowEvent::ScaleFactorChanged { .. } => {
+                windowing::monitor::process_event(&event);
+            }
             event::WindowEvent::RedrawRequested => {
                 if QUIT.get().is_some() {
                     event_loop.exit();
@@ -366,48 +464,22 @@ impl<T> ApplicationHandler for State<T> {
 
                 self.redraw();
             }
-            event::WindowEvent::KeyboardInput {
-                device_id: _,
-                event,
-                is_synthetic: _,
-            } => {
-                let state = match event.state {
-                    event::ElementState::Pressed => input::KeyState::Down,
-                    event::ElementState::Released => input::KeyState::Up,
-                };
-                let keycode = if let winit::keyboard::PhysicalKey::Code(code) = event.physical_key {
-                    Some(code)
-                } else {
-                    None
-                };
-                if keycode.is_none() {
+            _ => {
+                let Some(input) = input::InputEvent::from_window_event(&event) else {
                     return;
+                };
+                input::process_event(&input);
+
+                //Browsers only allow fullscreen and pointer capture during user gestures
+                #[cfg(target_arch = "wasm32")]
+                if matches!(
+                    input,
+                    input::InputEvent::Key { pressed: true, .. }
+                        | input::InputEvent::MouseButton { pressed: true, .. }
+                ) {
+                    platform::web::process_pending();
                 }
-                input::set_key(keycode.unwrap(), state);
-            }
-            event::WindowEvent::MouseInput {
-                device_id: _,
-                state,
-                button,
-            } => match state {
-                event::ElementState::Pressed => {
-                    input::set_mouse_button(button, input::KeyState::Down);
-                }
-                event::ElementState::Released => {
-                    input::set_mouse_button(button, input::KeyState::Up);
-                }
-            },
-
-            event::WindowEvent::CursorMoved {
-                device_id: _,
-                position,
-            } => {
-                input::set_cursor_position(math::Vec2 {
-                    x: position.x as f32,
-                    y: position.y as f32,
-                });
             }
-            _ => {}
         }
     }
 }
@@ -1,4 +1,9 @@
 #![allow(clippy::module_name_repetitions, dead_code)]
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, OnceLock},
+};
+
 fn parse_log_level(v: &str) -> log::LevelFilter {
     let upper = v.to_uppercase();
     #[allow(clippy::match_same_arms)]
@@ -46,5 +51,65 @@ pub fn initialize_logging() -> Result<(), lunar_logger::LoggerError> {
     if log_to_file {
         b = b.log_to_file();
     }
-    b.init()
+
+    let max_level = log_level
+        .max(engine_log_level)
+        .max(wgpu_log_level)
+        .max(wgpu_hal_log_level);
+    if LOGGER.set(RecordingLogger { inner: b.create() }).is_err() {
+        return Err(lunar_logger::LoggerError::LoggerAlreadySet);
+    }
+    log::set_logger(LOGGER.get().unwrap())
+        .map_err(|_| lunar_logger::LoggerError::LoggerAlreadySet)?;
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+///Number of the recent messages kept for crash reports
+const RECENT_CAPACITY: usize = 256;
+
+static LOGGER: OnceLock<RecordingLogger> = OnceLock::new();
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+///Logger that keeps the recent messages of level info and more severe
+struct RecordingLogger {
+    inner: lunar_logger::Logger,
+}
+
+impl log::Log for RecordingLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if record.level() <= log::Level::Info {
+            if let Ok(mut recent) = RECENT.lock() {
+                if recent.len() == RECENT_CAPACITY {
+                    recent.pop_front();
+                }
+                recent.push_back(format!(
+                    "[frame {} {} {}] {}",
+                    crate::time::frame(),
+                    record.level(),
+                    record.target(),
+                    record.args()
+                ));
+            }
+        }
+        self.inner.log(record);
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+///Returns the recent log messages, from the oldest to the newest
+///
+///Does not wait for the lock, as it is called while panicking
+pub fn recent_messages() -> Vec<String> {
+    RECENT
+        .try_lock()
+        .map(|r| r.iter().cloned().collect())
+        .unwrap_or_default()
 }
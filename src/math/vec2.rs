@@ -1,135 +0,0 @@
-use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign};
-
-use bytemuck::{Pod, Zeroable};
-
-pub use crate::math::traits::Vector;
-
-#[repr(C)]
-#[allow(missing_docs)]
-#[derive(Clone, Copy, Default, Debug, PartialEq, PartialOrd, Pod, Zeroable)]
-///A generic vector with 2 dimensions
-pub struct Vec2 {
-    pub x: f32,
-    pub y: f32,
-}
-
-impl Vec2 {
-    #[must_use]
-    ///Creates a new vector
-    pub const fn new(x: f32, y: f32) -> Self {
-        Self { x, y }
-    }
-
-    ///Returns the absolute vector
-    #[must_use]
-    pub fn abs(self) -> Self {
-        Self {
-            x: self.x.abs(),
-            y: self.y.abs(),
-        }
-    }
-}
-
-impl Vector for Vec2 {
-    fn square_length(&self) -> f32 {
-        self.x.mul_add(self.x, self.y * self.y)
-    }
-    fn dot_product(&self, other: &Self) -> f32 {
-        self.x.mul_add(other.x, self.y * other.y)
-    }
-}
-
-impl From<(f32, f32)> for Vec2 {
-    fn from(a: (f32, f32)) -> Self {
-        Self { x: a.0, y: a.1 }
-    }
-}
-
-impl Div<f32> for Vec2 {
-    type Output = Self;
-
-    fn div(self, rhs: f32) -> Self::Output {
-        Self::new(self.x / rhs, self.y / rhs)
-    }
-}
-impl Mul<f32> for Vec2 {
-    type Output = Self;
-
-    fn mul(self, rhs: f32) -> Self::Output {
-        Self::new(self.x * rhs, self.y * rhs)
-    }
-}
-impl Sub<Self> for Vec2 {
-    type Output = Self;
-
-    fn sub(self, rhs: Self) -> Self::Output {
-        Self::new(self.x - rhs.x, self.y - rhs.y)
-    }
-}
-impl Add<Self> for Vec2 {
-    type Output = Self;
-
-    fn add(self, rhs: Self) -> Self::Output {
-        Self::new(self.x + rhs.x, self.y + rhs.y)
-    }
-}
-
-impl AddAssign<Self> for Vec2 {
-    fn add_assign(&mut self, rhs: Self) {
-        self.x += rhs.x;
-        self.y += rhs.y;
-    }
-}
-
-impl SubAssign<Self> for Vec2 {
-    fn sub_assign(&mut self, rhs: Self) {
-        self.x -= rhs.x;
-        self.y -= rhs.y;
-    }
-}
-
-impl MulAssign<f32> for Vec2 {
-    fn mul_assign(&mut self, rhs: f32) {
-        self.x *= rhs;
-        self.y *= rhs;
-    }
-}
-
-impl DivAssign<f32> for Vec2 {
-    fn div_assign(&mut self, rhs: f32) {
-        self.x /= rhs;
-        self.y /= rhs;
-    }
-}
-
-impl Add<f32> for Vec2 {
-    type Output = Self;
-    fn add(self, rhs: f32) -> Self::Output {
-        Self {
-            x: self.x + rhs,
-            y: self.y + rhs,
-        }
-    }
-}
-
-impl Sub<f32> for Vec2 {
-    type Output = Self;
-    fn sub(self, rhs: f32) -> Self::Output {
-        Self {
-            x: self.x - rhs,
-            y: self.y - rhs,
-        }
-    }
-}
-
-impl From<f32> for Vec2 {
-    fn from(value: f32) -> Self {
-        Self { x: value, y: value }
-    }
-}
-
-impl std::fmt::Display for Vec2 {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "({}, {})", self.x, self.y)
-    }
-}
@@ -0,0 +1,577 @@
+//! 2D physics for the sprite and tilemap workflow
+//!
+//! Entities are simulated on the XY plane, using the x and y of the [`Transform`] position, the z
+//! coordinate is left untouched. Entities with a [`Collider2D`] are static, unless they also have
+//! a [`RigidBody2D`].
+//!
+//! Colliders use the local position and scale of the transform, so they should be placed on
+//! entities without a parent.
+//!
+//!```no_run
+//! # use lunar_engine::ecs::{EntityBuilder, World};
+//! # use lunar_engine::components::transform::Transform;
+//! # use lunar_engine::math::Vec2;
+//! use lunar_engine::physics2d::{Collider2D, Physics2D, RigidBody2D, Shape2D};
+//!
+//! let mut world = World::new();
+//! //Ground
+//! world.add_entity(
+//!     EntityBuilder::new()
+//!         .add_component::<Transform>()
+//!         .create_component(|| Collider2D::new(Shape2D::rect(Vec2::new(10.0, 0.5))))
+//!         .create()
+//!         .unwrap(),
+//! );
+//! //Player
+//! world.add_entity(
+//!     EntityBuilder::new()
+//!         .add_component::<Transform>()
+//!         .create_component(|| Collider2D::new(Shape2D::Circle { radius: 0.5 }))
+//!         .add_component::<RigidBody2D>()
+//!         .create()
+//!         .unwrap(),
+//! );
+//!
+//! let physics = Physics2D::default();
+//! //Every frame
+//! physics.step(&world, lunar_engine::time::delta_time());
+//!```
+use lunar_engine_derive::{as_any, dependencies};
+
+use crate as lunar_engine;
+
+use crate::{
+    components::transform::Transform,
+    ecs::{Component, ComponentReference, World, UUID},
+    math::{Vec2, Vector},
+};
+
+#[cfg(test)]
+mod tests;
+
+///Shape of a [`Collider2D`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shape2D {
+    ///Axis aligned rectangle
+    Rect {
+        ///Half of the width and height of the rectangle
+        half_extents: Vec2,
+    },
+    ///Circle
+    Circle {
+        ///Radius of the circle
+        radius: f32,
+    },
+}
+
+impl Shape2D {
+    ///Creates a rectangle with the given half extents
+    #[must_use]
+    pub const fn rect(half_extents: Vec2) -> Self {
+        Self::Rect { half_extents }
+    }
+
+    ///Returns the shape scaled by the scale of a transform, circles use the larger of the 2 axes
+    fn scaled(self, scale: Vec2) -> Self {
+        let scale = scale.abs();
+        match self {
+            Self::Rect { half_extents } => Self::Rect {
+                half_extents: half_extents * scale,
+            },
+            Self::Circle { radius } => Self::Circle {
+                radius: radius * scale.x.max(scale.y),
+            },
+        }
+    }
+}
+
+///Collision shape of an entity
+#[derive(Debug)]
+pub struct Collider2D {
+    ///Shape of the collider
+    pub shape: Shape2D,
+    ///Offset of the shape from the position of the transform
+    pub offset: Vec2,
+    ///One way platforms only collide with bodies falling on them from above and are ignored by
+    ///raycasts going up
+    pub one_way: bool,
+}
+
+impl Component for Collider2D {
+    #[as_any]
+    #[dependencies(Transform)]
+    fn mew() -> Self
+    where
+        Self: Sized,
+    {
+        Self::new(Shape2D::rect(Vec2::new(0.5, 0.5)))
+    }
+}
+
+impl Collider2D {
+    ///Creates a new collider with the given shape
+    #[must_use]
+    pub const fn new(shape: Shape2D) -> Self {
+        Self {
+            shape,
+            offset: Vec2::new(0.0, 0.0),
+            one_way: false,
+        }
+    }
+
+    ///Creates a new one way platform with the given shape
+    #[must_use]
+    pub const fn one_way(shape: Shape2D) -> Self {
+        Self {
+            shape,
+            offset: Vec2::new(0.0, 0.0),
+            one_way: true,
+        }
+    }
+}
+
+///Makes the entity with a [`Collider2D`] move and react to collisions
+#[derive(Debug)]
+pub struct RigidBody2D {
+    ///Velocity of the body
+    pub velocity: Vec2,
+    ///Multiplier of the gravity affecting the body
+    pub gravity_scale: f32,
+    ///Kinematic bodies are moved only by their velocity, they push dynamic bodies, but are not
+    ///affected by gravity or collisions themselves
+    pub kinematic: bool,
+    grounded: bool,
+}
+
+impl Component for RigidBody2D {
+    #[as_any]
+    #[dependencies(Collider2D)]
+    fn mew() -> Self
+    where
+        Self: Sized,
+    {
+        Self::new()
+    }
+}
+
+impl Default for RigidBody2D {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RigidBody2D {
+    ///Creates a new dynamic body
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            velocity: Vec2::new(0.0, 0.0),
+            gravity_scale: 1.0,
+            kinematic: false,
+            grounded: false,
+        }
+    }
+
+    ///Creates a new kinematic body
+    #[must_use]
+    pub const fn kinematic() -> Self {
+        Self {
+            velocity: Vec2::new(0.0, 0.0),
+            gravity_scale: 0.0,
+            kinematic: true,
+            grounded: false,
+        }
+    }
+
+    ///Returns `true` if the body was standing on something during the last step
+    #[must_use]
+    pub const fn is_grounded(&self) -> bool {
+        self.grounded
+    }
+}
+
+///Result of a raycast
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit2D {
+    ///Id of the entity that was hit
+    pub entity: UUID,
+    ///Point where the ray hit the collider
+    pub point: Vec2,
+    ///Normal of the collider at the hit point
+    pub normal: Vec2,
+    ///Distance from the origin of the ray to the hit point
+    pub distance: f32,
+}
+
+///Simulation settings of the 2D physics
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Physics2D {
+    ///Acceleration applied to all dynamic bodies
+    pub gravity: Vec2,
+    ///Number of collision resolution passes per step, more passes make stacks of bodies more
+    ///stable
+    pub iterations: u32,
+}
+
+impl Default for Physics2D {
+    ///The default settings have the following values:
+    /// - Gravity: -9.81 along the Y axis
+    /// - Iterations: 4
+    fn default() -> Self {
+        Self {
+            gravity: Vec2::new(0.0, -9.81),
+            iterations: 4,
+        }
+    }
+}
+
+//State of a collider during a step
+struct Body {
+    id: UUID,
+    transform: ComponentReference<Transform>,
+    rigidbody: Option<ComponentReference<RigidBody2D>>,
+    shape: Shape2D,
+    offset: Vec2,
+    one_way: bool,
+    position: Vec2,
+    velocity: Vec2,
+    grounded: bool,
+}
+
+impl Body {
+    fn center(&self) -> Vec2 {
+        self.position + self.offset
+    }
+
+    //Dynamic bodies are moved by collisions
+    fn dynamic(&self) -> bool {
+        self.rigidbody
+            .as_ref()
+            .is_some_and(|b| !b.borrow().kinematic)
+    }
+}
+
+//Normal pointing from the second shape to the first one and the penetration depth
+struct Contact {
+    normal: Vec2,
+    depth: f32,
+}
+
+impl Physics2D {
+    ///Creates new settings with the given gravity
+    #[must_use]
+    pub const fn new(gravity: Vec2) -> Self {
+        Self {
+            gravity,
+            iterations: 4,
+        }
+    }
+
+    ///Advances the simulation by `delta` seconds
+    pub fn step(&self, world: &World, delta: f32) {
+        let mut bodies = collect(world);
+
+        for b in &mut bodies {
+            let Some(rigid_body) = &b.rigidbody else {
+                continue;
+            };
+            let rigid_body = rigid_body.borrow();
+            if !rigid_body.kinematic {
+                b.velocity += self.gravity * (rigid_body.gravity_scale * delta);
+            }
+            b.position += b.velocity * delta;
+        }
+
+        let dynamic = bodies.iter().map(Body::dynamic).collect::<Vec<_>>();
+
+        for _ in 0..self.iterations {
+            for i in 0..bodies.len() {
+                if !dynamic[i] {
+                    continue;
+                }
+                for j in 0..bodies.len() {
+                    //Pairs of dynamic bodies are resolved once
+                    if i == j || (dynamic[j] && j < i) {
+                        continue;
+                    }
+
+                    let (a, b) = (&bodies[i], &bodies[j]);
+                    let Some(contact) = collide(a.center(), a.shape, b.center(), b.shape) else {
+                        continue;
+                    };
+
+                    //Only land on one way platforms from above
+                    if b.one_way && (contact.normal.y < 0.7 || a.velocity.y > 0.0) {
+                        continue;
+                    }
+                    if a.one_way && (contact.normal.y > -0.7 || b.velocity.y > 0.0) {
+                        continue;
+                    }
+
+                    if dynamic[j] {
+                        let correction = contact.normal * (contact.depth * 0.5);
+                        resolve(&mut bodies[i], correction, contact.normal);
+                        resolve(&mut bodies[j], -correction, -contact.normal);
+                    } else {
+                        resolve(
+                            &mut bodies[i],
+                            contact.normal * contact.depth,
+                            contact.normal,
+                        );
+                    }
+                }
+            }
+        }
+
+        for b in bodies {
+            let Some(rigid_body) = &b.rigidbody else {
+                continue;
+            };
+            let mut rigid_body = rigid_body.borrow_mut();
+            rigid_body.velocity = b.velocity;
+            rigid_body.grounded = b.grounded;
+
+            let mut transform = b.transform.borrow_mut();
+            transform.position.x = b.position.x;
+            transform.position.y = b.position.y;
+        }
+    }
+
+    ///Casts a ray and returns the closest collider it hits within `max_distance`
+    #[must_use]
+    pub fn raycast(
+        &self,
+        world: &World,
+        origin: Vec2,
+        direction: Vec2,
+        max_distance: f32,
+    ) -> Option<RayHit2D> {
+        if direction.square_length() == 0.0 {
+            return None;
+        }
+        let direction = direction.normalized();
+
+        collect(world)
+            .into_iter()
+            .filter(|b| !b.one_way || direction.y < 0.0)
+            .filter_map(|b| {
+                let (distance, normal) = match b.shape {
+                    Shape2D::Rect { half_extents } => {
+                        ray_rect(origin, direction, b.center(), half_extents)
+                    }
+                    Shape2D::Circle { radius } => ray_circle(origin, direction, b.center(), radius),
+                }?;
+                (distance <= max_distance).then(|| RayHit2D {
+                    entity: b.id,
+                    point: origin + direction * distance,
+                    normal,
+                    distance,
+                })
+            })
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+    }
+}
+
+///Returns the state of all the colliders in the world
+fn collect(world: &World) -> Vec<Body> {
+    world
+        .get_all_entities_with_component::<Collider2D>()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|e| {
+            let e = e.borrow();
+            let collider = e.get_component::<Collider2D>()?;
+            let collider = collider.borrow();
+            let transform = e.get_component::<Transform>()?;
+            let rigidbody = e.get_component::<RigidBody2D>();
+
+            let (position, scale) = {
+                let t = transform.borrow();
+                (
+                    Vec2::new(t.position.x, t.position.y),
+                    Vec2::new(t.scale.x, t.scale.y),
+                )
+            };
+            let velocity = rigidbody
+                .as_ref()
+                .map_or_else(Vec2::default, |b| b.borrow().velocity);
+
+            Some(Body {
+                id: e.get_id(),
+                shape: collider.shape.scaled(scale),
+                offset: collider.offset * scale,
+                one_way: collider.one_way,
+                transform,
+                rigidbody,
+                position,
+                velocity,
+                grounded: false,
+            })
+        })
+        .collect()
+}
+
+///Moves the body out of the collision and removes the velocity towards the surface
+fn resolve(body: &mut Body, correction: Vec2, normal: Vec2) {
+    body.position += correction;
+
+    let speed = body.velocity.dot_product(&normal);
+    if speed < 0.0 {
+        body.velocity -= normal * speed;
+    }
+    if normal.y > 0.7 {
+        body.grounded = true;
+    }
+}
+
+///Returns the contact between 2 shapes, if they overlap
+fn collide(a_center: Vec2, a: Shape2D, b_center: Vec2, b: Shape2D) -> Option<Contact> {
+    match (a, b) {
+        (Shape2D::Rect { half_extents: a }, Shape2D::Rect { half_extents: b }) => {
+            let d = a_center - b_center;
+            let overlap = a + b - d.abs();
+            if overlap.x <= 0.0 || overlap.y <= 0.0 {
+                return None;
+            }
+
+            Some(if overlap.x < overlap.y {
+                Contact {
+                    normal: Vec2::new(1.0f32.copysign(d.x), 0.0),
+                    depth: overlap.x,
+                }
+            } else {
+                Contact {
+                    normal: Vec2::new(0.0, 1.0f32.copysign(d.y)),
+                    depth: overlap.y,
+                }
+            })
+        }
+        (Shape2D::Circle { radius: a }, Shape2D::Circle { radius: b }) => {
+            let d = a_center - b_center;
+            let distance = d.length();
+            if distance >= a + b {
+                return None;
+            }
+
+            Some(Contact {
+                normal: if distance == 0.0 {
+                    Vec2::new(0.0, 1.0)
+                } else {
+                    d / distance
+                },
+                depth: a + b - distance,
+            })
+        }
+        (Shape2D::Rect { half_extents }, Shape2D::Circle { radius }) => {
+            rect_circle(a_center, half_extents, b_center, radius).map(|c| Contact {
+                normal: -c.normal,
+                depth: c.depth,
+            })
+        }
+        (Shape2D::Circle { radius }, Shape2D::Rect { half_extents }) => {
+            rect_circle(b_center, half_extents, a_center, radius)
+        }
+    }
+}
+
+///Returns the contact with the normal pointing from the rectangle to the circle
+fn rect_circle(rect: Vec2, half_extents: Vec2, circle: Vec2, radius: f32) -> Option<Contact> {
+    let d = circle - rect;
+    let closest = d.max(-half_extents).min(half_extents);
+
+    //The center of the circle is inside of the rectangle
+    if closest == d {
+        let overlap = half_extents - d.abs();
+        return Some(if overlap.x < overlap.y {
+            Contact {
+                normal: Vec2::new(1.0f32.copysign(d.x), 0.0),
+                depth: overlap.x + radius,
+            }
+        } else {
+            Contact {
+                normal: Vec2::new(0.0, 1.0f32.copysign(d.y)),
+                depth: overlap.y + radius,
+            }
+        });
+    }
+
+    let offset = d - closest;
+    let distance = offset.length();
+    if distance >= radius {
+        return None;
+    }
+
+    Some(Contact {
+        normal: offset / distance,
+        depth: radius - distance,
+    })
+}
+
+///Returns the distance to the rectangle along the ray and the normal at the hit point
+fn ray_rect(
+    origin: Vec2,
+    direction: Vec2,
+    center: Vec2,
+    half_extents: Vec2,
+) -> Option<(f32, Vec2)> {
+    let min = center - half_extents;
+    let max = center + half_extents;
+
+    let mut near = f32::NEG_INFINITY;
+    let mut far = f32::INFINITY;
+    let mut normal = -direction;
+
+    for (o, d, min, max, axis) in [
+        (origin.x, direction.x, min.x, max.x, Vec2::new(1.0, 0.0)),
+        (origin.y, direction.y, min.y, max.y, Vec2::new(0.0, 1.0)),
+    ] {
+        if d == 0.0 {
+            if o < min || o > max {
+                return None;
+            }
+            continue;
+        }
+
+        let (t0, t1) = ((min - o) / d, (max - o) / d);
+        let (t0, t1) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+        if t0 > near {
+            near = t0;
+            normal = axis * -1.0f32.copysign(d);
+        }
+        far = far.min(t1);
+    }
+
+    if near > far || far < 0.0 {
+        return None;
+    }
+    //The origin is inside of the rectangle
+    if near < 0.0 {
+        return Some((0.0, -direction));
+    }
+    Some((near, normal))
+}
+
+///Returns the distance to the circle along the ray and the normal at the hit point
+fn ray_circle(origin: Vec2, direction: Vec2, center: Vec2, radius: f32) -> Option<(f32, Vec2)> {
+    let d = origin - center;
+    let b = d.dot_product(&direction);
+    let c = radius.mul_add(-radius, d.square_length());
+
+    //The origin is inside of the circle
+    if c <= 0.0 {
+        return Some((0.0, -direction));
+    }
+
+    let discriminant = b.mul_add(b, -c);
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let t = -b - discriminant.sqrt();
+    if t < 0.0 {
+        return None;
+    }
+
+    let normal = (d + direction * t) / radius;
+    Some((t, normal))
+}
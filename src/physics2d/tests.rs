@@ -0,0 +1,131 @@
+use crate::{
+    components::transform::Transform,
+    ecs::{Entity, EntityBuilder, World},
+    math::{Vec2, Vec3},
+};
+
+use super::{Collider2D, Physics2D, RigidBody2D, Shape2D};
+
+fn create(position: Vec2, collider: Collider2D, body: Option<RigidBody2D>) -> Entity {
+    let mut builder = EntityBuilder::new()
+        .create_component(|| Transform {
+            position: Vec3::new(position.x, position.y, 0.0),
+            ..Default::default()
+        })
+        .add_existing_component(collider);
+    if let Some(body) = body {
+        builder = builder.add_existing_component(body);
+    }
+    builder.create().unwrap()
+}
+
+fn position(world: &World) -> Vec2 {
+    let entity = world
+        .get_all_entities_with_component::<RigidBody2D>()
+        .unwrap()[0]
+        .clone();
+    let transform = entity.borrow().get_component::<Transform>().unwrap();
+    let position = transform.borrow().position;
+    Vec2::new(position.x, position.y)
+}
+
+fn grounded(world: &World) -> bool {
+    world.get_all_components::<RigidBody2D>().unwrap()[0]
+        .borrow()
+        .is_grounded()
+}
+
+#[test]
+fn falling_test() {
+    let mut world = World::new();
+    world.add_entity(create(
+        Vec2::new(0.0, 0.0),
+        Collider2D::new(Shape2D::rect(Vec2::new(10.0, 0.5))),
+        None,
+    ));
+    world.add_entity(create(
+        Vec2::new(0.0, 3.0),
+        Collider2D::new(Shape2D::Circle { radius: 0.5 }),
+        Some(RigidBody2D::new()),
+    ));
+
+    let physics = Physics2D::default();
+    for _ in 0..120 {
+        physics.step(&world, 1.0 / 60.0);
+    }
+
+    assert!((position(&world).y - 1.0).abs() < 0.05);
+    assert!(grounded(&world));
+}
+
+#[test]
+fn one_way_test() {
+    let mut world = World::new();
+    world.add_entity(create(
+        Vec2::new(0.0, 2.0),
+        Collider2D::one_way(Shape2D::rect(Vec2::new(2.0, 0.5))),
+        None,
+    ));
+    let mut body = RigidBody2D::new();
+    body.velocity = Vec2::new(0.0, 10.0);
+    world.add_entity(create(
+        Vec2::new(0.0, 0.0),
+        Collider2D::new(Shape2D::rect(Vec2::new(0.5, 0.5))),
+        Some(body),
+    ));
+
+    //Jumps through the platform
+    let physics = Physics2D::new(Vec2::new(0.0, 0.0));
+    for _ in 0..5 {
+        physics.step(&world, 0.1);
+    }
+    assert!((position(&world).y - 5.0).abs() < 0.001);
+
+    //Lands on it
+    let physics = Physics2D::new(Vec2::new(0.0, -20.0));
+    for _ in 0..120 {
+        physics.step(&world, 1.0 / 60.0);
+    }
+    assert!((position(&world).y - 3.0).abs() < 0.05);
+    assert!(grounded(&world));
+}
+
+#[test]
+fn raycast_test() {
+    let mut world = World::new();
+    let ground = create(
+        Vec2::new(0.0, 0.0),
+        Collider2D::new(Shape2D::rect(Vec2::new(10.0, 0.5))),
+        None,
+    );
+    let ground_id = ground.get_id();
+    world.add_entity(ground);
+    world.add_entity(create(
+        Vec2::new(5.0, 5.0),
+        Collider2D::one_way(Shape2D::Circle { radius: 1.0 }),
+        None,
+    ));
+
+    let physics = Physics2D::default();
+
+    let hit = physics
+        .raycast(&world, Vec2::new(0.0, 5.0), Vec2::new(0.0, -1.0), 10.0)
+        .unwrap();
+    assert_eq!(hit.entity, ground_id);
+    assert!((hit.distance - 4.5).abs() < 0.0001);
+    assert_eq!(hit.normal, Vec2::new(0.0, 1.0));
+
+    assert!(physics
+        .raycast(&world, Vec2::new(0.0, 5.0), Vec2::new(0.0, -1.0), 4.0)
+        .is_none());
+
+    //One way platforms are ignored by rays going up
+    assert!(physics
+        .raycast(&world, Vec2::new(5.0, 1.0), Vec2::new(0.0, 1.0), 10.0)
+        .is_none());
+
+    let hit = physics
+        .raycast(&world, Vec2::new(5.0, 10.0), Vec2::new(0.0, -1.0), 10.0)
+        .unwrap();
+    assert!((hit.distance - 4.0).abs() < 0.0001);
+}
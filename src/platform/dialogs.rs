@@ -0,0 +1,201 @@
+//! Native file dialogs, requires the `dialogs` feature
+//!
+//! The dialogs don't block the main loop, opening one returns a [`DialogTask`] that is polled
+//! every frame until the user picks a file or closes the dialog. On the web target the browser
+//! file picker is used, the picked files have no path, only their name and their bytes, and
+//! save dialogs are not available.
+//!
+//!```no_run
+//! use lunar_engine::platform::dialogs::FileDialog;
+//!
+//! let mut task = FileDialog::new()
+//!     .with_title("Open a mesh")
+//!     .with_filter("Wavefront", &["obj"])
+//!     .open();
+//!
+//! //Every frame
+//! if let Some(file) = task.poll() {
+//!     if let Some(file) = file {
+//!         println!("Opened {} with {} bytes", file.name, file.bytes.len());
+//!     }
+//! }
+//!```
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+///File picked in a dialog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PickedFile {
+    ///Name of the file, including the extension
+    pub name: String,
+    ///Path of the file, always `None` on the web target
+    pub path: Option<PathBuf>,
+    ///Contents of the file
+    pub bytes: Vec<u8>,
+}
+
+///Dialog running in the background, created by [`FileDialog`]
+pub struct DialogTask<T> {
+    future: Option<Pin<Box<dyn Future<Output = T>>>>,
+}
+
+impl<T> DialogTask<T> {
+    fn new(future: impl Future<Output = T> + 'static) -> Self {
+        Self {
+            future: Some(Box::pin(future)),
+        }
+    }
+
+    ///Returns the result of the dialog once it's closed, `None` while it is still open
+    ///
+    ///The result is only returned once, afterwards the task is finished and always returns `None`
+    pub fn poll(&mut self) -> Option<T> {
+        let future = self.future.as_mut()?;
+
+        //The task is polled every frame, so there is no need to be woken up
+        let waker = futures::task::noop_waker();
+        match future.as_mut().poll(&mut Context::from_waker(&waker)) {
+            Poll::Ready(result) => {
+                self.future = None;
+                Some(result)
+            }
+            Poll::Pending => None,
+        }
+    }
+
+    ///Returns `true` if the dialog was closed and it's result was returned by [`Self::poll`]
+    #[must_use]
+    pub const fn is_finished(&self) -> bool {
+        self.future.is_none()
+    }
+}
+
+///Builder of file dialogs
+#[derive(Debug, Clone, Default)]
+pub struct FileDialog {
+    title: Option<String>,
+    directory: Option<PathBuf>,
+    file_name: Option<String>,
+    filters: Vec<(String, Vec<String>)>,
+}
+
+impl FileDialog {
+    ///Creates a new dialog without a title or filters
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            title: None,
+            directory: None,
+            file_name: None,
+            filters: Vec::new(),
+        }
+    }
+
+    ///Sets the title of the dialog
+    #[must_use]
+    pub fn with_title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_owned());
+        self
+    }
+
+    ///Adds a filter showing only the files with the given extensions, without the leading dot
+    #[must_use]
+    pub fn with_filter(mut self, name: &str, extensions: &[&str]) -> Self {
+        self.filters.push((
+            name.to_owned(),
+            extensions.iter().map(|e| (*e).to_owned()).collect(),
+        ));
+        self
+    }
+
+    ///Sets the directory the dialog starts in, ignored on the web target
+    #[must_use]
+    pub fn with_directory(mut self, directory: &Path) -> Self {
+        self.directory = Some(directory.to_owned());
+        self
+    }
+
+    ///Sets the default name of the file in save dialogs
+    #[must_use]
+    pub fn with_file_name(mut self, name: &str) -> Self {
+        self.file_name = Some(name.to_owned());
+        self
+    }
+
+    fn build(self) -> rfd::AsyncFileDialog {
+        let mut dialog = rfd::AsyncFileDialog::new();
+
+        if let Some(title) = &self.title {
+            dialog = dialog.set_title(title);
+        }
+        if let Some(directory) = &self.directory {
+            dialog = dialog.set_directory(directory);
+        }
+        if let Some(name) = self.file_name {
+            dialog = dialog.set_file_name(name);
+        }
+        for (name, extensions) in &self.filters {
+            dialog = dialog.add_filter(name, extensions.as_slice());
+        }
+
+        //Keeps the dialog on top of the window
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(window) = crate::WINDOW.get() {
+            dialog = dialog.set_parent(window);
+        }
+        dialog
+    }
+
+    ///Opens a dialog for picking a single file, the task returns `None` if the dialog was closed
+    ///without picking a file
+    #[must_use]
+    pub fn open(self) -> DialogTask<Option<PickedFile>> {
+        let dialog = self.build().pick_file();
+        DialogTask::new(async move {
+            match dialog.await {
+                Some(file) => Some(read(file).await),
+                None => None,
+            }
+        })
+    }
+
+    ///Opens a dialog for picking multiple files, the task returns no files if the dialog was
+    ///closed without picking any
+    #[must_use]
+    pub fn open_many(self) -> DialogTask<Vec<PickedFile>> {
+        let dialog = self.build().pick_files();
+        DialogTask::new(async move {
+            let mut files = Vec::new();
+            for file in dialog.await.unwrap_or_default() {
+                files.push(read(file).await);
+            }
+            files
+        })
+    }
+
+    ///Opens a dialog for choosing where to save a file, the task returns the chosen path or
+    ///`None` if the dialog was closed without choosing one
+    ///
+    ///Not available on the web target
+    #[cfg(not(target_arch = "wasm32"))]
+    #[must_use]
+    pub fn save(self) -> DialogTask<Option<PathBuf>> {
+        let dialog = self.build().save_file();
+        DialogTask::new(async move { dialog.await.map(|f| f.path().to_owned()) })
+    }
+}
+
+async fn read(file: rfd::FileHandle) -> PickedFile {
+    PickedFile {
+        name: file.file_name(),
+        #[cfg(not(target_arch = "wasm32"))]
+        path: Some(file.path().to_owned()),
+        #[cfg(target_arch = "wasm32")]
+        path: None,
+        bytes: file.read().await,
+    }
+}
@@ -0,0 +1,5 @@
+//! Platform specific functionality
+#[cfg(feature = "dialogs")]
+pub mod dialogs;
+#[cfg(target_arch = "wasm32")]
+pub mod web;
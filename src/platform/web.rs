@@ -0,0 +1,88 @@
+//! Browser specific functionality
+//!
+//! Browsers only allow entering fullscreen and capturing the pointer in response to a user
+//! gesture, like a key press or a click. Requests made with this module are therefore deferred
+//! until the next key press or mouse button press, during which they are executed by the
+//! windowing layer, so they can be made from anywhere, e.g. the main loop.
+//!
+//! ```ignore
+//! use lunar_engine::platform::web;
+//!
+//! //Enters fullscreen and captures the pointer on the next click
+//! web::request_fullscreen(true);
+//! web::request_pointer_capture(true);
+//! ```
+use std::sync::Mutex;
+
+use crate::{
+    input::{self, CursorLock, CursorVisibily},
+    WINDOW,
+};
+
+//Pending requests, `Some(true)` to enter, `Some(false)` to leave
+static FULLSCREEN: Mutex<Option<bool>> = Mutex::new(None);
+static POINTER_CAPTURE: Mutex<Option<bool>> = Mutex::new(None);
+
+///Requests entering or leaving fullscreen, executed on the next user input
+///
+///Replaces the previous pending fullscreen request
+pub fn request_fullscreen(fullscreen: bool) {
+    *FULLSCREEN.lock().unwrap() = Some(fullscreen);
+}
+
+///Requests capturing or releasing the pointer, executed on the next user input
+///
+///A captured pointer is locked in place and hidden, the browser releases it when the user presses
+///escape. Replaces the previous pending pointer capture request
+pub fn request_pointer_capture(capture: bool) {
+    *POINTER_CAPTURE.lock().unwrap() = Some(capture);
+}
+
+///Returns `true` if the page is in fullscreen
+#[must_use]
+pub fn is_fullscreen() -> bool {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.fullscreen_element())
+        .is_some()
+}
+
+///Returns `true` if the pointer is captured
+#[must_use]
+pub fn is_pointer_captured() -> bool {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.pointer_lock_element())
+        .is_some()
+}
+
+///Returns `true` if there are requests waiting for user input
+#[must_use]
+pub fn has_pending_requests() -> bool {
+    FULLSCREEN.lock().unwrap().is_some() || POINTER_CAPTURE.lock().unwrap().is_some()
+}
+
+///Executes the pending requests, must only be called while handling user input
+pub(crate) fn process_pending() {
+    let Some(window) = WINDOW.get() else {
+        return;
+    };
+
+    if let Some(fullscreen) = FULLSCREEN.lock().unwrap().take() {
+        log::debug!("Setting fullscreen to {fullscreen}");
+        window.set_fullscreen(fullscreen.then_some(winit::window::Fullscreen::Borderless(None)));
+    }
+
+    if let Some(capture) = POINTER_CAPTURE.lock().unwrap().take() {
+        log::debug!("Setting pointer capture to {capture}");
+        if capture {
+            input::set_cursor_grab_mode(CursorLock::Locked);
+            input::set_cursor_visible(CursorVisibily::Hidden);
+        } else {
+            input::set_cursor_grab_mode(CursorLock::Free);
+            input::set_cursor_visible(CursorVisibily::Visible);
+        }
+        //Applied right away, the next frame is no longer a part of the user gesture
+        input::process_cursor();
+    }
+}
@@ -0,0 +1,277 @@
+use std::{
+    any::Any,
+    ffi::c_void,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::SystemTime,
+};
+
+use crate::{ecs::World, math::Vec3};
+
+use super::{AbiStr, Error, WorldApi, ABI_VERSION};
+
+//Number of libraries copied so far, keeps the names of the copies unique
+static COPIES: AtomicU64 = AtomicU64::new(0);
+
+type Create = unsafe extern "C" fn() -> *mut c_void;
+type Update = unsafe extern "C" fn(*mut c_void, *const WorldApi);
+type Destroy = unsafe extern "C" fn(*mut c_void);
+
+//A loaded copy of the library and the plugin created from it
+struct Instance {
+    plugin: *mut c_void,
+    update: Update,
+    destroy: Destroy,
+    //Must outlive the plugin
+    library: Option<libloading::Library>,
+    //Path of the copy the library was loaded from
+    copy: PathBuf,
+}
+
+impl Instance {
+    fn load(path: &Path) -> Result<Self, Error> {
+        //The library is loaded from a copy, so that the original can be overwritten by the build
+        let copy = std::env::temp_dir().join(format!(
+            "lunar-plugin-{}-{}-{}",
+            std::process::id(),
+            COPIES.fetch_add(1, Ordering::Relaxed),
+            path.file_name().unwrap_or_default().to_string_lossy()
+        ));
+        std::fs::copy(path, &copy)?;
+
+        let instance = unsafe { Self::open(copy.clone()) };
+        if instance.is_err() {
+            _ = std::fs::remove_file(&copy);
+        }
+        instance
+    }
+
+    unsafe fn open(copy: PathBuf) -> Result<Self, Error> {
+        let library = libloading::Library::new(&copy)?;
+
+        let version = library.get::<extern "C" fn() -> u32>(b"lunar_plugin_abi_version")?();
+        if version != ABI_VERSION {
+            return Err(Error::IncompatibleVersion {
+                expected: ABI_VERSION,
+                found: version,
+            });
+        }
+        let create = *library.get::<Create>(b"lunar_plugin_create")?;
+        let update = *library.get::<Update>(b"lunar_plugin_update")?;
+        let destroy = *library.get::<Destroy>(b"lunar_plugin_destroy")?;
+
+        Ok(Self {
+            plugin: create(),
+            update,
+            destroy,
+            library: Some(library),
+            copy,
+        })
+    }
+}
+
+impl Drop for Instance {
+    fn drop(&mut self) {
+        unsafe { (self.destroy)(self.plugin) };
+        drop(self.library.take());
+        _ = std::fs::remove_file(&self.copy);
+    }
+}
+
+///Plugin loaded from a dynamic library, see [`super`]
+pub struct DynamicPlugin {
+    ///Whether or not the library is reloaded when it changes
+    pub hot_reload: bool,
+    path: PathBuf,
+    //Modification time of the loaded library
+    modified: Option<SystemTime>,
+    instance: Instance,
+    reloads: u32,
+}
+
+impl DynamicPlugin {
+    ///Loads the plugin from the library at `path` and creates it
+    ///
+    ///# Errors
+    ///Returns an error if the library can't be read or loaded, if it doesn't export the plugin
+    ///functions or if it was built for a different ABI version
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let modified = modified(&path);
+        let instance = Instance::load(&path)?;
+        log::info!("Loaded plugin {}", path.display());
+
+        Ok(Self {
+            hot_reload: true,
+            path,
+            modified,
+            instance,
+            reloads: 0,
+        })
+    }
+
+    ///Reloads the library and recreates the plugin, the old plugin is kept if the library fails to
+    ///load
+    ///
+    ///# Errors
+    ///Returns an error if the new library can't be loaded, see [`DynamicPlugin::load`]
+    pub fn reload(&mut self) -> Result<(), Error> {
+        let modified = modified(&self.path);
+        let instance = Instance::load(&self.path)?;
+        self.instance = instance;
+        self.modified = modified;
+        self.reloads += 1;
+        log::info!("Reloaded plugin {}", self.path.display());
+        Ok(())
+    }
+
+    ///Returns `true` if the library was modified since it was loaded
+    #[must_use]
+    pub fn is_outdated(&self) -> bool {
+        let modified = modified(&self.path);
+        modified.is_some() && modified != self.modified
+    }
+
+    ///Reloads the library if it was rebuilt and [`DynamicPlugin::hot_reload`] is enabled, then
+    ///updates the plugin
+    ///
+    ///A library that fails to reload is logged and the old plugin keeps running
+    pub fn update(&mut self, world: &World) {
+        if self.hot_reload && self.is_outdated() {
+            if let Err(e) = self.reload() {
+                log::error!("{e}");
+                //Not retried until the library changes again
+                self.modified = modified(&self.path);
+            }
+        }
+
+        let api = world_api(world);
+        unsafe { (self.instance.update)(self.instance.plugin, std::ptr::addr_of!(api)) };
+    }
+
+    ///Returns the path of the library
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    ///Returns the number of times the library was reloaded
+    #[must_use]
+    pub const fn reloads(&self) -> u32 {
+        self.reloads
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+///Returns the function table giving access to the `world`, valid as long as the world is
+#[must_use]
+pub(crate) fn world_api(world: &World) -> WorldApi {
+    WorldApi {
+        world: std::ptr::from_ref(world).cast(),
+        entity_ids,
+        get_f32: get::<f32>,
+        set_f32,
+        get_bool: get::<bool>,
+        set_bool,
+        get_vec3: get::<Vec3>,
+        set_vec3,
+        delta_time,
+        log,
+    }
+}
+
+//Calls `f` with the field of the component of the entity, returns `false` if it doesn't exist or
+//if it's of a different type
+unsafe fn with_field<T: Any>(
+    world: *const c_void,
+    entity: u64,
+    component: AbiStr,
+    field: AbiStr,
+    f: impl FnOnce(&mut T),
+) -> bool {
+    let world = &*world.cast::<World>();
+    let (component, field) = (component.as_str(), field.as_str());
+    let Some(entity) = world.get_entity_by_id(entity) else {
+        return false;
+    };
+
+    let mut f = Some(f);
+    entity.borrow().for_each_reflect_mut(|r| {
+        if r.type_name() != component {
+            return;
+        }
+        if let Some(value) = r.field_mut(field).and_then(<dyn Any>::downcast_mut::<T>) {
+            if let Some(f) = f.take() {
+                f(value);
+            }
+        }
+    });
+    f.is_none()
+}
+
+unsafe extern "C" fn entity_ids(world: *const c_void, out: *mut u64, capacity: usize) -> usize {
+    let ids = (*world.cast::<World>()).get_entity_ids();
+    let written = ids.len().min(capacity);
+    if written > 0 {
+        std::ptr::copy_nonoverlapping(ids.as_ptr(), out, written);
+    }
+    ids.len()
+}
+
+unsafe extern "C" fn get<T: Any + Copy>(
+    world: *const c_void,
+    entity: u64,
+    component: AbiStr,
+    field: AbiStr,
+    out: *mut T,
+) -> bool {
+    with_field(world, entity, component, field, |v: &mut T| *out = *v)
+}
+
+unsafe extern "C" fn set_f32(
+    world: *const c_void,
+    entity: u64,
+    component: AbiStr,
+    field: AbiStr,
+    value: f32,
+) -> bool {
+    with_field(world, entity, component, field, |v| *v = value)
+}
+
+unsafe extern "C" fn set_bool(
+    world: *const c_void,
+    entity: u64,
+    component: AbiStr,
+    field: AbiStr,
+    value: bool,
+) -> bool {
+    with_field(world, entity, component, field, |v| *v = value)
+}
+
+unsafe extern "C" fn set_vec3(
+    world: *const c_void,
+    entity: u64,
+    component: AbiStr,
+    field: AbiStr,
+    value: *const Vec3,
+) -> bool {
+    with_field(world, entity, component, field, |v| *v = *value)
+}
+
+extern "C" fn delta_time() -> f32 {
+    crate::time::delta_time()
+}
+
+unsafe extern "C" fn log(level: u32, message: AbiStr) {
+    let level = match level {
+        1 => log::Level::Error,
+        2 => log::Level::Warn,
+        3 => log::Level::Info,
+        4 => log::Level::Debug,
+        _ => log::Level::Trace,
+    };
+    log::log!(target: "plugin", level, "{}", message.as_str());
+}
@@ -0,0 +1,333 @@
+//! Gameplay plugins loaded from dynamic libraries, requires the `plugins` feature
+//!
+//! A plugin is a `cdylib` crate implementing [`Plugin`] and exporting it with
+//! [`declare_plugin!`]. The application loads it with [`DynamicPlugin`], which reloads the
+//! library whenever it's rebuilt, so the gameplay code can be changed without restarting the
+//! application and reloading the assets.
+//!
+//! The engine and the plugin don't share any rust types, since the layout of those may differ
+//! between compilations. Instead the plugin accesses the world through [`WorldApi`], a table of
+//! `extern "C"` functions, wrapped in [`PluginWorld`]. Components are accessed by their fields,
+//! using [`Reflect`](crate::ecs::reflect::Reflect), so only the components implementing it are
+//! visible to plugins.
+//!
+//! The state of a plugin is lost when it's reloaded, state that needs to survive reloads should
+//! be stored in the components of the world, for example in
+//! [`Metadata`](crate::components::Metadata).
+//!
+//! Plugins are not available on the web target.
+//!
+//! # Examples
+//! The plugin, built with `crate-type = ["cdylib"]`
+//!```ignore
+//!use lunar_engine::plugins::{Plugin, PluginWorld};
+//!
+//!#[derive(Default)]
+//!struct Spin;
+//!
+//!impl Plugin for Spin {
+//!    fn update(&mut self, world: &mut PluginWorld) {
+//!        for entity in world.entity_ids() {
+//!            if let Some(mut rotation) = world.get_vec3(entity, "Transform", "rotation") {
+//!                rotation.y += 90.0 * world.delta_time();
+//!                world.set_vec3(entity, "Transform", "rotation", rotation);
+//!            }
+//!        }
+//!    }
+//!}
+//!
+//!lunar_engine::declare_plugin!(Spin);
+//!```
+//! The application
+//!```no_run
+//!# use lunar_engine::ecs::World;
+//!use lunar_engine::plugins::DynamicPlugin;
+//!
+//!# let world = World::new();
+//!let mut plugin = DynamicPlugin::load("target/debug/libspin.so").unwrap();
+//!
+//!//Every frame
+//!plugin.update(&world);
+//!```
+use std::{ffi::c_void, marker::PhantomData};
+
+use crate::math::Vec3;
+
+mod host;
+#[cfg(test)]
+mod tests;
+
+pub use host::DynamicPlugin;
+
+///Version of the plugin ABI, plugins built against a different version are not loaded
+pub const ABI_VERSION: u32 = 1;
+
+///Errors of loading a plugin
+#[derive(Debug)]
+pub enum Error {
+    ///The library could not be read or copied
+    Io(std::io::Error),
+    ///The library could not be loaded, or does not export the plugin functions
+    Library(libloading::Error),
+    ///The plugin was built against a different version of the ABI
+    IncompatibleVersion {
+        ///Version of the engine
+        expected: u32,
+        ///Version of the plugin
+        found: u32,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "Failed to read the plugin: {e}"),
+            Self::Library(e) => write!(f, "Failed to load the plugin: {e}"),
+            Self::IncompatibleVersion { expected, found } => write!(
+                f,
+                "The plugin was built for ABI version {found}, expected {expected}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<libloading::Error> for Error {
+    fn from(value: libloading::Error) -> Self {
+        Self::Library(value)
+    }
+}
+
+///String passed across the plugin boundary
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct AbiStr {
+    ///Pointer to the utf-8 bytes of the string
+    pub ptr: *const u8,
+    ///Length of the string in bytes
+    pub len: usize,
+}
+
+impl AbiStr {
+    ///Creates a string borrowing `value`
+    #[must_use]
+    pub const fn new(value: &str) -> Self {
+        Self {
+            ptr: value.as_ptr(),
+            len: value.len(),
+        }
+    }
+
+    ///Returns the string
+    ///
+    ///# Safety
+    ///The string must have been created with [`AbiStr::new`] and the borrowed string must still
+    ///be alive
+    #[must_use]
+    pub const unsafe fn as_str<'a>(self) -> &'a str {
+        std::str::from_utf8_unchecked(std::slice::from_raw_parts(self.ptr, self.len))
+    }
+}
+
+///Table of functions the plugins access the world through, see [`PluginWorld`] for their
+///descriptions
+///
+///Fields are addressed by the name of the component, as returned by
+///[`Reflect::type_name`](crate::ecs::reflect::Reflect::type_name), and the name of the field. The
+///getters and the setters return `false` if the entity, the component or the field don't exist,
+///or if the field is of a different type.
+#[repr(C)]
+#[allow(missing_docs)]
+pub struct WorldApi {
+    pub world: *const c_void,
+    pub entity_ids: unsafe extern "C" fn(*const c_void, *mut u64, usize) -> usize,
+    pub get_f32: unsafe extern "C" fn(*const c_void, u64, AbiStr, AbiStr, *mut f32) -> bool,
+    pub set_f32: unsafe extern "C" fn(*const c_void, u64, AbiStr, AbiStr, f32) -> bool,
+    pub get_bool: unsafe extern "C" fn(*const c_void, u64, AbiStr, AbiStr, *mut bool) -> bool,
+    pub set_bool: unsafe extern "C" fn(*const c_void, u64, AbiStr, AbiStr, bool) -> bool,
+    pub get_vec3: unsafe extern "C" fn(*const c_void, u64, AbiStr, AbiStr, *mut Vec3) -> bool,
+    pub set_vec3: unsafe extern "C" fn(*const c_void, u64, AbiStr, AbiStr, *const Vec3) -> bool,
+    pub delta_time: extern "C" fn() -> f32,
+    pub log: unsafe extern "C" fn(u32, AbiStr),
+}
+
+///Access to the world from a plugin
+pub struct PluginWorld<'a> {
+    api: &'a WorldApi,
+    //The world is not thread safe
+    _marker: PhantomData<*const ()>,
+}
+
+impl<'a> PluginWorld<'a> {
+    ///Wraps the function table passed to the plugin
+    #[must_use]
+    pub const fn new(api: &'a WorldApi) -> Self {
+        Self {
+            api,
+            _marker: PhantomData,
+        }
+    }
+
+    ///Returns the ids of all the entities of the world
+    #[must_use]
+    pub fn entity_ids(&self) -> Vec<u64> {
+        let count = unsafe { (self.api.entity_ids)(self.api.world, std::ptr::null_mut(), 0) };
+        let mut ids = vec![0; count];
+        let written = unsafe { (self.api.entity_ids)(self.api.world, ids.as_mut_ptr(), count) };
+        ids.truncate(written);
+        ids
+    }
+
+    ///Returns the value of a `f32` field of a component of the entity
+    #[must_use]
+    pub fn get_f32(&self, entity: u64, component: &str, field: &str) -> Option<f32> {
+        let mut value = 0.0;
+        unsafe {
+            (self.api.get_f32)(
+                self.api.world,
+                entity,
+                AbiStr::new(component),
+                AbiStr::new(field),
+                std::ptr::addr_of_mut!(value),
+            )
+        }
+        .then_some(value)
+    }
+
+    ///Sets the value of a `f32` field of a component of the entity, returns `false` if the field
+    ///doesn't exist
+    pub fn set_f32(&mut self, entity: u64, component: &str, field: &str, value: f32) -> bool {
+        unsafe {
+            (self.api.set_f32)(
+                self.api.world,
+                entity,
+                AbiStr::new(component),
+                AbiStr::new(field),
+                value,
+            )
+        }
+    }
+
+    ///Returns the value of a `bool` field of a component of the entity
+    #[must_use]
+    pub fn get_bool(&self, entity: u64, component: &str, field: &str) -> Option<bool> {
+        let mut value = false;
+        unsafe {
+            (self.api.get_bool)(
+                self.api.world,
+                entity,
+                AbiStr::new(component),
+                AbiStr::new(field),
+                std::ptr::addr_of_mut!(value),
+            )
+        }
+        .then_some(value)
+    }
+
+    ///Sets the value of a `bool` field of a component of the entity, returns `false` if the field
+    ///doesn't exist
+    pub fn set_bool(&mut self, entity: u64, component: &str, field: &str, value: bool) -> bool {
+        unsafe {
+            (self.api.set_bool)(
+                self.api.world,
+                entity,
+                AbiStr::new(component),
+                AbiStr::new(field),
+                value,
+            )
+        }
+    }
+
+    ///Returns the value of a [`Vec3`] field of a component of the entity
+    #[must_use]
+    pub fn get_vec3(&self, entity: u64, component: &str, field: &str) -> Option<Vec3> {
+        let mut value = Vec3::default();
+        unsafe {
+            (self.api.get_vec3)(
+                self.api.world,
+                entity,
+                AbiStr::new(component),
+                AbiStr::new(field),
+                std::ptr::addr_of_mut!(value),
+            )
+        }
+        .then_some(value)
+    }
+
+    ///Sets the value of a [`Vec3`] field of a component of the entity, returns `false` if the
+    ///field doesn't exist
+    pub fn set_vec3(&mut self, entity: u64, component: &str, field: &str, value: Vec3) -> bool {
+        unsafe {
+            (self.api.set_vec3)(
+                self.api.world,
+                entity,
+                AbiStr::new(component),
+                AbiStr::new(field),
+                std::ptr::addr_of!(value),
+            )
+        }
+    }
+
+    ///Returns time between frames in seconds
+    #[must_use]
+    pub fn delta_time(&self) -> f32 {
+        (self.api.delta_time)()
+    }
+
+    ///Logs the message with the logger of the application
+    pub fn log(&self, level: log::Level, message: &str) {
+        unsafe { (self.api.log)(level as u32, AbiStr::new(message)) }
+    }
+}
+
+///Gameplay code loaded from a dynamic library, exported with [`declare_plugin!`]
+///
+///The plugin is created with [`Default`] when the library is loaded and dropped when it's
+///unloaded or reloaded
+pub trait Plugin: Default {
+    ///Called every frame
+    fn update(&mut self, world: &mut PluginWorld);
+}
+
+///Exports the functions of a type implementing [`Plugin`] from the library
+///
+///Panics must not unwind out of the plugin, the application is aborted if they do
+#[macro_export]
+macro_rules! declare_plugin {
+    ($plugin:ty) => {
+        #[no_mangle]
+        pub extern "C" fn lunar_plugin_abi_version() -> u32 {
+            $crate::plugins::ABI_VERSION
+        }
+
+        #[no_mangle]
+        pub extern "C" fn lunar_plugin_create() -> *mut ::std::ffi::c_void {
+            ::std::boxed::Box::into_raw(::std::boxed::Box::new(
+                <$plugin as ::std::default::Default>::default(),
+            ))
+            .cast()
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn lunar_plugin_update(
+            plugin: *mut ::std::ffi::c_void,
+            api: *const $crate::plugins::WorldApi,
+        ) {
+            let plugin = &mut *plugin.cast::<$plugin>();
+            let mut world = $crate::plugins::PluginWorld::new(&*api);
+            $crate::plugins::Plugin::update(plugin, &mut world);
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn lunar_plugin_destroy(plugin: *mut ::std::ffi::c_void) {
+            ::std::mem::drop(::std::boxed::Box::from_raw(plugin.cast::<$plugin>()));
+        }
+    };
+}
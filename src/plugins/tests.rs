@@ -0,0 +1,69 @@
+use crate::{
+    components::transform::Transform,
+    ecs::{EntityBuilder, World},
+    math::Vec3,
+};
+
+use super::{host::world_api, DynamicPlugin, Error, PluginWorld};
+
+#[test]
+fn plugin_world_test() {
+    let mut world = World::new();
+    world.add_entity(
+        EntityBuilder::new()
+            .create_component(|| Transform {
+                position: Vec3::new(1.0, 2.0, 3.0),
+                ..Default::default()
+            })
+            .create()
+            .unwrap(),
+    );
+    world.add_entity(EntityBuilder::new().create().unwrap());
+
+    let api = world_api(&world);
+    let mut plugin_world = PluginWorld::new(&api);
+
+    let ids = plugin_world.entity_ids();
+    assert_eq!(ids, world.get_entity_ids());
+    let (a, b) = (ids[0], ids[1]);
+
+    assert_eq!(
+        plugin_world.get_vec3(a, "Transform", "position"),
+        Some(Vec3::new(1.0, 2.0, 3.0))
+    );
+    assert!(plugin_world.set_vec3(a, "Transform", "scale", Vec3::new(2.0, 2.0, 2.0)));
+    assert_eq!(
+        world
+            .get_entity_by_id(a)
+            .unwrap()
+            .borrow()
+            .get_component::<Transform>()
+            .unwrap()
+            .borrow()
+            .scale,
+        Vec3::new(2.0, 2.0, 2.0)
+    );
+
+    //Wrong type, component, field or entity
+    assert_eq!(plugin_world.get_f32(a, "Transform", "position"), None);
+    assert!(!plugin_world.set_f32(a, "Transform", "position", 1.0));
+    assert_eq!(plugin_world.get_vec3(b, "Transform", "position"), None);
+    assert_eq!(plugin_world.get_vec3(a, "Transform", "parent"), None);
+    assert_eq!(
+        plugin_world.get_bool(u64::MAX, "Transform", "position"),
+        None
+    );
+}
+
+#[test]
+fn plugin_load_test() {
+    assert!(matches!(
+        DynamicPlugin::load("assets/test-data/missing-plugin.so"),
+        Err(Error::Io(_))
+    ));
+    //Not a library
+    assert!(matches!(
+        DynamicPlugin::load("assets/test-data/cube.obj"),
+        Err(Error::Library(_))
+    ));
+}
@@ -0,0 +1,72 @@
+//! Background of the rendered image
+//!
+//! The parts of the image not occupied by a mesh are cleared to the background of the camera,
+//! see [`Camera::background`]. Cameras inheriting the background use the global clear color set
+//! with [`set_clear_color`], or the clear color of the extension rendering them if there's none,
+//! so the background can be changed at runtime without recreating the extensions.
+//!
+//!```no_run
+//! use lunar_engine::{rendering, structures::Color};
+//!
+//! //Every camera inheriting the background, for example in a menu
+//! rendering::set_clear_color(Some(Color::rgb(0.1, 0.1, 0.2)));
+//! //Back to the clear colors of the extensions
+//! rendering::set_clear_color(None);
+//!```
+//!
+//! [`Camera::background`]: crate::components::camera::Camera::background
+use std::sync::RwLock;
+
+use crate::structures::Color;
+
+static CLEAR_COLOR: RwLock<Option<Color>> = RwLock::new(None);
+
+///What the parts of the rectangle of a camera not occupied by a mesh are filled with
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Background {
+    ///The global clear color, or the clear color of the extension if it's not set
+    #[default]
+    Inherit,
+    ///A solid color
+    Color(Color),
+    ///The sky of the [`Skybox`](super::extensions::Skybox) extension, which only draws behind
+    ///the main camera if it has this background or inherits it. Other cameras use the inherited
+    ///color
+    Skybox,
+    ///Fully transparent, for compositing the image with something else
+    Transparent,
+}
+
+impl Background {
+    ///Returns the color the background is cleared with, `fallback` is the clear color of the
+    ///extension
+    ///
+    ///The color is premultiplied if the window is composited with premultiplied alpha, see
+    ///[`super::surface::is_premultiplied`]
+    #[must_use]
+    pub fn clear_color(self, fallback: Color) -> Color {
+        super::surface::output_color(match self {
+            Self::Inherit | Self::Skybox => clear_color().unwrap_or(fallback),
+            Self::Color(color) => color,
+            Self::Transparent => Color::new(0.0, 0.0, 0.0, 0.0),
+        })
+    }
+
+    ///Returns `true` if the sky is drawn behind a main camera with this background
+    #[must_use]
+    pub const fn shows_skybox(self) -> bool {
+        matches!(self, Self::Inherit | Self::Skybox)
+    }
+}
+
+///Sets the clear color used by all the cameras inheriting their background, `None` uses the clear
+///colors of the extensions
+pub fn set_clear_color(color: Option<Color>) {
+    *CLEAR_COLOR.write().unwrap() = color;
+}
+
+///Returns the global clear color
+#[must_use]
+pub fn clear_color() -> Option<Color> {
+    *CLEAR_COLOR.read().unwrap()
+}
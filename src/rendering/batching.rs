@@ -0,0 +1,148 @@
+//! Static batching of the level geometry
+//!
+//! [`bake_static_batch`] merges the static meshes sharing a material into a single mesh asset,
+//! with the vertices transformed into world space. Hundreds of small static meshes are then drawn
+//! with a few draw calls, at the cost of the memory of the merged vertices and of culling the
+//! merged mesh as a whole.
+//!
+//!```no_run
+//! # use lunar_engine::{asset_managment::AssetStore, ecs::World};
+//! # let mut world = World::new();
+//! # let mut assets = AssetStore::new();
+//! let batch = lunar_engine::rendering::bake_static_batch(&mut world, &mut assets).unwrap();
+//! log::info!("Merged {} meshes into {}", batch.merged, batch.meshes.len());
+//!```
+use std::collections::{btree_map::Entry, BTreeMap};
+
+use crate::{
+    asset_managment::{self, AssetStore, UUID},
+    assets::Mesh,
+    components::{mesh::Mesh as MeshComponent, transform::Transform},
+    ecs::{self, Component, ComponentReference, EntityBuilder, World},
+    math::{Mat3x3, Vector},
+    structures, validation,
+};
+
+///Meshes created by [`bake_static_batch`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StaticBatch {
+    ///Ids of the merged mesh assets
+    pub meshes: Vec<UUID>,
+    ///Ids of the entities drawing the merged meshes
+    pub entities: Vec<ecs::UUID>,
+    ///Number of mesh components that were merged and hidden
+    pub merged: usize,
+}
+
+//Meshes can only be merged if everything but their transformation is the same
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct BatchKey {
+    material: UUID,
+    layers: u32,
+    cast_shadows: bool,
+    receive_shadows: bool,
+}
+
+///Merges the visible static meshes sharing a material into one mesh per material
+///
+///Each merged mesh is registered in the `assets` and drawn by a new static entity in the `world`,
+///the merged mesh components are hidden. Skinned meshes and materials used by a single static
+///mesh are left as they are. The vertices are transformed with the transformations the meshes
+///have when the batch is baked, later changes to them have no effect on the merged meshes
+///
+///# Errors
+///Returns an error if the data of a mesh could not be loaded
+pub fn bake_static_batch(
+    world: &mut World,
+    assets: &mut AssetStore,
+) -> Result<StaticBatch, asset_managment::Error> {
+    let mut groups = BTreeMap::<BatchKey, Vec<(UUID, ComponentReference<MeshComponent>)>>::new();
+
+    for component in world
+        .get_all_components::<MeshComponent>()
+        .unwrap_or_default()
+    {
+        let m = component.borrow();
+        if !m.get_static() || !m.get_visible() {
+            continue;
+        }
+        let Ok((mesh, material)) = validation::renderable(&m, assets) else {
+            continue;
+        };
+        if assets.get_by_id::<Mesh>(mesh)?.borrow().is_skinned() {
+            continue;
+        }
+
+        let key = BatchKey {
+            material,
+            layers: m.get_layers(),
+            cast_shadows: m.get_cast_shadows(),
+            receive_shadows: m.get_receive_shadows(),
+        };
+        drop(m);
+        groups.entry(key).or_default().push((mesh, component));
+    }
+
+    let mut batch = StaticBatch::default();
+    //Meshes used by multiple batches are only loaded once
+    let mut loaded = BTreeMap::<UUID, structures::Mesh>::new();
+
+    for (key, meshes) in groups {
+        if meshes.len() < 2 {
+            continue;
+        }
+
+        let mut merged = structures::Mesh::default();
+        for (mesh, component) in &meshes {
+            let data = match loaded.entry(*mesh) {
+                Entry::Occupied(e) => e.into_mut(),
+                Entry::Vacant(e) => e.insert(
+                    assets
+                        .get_by_id::<Mesh>(*mesh)?
+                        .borrow()
+                        .load_data()
+                        .map_err(|e| asset_managment::Error::InitializationError(e))?,
+                ),
+            };
+
+            let mut component = component.borrow_mut();
+            let matrix = component.get_transform().borrow().matrix();
+            let normal = Mat3x3::normal_matrix(&matrix);
+
+            let offset = merged.vertices.len() as u32;
+            merged
+                .vertices
+                .extend(data.vertices.iter().map(|v| structures::Vertex {
+                    coords: matrix * v.coords,
+                    normal: (normal * v.normal).normalized(),
+                    ..*v
+                }));
+            merged
+                .indices
+                .extend(data.indices.iter().map(|i| i + offset));
+
+            component.set_visible(false);
+        }
+
+        let mesh = assets.register(Mesh::new_from_data(merged));
+        let entity = EntityBuilder::new()
+            .create_component(Transform::mew)
+            .create_component(|| {
+                let mut m = MeshComponent::new(mesh, key.material);
+                m.set_static(true);
+                m.set_layers(key.layers);
+                m.set_cast_shadows(key.cast_shadows);
+                m.set_receive_shadows(key.receive_shadows);
+                m
+            })
+            .create()
+            .unwrap();
+        batch.entities.push(entity.get_id());
+        world.add_entity(entity);
+
+        batch.meshes.push(mesh);
+        batch.merged += meshes.len();
+    }
+
+    Ok(batch)
+}
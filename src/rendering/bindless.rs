@@ -0,0 +1,227 @@
+//! Texture array shared by the materials, so that meshes with different textures can be drawn
+//! without switching bind groups
+//!
+//! Used where the device supports binding arrays of textures indexed with non uniform values,
+//! check with [`supported`]. The materials supporting it, such as
+//! [`TextureUnlit`](crate::assets::materials::TextureUnlit), register their textures in the
+//! shared array and the meshes using them pass the index of the texture with their instance
+//! data. Materials of the same type then share one pipeline and one bind group and their meshes
+//! are drawn together. On other devices every material binds its own texture.
+//!
+//! All textures in the array are sampled with the same linear repeating sampler, so the
+//! samplers of the textures are ignored.
+use std::{
+    cell::RefCell,
+    num::NonZeroU32,
+    sync::{Arc, OnceLock},
+};
+
+use crate::{
+    asset_managment::{AssetStore, UUID},
+    assets::Texture,
+    DEVICE,
+};
+
+///Number of textures in the array, must match the size of the arrays in the shaders
+pub const MAX_TEXTURES: u32 = 256;
+
+///Features needed for the texture array
+pub(crate) const FEATURES: wgpu::Features = wgpu::Features::TEXTURE_BINDING_ARRAY
+    .union(wgpu::Features::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING);
+
+static SUPPORTED: OnceLock<bool> = OnceLock::new();
+
+thread_local! {
+    static TABLE: RefCell<TextureTable> = RefCell::new(TextureTable::default());
+}
+
+#[derive(Default)]
+struct TextureTable {
+    //Textures in the order of their indices
+    textures: Vec<UUID>,
+    layout: Option<Arc<wgpu::BindGroupLayout>>,
+    sampler: Option<wgpu::Sampler>,
+    bind_group: Option<Arc<wgpu::BindGroup>>,
+    //Number of textures the bind group was created with
+    bound: usize,
+}
+
+///Returns `true` if the adapter supports the shared texture array
+#[must_use]
+pub fn adapter_supported(adapter: &wgpu::Adapter) -> bool {
+    adapter.features().contains(FEATURES)
+        && adapter.limits().max_sampled_textures_per_shader_stage >= MAX_TEXTURES
+}
+
+///Returns `true` if the shared texture array is used by the materials
+#[must_use]
+pub fn supported() -> bool {
+    SUPPORTED.get().copied().unwrap_or_default()
+}
+
+pub(crate) fn set_supported(value: bool) {
+    _ = SUPPORTED.set(value);
+}
+
+///Returns the index of the texture in the shared array, adding it if it's not in the array yet
+///
+///Returns `None` if the array is full
+pub(crate) fn texture_index(texture: UUID) -> Option<u32> {
+    TABLE.with_borrow_mut(|t| {
+        if let Some(i) = t.textures.iter().position(|i| *i == texture) {
+            return Some(i as u32);
+        }
+        if t.textures.len() >= MAX_TEXTURES as usize {
+            return None;
+        }
+        t.textures.push(texture);
+        Some(t.textures.len() as u32 - 1)
+    })
+}
+
+///Returns the layout of the bind group containing the array and the sampler
+pub(crate) fn bind_group_layout() -> Arc<wgpu::BindGroupLayout> {
+    TABLE.with_borrow_mut(|t| {
+        t.layout
+            .get_or_insert_with(|| {
+                let device = DEVICE.get().unwrap();
+                Arc::new(
+                    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                        label: Some("Texture array bind group layout"),
+                        entries: &[
+                            wgpu::BindGroupLayoutEntry {
+                                binding: 0,
+                                visibility: wgpu::ShaderStages::FRAGMENT,
+                                ty: wgpu::BindingType::Texture {
+                                    sample_type: wgpu::TextureSampleType::Float {
+                                        filterable: true,
+                                    },
+                                    view_dimension: wgpu::TextureViewDimension::D2,
+                                    multisampled: false,
+                                },
+                                count: NonZeroU32::new(MAX_TEXTURES),
+                            },
+                            wgpu::BindGroupLayoutEntry {
+                                binding: 1,
+                                visibility: wgpu::ShaderStages::FRAGMENT,
+                                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                                count: None,
+                            },
+                        ],
+                    }),
+                )
+            })
+            .clone()
+    })
+}
+
+///Returns the bind group of the array, recreating it if textures were added since it was last
+///created
+///
+///Must be called before beginning the render pass the bind group is used in
+pub(crate) fn bind_group(assets: &AssetStore) -> Arc<wgpu::BindGroup> {
+    let layout = bind_group_layout();
+    TABLE.with_borrow_mut(|t| {
+        if let Some(bind_group) = &t.bind_group {
+            if t.bound == t.textures.len() {
+                return bind_group.clone();
+            }
+        }
+        let device = DEVICE.get().unwrap();
+
+        let views = t
+            .textures
+            .iter()
+            .map(|id| {
+                let texture = assets.get_by_id::<Texture>(*id).ok();
+                let texture = texture.as_ref().map(|t| t.borrow());
+                let view = texture.as_ref().and_then(|t| t.texture.as_ref()).map(|t| {
+                    t.create_view(&wgpu::TextureViewDescriptor {
+                        label: Some("Texture array view"),
+                        format: Some(wgpu::TextureFormat::Rgba8Unorm),
+                        mip_level_count: Some(1),
+                        ..Default::default()
+                    })
+                });
+                if view.is_none() {
+                    log::error!("Texture {id} of the texture array does not exist");
+                }
+                view
+            })
+            .collect::<Vec<_>>();
+
+        //Every element of the array must be bound, the unused ones are blank
+        let blank = blank_view();
+        let array = (0..MAX_TEXTURES as usize)
+            .map(|i| views.get(i).and_then(Option::as_ref).unwrap_or(&blank))
+            .collect::<Vec<_>>();
+
+        let sampler = t.sampler.get_or_insert_with(|| {
+            device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("Texture array sampler"),
+                address_mode_u: wgpu::AddressMode::Repeat,
+                address_mode_v: wgpu::AddressMode::Repeat,
+                address_mode_w: wgpu::AddressMode::Repeat,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            })
+        });
+
+        let bind_group = Arc::new(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Texture array bind group"),
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureViewArray(&array),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        }));
+        t.bind_group = Some(bind_group.clone());
+        t.bound = t.textures.len();
+        bind_group
+    })
+}
+
+///Returns the bind group of the array, `None` if it was not created yet
+pub(crate) fn current() -> Option<Arc<wgpu::BindGroup>> {
+    TABLE.with_borrow(|t| t.bind_group.clone())
+}
+
+///Returns `true` if the bind group contains all the textures of the array
+pub(crate) fn is_current() -> bool {
+    TABLE.with_borrow(|t| t.bind_group.is_some() && t.bound == t.textures.len())
+}
+
+///Releases the bind group of the array
+pub(crate) fn release() {
+    TABLE.set(TextureTable::default());
+}
+
+//1x1 texture bound when the array is empty
+fn blank_view() -> wgpu::TextureView {
+    DEVICE
+        .get()
+        .unwrap()
+        .create_texture(&wgpu::TextureDescriptor {
+            label: Some("Texture array blank"),
+            size: wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        })
+        .create_view(&wgpu::TextureViewDescriptor::default())
+}
@@ -0,0 +1,133 @@
+//! Capabilities of the gpu the engine is running on
+//!
+//! Can be used to only show the settings supported by the device, for example
+//!```no_run
+//! let capabilities = lunar_engine::rendering::capabilities().unwrap();
+//! for samples in &capabilities.msaa_levels {
+//!     println!("MSAA x{samples}");
+//! }
+//!```
+use std::sync::OnceLock;
+
+static CAPABILITIES: OnceLock<Capabilities> = OnceLock::new();
+
+///Features and limits of the gpu
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    ///Name of the gpu
+    pub adapter_name: String,
+    ///Graphics api used for rendering
+    pub backend: wgpu::Backend,
+    ///Supported multisampling sample counts of the frame buffer format, always contains 1
+    pub msaa_levels: Vec<u32>,
+    ///Maximum width and height of a 2D texture
+    pub max_texture_size: u32,
+    ///Whether or not compute shaders are supported
+    pub compute: bool,
+    ///Whether or not 16 bit float textures can be rendered to, needed for hdr rendering
+    pub float16_render_target: bool,
+    ///Whether or not 32 bit float textures can be rendered to
+    pub float32_render_target: bool,
+    ///Whether or not 32 bit float textures can be sampled with a filtering sampler
+    pub float32_filterable: bool,
+    ///Whether or not meshes can be rendered as wireframes by
+    ///[`DebugView`](super::extensions::DebugView)
+    pub wireframe: bool,
+    ///Whether or not the gpu time of the extensions can be measured, see [`super::profiling`]
+    pub timestamps: bool,
+    ///Formats the depth attachment can use, with the usages allowed for them, see
+    ///[`super::depth::set_config`]
+    pub depth_formats: Vec<(wgpu::TextureFormat, wgpu::TextureUsages)>,
+    ///Formats the frame buffer can use, empty when rendering without a window, see
+    ///[`super::surface::set_format`]
+    pub surface_formats: Vec<wgpu::TextureFormat>,
+    ///Modes of compositing the frame buffer with the desktop, empty when rendering without a
+    ///window, see [`super::surface::set_alpha_mode`]
+    pub alpha_modes: Vec<wgpu::CompositeAlphaMode>,
+}
+
+impl Capabilities {
+    fn new(
+        adapter: &wgpu::Adapter,
+        limits: &wgpu::Limits,
+        format: wgpu::TextureFormat,
+        surface: &wgpu::SurfaceCapabilities,
+    ) -> Self {
+        let renderable = |format| {
+            adapter
+                .get_texture_format_features(format)
+                .allowed_usages
+                .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+        };
+
+        Self {
+            adapter_name: adapter.get_info().name,
+            backend: adapter.get_info().backend,
+            msaa_levels: adapter
+                .get_texture_format_features(format)
+                .flags
+                .supported_sample_counts(),
+            max_texture_size: limits.max_texture_dimension_2d,
+            compute: adapter
+                .get_downlevel_capabilities()
+                .flags
+                .contains(wgpu::DownlevelFlags::COMPUTE_SHADERS),
+            float16_render_target: renderable(wgpu::TextureFormat::Rgba16Float),
+            float32_render_target: renderable(wgpu::TextureFormat::Rgba32Float),
+            float32_filterable: adapter
+                .features()
+                .contains(wgpu::Features::FLOAT32_FILTERABLE),
+            wireframe: adapter
+                .features()
+                .contains(wgpu::Features::POLYGON_MODE_LINE),
+            timestamps: adapter.features().contains(super::profiling::FEATURES),
+            depth_formats: super::depth::FORMATS
+                .iter()
+                .map(|f| (*f, adapter.get_texture_format_features(*f).allowed_usages))
+                .filter(|(_, usages)| usages.contains(wgpu::TextureUsages::RENDER_ATTACHMENT))
+                .collect(),
+            surface_formats: surface.formats.clone(),
+            alpha_modes: surface.alpha_modes.clone(),
+        }
+    }
+
+    ///Returns the highest supported multisampling sample count
+    #[must_use]
+    pub fn max_msaa(&self) -> u32 {
+        self.msaa_levels.iter().copied().max().unwrap_or(1)
+    }
+
+    ///Returns `true` if the window can be composited with the desktop using the alpha of the frame
+    ///buffer, see [`super::surface::set_transparent`]
+    #[must_use]
+    pub fn transparency(&self) -> bool {
+        self.alpha_modes.iter().any(|m| {
+            matches!(
+                m,
+                wgpu::CompositeAlphaMode::PreMultiplied | wgpu::CompositeAlphaMode::PostMultiplied
+            )
+        })
+    }
+
+    ///Returns `true` if the frame buffer can use [`super::surface::HDR_FORMAT`]
+    #[must_use]
+    pub fn hdr_output(&self) -> bool {
+        self.surface_formats.contains(&super::surface::HDR_FORMAT)
+    }
+}
+
+///Stores the capabilities of the adapter, the limits are the ones the device was created with
+pub(crate) fn initialize(
+    adapter: &wgpu::Adapter,
+    limits: &wgpu::Limits,
+    format: wgpu::TextureFormat,
+    surface: &wgpu::SurfaceCapabilities,
+) {
+    _ = CAPABILITIES.set(Capabilities::new(adapter, limits, format, surface));
+}
+
+///Returns the capabilities of the gpu, or `None` if the gpu is not initialized yet
+#[must_use]
+pub fn capabilities() -> Option<&'static Capabilities> {
+    CAPABILITIES.get()
+}
@@ -0,0 +1,255 @@
+//! Compute shaders
+//!
+//! A [`ComputePass`] is created from a WGSL shader, the layouts of its bind groups are derived
+//! from the shader. It can be dispatched in the encoder of a rendering extension with
+//! [`ComputePass::dispatch`], or queued from anywhere with [`ComputePass::queue`], in which case
+//! it's dispatched in the engine encoder at the start of the next [`super::render`], before the
+//! extensions, so they see its results in the same frame.
+//!
+//!```no_run
+//! use lunar_engine::rendering::compute::{self, ComputePass};
+//!
+//! let pass = ComputePass::new(
+//!     "double.wgsl",
+//!     "@group(0) @binding(0) var<storage, read_write> data: array<f32>;
+//!
+//!      @compute @workgroup_size(64)
+//!      fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!          if id.x < arrayLength(&data) {
+//!              data[id.x] *= 2.0;
+//!          }
+//!      }",
+//!     "main",
+//! )
+//! .unwrap();
+//!
+//! let data = vec![1.0_f32; 1000];
+//! let buffer = compute::storage_buffer("Data", &data);
+//! let bind_group = pass.create_bind_group(0, &[buffer.as_entire_binding()]);
+//! pass.queue(&[bind_group], [compute::workgroups(1000, 64), 1, 1]);
+//!```
+use std::{cell::RefCell, sync::Arc};
+
+use wgpu::util::DeviceExt;
+
+use crate::{assets::shader, errors, DEVICE};
+
+///Compute pipeline created from a WGSL shader
+pub struct ComputePass {
+    label: String,
+    pipeline: Arc<wgpu::ComputePipeline>,
+}
+
+//Dispatch recorded at the start of the next frame
+struct Queued {
+    label: String,
+    pipeline: Arc<wgpu::ComputePipeline>,
+    bind_groups: Vec<Arc<wgpu::BindGroup>>,
+    workgroups: [u32; 3],
+}
+
+thread_local! {
+    static QUEUED: RefCell<Vec<Queued>> = const { RefCell::new(Vec::new()) };
+}
+
+impl ComputePass {
+    ///Compiles the shader and creates a pipeline using its `entry_point`
+    ///
+    ///# Errors
+    ///Returns an error if the shader fails to compile or the pipeline can't be created
+    pub fn new(file: &str, source: &str, entry_point: &str) -> Result<Self, errors::Error> {
+        Self::from_module(file, &shader::compile(file, source)?, entry_point)
+    }
+
+    ///Creates a pipeline using the `entry_point` of a compiled shader, for example one compiled
+    ///with [`shader::compile_mapped`] after the [`Preprocessor`](crate::assets::Preprocessor)
+    ///
+    ///# Errors
+    ///Returns an error if the pipeline can't be created
+    pub fn from_module(
+        label: &str,
+        module: &wgpu::ShaderModule,
+        entry_point: &str,
+    ) -> Result<Self, errors::Error> {
+        let device = DEVICE.get().unwrap();
+
+        let pipeline = errors::try_scoped(label, || {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(label),
+                //Derived from the shader
+                layout: None,
+                module,
+                entry_point,
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            })
+        })?;
+
+        Ok(Self {
+            label: label.to_owned(),
+            pipeline: Arc::new(pipeline),
+        })
+    }
+
+    ///Returns the layout of the bind group used by the shader
+    ///
+    ///# Panics
+    ///Panics if the shader does not use the group
+    #[must_use]
+    pub fn bind_group_layout(&self, group: u32) -> wgpu::BindGroupLayout {
+        self.pipeline.get_bind_group_layout(group)
+    }
+
+    ///Creates a bind group for the `group` of the shader, the resources are bound in order,
+    ///starting from binding 0
+    ///
+    ///# Panics
+    ///Panics if the shader does not use the group
+    #[must_use]
+    pub fn create_bind_group(
+        &self,
+        group: u32,
+        resources: &[wgpu::BindingResource],
+    ) -> Arc<wgpu::BindGroup> {
+        let entries = resources
+            .iter()
+            .enumerate()
+            .map(|(i, r)| wgpu::BindGroupEntry {
+                binding: i as u32,
+                resource: r.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        Arc::new(
+            DEVICE
+                .get()
+                .unwrap()
+                .create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some(&format!("{} group {group}", self.label)),
+                    layout: &self.bind_group_layout(group),
+                    entries: &entries,
+                }),
+        )
+    }
+
+    ///Dispatches the workgroups in a new compute pass, the bind groups are set in order
+    pub fn dispatch(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        bind_groups: &[&wgpu::BindGroup],
+        workgroups: [u32; 3],
+    ) {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some(&self.label),
+            timestamp_writes: None,
+        });
+        record(&mut pass, &self.pipeline, bind_groups, workgroups);
+    }
+
+    ///Queues the dispatch for the start of the next render, the bind groups are set in order
+    pub fn queue(&self, bind_groups: &[Arc<wgpu::BindGroup>], workgroups: [u32; 3]) {
+        QUEUED.with_borrow_mut(|q| {
+            q.push(Queued {
+                label: self.label.clone(),
+                pipeline: self.pipeline.clone(),
+                bind_groups: bind_groups.to_vec(),
+                workgroups,
+            });
+        });
+    }
+}
+
+fn record<'a>(
+    pass: &mut wgpu::ComputePass<'a>,
+    pipeline: &'a wgpu::ComputePipeline,
+    bind_groups: &[&'a wgpu::BindGroup],
+    [x, y, z]: [u32; 3],
+) {
+    pass.set_pipeline(pipeline);
+    for (i, b) in bind_groups.iter().enumerate() {
+        pass.set_bind_group(i as u32, b, &[]);
+    }
+    pass.dispatch_workgroups(x, y, z);
+}
+
+///Returns the number of workgroups needed to cover `size` invocations
+#[must_use]
+pub const fn workgroups(size: u32, workgroup_size: u32) -> u32 {
+    size.div_ceil(workgroup_size)
+}
+
+///Creates a storage buffer containing the data, it can also be copied from and to, and used as a
+///vertex buffer
+#[must_use]
+pub fn storage_buffer<T: bytemuck::Pod>(label: &str, data: &[T]) -> wgpu::Buffer {
+    DEVICE
+        .get()
+        .unwrap()
+        .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(label),
+            contents: bytemuck::cast_slice(data),
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::VERTEX,
+        })
+}
+
+///Copies the buffer to the cpu, waiting for the gpu to finish all the submitted work
+///
+///The buffer must have been created with [`wgpu::BufferUsages::COPY_SRC`], not available on the
+///web target, where the gpu can't be waited on
+#[cfg(not(target_arch = "wasm32"))]
+#[must_use]
+pub fn read_buffer<T: bytemuck::Pod>(buffer: &wgpu::Buffer) -> Vec<T> {
+    let device = DEVICE.get().unwrap();
+    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Compute read back"),
+        size: buffer.size(),
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Compute read back encoder"),
+    });
+    encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, buffer.size());
+    crate::QUEUE.get().unwrap().submit(Some(encoder.finish()));
+
+    staging
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, |r| r.unwrap());
+    device.poll(wgpu::Maintain::Wait);
+
+    let data = bytemuck::cast_slice::<u8, T>(&staging.slice(..).get_mapped_range()).to_vec();
+    staging.unmap();
+    data
+}
+
+///Records the queued dispatches into the engine encoder
+pub(crate) fn record_queued(encoder: &mut wgpu::CommandEncoder) {
+    let queued = QUEUED.take();
+    if queued.is_empty() {
+        return;
+    }
+
+    //The pass borrows the bind groups until it ends
+    let bind_groups = queued
+        .iter()
+        .map(|q| q.bind_groups.iter().map(AsRef::as_ref).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+        label: Some("Queued compute"),
+        timestamp_writes: None,
+    });
+    for (q, bind_groups) in queued.iter().zip(&bind_groups) {
+        pass.push_debug_group(&q.label);
+        record(&mut pass, &q.pipeline, bind_groups, q.workgroups);
+        pass.pop_debug_group();
+    }
+}
+
+///Drops the queued dispatches
+pub(crate) fn release() {
+    QUEUED.take();
+}
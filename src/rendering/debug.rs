@@ -0,0 +1,125 @@
+//! Immediate mode drawing of debug lines
+//!
+//! The functions of this module can be called from anywhere, for example from the update of a
+//! component, the lines are accumulated until the end of the frame and rendered by the
+//! [`DebugLines`](super::extensions::DebugLines) extension. Lines must be drawn again every frame
+//! they should be visible for.
+use std::{f32::consts::TAU, sync::Mutex};
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::{
+    math::{geometry::Aabb, Mat4x4, Vec3},
+    structures::Color,
+};
+
+//Segments of each of the circles of a sphere
+const SPHERE_SEGMENTS: usize = 32;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub(crate) struct Vertex {
+    position: [f32; 3],
+    color: [f32; 4],
+}
+
+//Pairs of vertices drawn this frame
+static LINES: Mutex<Vec<Vertex>> = Mutex::new(Vec::new());
+
+///Draws a line from `a` to `b`
+pub fn line(a: Vec3, b: Vec3, color: Color) {
+    let color = [color.r, color.g, color.b, color.a];
+    LINES.lock().unwrap().extend([
+        Vertex {
+            position: [a.x, a.y, a.z],
+            color,
+        },
+        Vertex {
+            position: [b.x, b.y, b.z],
+            color,
+        },
+    ]);
+}
+
+///Draws a sphere as 3 circles around the x, y and z axes
+pub fn wire_sphere(center: Vec3, radius: f32, color: Color) {
+    let point = |i: usize| {
+        let angle = i as f32 / SPHERE_SEGMENTS as f32 * TAU;
+        (angle.cos() * radius, angle.sin() * radius)
+    };
+    for i in 0..SPHERE_SEGMENTS {
+        let (a, b) = (point(i), point(i + 1));
+        line(
+            center + Vec3::new(0.0, a.0, a.1),
+            center + Vec3::new(0.0, b.0, b.1),
+            color,
+        );
+        line(
+            center + Vec3::new(a.0, 0.0, a.1),
+            center + Vec3::new(b.0, 0.0, b.1),
+            color,
+        );
+        line(
+            center + Vec3::new(a.0, a.1, 0.0),
+            center + Vec3::new(b.0, b.1, 0.0),
+            color,
+        );
+    }
+}
+
+///Draws the edges of a box
+pub fn wire_box(aabb: &Aabb, color: Color) {
+    let (min, max) = (aabb.min, aabb.max);
+    let corner = |i: usize| {
+        Vec3::new(
+            if i & 1 == 0 { min.x } else { max.x },
+            if i & 2 == 0 { min.y } else { max.y },
+            if i & 4 == 0 { min.z } else { max.z },
+        )
+    };
+    for i in 0..8 {
+        //Connects every corner to the corners differing in a single axis
+        for axis in [1, 2, 4] {
+            if i & axis == 0 {
+                line(corner(i), corner(i | axis), color);
+            }
+        }
+    }
+}
+
+///Draws the x, y and z axes of a transformation matrix in red, green and blue, starting at its
+///translation
+pub fn axes(matrix: &Mat4x4) {
+    let origin = matrix.transform3(Vec3::new(0.0, 0.0, 0.0));
+    line(
+        origin,
+        matrix.transform3(Vec3::new(1.0, 0.0, 0.0)),
+        Color::rgb(1.0, 0.0, 0.0),
+    );
+    line(
+        origin,
+        matrix.transform3(Vec3::new(0.0, 1.0, 0.0)),
+        Color::rgb(0.0, 1.0, 0.0),
+    );
+    line(
+        origin,
+        matrix.transform3(Vec3::new(0.0, 0.0, 1.0)),
+        Color::rgb(0.0, 0.0, 1.0),
+    );
+}
+
+///Removes all the lines drawn this frame, called at the end of every frame
+pub fn clear() {
+    LINES.lock().unwrap().clear();
+}
+
+///Returns the number of lines drawn this frame
+#[must_use]
+pub fn line_count() -> usize {
+    LINES.lock().unwrap().len() / 2
+}
+
+///Returns the vertices of the lines drawn this frame
+pub(crate) fn vertices() -> Vec<Vertex> {
+    LINES.lock().unwrap().clone()
+}
@@ -0,0 +1,194 @@
+//! Depth range used by the renderer
+//!
+//! With [`DepthRange::Reversed`] the near plane is mapped to a depth of 1 and the far plane to 0.
+//! Combined with a floating point depth buffer this distributes the precision much more evenly
+//! over the viewing distance, which prevents z-fighting in scenes with distant far planes.
+//!
+//! The depth range must be set before any pipelines are created, i.e. before the materials are
+//! initialized. Custom rendering extensions should use [`compare_function`] and [`clear_value`]
+//! instead of hard coding them.
+//!
+//! The format and the usages of the depth attachment can be changed with [`set_config`], for
+//! example to get a stencil buffer, or to drop usages some gpus don't support for the format. Like
+//! the depth range it must be set before the window is created, custom extensions should create
+//! their pipelines with [`format`].
+//!
+//!```no_run
+//! use lunar_engine::rendering::depth;
+//!
+//! depth::set_config(
+//!     wgpu::TextureFormat::Depth24PlusStencil8,
+//!     wgpu::TextureUsages::RENDER_ATTACHMENT,
+//! )
+//! .unwrap();
+//!```
+use std::sync::{
+    atomic::{AtomicBool, AtomicU32, Ordering},
+    RwLock,
+};
+
+///Default format of the depth attachment
+pub const DEFAULT_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+///Default usages of the depth attachment, the depth can be copied into and sampled
+pub const DEFAULT_USAGE: wgpu::TextureUsages = wgpu::TextureUsages::RENDER_ATTACHMENT
+    .union(wgpu::TextureUsages::COPY_DST)
+    .union(wgpu::TextureUsages::TEXTURE_BINDING);
+
+///Formats the depth attachment can use, if they're supported by the gpu
+pub(crate) const FORMATS: [wgpu::TextureFormat; 4] = [
+    wgpu::TextureFormat::Depth16Unorm,
+    wgpu::TextureFormat::Depth24Plus,
+    wgpu::TextureFormat::Depth24PlusStencil8,
+    wgpu::TextureFormat::Depth32Float,
+];
+
+static REVERSED: AtomicBool = AtomicBool::new(false);
+static FORMAT: RwLock<wgpu::TextureFormat> = RwLock::new(DEFAULT_FORMAT);
+static USAGE: AtomicU32 = AtomicU32::new(DEFAULT_USAGE.bits());
+
+///Mapping of the distance from the camera to the values stored in the depth buffer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DepthRange {
+    ///Near plane is at 0, far plane is at 1
+    #[default]
+    Standard,
+    ///Near plane is at 1, far plane is at 0
+    Reversed,
+}
+
+///Sets the depth range used by the renderer
+///
+///Pipelines created before the change keep using the previous depth range
+pub fn set_depth_range(range: DepthRange) {
+    REVERSED.store(range == DepthRange::Reversed, Ordering::Relaxed);
+}
+
+///Returns the depth range used by the renderer
+#[must_use]
+pub fn get_depth_range() -> DepthRange {
+    if REVERSED.load(Ordering::Relaxed) {
+        DepthRange::Reversed
+    } else {
+        DepthRange::Standard
+    }
+}
+
+///Returns `true` if the depth range is reversed
+#[must_use]
+pub fn is_reversed() -> bool {
+    get_depth_range() == DepthRange::Reversed
+}
+
+///Returns the depth compare function for the current depth range, that passes fragments closer
+///to the camera
+#[must_use]
+pub fn compare_function() -> wgpu::CompareFunction {
+    match get_depth_range() {
+        DepthRange::Standard => wgpu::CompareFunction::Less,
+        DepthRange::Reversed => wgpu::CompareFunction::GreaterEqual,
+    }
+}
+
+///Returns the depth of the far plane, that the depth buffer is cleared with
+#[must_use]
+pub fn clear_value() -> f32 {
+    match get_depth_range() {
+        DepthRange::Standard => 1.0,
+        DepthRange::Reversed => 0.0,
+    }
+}
+
+///The format or the usages of the depth attachment are not supported by the gpu
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedDepthConfig {
+    ///Requested format
+    pub format: wgpu::TextureFormat,
+    ///Requested usages
+    pub usage: wgpu::TextureUsages,
+    ///Formats supported by the gpu, with the usages allowed for them
+    pub supported: Vec<(wgpu::TextureFormat, wgpu::TextureUsages)>,
+}
+
+impl std::fmt::Display for UnsupportedDepthConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Depth format {:?} with usages {:?} is not supported, supported formats are {:?}",
+            self.format, self.usage, self.supported
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedDepthConfig {}
+
+///Returns the formats that can be used with their allowed usages, all of [`FORMATS`] with any
+///usage before the gpu is initialized
+fn supported() -> Vec<(wgpu::TextureFormat, wgpu::TextureUsages)> {
+    super::capabilities().map_or_else(
+        || {
+            FORMATS
+                .iter()
+                .map(|f| (*f, wgpu::TextureUsages::all()))
+                .collect()
+        },
+        |c| c.depth_formats.clone(),
+    )
+}
+
+///Sets the format and the usages of the depth attachment, [`wgpu::TextureUsages::RENDER_ATTACHMENT`]
+///is always added to the usages
+///
+///The attachment is recreated on the next frame, pipelines created before the change keep using
+///the previous format
+///
+///# Errors
+///Returns an error if the format is not a depth format, or if the gpu doesn't support the format
+///with the usages, see [`super::capabilities::Capabilities::depth_formats`]
+pub fn set_config(
+    format: wgpu::TextureFormat,
+    usage: wgpu::TextureUsages,
+) -> Result<(), UnsupportedDepthConfig> {
+    let usage = usage | wgpu::TextureUsages::RENDER_ATTACHMENT;
+    let supported = supported();
+    if !supported
+        .iter()
+        .any(|(f, allowed)| *f == format && allowed.contains(usage))
+    {
+        return Err(UnsupportedDepthConfig {
+            format,
+            usage,
+            supported,
+        });
+    }
+    *FORMAT.write().unwrap() = format;
+    USAGE.store(usage.bits(), Ordering::Relaxed);
+    Ok(())
+}
+
+///Returns the format of the depth attachment
+#[must_use]
+pub fn format() -> wgpu::TextureFormat {
+    *FORMAT.read().unwrap()
+}
+
+///Returns the usages of the depth attachment
+#[must_use]
+pub fn usage() -> wgpu::TextureUsages {
+    wgpu::TextureUsages::from_bits_truncate(USAGE.load(Ordering::Relaxed))
+}
+
+///Returns `true` if the depth attachment has a stencil aspect
+#[must_use]
+pub fn has_stencil() -> bool {
+    format().has_stencil_aspect()
+}
+
+///Falls back to the default format and usages if the ones set before the initialization are not
+///supported by the gpu
+pub(crate) fn validate() {
+    if let Err(e) = set_config(format(), usage()) {
+        log::warn!("{e}, using the default depth format");
+        *FORMAT.write().unwrap() = DEFAULT_FORMAT;
+        USAGE.store(DEFAULT_USAGE.bits(), Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,239 @@
+//! Small per draw data, such as object ids or highlight flags
+//!
+//! Uses push constants where they are supported, otherwise falls back to a uniform buffer with
+//! dynamic offsets, so changing the data does not require rebuilding the instance buffers.
+//!
+//! Usage in a rendering extension:
+//! 1. Include [`DrawDataBinding::wgsl_declaration`] in the shader, it declares the `draw_data`
+//!    variable
+//! 2. Create the pipeline layout with [`DrawDataBinding::push_constant_ranges`] and, if
+//!    [`DrawDataBinding::bind_group_layout`] returns a layout, add it to the bind group layouts
+//! 3. Call [`DrawDataBinding::prepare`] with the data of all the draws before beginning the render
+//!    pass
+//! 4. Call [`DrawDataBinding::set`] before every draw
+use std::sync::OnceLock;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt;
+
+use crate::DEVICE;
+
+static PUSH_CONSTANTS: OnceLock<bool> = OnceLock::new();
+
+///Returns `true` if push constants are supported by the device
+#[must_use]
+pub fn push_constants_supported() -> bool {
+    PUSH_CONSTANTS.get().copied().unwrap_or_default()
+}
+
+pub(crate) fn set_push_constants_supported(value: bool) {
+    _ = PUSH_CONSTANTS.set(value);
+}
+
+///Stages in which the draw data is visible
+const STAGES: wgpu::ShaderStages = wgpu::ShaderStages::VERTEX_FRAGMENT;
+
+///Data of a single draw call
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Pod, Zeroable)]
+pub struct DrawData {
+    ///Id of the drawn object
+    pub object_id: u32,
+    ///User defined flags, for example whether or not the object is highlighted
+    pub flags: u32,
+    ///Additional user defined data
+    pub user: [u32; 2],
+}
+
+//Must match the wgsl declaration
+const _: () = assert!(std::mem::size_of::<DrawData>() == 16);
+
+const PUSH_CONSTANT_RANGES: &[wgpu::PushConstantRange] = &[wgpu::PushConstantRange {
+    stages: STAGES,
+    range: 0..std::mem::size_of::<DrawData>() as u32,
+}];
+
+///Binds [`DrawData`] for draw calls using either push constants or a uniform buffer
+pub struct DrawDataBinding {
+    //Data of all the draws of the frame, used with push constants
+    data: Vec<DrawData>,
+    //Fallback when push constants are not supported
+    fallback: Option<Fallback>,
+}
+
+struct Fallback {
+    layout: wgpu::BindGroupLayout,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    //Distance between the data of 2 draws in the buffer
+    stride: u64,
+    //Number of draws the buffer can hold
+    capacity: usize,
+}
+
+impl Fallback {
+    fn new(capacity: usize) -> Self {
+        let device = DEVICE.get().unwrap();
+        let alignment = u64::from(device.limits().min_uniform_buffer_offset_alignment);
+        let stride = (std::mem::size_of::<DrawData>() as u64).div_ceil(alignment) * alignment;
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Draw data"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: STAGES,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<DrawData>() as u64),
+                },
+                count: None,
+            }],
+        });
+
+        let (buffer, bind_group) = Self::create_buffer(&layout, stride, capacity);
+
+        Self {
+            layout,
+            buffer,
+            bind_group,
+            stride,
+            capacity,
+        }
+    }
+
+    ///Recreates the buffer to fit `capacity` draws, the layout stays the same, so the pipelines
+    ///remain valid
+    fn resize(&mut self, capacity: usize) {
+        (self.buffer, self.bind_group) = Self::create_buffer(&self.layout, self.stride, capacity);
+        self.capacity = capacity;
+    }
+
+    fn create_buffer(
+        layout: &wgpu::BindGroupLayout,
+        stride: u64,
+        capacity: usize,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        let device = DEVICE.get().unwrap();
+
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Draw data"),
+            contents: &vec![0; (stride as usize) * capacity],
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Draw data"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(std::mem::size_of::<DrawData>() as u64),
+                }),
+            }],
+        });
+
+        (buffer, bind_group)
+    }
+}
+
+impl Default for DrawDataBinding {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DrawDataBinding {
+    ///Creates a new binding, using push constants if they are supported
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_fallback(!push_constants_supported())
+    }
+
+    ///Creates a new binding, using the uniform buffer instead of push constants if `fallback` is
+    ///`true`
+    pub(crate) fn with_fallback(fallback: bool) -> Self {
+        Self {
+            data: Vec::new(),
+            fallback: fallback.then(|| Fallback::new(64)),
+        }
+    }
+
+    ///Returns the push constant ranges for the pipeline layout, empty if push constants are not
+    ///supported
+    #[must_use]
+    pub fn push_constant_ranges(&self) -> &'static [wgpu::PushConstantRange] {
+        if self.fallback.is_some() {
+            return &[];
+        }
+        PUSH_CONSTANT_RANGES
+    }
+
+    ///Returns the layout of the bind group used when push constants are not supported
+    #[must_use]
+    pub fn bind_group_layout(&self) -> Option<&wgpu::BindGroupLayout> {
+        self.fallback.as_ref().map(|f| &f.layout)
+    }
+
+    ///Returns the wgsl declaration of the `draw_data` variable, `group` is the index of the bind
+    ///group used when push constants are not supported
+    #[must_use]
+    pub fn wgsl_declaration(&self, group: u32) -> String {
+        let var = if self.fallback.is_some() {
+            format!("@group({group}) @binding(0) var<uniform>")
+        } else {
+            "var<push_constant>".to_owned()
+        };
+
+        format!(
+            "struct DrawData {{
+    object_id: u32,
+    flags: u32,
+    user: vec2<u32>,
+}}
+
+{var} draw_data: DrawData;
+"
+        )
+    }
+
+    ///Uploads the data of all the draws of the current pass, must be called before the pass
+    ///begins
+    pub fn prepare(&mut self, data: &[DrawData]) {
+        self.data.clear();
+        self.data.extend_from_slice(data);
+
+        let Some(fallback) = &mut self.fallback else {
+            return;
+        };
+
+        if fallback.capacity < data.len() {
+            fallback.resize(data.len().next_power_of_two());
+        }
+
+        let stride = fallback.stride as usize;
+        let mut bytes = vec![0; stride * data.len()];
+        for (d, chunk) in data.iter().zip(bytes.chunks_exact_mut(stride)) {
+            chunk[..std::mem::size_of::<DrawData>()].copy_from_slice(bytemuck::bytes_of(d));
+        }
+
+        crate::QUEUE
+            .get()
+            .unwrap()
+            .write_buffer(&fallback.buffer, 0, &bytes);
+    }
+
+    ///Binds the data of the `index`th draw passed to [`DrawDataBinding::prepare`]
+    ///
+    ///`group` is the index of the bind group used when push constants are not supported
+    pub fn set<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>, group: u32, index: usize) {
+        match &self.fallback {
+            Some(f) => {
+                pass.set_bind_group(group, &f.bind_group, &[(f.stride as usize * index) as u32])
+            }
+            None => pass.set_push_constants(STAGES, 0, bytemuck::bytes_of(&self.data[index])),
+        }
+    }
+}
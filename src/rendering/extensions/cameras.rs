@@ -0,0 +1,283 @@
+//! Rendering of multiple cameras into the same frame
+//!
+//! The [`Cameras`] extension renders the meshes of the world with every enabled [`Camera`]
+//! without a [`Camera::target`] into its [`Camera::rect`], used for split-screen or
+//! picture-in-picture.
+//!
+//!```no_run
+//! use lunar_engine::{
+//!     components::{camera::Camera, transform::Transform},
+//!     ecs::{EntityBuilder, World},
+//!     rendering::{extensions::Cameras, viewport::Rect},
+//! };
+//!
+//! let mut world = World::new();
+//!
+//! //Left and right halves of the screen
+//! for rect in [Rect::new(0.0, 0.0, 0.5, 1.0), Rect::new(0.5, 0.0, 0.5, 1.0)] {
+//!     world.add_entity(
+//!         EntityBuilder::new()
+//!             .add_component::<Transform>()
+//!             .create_component(move || {
+//!                 let mut camera = Camera::default();
+//!                 camera.rect = rect;
+//!                 camera
+//!             })
+//!             .create()
+//!             .unwrap(),
+//!     );
+//! }
+//!
+//! let mut cameras = Cameras::new(0);
+//!```
+use bytemuck::{Pod, Zeroable};
+
+use crate::{
+    asset_managment::AssetStore,
+    assets::shader,
+    components::{
+        camera::{Camera, CameraClear},
+        mesh::ALL_LAYERS,
+    },
+    ecs::World,
+    rendering::{self, depth, hdr, lighting, msaa, viewport},
+    structures::Color,
+    DEVICE, QUEUE,
+};
+
+use super::{scene::SceneDraws, AttachmentData, PassOps, RenderingExtension};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Clear {
+    color: [f32; 4],
+    depth: [f32; 4],
+}
+
+struct CamerasState {
+    //Clearing the color and the depth, clearing only the depth
+    pipelines: [wgpu::RenderPipeline; 2],
+    layout: wgpu::BindGroupLayout,
+    //One for every camera, they are all written before the frame is submitted
+    uniforms: Vec<(wgpu::Buffer, wgpu::BindGroup)>,
+    //Color attachment the pipelines were created for
+    targets: (u32, wgpu::TextureFormat),
+}
+
+///Renders the cameras without a [`Camera::target`] into their rectangles of the viewport
+///
+///The cameras are rendered in the order of their priorities, each clearing its rectangle
+///according to its [`Camera::clear`]. The [`MainCamera`](crate::components::camera::MainCamera)
+///is not rendered by this extension.
+pub struct Cameras {
+    ///Priority of the extension
+    pub priority: u32,
+    ///What the pass does with the color and depth attachments
+    pub ops: PassOps,
+    scene: SceneDraws,
+    state: Option<CamerasState>,
+}
+
+impl Cameras {
+    ///Creates a new cameras extension
+    #[must_use]
+    pub const fn new(priority: u32) -> Self {
+        Self {
+            priority,
+            ops: PassOps::new(),
+            scene: SceneDraws::new("Camera instances"),
+            state: None,
+        }
+    }
+
+    fn create_state() -> CamerasState {
+        let device = DEVICE.get().unwrap();
+
+        let shader = shader::compile(
+            "camera_clear.wgsl",
+            include_str!("../../shaders/camera_clear.wgsl"),
+        )
+        .expect("Failed to compile the camera clear shader");
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Camera clear bind group layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Camera clear pipeline layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = |label, write_mask| {
+            crate::errors::scoped(label, || {
+                device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some(label),
+                    layout: Some(&pipeline_layout),
+                    vertex: wgpu::VertexState {
+                        module: &shader,
+                        entry_point: "vertex",
+                        buffers: &[],
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    },
+                    primitive: wgpu::PrimitiveState::default(),
+                    depth_stencil: Some(wgpu::DepthStencilState {
+                        format: depth::format(),
+                        depth_write_enabled: true,
+                        depth_compare: wgpu::CompareFunction::Always,
+                        stencil: wgpu::StencilState::default(),
+                        bias: wgpu::DepthBiasState::default(),
+                    }),
+                    multisample: msaa::multisample_state(),
+                    fragment: Some(wgpu::FragmentState {
+                        module: &shader,
+                        entry_point: "fragment",
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: hdr::color_format(),
+                            blend: None,
+                            write_mask,
+                        })],
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    }),
+                    multiview: None,
+                })
+            })
+        };
+
+        CamerasState {
+            pipelines: [
+                pipeline("Camera clear pipeline", wgpu::ColorWrites::ALL),
+                pipeline("Camera depth clear pipeline", wgpu::ColorWrites::empty()),
+            ],
+            layout,
+            uniforms: Vec::new(),
+            targets: rendering::color_targets(),
+        }
+    }
+}
+
+impl RenderingExtension for Cameras {
+    fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        world: &World,
+        assets: &AssetStore,
+        attachments: &AttachmentData,
+    ) {
+        let mut cameras = world
+            .get_all_components::<Camera>()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|c| {
+                let c = c.borrow();
+                c.enabled && c.target.is_none()
+            })
+            .collect::<Vec<_>>();
+        if cameras.is_empty() {
+            return;
+        }
+        //Stable, so cameras with equal priorities keep the order they were added in
+        cameras.sort_by_key(|c| c.borrow().priority);
+
+        if self
+            .state
+            .as_ref()
+            .is_none_or(|s| s.targets != rendering::color_targets())
+        {
+            self.state = Some(Self::create_state());
+        }
+        let state = self.state.as_mut().unwrap();
+        let device = DEVICE.get().unwrap();
+        let queue = QUEUE.get().unwrap();
+
+        while state.uniforms.len() < cameras.len() {
+            let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Camera clear"),
+                size: std::mem::size_of::<Clear>() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Camera clear"),
+                layout: &state.layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }],
+            });
+            state.uniforms.push((buffer, bind_group));
+        }
+
+        lighting::update(world);
+        self.scene.prepare(world, assets, ALL_LAYERS);
+
+        let cameras = cameras.iter().map(|c| c.borrow()).collect::<Vec<_>>();
+        for (camera, (uniform, _)) in cameras.iter().zip(&state.uniforms) {
+            camera.update_gpu(encoder);
+            let color = camera.background.clear_color(Color::black());
+            queue.write_buffer(
+                uniform,
+                0,
+                bytemuck::bytes_of(&Clear {
+                    color: [color.r, color.g, color.b, color.a],
+                    depth: [depth::clear_value(), 0.0, 0.0, 0.0],
+                }),
+            );
+        }
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Cameras pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &attachments.color,
+                resolve_target: None,
+                ops: self.ops.color_ops(attachments, wgpu::Color::BLACK),
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &attachments.depth_stencil,
+                depth_ops: Some(self.ops.depth_ops(attachments)),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        for (camera, (_, clear)) in cameras.iter().zip(&state.uniforms) {
+            let rect = viewport::current().sub(camera.rect);
+            if rect.width < 1.0 || rect.height < 1.0 {
+                continue;
+            }
+            viewport::apply_rect(&mut render_pass, camera.rect);
+
+            let pipeline = match camera.clear {
+                CameraClear::Color => Some(&state.pipelines[0]),
+                CameraClear::Depth => Some(&state.pipelines[1]),
+                CameraClear::Nothing => None,
+            };
+            if let Some(pipeline) = pipeline {
+                render_pass.set_pipeline(pipeline);
+                render_pass.set_bind_group(0, clear, &[]);
+                render_pass.draw(0..3, 0..1);
+            }
+
+            self.scene
+                .draw(&mut render_pass, assets, camera.get_bind_group());
+        }
+    }
+
+    fn get_priority(&self) -> u32 {
+        self.priority
+    }
+
+    fn get_reads(&self) -> &'static [&'static str] {
+        &["shadow", "lights"]
+    }
+}
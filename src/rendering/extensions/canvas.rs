@@ -0,0 +1,699 @@
+//! Vector shapes and text drawn on top of the rendered image
+//!
+//! The [`Canvas`] extension draws rectangles with rounded corners, circles, lines and text, all
+//! rendered as signed distance fields, so the edges stay smooth at any scale. Positions and sizes
+//! are in pixels, relative to the top left corner of the viewport, see [`viewport::current`].
+//!
+//! The canvas works in immediate mode, the shapes added before rendering are drawn in the order
+//! they were added, and removed once they are rendered.
+//!
+//!```no_run
+//! # let mut assets = lunar_engine::asset_managment::AssetStore::new();
+//! use lunar_engine::{
+//!     assets::Texture,
+//!     math::Vec2,
+//!     rendering::extensions::canvas::{Canvas, LineJoin, SdfFont},
+//!     structures::Color,
+//! };
+//!
+//! let atlas = assets.register(Texture::new_png("font.png".as_ref()));
+//! let font = SdfFont::grid(atlas, 16, 6, ' ', 0.5);
+//! let mut canvas = Canvas::new(10);
+//!
+//! //Every frame
+//! canvas.rect(Vec2::new(8.0, 8.0), Vec2::new(200.0, 48.0), 8.0, Color::new(0.0, 0.0, 0.0, 0.6));
+//! canvas.text(&font, "Hello", Vec2::new(16.0, 16.0), 32.0, Color::white());
+//! canvas.polyline(
+//!     &[Vec2::new(8.0, 80.0), Vec2::new(40.0, 120.0), Vec2::new(72.0, 80.0)],
+//!     4.0,
+//!     LineJoin::Miter,
+//!     Color::white(),
+//! );
+//!```
+use std::{collections::HashMap, mem, ops::Range};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt;
+
+use crate::{
+    asset_managment::{AssetStore, UUID},
+    assets::{shader, Font, Texture},
+    ecs::World,
+    math::{Vec2, Vector},
+    rendering::{self, hdr, msaa, viewport},
+    structures::Color,
+    DEVICE, QUEUE,
+};
+
+use super::{AttachmentData, RenderingExtension};
+
+//Kinds of the shapes in the shader
+const BOX: f32 = 0.0;
+const GLYPH: f32 = 1.0;
+const TRIANGLE: f32 = 2.0;
+
+//Longest miter, relative to the width of the line, longer ones are beveled
+const MITER_LIMIT: f32 = 4.0;
+
+///Glyph of an [`SdfFont`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Glyph {
+    ///Top left and bottom right corners of the glyph in the atlas, from 0 to 1, with 0, 0 at the
+    ///top left corner of the image
+    pub uv: (Vec2, Vec2),
+    ///Size of the glyph, in multiples of the font size
+    pub size: Vec2,
+    ///Offset of the top left corner of the glyph from the top of the line, in multiples of the
+    ///font size
+    pub offset: Vec2,
+    ///Horizontal distance to the next glyph, in multiples of the font size
+    pub advance: f32,
+}
+
+///Font with the glyphs stored as signed distance fields in a texture
+///
+///The distance is stored in the red channel of the atlas, 0.5 being the edge of the glyph and
+///higher values being inside of it. Characters without a glyph are skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdfFont {
+    ///Texture asset containing the glyphs
+    pub atlas: UUID,
+    ///Distance between the tops of 2 lines, in multiples of the font size
+    pub line_height: f32,
+    glyphs: HashMap<char, Glyph>,
+    //Adjustments of the advance between pairs of characters, in multiples of the font size
+    kerning: HashMap<(char, char), f32>,
+}
+
+impl SdfFont {
+    ///Creates a font without any glyphs
+    #[must_use]
+    pub fn new(atlas: UUID, line_height: f32) -> Self {
+        Self {
+            atlas,
+            line_height,
+            glyphs: HashMap::new(),
+            kerning: HashMap::new(),
+        }
+    }
+
+    ///Creates a monospaced font from an atlas with the glyphs placed in a grid of equally sized
+    ///cells, row by row, starting with the character `first` and continuing with the consecutive
+    ///characters
+    ///
+    ///`aspect` is the width of the glyphs divided by their height
+    #[must_use]
+    pub fn grid(atlas: UUID, columns: u32, rows: u32, first: char, aspect: f32) -> Self {
+        let mut font = Self::new(atlas, 1.0);
+        let cell = Vec2::new(1.0 / columns as f32, 1.0 / rows as f32);
+
+        for i in 0..columns * rows {
+            let Some(c) = char::from_u32(first as u32 + i) else {
+                continue;
+            };
+            let min = Vec2::new((i % columns) as f32, (i / columns) as f32) * cell;
+            font.add_glyph(
+                c,
+                Glyph {
+                    uv: (min, min + cell),
+                    size: Vec2::new(aspect, 1.0),
+                    offset: Vec2::new(0.0, 0.0),
+                    advance: aspect,
+                },
+            );
+        }
+        font
+    }
+
+    ///Adds a glyph for the character, replacing the previous one
+    pub fn add_glyph(&mut self, c: char, glyph: Glyph) {
+        self.glyphs.insert(c, glyph);
+    }
+
+    ///Returns the glyph of the character
+    #[must_use]
+    pub fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs.get(&c)
+    }
+
+    ///Sets the adjustment of the advance between the `first` and the `second` character, in
+    ///multiples of the font size, negative values move the characters closer together
+    pub fn add_kerning(&mut self, first: char, second: char, kerning: f32) {
+        self.kerning.insert((first, second), kerning);
+    }
+
+    ///Returns the adjustment of the advance between the `first` and the `second` character
+    #[must_use]
+    pub fn kerning(&self, first: char, second: char) -> f32 {
+        self.kerning
+            .get(&(first, second))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    ///Returns the top left corner, the size and the glyph of every character of the text drawn
+    ///at the `position` with the font size of `size` pixels
+    #[must_use]
+    pub fn layout(&self, text: &str, position: Vec2, size: f32) -> Vec<(Vec2, Vec2, Glyph)> {
+        let mut pen = position;
+        let mut out = Vec::with_capacity(text.len());
+        let mut previous = None;
+
+        for c in text.chars() {
+            if c == '\n' {
+                pen = Vec2::new(position.x, self.line_height.mul_add(size, pen.y));
+                previous = None;
+                continue;
+            }
+            let Some(glyph) = self.glyph(c) else {
+                continue;
+            };
+            if let Some(p) = previous {
+                pen.x = self.kerning(p, c).mul_add(size, pen.x);
+            }
+            out.push((pen + glyph.offset * size, glyph.size * size, *glyph));
+            pen.x = glyph.advance.mul_add(size, pen.x);
+            previous = Some(c);
+        }
+        out
+    }
+
+    ///Returns the width and the height of the text drawn with the font size of `size` pixels
+    #[must_use]
+    pub fn measure(&self, text: &str, size: f32) -> Vec2 {
+        let lines = text.split('\n').collect::<Vec<_>>();
+        let width = lines
+            .iter()
+            .map(|l| {
+                let chars = l
+                    .chars()
+                    .filter(|c| self.glyph(*c).is_some())
+                    .collect::<Vec<_>>();
+                let kerning = chars
+                    .windows(2)
+                    .map(|p| self.kerning(p[0], p[1]))
+                    .sum::<f32>();
+                chars
+                    .iter()
+                    .filter_map(|c| self.glyph(*c))
+                    .map(|g| g.advance)
+                    .sum::<f32>()
+                    + kerning
+            })
+            .fold(0.0, f32::max);
+        Vec2::new(width, lines.len() as f32 * self.line_height) * size
+    }
+}
+
+///Shape of the corners where the segments of a line meet
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineJoin {
+    ///Segments are extended until their outer edges meet, sharp corners are beveled
+    #[default]
+    Miter,
+    ///Outer corners of the segments are connected with a straight edge
+    Bevel,
+    ///Corners are rounded
+    Round,
+}
+
+//A single instance, see the shader for the meaning of the fields
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Shape {
+    rect: [f32; 4],
+    params: [f32; 4],
+    color: [f32; 4],
+    uv: [f32; 4],
+}
+
+impl Shape {
+    fn oriented_box(center: Vec2, half_size: Vec2, axis: Vec2, radius: f32, color: Color) -> Self {
+        Self {
+            rect: [center.x, center.y, half_size.x, half_size.y],
+            params: [axis.x, axis.y, radius, BOX],
+            color: [color.r, color.g, color.b, color.a],
+            uv: [0.0; 4],
+        }
+    }
+
+    fn triangle(a: Vec2, b: Vec2, c: Vec2, color: Color) -> Self {
+        Self {
+            rect: [a.x, a.y, b.x, b.y],
+            params: [c.x, c.y, 0.0, TRIANGLE],
+            color: [color.r, color.g, color.b, color.a],
+            uv: [0.0; 4],
+        }
+    }
+}
+
+struct CanvasState {
+    pipeline: wgpu::RenderPipeline,
+    //Color attachment the pipeline was created for
+    targets: (u32, wgpu::TextureFormat),
+    globals: wgpu::Buffer,
+    globals_bind_group: wgpu::BindGroup,
+    atlas_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    //Bound for the shapes that don't use an atlas
+    blank: wgpu::BindGroup,
+    atlases: HashMap<UUID, wgpu::BindGroup>,
+    buffer: Option<wgpu::Buffer>,
+}
+
+///Draws vector shapes and text into the color attachment
+///
+///Should have a high priority, so that it is rendered on top of the scene
+pub struct Canvas {
+    ///Priority of the extension
+    pub priority: u32,
+    shapes: Vec<Shape>,
+    //Atlas used by the consecutive shapes
+    batches: Vec<(Option<UUID>, Range<u32>)>,
+    state: Option<CanvasState>,
+}
+
+impl Canvas {
+    ///Creates a new empty canvas
+    #[must_use]
+    pub const fn new(priority: u32) -> Self {
+        Self {
+            priority,
+            shapes: Vec::new(),
+            batches: Vec::new(),
+            state: None,
+        }
+    }
+
+    ///Removes all the shapes that were not rendered yet
+    pub fn clear(&mut self) {
+        self.shapes.clear();
+        self.batches.clear();
+    }
+
+    ///Returns `true` if there are no shapes to render
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_empty()
+    }
+
+    fn push(&mut self, shape: Shape, atlas: Option<UUID>) {
+        let index = self.shapes.len() as u32;
+        self.shapes.push(shape);
+        match self.batches.last_mut() {
+            Some((a, range)) if *a == atlas => range.end = index + 1,
+            _ => self.batches.push((atlas, index..index + 1)),
+        }
+    }
+
+    ///Draws a rectangle with the top left corner at the `position` and corners rounded with the
+    ///`radius`
+    pub fn rect(&mut self, position: Vec2, size: Vec2, radius: f32, color: Color) {
+        let half = size / 2.0;
+        let radius = radius.clamp(0.0, half.x.min(half.y));
+        self.push(
+            Shape::oriented_box(position + half, half, Vec2::new(1.0, 0.0), radius, color),
+            None,
+        );
+    }
+
+    ///Draws a circle
+    pub fn circle(&mut self, center: Vec2, radius: f32, color: Color) {
+        self.push(
+            Shape::oriented_box(
+                center,
+                Vec2::new(radius, radius),
+                Vec2::new(1.0, 0.0),
+                radius,
+                color,
+            ),
+            None,
+        );
+    }
+
+    ///Draws a straight line
+    pub fn line(&mut self, from: Vec2, to: Vec2, width: f32, color: Color) {
+        self.polyline(&[from, to], width, LineJoin::Miter, color);
+    }
+
+    ///Draws a line going through all the `points`, with the corners shaped by the `join`
+    pub fn polyline(&mut self, points: &[Vec2], width: f32, join: LineJoin, color: Color) {
+        let half_width = width / 2.0;
+
+        //Repeated points don't have a direction
+        let mut points = points.to_vec();
+        points.dedup_by(|a, b| (*a - *b).length() < f32::EPSILON);
+
+        for segment in points.windows(2) {
+            let (a, b) = (segment[0], segment[1]);
+            let length = (b - a).length();
+            self.push(
+                Shape::oriented_box(
+                    (a + b) / 2.0,
+                    Vec2::new(length / 2.0, half_width),
+                    (b - a) / length,
+                    0.0,
+                    color,
+                ),
+                None,
+            );
+        }
+
+        for corner in points.windows(3) {
+            let (a, p, b) = (corner[0], corner[1], corner[2]);
+            let d0 = (p - a).normalized();
+            let d1 = (b - p).normalized();
+
+            let turn = d0.x * d1.y - d0.y * d1.x;
+            if turn.abs() < 1e-4 && d0.dot_product(&d1) > 0.0 {
+                continue;
+            }
+
+            if join == LineJoin::Round {
+                self.circle(p, half_width, color);
+                continue;
+            }
+
+            //Outer edges of the segments at the corner
+            let side = if turn > 0.0 { -1.0 } else { 1.0 };
+            let o0 = Vec2::new(-d0.y, d0.x) * (half_width * side);
+            let o1 = Vec2::new(-d1.y, d1.x) * (half_width * side);
+
+            let bisector = o0 + o1;
+            let cos = if bisector.length() > f32::EPSILON {
+                bisector.normalized().dot_product(&(o0 / half_width))
+            } else {
+                0.0
+            };
+
+            if join == LineJoin::Miter && cos > 1.0 / MITER_LIMIT {
+                let miter = p + bisector.normalized() * (half_width / cos);
+                self.push(Shape::triangle(p, p + o0, miter, color), None);
+                self.push(Shape::triangle(p, miter, p + o1, color), None);
+            } else {
+                self.push(Shape::triangle(p, p + o0, p + o1, color), None);
+            }
+        }
+    }
+
+    ///Draws the text with the top left corner at the `position` and the font size of `size`
+    ///pixels
+    pub fn text(&mut self, font: &SdfFont, text: &str, position: Vec2, size: f32, color: Color) {
+        for (top_left, glyph_size, glyph) in font.layout(text, position, size) {
+            let half = glyph_size / 2.0;
+            self.push(
+                Shape {
+                    rect: [top_left.x + half.x, top_left.y + half.y, half.x, half.y],
+                    params: [1.0, 0.0, 0.0, GLYPH],
+                    color: [color.r, color.g, color.b, color.a],
+                    uv: [glyph.uv.0.x, glyph.uv.0.y, glyph.uv.1.x, glyph.uv.1.y],
+                },
+                Some(font.atlas),
+            );
+        }
+    }
+
+    fn create_state() -> CanvasState {
+        let device = DEVICE.get().unwrap();
+
+        let shader = shader::compile("canvas.wgsl", include_str!("../../shaders/canvas.wgsl"))
+            .expect("Failed to compile the canvas shader");
+
+        let globals_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Canvas globals bind group layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let atlas_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Canvas atlas bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Canvas pipeline layout"),
+            bind_group_layouts: &[&globals_layout, &atlas_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = crate::errors::scoped("Canvas pipeline", || {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Canvas pipeline"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vertex",
+                    buffers: &[wgpu::VertexBufferLayout {
+                        array_stride: mem::size_of::<Shape>() as u64,
+                        step_mode: wgpu::VertexStepMode::Instance,
+                        attributes: &wgpu::vertex_attr_array![
+                            0 => Float32x4,
+                            1 => Float32x4,
+                            2 => Float32x4,
+                            3 => Float32x4
+                        ],
+                    }],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: msaa::multisample_state(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fragment",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: hdr::color_format(),
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                }),
+                multiview: None,
+            })
+        });
+
+        let globals = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Canvas globals"),
+            size: mem::size_of::<[f32; 4]>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let globals_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Canvas globals bind group"),
+            layout: &globals_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: globals.as_entire_binding(),
+            }],
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Canvas sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let blank = device.create_texture_with_data(
+            QUEUE.get().unwrap(),
+            &wgpu::TextureDescriptor {
+                label: Some("Canvas blank atlas"),
+                size: wgpu::Extent3d {
+                    width: 1,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &[255; 4],
+        );
+        let blank = atlas_bind_group(
+            &atlas_layout,
+            &sampler,
+            &blank.create_view(&wgpu::TextureViewDescriptor::default()),
+        );
+
+        CanvasState {
+            pipeline,
+            targets: rendering::color_targets(),
+            globals,
+            globals_bind_group,
+            atlas_layout,
+            sampler,
+            blank,
+            atlases: HashMap::new(),
+            buffer: None,
+        }
+    }
+}
+
+///Creates a view of the atlas of a font, which is either a [`Texture`] or a [`Font`] asset
+pub(super) fn atlas_view(assets: &AssetStore, id: UUID) -> Option<wgpu::TextureView> {
+    let view = wgpu::TextureViewDescriptor::default();
+    if let Ok(texture) = assets.get_by_id::<Texture>(id) {
+        return texture
+            .borrow()
+            .texture
+            .as_ref()
+            .map(|t| t.create_view(&view));
+    }
+    let font = assets.get_by_id::<Font>(id).ok()?;
+    let font = font.borrow();
+    font.texture.as_ref().map(|t| t.create_view(&view))
+}
+
+pub(super) fn atlas_bind_group(
+    layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    view: &wgpu::TextureView,
+) -> wgpu::BindGroup {
+    DEVICE
+        .get()
+        .unwrap()
+        .create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Canvas atlas bind group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        })
+}
+
+impl RenderingExtension for Canvas {
+    fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        _: &World,
+        assets: &AssetStore,
+        attachments: &AttachmentData,
+    ) {
+        let viewport = viewport::current();
+        if self.shapes.is_empty() || viewport.width <= 0.0 || viewport.height <= 0.0 {
+            self.clear();
+            return;
+        }
+
+        if self
+            .state
+            .as_ref()
+            .is_none_or(|s| s.targets != rendering::color_targets())
+        {
+            self.state = Some(Self::create_state());
+        }
+        let state = self.state.as_mut().unwrap();
+        let device = DEVICE.get().unwrap();
+        let queue = QUEUE.get().unwrap();
+
+        queue.write_buffer(
+            &state.globals,
+            0,
+            bytemuck::bytes_of(&[viewport.width, viewport.height, 0.0, 0.0]),
+        );
+
+        let size = (mem::size_of::<Shape>() * self.shapes.len()) as u64;
+        if state.buffer.as_ref().is_none_or(|b| b.size() < size) {
+            state.buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Canvas shapes"),
+                size: size.next_power_of_two(),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+        let buffer = state.buffer.as_ref().unwrap();
+        queue.write_buffer(buffer, 0, bytemuck::cast_slice(&self.shapes));
+
+        for id in self.batches.iter().filter_map(|b| b.0) {
+            if state.atlases.contains_key(&id) {
+                continue;
+            }
+            let Some(view) = atlas_view(assets, id) else {
+                log::error!("Canvas atlas {id} does not exist");
+                continue;
+            };
+            state.atlases.insert(
+                id,
+                atlas_bind_group(&state.atlas_layout, &state.sampler, &view),
+            );
+        }
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Canvas pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &attachments.color,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        viewport::apply(&mut render_pass);
+        render_pass.set_pipeline(&state.pipeline);
+        render_pass.set_bind_group(0, &state.globals_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, buffer.slice(..size));
+
+        for (atlas, range) in &self.batches {
+            let bind_group = match atlas {
+                Some(id) => match state.atlases.get(id) {
+                    Some(b) => b,
+                    None => continue,
+                },
+                None => &state.blank,
+            };
+            render_pass.set_bind_group(1, bind_group, &[]);
+            render_pass.draw(0..6, range.clone());
+        }
+        drop(render_pass);
+
+        self.clear();
+    }
+
+    fn get_priority(&self) -> u32 {
+        self.priority
+    }
+
+    fn get_attachments(&self) -> &'static [&'static str] {
+        &["color"]
+    }
+}
@@ -0,0 +1,325 @@
+//! Clustered forward lighting
+//!
+//! The view of the main camera is split into a grid of [`CLUSTERS`] clusters, tiles on the screen
+//! that are further split into slices along the depth, distributed exponentially between the near
+//! plane and [`LightCulling::max_distance`]. The [`LightCulling`] extension assigns every point and
+//! spot light to the clusters its range overlaps, so the [`crate::assets::materials::Lit`]
+//! material only shades the few lights affecting each fragment, which makes scenes with dozens of
+//! lights practical.
+//!
+//! The assignment is done on the cpu every frame, the extension doesn't render anything.
+use crate::{
+    asset_managment::AssetStore,
+    components::{
+        camera::MainCamera,
+        light::{PointLight, SpotLight},
+    },
+    ecs::World,
+    math::{Mat4x4, Vec3, Vec4, Vector},
+    rendering::lighting::{
+        self, ClusterParams, LocalLightData, CLUSTERS, CLUSTER_COUNT, MAX_LIGHTS_PER_CLUSTER,
+    },
+};
+
+use super::{AttachmentData, RenderingExtension};
+
+///Camera the lights are assigned to the clusters of
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClusterCamera {
+    ///View projection matrix of the camera
+    pub view_projection: Mat4x4,
+    ///Position of the camera
+    pub position: Vec3,
+    ///Forward direction of the camera
+    pub forward: Vec3,
+    ///Distance to the first slice
+    pub near: f32,
+    ///Distance to the end of the last slice
+    pub far: f32,
+}
+
+///Statistics of a single frame processed by [`LightCulling`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LightCullingStats {
+    ///Number of point and spot lights uploaded to the gpu
+    pub lights: usize,
+    ///Number of lights that overlap at least one cluster
+    pub visible: usize,
+    ///Number of lights in all the clusters combined
+    pub assignments: usize,
+    ///Number of lights that were dropped, because they didn't fit into the buffers or into a
+    ///cluster, see [`lighting::max_lights`] and [`MAX_LIGHTS_PER_CLUSTER`]
+    pub dropped: usize,
+}
+
+///Assigns the point and spot lights to the clusters of the main camera
+///
+///Writes the `lights` attachment, which is read by the [`super::Base`] extensions, so it is always
+///processed before them
+pub struct LightCulling {
+    ///Priority of the extension
+    pub priority: u32,
+    ///Distance from the camera after which point and spot lights are not rendered, used instead of
+    ///the far plane of the camera if it is closer
+    pub max_distance: f32,
+    stats: LightCullingStats,
+    //Scratch buffer, kept between frames to avoid allocating it every frame
+    lights: Vec<LocalLightData>,
+}
+
+impl Default for LightCulling {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl LightCulling {
+    ///Creates a new light culling extension, with the maximum distance of 100
+    #[must_use]
+    pub const fn new(priority: u32) -> Self {
+        Self {
+            priority,
+            max_distance: 100.0,
+            stats: LightCullingStats {
+                lights: 0,
+                visible: 0,
+                assignments: 0,
+                dropped: 0,
+            },
+            lights: Vec::new(),
+        }
+    }
+
+    ///Returns the statistics of the last processed frame
+    #[must_use]
+    pub const fn get_stats(&self) -> LightCullingStats {
+        self.stats
+    }
+
+    ///Collects the point and spot lights of the world
+    fn collect(&mut self, world: &World) {
+        self.lights.clear();
+
+        for l in world.get_all_components::<PointLight>().unwrap_or_default() {
+            let l = l.borrow();
+            let p = l.position();
+            self.lights.push(LocalLightData {
+                position: [p.x, p.y, p.z, l.range],
+                color: [
+                    l.color.r * l.intensity,
+                    l.color.g * l.intensity,
+                    l.color.b * l.intensity,
+                    0.0,
+                ],
+                direction: [0.0; 4],
+                cone: [0.0; 4],
+            });
+        }
+
+        for l in world.get_all_components::<SpotLight>().unwrap_or_default() {
+            let l = l.borrow();
+            let p = l.position();
+            let d = l.direction();
+            self.lights.push(LocalLightData {
+                position: [p.x, p.y, p.z, l.range],
+                color: [
+                    l.color.r * l.intensity,
+                    l.color.g * l.intensity,
+                    l.color.b * l.intensity,
+                    1.0,
+                ],
+                direction: [d.x, d.y, d.z, l.outer_angle.to_radians().cos()],
+                cone: [l.inner_angle.to_radians().cos(), 0.0, 0.0, 0.0],
+            });
+        }
+    }
+}
+
+///Returns the slice containing the `depth`
+fn slice(depth: f32, near: f32, far: f32) -> u32 {
+    let slice = (depth.max(near) / near).ln() / (far / near).ln() * CLUSTERS[2] as f32;
+    (slice.max(0.0) as u32).min(CLUSTERS[2] - 1)
+}
+
+///Assigns the lights, spheres given as a position and a range, to the clusters of the `camera`
+///
+///Returns the clusters, packed as used by the shaders, and the indices of the lights in every
+///cluster. At most [`MAX_LIGHTS_PER_CLUSTER`] lights are assigned to a single cluster and at most
+///`max_indices` lights to all clusters combined, the other assignments are dropped and counted in
+///the returned stats
+#[must_use]
+pub fn assign_lights(
+    camera: &ClusterCamera,
+    lights: &[(Vec3, f32)],
+    max_indices: usize,
+) -> (Vec<u32>, Vec<u32>, LightCullingStats) {
+    let near = camera.near.max(0.01);
+    let far = camera.far.max(near * 1.01);
+    //Transposed for transforming column vectors
+    let view_projection = camera.view_projection.transpose();
+
+    let mut stats = LightCullingStats::default();
+    let mut cluster_lights = vec![Vec::new(); CLUSTER_COUNT];
+
+    for (index, &(position, range)) in lights.iter().enumerate() {
+        let depth = (position - camera.position).dot_product(&camera.forward);
+        if depth + range < near || depth - range > far {
+            continue;
+        }
+        stats.visible += 1;
+
+        let slices = slice(depth - range, near, far)..=slice(depth + range, near, far);
+
+        //Bounds of the light on the screen, from the corners of the box around the sphere, all
+        //of them need to be in front of the camera
+        let (mut min, mut max) = ((0, 0), (CLUSTERS[0] - 1, CLUSTERS[1] - 1));
+        if depth - range * 3f32.sqrt() > near {
+            let (mut low, mut high) = (
+                (f32::INFINITY, f32::INFINITY),
+                (f32::NEG_INFINITY, f32::NEG_INFINITY),
+            );
+            for corner in 0..8 {
+                let offset = Vec3::new(
+                    if corner & 1 == 0 { -range } else { range },
+                    if corner & 2 == 0 { -range } else { range },
+                    if corner & 4 == 0 { -range } else { range },
+                );
+                let p = position + offset;
+                let clip = view_projection.transform(Vec4::new(p.x, p.y, p.z, 1.0));
+                let (x, y) = (clip.x / clip.w, clip.y / clip.w);
+                low = (low.0.min(x), low.1.min(y));
+                high = (high.0.max(x), high.1.max(y));
+            }
+
+            if high.0 < -1.0 || high.1 < -1.0 || low.0 > 1.0 || low.1 > 1.0 {
+                stats.visible -= 1;
+                continue;
+            }
+
+            let tile = |v: f32, count: u32| {
+                (((v * 0.5 + 0.5) * count as f32).max(0.0) as u32).min(count - 1)
+            };
+            min = (tile(low.0, CLUSTERS[0]), tile(low.1, CLUSTERS[1]));
+            max = (tile(high.0, CLUSTERS[0]), tile(high.1, CLUSTERS[1]));
+        }
+
+        for z in slices {
+            for y in min.1..=max.1 {
+                for x in min.0..=max.0 {
+                    let cluster = ((z * CLUSTERS[1] + y) * CLUSTERS[0] + x) as usize;
+                    cluster_lights[cluster].push(index as u32);
+                }
+            }
+        }
+    }
+
+    let mut clusters = Vec::with_capacity(CLUSTER_COUNT);
+    let mut indices = Vec::new();
+    for lights in cluster_lights {
+        let count = lights
+            .len()
+            .min(MAX_LIGHTS_PER_CLUSTER)
+            .min(max_indices - indices.len());
+        stats.dropped += lights.len() - count;
+
+        clusters.push(((indices.len() as u32) << 8) | count as u32);
+        indices.extend_from_slice(&lights[..count]);
+    }
+    stats.assignments = indices.len();
+
+    (clusters, indices, stats)
+}
+
+impl RenderingExtension for LightCulling {
+    fn render(
+        &mut self,
+        _: &mut wgpu::CommandEncoder,
+        world: &World,
+        _: &AssetStore,
+        _: &AttachmentData,
+    ) {
+        let Some(camera) = world
+            .get_all_components::<MainCamera>()
+            .and_then(|c| c.first().cloned())
+        else {
+            lighting::set_clusters(
+                &[],
+                &[0; CLUSTER_COUNT],
+                &[],
+                ClusterParams {
+                    view_projection: Mat4x4::identity(),
+                    camera_position: [0.0; 4],
+                    camera_forward: [0.0; 4],
+                    grid: [0; 4],
+                },
+            );
+            self.stats = LightCullingStats::default();
+            return;
+        };
+        let camera = camera.borrow();
+
+        let transform = camera.camera_transform();
+        let camera = ClusterCamera {
+            view_projection: camera.matrix(),
+            position: Vec3::new(transform.m03, transform.m13, transform.m23),
+            forward: (transform * Vec4::new(0.0, 0.0, 1.0, 0.0))
+                .xyz()
+                .normalized(),
+            near: camera.near.max(0.01),
+            far: camera.far.min(self.max_distance),
+        };
+
+        self.collect(world);
+        let (max_lights, max_indices) = lighting::max_lights();
+        let dropped = self.lights.len().saturating_sub(max_lights);
+        self.lights.truncate(max_lights);
+
+        let spheres = self
+            .lights
+            .iter()
+            .map(|l| {
+                (
+                    Vec3::new(l.position[0], l.position[1], l.position[2]),
+                    l.position[3],
+                )
+            })
+            .collect::<Vec<_>>();
+        let (clusters, indices, stats) = assign_lights(&camera, &spheres, max_indices);
+
+        lighting::set_clusters(
+            &self.lights,
+            &clusters,
+            &indices,
+            ClusterParams {
+                view_projection: camera.view_projection,
+                camera_position: [
+                    camera.position.x,
+                    camera.position.y,
+                    camera.position.z,
+                    camera.near,
+                ],
+                camera_forward: [
+                    camera.forward.x,
+                    camera.forward.y,
+                    camera.forward.z,
+                    camera.far.max(camera.near * 1.01),
+                ],
+                grid: [CLUSTERS[0], CLUSTERS[1], CLUSTERS[2], 0],
+            },
+        );
+
+        self.stats = LightCullingStats {
+            lights: self.lights.len(),
+            dropped: stats.dropped + dropped,
+            ..stats
+        };
+    }
+
+    fn get_priority(&self) -> u32 {
+        self.priority
+    }
+
+    fn get_attachments(&self) -> &'static [&'static str] {
+        &["lights"]
+    }
+}
@@ -0,0 +1,205 @@
+//! Rendering of the lines drawn with [`crate::rendering::debug`]
+use std::mem;
+
+use crate::{
+    asset_managment::AssetStore,
+    assets::shader,
+    components::camera::MainCamera,
+    ecs::World,
+    grimoire::CAMERA_BIND_GROUP_LAYOUT_DESCRIPTOR,
+    rendering::{self, debug, depth, hdr, msaa, viewport},
+    DEVICE, QUEUE,
+};
+
+use super::{AttachmentData, PassOps, RenderingExtension};
+
+struct DebugLinesState {
+    pipeline: wgpu::RenderPipeline,
+    //Color attachment, the depth range and the depth test the pipeline was created for
+    targets: ((u32, wgpu::TextureFormat), bool, bool),
+    buffer: Option<wgpu::Buffer>,
+}
+
+///Renders the lines drawn with the functions of [`crate::rendering::debug`] during the frame
+///
+///The lines are tested against the depth attachment, so the extension should be rendered after
+///the extensions rendering the geometry.
+pub struct DebugLines {
+    ///Priority of the extension
+    pub priority: u32,
+    ///What the pass does with the color and depth attachments
+    pub ops: PassOps,
+    ///Whether the lines are hidden by the geometry, if `false` they are drawn on top of it
+    pub depth_test: bool,
+    state: Option<DebugLinesState>,
+}
+
+impl DebugLines {
+    ///Creates a new debug line renderer
+    #[must_use]
+    pub const fn new(priority: u32) -> Self {
+        Self {
+            priority,
+            ops: PassOps::new(),
+            depth_test: true,
+            state: None,
+        }
+    }
+
+    fn create_state(depth_test: bool) -> DebugLinesState {
+        let device = DEVICE.get().unwrap();
+
+        let shader = shader::compile(
+            "debug_lines.wgsl",
+            include_str!("../../shaders/debug_lines.wgsl"),
+        )
+        .expect("Failed to compile the debug lines shader");
+
+        let camera_layout = device.create_bind_group_layout(&CAMERA_BIND_GROUP_LAYOUT_DESCRIPTOR);
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Debug lines pipeline layout"),
+            bind_group_layouts: &[&camera_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = crate::errors::scoped("Debug lines pipeline", || {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Debug lines pipeline"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vertex",
+                    buffers: &[wgpu::VertexBufferLayout {
+                        array_stride: mem::size_of::<debug::Vertex>() as u64,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &wgpu::vertex_attr_array![
+                            0 => Float32x3,
+                            1 => Float32x4
+                        ],
+                    }],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::LineList,
+                    ..Default::default()
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: depth::format(),
+                    depth_write_enabled: false,
+                    depth_compare: if depth_test {
+                        depth::compare_function()
+                    } else {
+                        wgpu::CompareFunction::Always
+                    },
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: msaa::multisample_state(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fragment",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: hdr::color_format(),
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                }),
+                multiview: None,
+            })
+        });
+
+        DebugLinesState {
+            pipeline,
+            targets: (rendering::color_targets(), depth::is_reversed(), depth_test),
+            buffer: None,
+        }
+    }
+}
+
+impl RenderingExtension for DebugLines {
+    fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        world: &World,
+        _: &AssetStore,
+        attachments: &AttachmentData,
+    ) {
+        let vertices = debug::vertices();
+        if vertices.is_empty() {
+            return;
+        }
+        let Some(camera) = world
+            .get_all_components::<MainCamera>()
+            .and_then(|c| c.first().cloned())
+        else {
+            return;
+        };
+        let camera = camera.borrow();
+
+        let targets = (
+            rendering::color_targets(),
+            depth::is_reversed(),
+            self.depth_test,
+        );
+        if self.state.as_ref().is_none_or(|s| s.targets != targets) {
+            self.state = Some(Self::create_state(self.depth_test));
+        }
+        let state = self.state.as_mut().unwrap();
+
+        let size = mem::size_of_val(vertices.as_slice()) as u64;
+        if state.buffer.as_ref().is_none_or(|b| b.size() < size) {
+            state.buffer = Some(
+                DEVICE
+                    .get()
+                    .unwrap()
+                    .create_buffer(&wgpu::BufferDescriptor {
+                        label: Some("Debug lines vertices"),
+                        size: size.next_power_of_two(),
+                        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                        mapped_at_creation: false,
+                    }),
+            );
+        }
+        let buffer = state.buffer.as_ref().unwrap();
+        QUEUE
+            .get()
+            .unwrap()
+            .write_buffer(buffer, 0, bytemuck::cast_slice(&vertices));
+
+        camera.update_gpu(encoder);
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Debug lines pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &attachments.color,
+                resolve_target: None,
+                ops: self.ops.color_ops(attachments, wgpu::Color::BLACK),
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &attachments.depth_stencil,
+                depth_ops: Some(self.ops.depth_ops(attachments)),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        viewport::apply(&mut render_pass);
+        render_pass.set_pipeline(&state.pipeline);
+        camera.set_bindgroup(&mut render_pass);
+        render_pass.set_vertex_buffer(0, buffer.slice(..size));
+        render_pass.draw(0..vertices.len() as u32, 0..1);
+    }
+
+    fn get_priority(&self) -> u32 {
+        self.priority
+    }
+
+    fn get_attachments(&self) -> &'static [&'static str] {
+        &["color"]
+    }
+
+    fn get_reads(&self) -> &'static [&'static str] {
+        &["depth_stencil"]
+    }
+}
@@ -0,0 +1,311 @@
+//! Debug visualisations of the geometry of the scene
+use std::mem;
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::{
+    asset_managment::AssetStore,
+    assets::{materials::helpers::vertex_binding, shader},
+    components::{self, camera::MainCamera},
+    ecs::World,
+    grimoire::CAMERA_BIND_GROUP_LAYOUT_DESCRIPTOR,
+    math::Vec3,
+    rendering::{self, depth, hdr, msaa, viewport},
+    structures::Color,
+    DEVICE, QUEUE,
+};
+
+use super::{scene::SceneDraws, AttachmentData, PassOps, RenderingExtension};
+
+//Distance shown as white in the depth mode when the far plane of the camera is infinite
+const INFINITE_DEPTH_RANGE: f32 = 1000.0;
+
+///What the [`DebugView`] extension shows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RenderMode {
+    ///Edges of the triangles, requires [`wgpu::Features::POLYGON_MODE_LINE`], see
+    ///[`crate::rendering::capabilities::Capabilities::wireframe`]. The triangles are filled if it's not supported
+    #[default]
+    Wireframe,
+    ///World space normals, mapped from -1..1 to 0..1
+    Normals,
+    ///Distance from the camera, from black at the near plane to white at the far plane
+    Depth,
+    ///Number of triangles covering each pixel, brighter pixels are drawn more times
+    Overdraw,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Parameters {
+    color: [f32; 4],
+    //Position of the camera, near plane
+    eye: [f32; 4],
+    //Far plane, padding
+    range: [f32; 4],
+}
+
+struct DebugViewState {
+    pipeline: wgpu::RenderPipeline,
+    //Color attachment, the depth range and the mode the pipeline was created for
+    targets: ((u32, wgpu::TextureFormat), bool, RenderMode),
+    uniform: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    scene: SceneDraws,
+}
+
+///Renders the meshes of the world with one of the [`RenderMode`] visualisations, for debugging
+///the geometry
+///
+///The mode can be changed at any time. The wireframe is tested against the depth attachment, so
+///when the extension is rendered after [`super::Base`] it's drawn over the visible surfaces. The
+///other modes are meant to be rendered instead of [`super::Base`], or after it with
+///[`LoadBehavior::Clear`](super::LoadBehavior::Clear) ops. Skinned meshes are shown in their bind
+///pose.
+pub struct DebugView {
+    ///Priority of the extension
+    pub priority: u32,
+    ///What is rendered
+    pub mode: RenderMode,
+    ///Color of the lines in the [`RenderMode::Wireframe`] mode
+    pub wireframe_color: Color,
+    ///Color the attachment is cleared with, if the pass clears it
+    pub clear_color: Color,
+    ///What the pass does with the color and depth attachments
+    pub ops: PassOps,
+    ///Bit mask of the render layers rendered by the extension, see
+    ///[`components::mesh::Mesh::set_layers`]
+    pub layers: u32,
+    state: Option<DebugViewState>,
+}
+
+impl DebugView {
+    ///Creates a new debug view with the given mode
+    #[must_use]
+    pub const fn new(priority: u32, mode: RenderMode) -> Self {
+        Self {
+            priority,
+            mode,
+            wireframe_color: Color::rgb(1.0, 1.0, 1.0),
+            clear_color: Color::rgb(0.0, 0.0, 0.0),
+            ops: PassOps::new(),
+            layers: components::mesh::ALL_LAYERS,
+            state: None,
+        }
+    }
+
+    fn create_state(mode: RenderMode) -> DebugViewState {
+        let device = DEVICE.get().unwrap();
+
+        let shader = shader::compile(
+            "debug_view.wgsl",
+            include_str!("../../shaders/debug_view.wgsl"),
+        )
+        .expect("Failed to compile the debug view shader");
+
+        let camera_layout = device.create_bind_group_layout(&CAMERA_BIND_GROUP_LAYOUT_DESCRIPTOR);
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Debug view bind group layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Debug view pipeline layout"),
+            bind_group_layouts: &[&camera_layout, &layout],
+            push_constant_ranges: &[],
+        });
+
+        let wireframe = device
+            .features()
+            .contains(wgpu::Features::POLYGON_MODE_LINE);
+        if mode == RenderMode::Wireframe && !wireframe {
+            log::warn!("Wireframe rendering is not supported by the gpu, drawing filled triangles");
+        }
+
+        let (entry_point, polygon_mode, depth_write_enabled, depth_compare, blend) = match mode {
+            RenderMode::Wireframe => (
+                "wireframe",
+                if wireframe {
+                    wgpu::PolygonMode::Line
+                } else {
+                    wgpu::PolygonMode::Fill
+                },
+                false,
+                //Lines lie on the surfaces in the depth attachment
+                if depth::is_reversed() {
+                    wgpu::CompareFunction::GreaterEqual
+                } else {
+                    wgpu::CompareFunction::LessEqual
+                },
+                None,
+            ),
+            RenderMode::Normals => (
+                "normals",
+                wgpu::PolygonMode::Fill,
+                true,
+                depth::compare_function(),
+                None,
+            ),
+            RenderMode::Depth => (
+                "depth",
+                wgpu::PolygonMode::Fill,
+                true,
+                depth::compare_function(),
+                None,
+            ),
+            RenderMode::Overdraw => (
+                "overdraw",
+                wgpu::PolygonMode::Fill,
+                false,
+                wgpu::CompareFunction::Always,
+                Some(wgpu::BlendState {
+                    color: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::One,
+                        dst_factor: wgpu::BlendFactor::One,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                    alpha: wgpu::BlendComponent::REPLACE,
+                }),
+            ),
+        };
+
+        let pipeline = crate::errors::scoped("Debug view pipeline", || {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Debug view pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vertex",
+                    buffers: &vertex_binding(),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                primitive: wgpu::PrimitiveState {
+                    polygon_mode,
+                    ..Default::default()
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: depth::format(),
+                    depth_write_enabled,
+                    depth_compare,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: msaa::multisample_state(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point,
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: hdr::color_format(),
+                        blend,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                }),
+                multiview: None,
+            })
+        });
+
+        let uniform = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Debug view parameters"),
+            size: mem::size_of::<Parameters>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Debug view bind group"),
+            layout: &layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform.as_entire_binding(),
+            }],
+        });
+
+        DebugViewState {
+            pipeline,
+            targets: (rendering::color_targets(), depth::is_reversed(), mode),
+            uniform,
+            bind_group,
+            scene: SceneDraws::new("Debug view instances"),
+        }
+    }
+}
+
+impl RenderingExtension for DebugView {
+    fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        world: &World,
+        assets: &AssetStore,
+        attachments: &AttachmentData,
+    ) {
+        let Some(camera) = world
+            .get_all_components::<MainCamera>()
+            .and_then(|c| c.first().cloned())
+        else {
+            return;
+        };
+        let camera = camera.borrow();
+
+        let targets = (rendering::color_targets(), depth::is_reversed(), self.mode);
+        if self.state.as_ref().is_none_or(|s| s.targets != targets) {
+            self.state = Some(Self::create_state(self.mode));
+        }
+        let state = self.state.as_mut().unwrap();
+
+        let eye = camera
+            .camera_transform()
+            .transform3(Vec3::new(0.0, 0.0, 0.0));
+        let far = if camera.far.is_finite() {
+            camera.far
+        } else {
+            INFINITE_DEPTH_RANGE
+        };
+        let color = self.wireframe_color;
+        QUEUE.get().unwrap().write_buffer(
+            &state.uniform,
+            0,
+            bytemuck::bytes_of(&Parameters {
+                color: [color.r, color.g, color.b, color.a],
+                eye: [eye.x, eye.y, eye.z, camera.near],
+                range: [far, 0.0, 0.0, 0.0],
+            }),
+        );
+        state.scene.prepare(world, assets, self.layers);
+
+        camera.update_gpu(encoder);
+
+        let clear_color = camera.background.clear_color(self.clear_color);
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Debug view pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &attachments.color,
+                resolve_target: None,
+                ops: self.ops.color_ops(attachments, clear_color.into()),
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &attachments.depth_stencil,
+                depth_ops: Some(self.ops.depth_ops(attachments)),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        viewport::apply(&mut render_pass);
+        render_pass.set_pipeline(&state.pipeline);
+        camera.set_bindgroup(&mut render_pass);
+        render_pass.set_bind_group(1, &state.bind_group, &[]);
+        state.scene.draw_geometry(&mut render_pass, assets);
+    }
+
+    fn get_priority(&self) -> u32 {
+        self.priority
+    }
+}
@@ -0,0 +1,335 @@
+//! Overlay showing the recent frame times
+//!
+//! Every frame is drawn as a bar, green for frames within the 60 fps budget, yellow for frames
+//! within the 30 fps budget and red for the slower ones. A white line marks the 60 fps budget.
+//!
+//! When the extensions are [profiled](rendering::profiling), the gpu times of the extensions from
+//! [`rendering::frame_gpu_timings`] are drawn next to the bar of every frame, stacked in the order
+//! the extensions ran. The gpu times lag a few frames behind the cpu times.
+use std::{collections::VecDeque, mem, time::Duration};
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::{
+    asset_managment::AssetStore,
+    assets::shader,
+    ecs::World,
+    rendering::{self, hdr, msaa},
+    time, DEVICE, QUEUE, RESOLUTION,
+};
+
+use super::{AttachmentData, RenderingExtension};
+
+//Frame budgets
+const TARGET: Duration = Duration::from_nanos(16_666_667);
+const SLOW: Duration = Duration::from_nanos(33_333_333);
+
+const BACKGROUND: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const GOOD: [f32; 4] = [0.2, 0.8, 0.2, 1.0];
+const WARNING: [f32; 4] = [0.9, 0.8, 0.1, 1.0];
+const BAD: [f32; 4] = [0.9, 0.2, 0.1, 1.0];
+const LINE: [f32; 4] = [1.0, 1.0, 1.0, 0.8];
+//Alternating colors of the extensions in the gpu bars
+const GPU: [[f32; 4]; 3] = [
+    [0.2, 0.5, 0.9, 1.0],
+    [0.5, 0.3, 0.9, 1.0],
+    [0.2, 0.8, 0.9, 1.0],
+];
+
+//Distance from the edges of the screen in pixels
+const MARGIN: f32 = 8.0;
+
+///Corner of the screen
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Corner {
+    ///Top left corner
+    #[default]
+    TopLeft,
+    ///Top right corner
+    TopRight,
+    ///Bottom left corner
+    BottomLeft,
+    ///Bottom right corner
+    BottomRight,
+}
+
+//A single rectangle of the graph
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Bar {
+    //min x, min y, max x, max y in clip space
+    rect: [f32; 4],
+    color: [f32; 4],
+}
+
+///Draws a bar graph of the last frame times on top of the rendered image
+///
+///Should have the highest priority, so that it is rendered after everything else
+pub struct FrameTimeOverlay {
+    ///Priority of the extension
+    pub priority: u32,
+    ///Whether or not the graph is shown, the frame times are recorded even when it is hidden
+    pub enabled: bool,
+    ///Corner of the screen the graph is placed in
+    pub corner: Corner,
+    ///Width and height of the graph in pixels
+    pub size: (f32, f32),
+    ///Frame time corresponding to the full height of the graph
+    pub max_time: Duration,
+    samples: VecDeque<Duration>,
+    //Gpu times of the extensions of every frame, empty if they were not available
+    gpu_samples: VecDeque<Vec<Duration>>,
+    capacity: usize,
+    //Pipeline and the color attachment it was created for
+    pipeline: Option<(wgpu::RenderPipeline, (u32, wgpu::TextureFormat))>,
+    buffer: Option<wgpu::Buffer>,
+    bars: Vec<Bar>,
+}
+
+impl Default for FrameTimeOverlay {
+    fn default() -> Self {
+        Self::new(u32::MAX, 120)
+    }
+}
+
+impl FrameTimeOverlay {
+    ///Creates a new overlay showing the last `frames` frame times
+    #[must_use]
+    pub fn new(priority: u32, frames: usize) -> Self {
+        Self {
+            priority,
+            enabled: true,
+            corner: Corner::TopLeft,
+            size: (240.0, 80.0),
+            max_time: SLOW + TARGET,
+            samples: VecDeque::with_capacity(frames),
+            gpu_samples: VecDeque::with_capacity(frames),
+            capacity: frames.max(1),
+            pipeline: None,
+            buffer: None,
+            bars: Vec::new(),
+        }
+    }
+
+    ///Shows the graph if it is hidden, hides it otherwise
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    ///Returns the recorded frame times, from the oldest to the newest
+    pub fn frame_times(&self) -> impl Iterator<Item = Duration> + '_ {
+        self.samples.iter().copied()
+    }
+
+    fn create_pipeline() -> wgpu::RenderPipeline {
+        let device = DEVICE.get().unwrap();
+
+        let shader = shader::compile(
+            "frame_time.wgsl",
+            include_str!("../../shaders/frame_time.wgsl"),
+        )
+        .expect("Failed to compile the frame time shader");
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Frame time pipeline layout"),
+            bind_group_layouts: &[],
+            push_constant_ranges: &[],
+        });
+
+        crate::errors::scoped("Frame time pipeline", || {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Frame time pipeline"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vertex",
+                    buffers: &[wgpu::VertexBufferLayout {
+                        array_stride: mem::size_of::<Bar>() as u64,
+                        step_mode: wgpu::VertexStepMode::Instance,
+                        attributes: &wgpu::vertex_attr_array![0 => Float32x4, 1 => Float32x4],
+                    }],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: msaa::multisample_state(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fragment",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: hdr::color_format(),
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                }),
+                multiview: None,
+            })
+        })
+    }
+
+    ///Creates the rectangles of the graph in clip space
+    fn build_bars(&mut self, width: f32, height: f32) {
+        //Pixels to clip space
+        let x = |px: f32| px / width * 2.0 - 1.0;
+        let y = |px: f32| 1.0 - px / height * 2.0;
+
+        let (w, h) = self.size;
+        let left = match self.corner {
+            Corner::TopLeft | Corner::BottomLeft => MARGIN,
+            Corner::TopRight | Corner::BottomRight => width - MARGIN - w,
+        };
+        let top = match self.corner {
+            Corner::TopLeft | Corner::TopRight => MARGIN,
+            Corner::BottomLeft | Corner::BottomRight => height - MARGIN - h,
+        };
+        let bottom = top + h;
+
+        self.bars.clear();
+        self.bars.push(Bar {
+            rect: [x(left), y(bottom), x(left + w), y(top)],
+            color: BACKGROUND,
+        });
+
+        let column_width = w / self.capacity as f32;
+        //The gpu times take the right half of the columns
+        let profiled = self.gpu_samples.iter().any(|t| !t.is_empty());
+        let bar_width = if profiled {
+            column_width / 2.0
+        } else {
+            column_width
+        };
+        let max = self.max_time.as_secs_f32();
+        //Height of the duration in pixels
+        let pixels = |t: Duration| (t.as_secs_f32() / max).min(1.0) * h;
+
+        for (i, (t, gpu)) in self.samples.iter().zip(&self.gpu_samples).enumerate() {
+            let bar_left = (i as f32).mul_add(column_width, left);
+
+            self.bars.push(Bar {
+                rect: [
+                    x(bar_left),
+                    y(bottom),
+                    x(bar_left + (bar_width - 1.0).max(1.0)),
+                    y(bottom - pixels(*t)),
+                ],
+                color: if *t <= TARGET {
+                    GOOD
+                } else if *t <= SLOW {
+                    WARNING
+                } else {
+                    BAD
+                },
+            });
+
+            let gpu_left = bar_left + bar_width;
+            let mut start = Duration::ZERO;
+            for (j, t) in gpu.iter().enumerate() {
+                let end = start + *t;
+                self.bars.push(Bar {
+                    rect: [
+                        x(gpu_left),
+                        y(bottom - pixels(start)),
+                        x(gpu_left + (bar_width - 1.0).max(1.0)),
+                        y(bottom - pixels(end)),
+                    ],
+                    color: GPU[j % GPU.len()],
+                });
+                start = end;
+            }
+        }
+
+        let line = bottom - pixels(TARGET);
+        self.bars.push(Bar {
+            rect: [x(left), y(line + 1.0), x(left + w), y(line)],
+            color: LINE,
+        });
+    }
+}
+
+impl RenderingExtension for FrameTimeOverlay {
+    fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        _: &World,
+        _: &AssetStore,
+        attachments: &AttachmentData,
+    ) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+            self.gpu_samples.pop_front();
+        }
+        self.samples.push_back(time::delta());
+        self.gpu_samples.push_back(
+            rendering::frame_gpu_timings()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|t| t.duration)
+                .collect(),
+        );
+
+        if !self.enabled {
+            return;
+        }
+
+        let resolution = *RESOLUTION.read().unwrap();
+        if resolution.width == 0 || resolution.height == 0 {
+            return;
+        }
+        self.build_bars(resolution.width as f32, resolution.height as f32);
+
+        let device = DEVICE.get().unwrap();
+        let size = (mem::size_of::<Bar>() * self.bars.len()) as u64;
+        if self.buffer.as_ref().is_none_or(|b| b.size() < size) {
+            //Room for all the bars, the background and the line, the number of the gpu bars
+            //depends on the number of the extensions
+            self.buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Frame time bars"),
+                size: size.max((mem::size_of::<Bar>() * (self.capacity + 2)) as u64),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+        let buffer = self.buffer.as_ref().unwrap();
+        QUEUE
+            .get()
+            .unwrap()
+            .write_buffer(buffer, 0, bytemuck::cast_slice(&self.bars));
+
+        if self
+            .pipeline
+            .as_ref()
+            .is_none_or(|p| p.1 != rendering::color_targets())
+        {
+            self.pipeline = Some((Self::create_pipeline(), rendering::color_targets()));
+        }
+        let pipeline = &self.pipeline.as_ref().unwrap().0;
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Frame time pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &attachments.color,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_vertex_buffer(0, buffer.slice(..size));
+        render_pass.draw(0..6, 0..self.bars.len() as u32);
+    }
+
+    fn get_priority(&self) -> u32 {
+        self.priority
+    }
+
+    fn get_attachments(&self) -> &'static [&'static str] {
+        &["color"]
+    }
+}
@@ -1,21 +1,74 @@
-use core::f32;
-use std::{mem, num::NonZeroU64, sync::Arc};
+use std::mem;
 
 use log::{debug, trace};
-use vec_key_value_pair::set::VecSet;
-use wgpu::util::DeviceExt;
 
 use crate::{
     asset_managment::AssetStore,
-    assets::{BindgroupState, Material, Mesh},
-    components,
+    assets::{material, mesh::geometry_id, BindgroupState, Material, Mesh},
+    components::{self, camera::ProjectionType},
     ecs::{ComponentReference, World},
-    math::{Mat4x4, Vec2, Vec3, Vec4, Vector},
-    structures::Color,
-    DEVICE, RESOLUTION, STAGING_BELT,
+    grimoire::FRAMES_IN_FLIGHT,
+    math::{
+        bvh::{Bvh, LeafId},
+        geometry::{Aabb, Frustum},
+        Vec3, Vector,
+    },
+    rendering::{
+        indirect::DrawCommands,
+        lighting,
+        occlusion::{self, OcclusionCulling},
+        viewport,
+    },
+    structures::{Color, InstanceData},
+    validation,
 };
 
-use super::{AttachmentData, RenderingExtension};
+use super::{
+    draw_commands, draw_groups, instance_buffers_init, static_groups, write_instances,
+    AttachmentData, MeshMaterial, PassOps, RenderingExtension, SkinnedDraws,
+};
+
+//Distance the bounds of the meshes are enlarged by in the bounding volume hierarchy, meshes moving
+//less than this don't change the hierarchy
+const BVH_MARGIN: f32 = 0.5;
+
+///Statistics of a single frame rendered by [`Base`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CullingStats {
+    ///Number of meshes in the world
+    pub total: usize,
+    ///Number of meshes that passed the culling
+    pub visible: usize,
+    ///Number of meshes that were culled, including the ones that are not visible
+    pub culled: usize,
+    ///Number of bounding boxes tested against the frustum, when traversing the bounding volume
+    ///hierarchy
+    pub frustum_tests: usize,
+    ///Number of instance groups, including the skinned meshes
+    pub groups: usize,
+    ///Number of draw calls, groups of the same mesh and material are drawn with a single call
+    ///where multi draws are supported
+    pub draw_calls: usize,
+    ///Whether or not the instance buffers were rebuilt this frame
+    pub rebuilt: bool,
+}
+
+///Event emitted by [`Base`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CullingEvent {
+    ///Emitted at the end of every frame
+    Frame(CullingStats),
+    ///Emitted when the set of visible meshes changed and the instance buffers had to be recreated
+    BuffersRebuilt {
+        ///Number of instance groups
+        groups: usize,
+        ///Number of instances in all the groups
+        instances: usize,
+    },
+}
+
+///Receives the events emitted by [`Base`]
+pub type EventSink = Box<dyn FnMut(&CullingEvent)>;
 
 ///Base but with frustum culling
 #[derive(Default)]
@@ -24,12 +77,64 @@ pub struct Base {
     pub priority: u32,
     ///Clear color used for rendering
     pub clear_color: Color,
+    ///What the pass does with the color and depth attachments
+    pub ops: PassOps,
+    ///Distance from the camera beyond which the meshes are culled, `None` disables the distance
+    ///culling, can be overridden per mesh with
+    ///[`Mesh::set_max_draw_distance`](components::mesh::Mesh::set_max_draw_distance)
+    pub max_draw_distance: Option<f32>,
+    ///Fraction of the height of the screen the bounding sphere of a mesh must cover to be
+    ///rendered, 0 disables the screen size culling, can be overridden per mesh with
+    ///[`Mesh::set_min_screen_size`](components::mesh::Mesh::set_min_screen_size)
+    pub min_screen_size: f32,
+    ///Whether or not the instances hidden behind the depth of the previous frame are culled on
+    ///the gpu, before they are drawn
+    ///
+    ///Meshes that become visible after being hidden are drawn a frame late. The culling is skipped
+    ///on the web, on the OpenGL backend, on gpus without compute shaders, when the depth
+    ///attachment can't be sampled or has a stencil, see [`depth::set_config`], and when the
+    ///extension renders at a different resolution than the frame buffer. The occluded instances
+    ///are not included in [`CullingStats`], as they are only known to the gpu.
+    ///
+    ///[`depth::set_config`]: crate::rendering::depth::set_config
+    pub occlusion_culling: bool,
+    stats: CullingStats,
+    sink: Option<EventSink>,
     //Stores vector of (mesh_id, material_id) for caching
     identifier: Vec<(u128, u128)>,
-    v_buffers: Vec<wgpu::Buffer>,
+    //Scratch buffers, kept between frames to avoid allocating them every frame
+    //Indices of the visible meshes
+    visible: Vec<usize>,
+    //(mesh_id, material_id) of the visible meshes in the current frame
+    ids: Vec<(u128, u128)>,
+    //Instance buffer shared by all the groups, one per frame in flight, so that the buffers the gpu
+    //is still reading from are never written to
+    instances: Option<[wgpu::Buffer; FRAMES_IN_FLIGHT]>,
+    //Index of the instance buffer used in the current frame
+    frame: usize,
+    //Draw commands of the groups
+    draws: DrawCommands,
     mesh_materials: Vec<MeshMaterial>,
     num_instances: Vec<usize>,
     mesh_refs: Vec<Vec<ComponentReference<components::mesh::Mesh>>>,
+    //Indices of the textures of the materials of the meshes in the shared texture array
+    texture_indices: Vec<Vec<u32>>,
+    //Whether or not all the meshes in the instance buffer are static
+    static_buffers: Vec<bool>,
+    //Generation of the static meshes the buffers were written with
+    static_generation: u64,
+    //Number of frames the static buffers still need to be written for
+    static_dirty: usize,
+    //Skinned meshes, drawn separately from the instanced meshes
+    skinned: SkinnedDraws,
+    //Hierarchy of the bounds of the meshes, storing their indices
+    bvh: Bvh<usize>,
+    //Meshes the hierarchy was built for, and their leaves
+    bvh_meshes: Vec<ComponentReference<components::mesh::Mesh>>,
+    leaves: Vec<Option<LeafId>>,
+    //Generation of the static meshes the hierarchy was updated with
+    bvh_generation: u64,
+    occlusion: OcclusionCulling,
 }
 
 impl Base {
@@ -44,11 +149,39 @@ impl Base {
                 b: 0.0,
                 a: 1.0,
             },
+            ops: PassOps::new(),
+            max_draw_distance: None,
+            min_screen_size: 0.0,
+            occlusion_culling: false,
             identifier: Vec::new(),
-            v_buffers: Vec::new(),
+            visible: Vec::new(),
+            ids: Vec::new(),
+            instances: None,
+            frame: 0,
+            draws: DrawCommands::new(),
             mesh_materials: Vec::new(),
             num_instances: Vec::new(),
             mesh_refs: Vec::new(),
+            texture_indices: Vec::new(),
+            static_buffers: Vec::new(),
+            static_generation: 0,
+            static_dirty: 0,
+            skinned: SkinnedDraws::new(),
+            bvh: Bvh::new(BVH_MARGIN),
+            bvh_meshes: Vec::new(),
+            leaves: Vec::new(),
+            bvh_generation: 0,
+            occlusion: OcclusionCulling::new(),
+            stats: CullingStats {
+                total: 0,
+                visible: 0,
+                culled: 0,
+                frustum_tests: 0,
+                groups: 0,
+                draw_calls: 0,
+                rebuilt: false,
+            },
+            sink: None,
         }
     }
 
@@ -62,32 +195,108 @@ impl Base {
         Self {
             priority: order,
             clear_color: color,
+            ops: PassOps::new(),
+            max_draw_distance: None,
+            min_screen_size: 0.0,
+            occlusion_culling: false,
             identifier: Vec::new(),
-            v_buffers: Vec::new(),
+            visible: Vec::new(),
+            ids: Vec::new(),
+            instances: None,
+            frame: 0,
+            draws: DrawCommands::new(),
             mesh_materials: Vec::new(),
             num_instances: Vec::new(),
             mesh_refs: Vec::new(),
+            texture_indices: Vec::new(),
+            static_buffers: Vec::new(),
+            static_generation: 0,
+            static_dirty: 0,
+            skinned: SkinnedDraws::new(),
+            bvh: Bvh::new(BVH_MARGIN),
+            bvh_meshes: Vec::new(),
+            leaves: Vec::new(),
+            bvh_generation: 0,
+            occlusion: OcclusionCulling::new(),
+            stats: CullingStats {
+                total: 0,
+                visible: 0,
+                culled: 0,
+                frustum_tests: 0,
+                groups: 0,
+                draw_calls: 0,
+                rebuilt: false,
+            },
+            sink: None,
         }
     }
-}
 
-#[derive(Clone, Copy)]
-struct MeshMaterial {
-    mesh_id: u128,
-    material_id: u128,
-}
+    ///Returns the statistics of the last rendered frame
+    #[must_use]
+    pub const fn get_stats(&self) -> CullingStats {
+        self.stats
+    }
 
-impl PartialEq<(u128, u128)> for MeshMaterial {
-    fn eq(&self, other: &(u128, u128)) -> bool {
-        self.mesh_id == other.0 && self.material_id == other.1
+    ///Sets the function receiving the events of the extension, replacing the previous one
+    ///
+    ///The sink is called during rendering, so it should be cheap, e.g. pushing the events into a
+    ///buffer that is graphed later
+    pub fn set_event_sink(&mut self, sink: impl FnMut(&CullingEvent) + 'static) {
+        self.sink = Some(Box::new(sink));
     }
-}
 
-impl MeshMaterial {
-    const fn new(mesh_id: u128, material_id: u128) -> Self {
-        Self {
-            mesh_id,
-            material_id,
+    ///Removes the event sink
+    pub fn clear_event_sink(&mut self) {
+        self.sink = None;
+    }
+
+    ///Passes the event to the sink, if there is one
+    fn emit(&mut self, event: CullingEvent) {
+        if let Some(sink) = &mut self.sink {
+            sink(&event);
+        }
+    }
+
+    ///Updates the bounds of the meshes in the hierarchy, static meshes are only updated when they
+    ///are invalidated
+    fn update_bvh(
+        &mut self,
+        meshes: &[ComponentReference<components::mesh::Mesh>],
+        assets: &AssetStore,
+    ) {
+        //Meshes were added or removed, the indices are no longer valid
+        let rebuild = meshes.len() != self.bvh_meshes.len()
+            || meshes
+                .iter()
+                .zip(&self.bvh_meshes)
+                .any(|(a, b)| !a.ptr_eq(b));
+        if rebuild {
+            self.bvh.clear();
+            self.bvh_meshes = meshes.to_vec();
+            self.leaves.clear();
+            self.leaves.resize(meshes.len(), None);
+        }
+
+        let generation = components::mesh::static_generation();
+        let static_changed = rebuild || generation != self.bvh_generation;
+        self.bvh_generation = generation;
+
+        for (index, (mesh, leaf)) in meshes.iter().zip(&mut self.leaves).enumerate() {
+            let m = mesh.borrow();
+            if m.get_static() && !static_changed && leaf.is_some() {
+                continue;
+            }
+            match (mesh_bounds(&m, assets), *leaf) {
+                (Some(bounds), Some(id)) => {
+                    self.bvh.update(id, bounds);
+                }
+                (Some(bounds), None) => *leaf = Some(self.bvh.insert(bounds, index)),
+                (None, Some(id)) => {
+                    self.bvh.remove(id);
+                    *leaf = None;
+                }
+                (None, None) => {}
+            }
         }
     }
 }
@@ -112,87 +321,125 @@ impl RenderingExtension for Base {
         camera.update_gpu(encoder);
         trace!("Accquired camera");
 
-        let frustum = calculate_frustum(
-            camera.inner.near,
-            camera.inner.far,
-            camera.inner.projection_type.fov().unwrap_or_default(),
-        );
+        lighting::update(world);
+
         let camera_transform = camera.camera_transform();
+        let camera_position = Vec3::new(
+            camera_transform.m03,
+            camera_transform.m13,
+            camera_transform.m23,
+        );
+        let (max_draw_distance, min_screen_size) = (self.max_draw_distance, self.min_screen_size);
+
+        //Levels of detail change the meshes, so they're picked before the meshes are culled
+        components::lod::select_levels(
+            world,
+            assets,
+            camera_position,
+            &camera.inner.projection_type,
+        );
 
         //This is cached, so should be reasonably fast
         let binding = world
             .get_all_components::<crate::components::mesh::Mesh>()
             .unwrap_or_default();
 
-        //Precompute the transformation matrix, since it's the same for all the objects
-        let matrix = calculate_frustum_matrix(frustum, camera_transform);
-
-        let meshes = binding
-            .iter()
-            .filter(|i| {
-                let m = i.borrow();
-                let binding = m.get_transform();
-                let t = binding.borrow();
-
-                m.get_visible()
-                    && check_frustum(
-                        frustum.z,
-                        matrix,
-                        t.position,
-                        assets
-                            .get_by_id::<Mesh>(m.get_mesh_id().unwrap())
-                            .unwrap()
-                            .borrow()
-                            .get_extent(),
-                        t.scale,
-                    )
-                    .0
-            })
-            .collect::<Vec<_>>();
+        //Planes of the view projection, with an infinite far plane the far plane always passes
+        let frustum = Frustum::from_matrix(&camera.matrix().transpose());
+
+        //Whole subtrees of meshes outside of the frustum are rejected at once
+        self.update_bvh(&binding, assets);
+        self.visible.clear();
+        let visible = &mut self.visible;
+        let frustum_tests = self.bvh.query_frustum(&frustum, |_, i| visible.push(*i));
+        //Keeps the order stable, so that the instance buffers can be reused
+        self.visible.sort_unstable();
+
+        //Indices of the visible meshes
+        self.visible.retain(|i| {
+            let m = binding[*i].borrow();
+            if !m.get_visible() {
+                return false;
+            }
+            //Static meshes are not updated in the hierarchy every frame, so they may have been
+            //changed since
+            let mesh = match validation::renderable(&m, assets) {
+                Ok((mesh, _)) => assets.get_by_id::<Mesh>(mesh),
+                Err(warning) => {
+                    validation::warn_once(warning);
+                    return false;
+                }
+            };
+            let Ok(mesh) = mesh else {
+                return false;
+            };
+            let extent = mesh.borrow().get_extent();
+
+            let binding = m.get_transform();
+            let t = binding.borrow();
+            let radius = extent * f32::max(t.scale.x, f32::max(t.scale.y, t.scale.z));
+
+            let distance = (t.position - camera_position).length();
+            if m.get_max_draw_distance()
+                .or(max_draw_distance)
+                .is_some_and(|max| distance - radius > max)
+            {
+                return false;
+            }
+            let min_size = m.get_min_screen_size().unwrap_or(min_screen_size);
+            !(min_size > 0.0
+                && screen_size(radius, distance, &camera.inner.projection_type) < min_size)
+        });
         trace!("Got all the meshes");
 
-        //List of materials used for rendering
-        let mut materials = VecSet::new();
-        //List of (mesh_ID, (transformation matrix, material_id))
-        let mut matrices = Vec::new();
-
-        //Collect all the matrices
-        for m in &meshes {
-            let m = m.borrow();
-            materials.insert(m.get_material_id().unwrap());
-            matrices.push((
-                m.get_mesh_id().unwrap(),
-                (m.get_matrix(), m.get_material_id().unwrap()),
-            ));
-        }
+        let total = binding.len();
+        let visible = self.visible.len();
 
-        //What is even going on here?
+        //Skinned meshes are not instanced
+        self.skinned
+            .partition(&mut self.visible, &binding, world, assets);
 
-        let mut matrices = matrices
-            .iter()
-            .zip(meshes)
-            .map(|i| (i.0 .0, (i.0 .1 .0, i.0 .1 .1, i.1)))
-            .collect::<Vec<_>>();
+        //List of (mesh_ID, material_id), used to determine if the cache can be reused
+        self.ids.clear();
+        self.ids.extend(self.visible.iter().map(|i| {
+            let m = binding[*i].borrow();
+            (
+                geometry_id(assets, m.get_mesh_id().unwrap()),
+                m.get_material_id().unwrap(),
+            )
+        }));
 
-        //determine if can re use cache
-        let mut identical = true;
+        //The occlusion culling reads the instance buffers as storage buffers, so toggling it
+        //recreates them
+        let occlusion_culling = self.occlusion_culling && occlusion::supported();
 
-        if matrices.len() == self.identifier.len() {
-            for (index, data) in self.identifier.iter().enumerate() {
-                if data.0 == matrices[index].0 && data.1 == matrices[index].1 .1 {
-                    continue;
-                }
-                identical = false;
-                break;
-            }
-        } else {
-            identical = false;
-        }
+        //determine if can re use cache
+        let identical =
+            self.ids == self.identifier && occlusion_culling == self.occlusion.is_enabled();
 
         #[allow(clippy::if_not_else)]
         if !identical {
             debug!("Generating new cache data");
-            self.identifier = matrices.iter().map(|i| (i.0, i.1 .1)).collect::<Vec<_>>();
+            mem::swap(&mut self.identifier, &mut self.ids);
+
+            //List of (mesh_ID, (instance data, material_id, mesh reference, static))
+            let mut matrices = self
+                .visible
+                .iter()
+                .map(|i| {
+                    let mesh = &binding[*i];
+                    let m = mesh.borrow();
+                    //Materials sharing the texture array are drawn together
+                    let (material, texture_index) =
+                        material::batch(assets, m.get_material_id().unwrap());
+                    let mut data = m.get_instance_data();
+                    data.texture_index = texture_index;
+                    (
+                        geometry_id(assets, m.get_mesh_id().unwrap()),
+                        (data, material, mesh, m.get_static()),
+                    )
+                })
+                .collect::<Vec<_>>();
 
             //Sort meshes by mesh id for easier buffer creation
             //NO Sort by material id?
@@ -214,65 +461,49 @@ impl RenderingExtension for Base {
             //Guarantee that there's at least 1 window
             split_points.push(matrices.len());
 
-            //assemble vertex buffers
-            let mut v_buffers = Vec::new();
-
-            let device = DEVICE.get().unwrap();
-
             let mut mesh_materials = Vec::new();
             let mut num_instances = Vec::new();
 
             let mut mesh_refs = Vec::new();
+            let mut texture_indices = Vec::new();
+
+            //Instance data of all the groups, one after another
+            let mut instances = Vec::with_capacity(matrices.len() * mem::size_of::<InstanceData>());
 
             for m in split_points.windows(2) {
                 //beginning and end of the window
                 let points = (*m.first().unwrap(), *m.last().unwrap());
 
-                //Label for easier debugging
-                let label = format!("Instances: {}..{}", m.first().unwrap(), m.last().unwrap());
-
-                //(mesh_ID, (transformation matrix, material_id, mesh reference));
+                //(mesh_ID, (transformation matrix, material_id, mesh reference, static));
                 let mut current_window = matrices[points.0..points.1].iter().collect::<Vec<_>>();
 
-                //Split into vectors and sorted by material
-                //Sort the window by materials
-                current_window.sort_unstable_by(|s, o| s.1 .1.cmp(&o.1 .1));
+                //Split into vectors and sorted by material, static meshes are kept in their own
+                //groups so that their instance data is not rewritten every frame
+                current_window.sort_unstable_by_key(|m| (m.1 .1, m.1 .3));
 
                 //find where materials change, similar to how meshes were sorted
                 let mut material_split_points = Vec::new();
-                let mut old = 0;
+                let mut old = None;
                 for (i, m) in current_window.iter().enumerate() {
-                    if m.1 .1 != old {
+                    if old != Some((m.1 .1, m.1 .3)) {
                         material_split_points.push(i);
-                        old = m.1 .1;
+                        old = Some((m.1 .1, m.1 .3));
                     }
                 }
                 //Again ensure there's at least one window
                 material_split_points.push(current_window.len());
 
-                let mut last = MeshMaterial {
-                    mesh_id: 0,
-                    material_id: 0,
-                };
-
-                //Need to iterate over it twice...
-                //Get indicators for every block of what mesh and material they are
-                for i in &material_split_points[..material_split_points.len() - 1] {
-                    let curent = current_window[*i];
-                    if last != (curent.0, curent.1 .1) {
-                        last = MeshMaterial::new(curent.0, curent.1 .1);
-                        mesh_materials.push(last);
-                    }
-                }
-
-                //AGAIN!?!?
-                //Create vertex buffers for matrices
+                //Create the groups of instances
                 for m in material_split_points.windows(2) {
                     //Now this is stored per mesh per material
                     let points = (*m.first().unwrap(), *m.last().unwrap());
+                    let current_window = &current_window[points.0..points.1];
 
+                    mesh_materials.push(MeshMaterial::new(
+                        current_window[0].0,
+                        current_window[0].1 .1,
+                    ));
                     num_instances.push(points.1 - points.0);
-                    let current_window = &current_window[points.0..points.1];
 
                     //Copy mesh references
                     mesh_refs.push(
@@ -281,30 +512,24 @@ impl RenderingExtension for Base {
                             .map(|i| i.1 .2.clone())
                             .collect::<Vec<_>>(),
                     );
+                    texture_indices.push(
+                        current_window
+                            .iter()
+                            .map(|i| i.1 .0.texture_index)
+                            .collect::<Vec<_>>(),
+                    );
 
-                    let matrices = current_window
-                        .iter()
-                        .flat_map(|i| bytemuck::bytes_of(&i.1 .0))
-                        .copied()
-                        .collect::<Vec<u8>>();
-                    v_buffers.push(
-                        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                            label: Some(&label),
-                            contents: &matrices,
-                            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                        }),
+                    instances.extend(
+                        current_window
+                            .iter()
+                            .flat_map(|i| bytemuck::bytes_of(&i.1 .0)),
                     );
                 }
             }
             //Check if they're the same length
             assert_eq!(
-                v_buffers.len(),
-                mesh_materials.len(),
-                "You are a moron, they're not the same"
-            );
-            assert_eq!(
-                v_buffers.len(),
                 mesh_refs.len(),
+                mesh_materials.len(),
                 "You are stupid, they're not the same"
             );
             assert_eq!(
@@ -313,43 +538,71 @@ impl RenderingExtension for Base {
                 "You are an idiot, they're not the same"
             );
 
-            self.v_buffers = v_buffers;
+            //The occlusion culling reads the instances from a storage buffer
+            let usage = if occlusion_culling {
+                wgpu::BufferUsages::STORAGE
+            } else {
+                wgpu::BufferUsages::empty()
+            };
+            self.instances = instance_buffers_init(&instances, usage);
+            self.frame = 0;
+            self.draws
+                .set(draw_commands(&mesh_materials, &num_instances, assets));
             self.mesh_materials = mesh_materials;
             self.num_instances = num_instances;
             self.mesh_refs = mesh_refs;
-        } else {
+            self.texture_indices = texture_indices;
+            self.static_buffers = static_groups(&self.mesh_refs);
+            self.static_generation = components::mesh::static_generation();
+            self.static_dirty = 0;
+
+            if occlusion_culling {
+                let bounds = self.mesh_materials.iter().map(|m| {
+                    let mesh = assets.get_by_id::<Mesh>(m.mesh_id).unwrap();
+                    let bounds = mesh.borrow().get_bounds();
+                    bounds
+                });
+                self.occlusion
+                    .set_groups(self.instances.as_ref(), self.draws.commands(), bounds);
+            } else {
+                self.occlusion.clear();
+            }
+
+            self.emit(CullingEvent::BuffersRebuilt {
+                groups: self.mesh_materials.len(),
+                instances: visible,
+            });
+        } else if let Some(buffers) = &self.instances {
             //Reusing data
             trace!("Cache exists, updating v buffers");
-            let mut belt = STAGING_BELT.get().unwrap().write().unwrap();
-            let device = DEVICE.get().unwrap();
-
-            for (buffer, meshes) in self.v_buffers.iter().zip(self.mesh_refs.iter()) {
-                //I do have to collect here
-                let matrices = meshes
-                    .iter()
-                    .map(|m| m.borrow().get_matrix())
-                    .collect::<Vec<_>>();
-
-                let matrix_data = matrices
-                    .iter()
-                    .flat_map(bytemuck::bytes_of)
-                    .copied()
-                    .collect::<Vec<u8>>();
-
-                belt.write_buffer(
-                    encoder,
-                    buffer,
-                    0,
-                    NonZeroU64::new(buffer.size()).unwrap(),
-                    device,
-                )
-                .copy_from_slice(matrix_data.as_slice());
+
+            //Move on to the next set of buffers
+            self.frame = (self.frame + 1) % FRAMES_IN_FLIGHT;
+
+            //Static meshes changed, all of the buffer sets need to be updated
+            let generation = components::mesh::static_generation();
+            if generation != self.static_generation {
+                self.static_generation = generation;
+                self.static_dirty = FRAMES_IN_FLIGHT;
+                self.static_buffers = static_groups(&self.mesh_refs);
             }
+            let write_static = self.static_dirty > 0;
+            self.static_dirty = self.static_dirty.saturating_sub(1);
+
+            write_instances(
+                encoder,
+                &buffers[self.frame],
+                self.draws.commands(),
+                &self.mesh_refs,
+                &self.texture_indices,
+                &self.static_buffers,
+                write_static,
+            );
         }
 
         //Initialize bindgroups for all needed materials
-        for m in materials {
-            let m = assets.get_by_id::<Material>(m).unwrap();
+        for m in &self.mesh_materials {
+            let m = assets.get_by_id::<Material>(m.material_id).unwrap();
             let mut m = m.borrow_mut();
 
             if matches!(m.get_bindgroup_state(), BindgroupState::Initialized) {
@@ -358,211 +611,103 @@ impl RenderingExtension for Base {
             m.initialize_bindgroups(assets);
         }
 
+        self.skinned.update(encoder, world, assets);
+
+        //The depth attachment still contains the previous frame
+        if occlusion_culling {
+            self.occlusion.cull(
+                encoder,
+                &attachments.depth_stencil,
+                camera.matrix(),
+                self.frame,
+            );
+        }
+
+        let clear_color = camera.background.clear_color(self.clear_color);
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("First pass"),
+            label: Some("Frustum culling pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
                 view: &attachments.color,
                 resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(self.clear_color.into()),
-                    store: wgpu::StoreOp::Store,
-                },
+                ops: self.ops.color_ops(attachments, clear_color.into()),
             })],
             depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
                 view: &attachments.depth_stencil,
-                depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.0),
-                    store: wgpu::StoreOp::Store,
-                }),
+                depth_ops: Some(self.ops.depth_ops(attachments)),
                 stencil_ops: None,
             }),
             timestamp_writes: None,
             occlusion_query_set: None,
         });
 
+        viewport::apply(&mut render_pass);
+
         //Set the camera
         camera.set_bindgroup(&mut render_pass);
 
-        let mut previous_mat = 0;
-
-        //Iterate through the meshes and render them
-        for (i, m) in self.mesh_materials.iter().enumerate() {
-            let mat = m.material_id;
-
-            if mat != previous_mat {
-                let mat = assets.get_by_id::<Material>(mat).unwrap();
-                let mat = mat.borrow();
-
-                mat.render(&mut render_pass);
-            }
-            previous_mat = mat;
-
-            let mesh = assets.get_by_id::<Mesh>(m.mesh_id).unwrap();
-            let mesh = mesh.borrow();
-
-            let vert = unsafe { Arc::as_ptr(&mesh.get_vertex_buffer()).as_ref().unwrap() };
-            let ind = unsafe { Arc::as_ptr(&mesh.get_index_buffer()).as_ref().unwrap() };
-
-            render_pass.set_vertex_buffer(0, vert.slice(..));
-            render_pass.set_vertex_buffer(1, self.v_buffers[i].slice(..));
-
-            render_pass.set_index_buffer(ind.slice(..), wgpu::IndexFormat::Uint32);
-            render_pass.draw_indexed(
-                0..mesh.get_index_count(),
-                0,
-                0..(self.num_instances[i] as u32),
-            );
-        }
+        let draw_calls = self.instances.as_ref().map_or(0, |buffers| {
+            //Only the instances that passed the occlusion culling are drawn
+            let (instances, draws) = self
+                .occlusion
+                .draw_buffers()
+                .map_or((&buffers[self.frame], self.draws.buffer()), |(v, d)| {
+                    (v, Some(d))
+                });
+            draw_groups(
+                &mut render_pass,
+                assets,
+                &self.mesh_materials,
+                self.draws.commands(),
+                instances,
+                draws,
+            )
+        });
+        self.skinned.draw(&mut render_pass, assets);
         drop(render_pass);
+        drop(camera);
+
+        self.stats = CullingStats {
+            total,
+            visible,
+            culled: total - visible,
+            frustum_tests,
+            groups: self.mesh_materials.len() + self.skinned.len(),
+            draw_calls: draw_calls + self.skinned.len(),
+            rebuilt: !identical,
+        };
+        self.emit(CullingEvent::Frame(self.stats));
     }
 
     fn get_priority(&self) -> u32 {
         self.priority
     }
-}
-
-fn calculate_frustum(near: f32, far: f32, fov: f32) -> Vec3 {
-    let beta = f32::consts::FRAC_PI_2 - (fov / 2.0);
-    let bottom = 2.0 * (((near + far) * f32::sin(fov / 2.0)) / f32::sin(beta));
 
-    let resolution = RESOLUTION.read().unwrap();
-    let aspect = resolution.width as f32 / resolution.height as f32;
-    drop(resolution);
-
-    // let aspect = 1.3333333334;
-
-    let side = bottom / aspect;
-
-    (bottom, side, near + far).into()
-}
-
-fn calculate_frustum_matrix(frustum: Vec3, camera_transform: Mat4x4) -> Mat4x4 {
-    let h = frustum.z;
-
-    let scale = Mat4x4::scale_matrix(&(Vec3::new(frustum.x, 1.0, frustum.y)))
-        .invert()
-        .unwrap();
-    let translation = Mat4x4::translation_matrix(&Vec3::new(0.0, h, 0.0));
-    let rotation = Mat4x4::rotation_matrix_euler(&Vec3::new(-90.0, 0.0, 90.0))
-        .invert()
-        .unwrap();
-
-    translation * scale * rotation * camera_transform.inverted().unwrap()
-}
-
-fn check_frustum(
-    h: f32,
-    frustum_matrix: Mat4x4,
-    point: Vec3,
-    radius: f32,
-    scale: Vec3,
-) -> (bool, f32) {
-    let p: Vec4 = (point, 1.0).into();
-
-    let p = p * frustum_matrix;
-    let p = p.xyz();
-
-    let distance = sdf(p, h);
-
-    if distance <= 0.0 {
-        return (true, distance);
+    fn get_reads(&self) -> &'static [&'static str] {
+        &["shadow", "lights"]
     }
-
-    //Factor in scale
-    (
-        radius.mul_add(-f32::max(scale.x, f32::max(scale.y, scale.z)), distance) <= 0.001,
-        distance,
-    )
 }
 
-// fn sdf(mut p: Vec3, h: f32) -> f32 {
-//     // Original SDF license:
-//     // The MIT License
-//     // Copyright © 2019 Inigo Quilez
-//     // Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions: The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software. THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
-
-//     if p.y <= 0.0 {
-//         let p = p.abs() - Vec3::new(0.5, 0.0, 0.5);
-
-//         return if p > Vec3::new(0.0, 0.0, 0.0) {
-//             p.length()
-//         } else {
-//             0.0
-//         };
-//     }
-
-//     //Symmetry
-//     p.x = f32::abs(p.x);
-//     p.z = f32::abs(p.z);
-
-//     if p.z > p.x {
-//         p.x = p.z;
-//         p.z = p.x;
-//     }
-//     p.x -= 0.5;
-//     p.z -= 0.5;
-
-//     //project into face plane (2d)
-
-//     let m2 = h * h + 0.25;
-
-//     let q = Vec3::new(p.z, h * p.y - 0.5 * p.x, h * p.x + 0.5 * p.y);
-
-//     let sign = f32::signum(f32::max(q.z, -p.y));
-
-//     // if sign <= 0.0 {
-//     //     return (true, -1.0);
-//     // }
-
-//     let s = f32::max(-q.x, 0.0);
-
-//     let t = f32::clamp((q.y - 0.5 * q.x) / (m2 + 0.25), 0.0, 1.0);
-
-//     let a = m2 * (q.x + s) * (q.x + s) + q.y * q.y;
-
-//     let b = m2 * (q.x + 0.5 * t) * (q.x + 0.5 * t) + (q.y - m2 * t) * (q.y - m2 * t);
-
-//     let d2 = if f32::max(-q.y, q.x * m2 + q.y * 0.5) < 0.0 {
-//         0.0
-//     } else {
-//         f32::min(a, b)
-//     };
-
-//     f32::sqrt((d2 + q.z * q.z) / m2) * sign
-// }
-
-fn sdf(mut p: Vec3, h: f32) -> f32 {
-    let half_h = h / 2.0;
-
-    p.x = p.x.abs();
-    p.z = p.z.abs();
-
-    if p.x > p.z {
-        mem::swap(&mut p.x, &mut p.z);
-    }
-
-    let d1 = Vec3::new(f32::max(p.x - 0.5, 0.0), p.y, f32::max(p.z - 0.5, 0.0));
-
-    let mut q = p;
-
-    let k = (4.0 * half_h).mul_add(half_h, 0.25);
-
-    let h1 = Vec2::dot_product(
-        &(Vec2::new(q.y, q.z) - Vec2::new(0.0, 0.5)),
-        &Vec2::new(0.5, h),
-    ) / k;
-
-    //I don't really care about the insides, if it is inside, it IS inside, so i can computer the
-    //sqrt if it is outside
-    if f32::max(h1, -p.y) < 0.0 {
-        f32::NEG_INFINITY
-    } else {
-        q.y -= 0.5 * h1;
-        q.z -= h * h1;
-
-        q -= Vec3::new(k, half_h, -0.25) * f32::max(q.x - q.z, 0.0) / (k + 0.25);
+///Returns the world space bounds of the mesh, `None` if it can't be rendered
+fn mesh_bounds(m: &components::mesh::Mesh, assets: &AssetStore) -> Option<Aabb> {
+    //Partially initialized meshes are skipped instead of crashing the frame
+    let mesh = match validation::renderable(m, assets) {
+        Ok((mesh, _)) => assets.get_by_id::<Mesh>(mesh).ok()?,
+        Err(warning) => {
+            validation::warn_once(warning);
+            return None;
+        }
+    };
+    let bounds = mesh.borrow().get_bounds();
+    Some(bounds.transformed(&m.get_transform().borrow().matrix()))
+}
 
-        let d2 = p - q.clamp(0.0.into(), Vec3::new(0.5, h, 0.5));
-        f32::sqrt(f32::min(d1.dot_product(&d1), d2.dot_product(&d2)))
+///Returns the fraction of the height of the screen covered by a sphere with the `radius` at the
+///`distance` from the camera
+pub(crate) fn screen_size(radius: f32, distance: f32, projection: &ProjectionType) -> f32 {
+    match projection {
+        //The camera is inside of the sphere
+        ProjectionType::Perspective { .. } if distance <= radius => f32::INFINITY,
+        ProjectionType::Perspective { fov } => radius / (distance * (fov / 2.0).tan()),
+        ProjectionType::Orthographic { size } => radius / size,
     }
 }
@@ -0,0 +1,456 @@
+//! Top-down minimap shown in a corner of the screen
+//!
+//! The [`Minimap`] extension renders the meshes of the world with its own orthographic camera,
+//! looking down the y axis with the z axis pointing up on the map, into a texture, which is then
+//! drawn over the color attachment. Meshes can be shown only on the minimap, or hidden from it,
+//! using render layers, see [`Mesh::set_layers`].
+//!
+//!```no_run
+//! use lunar_engine::{
+//!     components::mesh::DEFAULT_LAYER,
+//!     math::{Vec2, Vec3},
+//!     rendering::extensions::{Base, Minimap},
+//!     structures::Color,
+//! };
+//!
+//! const MAP_ICONS: u32 = 2;
+//!
+//! //Map icons are only shown on the minimap
+//! let mut base = Base::new(0);
+//! base.layers = DEFAULT_LAYER;
+//! let mut minimap = Minimap::new(10, 192);
+//! minimap.layers = DEFAULT_LAYER | MAP_ICONS;
+//! minimap.circular = true;
+//!
+//! //Every frame
+//! let player = Vec3::new(4.0, 0.0, 2.0);
+//! minimap.center = player;
+//! minimap.marker(player, 4.0, Color::red());
+//!```
+//!
+//! [`Mesh::set_layers`]: crate::components::mesh::Mesh::set_layers
+use std::mem;
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::{
+    asset_managment::AssetStore,
+    components,
+    ecs::World,
+    grimoire::CAMERA_BIND_GROUP_LAYOUT_DESCRIPTOR,
+    math::{Mat4x4, Vec2, Vec3, Vec4},
+    rendering::{self, depth, hdr, msaa, viewport},
+    structures::Color,
+    DEVICE, QUEUE,
+};
+
+use super::{scene::SceneDraws, AttachmentData, Canvas, RenderingExtension};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Parameters {
+    //Top left corner and size in pixels
+    rect: [f32; 4],
+    //Size of the viewport, circular
+    settings: [f32; 4],
+}
+
+struct MinimapState {
+    //Multisampled color, if multisampling is enabled
+    multisampled: Option<wgpu::Texture>,
+    color: wgpu::Texture,
+    depth: wgpu::Texture,
+    camera: wgpu::Buffer,
+    camera_bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+    uniform: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    scene: SceneDraws,
+    //Size, color attachment and depth range the state was created for
+    key: (u32, (u32, wgpu::TextureFormat), bool),
+}
+
+///Renders a top-down view of the world into a square in the corner of the screen
+///
+///Should be rendered after the extensions rendering the scene. The minimap uses the pipelines of
+///the materials, so it has the same lighting as the scene.
+pub struct Minimap {
+    ///Priority of the extension
+    pub priority: u32,
+    ///Top left corner of the minimap, in pixels relative to the top left corner of the viewport
+    pub position: Vec2,
+    ///Width and height of the minimap in pixels
+    pub size: u32,
+    ///Point of the world in the center of the minimap
+    pub center: Vec3,
+    ///Width of the area shown on the minimap, in world units
+    pub extent: f32,
+    ///Height of the camera above the center, the meshes above it are not shown
+    pub height: f32,
+    ///Bit mask of the render layers shown on the minimap, see
+    ///[`components::mesh::Mesh::set_layers`]
+    pub layers: u32,
+    ///Color of the parts of the minimap without any meshes
+    pub background: Color,
+    ///Whether the minimap is a circle or a square
+    pub circular: bool,
+    markers: Canvas,
+    state: Option<MinimapState>,
+}
+
+impl Minimap {
+    ///Creates a new minimap `size` pixels wide, showing 50 units of the world around the origin
+    #[must_use]
+    pub const fn new(priority: u32, size: u32) -> Self {
+        Self {
+            priority,
+            position: Vec2::new(16.0, 16.0),
+            size,
+            center: Vec3::new(0.0, 0.0, 0.0),
+            extent: 50.0,
+            height: 100.0,
+            layers: components::mesh::ALL_LAYERS,
+            background: Color::new(0.05, 0.05, 0.05, 1.0),
+            circular: false,
+            markers: Canvas::new(0),
+            state: None,
+        }
+    }
+
+    ///Returns the matrix of the camera of the minimap, in the same format as
+    ///[`components::camera::Camera::matrix`]
+    #[must_use]
+    pub fn matrix(&self) -> Mat4x4 {
+        let eye = self.center + Vec3::new(0.0, self.height, 0.0);
+        let view = Mat4x4::look_at_matrix(eye, Vec3::new(0.0, 0.0, 1.0), self.center);
+        let projection = Mat4x4::orth_aspect_projection(self.extent, 1.0, 0.0, self.height * 2.0);
+        let projection = if depth::is_reversed() {
+            projection.reverse_depth()
+        } else {
+            projection
+        };
+        view * projection
+    }
+
+    ///Returns the position of the point of the world on the screen, in pixels relative to the top
+    ///left corner of the viewport, or `None` if the point is not on the minimap
+    #[must_use]
+    pub fn world_to_map(&self, point: Vec3) -> Option<Vec2> {
+        //The shaders multiply the vector by the matrix from the left
+        let clip = self
+            .matrix()
+            .transpose()
+            .transform(Vec4::new(point.x, point.y, point.z, 1.0));
+        let ndc = Vec2::new(clip.x / clip.w, clip.y / clip.w);
+        if ndc.x.abs() > 1.0 || ndc.y.abs() > 1.0 {
+            return None;
+        }
+        if self.circular && ndc.x.mul_add(ndc.x, ndc.y * ndc.y) > 1.0 {
+            return None;
+        }
+
+        let size = self.size as f32;
+        Some(self.position + Vec2::new((ndc.x + 1.0) * 0.5 * size, (1.0 - ndc.y) * 0.5 * size))
+    }
+
+    ///Draws a circular marker with the `radius` in pixels at the point of the world, markers are
+    ///removed after every frame
+    pub fn marker(&mut self, point: Vec3, radius: f32, color: Color) {
+        if let Some(p) = self.world_to_map(point) {
+            self.markers.circle(p, radius, color);
+        }
+    }
+
+    fn create_state(&self) -> MinimapState {
+        let device = DEVICE.get().unwrap();
+        let samples = msaa::sample_count();
+
+        let texture = |label, format, samples, usage| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width: self.size.max(1),
+                    height: self.size.max(1),
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: samples,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage,
+                view_formats: &[],
+            })
+        };
+        let multisampled =
+            (samples > 1).then(|| msaa::create_color(self.size.max(1), self.size.max(1), samples));
+        let color = texture(
+            "Minimap color",
+            hdr::color_format(),
+            1,
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        );
+        let depth = texture(
+            "Minimap depth",
+            depth::format(),
+            samples,
+            wgpu::TextureUsages::RENDER_ATTACHMENT,
+        );
+
+        let camera = crate::helpers::create_uniform_matrix(Some("Minimap camera"));
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Minimap camera"),
+            layout: &device.create_bind_group_layout(&CAMERA_BIND_GROUP_LAYOUT_DESCRIPTOR),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera.as_entire_binding(),
+            }],
+        });
+
+        let shader = crate::assets::shader::compile(
+            "minimap.wgsl",
+            include_str!("../../shaders/minimap.wgsl"),
+        )
+        .expect("Failed to compile the minimap shader");
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Minimap bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Minimap pipeline layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = crate::errors::scoped("Minimap pipeline", || {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Minimap pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vertex",
+                    buffers: &[],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: msaa::multisample_state(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fragment",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: hdr::color_format(),
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                }),
+                multiview: None,
+            })
+        });
+
+        let uniform = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Minimap parameters"),
+            size: mem::size_of::<Parameters>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Minimap sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Minimap bind group"),
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(
+                        &color.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: uniform.as_entire_binding(),
+                },
+            ],
+        });
+
+        MinimapState {
+            multisampled,
+            color,
+            depth,
+            camera,
+            camera_bind_group,
+            pipeline,
+            uniform,
+            bind_group,
+            scene: SceneDraws::new("Minimap instances"),
+            key: self.key(),
+        }
+    }
+
+    fn key(&self) -> (u32, (u32, wgpu::TextureFormat), bool) {
+        (self.size, rendering::color_targets(), depth::is_reversed())
+    }
+
+    ///Renders the meshes on the layers of the minimap into its texture
+    fn render_map(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        world: &World,
+        assets: &AssetStore,
+    ) {
+        let matrix = self.matrix();
+        let state = self.state.as_mut().unwrap();
+        QUEUE
+            .get()
+            .unwrap()
+            .write_buffer(&state.camera, 0, bytemuck::bytes_of(&matrix));
+        state.scene.prepare(world, assets, self.layers);
+
+        let color = state
+            .color
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let multisampled = state
+            .multisampled
+            .as_ref()
+            .map(|t| t.create_view(&wgpu::TextureViewDescriptor::default()));
+        let depth = state
+            .depth
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Minimap scene pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: multisampled.as_ref().unwrap_or(&color),
+                resolve_target: multisampled.as_ref().map(|_| &color),
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(self.background.into()),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &depth,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(depth::clear_value()),
+                    store: wgpu::StoreOp::Discard,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        state
+            .scene
+            .draw(&mut render_pass, assets, &state.camera_bind_group);
+    }
+}
+
+impl RenderingExtension for Minimap {
+    fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        world: &World,
+        assets: &AssetStore,
+        attachments: &AttachmentData,
+    ) {
+        if self.size == 0 {
+            self.markers.clear();
+            return;
+        }
+        if self.state.as_ref().is_none_or(|s| s.key != self.key()) {
+            self.state = Some(self.create_state());
+        }
+
+        self.render_map(encoder, world, assets);
+
+        let state = self.state.as_ref().unwrap();
+        let viewport = viewport::current();
+        let size = self.size as f32;
+        QUEUE.get().unwrap().write_buffer(
+            &state.uniform,
+            0,
+            bytemuck::bytes_of(&Parameters {
+                rect: [self.position.x, self.position.y, size, size],
+                settings: [
+                    viewport.width,
+                    viewport.height,
+                    if self.circular { 1.0 } else { 0.0 },
+                    0.0,
+                ],
+            }),
+        );
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Minimap pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &attachments.color,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        viewport::apply(&mut render_pass);
+        render_pass.set_pipeline(&state.pipeline);
+        render_pass.set_bind_group(0, &state.bind_group, &[]);
+        render_pass.draw(0..6, 0..1);
+        drop(render_pass);
+
+        self.markers.render(encoder, world, assets, attachments);
+    }
+
+    fn get_priority(&self) -> u32 {
+        self.priority
+    }
+
+    fn get_attachments(&self) -> &'static [&'static str] {
+        &["color"]
+    }
+
+    fn get_reads(&self) -> &'static [&'static str] {
+        &["shadow", "lights"]
+    }
+}
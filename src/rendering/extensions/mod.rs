@@ -1,29 +1,283 @@
 #![allow(clippy::too_many_lines)]
 
-use std::{num::NonZeroU64, sync::Arc};
+use std::{cell::RefCell, mem, num::NonZeroU64, sync::Arc};
 
 use log::{debug, trace};
-use vec_key_value_pair::set::VecSet;
-use wgpu::util::DeviceExt;
+use wgpu::util::{DeviceExt, DrawIndexedIndirectArgs};
 
 use crate::{
     asset_managment::AssetStore,
-    assets::{BindgroupState, Material, Mesh},
+    assets::{material, mesh::geometry_id, BindgroupState, Material, Mesh},
     components,
     ecs::{ComponentReference, World},
-    structures::Color,
-    DEVICE, STAGING_BELT,
+    grimoire::{FRAMES_IN_FLIGHT, JOINTS_BIND_GROUP_INDEX},
+    math::Vec3,
+    rendering::{
+        depth,
+        graph::{AttachmentDescriptor, AttachmentSize},
+        indirect::{self, DrawCommands},
+        lighting, viewport,
+    },
+    structures::{Color, InstanceData},
+    validation, DEVICE, STAGING_BELT,
 };
 
+pub mod cameras;
+pub mod canvas;
+pub mod clustered;
+pub mod debug_lines;
+pub mod debug_view;
+pub mod frame_time;
 ///Frustum culling experiment
 pub mod frustum_culling;
-
-///A color buffer and a depth stencil buffer
+pub mod minimap;
+pub mod picking;
+pub mod render_targets;
+mod scene;
+pub mod shadows;
+pub mod skybox;
+pub mod sprites;
+pub mod text;
+pub mod tonemap;
+pub mod world_text;
+
+pub use cameras::Cameras;
+pub use canvas::{Canvas, LineJoin, SdfFont};
+pub use debug_lines::DebugLines;
+pub use debug_view::{DebugView, RenderMode};
+pub use minimap::Minimap;
+pub use render_targets::RenderTargets;
+pub use skybox::{Skybox, SkyboxSource};
+pub use sprites::SpriteRenderer;
+pub use text::TextRenderer;
+pub use tonemap::{Tonemap, TonemapOperator};
+pub use world_text::WorldTextRenderer;
+
+///A color buffer, a depth stencil buffer and the attachments declared by the extensions
 pub struct AttachmentData {
     ///Color buffer
     pub color: wgpu::TextureView,
     ///Depth stencil buffer
     pub depth_stencil: wgpu::TextureView,
+    ///Attachment in the format of the frame buffer the HDR color buffer is tonemapped into,
+    ///`None` if HDR rendering is disabled, see [`crate::rendering::hdr`]
+    pub output: Option<wgpu::TextureView>,
+    //Single sampled view of the HDR color buffer, or its resolve target if it's multisampled
+    hdr: Option<wgpu::TextureView>,
+    //Attachments declared with `RenderingExtension::declare_attachments`
+    declared: Vec<(&'static str, wgpu::TextureView)>,
+    //Attachments an earlier pass of the frame wrote to
+    written: RefCell<Vec<&'static str>>,
+}
+
+impl AttachmentData {
+    ///Creates new attachment data, that no pass wrote to yet
+    #[must_use]
+    pub const fn new(color: wgpu::TextureView, depth_stencil: wgpu::TextureView) -> Self {
+        Self {
+            color,
+            depth_stencil,
+            output: None,
+            hdr: None,
+            declared: Vec::new(),
+            written: RefCell::new(Vec::new()),
+        }
+    }
+
+    ///Adds the single sampled HDR color buffer and the output it's tonemapped into
+    pub(crate) fn with_hdr(mut self, hdr: wgpu::TextureView, output: wgpu::TextureView) -> Self {
+        self.hdr = Some(hdr);
+        self.output = Some(output);
+        self
+    }
+
+    ///Adds the attachments declared by the extensions
+    pub(crate) fn with_declared(
+        mut self,
+        declared: Vec<(&'static str, wgpu::TextureView)>,
+    ) -> Self {
+        self.declared = declared;
+        self
+    }
+
+    ///Returns the attachment with the given name, `color`, `depth_stencil`, `output` or one
+    ///declared with [`RenderingExtension::declare_attachments`]
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&wgpu::TextureView> {
+        match name {
+            "color" => Some(&self.color),
+            "depth_stencil" => Some(&self.depth_stencil),
+            "output" => self.output.as_ref(),
+            _ => self.declared.iter().find(|a| a.0 == name).map(|a| &a.1),
+        }
+    }
+
+    ///Creates attachment data for an extension rendering at a different resolution, with the
+    ///given color and depth attachments and the declared attachments
+    ///
+    ///The depth attachment is always cleared by the pass, the color attachment only if no earlier
+    ///pass wrote to the color attachment of the frame buffer, otherwise it is cleared to
+    ///transparent black before the pass
+    pub(crate) fn scaled(
+        &self,
+        color: wgpu::TextureView,
+        depth_stencil: wgpu::TextureView,
+        declared: Vec<(&'static str, wgpu::TextureView)>,
+    ) -> Self {
+        let written = self
+            .written
+            .borrow()
+            .iter()
+            .copied()
+            .filter(|a| *a != "depth_stencil")
+            .collect();
+
+        Self {
+            color,
+            depth_stencil,
+            output: None,
+            hdr: None,
+            declared,
+            written: RefCell::new(written),
+        }
+    }
+
+    ///Returns a view of the HDR color buffer that can be sampled, resolving the color buffer into
+    ///it first if it's multisampled, `None` if HDR rendering is disabled
+    pub fn hdr_color(&self, encoder: &mut wgpu::CommandEncoder) -> Option<&wgpu::TextureView> {
+        let hdr = self.hdr.as_ref()?;
+        if crate::rendering::msaa::sample_count() > 1 {
+            crate::rendering::msaa::resolve(encoder, &self.color, hdr);
+        }
+        Some(hdr)
+    }
+
+    ///Returns `true` if an earlier pass of the frame wrote to the attachment with the given name
+    #[must_use]
+    pub fn is_written(&self, name: &str) -> bool {
+        self.written.borrow().contains(&name)
+    }
+
+    ///Returns `true` if an earlier pass of the frame wrote to the color attachment
+    #[must_use]
+    pub fn color_written(&self) -> bool {
+        self.is_written("color")
+    }
+
+    ///Returns `true` if an earlier pass of the frame wrote to the depth stencil attachment
+    #[must_use]
+    pub fn depth_written(&self) -> bool {
+        self.is_written("depth_stencil")
+    }
+
+    ///Marks the attachments as written to, based on the names returned by
+    ///[`RenderingExtension::get_attachments`]
+    pub(crate) fn mark_written(&self, attachments: &[&'static str]) {
+        let mut written = self.written.borrow_mut();
+        for a in attachments {
+            if !written.contains(a) {
+                written.push(a);
+            }
+        }
+    }
+}
+
+///What a pass does with an attachment at its start
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LoadBehavior {
+    ///Clears the attachment if no earlier pass of the frame wrote to it, keeps the contents
+    ///otherwise
+    #[default]
+    Auto,
+    ///Always clears the attachment
+    Clear,
+    ///Always keeps the contents of the attachment
+    Load,
+}
+
+///Load and store behavior of the color and depth attachments of a pass
+///
+///With the default [`LoadBehavior::Auto`], only the first extension of the frame clears the
+///attachments, so multiple extensions rendering the world can be layered on top of each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PassOps {
+    ///What the pass does with the color attachment
+    pub color: LoadBehavior,
+    ///What the pass does with the depth attachment
+    pub depth: LoadBehavior,
+    ///Whether or not the contents of the color attachment are stored at the end of the pass
+    pub store_color: bool,
+    ///Whether or not the contents of the depth attachment are stored at the end of the pass,
+    ///may be disabled for the last pass using the depth
+    pub store_depth: bool,
+}
+
+impl Default for PassOps {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PassOps {
+    ///Creates new pass ops, that only clear the attachments if no earlier pass wrote to them and
+    ///store both attachments
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            color: LoadBehavior::Auto,
+            depth: LoadBehavior::Auto,
+            store_color: true,
+            store_depth: true,
+        }
+    }
+
+    ///Returns the operations for the color attachment, clearing it with `clear` if needed
+    #[must_use]
+    pub fn color_ops(
+        &self,
+        attachments: &AttachmentData,
+        clear: wgpu::Color,
+    ) -> wgpu::Operations<wgpu::Color> {
+        wgpu::Operations {
+            load: if resolve(self.color, attachments.color_written()) {
+                wgpu::LoadOp::Clear(clear)
+            } else {
+                wgpu::LoadOp::Load
+            },
+            store: store_op(self.store_color),
+        }
+    }
+
+    ///Returns the operations for the depth attachment, clearing it with
+    ///[`depth::clear_value`] if needed
+    #[must_use]
+    pub fn depth_ops(&self, attachments: &AttachmentData) -> wgpu::Operations<f32> {
+        wgpu::Operations {
+            load: if resolve(self.depth, attachments.depth_written()) {
+                wgpu::LoadOp::Clear(depth::clear_value())
+            } else {
+                wgpu::LoadOp::Load
+            },
+            store: store_op(self.store_depth),
+        }
+    }
+}
+
+///Returns `true` if the attachment needs to be cleared
+const fn resolve(behavior: LoadBehavior, written: bool) -> bool {
+    match behavior {
+        LoadBehavior::Auto => !written,
+        LoadBehavior::Clear => true,
+        LoadBehavior::Load => false,
+    }
+}
+
+const fn store_op(store: bool) -> wgpu::StoreOp {
+    if store {
+        wgpu::StoreOp::Store
+    } else {
+        wgpu::StoreOp::Discard
+    }
 }
 
 ///Trait that all rendering extensions must implement
@@ -39,10 +293,65 @@ pub trait RenderingExtension {
         attachments: &AttachmentData,
     );
 
-    ///Returns the priority of the extension, extensions with smaller priorities are rendered first.
+    ///Returns the priority of the extension, extensions with smaller priorities are rendered first,
+    ///unless they depend on the output of an extension with a larger priority, see
+    ///[`crate::rendering::graph`]. Extensions with equal priorities are rendered in the order they
+    ///are passed to [`crate::rendering::render`]
     fn get_priority(&self) -> u32;
+
+    ///Returns the name of the extension, used for debug labels
+    fn get_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    ///Returns the names of the attachments the extension writes to, used for ordering the
+    ///extensions and for the [`crate::rendering::graph::FrameGraph`]
+    fn get_attachments(&self) -> &'static [&'static str] {
+        &["color", "depth_stencil"]
+    }
+
+    ///Returns the names of the attachments the extension reads from, the extension is rendered
+    ///after all the extensions writing to them
+    fn get_reads(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    ///Returns the attachments the renderer should create for the extension, accessed with
+    ///[`AttachmentData::get`]
+    ///
+    ///Multiple extensions can declare the same attachment to share it
+    fn declare_attachments(&self) -> &'static [AttachmentDescriptor] {
+        &[]
+    }
+
+    ///Returns the identifiers of the features the extension provides to other extensions, see
+    ///[`Self::get_required`]
+    fn get_provided(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    ///Returns the identifiers of the features the extension requires from other extensions,
+    ///provided with [`Self::get_provided`]
+    ///
+    ///The extension is not rendered if any of them are missing, see
+    ///[`crate::rendering::graph::check_dependencies`]
+    fn get_required(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    ///Returns the resolution the extension renders at, relative to the frame buffer
+    ///
+    ///Extensions rendering at a different resolution receive their own color and depth
+    ///attachments, the color attachment is upsampled and blended over the `color` attachment
+    ///afterwards, see [`crate::rendering::graph`]
+    fn get_resolution(&self) -> AttachmentSize {
+        AttachmentSize::Full
+    }
 }
 
+///Extensions are compared by their priorities only, extensions with equal priorities compare
+///equal, the renderer orders them by their position in the slice passed to
+///[`crate::rendering::render`], see [`crate::rendering::graph`]
 impl std::cmp::PartialEq for dyn RenderingExtension {
     fn eq(&self, other: &Self) -> bool {
         self.get_priority().eq(&other.get_priority())
@@ -63,7 +372,6 @@ impl std::cmp::Ord for dyn RenderingExtension {
     }
 }
 
-#[derive(Default)]
 ///Basic renderer that renders all [`crate::components::mesh::Mesh`] components
 ///
 ///# Usage
@@ -89,12 +397,48 @@ pub struct Base {
     pub priority: u32,
     ///Clear color used for rendering
     pub clear_color: Color,
+    ///What the pass does with the color and depth attachments
+    pub ops: PassOps,
+    ///Resolution the extension renders at
+    pub resolution: AttachmentSize,
+    ///Bit mask of the render layers rendered by the extension, see
+    ///[`components::mesh::Mesh::set_layers`]
+    pub layers: u32,
     //Stores vector of (mesh_id, material_id) for caching
     identifier: Vec<(u128, u128)>,
-    v_buffers: Vec<wgpu::Buffer>,
+    //Scratch buffers, kept between frames to avoid allocating them every frame
+    //Indices of the visible meshes
+    visible: Vec<usize>,
+    //(mesh_id, material_id) of the visible meshes in the current frame
+    ids: Vec<(u128, u128)>,
+    //Instance buffer shared by all the groups, one per frame in flight, so that the buffers the gpu
+    //is still reading from are never written to
+    instances: Option<[wgpu::Buffer; FRAMES_IN_FLIGHT]>,
+    //Index of the instance buffer used in the current frame
+    frame: usize,
+    //Draw commands of the groups
+    draws: DrawCommands,
     mesh_materials: Vec<MeshMaterial>,
     num_instances: Vec<usize>,
     mesh_refs: Vec<Vec<ComponentReference<components::mesh::Mesh>>>,
+    //Indices of the textures of the materials of the meshes in the shared texture array
+    texture_indices: Vec<Vec<u32>>,
+    //Whether or not all the meshes in the instance buffer are static
+    static_buffers: Vec<bool>,
+    //Generation of the static meshes the buffers were written with
+    static_generation: u64,
+    //Number of frames the static buffers still need to be written for
+    static_dirty: usize,
+    //Skinned meshes, drawn separately from the instanced meshes
+    skinned: SkinnedDraws,
+    //Number of material swaps the buffers were updated for
+    material_generation: u64,
+}
+
+impl Default for Base {
+    fn default() -> Self {
+        Self::new_with_color(0, Color::default())
+    }
 }
 
 impl Base {
@@ -109,11 +453,24 @@ impl Base {
                 b: 0.0,
                 a: 1.0,
             },
+            ops: PassOps::new(),
+            resolution: AttachmentSize::Full,
+            layers: components::mesh::ALL_LAYERS,
             identifier: Vec::new(),
-            v_buffers: Vec::new(),
+            visible: Vec::new(),
+            ids: Vec::new(),
+            instances: None,
+            frame: 0,
+            draws: DrawCommands::new(),
             mesh_materials: Vec::new(),
             num_instances: Vec::new(),
             mesh_refs: Vec::new(),
+            texture_indices: Vec::new(),
+            static_buffers: Vec::new(),
+            static_generation: 0,
+            static_dirty: 0,
+            skinned: SkinnedDraws::new(),
+            material_generation: 0,
         }
     }
 
@@ -127,19 +484,517 @@ impl Base {
         Self {
             priority: order,
             clear_color: color,
+            ops: PassOps::new(),
+            resolution: AttachmentSize::Full,
+            layers: components::mesh::ALL_LAYERS,
             identifier: Vec::new(),
-            v_buffers: Vec::new(),
+            visible: Vec::new(),
+            ids: Vec::new(),
+            instances: None,
+            frame: 0,
+            draws: DrawCommands::new(),
             mesh_materials: Vec::new(),
             num_instances: Vec::new(),
             mesh_refs: Vec::new(),
+            texture_indices: Vec::new(),
+            static_buffers: Vec::new(),
+            static_generation: 0,
+            static_dirty: 0,
+            skinned: SkinnedDraws::new(),
+            material_generation: 0,
+        }
+    }
+
+    ///Determines which groups contain only static meshes
+    fn update_static_buffers(&mut self) {
+        self.static_buffers = static_groups(&self.mesh_refs);
+    }
+
+    ///Moves the meshes whose materials were swapped to the instances of their new materials
+    ///
+    ///Returns `false` if the visible meshes changed in other ways, in which case the buffers must
+    ///be rebuilt
+    fn swap_materials(
+        &mut self,
+        meshes: &[ComponentReference<components::mesh::Mesh>],
+        assets: &AssetStore,
+    ) -> bool {
+        if self.ids.len() != self.identifier.len()
+            || self
+                .ids
+                .iter()
+                .zip(&self.identifier)
+                .any(|(new, old)| new.0 != old.0)
+        {
+            return false;
+        }
+        for (i, (new, old)) in self.ids.iter().zip(&self.identifier).enumerate() {
+            if new.1 == old.1 {
+                continue;
+            }
+            let mesh = &meshes[self.visible[i]];
+            let (old_material, _) = material::batch(assets, old.1);
+            let (new_material, texture_index) = material::batch(assets, new.1);
+
+            //Static and dynamic instances of the same mesh and material are in separate groups
+            let Some((bucket, index)) = self
+                .mesh_materials
+                .iter()
+                .zip(&self.mesh_refs)
+                .enumerate()
+                .filter(|(_, (m, _))| **m == (old.0, old_material))
+                .find_map(|(bucket, (_, refs))| {
+                    refs.iter()
+                        .position(|m| m.ptr_eq(mesh))
+                        .map(|index| (bucket, index))
+                })
+            else {
+                return false;
+            };
+
+            //Both materials share the texture array, only the texture changes
+            if old_material == new_material {
+                self.texture_indices[bucket][index] = texture_index;
+                continue;
+            }
+
+            self.mesh_refs[bucket].swap_remove(index);
+            self.texture_indices[bucket].swap_remove(index);
+            self.num_instances[bucket] -= 1;
+            if self.num_instances[bucket] == 0 {
+                self.mesh_materials.remove(bucket);
+                self.num_instances.remove(bucket);
+                self.mesh_refs.remove(bucket);
+                self.texture_indices.remove(bucket);
+            }
+
+            let bucket = if let Some(bucket) = self
+                .mesh_materials
+                .iter()
+                .position(|m| *m == (new.0, new_material))
+            {
+                bucket
+            } else {
+                //Kept next to the other instances of the mesh
+                let bucket = self
+                    .mesh_materials
+                    .iter()
+                    .rposition(|m| m.mesh_id == new.0)
+                    .map_or(self.mesh_materials.len(), |b| b + 1);
+                self.mesh_materials
+                    .insert(bucket, MeshMaterial::new(new.0, new_material));
+                self.num_instances.insert(bucket, 0);
+                self.mesh_refs.insert(bucket, Vec::new());
+                self.texture_indices.insert(bucket, Vec::new());
+                bucket
+            };
+
+            self.mesh_refs[bucket].push(mesh.clone());
+            self.texture_indices[bucket].push(texture_index);
+            self.num_instances[bucket] += 1;
+        }
+
+        self.identifier.clone_from(&self.ids);
+        self.update_static_buffers();
+        //The number of instances stays the same, only the first instances of the groups move
+        self.draws.set(draw_commands(
+            &self.mesh_materials,
+            &self.num_instances,
+            assets,
+        ));
+        //The moved static meshes must be written into all the instance buffers
+        self.static_dirty = FRAMES_IN_FLIGHT;
+        true
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+type SkinBuffer = crate::wrappers::WgpuWrapper<wgpu::Buffer>;
+#[cfg(not(target_arch = "wasm32"))]
+type SkinBuffer = wgpu::Buffer;
+
+//Joints bind group and skin buffer of a skinned mesh
+type Skin = Result<(Arc<wgpu::BindGroup>, Arc<SkinBuffer>), validation::Warning>;
+
+///Uploads the joint matrices of the [`Animator`](components::animator::Animator) on the entity of
+///the skinned mesh, returns the bind group of the joints and the skin buffer of the mesh
+fn skin(
+    encoder: &mut wgpu::CommandEncoder,
+    world: &World,
+    assets: &AssetStore,
+    mesh: &components::mesh::Mesh,
+) -> Skin {
+    let entity = mesh.get_entity_id().unwrap_or_default();
+    let buffer = assets
+        .get_by_id::<Mesh>(mesh.get_mesh_id().unwrap())
+        .ok()
+        .and_then(|m| m.borrow().get_skin_buffer())
+        .ok_or(validation::Warning::SkinningUnsupported { entity })?;
+    let animator = world
+        .get_entity_by_id(entity)
+        .and_then(|e| e.borrow().get_component::<components::animator::Animator>())
+        .ok_or(validation::Warning::MissingAnimator { entity })?;
+    let bind_group = animator.borrow_mut().update_gpu(encoder);
+    Ok((bind_group, buffer))
+}
+
+///Returns `true` if the mesh is drawn skinned, which requires a skinned mesh asset, a material
+///supporting skinning and an [`Animator`](components::animator::Animator) on the entity,
+///otherwise the mesh is drawn in its bind pose with the other instances
+fn is_skinned(world: &World, assets: &AssetStore, mesh: &components::mesh::Mesh) -> bool {
+    mesh.get_mesh_id()
+        .and_then(|m| assets.get_by_id::<Mesh>(m).ok())
+        .is_some_and(|m| m.borrow().is_skinned())
+        && mesh
+            .get_material_id()
+            .and_then(|m| assets.get_by_id::<Material>(m).ok())
+            .is_some_and(|m| m.borrow().supports_skinning())
+        && mesh
+            .get_entity_id()
+            .and_then(|e| world.get_entity_by_id(e))
+            .is_some_and(|e| e.borrow().has_component::<components::animator::Animator>())
+}
+
+///Skinned meshes of a frame, every one of them has its own joints, so they can not be instanced
+///and are drawn one by one with the skinned pipelines of their materials
+pub(crate) struct SkinnedDraws {
+    //Meshes and the materials they are drawn with, sorted by the materials
+    meshes: Vec<(ComponentReference<components::mesh::Mesh>, u128)>,
+    //Instance data of all the meshes, one set per frame in flight
+    buffers: Option<[wgpu::Buffer; FRAMES_IN_FLIGHT]>,
+    frame: usize,
+    //Joints and skin buffers of the meshes
+    skins: Vec<Skin>,
+}
+
+impl Default for SkinnedDraws {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SkinnedDraws {
+    pub(crate) const fn new() -> Self {
+        Self {
+            meshes: Vec::new(),
+            buffers: None,
+            frame: 0,
+            skins: Vec::new(),
+        }
+    }
+
+    ///Moves the skinned meshes out of the visible meshes
+    pub(crate) fn partition(
+        &mut self,
+        visible: &mut Vec<usize>,
+        meshes: &[ComponentReference<components::mesh::Mesh>],
+        world: &World,
+        assets: &AssetStore,
+    ) {
+        self.meshes.clear();
+        visible.retain(|i| {
+            let m = meshes[*i].borrow();
+            if !is_skinned(world, assets, &m) {
+                return true;
+            }
+            let (material, _) = material::batch(assets, m.get_material_id().unwrap());
+            self.meshes.push((meshes[*i].clone(), material));
+            false
+        });
+        self.meshes.sort_by_key(|m| m.1);
+    }
+
+    ///Returns the number of skinned meshes drawn this frame
+    pub(crate) fn len(&self) -> usize {
+        self.meshes.len()
+    }
+
+    ///Writes the instance data and the joints of the meshes
+    pub(crate) fn update(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        world: &World,
+        assets: &AssetStore,
+    ) {
+        self.skins.clear();
+        if self.meshes.is_empty() {
+            return;
+        }
+        let device = DEVICE.get().unwrap();
+
+        let size = (self.meshes.len() * mem::size_of::<InstanceData>()) as u64;
+        if self.buffers.as_ref().is_none_or(|b| b[0].size() < size) {
+            self.buffers = Some(instance_buffers(
+                device,
+                self.meshes.len().next_power_of_two(),
+            ));
+        }
+        self.frame = (self.frame + 1) % FRAMES_IN_FLIGHT;
+        let buffer = &self.buffers.as_ref().unwrap()[self.frame];
+
+        {
+            let mut belt = STAGING_BELT.get().unwrap().write().unwrap();
+            let mut view =
+                belt.write_buffer(encoder, buffer, 0, NonZeroU64::new(size).unwrap(), device);
+            for ((m, _), data) in self
+                .meshes
+                .iter()
+                .zip(view.chunks_exact_mut(mem::size_of::<InstanceData>()))
+            {
+                let m = m.borrow();
+                let mut instance = m.get_instance_data();
+                instance.texture_index = material::batch(assets, m.get_material_id().unwrap()).1;
+                data.copy_from_slice(bytemuck::bytes_of(&instance));
+            }
+        }
+
+        for (_, m) in &self.meshes {
+            let m = assets.get_by_id::<Material>(*m).unwrap();
+            let mut m = m.borrow_mut();
+            if !matches!(m.get_bindgroup_state(), BindgroupState::Initialized) {
+                m.initialize_bindgroups(assets);
+            }
+        }
+
+        for (m, _) in &self.meshes {
+            let skin = skin(encoder, world, assets, &m.borrow());
+            self.skins.push(skin);
+        }
+    }
+
+    ///Draws the meshes, must be called after [`Self::update`]
+    pub(crate) fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, assets: &AssetStore) {
+        let Some(buffers) = &self.buffers else {
+            return;
+        };
+        let mut previous_mat = 0;
+
+        for (i, ((m, mat), skin)) in self.meshes.iter().zip(&self.skins).enumerate() {
+            let (joints, skin) = match skin {
+                Ok(skin) => skin,
+                Err(warning) => {
+                    validation::warn_once(warning.clone());
+                    continue;
+                }
+            };
+
+            if *mat != previous_mat {
+                let material = assets.get_by_id::<Material>(*mat).unwrap();
+                material.borrow().render_skinned(render_pass);
+            }
+            previous_mat = *mat;
+
+            let mesh_id = m.borrow().get_mesh_id().unwrap();
+            let mesh = assets.get_by_id::<Mesh>(mesh_id).unwrap();
+            let mesh = mesh.borrow();
+
+            #[cfg(debug_assertions)]
+            render_pass.insert_debug_marker(&format!("Skinned mesh {mesh_id}, material {mat}"));
+
+            let vert = unsafe { Arc::as_ptr(&mesh.get_vertex_buffer()).as_ref().unwrap() };
+            let ind = unsafe { Arc::as_ptr(&mesh.get_index_buffer()).as_ref().unwrap() };
+
+            render_pass.set_vertex_buffer(0, vert.slice(..));
+            render_pass.set_vertex_buffer(1, buffers[self.frame].slice(..));
+            render_pass.set_vertex_buffer(2, skin.slice(..));
+            render_pass.set_bind_group(JOINTS_BIND_GROUP_INDEX, joints, &[]);
+            render_pass.set_index_buffer(ind.slice(..), wgpu::IndexFormat::Uint32);
+
+            //Every mesh uses its own instance
+            let instance = i as u32;
+            render_pass.draw_indexed(0..mesh.get_index_count(), 0, instance..instance + 1);
+        }
+    }
+}
+
+///Creates a set of instance buffers for `capacity` instances, one for every frame in flight
+fn instance_buffers(device: &wgpu::Device, capacity: usize) -> [wgpu::Buffer; FRAMES_IN_FLIGHT] {
+    std::array::from_fn(|frame| {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("Instances: {capacity}, frame {frame}")),
+            size: (capacity * mem::size_of::<InstanceData>()) as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    })
+}
+
+///Creates the instance buffers shared by the instance groups, one for every frame in flight,
+///`None` if there are no instances
+pub(crate) fn instance_buffers_init(
+    contents: &[u8],
+    usage: wgpu::BufferUsages,
+) -> Option<[wgpu::Buffer; FRAMES_IN_FLIGHT]> {
+    if contents.is_empty() {
+        return None;
+    }
+    let device = DEVICE.get().unwrap();
+    let count = contents.len() / mem::size_of::<InstanceData>();
+    Some(std::array::from_fn(|frame| {
+        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("Instances: {count}, frame {frame}")),
+            contents,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST | usage,
+        })
+    }))
+}
+
+///Returns the draw commands of the instance groups, the instances of the groups follow each other
+///in the shared instance buffer
+pub(crate) fn draw_commands(
+    groups: &[MeshMaterial],
+    num_instances: &[usize],
+    assets: &AssetStore,
+) -> Vec<DrawIndexedIndirectArgs> {
+    let mut first_instance = 0;
+    groups
+        .iter()
+        .zip(num_instances)
+        .map(|(m, count)| {
+            let mesh = assets.get_by_id::<Mesh>(m.mesh_id).unwrap();
+            let command = DrawIndexedIndirectArgs {
+                index_count: mesh.borrow().get_index_count(),
+                instance_count: *count as u32,
+                first_index: 0,
+                base_vertex: 0,
+                first_instance,
+            };
+            first_instance += *count as u32;
+            command
+        })
+        .collect()
+}
+
+///Returns which groups contain only static meshes
+pub(crate) fn static_groups(
+    mesh_refs: &[Vec<ComponentReference<components::mesh::Mesh>>],
+) -> Vec<bool> {
+    mesh_refs
+        .iter()
+        .map(|m| m.iter().all(|m| m.borrow().get_static()))
+        .collect()
+}
+
+///Writes the instance data of the groups into their parts of the shared instance `buffer`, the
+///groups containing only static meshes are skipped unless `write_static` is set
+pub(crate) fn write_instances(
+    encoder: &mut wgpu::CommandEncoder,
+    buffer: &wgpu::Buffer,
+    commands: &[DrawIndexedIndirectArgs],
+    mesh_refs: &[Vec<ComponentReference<components::mesh::Mesh>>],
+    texture_indices: &[Vec<u32>],
+    static_groups: &[bool],
+    write_static: bool,
+) {
+    let mut belt = STAGING_BELT.get().unwrap().write().unwrap();
+    let device = DEVICE.get().unwrap();
+    let stride = mem::size_of::<InstanceData>() as u64;
+
+    for (((command, meshes), indices), is_static) in commands
+        .iter()
+        .zip(mesh_refs)
+        .zip(texture_indices)
+        .zip(static_groups)
+    {
+        //Data of static meshes doesn't change
+        if *is_static && !write_static {
+            continue;
+        }
+        let Some(size) = NonZeroU64::new(u64::from(command.instance_count) * stride) else {
+            continue;
+        };
+
+        //Write the instance data straight into the staging belt
+        let mut view = belt.write_buffer(
+            encoder,
+            buffer,
+            u64::from(command.first_instance) * stride,
+            size,
+            device,
+        );
+
+        for ((m, texture_index), data) in meshes
+            .iter()
+            .zip(indices)
+            .zip(view.chunks_exact_mut(mem::size_of::<InstanceData>()))
+        {
+            let mut instance = m.borrow().get_instance_data();
+            instance.texture_index = *texture_index;
+            data.copy_from_slice(bytemuck::bytes_of(&instance));
+        }
+    }
+}
+
+///Draws the instance groups with the `instances`, returns the number of draw calls
+///
+///Uses the indirect draws with the commands in the `draws` buffer if there is one, otherwise draws
+///the `commands` directly
+pub(crate) fn draw_groups<'a>(
+    render_pass: &mut wgpu::RenderPass<'a>,
+    assets: &AssetStore,
+    groups: &[MeshMaterial],
+    commands: &[DrawIndexedIndirectArgs],
+    instances: &'a wgpu::Buffer,
+    draws: Option<&'a wgpu::Buffer>,
+) -> usize {
+    render_pass.set_vertex_buffer(1, instances.slice(..));
+
+    let mut previous_mat = 0;
+    let mut draw_calls = 0;
+    let mut start = 0;
+
+    while start < groups.len() {
+        let m = groups[start];
+        //Groups of the same mesh and material, e.g. its static and dynamic instances, follow each
+        //other and are drawn together
+        let end = groups[start..]
+            .iter()
+            .position(|g| g.mesh_id != m.mesh_id || g.material_id != m.material_id)
+            .map_or(groups.len(), |i| start + i);
+
+        let mat = m.material_id;
+        if mat != previous_mat {
+            let mat = assets.get_by_id::<Material>(mat).unwrap();
+            let mat = mat.borrow();
+
+            mat.render(render_pass);
         }
+        previous_mat = mat;
+
+        let mesh = assets.get_by_id::<Mesh>(m.mesh_id).unwrap();
+        let mesh = mesh.borrow();
+
+        #[cfg(debug_assertions)]
+        render_pass.insert_debug_marker(&format!(
+            "Mesh {}, material {}, {} instances",
+            m.mesh_id,
+            m.material_id,
+            commands[start..end]
+                .iter()
+                .map(|c| c.instance_count)
+                .sum::<u32>()
+        ));
+
+        let vert = unsafe { Arc::as_ptr(&mesh.get_vertex_buffer()).as_ref().unwrap() };
+        let ind = unsafe { Arc::as_ptr(&mesh.get_index_buffer()).as_ref().unwrap() };
+
+        render_pass.set_vertex_buffer(0, vert.slice(..));
+        render_pass.set_index_buffer(ind.slice(..), wgpu::IndexFormat::Uint32);
+
+        draw_calls += match draws {
+            Some(draws) => indirect::draw(render_pass, draws, start..end),
+            None => indirect::draw_direct(render_pass, &commands[start..end]),
+        };
+        start = end;
     }
+    draw_calls
 }
 
+///Mesh and material of an instance group
 #[derive(Clone, Copy)]
-struct MeshMaterial {
-    mesh_id: u128,
-    material_id: u128,
+pub(crate) struct MeshMaterial {
+    pub(crate) mesh_id: u128,
+    pub(crate) material_id: u128,
 }
 
 impl PartialEq<(u128, u128)> for MeshMaterial {
@@ -149,7 +1004,7 @@ impl PartialEq<(u128, u128)> for MeshMaterial {
 }
 
 impl MeshMaterial {
-    const fn new(mesh_id: u128, material_id: u128) -> Self {
+    pub(crate) const fn new(mesh_id: u128, material_id: u128) -> Self {
         Self {
             mesh_id,
             material_id,
@@ -177,60 +1032,91 @@ impl RenderingExtension for Base {
         camera.update_gpu(encoder);
         trace!("Accquired camera");
 
+        lighting::update(world);
+
+        //Levels of detail change the meshes, so they're picked before the meshes are batched
+        let camera_transform = camera.camera_transform();
+        components::lod::select_levels(
+            world,
+            assets,
+            Vec3::new(
+                camera_transform.m03,
+                camera_transform.m13,
+                camera_transform.m23,
+            ),
+            &camera.inner.projection_type,
+        );
+
         //This is cached, so should be reasonably fast
         let binding = world
             .get_all_components::<crate::components::mesh::Mesh>()
             .unwrap_or_default();
 
-        let meshes = binding
-            .iter()
-            .filter(|i| i.borrow().get_visible())
-            .collect::<Vec<_>>();
+        //Indices of the visible meshes
+        self.visible.clear();
+        self.visible.extend(
+            binding
+                .iter()
+                .enumerate()
+                .filter(|(_, i)| {
+                    let m = i.borrow();
+                    m.get_visible()
+                        && m.is_on_layers(self.layers)
+                        && validation::renderable(&m, assets)
+                            .map_err(validation::warn_once)
+                            .is_ok()
+                })
+                .map(|(index, _)| index),
+        );
         trace!("Got all the meshes");
 
-        //List of materials used for rendering
-        let mut materials = VecSet::new();
-        //List of (mesh_ID, (transformation matrix, material_id))
-        let mut matrices = Vec::new();
-
-        //Collect all the matrices
-        for m in &meshes {
-            let m = m.borrow();
-            materials.insert(m.get_material_id().unwrap());
-            matrices.push((
-                m.get_mesh_id().unwrap(),
-                (m.get_matrix(), m.get_material_id().unwrap()),
-            ));
-        }
-
-        //What is even going on here?
-        //I... don't know...
+        //Skinned meshes are not instanced
+        self.skinned
+            .partition(&mut self.visible, &binding, world, assets);
 
-        let mut matrices = matrices
-            .iter()
-            .zip(meshes)
-            .map(|i| (i.0 .0, (i.0 .1 .0, i.0 .1 .1, i.1)))
-            .collect::<Vec<_>>();
+        //List of (mesh_ID, material_id), used to determine if the cache can be reused
+        self.ids.clear();
+        self.ids.extend(self.visible.iter().map(|i| {
+            let m = binding[*i].borrow();
+            (
+                geometry_id(assets, m.get_mesh_id().unwrap()),
+                m.get_material_id().unwrap(),
+            )
+        }));
 
         //determine if can re use cache
-        let mut identical = true;
+        let mut identical = self.ids == self.identifier;
 
-        if matrices.len() == self.identifier.len() {
-            for (index, data) in self.identifier.iter().enumerate() {
-                if data.0 == matrices[index].0 && data.1 == matrices[index].1 .1 {
-                    continue;
-                }
-                identical = false;
-                break;
-            }
-        } else {
-            identical = false;
+        //Materials of some of the meshes were swapped, only those meshes need to be moved
+        let generation = components::mesh::material_generation();
+        if generation != self.material_generation {
+            self.material_generation = generation;
+            identical = identical || self.swap_materials(&binding, assets);
         }
 
         #[allow(clippy::if_not_else)]
         if !identical {
             debug!("Generating new cache data");
-            self.identifier = matrices.iter().map(|i| (i.0, i.1 .1)).collect::<Vec<_>>();
+            mem::swap(&mut self.identifier, &mut self.ids);
+
+            //List of (mesh_ID, (instance data, material_id, mesh reference, static))
+            let mut matrices = self
+                .visible
+                .iter()
+                .map(|i| {
+                    let mesh = &binding[*i];
+                    let m = mesh.borrow();
+                    //Materials sharing the texture array are drawn together
+                    let (material, texture_index) =
+                        material::batch(assets, m.get_material_id().unwrap());
+                    let mut data = m.get_instance_data();
+                    data.texture_index = texture_index;
+                    (
+                        geometry_id(assets, m.get_mesh_id().unwrap()),
+                        (data, material, mesh, m.get_static()),
+                    )
+                })
+                .collect::<Vec<_>>();
 
             //Sort meshes by mesh id for easier buffer creation
             //NO Sort by material id?
@@ -252,65 +1138,49 @@ impl RenderingExtension for Base {
             //Guarantee that there's at least 1 window
             split_points.push(matrices.len());
 
-            //assemble vertex buffers
-            let mut v_buffers = Vec::new();
-
-            let device = DEVICE.get().unwrap();
-
             let mut mesh_materials = Vec::new();
             let mut num_instances = Vec::new();
 
             let mut mesh_refs = Vec::new();
+            let mut texture_indices = Vec::new();
+
+            //Instance data of all the groups, one after another
+            let mut instances = Vec::with_capacity(matrices.len() * mem::size_of::<InstanceData>());
 
             for m in split_points.windows(2) {
                 //beginning and end of the window
                 let points = (*m.first().unwrap(), *m.last().unwrap());
 
-                //Label for easier debugging
-                let label = format!("Instances: {}..{}", m.first().unwrap(), m.last().unwrap());
-
-                //(mesh_ID, (transformation matrix, material_id, mesh reference));
+                //(mesh_ID, (transformation matrix, material_id, mesh reference, static));
                 let mut current_window = matrices[points.0..points.1].iter().collect::<Vec<_>>();
 
-                //Split into vectors and sorted by material
-                //Sort the window by materials
-                current_window.sort_unstable_by(|s, o| s.1 .1.cmp(&o.1 .1));
+                //Split into vectors and sorted by material, static meshes are kept in their own
+                //groups so that their instance data is not rewritten every frame
+                current_window.sort_unstable_by_key(|m| (m.1 .1, m.1 .3));
 
                 //find where materials change, similar to how meshes were sorted
                 let mut material_split_points = Vec::new();
-                let mut old = 0;
+                let mut old = None;
                 for (i, m) in current_window.iter().enumerate() {
-                    if m.1 .1 != old {
+                    if old != Some((m.1 .1, m.1 .3)) {
                         material_split_points.push(i);
-                        old = m.1 .1;
+                        old = Some((m.1 .1, m.1 .3));
                     }
                 }
                 //Again ensure there's at least one window
                 material_split_points.push(current_window.len());
 
-                let mut last = MeshMaterial {
-                    mesh_id: 0,
-                    material_id: 0,
-                };
-
-                //Need to iterate over it twice...
-                //Get indicators for every block of what mesh and material they are
-                for i in &material_split_points[..material_split_points.len() - 1] {
-                    let curent = current_window[*i];
-                    if last != (curent.0, curent.1 .1) {
-                        last = MeshMaterial::new(curent.0, curent.1 .1);
-                        mesh_materials.push(last);
-                    }
-                }
-
-                //AGAIN!?!?
-                //Create vertex buffers for matrices
+                //Create the groups of instances
                 for m in material_split_points.windows(2) {
                     //Now this is stored per mesh per material
                     let points = (*m.first().unwrap(), *m.last().unwrap());
+                    let current_window = &current_window[points.0..points.1];
 
+                    mesh_materials.push(MeshMaterial::new(
+                        current_window[0].0,
+                        current_window[0].1 .1,
+                    ));
                     num_instances.push(points.1 - points.0);
-                    let current_window = &current_window[points.0..points.1];
 
                     //Copy mesh references
                     mesh_refs.push(
@@ -319,31 +1189,24 @@ impl RenderingExtension for Base {
                             .map(|i| i.1 .2.clone())
                             .collect::<Vec<_>>(),
                     );
+                    texture_indices.push(
+                        current_window
+                            .iter()
+                            .map(|i| i.1 .0.texture_index)
+                            .collect::<Vec<_>>(),
+                    );
 
-                    let matrices = current_window
-                        .iter()
-                        .flat_map(|i| bytemuck::bytes_of(&i.1 .0))
-                        .copied()
-                        .collect::<Vec<u8>>();
-
-                    v_buffers.push(
-                        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                            label: Some(&label),
-                            contents: &matrices,
-                            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                        }),
+                    instances.extend(
+                        current_window
+                            .iter()
+                            .flat_map(|i| bytemuck::bytes_of(&i.1 .0)),
                     );
                 }
             }
             //Check if they're the same length
             assert_eq!(
-                v_buffers.len(),
-                mesh_materials.len(),
-                "You are a moron, they're not the same"
-            );
-            assert_eq!(
-                v_buffers.len(),
                 mesh_refs.len(),
+                mesh_materials.len(),
                 "You are stupid, they're not the same"
             );
             assert_eq!(
@@ -352,43 +1215,48 @@ impl RenderingExtension for Base {
                 "You are an idiot, they're not the same"
             );
 
-            self.v_buffers = v_buffers;
+            self.instances = instance_buffers_init(&instances, wgpu::BufferUsages::empty());
+            self.frame = 0;
+            self.draws
+                .set(draw_commands(&mesh_materials, &num_instances, assets));
             self.mesh_materials = mesh_materials;
             self.num_instances = num_instances;
             self.mesh_refs = mesh_refs;
-        } else {
+            self.texture_indices = texture_indices;
+            self.update_static_buffers();
+            self.static_generation = components::mesh::static_generation();
+            self.static_dirty = 0;
+        } else if let Some(buffers) = &self.instances {
             //Reusing data
             trace!("Cache exists, updating v buffers");
-            let mut belt = STAGING_BELT.get().unwrap().write().unwrap();
-            let device = DEVICE.get().unwrap();
 
-            for (buffer, meshes) in self.v_buffers.iter().zip(self.mesh_refs.iter()) {
-                //I do have to collect here
-                let matrices = meshes
-                    .iter()
-                    .map(|m| m.borrow().get_matrix())
-                    .collect::<Vec<_>>();
+            //Move on to the next set of buffers
+            self.frame = (self.frame + 1) % FRAMES_IN_FLIGHT;
 
-                let matrix_data = matrices
-                    .iter()
-                    .flat_map(bytemuck::bytes_of)
-                    .copied()
-                    .collect::<Vec<u8>>();
-
-                belt.write_buffer(
-                    encoder,
-                    buffer,
-                    0,
-                    NonZeroU64::new(buffer.size()).unwrap(),
-                    device,
-                )
-                .copy_from_slice(matrix_data.as_slice());
+            //Static meshes changed, all of the buffer sets need to be updated
+            let generation = components::mesh::static_generation();
+            if generation != self.static_generation {
+                self.static_generation = generation;
+                self.static_dirty = FRAMES_IN_FLIGHT;
+                self.static_buffers = static_groups(&self.mesh_refs);
             }
+            let write_static = self.static_dirty > 0;
+            self.static_dirty = self.static_dirty.saturating_sub(1);
+
+            write_instances(
+                encoder,
+                &buffers[self.frame],
+                self.draws.commands(),
+                &self.mesh_refs,
+                &self.texture_indices,
+                &self.static_buffers,
+                write_static,
+            );
         }
 
         //Initialize bindgroups for all needed materials
-        for m in materials {
-            let m = assets.get_by_id::<Material>(m).unwrap();
+        for m in &self.mesh_materials {
+            let m = assets.get_by_id::<Material>(m.material_id).unwrap();
             let mut m = m.borrow_mut();
 
             if matches!(m.get_bindgroup_state(), BindgroupState::Initialized) {
@@ -397,65 +1265,53 @@ impl RenderingExtension for Base {
             m.initialize_bindgroups(assets);
         }
 
+        self.skinned.update(encoder, world, assets);
+
+        let clear_color = camera.background.clear_color(self.clear_color);
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("First pass"),
+            label: Some("Base pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
                 view: &attachments.color,
                 resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(self.clear_color.into()),
-                    store: wgpu::StoreOp::Store,
-                },
+                ops: self.ops.color_ops(attachments, clear_color.into()),
             })],
             depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
                 view: &attachments.depth_stencil,
-                depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.0),
-                    store: wgpu::StoreOp::Store,
-                }),
+                depth_ops: Some(self.ops.depth_ops(attachments)),
                 stencil_ops: None,
             }),
             timestamp_writes: None,
             occlusion_query_set: None,
         });
 
+        viewport::apply(&mut render_pass);
+
         //Set the camera
         camera.set_bindgroup(&mut render_pass);
 
-        let mut previous_mat = 0;
-
-        //Iterate through the meshes and render them
-        for (i, m) in self.mesh_materials.iter().enumerate() {
-            let mat = m.material_id;
-
-            if mat != previous_mat {
-                let mat = assets.get_by_id::<Material>(mat).unwrap();
-                let mat = mat.borrow();
-
-                mat.render(&mut render_pass);
-            }
-            previous_mat = mat;
-
-            let mesh = assets.get_by_id::<Mesh>(m.mesh_id).unwrap();
-            let mesh = mesh.borrow();
-
-            let vert = unsafe { Arc::as_ptr(&mesh.get_vertex_buffer()).as_ref().unwrap() };
-            let ind = unsafe { Arc::as_ptr(&mesh.get_index_buffer()).as_ref().unwrap() };
-
-            render_pass.set_vertex_buffer(0, vert.slice(..));
-            render_pass.set_vertex_buffer(1, self.v_buffers[i].slice(..));
-
-            render_pass.set_index_buffer(ind.slice(..), wgpu::IndexFormat::Uint32);
-            render_pass.draw_indexed(
-                0..mesh.get_index_count(),
-                0,
-                0..(self.num_instances[i] as u32),
+        if let Some(buffers) = &self.instances {
+            draw_groups(
+                &mut render_pass,
+                assets,
+                &self.mesh_materials,
+                self.draws.commands(),
+                &buffers[self.frame],
+                self.draws.buffer(),
             );
         }
+        self.skinned.draw(&mut render_pass, assets);
         drop(render_pass);
     }
 
     fn get_priority(&self) -> u32 {
         self.priority
     }
+
+    fn get_reads(&self) -> &'static [&'static str] {
+        &["shadow", "lights"]
+    }
+
+    fn get_resolution(&self) -> AttachmentSize {
+        self.resolution
+    }
 }
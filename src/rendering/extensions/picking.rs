@@ -0,0 +1,551 @@
+//! Picking of entities under the cursor
+//!
+//! The [`Picking`] extension renders ids of all the visible meshes into an `R32Uint` attachment,
+//! when a pick was requested using [`pick_pixel`]. Every instance gets its own id, the id of the
+//! first instance of a draw is passed in its [`DrawData`], so picking works correctly with
+//! instanced meshes.
+//!
+//! The picking pass uses the same vertex transformation as the base shaders, including the
+//! skinning of animated meshes, so meshes deformed in the vertex shaders of custom materials are
+//! picked using their undeformed geometry.
+use std::{
+    mem,
+    ops::Range,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use wgpu::util::DeviceExt;
+
+use crate::{
+    asset_managment::AssetStore,
+    assets::{shader, Mesh, Preprocessor},
+    components::{self, camera::MainCamera},
+    ecs::{World, UUID},
+    grimoire,
+    rendering::{
+        depth,
+        draw_data::{DrawData, DrawDataBinding},
+        viewport,
+    },
+    structures::InstanceData,
+    validation, DEVICE, RESOLUTION,
+};
+
+use super::{is_skinned, skin, AttachmentData, RenderingExtension, SkinBuffer};
+
+#[derive(Default)]
+struct PickState {
+    //Pixel to pick in the next frame
+    requested: Option<(u32, u32)>,
+    //Last resolved pick
+    result: Option<((u32, u32), Option<UUID>)>,
+}
+
+static STATE: RwLock<PickState> = RwLock::new(PickState {
+    requested: None,
+    result: None,
+});
+
+///Returns the id of the entity whose mesh covers the pixel, (0, 0) being the top left corner of
+///the window
+///
+///Requires the [`Picking`] extension to be rendered. The id is read back from the gpu
+///asynchronously, so the first call for a pixel returns `None` and the result becomes available
+///a couple of frames later. Every call requests the pixel again, so calling this every frame keeps
+///the result up to date.
+#[must_use]
+pub fn pick_pixel(x: u32, y: u32) -> Option<UUID> {
+    let mut state = STATE.write().unwrap();
+    state.requested = Some((x, y));
+
+    match state.result {
+        Some((pixel, id)) if pixel == (x, y) => id,
+        _ => None,
+    }
+}
+
+fn resolve(pixel: (u32, u32), id: Option<UUID>) {
+    STATE.write().unwrap().result = Some((pixel, id));
+}
+
+const ID_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Uint;
+//Bind group of the joints of the skinned pipeline
+const JOINTS_GROUP: u32 = 1;
+
+///Returns the bind group of the draw data, used when push constants are not supported
+const fn draw_data_group(skinned: bool) -> u32 {
+    if skinned {
+        2
+    } else {
+        1
+    }
+}
+
+//Mesh drawn with all of its instances, skinned meshes are drawn one by one
+struct Draw {
+    mesh: u128,
+    instances: Range<usize>,
+    skin: Option<(Arc<wgpu::BindGroup>, Arc<SkinBuffer>)>,
+}
+
+enum Readback {
+    //No readback is in progress
+    Idle,
+    //The pixel was copied into the readback buffer, ids are the entities of the instances
+    Copied {
+        pixel: (u32, u32),
+        ids: Vec<UUID>,
+    },
+    //The readback buffer is being mapped
+    Mapping {
+        pixel: (u32, u32),
+        ids: Vec<UUID>,
+        mapped: Arc<AtomicBool>,
+    },
+}
+
+struct Targets {
+    width: u32,
+    height: u32,
+    ids: wgpu::TextureView,
+    id_texture: wgpu::Texture,
+    depth: wgpu::TextureView,
+}
+
+///Renders the ids of the entities for [`pick_pixel`]
+///
+///Only renders when a pick was requested, uses its own attachments, so it can be placed anywhere
+///in the extension list
+pub struct Picking {
+    ///Priority of the extension
+    pub priority: u32,
+    pipeline: Option<wgpu::RenderPipeline>,
+    skinned_pipeline: Option<wgpu::RenderPipeline>,
+    draw_data: Option<DrawDataBinding>,
+    targets: Option<Targets>,
+    readback_buffer: Option<wgpu::Buffer>,
+    readback: Readback,
+}
+
+impl Default for Picking {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl Picking {
+    ///Creates a new [`Picking`] extension
+    #[must_use]
+    pub const fn new(priority: u32) -> Self {
+        Self {
+            priority,
+            pipeline: None,
+            skinned_pipeline: None,
+            draw_data: None,
+            targets: None,
+            readback_buffer: None,
+            readback: Readback::Idle,
+        }
+    }
+
+    ///Creates the picking pipeline, the `skinned` variant transforms the vertices by the joints
+    fn create_pipeline(draw_data: &DrawDataBinding, skinned: bool) -> wgpu::RenderPipeline {
+        let device = DEVICE.get().unwrap();
+
+        let defines: &[&str] = if skinned { &["SKINNED"] } else { &[] };
+        let (source, map) = Preprocessor::new()
+            .with_defines(defines)
+            .with_include(
+                "draw_data.wgsl",
+                &draw_data.wgsl_declaration(draw_data_group(skinned)),
+            )
+            .process("picking.wgsl", include_str!("../../shaders/picking.wgsl"))
+            .expect("Failed to process the picking shader");
+        let shader =
+            shader::compile_mapped(&source, &map).expect("Failed to compile the picking shader");
+
+        let cam_bind_group_layout =
+            device.create_bind_group_layout(&grimoire::CAMERA_BIND_GROUP_LAYOUT_DESCRIPTOR);
+
+        let joints_bind_group_layout =
+            device.create_bind_group_layout(&grimoire::JOINTS_BIND_GROUP_LAYOUT_DESCRIPTOR);
+
+        let mut bind_group_layouts = vec![&cam_bind_group_layout];
+        if skinned {
+            bind_group_layouts.push(&joints_bind_group_layout);
+        }
+        bind_group_layouts.extend(draw_data.bind_group_layout());
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Picking pipeline layout"),
+            bind_group_layouts: &bind_group_layouts,
+            push_constant_ranges: draw_data.push_constant_ranges(),
+        });
+
+        let buffers = crate::assets::materials::helpers::skinned_vertex_binding();
+        //The skin data is only used by the skinned variant
+        let buffers = if skinned { &buffers[..] } else { &buffers[..2] };
+
+        crate::errors::scoped("Picking pipeline", || {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Picking pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vertex",
+                    buffers,
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: Some(wgpu::Face::Back),
+                    unclipped_depth: false,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    conservative: false,
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: depth::format(),
+                    depth_write_enabled: true,
+                    depth_compare: depth::compare_function(),
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fragment",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: ID_FORMAT,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                }),
+                multiview: None,
+            })
+        })
+    }
+
+    fn create_targets(width: u32, height: u32) -> Targets {
+        let device = DEVICE.get().unwrap();
+
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        let id_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Picking ids"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: ID_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        //The ids are not multisampled, so neither is their depth
+        let depth = device.create_texture(&wgpu::TextureDescriptor {
+            sample_count: 1,
+            ..crate::windowing::get_depth_descriptor(width, height)
+        });
+
+        Targets {
+            width,
+            height,
+            ids: id_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            id_texture,
+            depth: depth.create_view(&wgpu::TextureViewDescriptor::default()),
+        }
+    }
+
+    ///Advances the readback of the previous picks
+    fn update_readback(&mut self) {
+        match mem::replace(&mut self.readback, Readback::Idle) {
+            Readback::Idle => {}
+            //The frame with the copy was submitted, the buffer can be mapped now
+            Readback::Copied { pixel, ids } => {
+                let mapped = Arc::new(AtomicBool::new(false));
+                let flag = mapped.clone();
+
+                self.readback_buffer.as_ref().unwrap().slice(..).map_async(
+                    wgpu::MapMode::Read,
+                    move |r| {
+                        if r.is_err() {
+                            log::error!("Failed to map the picking buffer");
+                        }
+                        flag.store(true, Ordering::Release);
+                    },
+                );
+
+                self.readback = Readback::Mapping { pixel, ids, mapped };
+            }
+            Readback::Mapping { pixel, ids, mapped } => {
+                if !mapped.load(Ordering::Acquire) {
+                    self.readback = Readback::Mapping { pixel, ids, mapped };
+                    return;
+                }
+
+                let buffer = self.readback_buffer.as_ref().unwrap();
+                let index = {
+                    let view = buffer.slice(..).get_mapped_range();
+                    *bytemuck::from_bytes::<u32>(&view[..4])
+                };
+                buffer.unmap();
+
+                //0 is the background
+                resolve(
+                    pixel,
+                    (index as usize)
+                        .checked_sub(1)
+                        .and_then(|i| ids.get(i).copied()),
+                );
+            }
+        }
+    }
+}
+
+impl RenderingExtension for Picking {
+    fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        world: &World,
+        assets: &AssetStore,
+        _: &AttachmentData,
+    ) {
+        self.update_readback();
+
+        if !matches!(self.readback, Readback::Idle) {
+            return;
+        }
+
+        let Some(pixel) = STATE.write().unwrap().requested.take() else {
+            return;
+        };
+
+        let resolution = *RESOLUTION.read().unwrap();
+        if pixel.0 >= resolution.width || pixel.1 >= resolution.height {
+            resolve(pixel, None);
+            return;
+        }
+
+        let device = DEVICE.get().unwrap();
+
+        if self.pipeline.is_none() {
+            let draw_data = DrawDataBinding::new();
+            self.pipeline = Some(Self::create_pipeline(&draw_data, false));
+            self.skinned_pipeline = Some(Self::create_pipeline(&draw_data, true));
+            self.draw_data = Some(draw_data);
+        }
+        if !self
+            .targets
+            .as_ref()
+            .is_some_and(|t| t.width == resolution.width && t.height == resolution.height)
+        {
+            self.targets = Some(Self::create_targets(resolution.width, resolution.height));
+        }
+        let readback_buffer = self.readback_buffer.get_or_insert_with(|| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Picking readback"),
+                size: 4,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            })
+        });
+
+        let binding = world
+            .get_all_components::<MainCamera>()
+            .expect("Could not find the main camera");
+        let camera = binding.first().unwrap().borrow();
+        camera.update_gpu(encoder);
+
+        //Entity id, mesh id, mesh component and whether or not it's skinned, of every visible
+        //mesh, sorted by mesh, skinned meshes last
+        let mut meshes = world
+            .get_all_entities_with_component::<components::mesh::Mesh>()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|e| {
+                let e = e.borrow();
+                let component = e.get_component::<components::mesh::Mesh>()?;
+                let m = component.borrow();
+                if !m.get_visible() {
+                    return None;
+                }
+                let (mesh, _) = validation::renderable(&m, assets)
+                    .map_err(validation::warn_once)
+                    .ok()?;
+                let skinned = is_skinned(world, assets, &m);
+                drop(m);
+                Some((e.get_id(), mesh, component, skinned))
+            })
+            .collect::<Vec<_>>();
+        meshes.sort_by_key(|m| (m.3, m.1));
+
+        let ids = meshes.iter().map(|m| m.0).collect::<Vec<_>>();
+        let instances = meshes
+            .iter()
+            .map(|m| m.2.borrow().get_instance_data())
+            .collect::<Vec<_>>();
+
+        let mut draws = Vec::new();
+        let mut start = 0;
+        while start < meshes.len() {
+            let (_, mesh, component, skinned) = &meshes[start];
+            if *skinned {
+                match skin(encoder, world, assets, &component.borrow()) {
+                    Ok(skin) => draws.push(Draw {
+                        mesh: *mesh,
+                        instances: start..start + 1,
+                        skin: Some(skin),
+                    }),
+                    Err(warning) => {
+                        validation::warn_once(warning);
+                    }
+                }
+                start += 1;
+                continue;
+            }
+
+            let end = meshes[start..]
+                .iter()
+                .position(|m| m.1 != *mesh || m.3)
+                .map_or(meshes.len(), |i| start + i);
+            draws.push(Draw {
+                mesh: *mesh,
+                instances: start..end,
+                skin: None,
+            });
+            start = end;
+        }
+
+        //0 is the background, the ids of the instances follow the id of the first one
+        let draw_data = self.draw_data.as_mut().unwrap();
+        draw_data.prepare(
+            &draws
+                .iter()
+                .map(|d| DrawData {
+                    object_id: d.instances.start as u32 + 1,
+                    ..Default::default()
+                })
+                .collect::<Vec<_>>(),
+        );
+
+        let instance_buffer = (!instances.is_empty()).then(|| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Picking instances"),
+                contents: bytemuck::cast_slice(&instances),
+                usage: wgpu::BufferUsages::VERTEX,
+            })
+        });
+
+        let targets = self.targets.as_ref().unwrap();
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Picking pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &targets.ids,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &targets.depth,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(depth::clear_value()),
+                        store: wgpu::StoreOp::Discard,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            //Keep the ids aligned with the rendered image
+            viewport::apply(&mut render_pass);
+
+            if let Some(instance_buffer) = &instance_buffer {
+                render_pass.set_pipeline(self.pipeline.as_ref().unwrap());
+                camera.set_bindgroup(&mut render_pass);
+
+                let draw_data = self.draw_data.as_ref().unwrap();
+                let stride = mem::size_of::<InstanceData>() as u64;
+                for (i, draw) in draws.iter().enumerate() {
+                    //Skinned meshes are drawn last, every one with its own joints
+                    if let Some((joints, skin)) = &draw.skin {
+                        render_pass.set_pipeline(self.skinned_pipeline.as_ref().unwrap());
+                        render_pass.set_bind_group(JOINTS_GROUP, joints, &[]);
+                        render_pass.set_vertex_buffer(2, skin.slice(..));
+                    }
+
+                    let mesh = assets.get_by_id::<Mesh>(draw.mesh).unwrap();
+                    let mesh = mesh.borrow();
+
+                    let vert = unsafe { Arc::as_ptr(&mesh.get_vertex_buffer()).as_ref().unwrap() };
+                    let ind = unsafe { Arc::as_ptr(&mesh.get_index_buffer()).as_ref().unwrap() };
+
+                    render_pass.set_vertex_buffer(0, vert.slice(..));
+                    render_pass.set_vertex_buffer(
+                        1,
+                        instance_buffer.slice(
+                            draw.instances.start as u64 * stride
+                                ..draw.instances.end as u64 * stride,
+                        ),
+                    );
+                    render_pass.set_index_buffer(ind.slice(..), wgpu::IndexFormat::Uint32);
+                    draw_data.set(&mut render_pass, draw_data_group(draw.skin.is_some()), i);
+                    render_pass.draw_indexed(
+                        0..mesh.get_index_count(),
+                        0,
+                        0..draw.instances.len() as u32,
+                    );
+                }
+            }
+        }
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &targets.id_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: pixel.0,
+                    y: pixel.1,
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: readback_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: None,
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        self.readback = Readback::Copied { pixel, ids };
+    }
+
+    fn get_priority(&self) -> u32 {
+        self.priority
+    }
+
+    fn get_attachments(&self) -> &'static [&'static str] {
+        &["picking"]
+    }
+}
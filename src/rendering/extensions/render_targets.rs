@@ -0,0 +1,334 @@
+//! Rendering of the cameras with a render target
+//!
+//! The [`RenderTargets`] extension renders the meshes of the world with every enabled
+//! [`Camera`] that has a [`Camera::target`] into the target texture, which can then be used by
+//! the materials like any other texture, e.g. for mirrors, security cameras or portals.
+//!
+//!```no_run
+//! use lunar_engine::{
+//!     asset_managment::AssetStore,
+//!     assets::{materials::TextureUnlit, Texture},
+//!     components::{camera::Camera, transform::Transform},
+//!     ecs::{EntityBuilder, World},
+//!     rendering::extensions::{Base, RenderTargets},
+//! };
+//!
+//! let mut world = World::new();
+//! let mut assets = AssetStore::new();
+//!
+//! let screen = assets.register(Texture::render_target(256, 256));
+//! let material = assets.register(TextureUnlit::new(screen));
+//!
+//! world.add_entity(
+//!     EntityBuilder::new()
+//!         .add_component::<Transform>()
+//!         .create_component(|| {
+//!             let mut camera = Camera::default();
+//!             camera.target = Some(screen);
+//!             camera
+//!         })
+//!         .create()
+//!         .unwrap(),
+//! );
+//!
+//! //Rendered before the extensions using the textures
+//! let mut targets = RenderTargets::new(0);
+//! let mut base = Base::new(1);
+//!```
+use std::collections::HashMap;
+
+use crate::{
+    asset_managment::{AssetStore, UUID},
+    assets::Texture,
+    components::{camera::Camera, mesh::ALL_LAYERS},
+    ecs::World,
+    rendering::{
+        self, depth,
+        fullscreen::{self, FullscreenPipeline},
+        lighting, msaa,
+    },
+    structures::Color,
+    DEVICE,
+};
+
+use super::{scene::SceneDraws, AttachmentData, RenderingExtension};
+
+//Textures the scene is rendered into before being copied into the target
+struct TargetState {
+    //Multisampled color, if multisampling is enabled
+    multisampled: Option<wgpu::Texture>,
+    color: wgpu::Texture,
+    depth: wgpu::Texture,
+    //Color texture read by the copy pass
+    bind_group: wgpu::BindGroup,
+    scene: SceneDraws,
+    //Size, color attachment and depth range the state was created for
+    key: ((u32, u32), (u32, wgpu::TextureFormat), bool),
+}
+
+struct CopyState {
+    pipeline: FullscreenPipeline,
+    layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+}
+
+///Renders the cameras with a [`Camera::target`] into their target textures
+///
+///Should be rendered before the extensions rendering the meshes using the targets. The cameras
+///use the pipelines of the materials, so they have the same lighting as the scene. A texture
+///rendered by a camera seeing it shows the image of the previous frame.
+pub struct RenderTargets {
+    ///Priority of the extension
+    pub priority: u32,
+    targets: HashMap<UUID, TargetState>,
+    copy: Option<CopyState>,
+}
+
+impl RenderTargets {
+    ///Creates a new render targets extension
+    #[must_use]
+    pub fn new(priority: u32) -> Self {
+        Self {
+            priority,
+            targets: HashMap::new(),
+            copy: None,
+        }
+    }
+
+    fn create_copy() -> CopyState {
+        let device = DEVICE.get().unwrap();
+
+        let shader = fullscreen::compile(
+            "render_target.wgsl",
+            include_str!("../../shaders/render_target.wgsl"),
+        )
+        .expect("Failed to compile the render target shader");
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Render target bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline = FullscreenPipeline::new(
+            "Render target pipeline",
+            &shader,
+            &[&layout],
+            wgpu::ColorTargetState {
+                //Format of the textures
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            },
+            wgpu::MultisampleState::default(),
+        );
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Render target sampler"),
+            ..Default::default()
+        });
+
+        CopyState {
+            pipeline,
+            layout,
+            sampler,
+        }
+    }
+
+    fn create_target(
+        copy: &CopyState,
+        key: ((u32, u32), (u32, wgpu::TextureFormat), bool),
+    ) -> TargetState {
+        let device = DEVICE.get().unwrap();
+        let ((width, height), (samples, format), _) = key;
+
+        let texture = |label, format, samples, usage| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: samples,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage,
+                view_formats: &[],
+            })
+        };
+        let multisampled = (samples > 1).then(|| msaa::create_color(width, height, samples));
+        let color = texture(
+            "Render target color",
+            format,
+            1,
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        );
+        let depth = texture(
+            "Render target depth",
+            depth::format(),
+            samples,
+            wgpu::TextureUsages::RENDER_ATTACHMENT,
+        );
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Render target bind group"),
+            layout: &copy.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(
+                        &color.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&copy.sampler),
+                },
+            ],
+        });
+
+        TargetState {
+            multisampled,
+            color,
+            depth,
+            bind_group,
+            scene: SceneDraws::new("Render target instances"),
+            key,
+        }
+    }
+}
+
+impl RenderingExtension for RenderTargets {
+    fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        world: &World,
+        assets: &AssetStore,
+        _: &AttachmentData,
+    ) {
+        let cameras = world
+            .get_all_components::<Camera>()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|c| c.borrow().enabled && c.borrow().target.is_some())
+            .collect::<Vec<_>>();
+        if cameras.is_empty() {
+            return;
+        }
+        let copy = self.copy.get_or_insert_with(Self::create_copy);
+        lighting::update(world);
+
+        for camera in cameras {
+            let camera = camera.borrow();
+            let id = camera.target.unwrap();
+            let Ok(texture) = assets.get_by_id::<Texture>(id) else {
+                log::error!("Render target {id} does not exist");
+                continue;
+            };
+            let texture = texture.borrow();
+            if !texture.is_render_target() {
+                log::error!("Texture {id} is not a render target");
+                continue;
+            }
+            let Some(output) = texture.texture.as_ref() else {
+                continue;
+            };
+            let (width, height) = (output.width(), output.height());
+            let output = output.create_view(&wgpu::TextureViewDescriptor::default());
+            drop(texture);
+
+            let key = (
+                (width, height),
+                rendering::color_targets(),
+                depth::is_reversed(),
+            );
+            let state = self
+                .targets
+                .entry(id)
+                .or_insert_with(|| Self::create_target(copy, key));
+            if state.key != key {
+                *state = Self::create_target(copy, key);
+            }
+
+            camera.write_matrix(
+                encoder,
+                &camera.matrix_with_aspect(width as f32 / height as f32),
+            );
+            state.scene.prepare(world, assets, ALL_LAYERS);
+
+            let color = state
+                .color
+                .create_view(&wgpu::TextureViewDescriptor::default());
+            let multisampled = state
+                .multisampled
+                .as_ref()
+                .map(|t| t.create_view(&wgpu::TextureViewDescriptor::default()));
+            let depth = state
+                .depth
+                .create_view(&wgpu::TextureViewDescriptor::default());
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render target scene pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: multisampled.as_ref().unwrap_or(&color),
+                    resolve_target: multisampled.as_ref().map(|_| &color),
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(
+                            camera.background.clear_color(Color::black()).into(),
+                        ),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &depth,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(depth::clear_value()),
+                        store: wgpu::StoreOp::Discard,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            state
+                .scene
+                .draw(&mut render_pass, assets, camera.get_bind_group());
+            drop(render_pass);
+
+            //Flips the image into the orientation of the textures
+            copy.pipeline.draw(
+                encoder,
+                "Render target copy pass",
+                &output,
+                &[&state.bind_group],
+                wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+            );
+        }
+    }
+
+    fn get_priority(&self) -> u32 {
+        self.priority
+    }
+
+    fn get_attachments(&self) -> &'static [&'static str] {
+        &[]
+    }
+}
@@ -0,0 +1,150 @@
+//! Drawing of the meshes of the world with a custom camera, used by the extensions rendering the
+//! scene into their own textures
+use std::{mem, ops::Range, sync::Arc};
+
+use crate::{
+    asset_managment::{AssetStore, UUID},
+    assets::{material, mesh::geometry_id, BindgroupState, Material, Mesh},
+    components,
+    ecs::World,
+    grimoire::CAMERA_BIND_GROUP_INDEX,
+    structures::InstanceData,
+    validation, DEVICE, QUEUE,
+};
+
+pub(super) struct SceneDraws {
+    label: &'static str,
+    instances: Option<wgpu::Buffer>,
+    //(material, mesh, instances)
+    draws: Vec<(UUID, UUID, Range<u32>)>,
+}
+
+impl SceneDraws {
+    pub(super) const fn new(label: &'static str) -> Self {
+        Self {
+            label,
+            instances: None,
+            draws: Vec::new(),
+        }
+    }
+
+    ///Collects the visible meshes on the `layers` and writes their instances
+    pub(super) fn prepare(&mut self, world: &World, assets: &AssetStore, layers: u32) {
+        let device = DEVICE.get().unwrap();
+        let queue = QUEUE.get().unwrap();
+
+        //Instances sorted by the material and the mesh, so that equal ones are drawn together
+        let mut meshes = world
+            .get_all_components::<components::mesh::Mesh>()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|m| {
+                let m = m.borrow();
+                if !m.get_visible() || !m.is_on_layers(layers) {
+                    return None;
+                }
+                let (mesh, material) = validation::renderable(&m, assets)
+                    .map_err(validation::warn_once)
+                    .ok()?;
+                //Materials sharing the texture array are drawn together
+                let (material, texture_index) = material::batch(assets, material);
+                let mut data = m.get_instance_data();
+                data.texture_index = texture_index;
+                Some((material, geometry_id(assets, mesh), data))
+            })
+            .collect::<Vec<_>>();
+        meshes.sort_unstable_by_key(|m| (m.0, m.1));
+
+        self.draws.clear();
+        for (i, (material, mesh, _)) in meshes.iter().enumerate() {
+            let i = i as u32;
+            match self.draws.last_mut() {
+                Some((a, b, range)) if a == material && b == mesh => range.end = i + 1,
+                _ => self.draws.push((*material, *mesh, i..i + 1)),
+            }
+        }
+
+        let instances = meshes.iter().map(|m| m.2).collect::<Vec<InstanceData>>();
+        let size = mem::size_of_val(instances.as_slice()) as u64;
+        if size > 0 && self.instances.as_ref().is_none_or(|b| b.size() < size) {
+            self.instances = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(self.label),
+                size: size.next_power_of_two(),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+        if let Some(buffer) = &self.instances {
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(&instances));
+        }
+
+        for (material, _, _) in &self.draws {
+            let Ok(m) = assets.get_by_id::<Material>(*material) else {
+                continue;
+            };
+            let mut m = m.borrow_mut();
+            if !matches!(m.get_bindgroup_state(), BindgroupState::Initialized) {
+                m.initialize_bindgroups(assets);
+            }
+        }
+    }
+
+    ///Draws the prepared meshes with the `camera` bind group
+    pub(super) fn draw<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        assets: &AssetStore,
+        camera: &'a wgpu::BindGroup,
+    ) {
+        let Some(instances) = &self.instances else {
+            return;
+        };
+        let mut previous = None;
+        for (material, mesh, range) in &self.draws {
+            let (Ok(material_ref), Ok(mesh)) = (
+                assets.get_by_id::<Material>(*material),
+                assets.get_by_id::<Mesh>(*mesh),
+            ) else {
+                continue;
+            };
+            if previous != Some(*material) {
+                material_ref.borrow().render(render_pass);
+                render_pass.set_bind_group(CAMERA_BIND_GROUP_INDEX, camera, &[]);
+                previous = Some(*material);
+            }
+            draw_mesh(render_pass, &mesh.borrow(), instances, range.clone());
+        }
+    }
+
+    ///Draws the prepared meshes without binding their materials, the pipeline and the bind groups
+    ///must already be set
+    pub(super) fn draw_geometry<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        assets: &AssetStore,
+    ) {
+        let Some(instances) = &self.instances else {
+            return;
+        };
+        for (_, mesh, range) in &self.draws {
+            let Ok(mesh) = assets.get_by_id::<Mesh>(*mesh) else {
+                continue;
+            };
+            draw_mesh(render_pass, &mesh.borrow(), instances, range.clone());
+        }
+    }
+}
+
+fn draw_mesh<'a>(
+    render_pass: &mut wgpu::RenderPass<'a>,
+    mesh: &Mesh,
+    instances: &'a wgpu::Buffer,
+    range: Range<u32>,
+) {
+    let vertices = unsafe { Arc::as_ptr(&mesh.get_vertex_buffer()).as_ref().unwrap() };
+    let indices = unsafe { Arc::as_ptr(&mesh.get_index_buffer()).as_ref().unwrap() };
+    render_pass.set_vertex_buffer(0, vertices.slice(..));
+    render_pass.set_vertex_buffer(1, instances.slice(..));
+    render_pass.set_index_buffer(indices.slice(..), wgpu::IndexFormat::Uint32);
+    render_pass.draw_indexed(0..mesh.get_index_count(), 0, range);
+}
@@ -0,0 +1,276 @@
+//! Shadows of the directional light
+//!
+//! The [`ShadowMap`] extension renders the depth of all the visible meshes, as seen from the
+//! [`crate::components::light::DirectionalLight`], into the shadow map. The
+//! [`crate::assets::materials::Lit`] material samples it using a 3x3 PCF filter.
+//!
+//! The shadow map covers an area of [`DirectionalLight::shadow_size`] around the camera, smaller
+//! areas give sharper shadows.
+//!
+//![`DirectionalLight::shadow_size`]: crate::components::light::DirectionalLight::shadow_size
+use std::sync::Arc;
+
+use wgpu::util::DeviceExt;
+
+use crate::{
+    asset_managment::AssetStore,
+    assets::{mesh::geometry_id, shader, Mesh},
+    components,
+    ecs::World,
+    grimoire,
+    rendering::lighting,
+    structures::InstanceData,
+    DEVICE, QUEUE,
+};
+
+use super::{AttachmentData, RenderingExtension};
+
+const SHADOW_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+struct Target {
+    size: u32,
+    view: wgpu::TextureView,
+}
+
+///Renders the shadow map of the directional light
+///
+///Writes the `shadow` attachment, which is read by the [`super::Base`] extension, so it is always
+///rendered before it
+pub struct ShadowMap {
+    ///Priority of the extension
+    pub priority: u32,
+    ///Width and height of the shadow map in pixels
+    pub resolution: u32,
+    pipeline: Option<wgpu::RenderPipeline>,
+    target: Option<Target>,
+    //Buffer with the matrix of the light and it's bind group
+    light: Option<(wgpu::Buffer, wgpu::BindGroup)>,
+}
+
+impl Default for ShadowMap {
+    fn default() -> Self {
+        Self::new(0, 2048)
+    }
+}
+
+impl ShadowMap {
+    ///Creates a new shadow map extension with a shadow map of the given resolution
+    #[must_use]
+    pub const fn new(priority: u32, resolution: u32) -> Self {
+        Self {
+            priority,
+            resolution,
+            pipeline: None,
+            target: None,
+            light: None,
+        }
+    }
+
+    fn create_pipeline() -> wgpu::RenderPipeline {
+        let device = DEVICE.get().unwrap();
+
+        let shader = shader::compile("shadow.wgsl", include_str!("../../shaders/shadow.wgsl"))
+            .expect("Failed to compile the shadow shader");
+
+        let light_bind_group_layout =
+            device.create_bind_group_layout(&grimoire::CAMERA_BIND_GROUP_LAYOUT_DESCRIPTOR);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Shadow pipeline layout"),
+            bind_group_layouts: &[&light_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        crate::errors::scoped("Shadow pipeline", || {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Shadow pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "main",
+                    buffers: &crate::assets::materials::helpers::vertex_binding(),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: Some(wgpu::Face::Back),
+                    unclipped_depth: false,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    conservative: false,
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: SHADOW_FORMAT,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::Less,
+                    stencil: wgpu::StencilState::default(),
+                    //Slope scaled bias, the constant bias is applied when sampling
+                    bias: wgpu::DepthBiasState {
+                        constant: 0,
+                        slope_scale: 2.0,
+                        clamp: 0.0,
+                    },
+                }),
+                multisample: wgpu::MultisampleState::default(),
+                fragment: None,
+                multiview: None,
+            })
+        })
+    }
+
+    fn create_target(size: u32) -> Target {
+        let texture = DEVICE
+            .get()
+            .unwrap()
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("Shadow map"),
+                size: wgpu::Extent3d {
+                    width: size,
+                    height: size,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: SHADOW_FORMAT,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            });
+
+        Target {
+            size,
+            view: texture.create_view(&wgpu::TextureViewDescriptor::default()),
+        }
+    }
+}
+
+impl RenderingExtension for ShadowMap {
+    fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        world: &World,
+        assets: &AssetStore,
+        _: &AttachmentData,
+    ) {
+        if !lighting::casts_shadows(world) {
+            if self.target.take().is_some() {
+                lighting::set_shadow_map(None);
+            }
+            return;
+        }
+
+        let device = DEVICE.get().unwrap();
+        let size = self.resolution.max(1);
+
+        if self.pipeline.is_none() {
+            self.pipeline = Some(Self::create_pipeline());
+        }
+        if self.target.as_ref().is_none_or(|t| t.size != size) {
+            let target = Self::create_target(size);
+            lighting::set_shadow_map(Some(&target.view));
+            self.target = Some(target);
+        }
+
+        let data = lighting::update(world);
+        let (light_buffer, light_bind_group) = self.light.get_or_insert_with(|| {
+            let buffer = crate::helpers::create_uniform_matrix(Some("Shadow light"));
+            let layout =
+                device.create_bind_group_layout(&grimoire::CAMERA_BIND_GROUP_LAYOUT_DESCRIPTOR);
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Shadow light"),
+                layout: &layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }],
+            });
+            (buffer, bind_group)
+        });
+        QUEUE
+            .get()
+            .unwrap()
+            .write_buffer(light_buffer, 0, bytemuck::bytes_of(&data.matrix));
+
+        //Instance data of every visible mesh casting shadows, sorted by mesh
+        let mut meshes = world
+            .get_all_components::<components::mesh::Mesh>()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|m| {
+                let m = m.borrow();
+                m.get_mesh_id()
+                    .filter(|_| m.get_visible() && m.get_cast_shadows())
+                    .map(|id| (geometry_id(assets, id), m.get_instance_data()))
+            })
+            .collect::<Vec<_>>();
+        meshes.sort_by_key(|m| m.0);
+
+        let instances = meshes.iter().map(|m| m.1).collect::<Vec<_>>();
+        let instance_buffer = (!instances.is_empty()).then(|| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Shadow instances"),
+                contents: bytemuck::cast_slice(&instances),
+                usage: wgpu::BufferUsages::VERTEX,
+            })
+        });
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Shadow pass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.target.as_ref().unwrap().view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        let Some(instance_buffer) = &instance_buffer else {
+            return;
+        };
+
+        render_pass.set_pipeline(self.pipeline.as_ref().unwrap());
+        render_pass.set_bind_group(grimoire::CAMERA_BIND_GROUP_INDEX, light_bind_group, &[]);
+
+        let stride = std::mem::size_of::<InstanceData>() as u64;
+        let mut start = 0;
+        //Draw every mesh with all of its instances
+        while start < meshes.len() {
+            let mesh_id = meshes[start].0;
+            let end = meshes[start..]
+                .iter()
+                .position(|m| m.0 != mesh_id)
+                .map_or(meshes.len(), |i| start + i);
+
+            let mesh = assets.get_by_id::<Mesh>(mesh_id).unwrap();
+            let mesh = mesh.borrow();
+
+            let vert = unsafe { Arc::as_ptr(&mesh.get_vertex_buffer()).as_ref().unwrap() };
+            let ind = unsafe { Arc::as_ptr(&mesh.get_index_buffer()).as_ref().unwrap() };
+
+            render_pass.set_vertex_buffer(0, vert.slice(..));
+            render_pass.set_vertex_buffer(
+                1,
+                instance_buffer.slice(start as u64 * stride..end as u64 * stride),
+            );
+            render_pass.set_index_buffer(ind.slice(..), wgpu::IndexFormat::Uint32);
+            render_pass.draw_indexed(0..mesh.get_index_count(), 0, 0..(end - start) as u32);
+
+            start = end;
+        }
+    }
+
+    fn get_priority(&self) -> u32 {
+        self.priority
+    }
+
+    fn get_attachments(&self) -> &'static [&'static str] {
+        &["shadow"]
+    }
+}
@@ -0,0 +1,491 @@
+//! Skybox rendered behind all the geometry
+//!
+//! The [`Skybox`] extension renders a cubemap in the parts of the color attachment no geometry
+//! was rendered to, using the rotation of the main camera, but not its position, so the sky
+//! appears infinitely far away. It's only drawn if the main camera inherits its background or has
+//! [`Background::Skybox`](crate::rendering::Background::Skybox).
+//!
+//! The cubemap is created from [`Texture`] assets the first time the extension is rendered,
+//! either from 6 faces or by projecting an equirectangular panorama onto the faces. Textures are
+//! loaded as 8 bit images, so HDR panoramas are not supported yet.
+//!
+//!```no_run
+//! # let mut assets = lunar_engine::asset_managment::AssetStore::new();
+//! use lunar_engine::rendering::extensions::{Base, Skybox, SkyboxSource};
+//! use lunar_engine::assets::Texture;
+//!
+//! let panorama = assets.register(Texture::new_png("sky.png".as_ref()));
+//! let mut base = Base::new(0);
+//! //Rendered after the geometry, so only the empty parts of the screen are shaded
+//! let mut skybox = Skybox::new(1, SkyboxSource::Equirectangular(panorama));
+//!```
+use bytemuck::{Pod, Zeroable};
+
+use crate::{
+    asset_managment::{AssetStore, UUID},
+    assets::{shader, Texture},
+    components::camera::MainCamera,
+    ecs::World,
+    math::Mat4x4,
+    rendering::{self, depth, hdr, msaa, viewport},
+    DEVICE, QUEUE,
+};
+
+use super::{AttachmentData, PassOps, RenderingExtension};
+
+const CUBE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+///Textures the cubemap of a [`Skybox`] is created from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkyboxSource {
+    ///Square textures of the faces, in the order +X, -X, +Y, -Y, +Z, -Z, all of the same size
+    Faces([UUID; 6]),
+    ///Equirectangular panorama, twice as wide as it is high, the faces are half as big as the
+    ///height of the panorama
+    Equirectangular(UUID),
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct Parameters {
+    inverse: Mat4x4,
+    depth: [f32; 4],
+}
+
+struct SkyboxState {
+    pipeline: wgpu::RenderPipeline,
+    uniform: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    //Color attachment the pipeline was created for
+    targets: (u32, wgpu::TextureFormat),
+}
+
+///Renders a cubemap behind all the geometry
+///
+///Only draws where the depth attachment is still at the far plane, so it should be rendered after
+///the extensions rendering the geometry. If it's the first extension of the frame it clears the
+///attachments itself.
+pub struct Skybox {
+    ///Priority of the extension
+    pub priority: u32,
+    ///What the pass does with the color and depth attachments
+    pub ops: PassOps,
+    source: SkyboxSource,
+    state: Option<SkyboxState>,
+}
+
+impl Skybox {
+    ///Creates a new skybox rendering the cubemap created from the `source`
+    #[must_use]
+    pub const fn new(priority: u32, source: SkyboxSource) -> Self {
+        Self {
+            priority,
+            ops: PassOps::new(),
+            source,
+            state: None,
+        }
+    }
+
+    ///Returns the textures the cubemap is created from
+    #[must_use]
+    pub const fn get_source(&self) -> SkyboxSource {
+        self.source
+    }
+
+    ///Sets the textures the cubemap is created from, the cubemap is recreated the next time the
+    ///extension is rendered
+    pub fn set_source(&mut self, source: SkyboxSource) {
+        self.source = source;
+        self.state = None;
+    }
+
+    fn create_state(&self, encoder: &mut wgpu::CommandEncoder, assets: &AssetStore) -> SkyboxState {
+        let device = DEVICE.get().unwrap();
+
+        let shader = shader::compile("skybox.wgsl", include_str!("../../shaders/skybox.wgsl"))
+            .expect("Failed to compile the skybox shader");
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Skybox bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::Cube,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Skybox pipeline layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = crate::errors::scoped("Skybox pipeline", || {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Skybox pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vertex",
+                    buffers: &[],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                //Only passes where nothing was rendered
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: depth::format(),
+                    depth_write_enabled: false,
+                    depth_compare: if depth::is_reversed() {
+                        wgpu::CompareFunction::GreaterEqual
+                    } else {
+                        wgpu::CompareFunction::LessEqual
+                    },
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: msaa::multisample_state(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fragment",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: hdr::color_format(),
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                }),
+                multiview: None,
+            })
+        });
+
+        let uniform = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Skybox parameters"),
+            size: std::mem::size_of::<Parameters>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let cube = create_cubemap(encoder, self.source, assets);
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Skybox sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Skybox bind group"),
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&cube.create_view(
+                        &wgpu::TextureViewDescriptor {
+                            label: Some("Skybox cubemap view"),
+                            dimension: Some(wgpu::TextureViewDimension::Cube),
+                            ..Default::default()
+                        },
+                    )),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        SkyboxState {
+            pipeline,
+            uniform,
+            bind_group,
+            targets: rendering::color_targets(),
+        }
+    }
+}
+
+///Renders the `source` textures into the faces of a new cubemap
+fn create_cubemap(
+    encoder: &mut wgpu::CommandEncoder,
+    source: SkyboxSource,
+    assets: &AssetStore,
+) -> wgpu::Texture {
+    let device = DEVICE.get().unwrap();
+
+    let (textures, entry_point) = match source {
+        SkyboxSource::Faces(faces) => (faces.to_vec(), "copy_face"),
+        SkyboxSource::Equirectangular(panorama) => (vec![panorama], "equirectangular"),
+    };
+    let textures = textures
+        .into_iter()
+        .map(|id| {
+            assets
+                .get_by_id::<Texture>(id)
+                .expect("Skybox texture is not in the asset store")
+        })
+        .collect::<Vec<_>>();
+
+    let size = {
+        let texture = textures[0].borrow();
+        let texture = texture.texture.as_ref().unwrap();
+        match source {
+            SkyboxSource::Faces(_) => texture.width(),
+            SkyboxSource::Equirectangular(_) => texture.height() / 2,
+        }
+        .max(1)
+    };
+
+    let cube = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Skybox cubemap"),
+        size: wgpu::Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 6,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: CUBE_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+
+    let shader = shader::compile("cubemap.wgsl", include_str!("../../shaders/cubemap.wgsl"))
+        .expect("Failed to compile the cubemap shader");
+
+    let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Cubemap bind group layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    });
+
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Cubemap pipeline layout"),
+        bind_group_layouts: &[&layout],
+        push_constant_ranges: &[],
+    });
+
+    let pipeline = crate::errors::scoped("Cubemap pipeline", || {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Cubemap pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vertex",
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point,
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: CUBE_FORMAT,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            multiview: None,
+        })
+    });
+
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("Cubemap sampler"),
+        address_mode_u: wgpu::AddressMode::Repeat,
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+    });
+
+    for face in 0..6u32 {
+        let texture = textures[face as usize % textures.len()].borrow();
+        let uniform = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Cubemap face"),
+            size: 16,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        QUEUE
+            .get()
+            .unwrap()
+            .write_buffer(&uniform, 0, bytemuck::cast_slice(&[face, size, 0, 0]));
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Cubemap bind group"),
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(
+                        &texture
+                            .texture
+                            .as_ref()
+                            .unwrap()
+                            .create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        let view = cube.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Cubemap face view"),
+            dimension: Some(wgpu::TextureViewDimension::D2),
+            base_array_layer: face,
+            array_layer_count: Some(1),
+            ..Default::default()
+        });
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Cubemap face pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    cube
+}
+
+impl RenderingExtension for Skybox {
+    fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        world: &World,
+        assets: &AssetStore,
+        attachments: &AttachmentData,
+    ) {
+        let Some(camera) = world
+            .get_all_components::<MainCamera>()
+            .and_then(|c| c.first().cloned())
+        else {
+            return;
+        };
+        let camera = camera.borrow();
+        if !camera.background.shows_skybox() {
+            return;
+        }
+        let matrix = camera.matrix();
+        drop(camera);
+        let Some(inverse) = matrix.inverted() else {
+            return;
+        };
+
+        if self
+            .state
+            .as_ref()
+            .is_none_or(|s| s.targets != rendering::color_targets())
+        {
+            self.state = Some(self.create_state(encoder, assets));
+        }
+        let state = self.state.as_ref().unwrap();
+
+        let near = if depth::is_reversed() { 1.0 } else { 0.0 };
+        QUEUE.get().unwrap().write_buffer(
+            &state.uniform,
+            0,
+            bytemuck::bytes_of(&Parameters {
+                inverse,
+                depth: [depth::clear_value(), near, 0.0, 0.0],
+            }),
+        );
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Skybox pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &attachments.color,
+                resolve_target: None,
+                ops: self.ops.color_ops(attachments, wgpu::Color::BLACK),
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &attachments.depth_stencil,
+                depth_ops: Some(self.ops.depth_ops(attachments)),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        viewport::apply(&mut render_pass);
+        render_pass.set_pipeline(&state.pipeline);
+        render_pass.set_bind_group(0, &state.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    fn get_priority(&self) -> u32 {
+        self.priority
+    }
+}
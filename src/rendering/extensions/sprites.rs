@@ -0,0 +1,327 @@
+//! Rendering of the [`Sprite`] components
+//!
+//!```no_run
+//! use lunar_engine::{
+//!     asset_managment::AssetStore,
+//!     assets::Texture,
+//!     components::{sprite::Sprite, transform::Transform},
+//!     ecs::{EntityBuilder, World},
+//!     math::Vec2,
+//!     rendering::extensions::SpriteRenderer,
+//! };
+//!
+//! let mut world = World::new();
+//! let mut assets = AssetStore::new();
+//!
+//! let atlas = assets.register(Texture::new_png("atlas.png".as_ref()));
+//!
+//! //Sprites sharing the atlas are drawn with a single draw call
+//! for x in 0..10 {
+//!     world.add_entity(
+//!         EntityBuilder::new()
+//!             .add_component::<Transform>()
+//!             .create_component(move || {
+//!                 let offset = Vec2::new(0.25 * (x % 4) as f32, 0.0);
+//!                 Sprite::from_region(atlas, offset, Vec2::new(0.25, 0.25))
+//!             })
+//!             .create()
+//!             .unwrap(),
+//!     );
+//! }
+//!
+//! let mut sprites = SpriteRenderer::new(1);
+//!```
+use std::{collections::HashMap, mem, ops::Range};
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::{
+    asset_managment::{AssetStore, UUID},
+    assets::{shader, Texture},
+    components::{camera::MainCamera, mesh::ALL_LAYERS, sprite::Sprite},
+    ecs::World,
+    grimoire::CAMERA_BIND_GROUP_LAYOUT_DESCRIPTOR,
+    rendering::{self, depth, hdr, msaa, viewport},
+    DEVICE, QUEUE,
+};
+
+use super::{canvas::atlas_bind_group, AttachmentData, PassOps, RenderingExtension};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct SpriteInstance {
+    transform: [[f32; 4]; 4],
+    //Offset and size of the region of the texture
+    region: [f32; 4],
+    color: [f32; 4],
+}
+
+struct SpritesState {
+    pipeline: wgpu::RenderPipeline,
+    //Color attachment and the depth range the pipeline was created for
+    targets: ((u32, wgpu::TextureFormat), bool),
+    texture_layout: wgpu::BindGroupLayout,
+    textures: HashMap<UUID, wgpu::BindGroup>,
+    instances: Option<wgpu::Buffer>,
+}
+
+///Renders the [`Sprite`] components of the world
+///
+///Sprites are drawn in the order of [`Sprite::order`], consecutive sprites using the same texture
+///are drawn with a single instanced draw call, so sprites should share atlases where possible.
+///The sprites are blended over the color attachment and hidden by the geometry in the depth
+///attachment, without writing into it.
+pub struct SpriteRenderer {
+    ///Priority of the extension
+    pub priority: u32,
+    ///What the pass does with the color and depth attachments
+    pub ops: PassOps,
+    ///Bit mask of the render layers rendered by the extension, see [`Sprite::set_layers`]
+    pub layers: u32,
+    state: Option<SpritesState>,
+}
+
+impl SpriteRenderer {
+    ///Creates a new sprite renderer
+    #[must_use]
+    pub const fn new(priority: u32) -> Self {
+        Self {
+            priority,
+            ops: PassOps::new(),
+            layers: ALL_LAYERS,
+            state: None,
+        }
+    }
+
+    fn create_state() -> SpritesState {
+        let device = DEVICE.get().unwrap();
+
+        let shader = shader::compile("sprite.wgsl", include_str!("../../shaders/sprite.wgsl"))
+            .expect("Failed to compile the sprite shader");
+
+        let camera_layout = device.create_bind_group_layout(&CAMERA_BIND_GROUP_LAYOUT_DESCRIPTOR);
+        let texture_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Sprite texture bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Sprite pipeline layout"),
+            bind_group_layouts: &[&camera_layout, &texture_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = crate::errors::scoped("Sprite pipeline", || {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Sprite pipeline"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vertex",
+                    buffers: &[wgpu::VertexBufferLayout {
+                        array_stride: mem::size_of::<SpriteInstance>() as u64,
+                        step_mode: wgpu::VertexStepMode::Instance,
+                        attributes: &wgpu::vertex_attr_array![
+                            0 => Float32x4,
+                            1 => Float32x4,
+                            2 => Float32x4,
+                            3 => Float32x4,
+                            4 => Float32x4,
+                            5 => Float32x4
+                        ],
+                    }],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: depth::format(),
+                    //Sprites are blended in order instead
+                    depth_write_enabled: false,
+                    depth_compare: depth::compare_function(),
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: msaa::multisample_state(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fragment",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: hdr::color_format(),
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                }),
+                multiview: None,
+            })
+        });
+
+        SpritesState {
+            pipeline,
+            targets: (rendering::color_targets(), depth::is_reversed()),
+            texture_layout,
+            textures: HashMap::new(),
+            instances: None,
+        }
+    }
+}
+
+///Sorts the sprites by their order and texture and splits them into batches of consecutive
+///sprites using the same texture
+pub(crate) fn batch_sprites<T>(sprites: &mut [(i32, UUID, T)]) -> Vec<(UUID, Range<u32>)> {
+    //Stable, so sprites with equal orders and textures keep the order they were added in
+    sprites.sort_by_key(|s| (s.0, s.1));
+
+    let mut batches: Vec<(UUID, Range<u32>)> = Vec::new();
+    for (i, (_, texture, _)) in sprites.iter().enumerate() {
+        let i = i as u32;
+        match batches.last_mut() {
+            Some((t, range)) if t == texture => range.end = i + 1,
+            _ => batches.push((*texture, i..i + 1)),
+        }
+    }
+    batches
+}
+
+impl RenderingExtension for SpriteRenderer {
+    fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        world: &World,
+        assets: &AssetStore,
+        attachments: &AttachmentData,
+    ) {
+        let Some(camera) = world
+            .get_all_components::<MainCamera>()
+            .and_then(|c| c.first().cloned())
+        else {
+            return;
+        };
+
+        let mut sprites = world
+            .get_all_components::<Sprite>()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|s| {
+                let s = s.borrow();
+                if !s.visible || !s.is_on_layers(self.layers) {
+                    return None;
+                }
+                let texture = s.texture?;
+                let (offset, size) = s.uv;
+                let instance = SpriteInstance {
+                    transform: bytemuck::cast(s.matrix().transpose()),
+                    region: [offset.x, offset.y, size.x, size.y],
+                    color: [s.color.r, s.color.g, s.color.b, s.color.a],
+                };
+                Some((s.order, texture, instance))
+            })
+            .collect::<Vec<_>>();
+        if sprites.is_empty() {
+            return;
+        }
+        let batches = batch_sprites(&mut sprites);
+
+        let targets = (rendering::color_targets(), depth::is_reversed());
+        if self.state.as_ref().is_none_or(|s| s.targets != targets) {
+            self.state = Some(Self::create_state());
+        }
+        let state = self.state.as_mut().unwrap();
+        let device = DEVICE.get().unwrap();
+
+        let instances = sprites.iter().map(|s| s.2).collect::<Vec<_>>();
+        let size = mem::size_of_val(instances.as_slice()) as u64;
+        if state.instances.as_ref().is_none_or(|b| b.size() < size) {
+            state.instances = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Sprite instances"),
+                size: size.next_power_of_two(),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+        let buffer = state.instances.as_ref().unwrap();
+        QUEUE
+            .get()
+            .unwrap()
+            .write_buffer(buffer, 0, bytemuck::cast_slice(&instances));
+
+        for (id, _) in &batches {
+            if state.textures.contains_key(id) {
+                continue;
+            }
+            let Ok(texture) = assets.get_by_id::<Texture>(*id) else {
+                log::error!("Sprite texture {id} does not exist");
+                continue;
+            };
+            let texture = texture.borrow();
+            let (Some(t), Some(sampler)) = (texture.texture.as_ref(), texture.sampler.as_ref())
+            else {
+                continue;
+            };
+            let view = t.create_view(&wgpu::TextureViewDescriptor::default());
+            state
+                .textures
+                .insert(*id, atlas_bind_group(&state.texture_layout, sampler, &view));
+        }
+
+        let camera = camera.borrow();
+        camera.update_gpu(encoder);
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Sprite pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &attachments.color,
+                resolve_target: None,
+                ops: self.ops.color_ops(attachments, wgpu::Color::BLACK),
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &attachments.depth_stencil,
+                depth_ops: Some(self.ops.depth_ops(attachments)),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        viewport::apply(&mut render_pass);
+        render_pass.set_pipeline(&state.pipeline);
+        camera.set_bindgroup(&mut render_pass);
+        render_pass.set_vertex_buffer(0, buffer.slice(..size));
+
+        for (id, range) in batches {
+            let Some(bind_group) = state.textures.get(&id) else {
+                continue;
+            };
+            render_pass.set_bind_group(1, bind_group, &[]);
+            render_pass.draw(0..6, range);
+        }
+    }
+
+    fn get_priority(&self) -> u32 {
+        self.priority
+    }
+
+    fn get_attachments(&self) -> &'static [&'static str] {
+        &["color"]
+    }
+
+    fn get_reads(&self) -> &'static [&'static str] {
+        &["depth_stencil"]
+    }
+}
@@ -0,0 +1,150 @@
+//! Rendering of the [`Text`] components
+//!
+//!```no_run
+//! use lunar_engine::{
+//!     asset_managment::AssetStore,
+//!     assets::Font,
+//!     components::{
+//!         text::{Text, TextPlacement},
+//!         transform::Transform,
+//!     },
+//!     ecs::{EntityBuilder, World},
+//!     math::{Vec2, Vec3},
+//!     rendering::extensions::TextRenderer,
+//! };
+//!
+//! let mut world = World::new();
+//! let mut assets = AssetStore::new();
+//!
+//! let font = assets.register(Font::new_ttf("font.ttf".as_ref()));
+//!
+//! world.add_entity(
+//!     EntityBuilder::new()
+//!         .add_component::<Transform>()
+//!         .create_component(move || {
+//!             Text::new("Score: 0", font, 24.0)
+//!                 .with_placement(TextPlacement::Screen(Vec2::new(10.0, 10.0)))
+//!         })
+//!         .create()
+//!         .unwrap(),
+//! );
+//!
+//! //Label following the entity
+//! world.add_entity(
+//!     EntityBuilder::new()
+//!         .add_component::<Transform>()
+//!         .create_component(move || {
+//!             Text::new("Player", font, 16.0).with_placement(TextPlacement::World {
+//!                 offset: Vec3::new(0.0, 2.0, 0.0),
+//!             })
+//!         })
+//!         .create()
+//!         .unwrap(),
+//! );
+//!
+//! let mut text = TextRenderer::new(10);
+//!```
+use crate::{
+    asset_managment::{Asset, AssetStore},
+    assets::Font,
+    components::{
+        camera::MainCamera,
+        text::{Text, TextPlacement},
+    },
+    ecs::World,
+    math::{Vec2, Vec3, Vec4},
+    rendering::viewport,
+};
+
+use super::{AttachmentData, Canvas, RenderingExtension};
+
+///Renders the [`Text`] components of the world on top of the color attachment
+///
+///Should have a high priority, so that the text is rendered over the scene. Texts using fonts
+///that are not initialized yet are skipped.
+pub struct TextRenderer {
+    ///Priority of the extension
+    pub priority: u32,
+    canvas: Canvas,
+}
+
+impl TextRenderer {
+    ///Creates a new text renderer
+    #[must_use]
+    pub const fn new(priority: u32) -> Self {
+        Self {
+            priority,
+            canvas: Canvas::new(priority),
+        }
+    }
+}
+
+///Projects the point in world space onto the viewport of the size of `viewport`, in pixels from
+///its top left corner, `None` if the point is behind the camera
+pub(crate) fn project(camera: &MainCamera, point: Vec3, viewport: Vec2) -> Option<Vec2> {
+    let clip = camera.matrix().transpose() * Vec4::new(point.x, point.y, point.z, 1.0);
+    if clip.w <= 0.0 {
+        return None;
+    }
+    Some(Vec2::new(
+        (clip.x / clip.w + 1.0) * 0.5 * viewport.x,
+        (1.0 - clip.y / clip.w) * 0.5 * viewport.y,
+    ))
+}
+
+impl RenderingExtension for TextRenderer {
+    fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        world: &World,
+        assets: &AssetStore,
+        attachments: &AttachmentData,
+    ) {
+        let camera = world
+            .get_all_components::<MainCamera>()
+            .and_then(|c| c.first().cloned());
+        let size = viewport::current();
+        let size = Vec2::new(size.width, size.height);
+
+        for text in world.get_all_components::<Text>().unwrap_or_default() {
+            let text = text.borrow();
+            if !text.visible || text.text.is_empty() {
+                continue;
+            }
+            let Some(font) = text
+                .font
+                .and_then(|f| assets.get_by_id::<Font>(f).ok())
+                .filter(|f| f.borrow().is_initialized())
+            else {
+                continue;
+            };
+            let font = font.borrow().get_font();
+
+            let position = match text.placement {
+                TextPlacement::Screen(position) => position,
+                TextPlacement::World { .. } => {
+                    let Some(camera) = camera.as_ref() else {
+                        continue;
+                    };
+                    let anchor = text.anchor().unwrap();
+                    let Some(center) = project(&camera.borrow(), anchor, size) else {
+                        continue;
+                    };
+                    center - font.measure(&text.text, text.size) / 2.0
+                }
+            };
+            self.canvas
+                .text(&font, &text.text, position, text.size, text.color);
+        }
+
+        self.canvas.render(encoder, world, assets, attachments);
+    }
+
+    fn get_priority(&self) -> u32 {
+        self.priority
+    }
+
+    fn get_attachments(&self) -> &'static [&'static str] {
+        &["color"]
+    }
+}
@@ -0,0 +1,234 @@
+//! Tonemapping of the HDR color attachment
+//!
+//! With HDR rendering enabled, see [`crate::rendering::hdr`], the [`Tonemap`] extension maps the
+//! colors of the scene into the range of the frame buffer. Extensions rendered after it don't
+//! show up in the final image, unless they render into the `output` attachment.
+//!
+//! With an HDR frame buffer, see [`crate::rendering::surface::HDR_FORMAT`], only the exposure is
+//! applied, the colors above 1 are passed to the display.
+use std::cell::RefCell;
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::{
+    asset_managment::AssetStore,
+    ecs::World,
+    rendering::{
+        fullscreen::{self, FullscreenPipeline},
+        surface,
+    },
+    DEVICE, FORMAT, QUEUE,
+};
+
+use super::{AttachmentData, RenderingExtension};
+
+thread_local! {
+    //Used when no tonemapping extension rendered in the frame
+    static FALLBACK: RefCell<Tonemap> = const { RefCell::new(Tonemap::new(0, TonemapOperator::Clamp)) };
+}
+
+///Curve mapping the HDR colors into the range of the frame buffer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TonemapOperator {
+    ///Filmic curve of the Academy Color Encoding System, desaturates the bright colors
+    #[default]
+    Aces,
+    ///`x / (1 + x)`, keeps the hue of the bright colors
+    Reinhard,
+    ///Clamps the colors, everything above 1 is lost
+    Clamp,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct Parameters {
+    //Exposure, operator, extended range output
+    settings: [f32; 4],
+}
+
+struct TonemapState {
+    pipeline: FullscreenPipeline,
+    layout: wgpu::BindGroupLayout,
+    uniform: wgpu::Buffer,
+}
+
+///Tonemaps the HDR color attachment into the `output` attachment
+///
+///Should be rendered after all the extensions rendering into the color attachment. Does nothing
+///if HDR rendering is disabled.
+pub struct Tonemap {
+    ///Priority of the extension
+    pub priority: u32,
+    ///Curve used for the tonemapping
+    pub operator: TonemapOperator,
+    ///Multiplier of the colors before the tonemapping
+    pub exposure: f32,
+    state: Option<TonemapState>,
+}
+
+impl Tonemap {
+    ///Creates a new tonemapping extension with the exposure of 1
+    #[must_use]
+    pub const fn new(priority: u32, operator: TonemapOperator) -> Self {
+        Self {
+            priority,
+            operator,
+            exposure: 1.0,
+            state: None,
+        }
+    }
+
+    fn create_state() -> TonemapState {
+        let device = DEVICE.get().unwrap();
+
+        let shader =
+            fullscreen::compile("tonemap.wgsl", include_str!("../../shaders/tonemap.wgsl"))
+                .expect("Failed to compile the tonemapping shader");
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Tonemapping bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline = FullscreenPipeline::new(
+            "Tonemapping pipeline",
+            &shader,
+            &[&layout],
+            wgpu::ColorTargetState {
+                format: *FORMAT.get().unwrap(),
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            },
+            wgpu::MultisampleState::default(),
+        );
+
+        let uniform = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Tonemapping parameters"),
+            size: std::mem::size_of::<Parameters>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        TonemapState {
+            pipeline,
+            layout,
+            uniform,
+        }
+    }
+}
+
+impl RenderingExtension for Tonemap {
+    fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        _: &World,
+        _: &AssetStore,
+        attachments: &AttachmentData,
+    ) {
+        let Some(output) = &attachments.output else {
+            return;
+        };
+        let Some(input) = attachments.hdr_color(encoder) else {
+            return;
+        };
+
+        let state = self.state.get_or_insert_with(Self::create_state);
+
+        let operator = match self.operator {
+            TonemapOperator::Aces => 0.0,
+            TonemapOperator::Reinhard => 1.0,
+            TonemapOperator::Clamp => 2.0,
+        };
+        QUEUE.get().unwrap().write_buffer(
+            &state.uniform,
+            0,
+            bytemuck::bytes_of(&Parameters {
+                settings: [
+                    self.exposure,
+                    operator,
+                    f32::from(u8::from(surface::is_hdr_output())),
+                    0.0,
+                ],
+            }),
+        );
+
+        let bind_group = DEVICE
+            .get()
+            .unwrap()
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Tonemapping bind group"),
+                layout: &state.layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(input),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: state.uniform.as_entire_binding(),
+                    },
+                ],
+            });
+
+        //Covers the whole output, nothing else writes to it before
+        state.pipeline.draw(
+            encoder,
+            "Tonemapping pass",
+            output,
+            &[&bind_group],
+            wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+        );
+    }
+
+    fn get_priority(&self) -> u32 {
+        self.priority
+    }
+
+    fn get_attachments(&self) -> &'static [&'static str] {
+        &["output"]
+    }
+
+    fn get_reads(&self) -> &'static [&'static str] {
+        &["color"]
+    }
+}
+
+///Clamps the HDR color attachment into the output if no tonemapping extension rendered in the
+///frame
+pub(crate) fn fallback(
+    encoder: &mut wgpu::CommandEncoder,
+    world: &World,
+    assets: &AssetStore,
+    attachments: &AttachmentData,
+) {
+    if attachments.is_written("output") {
+        return;
+    }
+    FALLBACK.with_borrow_mut(|t| t.render(encoder, world, assets, attachments));
+}
+
+///Releases the resources of the fallback tonemapping
+pub(crate) fn release() {
+    FALLBACK.set(Tonemap::new(0, TonemapOperator::Clamp));
+}
@@ -0,0 +1,321 @@
+//! Rendering of the [`WorldText`] components
+use std::{collections::HashMap, mem};
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::{
+    asset_managment::{AssetStore, UUID},
+    assets::shader,
+    components::{camera::MainCamera, world_text::WorldText},
+    ecs::World,
+    grimoire::CAMERA_BIND_GROUP_LAYOUT_DESCRIPTOR,
+    math::{Vec3, Vector},
+    rendering::{self, depth, hdr, msaa, viewport},
+    DEVICE, QUEUE,
+};
+
+use super::{
+    canvas::{atlas_bind_group, atlas_view},
+    AttachmentData, PassOps, RenderingExtension,
+};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Vertex {
+    position: [f32; 3],
+    uv: [f32; 2],
+    color: [f32; 4],
+}
+
+struct WorldTextState {
+    pipeline: wgpu::RenderPipeline,
+    //Color attachment and the depth range the pipeline was created for
+    targets: ((u32, wgpu::TextureFormat), bool),
+    atlas_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    atlases: HashMap<UUID, wgpu::BindGroup>,
+    buffer: Option<wgpu::Buffer>,
+}
+
+///Renders the [`WorldText`] components of the world
+///
+///The text is hidden by the geometry in the depth attachment, so the extension should be rendered
+///after the extensions rendering the geometry. Texts are sorted back to front and blended over the
+///color attachment.
+pub struct WorldTextRenderer {
+    ///Priority of the extension
+    pub priority: u32,
+    ///What the pass does with the color and depth attachments
+    pub ops: PassOps,
+    state: Option<WorldTextState>,
+}
+
+impl WorldTextRenderer {
+    ///Creates a new world text renderer
+    #[must_use]
+    pub const fn new(priority: u32) -> Self {
+        Self {
+            priority,
+            ops: PassOps::new(),
+            state: None,
+        }
+    }
+
+    fn create_state() -> WorldTextState {
+        let device = DEVICE.get().unwrap();
+
+        let shader = shader::compile(
+            "world_text.wgsl",
+            include_str!("../../shaders/world_text.wgsl"),
+        )
+        .expect("Failed to compile the world text shader");
+
+        let camera_layout = device.create_bind_group_layout(&CAMERA_BIND_GROUP_LAYOUT_DESCRIPTOR);
+        let atlas_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("World text atlas bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("World text pipeline layout"),
+            bind_group_layouts: &[&camera_layout, &atlas_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = crate::errors::scoped("World text pipeline", || {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("World text pipeline"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vertex",
+                    buffers: &[wgpu::VertexBufferLayout {
+                        array_stride: mem::size_of::<Vertex>() as u64,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &wgpu::vertex_attr_array![
+                            0 => Float32x3,
+                            1 => Float32x2,
+                            2 => Float32x4
+                        ],
+                    }],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: depth::format(),
+                    //Overlapping labels are blended
+                    depth_write_enabled: false,
+                    depth_compare: depth::compare_function(),
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: msaa::multisample_state(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fragment",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: hdr::color_format(),
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                }),
+                multiview: None,
+            })
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("World text sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        WorldTextState {
+            pipeline,
+            targets: (rendering::color_targets(), depth::is_reversed()),
+            atlas_layout,
+            sampler,
+            atlases: HashMap::new(),
+            buffer: None,
+        }
+    }
+}
+
+impl RenderingExtension for WorldTextRenderer {
+    fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        world: &World,
+        assets: &AssetStore,
+        attachments: &AttachmentData,
+    ) {
+        let Some(camera) = world
+            .get_all_components::<MainCamera>()
+            .and_then(|c| c.first().cloned())
+        else {
+            return;
+        };
+        let camera = camera.borrow();
+        let matrix = camera.matrix();
+        let camera_position = {
+            let t = camera.camera_transform();
+            Vec3::new(t.m03, t.m13, t.m23)
+        };
+        //Directions of the screen in world space, the projection only scales them
+        let right = Vec3::new(matrix.m00, matrix.m10, matrix.m20).normalized();
+        let up = Vec3::new(matrix.m01, matrix.m11, matrix.m21).normalized();
+
+        let mut texts = world
+            .get_all_components::<WorldText>()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|t| {
+                let text = t.borrow();
+                if !text.visible || text.text.is_empty() {
+                    return None;
+                }
+                let distance = (text.position() - camera_position).length();
+                let opacity = text.opacity(camera_position);
+                drop(text);
+                (opacity > 0.0).then_some((t, distance, opacity))
+            })
+            .collect::<Vec<_>>();
+        if texts.is_empty() {
+            return;
+        }
+        //Back to front
+        texts.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let mut vertices = Vec::new();
+        let mut batches: Vec<(UUID, std::ops::Range<u32>)> = Vec::new();
+        for (text, _, opacity) in &texts {
+            let text = text.borrow();
+            let color = [
+                text.color.r,
+                text.color.g,
+                text.color.b,
+                text.color.a * opacity,
+            ];
+            let start = vertices.len() as u32;
+            for (corners, (uv_min, uv_max)) in text.glyph_quads(right, up) {
+                let vertex = |i: usize, u: f32, v: f32| Vertex {
+                    position: [corners[i].x, corners[i].y, corners[i].z],
+                    uv: [u, v],
+                    color,
+                };
+                vertices.extend([
+                    vertex(0, uv_min.x, uv_min.y),
+                    vertex(2, uv_min.x, uv_max.y),
+                    vertex(1, uv_max.x, uv_min.y),
+                    vertex(1, uv_max.x, uv_min.y),
+                    vertex(2, uv_min.x, uv_max.y),
+                    vertex(3, uv_max.x, uv_max.y),
+                ]);
+            }
+            let end = vertices.len() as u32;
+            match batches.last_mut() {
+                Some((atlas, range)) if *atlas == text.font.atlas => range.end = end,
+                _ => batches.push((text.font.atlas, start..end)),
+            }
+        }
+        if vertices.is_empty() {
+            return;
+        }
+
+        let targets = (rendering::color_targets(), depth::is_reversed());
+        if self.state.as_ref().is_none_or(|s| s.targets != targets) {
+            self.state = Some(Self::create_state());
+        }
+        let state = self.state.as_mut().unwrap();
+        let device = DEVICE.get().unwrap();
+
+        let size = mem::size_of_val(vertices.as_slice()) as u64;
+        if state.buffer.as_ref().is_none_or(|b| b.size() < size) {
+            state.buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("World text vertices"),
+                size: size.next_power_of_two(),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+        let buffer = state.buffer.as_ref().unwrap();
+        QUEUE
+            .get()
+            .unwrap()
+            .write_buffer(buffer, 0, bytemuck::cast_slice(&vertices));
+
+        for (id, _) in &batches {
+            if state.atlases.contains_key(id) {
+                continue;
+            }
+            let Some(view) = atlas_view(assets, *id) else {
+                log::error!("World text atlas {id} does not exist");
+                continue;
+            };
+            state.atlases.insert(
+                *id,
+                atlas_bind_group(&state.atlas_layout, &state.sampler, &view),
+            );
+        }
+
+        camera.update_gpu(encoder);
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("World text pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &attachments.color,
+                resolve_target: None,
+                ops: self.ops.color_ops(attachments, wgpu::Color::BLACK),
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &attachments.depth_stencil,
+                depth_ops: Some(self.ops.depth_ops(attachments)),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        viewport::apply(&mut render_pass);
+        render_pass.set_pipeline(&state.pipeline);
+        camera.set_bindgroup(&mut render_pass);
+        render_pass.set_vertex_buffer(0, buffer.slice(..size));
+
+        for (id, range) in batches {
+            let Some(bind_group) = state.atlases.get(&id) else {
+                continue;
+            };
+            render_pass.set_bind_group(1, bind_group, &[]);
+            render_pass.draw(range, 0..1);
+        }
+    }
+
+    fn get_priority(&self) -> u32 {
+        self.priority
+    }
+
+    fn get_attachments(&self) -> &'static [&'static str] {
+        &["color"]
+    }
+
+    fn get_reads(&self) -> &'static [&'static str] {
+        &["depth_stencil"]
+    }
+}
@@ -0,0 +1,134 @@
+//! Passes drawing a single triangle covering the whole attachment
+//!
+//! Used for passes processing a whole image, like tonemapping. Shaders compiled with [`compile`]
+//! get a `vertex` entry point passing a `FullscreenOutput` to the fragment shader, containing the
+//! `position` and the `uv`, with 0, 0 at the top left corner.
+//!
+//!```wgsl
+//! @group(0) @binding(0)
+//! var input: texture_2d<f32>;
+//!
+//! @fragment
+//! fn fragment(in: FullscreenOutput) -> @location(0) vec4<f32> {
+//!     return textureLoad(input, vec2<i32>(in.position.xy), 0);
+//! }
+//!```
+use crate::{assets::shader, errors, DEVICE};
+
+///Render pipeline drawing a triangle covering the whole attachment
+pub struct FullscreenPipeline {
+    pipeline: wgpu::RenderPipeline,
+}
+
+///Compiles a shader containing a fragment shader together with the fullscreen vertex shader
+///
+///# Errors
+///Returns the first compilation error if the shader failed to compile
+pub fn compile(file: &str, source: &str) -> Result<wgpu::ShaderModule, errors::Error> {
+    let vertex = include_str!("../shaders/fullscreen.wgsl");
+
+    let mut map = shader::SourceMap::new();
+    for (f, text) in [("fullscreen.wgsl", vertex), (file, source)] {
+        for l in 1..=text.lines().count() as u32 {
+            map.push(f, l);
+        }
+    }
+    //The included file ends with a new line
+    shader::compile_mapped(&format!("{vertex}{source}"), &map)
+}
+
+impl FullscreenPipeline {
+    ///Creates a pipeline using the `fragment` entry point of a shader compiled with [`compile`]
+    #[must_use]
+    pub fn new(
+        label: &str,
+        shader: &wgpu::ShaderModule,
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
+        target: wgpu::ColorTargetState,
+        multisample: wgpu::MultisampleState,
+    ) -> Self {
+        Self::new_with_entry_point(
+            label,
+            shader,
+            "fragment",
+            bind_group_layouts,
+            target,
+            multisample,
+        )
+    }
+
+    ///Creates a pipeline using the `entry_point` of a shader compiled with [`compile`], for
+    ///shaders containing multiple passes
+    #[must_use]
+    pub fn new_with_entry_point(
+        label: &str,
+        shader: &wgpu::ShaderModule,
+        entry_point: &str,
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
+        target: wgpu::ColorTargetState,
+        multisample: wgpu::MultisampleState,
+    ) -> Self {
+        let device = DEVICE.get().unwrap();
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(label),
+            bind_group_layouts,
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = errors::scoped(label, || {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: shader,
+                    entry_point: "vertex",
+                    buffers: &[],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample,
+                fragment: Some(wgpu::FragmentState {
+                    module: shader,
+                    entry_point,
+                    targets: &[Some(target)],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                }),
+                multiview: None,
+            })
+        });
+
+        Self { pipeline }
+    }
+
+    ///Draws the triangle into the `view` in a new render pass
+    pub fn draw(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        label: &str,
+        view: &wgpu::TextureView,
+        bind_groups: &[&wgpu::BindGroup],
+        load: wgpu::LoadOp<wgpu::Color>,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(label),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        for (i, b) in bind_groups.iter().enumerate() {
+            render_pass.set_bind_group(i as u32, b, &[]);
+        }
+        render_pass.draw(0..3, 0..1);
+    }
+}
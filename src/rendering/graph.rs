@@ -0,0 +1,752 @@
+//! Render graph, the order of the passes and the work the renderer performs during a frame
+//!
+//! # Ordering
+//! Rendering extensions declare the attachments they read with
+//! [`RenderingExtension::get_reads`] and the ones they write with
+//! [`RenderingExtension::get_attachments`]. Every frame the extensions are ordered so that:
+//! - Extensions writing to the same attachment are rendered in the order of their priorities
+//! - Extensions reading an attachment they don't write are rendered after all the extensions
+//!   writing to it
+//!
+//! Extensions that don't depend on each other are rendered in the order of their priorities, see
+//! [`resolve_order`]. Extensions with equal priorities are rendered in the order they are passed
+//! to [`crate::rendering::render`], so the order is always deterministic. The order of the last
+//! rendered frame is returned by [`extension_order`].
+//!
+//! # Dependencies
+//! Extensions can require features provided by other extensions, identified by strings, with
+//! [`RenderingExtension::get_required`] and [`RenderingExtension::get_provided`]. Extensions with
+//! missing requirements, including the ones requiring features of such extensions, are not
+//! rendered and an error is logged once. Dependencies don't affect the order of the extensions,
+//! that is still determined by the attachments. The extensions can be checked up front with
+//! [`check_dependencies`].
+//!
+//! # Attachments
+//! Besides `color` and `depth_stencil` extensions can declare additional attachments with
+//! [`RenderingExtension::declare_attachments`], the renderer creates them, recreates them when
+//! the resolution changes and destroys them once no extension declares them. They are accessed
+//! with [`crate::rendering::extensions::AttachmentData::get`]. Synchronization between the passes using the same attachment is
+//! handled by wgpu.
+//!
+//! # Resolution
+//! Extensions can render at a different resolution than the frame buffer, by returning a size
+//! from [`RenderingExtension::get_resolution`], for example half resolution particles. Such
+//! extensions receive their own color and depth attachments of that size. After the extension is
+//! rendered the color attachment is upsampled and blended over the `color` attachment, unless the
+//! extension is the first to render the `color` attachment, its color attachment starts
+//! transparent, so only the parts it draws are visible. The depth attachment always starts
+//! cleared, it does not contain the depth of the earlier passes. The attachments are shared by
+//! all the extensions using the same size.
+//!
+//! # Inspection
+//! A frame graph lists all the attachments used during a frame and the passes of the rendering
+//! extensions that use them. Capturing is opt-in, to avoid the allocations on every frame.
+//!
+//!```no_run
+//! lunar_engine::rendering::graph::capture_next_frame();
+//! //Render a frame
+//! if let Some(graph) = lunar_engine::rendering::graph::take_capture() {
+//!     std::fs::write("frame.dot", graph.to_dot()).unwrap();
+//! }
+//!```
+use std::{
+    cell::RefCell,
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        RwLock,
+    },
+};
+
+use crate::{DEVICE, FORMAT};
+
+use super::extensions::RenderingExtension;
+
+static CAPTURE: AtomicBool = AtomicBool::new(false);
+static CAPTURED: RwLock<Option<FrameGraph>> = RwLock::new(None);
+static ORDER: RwLock<Vec<ScheduledExtension>> = RwLock::new(Vec::new());
+
+type Names = &'static [&'static str];
+//(priority, reads, writes, provided, required) of every extension
+type ScheduleKey = Vec<(u32, Names, Names, Names, Names)>;
+type ScaledTextures = (
+    (u32, u32),
+    wgpu::Texture,
+    wgpu::Texture,
+    Option<wgpu::Texture>,
+);
+///Color, depth and resolve views of the attachments of an extension rendering at a different
+///resolution
+pub(crate) type ScaledViews = (
+    wgpu::TextureView,
+    wgpu::TextureView,
+    Option<wgpu::TextureView>,
+    AttachmentInfo,
+);
+
+//Attachment declared by an extension, its size and its texture
+type TransientAttachment = (AttachmentDescriptor, (u32, u32), wgpu::Texture);
+
+thread_local! {
+    //Order of the extensions in the last frame, only resolved again if the extensions change
+    static SCHEDULE: RefCell<Option<(ScheduleKey, Vec<usize>)>> = const { RefCell::new(None) };
+    //Attachments declared by the extensions, along with their size
+    static TRANSIENT: RefCell<Vec<TransientAttachment>> = const { RefCell::new(Vec::new()) };
+    //Color, depth and, with multisampling, resolve attachments of the extensions rendering at a
+    //different resolution
+    static SCALED: RefCell<Vec<ScaledTextures>> = const { RefCell::new(Vec::new()) };
+}
+
+///Size of an attachment declared by an extension, or of the attachments an extension renders
+///into
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum AttachmentSize {
+    ///Same size as the frame buffer
+    #[default]
+    Full,
+    ///Size of the frame buffer multiplied by the factor
+    Scaled(f32),
+    ///Fixed size in pixels
+    Fixed(u32, u32),
+}
+
+impl AttachmentSize {
+    ///Returns the size in pixels for the frame buffer of the given size, at least 1x1
+    #[must_use]
+    pub fn resolve(&self, width: u32, height: u32) -> (u32, u32) {
+        let (w, h) = match *self {
+            Self::Full => (width, height),
+            Self::Scaled(f) => (
+                (width as f32 * f).round() as u32,
+                (height as f32 * f).round() as u32,
+            ),
+            Self::Fixed(w, h) => (w, h),
+        };
+        (w.max(1), h.max(1))
+    }
+}
+
+///Attachment created by the renderer for the extensions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AttachmentDescriptor {
+    ///Name of the attachment, used in [`RenderingExtension::get_attachments`] and
+    ///[`RenderingExtension::get_reads`]
+    pub name: &'static str,
+    ///Format of the attachment, `None` uses the format of the frame buffer
+    pub format: Option<wgpu::TextureFormat>,
+    ///Size of the attachment
+    pub size: AttachmentSize,
+}
+
+impl AttachmentDescriptor {
+    ///Creates a new descriptor of an attachment with the size of the frame buffer
+    #[must_use]
+    pub const fn new(name: &'static str, format: Option<wgpu::TextureFormat>) -> Self {
+        Self {
+            name,
+            format,
+            size: AttachmentSize::Full,
+        }
+    }
+
+    ///Sets the size of the attachment
+    #[must_use]
+    pub const fn with_size(mut self, size: AttachmentSize) -> Self {
+        self.size = size;
+        self
+    }
+}
+
+///Extension requires features no other rendered extension provides
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyError {
+    ///Index of the extension
+    pub index: usize,
+    ///Name of the extension
+    pub name: &'static str,
+    ///Features that are not provided
+    pub missing: Vec<&'static str>,
+}
+
+impl std::fmt::Display for DependencyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Rendering extension {} ({}) requires {:?}, which no other extension provides",
+            self.name, self.index, self.missing
+        )
+    }
+}
+
+impl std::error::Error for DependencyError {}
+
+///Extension rendered in a frame, see [`extension_order`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduledExtension {
+    ///Index of the extension in the slice passed to [`crate::rendering::render`]
+    pub index: usize,
+    ///Name of the extension
+    pub name: &'static str,
+    ///Priority of the extension
+    pub priority: u32,
+}
+
+///The extensions depend on each other in a cycle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleError {
+    ///Indices of the extensions that could not be ordered
+    pub passes: Vec<usize>,
+}
+
+impl std::fmt::Display for CycleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Rendering extensions {:?} depend on each other in a cycle",
+            self.passes
+        )
+    }
+}
+
+impl std::error::Error for CycleError {}
+
+///Attachment used during a frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttachmentInfo {
+    ///Name of the attachment
+    pub name: &'static str,
+    ///Format of the attachment
+    pub format: wgpu::TextureFormat,
+    ///Width of the attachment
+    pub width: u32,
+    ///Height of the attachment
+    pub height: u32,
+}
+
+///Pass performed by a rendering extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassInfo {
+    ///Name of the extension
+    pub name: String,
+    ///Priority of the extension
+    pub priority: u32,
+    ///Names of the attachments the pass reads from
+    pub reads: Vec<&'static str>,
+    ///Names of the attachments the pass writes to
+    pub writes: Vec<&'static str>,
+}
+
+///Attachments and passes of a single frame, in the order of execution
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrameGraph {
+    ///All the attachments used during the frame
+    pub attachments: Vec<AttachmentInfo>,
+    ///All the passes of the frame
+    pub passes: Vec<PassInfo>,
+}
+
+impl FrameGraph {
+    ///Returns the graph in the graphviz DOT format
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let mut o = String::from("digraph frame {\n    rankdir=LR;\n");
+
+        for a in &self.attachments {
+            _ = writeln!(
+                o,
+                "    \"{}\" [shape=box, label=\"{}\\n{:?}\\n{}x{}\"];",
+                a.name, a.name, a.format, a.width, a.height
+            );
+        }
+
+        for (i, p) in self.passes.iter().enumerate() {
+            _ = writeln!(
+                o,
+                "    pass{i} [shape=ellipse, label=\"{}\\npriority {}\"];",
+                escape(&p.name),
+                p.priority
+            );
+            if i > 0 {
+                _ = writeln!(o, "    pass{} -> pass{i} [style=dashed];", i - 1);
+            }
+            for r in &p.reads {
+                _ = writeln!(o, "    \"{r}\" -> pass{i};");
+            }
+            for w in &p.writes {
+                _ = writeln!(o, "    pass{i} -> \"{w}\";");
+            }
+        }
+
+        o.push_str("}\n");
+        o
+    }
+
+    ///Returns the graph in the JSON format
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let attachments = self
+            .attachments
+            .iter()
+            .map(|a| {
+                format!(
+                    "{{\"name\":\"{}\",\"format\":\"{:?}\",\"width\":{},\"height\":{}}}",
+                    a.name, a.format, a.width, a.height
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let passes = self
+            .passes
+            .iter()
+            .map(|p| {
+                format!(
+                    "{{\"name\":\"{}\",\"priority\":{},\"reads\":[{}],\"writes\":[{}]}}",
+                    escape(&p.name),
+                    p.priority,
+                    names(&p.reads),
+                    names(&p.writes)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        format!("{{\"attachments\":[{attachments}],\"passes\":[{passes}]}}")
+    }
+}
+
+///Escapes quotes and backslashes
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+///Joins the attachment names into JSON strings
+fn names(names: &[&str]) -> String {
+    names
+        .iter()
+        .map(|n| format!("\"{n}\""))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+///Resolves the order the passes are executed in, returns the indices of the passes
+///
+///Passes writing to the same attachment are ordered by their priorities, passes reading an
+///attachment they don't write come after all the passes writing to it. Independent passes are
+///ordered by their priorities. Passes with equal priorities are ordered by their indices, in all
+///cases.
+///
+///# Errors
+///Returns an error if the passes depend on each other in a cycle
+pub fn resolve_order(passes: &[PassInfo]) -> Result<Vec<usize>, CycleError> {
+    let mut edges = vec![Vec::new(); passes.len()];
+    let mut incoming = vec![0usize; passes.len()];
+    let mut add_edge = |from: usize, to: usize| {
+        if !edges[from].contains(&to) {
+            edges[from].push(to);
+            incoming[to] += 1;
+        }
+    };
+
+    let mut attachments = passes
+        .iter()
+        .flat_map(|p| p.reads.iter().chain(p.writes.iter()))
+        .copied()
+        .collect::<Vec<_>>();
+    attachments.sort_unstable();
+    attachments.dedup();
+
+    for a in attachments {
+        let mut writers = (0..passes.len())
+            .filter(|i| passes[*i].writes.contains(&a))
+            .collect::<Vec<_>>();
+        writers.sort_by_key(|i| (passes[*i].priority, *i));
+
+        for w in writers.windows(2) {
+            add_edge(w[0], w[1]);
+        }
+
+        for r in (0..passes.len())
+            .filter(|i| passes[*i].reads.contains(&a) && !passes[*i].writes.contains(&a))
+        {
+            for w in &writers {
+                add_edge(*w, r);
+            }
+        }
+    }
+
+    let mut ready = (0..passes.len())
+        .filter(|i| incoming[*i] == 0)
+        .map(|i| Reverse((passes[i].priority, i)))
+        .collect::<BinaryHeap<_>>();
+    let mut order = Vec::with_capacity(passes.len());
+
+    while let Some(Reverse((_, i))) = ready.pop() {
+        order.push(i);
+        for next in &edges[i] {
+            incoming[*next] -= 1;
+            if incoming[*next] == 0 {
+                ready.push(Reverse((passes[*next].priority, *next)));
+            }
+        }
+    }
+
+    if order.len() == passes.len() {
+        Ok(order)
+    } else {
+        Err(CycleError {
+            passes: (0..passes.len()).filter(|i| !order.contains(i)).collect(),
+        })
+    }
+}
+
+///Returns the indices of the extensions whose requirements are not met along with the missing
+///features, given the (provided, required) features of every extension
+///
+///Extensions requiring features provided only by such extensions are not met either
+fn unmet(features: &[(Names, Names)]) -> Vec<(usize, Vec<&'static str>)> {
+    let mut unmet = Vec::<(usize, Vec<&'static str>)>::new();
+
+    loop {
+        let provided = features
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !unmet.iter().any(|u| u.0 == *i))
+            .flat_map(|(_, f)| f.0.iter())
+            .copied()
+            .collect::<Vec<_>>();
+
+        let before = unmet.len();
+        for (i, (_, required)) in features.iter().enumerate() {
+            if unmet.iter().any(|u| u.0 == i) {
+                continue;
+            }
+            let missing = required
+                .iter()
+                .filter(|r| !provided.contains(r))
+                .copied()
+                .collect::<Vec<_>>();
+            if !missing.is_empty() {
+                unmet.push((i, missing));
+            }
+        }
+
+        if unmet.len() == before {
+            unmet.sort_by_key(|u| u.0);
+            return unmet;
+        }
+    }
+}
+
+///Checks that the requirements of all the extensions are provided by the other extensions
+///
+///# Errors
+///Returns an error for every extension that would not be rendered, because of missing
+///requirements
+pub fn check_dependencies(
+    extensions: &[&mut dyn RenderingExtension],
+) -> Result<(), Vec<DependencyError>> {
+    let features = extensions
+        .iter()
+        .map(|e| (e.get_provided(), e.get_required()))
+        .collect::<Vec<_>>();
+
+    let errors = unmet(&features)
+        .into_iter()
+        .map(|(index, missing)| DependencyError {
+            index,
+            name: extensions[index].get_name(),
+            missing,
+        })
+        .collect::<Vec<_>>();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+///Returns the order the extensions are rendered in, falls back to the order of the priorities if
+///the extensions depend on each other in a cycle
+///
+///Extensions with unmet requirements are left out
+pub(crate) fn schedule(extensions: &[&mut dyn RenderingExtension]) -> Vec<usize> {
+    let key = extensions
+        .iter()
+        .map(|e| {
+            (
+                e.get_priority(),
+                e.get_reads(),
+                e.get_attachments(),
+                e.get_provided(),
+                e.get_required(),
+            )
+        })
+        .collect::<ScheduleKey>();
+
+    SCHEDULE.with_borrow_mut(|schedule| {
+        if let Some((k, order)) = schedule.as_ref() {
+            if *k == key {
+                return order.clone();
+            }
+        }
+
+        let passes = key
+            .iter()
+            .map(|(priority, reads, writes, _, _)| PassInfo {
+                name: String::new(),
+                priority: *priority,
+                reads: reads.to_vec(),
+                writes: writes.to_vec(),
+            })
+            .collect::<Vec<_>>();
+
+        warn_collisions(&passes);
+        let order = resolve_order(&passes).unwrap_or_else(|e| {
+            log::error!("{e}, rendering them in the order of their priorities");
+            let mut order = (0..passes.len()).collect::<Vec<_>>();
+            order.sort_by_key(|i| (passes[*i].priority, *i));
+            order
+        });
+
+        let features = key.iter().map(|k| (k.3, k.4)).collect::<Vec<_>>();
+        let unmet = unmet(&features);
+        for (index, missing) in &unmet {
+            log::error!(
+                "{}, it will not be rendered",
+                DependencyError {
+                    index: *index,
+                    name: extensions[*index].get_name(),
+                    missing: missing.clone(),
+                }
+            );
+        }
+        let order = order
+            .into_iter()
+            .filter(|i| !unmet.iter().any(|u| u.0 == *i))
+            .collect::<Vec<_>>();
+
+        *schedule = Some((key, order.clone()));
+        order
+    })
+}
+
+///Warns about extensions writing to the same attachment with the same priority, their order
+///depends on the order they are passed to the renderer in
+fn warn_collisions(passes: &[PassInfo]) {
+    for (i, a) in passes.iter().enumerate() {
+        for (j, b) in passes.iter().enumerate().skip(i + 1) {
+            if a.priority == b.priority && a.writes.iter().any(|w| b.writes.contains(w)) {
+                log::warn!(
+                    "Rendering extensions {i} and {j} write to the same attachments with the same \
+                     priority {}, they are rendered in the order they are passed in",
+                    a.priority
+                );
+            }
+        }
+    }
+}
+
+///Returns the extensions rendered in the last frame, in the order they were rendered in
+#[must_use]
+pub fn extension_order() -> Vec<ScheduledExtension> {
+    ORDER.read().unwrap().clone()
+}
+
+///Stores the order of the extensions rendered in the current frame
+pub(crate) fn set_order(extensions: &[&mut dyn RenderingExtension], order: &[usize]) {
+    let mut stored = ORDER.write().unwrap();
+    stored.clear();
+    stored.extend(order.iter().map(|i| ScheduledExtension {
+        index: *i,
+        name: extensions[*i].get_name(),
+        priority: extensions[*i].get_priority(),
+    }));
+}
+
+///Returns the views and the descriptions of the attachments declared by the extensions, creating
+///the textures if needed
+///
+///Textures of the attachments no longer declared are destroyed
+pub(crate) fn transient_attachments(
+    extensions: &[&mut dyn RenderingExtension],
+    width: u32,
+    height: u32,
+) -> Vec<(&'static str, wgpu::TextureView, AttachmentInfo)> {
+    let mut declared = Vec::<AttachmentDescriptor>::new();
+    for d in extensions.iter().flat_map(|e| e.declare_attachments()) {
+        match declared.iter().find(|i| i.name == d.name) {
+            Some(i) if i != d => log::error!(
+                "Attachment {} is declared with different descriptors, using the first one",
+                d.name
+            ),
+            Some(_) => {}
+            None => declared.push(*d),
+        }
+    }
+
+    TRANSIENT.with_borrow_mut(|textures| {
+        textures
+            .retain(|(d, size, _)| declared.contains(d) && *size == d.size.resolve(width, height));
+
+        declared
+            .iter()
+            .map(|d| {
+                let size = d.size.resolve(width, height);
+                let format = d.format.unwrap_or_else(|| *FORMAT.get().unwrap());
+
+                let i = textures.iter().position(|t| t.0 == *d).unwrap_or_else(|| {
+                    textures.push((*d, size, create_texture(d.name, format, size, 1)));
+                    textures.len() - 1
+                });
+
+                (
+                    d.name,
+                    textures[i]
+                        .2
+                        .create_view(&wgpu::TextureViewDescriptor::default()),
+                    AttachmentInfo {
+                        name: d.name,
+                        format,
+                        width: size.0,
+                        height: size.1,
+                    },
+                )
+            })
+            .collect()
+    })
+}
+
+///Returns the color and depth views of the attachments for every extension rendering at a
+///different resolution than the frame buffer, `None` for the other extensions, creating the
+///textures if needed
+///
+///With multisampling the color and depth attachments are multisampled and the color is resolved
+///into the resolve attachment before it's composited
+///
+///Textures of the sizes no longer used are destroyed
+pub(crate) fn scaled_attachments(
+    extensions: &[&mut dyn RenderingExtension],
+    width: u32,
+    height: u32,
+) -> Vec<Option<ScaledViews>> {
+    let sizes = extensions
+        .iter()
+        .map(|e| Some(e.get_resolution().resolve(width, height)).filter(|s| *s != (width, height)))
+        .collect::<Vec<_>>();
+
+    let samples = super::msaa::sample_count();
+
+    SCALED.with_borrow_mut(|textures| {
+        textures.retain(|(size, color, _, _)| {
+            sizes.contains(&Some(*size))
+                && color.sample_count() == samples
+                && color.format() == super::hdr::color_format()
+        });
+
+        sizes
+            .iter()
+            .map(|size| {
+                let size = (*size)?;
+                let format = super::hdr::color_format();
+
+                let i = textures
+                    .iter()
+                    .position(|t| t.0 == size)
+                    .unwrap_or_else(|| {
+                        let depth = super::depth::format();
+                        textures.push((
+                            size,
+                            create_texture("Scaled color", format, size, samples),
+                            create_texture("Scaled depth", depth, size, samples),
+                            (samples > 1)
+                                .then(|| create_texture("Scaled resolve", format, size, 1)),
+                        ));
+                        textures.len() - 1
+                    });
+
+                let view = wgpu::TextureViewDescriptor::default();
+                Some((
+                    textures[i].1.create_view(&view),
+                    textures[i].2.create_view(&view),
+                    textures[i].3.as_ref().map(|t| t.create_view(&view)),
+                    AttachmentInfo {
+                        name: "scaled_color",
+                        format,
+                        width: size.0,
+                        height: size.1,
+                    },
+                ))
+            })
+            .collect()
+    })
+}
+
+///Releases the attachments declared by the extensions and the ones of the extensions rendering
+///at a different resolution
+pub(crate) fn release() {
+    TRANSIENT.set(Vec::new());
+    SCALED.set(Vec::new());
+}
+
+fn create_texture(
+    name: &str,
+    format: wgpu::TextureFormat,
+    size: (u32, u32),
+    samples: u32,
+) -> wgpu::Texture {
+    //Multisampled textures can only be rendered to and resolved
+    let usage = if samples > 1 {
+        wgpu::TextureUsages::RENDER_ATTACHMENT
+    } else if format.is_depth_stencil_format() {
+        super::depth::usage()
+    } else {
+        wgpu::TextureUsages::RENDER_ATTACHMENT
+            | wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_SRC
+            | wgpu::TextureUsages::COPY_DST
+    };
+
+    DEVICE
+        .get()
+        .unwrap()
+        .create_texture(&wgpu::TextureDescriptor {
+            label: Some(name),
+            size: wgpu::Extent3d {
+                width: size.0,
+                height: size.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: samples,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage,
+            view_formats: &[],
+        })
+}
+
+///Captures the frame graph of the next rendered frame
+pub fn capture_next_frame() {
+    CAPTURE.store(true, Ordering::Relaxed);
+}
+
+///Returns the last captured frame graph, if a frame was captured since the last call
+#[must_use]
+pub fn take_capture() -> Option<FrameGraph> {
+    CAPTURED.write().unwrap().take()
+}
+
+///Returns `true` if the current frame should be captured
+pub(crate) fn capturing() -> bool {
+    CAPTURE.load(Ordering::Relaxed)
+}
+
+///Stores the captured graph
+pub(crate) fn store(graph: FrameGraph) {
+    CAPTURE.store(false, Ordering::Relaxed);
+    *CAPTURED.write().unwrap() = Some(graph);
+}
@@ -0,0 +1,101 @@
+//! High dynamic range rendering
+//!
+//! When enabled, the extensions render into an [`FORMAT`] color attachment, that can hold values
+//! above 1. The [`Tonemap`] extension then maps the colors into the range of the frame buffer,
+//! writing the `output` attachment. Without it the colors are clamped.
+//!
+//!```no_run
+//! use lunar_engine::rendering::{
+//!     extensions::{Base, Tonemap, TonemapOperator},
+//!     hdr,
+//! };
+//!
+//! hdr::set_enabled(true);
+//! let mut base = Base::new(0);
+//! let mut tonemap = Tonemap::new(1, TonemapOperator::Aces);
+//!```
+//!
+//! Pipelines rendering into the color attachment must use [`color_format`] and be recreated when
+//! it changes, like the ones rendering with multisampling, see [`super::msaa`].
+//!
+//! [`Tonemap`]: super::extensions::Tonemap
+use std::{
+    cell::RefCell,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::DEVICE;
+
+///Format of the color attachment with HDR rendering enabled
+pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    //HDR color attachment, or the resolve target of the multisampled one
+    static TARGET: RefCell<Option<wgpu::Texture>> = const { RefCell::new(None) };
+}
+
+///Enables or disables HDR rendering, the attachments and the pipelines are recreated on the next
+///frame
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+///Returns `true` if HDR rendering is enabled
+#[must_use]
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+///Returns the format of the color attachment the extensions render into, [`FORMAT`] with HDR
+///rendering enabled, the format of the frame buffer otherwise
+#[must_use]
+pub fn color_format() -> wgpu::TextureFormat {
+    if is_enabled() {
+        FORMAT
+    } else {
+        *crate::FORMAT.get().unwrap()
+    }
+}
+
+///Releases the HDR color attachment
+pub(crate) fn release() {
+    TARGET.set(None);
+}
+
+///Returns a view of the single sampled HDR color attachment, creating it if needed
+pub(crate) fn target(width: u32, height: u32) -> wgpu::TextureView {
+    TARGET.with_borrow_mut(|target| {
+        let outdated = target
+            .as_ref()
+            .is_none_or(|t| t.width() != width || t.height() != height);
+        if outdated {
+            *target = Some(
+                DEVICE
+                    .get()
+                    .unwrap()
+                    .create_texture(&wgpu::TextureDescriptor {
+                        label: Some("HDR color"),
+                        size: wgpu::Extent3d {
+                            width,
+                            height,
+                            depth_or_array_layers: 1,
+                        },
+                        mip_level_count: 1,
+                        sample_count: 1,
+                        dimension: wgpu::TextureDimension::D2,
+                        format: FORMAT,
+                        usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                            | wgpu::TextureUsages::TEXTURE_BINDING,
+                        view_formats: &[],
+                    }),
+            );
+        }
+
+        target
+            .as_ref()
+            .unwrap()
+            .create_view(&wgpu::TextureViewDescriptor::default())
+    })
+}
@@ -0,0 +1,126 @@
+//! Indirect draws of the instance groups drawn by [`Base`](super::extensions::Base)
+//!
+//! The arguments of the draws are stored in a gpu buffer, one [`DrawIndexedIndirectArgs`] per
+//! instance group, with the instances of all the groups in a single instance buffer. The gpu can
+//! then change the draws without the cpu knowing about it, e.g. when culling the instances. Runs
+//! of groups drawing the same mesh with the same material are submitted with a single multi draw
+//! where it's supported.
+//!
+//! The instances of a group start at its first instance, so the indirect draws need
+//! [`wgpu::Features::INDIRECT_FIRST_INSTANCE`], which is not available on every gpu and the web
+//! backends. Use [`supported`] to check, the groups should be drawn directly otherwise.
+use std::ops::Range;
+
+use wgpu::util::{DeviceExt, DrawIndexedIndirectArgs};
+
+use crate::DEVICE;
+
+///Features used by the indirect draws, requested if the adapter supports them
+pub(crate) const FEATURES: wgpu::Features =
+    wgpu::Features::INDIRECT_FIRST_INSTANCE.union(wgpu::Features::MULTI_DRAW_INDIRECT);
+
+///Size of the arguments of a single draw
+pub(crate) const COMMAND_SIZE: u64 = std::mem::size_of::<DrawIndexedIndirectArgs>() as u64;
+
+///Returns `true` if the groups can be drawn with indirect draws
+pub(crate) fn supported() -> bool {
+    DEVICE.get().is_some_and(|d| {
+        d.features()
+            .contains(wgpu::Features::INDIRECT_FIRST_INSTANCE)
+    })
+}
+
+///Returns `true` if multiple indirect draws can be submitted with a single call
+fn multi_draw_supported() -> bool {
+    DEVICE
+        .get()
+        .is_some_and(|d| d.features().contains(wgpu::Features::MULTI_DRAW_INDIRECT))
+}
+
+///Returns the bytes of the draw `commands`, as they are stored in the gpu buffer
+pub(crate) fn command_bytes(commands: &[DrawIndexedIndirectArgs]) -> Vec<u8> {
+    commands
+        .iter()
+        .flat_map(DrawIndexedIndirectArgs::as_bytes)
+        .copied()
+        .collect()
+}
+
+///Draw commands of the instance groups, and the buffer storing them
+#[derive(Default)]
+pub(crate) struct DrawCommands {
+    commands: Vec<DrawIndexedIndirectArgs>,
+    buffer: Option<wgpu::Buffer>,
+}
+
+impl DrawCommands {
+    pub(crate) const fn new() -> Self {
+        Self {
+            commands: Vec::new(),
+            buffer: None,
+        }
+    }
+
+    ///Replaces the commands, the buffer is only created if the indirect draws are supported
+    pub(crate) fn set(&mut self, commands: Vec<DrawIndexedIndirectArgs>) {
+        self.buffer = (supported() && !commands.is_empty()).then(|| {
+            DEVICE
+                .get()
+                .unwrap()
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Draw commands"),
+                    contents: &command_bytes(&commands),
+                    usage: wgpu::BufferUsages::INDIRECT
+                        | wgpu::BufferUsages::STORAGE
+                        | wgpu::BufferUsages::COPY_DST
+                        | wgpu::BufferUsages::COPY_SRC,
+                })
+        });
+        self.commands = commands;
+    }
+
+    ///Returns the commands, in the order of the groups
+    pub(crate) fn commands(&self) -> &[DrawIndexedIndirectArgs] {
+        &self.commands
+    }
+
+    ///Returns the buffer of the commands, `None` if the indirect draws are not supported
+    pub(crate) const fn buffer(&self) -> Option<&wgpu::Buffer> {
+        self.buffer.as_ref()
+    }
+}
+
+///Draws the `groups` with the commands in the `buffer`, returns the number of draw calls
+///
+///The groups must use the same mesh and material, which must be bound already
+pub(crate) fn draw<'a>(
+    render_pass: &mut wgpu::RenderPass<'a>,
+    buffer: &'a wgpu::Buffer,
+    groups: Range<usize>,
+) -> usize {
+    let offset = groups.start as u64 * COMMAND_SIZE;
+    if multi_draw_supported() {
+        render_pass.multi_draw_indexed_indirect(buffer, offset, groups.len() as u32);
+        return 1;
+    }
+    for i in 0..groups.len() as u64 {
+        render_pass.draw_indexed_indirect(buffer, offset + i * COMMAND_SIZE);
+    }
+    groups.len()
+}
+
+///Draws the `commands` directly, for gpus without the indirect draws, returns the number of draw
+///calls
+pub(crate) fn draw_direct(
+    render_pass: &mut wgpu::RenderPass<'_>,
+    commands: &[DrawIndexedIndirectArgs],
+) -> usize {
+    for c in commands {
+        render_pass.draw_indexed(
+            c.first_index..c.first_index + c.index_count,
+            c.base_vertex,
+            c.first_instance..c.first_instance + c.instance_count,
+        );
+    }
+    commands.len()
+}
@@ -0,0 +1,455 @@
+//! Lighting shared by the lit materials and the shadow map
+//!
+//! The first [`DirectionalLight`] in the world lights the scene, the ambient light is added to
+//! every lit surface, including the ones in the shadow. Without a directional light the lit
+//! materials only receive the ambient light.
+//!
+//! Point and spot lights are assigned to the clusters of the view by the
+//! [`crate::rendering::extensions::clustered::LightCulling`] extension, each fragment only shades
+//! the lights of it's cluster. The lights are stored in storage buffers, or in uniform buffers of
+//! a limited size when storage buffers are not available, e.g. on WebGL, see [`max_lights`].
+use std::{cell::RefCell, sync::Arc, sync::RwLock};
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::{
+    components::{camera::MainCamera, light::DirectionalLight},
+    ecs::World,
+    errors,
+    grimoire::{
+        LIGHTING_BIND_GROUP_LAYOUT_DESCRIPTOR, LIGHTING_UNIFORM_BIND_GROUP_LAYOUT_DESCRIPTOR,
+    },
+    math::{Mat4x4, Vec3, Vector},
+    structures::Color,
+    DEVICE, QUEUE,
+};
+
+///Number of clusters along the width, height and depth of the view
+pub const CLUSTERS: [u32; 3] = [16, 8, 16];
+pub(crate) const CLUSTER_COUNT: usize = (CLUSTERS[0] * CLUSTERS[1] * CLUSTERS[2]) as usize;
+///Maximum number of lights in a single cluster
+pub const MAX_LIGHTS_PER_CLUSTER: usize = 255;
+
+//Capacities of the buffers, must match the sizes in `lights_uniform.wgsl`
+const STORAGE_LIGHTS: usize = 1024;
+const STORAGE_INDICES: usize = 65536;
+const UNIFORM_LIGHTS: usize = 64;
+const UNIFORM_INDICES: usize = 4096;
+
+static AMBIENT: RwLock<Color> = RwLock::new(Color::rgb(0.15, 0.15, 0.15));
+
+thread_local! {
+    static STATE: RefCell<Option<LightingState>> = const { RefCell::new(None) };
+}
+
+///Data of the light, as used by the shaders
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub(crate) struct LightData {
+    ///View projection matrix of the light
+    pub matrix: Mat4x4,
+    ///Direction towards the light, shadow bias
+    pub direction: [f32; 4],
+    ///Color multiplied by the intensity, 1 if the shadow map is used
+    pub color: [f32; 4],
+    pub ambient: [f32; 4],
+    ///Position of the main camera
+    pub camera: [f32; 4],
+    pub clusters: ClusterParams,
+}
+
+///Parameters of the clusters, as used by the shaders
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub(crate) struct ClusterParams {
+    ///View projection matrix of the camera
+    pub view_projection: Mat4x4,
+    ///Position of the camera, near plane
+    pub camera_position: [f32; 4],
+    ///Forward direction of the camera, far plane
+    pub camera_forward: [f32; 4],
+    ///Number of clusters along every axis, 0 if the clusters are not used
+    pub grid: [u32; 4],
+}
+
+///Point or spot light, as used by the shaders
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub(crate) struct LocalLightData {
+    ///Position, range
+    pub position: [f32; 4],
+    ///Color multiplied by the intensity, 1 for spot lights
+    pub color: [f32; 4],
+    ///Direction of the spot light, cosine of the outer angle
+    pub direction: [f32; 4],
+    ///Cosine of the inner angle of the spot light
+    pub cone: [f32; 4],
+}
+
+struct Buffers {
+    uniform: wgpu::Buffer,
+    lights: wgpu::Buffer,
+    clusters: wgpu::Buffer,
+    indices: wgpu::Buffer,
+}
+
+struct LightingState {
+    buffers: Buffers,
+    params: ClusterParams,
+    sampler: wgpu::Sampler,
+    //Used when there is no shadow map
+    empty: wgpu::TextureView,
+    bind_group: Arc<wgpu::BindGroup>,
+    has_shadow_map: bool,
+}
+
+impl LightingState {
+    fn new() -> Self {
+        let device = DEVICE.get().unwrap();
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Lighting"),
+            size: std::mem::size_of::<LightData>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let usage = if uses_storage_buffers() {
+            wgpu::BufferUsages::STORAGE
+        } else {
+            wgpu::BufferUsages::UNIFORM
+        } | wgpu::BufferUsages::COPY_DST;
+        let (max_lights, max_indices) = max_lights();
+
+        let create = |label, size| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: size as u64,
+                usage,
+                mapped_at_creation: false,
+            })
+        };
+        let lights = create(
+            "Local lights",
+            max_lights * std::mem::size_of::<LocalLightData>(),
+        );
+        let clusters = create("Light clusters", CLUSTER_COUNT * 4);
+        let indices = create("Light indices", max_indices * 4);
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Shadow sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Default::default()
+        });
+
+        let empty = device
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("Empty shadow map"),
+                size: wgpu::Extent3d {
+                    width: 1,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Depth32Float,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let buffers = Buffers {
+            uniform: buffer,
+            lights,
+            clusters,
+            indices,
+        };
+        let bind_group = Arc::new(buffers.create_bind_group(&empty, &sampler));
+
+        Self {
+            buffers,
+            params: ClusterParams::zeroed(),
+            sampler,
+            empty,
+            bind_group,
+            has_shadow_map: false,
+        }
+    }
+}
+
+impl Buffers {
+    fn create_bind_group(
+        &self,
+        shadow_map: &wgpu::TextureView,
+        sampler: &wgpu::Sampler,
+    ) -> wgpu::BindGroup {
+        DEVICE
+            .get()
+            .unwrap()
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Lighting"),
+                layout: &create_bind_group_layout(),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: self.uniform.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(shadow_map),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: self.lights.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: self.clusters.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 5,
+                        resource: self.indices.as_entire_binding(),
+                    },
+                ],
+            })
+    }
+}
+
+///Returns `true` if the local lights are stored in storage buffers, `false` if they are stored in
+///uniform buffers
+pub(crate) fn uses_storage_buffers() -> bool {
+    DEVICE
+        .get()
+        .unwrap()
+        .limits()
+        .max_storage_buffers_per_shader_stage
+        >= 3
+}
+
+///Returns the maximum number of point and spot lights, and the maximum number of lights in all
+///the clusters combined
+///
+///The limits are smaller when the lights are stored in uniform buffers
+#[must_use]
+pub fn max_lights() -> (usize, usize) {
+    if uses_storage_buffers() {
+        (STORAGE_LIGHTS, STORAGE_INDICES)
+    } else {
+        (UNIFORM_LIGHTS, UNIFORM_INDICES)
+    }
+}
+
+///Creates the layout of the lighting bind group
+pub(crate) fn create_bind_group_layout() -> wgpu::BindGroupLayout {
+    DEVICE
+        .get()
+        .unwrap()
+        .create_bind_group_layout(if uses_storage_buffers() {
+            &LIGHTING_BIND_GROUP_LAYOUT_DESCRIPTOR
+        } else {
+            &LIGHTING_UNIFORM_BIND_GROUP_LAYOUT_DESCRIPTOR
+        })
+}
+
+///Compiles a fragment shader using the lighting bind group, declaring the buffers of the local
+///lights and the shared lighting functions of `lighting.wgsl` before the `source`
+pub(crate) fn compile_shader(
+    file: &str,
+    source: &str,
+) -> Result<wgpu::ShaderModule, errors::Error> {
+    let (prelude_file, prelude) = if uses_storage_buffers() {
+        (
+            "lights_storage.wgsl",
+            include_str!("../shaders/lights_storage.wgsl"),
+        )
+    } else {
+        (
+            "lights_uniform.wgsl",
+            include_str!("../shaders/lights_uniform.wgsl"),
+        )
+    };
+
+    let lighting = include_str!("../shaders/lighting.wgsl");
+
+    let mut map = crate::assets::shader::SourceMap::new();
+    for (f, text) in [
+        (prelude_file, prelude),
+        ("lighting.wgsl", lighting),
+        (file, source),
+    ] {
+        for l in 1..=text.lines().count() as u32 {
+            map.push(f, l);
+        }
+    }
+    //The included files end with a new line
+    crate::assets::shader::compile_mapped(&format!("{prelude}{lighting}{source}"), &map)
+}
+
+///Releases the buffers of the lights and the shadow maps
+pub(crate) fn release() {
+    STATE.set(None);
+}
+
+fn with_state<R>(f: impl FnOnce(&mut LightingState) -> R) -> R {
+    STATE.with_borrow_mut(|s| f(s.get_or_insert_with(LightingState::new)))
+}
+
+///Sets the color of the ambient light
+pub fn set_ambient(color: Color) {
+    *AMBIENT.write().unwrap() = color;
+}
+
+///Returns the color of the ambient light
+#[must_use]
+pub fn get_ambient() -> Color {
+    *AMBIENT.read().unwrap()
+}
+
+///Returns the view projection matrix of a directional light shining in the `direction`, covering
+///a `size` by `size` by `depth` box centered on the `center`
+///
+///Maps the depth to the range 0 to 1, regardless of [`crate::rendering::depth::DepthRange`]
+#[must_use]
+pub fn light_matrix(direction: Vec3, center: Vec3, size: f32, depth: f32) -> Mat4x4 {
+    let direction = direction.normalized();
+    let position = center - direction * (depth / 2.0);
+
+    //Any up vector not parallel to the direction works
+    let up = if direction.y.abs() > 0.99 {
+        Vec3::new(1.0, 0.0, 0.0)
+    } else {
+        Vec3::new(0.0, 1.0, 0.0)
+    };
+    let up = direction.cross(&up).cross(&direction).normalized();
+
+    let view = Mat4x4::look_at_matrix(position, up, position + direction);
+    let projection = Mat4x4 {
+        m00: 2.0 / size,
+        m11: 2.0 / size,
+        m22: -1.0 / depth,
+        ..Default::default()
+    };
+
+    view * projection
+}
+
+///Updates the lighting data for the current frame, returns it along with the light
+pub(crate) fn update(world: &World) -> LightData {
+    let ambient = get_ambient();
+    let ambient = [ambient.r, ambient.g, ambient.b, 1.0];
+    let clusters = with_state(|s| s.params);
+
+    //Position of the main camera, the shadows follow it
+    let camera = world
+        .get_all_components::<MainCamera>()
+        .and_then(|c| {
+            c.first().map(|c| {
+                let m = c.borrow().camera_transform();
+                Vec3::new(m.m03, m.m13, m.m23)
+            })
+        })
+        .unwrap_or_default();
+
+    let light = world
+        .get_all_components::<DirectionalLight>()
+        .and_then(|l| l.first().cloned());
+
+    let data = light.map_or(
+        LightData {
+            matrix: Mat4x4::identity(),
+            direction: [0.0, 0.0, 1.0, 0.0],
+            color: [0.0; 4],
+            ambient,
+            camera: [camera.x, camera.y, camera.z, 1.0],
+            clusters,
+        },
+        |l| {
+            let l = l.borrow();
+            let direction = l.direction();
+
+            let shadows = l.cast_shadows && with_state(|s| s.has_shadow_map);
+            LightData {
+                matrix: light_matrix(direction, camera, l.shadow_size, l.shadow_depth),
+                direction: [-direction.x, -direction.y, -direction.z, l.shadow_bias],
+                color: [
+                    l.color.r * l.intensity,
+                    l.color.g * l.intensity,
+                    l.color.b * l.intensity,
+                    if shadows { 1.0 } else { 0.0 },
+                ],
+                ambient,
+                camera: [camera.x, camera.y, camera.z, 1.0],
+                clusters,
+            }
+        },
+    );
+
+    with_state(|s| {
+        QUEUE
+            .get()
+            .unwrap()
+            .write_buffer(&s.buffers.uniform, 0, bytemuck::bytes_of(&data));
+    });
+    data
+}
+
+///Returns `true` if the light of the world casts shadows
+pub(crate) fn casts_shadows(world: &World) -> bool {
+    world
+        .get_all_components::<DirectionalLight>()
+        .and_then(|l| l.first().map(|l| l.borrow().cast_shadows))
+        .unwrap_or(false)
+}
+
+///Sets the shadow map sampled by the lit materials, `None` disables the shadows
+pub(crate) fn set_shadow_map(shadow_map: Option<&wgpu::TextureView>) {
+    with_state(|s| {
+        s.has_shadow_map = shadow_map.is_some();
+        s.bind_group = Arc::new(
+            s.buffers
+                .create_bind_group(shadow_map.unwrap_or(&s.empty), &s.sampler),
+        );
+    });
+}
+
+///Returns the bind group of the lighting
+pub(crate) fn bind_group() -> Arc<wgpu::BindGroup> {
+    with_state(|s| s.bind_group.clone())
+}
+
+///Uploads the point and spot lights along with the clusters they are assigned to
+///
+///Every cluster is packed into a single value, the offset of it's first light in the `indices`
+///shifted by 8 bits, and the number of it's lights in the lowest 8 bits. The `lights` and the
+///`indices` must fit into the limits of [`max_lights`]
+pub(crate) fn set_clusters(
+    lights: &[LocalLightData],
+    clusters: &[u32],
+    indices: &[u32],
+    params: ClusterParams,
+) {
+    let (max_lights, max_indices) = max_lights();
+    debug_assert!(lights.len() <= max_lights && indices.len() <= max_indices);
+    debug_assert_eq!(clusters.len(), CLUSTER_COUNT);
+
+    let queue = QUEUE.get().unwrap();
+    with_state(|s| {
+        s.params = params;
+        if !lights.is_empty() {
+            queue.write_buffer(&s.buffers.lights, 0, bytemuck::cast_slice(lights));
+        }
+        if !indices.is_empty() {
+            queue.write_buffer(&s.buffers.indices, 0, bytemuck::cast_slice(indices));
+        }
+        queue.write_buffer(&s.buffers.clusters, 0, bytemuck::cast_slice(clusters));
+    });
+}
@@ -9,29 +9,77 @@
 //! The render function accepts a world and an asset store.
 //! The rendering function gets the asset ids and queries them from the store.
 
+use std::sync::RwLock;
+
 use log::trace;
 
 use crate::{
-    asset_managment::AssetStore, ecs::World, DEPTH, DEVICE, FORMAT, QUEUE, STAGING_BELT, SURFACE,
+    asset_managment::{self, AssetStore},
+    assets::{BindgroupState, Material},
+    ecs::World,
+    timeline, DEPTH, DEVICE, FORMAT, QUEUE, RESOLUTION, STAGING_BELT, SURFACE,
 };
 
 use self::extensions::{AttachmentData, RenderingExtension};
 
+//Color attachment the materials were last initialized for, `None` until a material is initialized
+static MATERIAL_TARGETS: RwLock<Option<(u32, wgpu::TextureFormat)>> = RwLock::new(None);
+
+pub mod background;
+pub mod batching;
+pub mod bindless;
+pub mod capabilities;
+pub mod compute;
+pub mod debug;
+pub mod depth;
+pub mod draw_data;
 ///System for making custom renderers for objects, also contains implemented rendering extensions
 pub mod extensions;
+pub mod fullscreen;
+pub mod graph;
+pub mod hdr;
+pub(crate) mod indirect;
+pub mod lighting;
+pub mod msaa;
+mod occlusion;
+pub mod post;
+pub mod profiling;
+pub mod screenshot;
+pub mod surface;
+#[cfg(test)]
+mod tests;
+mod upsample;
+pub mod viewport;
+
+pub use background::{clear_color, set_clear_color, Background};
+pub use batching::bake_static_batch;
+pub use capabilities::capabilities;
+pub use extensions::picking::pick_pixel;
+pub use graph::extension_order;
+pub use profiling::frame_gpu_timings;
 
 ///Renders all the entities in the world
 pub fn render(world: &World, assets: &AssetStore, extensions: &mut [&mut dyn RenderingExtension]) {
     trace!("Beginning of the render function");
+    let _span = timeline::span_with_category("Render", "engine");
+
+    crate::validation::validate_frame(world, assets);
+    screenshot::poll();
+    profiling::poll();
+    #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+    assets.reload_changed();
 
     let device = DEVICE.get().unwrap();
-    let mut encoder =
-        device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    let encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Main encoder"),
+    });
 
     let color = SURFACE
         .get()
         .and_then(|i| i.read().ok())
         .unwrap()
+        .as_ref()
+        .expect("The surface was released")
         .get_current_texture()
         .unwrap();
     trace!("Accquiered surface");
@@ -47,34 +95,277 @@ pub fn render(world: &World, assets: &AssetStore, extensions: &mut [&mut dyn Ren
         array_layer_count: None,
     });
 
-    let depth_setencil_veiw =
-        DEPTH
-            .get()
-            .unwrap()
-            .read()
-            .unwrap()
-            .create_view(&wgpu::TextureViewDescriptor {
-                label: Some("Depth stencil attachment"),
-                format: Some(wgpu::TextureFormat::Depth32Float),
-                dimension: Some(wgpu::TextureViewDimension::D2),
-                aspect: wgpu::TextureAspect::DepthOnly,
-                base_mip_level: 0,
-                mip_level_count: None,
-                base_array_layer: 0,
-                array_layer_count: None,
-            });
+    //Recreate the depth attachment if the sample count or its configuration changed
+    let outdated = DEPTH
+        .get()
+        .unwrap()
+        .read()
+        .unwrap()
+        .as_ref()
+        .is_none_or(|d| {
+            d.sample_count() != msaa::sample_count()
+                || d.format() != depth::format()
+                || d.usage() != depth::usage()
+        });
+    if outdated {
+        let texture = color.texture.size();
+        let desc = crate::windowing::get_depth_descriptor(texture.width, texture.height);
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            *DEPTH.get().unwrap().write().unwrap() = Some(crate::wrappers::WgpuWrapper::new(
+                device.create_texture(&desc),
+            ));
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            *DEPTH.get().unwrap().write().unwrap() = Some(device.create_texture(&desc));
+        }
+    }
+
+    let depth_setencil_veiw = DEPTH
+        .get()
+        .unwrap()
+        .read()
+        .unwrap()
+        .as_ref()
+        .unwrap()
+        .create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Depth stencil attachment"),
+            format: Some(depth::format()),
+            dimension: Some(wgpu::TextureViewDimension::D2),
+            aspect: if depth::has_stencil() {
+                wgpu::TextureAspect::All
+            } else {
+                wgpu::TextureAspect::DepthOnly
+            },
+            base_mip_level: 0,
+            mip_level_count: None,
+            base_array_layer: 0,
+            array_layer_count: None,
+        });
+
+    trace!("Created attachment views");
+
+    let mut frame_graph = graph::capturing().then(|| {
+        let depth = DEPTH.get().unwrap().read().unwrap();
+        let depth = depth.as_ref().unwrap();
+        graph::FrameGraph {
+            attachments: vec![
+                graph::AttachmentInfo {
+                    name: "color",
+                    format: hdr::color_format(),
+                    width: color.texture.width(),
+                    height: color.texture.height(),
+                },
+                graph::AttachmentInfo {
+                    name: "depth_stencil",
+                    format: depth.format(),
+                    width: depth.width(),
+                    height: depth.height(),
+                },
+            ]
+            .into_iter()
+            .chain(hdr::is_enabled().then(|| graph::AttachmentInfo {
+                name: "output",
+                format: *FORMAT.get().unwrap(),
+                width: color.texture.width(),
+                height: color.texture.height(),
+            }))
+            .collect(),
+            passes: Vec::new(),
+        }
+    });
+
+    execute(
+        world,
+        assets,
+        extensions,
+        (color_view, depth_setencil_veiw),
+        encoder,
+        frame_graph.as_mut(),
+    );
+
+    let present = timeline::span_with_category("Present", "engine");
+    screenshot::capture(&color.texture);
+    color.present();
+    drop(present);
 
-    let attachments = AttachmentData {
-        color: color_view,
-        depth_stencil: depth_setencil_veiw,
+    if let Some(g) = frame_graph {
+        graph::store(g);
+    }
+}
+
+///Runs all the extensions on the (color, depth stencil) views and submits the recorded commands
+fn execute(
+    world: &World,
+    assets: &AssetStore,
+    extensions: &mut [&mut dyn RenderingExtension],
+    (color, depth_stencil): (wgpu::TextureView, wgpu::TextureView),
+    mut encoder: wgpu::CommandEncoder,
+    mut frame_graph: Option<&mut graph::FrameGraph>,
+) {
+    update_materials(assets);
+    compute::record_queued(&mut encoder);
+    profiling::begin_frame();
+
+    //With post processing the extensions render into an intermediate texture
+    let resolution = *RESOLUTION.read().unwrap();
+    let (target, output) = match post::intermediate(resolution.width, resolution.height) {
+        Some(intermediate) => (intermediate, Some(color)),
+        None => (color, None),
+    };
+    let (width, height) = (resolution.width, resolution.height);
+    let (attachments, target) = if hdr::is_enabled() {
+        //With HDR rendering they render into an HDR texture, tonemapped into the target
+        let color = msaa::color_target(width, height).unwrap_or_else(|| hdr::target(width, height));
+        (
+            AttachmentData::new(color, depth_stencil).with_hdr(hdr::target(width, height), target),
+            None,
+        )
+    } else if let Some(multisampled) = msaa::color_target(width, height) {
+        //With multisampling they render into a multisampled texture, resolved into the target
+        (
+            AttachmentData::new(multisampled, depth_stencil),
+            Some(target),
+        )
+    } else {
+        (AttachmentData::new(target, depth_stencil), None)
     };
 
-    trace!("Created attachment data");
+    let mut declared = Vec::new();
+    for (name, view, info) in
+        graph::transient_attachments(extensions, resolution.width, resolution.height)
+    {
+        if let Some(g) = &mut frame_graph {
+            g.attachments.push(info);
+        }
+        declared.push((name, view));
+    }
+    let attachments = &attachments.with_declared(declared);
+
+    let mut scaled = graph::scaled_attachments(extensions, resolution.width, resolution.height);
+    if let Some(g) = &mut frame_graph {
+        for (_, _, _, info) in scaled.iter().flatten() {
+            if !g.attachments.contains(info) {
+                g.attachments.push(info.clone());
+            }
+        }
+    }
+
+    let order = graph::schedule(extensions);
+    graph::set_order(extensions, &order);
+
+    for i in order {
+        //Extensions rendering at a different resolution render into their own attachments
+        let scaled = scaled[i]
+            .take()
+            .map(|(color, depth_stencil, resolve, info)| {
+                let declared =
+                    graph::transient_attachments(extensions, resolution.width, resolution.height)
+                        .into_iter()
+                        .map(|(name, view, _)| (name, view))
+                        .collect();
+                if attachments.color_written() {
+                    upsample::clear(&mut encoder, &color);
+                }
+                viewport::set_scale(
+                    info.width as f32 / resolution.width as f32,
+                    info.height as f32 / resolution.height as f32,
+                );
+                (attachments.scaled(color, depth_stencil, declared), resolve)
+            });
 
-    for e in extensions {
+        let e = &mut extensions[i];
         trace!("Calling render on an extension");
-        e.render(&mut encoder, world, assets, &attachments);
+        if let Some(g) = &mut frame_graph {
+            g.passes.push(graph::PassInfo {
+                name: e.get_name().to_owned(),
+                priority: e.get_priority(),
+                reads: e.get_reads().to_vec(),
+                writes: e.get_attachments().to_vec(),
+            });
+        }
+        encoder.push_debug_group(e.get_name());
+        let span = timeline::span_with_category(e.get_name(), "extension");
+        profiling::begin_extension(&mut encoder, e.get_name());
+        e.render(
+            &mut encoder,
+            world,
+            assets,
+            scaled.as_ref().map_or(attachments, |s| &s.0),
+        );
+        profiling::end_extension(&mut encoder);
+        if let Some((scaled, resolve)) = &scaled {
+            viewport::set_scale(1.0, 1.0);
+            if let Some(resolve) = resolve {
+                msaa::resolve(&mut encoder, &scaled.color, resolve);
+            }
+            upsample::composite(
+                &mut encoder,
+                resolve.as_ref().unwrap_or(&scaled.color),
+                &attachments.color,
+            );
+            if let Some(g) = &mut frame_graph {
+                g.passes.push(graph::PassInfo {
+                    name: "Upsample".to_owned(),
+                    priority: e.get_priority(),
+                    reads: vec!["scaled_color"],
+                    writes: vec!["color"],
+                });
+            }
+        }
+        drop(span);
+        encoder.pop_debug_group();
+        //Later passes keep the contents instead of clearing them
+        if scaled.is_some() {
+            //The depth of the frame buffer is not touched by the scaled passes
+            let written = e
+                .get_attachments()
+                .iter()
+                .copied()
+                .filter(|a| *a != "depth_stencil")
+                .chain(["color"])
+                .collect::<Vec<_>>();
+            attachments.mark_written(&written);
+        } else {
+            attachments.mark_written(e.get_attachments());
+        }
+    }
+    if let Some(target) = &target {
+        msaa::resolve(&mut encoder, &attachments.color, target);
+        if let Some(g) = &mut frame_graph {
+            g.passes.push(graph::PassInfo {
+                name: "Resolve".to_owned(),
+                priority: u32::MAX,
+                reads: vec!["color"],
+                writes: vec!["color"],
+            });
+        }
     }
+    if hdr::is_enabled() {
+        extensions::tonemap::fallback(&mut encoder, world, assets, attachments);
+        if let Some(g) = &mut frame_graph {
+            if !g.passes.iter().any(|p| p.writes.contains(&"output")) {
+                g.passes.push(graph::PassInfo {
+                    name: "Tonemap".to_owned(),
+                    priority: u32::MAX,
+                    reads: vec!["color"],
+                    writes: vec!["output"],
+                });
+            }
+        }
+    }
+    if let Some(output) = &output {
+        post::apply(&mut encoder, output);
+    }
+    let target = target
+        .as_ref()
+        .or(attachments.output.as_ref())
+        .unwrap_or(&attachments.color);
+    viewport::draw_bars(&mut encoder, output.as_ref().unwrap_or(target));
+    profiling::resolve(&mut encoder);
 
     let cmd_buffer = encoder.finish();
 
@@ -84,9 +375,260 @@ pub fn render(world: &World, assets: &AssetStore, extensions: &mut [&mut dyn Ren
     belt.finish();
 
     queue.submit(Some(cmd_buffer));
+    profiling::submitted();
 
     belt.recall();
-    drop(belt);
 
-    color.present();
+    //The lines are drawn again every frame
+    debug::clear();
+}
+
+///Returns the sample count and the format of the color attachment the extensions render into,
+///pipelines rendering into it must be recreated when they change
+pub(crate) fn color_targets() -> (u32, wgpu::TextureFormat) {
+    (msaa::sample_count(), hdr::color_format())
+}
+
+///Releases the gpu resources cached by the renderer, in the reverse order of the passes using them
+pub(crate) fn release() {
+    extensions::tonemap::release();
+    upsample::release();
+    post::release();
+    viewport::release();
+    graph::release();
+    hdr::release();
+    msaa::release();
+    lighting::release();
+    bindless::release();
+    compute::release();
+    profiling::release();
+    *MATERIAL_TARGETS.write().unwrap() = None;
+}
+
+///Records the color attachment a material is being initialized for, unless the materials already
+///have one
+pub(crate) fn material_initialized() {
+    MATERIAL_TARGETS
+        .write()
+        .unwrap()
+        .get_or_insert_with(color_targets);
+}
+
+///Reinitializes the materials if the color attachment changed since they were initialized, see
+///[`color_targets`]
+fn update_materials(assets: &AssetStore) {
+    let targets = color_targets();
+    let previous = MATERIAL_TARGETS.write().unwrap().replace(targets);
+    if previous.is_none_or(|p| p == targets) {
+        return;
+    }
+
+    //Disposing also resets the bind groups, they are recreated by the extensions
+    for m in assets.get_all_raw::<Material>() {
+        let mut m = m.write();
+        if m.is_initialized() {
+            m.dispose();
+            if let Err(e) = m.initialize() {
+                log::error!("Failed to reinitialize a material: {e}");
+            }
+        }
+    }
+}
+
+///Renders all the entities in the world into an image of the given size instead of the window
+///
+///Does not require a window, only the device, the queue, the staging belt and the format must be
+///initialized. Used for screenshot based tests, not available on the web target. The resolution
+///is set to the size of the image for the duration of the call.
+///
+///Returns tightly packed RGBA8 pixels, starting at the top left corner
+///
+///# Panics
+///Panics if the format is not an 8 bit RGBA or BGRA format
+#[cfg(not(target_arch = "wasm32"))]
+#[must_use]
+pub fn render_to_image(
+    world: &World,
+    assets: &AssetStore,
+    extensions: &mut [&mut dyn RenderingExtension],
+    width: u32,
+    height: u32,
+) -> Vec<u8> {
+    let device = DEVICE.get().unwrap();
+    let format = *FORMAT.get().unwrap();
+    let bgra = match format {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+        f => panic!("Unsupported format for rendering into an image: {f:?}"),
+    };
+
+    let resolution = {
+        let mut r = RESOLUTION.write().unwrap();
+        std::mem::replace(&mut *r, winit::dpi::PhysicalSize::new(width, height))
+    };
+
+    let color = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Image color attachment"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    let depth = device.create_texture(&crate::windowing::get_depth_descriptor(width, height));
+
+    let views = (
+        color.create_view(&wgpu::TextureViewDescriptor::default()),
+        depth.create_view(&wgpu::TextureViewDescriptor::default()),
+    );
+
+    let encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Image encoder"),
+    });
+    execute(world, assets, extensions, views, encoder, None);
+
+    //Copy the image into a buffer that can be read
+    let bpr = crate::helpers::calculate_bpr(width, format);
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Image read back"),
+        size: bpr * u64::from(height),
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Image copy encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+        color.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(bpr as u32),
+                rows_per_image: None,
+            },
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+    QUEUE.get().unwrap().submit(Some(encoder.finish()));
+
+    buffer
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, |r| r.unwrap());
+    device.poll(wgpu::Maintain::Wait);
+
+    let mut image = Vec::with_capacity((width * height * 4) as usize);
+    {
+        let data = buffer.slice(..).get_mapped_range();
+        for row in data.chunks_exact(bpr as usize) {
+            image.extend_from_slice(&row[..(width * 4) as usize]);
+        }
+    }
+    buffer.unmap();
+
+    if bgra {
+        for pixel in image.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+
+    *RESOLUTION.write().unwrap() = resolution;
+
+    image
+}
+
+///Initializes all the materials in the asset store along with their bind groups
+///
+///Materials are otherwise initialized during rendering, causing hitches on the frames new
+///materials appear on, calling this after loading a scene avoids that
+///
+///# Errors
+///Returns an error if one of the materials fails to initialize
+pub fn prepare_materials(assets: &AssetStore) -> Result<(), asset_managment::Error> {
+    for m in assets.get_all_by_type::<Material>()? {
+        let mut m = m.borrow_mut();
+        if matches!(m.get_bindgroup_state(), BindgroupState::Uninitialized) {
+            m.initialize_bindgroups(assets);
+        }
+    }
+    Ok(())
+}
+
+///Starts initialization of all the materials in the asset store, including compilation of their
+///pipelines, on a background thread
+///
+///Useful for loading screens, the rendering can continue while the materials are being
+///initialized. Call [`MaterialPreparation::finish`] once [`MaterialPreparation::is_finished`]
+///returns `true` to create the bind groups.
+///
+///On the web the materials are initialized in [`MaterialPreparation::finish`]
+#[must_use]
+pub fn prepare_materials_background(assets: &AssetStore) -> MaterialPreparation {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let materials = assets.get_all_raw::<Material>();
+
+        MaterialPreparation {
+            handle: std::thread::spawn(move || {
+                for m in materials {
+                    let mut m = m.write();
+                    if !m.is_initialized() {
+                        m.initialize()?;
+                    }
+                }
+                Ok(())
+            }),
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        _ = assets;
+        MaterialPreparation {}
+    }
+}
+
+///Materials being initialized in the background, see [`prepare_materials_background`]
+pub struct MaterialPreparation {
+    #[cfg(not(target_arch = "wasm32"))]
+    handle: std::thread::JoinHandle<Result<(), Box<dyn std::error::Error + Send>>>,
+}
+
+impl MaterialPreparation {
+    ///Returns `true` if all the materials are initialized
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.handle.is_finished()
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            true
+        }
+    }
+
+    ///Waits for the initialization to finish and creates the bind groups of the materials
+    ///
+    ///# Errors
+    ///Returns an error if one of the materials fails to initialize
+    pub fn finish(self, assets: &AssetStore) -> Result<(), asset_managment::Error> {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.handle
+            .join()
+            .expect("Material initialization thread panicked")
+            .map_err(|e| asset_managment::Error::InitializationError(e))?;
+
+        prepare_materials(assets)
+    }
 }
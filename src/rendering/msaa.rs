@@ -0,0 +1,174 @@
+//! Multisample anti-aliasing of the main attachments
+//!
+//! With a sample count above 1 the extensions render into multisampled color and depth
+//! attachments, which are resolved into the frame buffer at the end of the frame. The sample
+//! count can be set before the window is created, or changed at any time, the attachments and the
+//! pipelines of the materials and the built-in extensions are recreated on the next frame.
+//!
+//!```no_run
+//! use lunar_engine::rendering::{capabilities, msaa};
+//!
+//! let samples = capabilities().map_or(1, |c| c.max_msaa().min(4));
+//! msaa::set_sample_count(samples).unwrap();
+//!```
+//!
+//! Custom extensions rendering into the main attachments must create their pipelines with
+//! [`multisample_state`] and recreate them when [`sample_count`] changes.
+use std::{
+    cell::RefCell,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use crate::DEVICE;
+
+static SAMPLES: AtomicU32 = AtomicU32::new(1);
+
+thread_local! {
+    //Multisampled color attachment
+    static TARGET: RefCell<Option<wgpu::Texture>> = const { RefCell::new(None) };
+}
+
+///The sample count is not supported by the gpu
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedSampleCount {
+    ///Requested sample count
+    pub requested: u32,
+    ///Sample counts supported by the gpu
+    pub supported: Vec<u32>,
+}
+
+impl std::fmt::Display for UnsupportedSampleCount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Sample count {} is not supported, supported sample counts are {:?}",
+            self.requested, self.supported
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedSampleCount {}
+
+///Returns the sample counts that can be used, all the common ones before the gpu is initialized
+fn supported() -> Vec<u32> {
+    super::capabilities().map_or_else(|| vec![1, 2, 4, 8], |c| c.msaa_levels.clone())
+}
+
+///Sets the number of samples per pixel of the main attachments, 1 disables multisampling
+///
+///# Errors
+///Returns an error if the sample count is not supported by the gpu, see
+///[`super::capabilities::Capabilities::msaa_levels`]
+pub fn set_sample_count(count: u32) -> Result<(), UnsupportedSampleCount> {
+    let supported = supported();
+    if !supported.contains(&count) {
+        return Err(UnsupportedSampleCount {
+            requested: count,
+            supported,
+        });
+    }
+    SAMPLES.store(count, Ordering::Relaxed);
+    Ok(())
+}
+
+///Returns the number of samples per pixel of the main attachments
+#[must_use]
+pub fn sample_count() -> u32 {
+    SAMPLES.load(Ordering::Relaxed)
+}
+
+///Returns the multisample state for pipelines rendering into the main attachments
+#[must_use]
+pub fn multisample_state() -> wgpu::MultisampleState {
+    wgpu::MultisampleState {
+        count: sample_count(),
+        mask: !0,
+        alpha_to_coverage_enabled: false,
+    }
+}
+
+///Falls back to no multisampling if the sample count set before the initialization is not
+///supported by the gpu
+pub(crate) fn validate() {
+    let count = sample_count();
+    if let Err(e) = set_sample_count(count) {
+        log::warn!("{e}, disabling multisampling");
+        SAMPLES.store(1, Ordering::Relaxed);
+    }
+}
+
+///Releases the multisampled color attachment
+pub(crate) fn release() {
+    TARGET.set(None);
+}
+
+///Returns the view of the multisampled color attachment, or `None` if multisampling is disabled
+pub(crate) fn color_target(width: u32, height: u32) -> Option<wgpu::TextureView> {
+    let count = sample_count();
+    if count == 1 {
+        TARGET.set(None);
+        return None;
+    }
+
+    TARGET.with_borrow_mut(|target| {
+        let outdated = target.as_ref().is_none_or(|t| {
+            t.width() != width
+                || t.height() != height
+                || t.sample_count() != count
+                || t.format() != super::hdr::color_format()
+        });
+        if outdated {
+            *target = Some(create_color(width, height, count));
+        }
+
+        Some(
+            target
+                .as_ref()
+                .unwrap()
+                .create_view(&wgpu::TextureViewDescriptor::default()),
+        )
+    })
+}
+
+///Creates a multisampled color texture with the format of the color attachment
+pub(crate) fn create_color(width: u32, height: u32, count: u32) -> wgpu::Texture {
+    DEVICE
+        .get()
+        .unwrap()
+        .create_texture(&wgpu::TextureDescriptor {
+            label: Some("Multisampled color"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: count,
+            dimension: wgpu::TextureDimension::D2,
+            format: super::hdr::color_format(),
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        })
+}
+
+///Resolves the multisampled `input` into the `output`
+pub(crate) fn resolve(
+    encoder: &mut wgpu::CommandEncoder,
+    input: &wgpu::TextureView,
+    output: &wgpu::TextureView,
+) {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Resolve pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: input,
+            resolve_target: Some(output),
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+}
@@ -0,0 +1,667 @@
+//! Occlusion culling of the instances drawn by [`Base`](super::extensions::Base) against the depth
+//! of the previous frame
+//!
+//! Before the extension clears the depth attachment, it still contains the depth of the previous
+//! frame. It is reduced into a hierarchical depth pyramid, where every texel stores the farthest
+//! depth of the texels of the level below it. A compute pass then projects the bounds of every
+//! instance with the view projection of the previous frame, and compares their nearest depth with
+//! the pyramid. The instances that are not hidden are copied into a separate instance buffer, at
+//! the same offsets, and counted in a copy of the draw commands of the groups, see
+//! [`indirect`](super::indirect).
+//!
+//! Instances that were hidden in the previous frame and became visible are only drawn a frame
+//! late. Compute shaders are not available on every web backend, so the culling is native only,
+//! and it's not available on the OpenGL backend. The depth attachment must be usable as a texture
+//! and have no stencil aspect.
+use std::num::NonZeroU64;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::{DeviceExt, DrawIndexedIndirectArgs};
+
+use crate::{
+    assets::shader,
+    grimoire::FRAMES_IN_FLIGHT,
+    math::{geometry::Aabb, Mat4x4, Vec3},
+    DEVICE, RESOLUTION, STAGING_BELT,
+};
+
+use super::{depth, indirect, msaa, viewport};
+
+//Number of instances culled by a single workgroup
+const WORKGROUP_SIZE: u32 = 64;
+//Size of the workgroups building the pyramid
+const PYRAMID_WORKGROUP_SIZE: u32 = 8;
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct Parameters {
+    view_projection: Mat4x4,
+    viewport: [f32; 4],
+    size: [u32; 2],
+    levels: u32,
+    reversed: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct GroupData {
+    bounds_min: Vec3,
+    first: u32,
+    bounds_max: Vec3,
+    count: u32,
+    index: u32,
+    padding: [u32; 3],
+}
+
+//The frame the pyramid is built from
+#[derive(Clone, Copy)]
+struct Frame {
+    view_projection: Mat4x4,
+    //Viewport in fractions of the attachment
+    viewport: [f32; 4],
+    //Size of the depth attachment
+    size: (u32, u32),
+    samples: u32,
+    reversed: bool,
+}
+
+struct State {
+    copy_depth: wgpu::ComputePipeline,
+    copy_depth_multisampled: wgpu::ComputePipeline,
+    downsample: wgpu::ComputePipeline,
+    cull: wgpu::ComputePipeline,
+    copy_layout: wgpu::BindGroupLayout,
+    copy_multisampled_layout: wgpu::BindGroupLayout,
+    downsample_layout: wgpu::BindGroupLayout,
+    parameters_layout: wgpu::BindGroupLayout,
+    buffers_layout: wgpu::BindGroupLayout,
+    group_layout: wgpu::BindGroupLayout,
+    //Whether or not the depth is reversed, used by the pyramid
+    reversed: wgpu::Buffer,
+    parameters: wgpu::Buffer,
+}
+
+struct Pyramid {
+    size: (u32, u32),
+    levels: u32,
+    //Views of the single levels
+    views: Vec<wgpu::TextureView>,
+    //Bind groups reducing each level into the next one
+    downsample: Vec<wgpu::BindGroup>,
+    //Bind group of the culling, with the whole pyramid
+    cull: wgpu::BindGroup,
+}
+
+struct Buffers {
+    //Instances that passed the culling
+    visible: wgpu::Buffer,
+    //Draw commands counting the visible instances
+    draws: wgpu::Buffer,
+    //Draw commands without any instances, copied into the draws before culling
+    empty: wgpu::Buffer,
+    //Bind groups reading from the instance buffer of each frame in flight
+    bind_groups: [wgpu::BindGroup; FRAMES_IN_FLIGHT],
+}
+
+struct Group {
+    count: u32,
+    bind_group: wgpu::BindGroup,
+}
+
+///Culls the instances of instance groups against the depth of the previous frame, see [`self`]
+#[derive(Default)]
+pub(crate) struct OcclusionCulling {
+    state: Option<State>,
+    pyramid: Option<Pyramid>,
+    buffers: Option<Buffers>,
+    groups: Vec<Group>,
+    //Whether or not the groups were set, the instance buffers must be usable as storage buffers
+    enabled: bool,
+    previous: Option<Frame>,
+    //Whether or not the indirect draws were culled this frame
+    active: bool,
+}
+
+///Returns `true` if the occlusion culling is supported by the gpu
+pub(crate) fn supported() -> bool {
+    //Depth textures can't be loaded from in GLSL
+    !cfg!(target_arch = "wasm32")
+        && super::capabilities().is_some_and(|c| c.compute && c.backend != wgpu::Backend::Gl)
+        && indirect::supported()
+        //The pyramid is built by sampling the depth attachment
+        && depth::usage().contains(wgpu::TextureUsages::TEXTURE_BINDING)
+        && !depth::has_stencil()
+}
+
+impl OcclusionCulling {
+    pub(crate) const fn new() -> Self {
+        Self {
+            state: None,
+            pyramid: None,
+            buffers: None,
+            groups: Vec::new(),
+            enabled: false,
+            previous: None,
+            active: false,
+        }
+    }
+
+    ///Returns `true` if the groups were set with [`Self::set_groups`]
+    pub(crate) const fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    ///Releases the buffers and disables the culling
+    pub(crate) fn clear(&mut self) {
+        self.groups.clear();
+        self.buffers = None;
+        self.pyramid = None;
+        self.previous = None;
+        self.enabled = false;
+        self.active = false;
+    }
+
+    ///Sets the instance groups that are culled, as the instance buffers shared by the groups,
+    ///the draw commands of the groups and the local bounds of their meshes
+    ///
+    ///The instance buffers must be usable as storage buffers, `None` if there are no instances
+    pub(crate) fn set_groups(
+        &mut self,
+        instances: Option<&[wgpu::Buffer; FRAMES_IN_FLIGHT]>,
+        commands: &[DrawIndexedIndirectArgs],
+        bounds: impl IntoIterator<Item = Aabb>,
+    ) {
+        self.enabled = true;
+        self.active = false;
+        self.groups.clear();
+        self.buffers = None;
+        //Empty buffers can't be bound
+        let Some(instances) = instances else {
+            return;
+        };
+
+        let device = DEVICE.get().unwrap();
+        let state = self.state.get_or_insert_with(State::new);
+
+        let visible = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Visible instances"),
+            size: instances[0].size(),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let draws = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Occlusion culling draws"),
+            contents: &indirect::command_bytes(commands),
+            usage: wgpu::BufferUsages::INDIRECT
+                | wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_DST,
+        });
+        let empty = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Occlusion culling empty draws"),
+            contents: &indirect::command_bytes(
+                &commands
+                    .iter()
+                    .map(|c| DrawIndexedIndirectArgs {
+                        instance_count: 0,
+                        ..*c
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            usage: wgpu::BufferUsages::COPY_SRC,
+        });
+        let bind_groups = std::array::from_fn(|frame| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Occlusion culling buffers bind group"),
+                layout: &state.buffers_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: instances[frame].as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: visible.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: draws.as_entire_binding(),
+                    },
+                ],
+            })
+        });
+
+        self.groups = commands
+            .iter()
+            .zip(bounds)
+            .enumerate()
+            .map(|(index, (command, bounds))| {
+                let data = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Occlusion culling group"),
+                    contents: bytemuck::bytes_of(&GroupData {
+                        bounds_min: bounds.min,
+                        first: command.first_instance,
+                        bounds_max: bounds.max,
+                        count: command.instance_count,
+                        index: index as u32,
+                        padding: [0; 3],
+                    }),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Occlusion culling group bind group"),
+                    layout: &state.group_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: data.as_entire_binding(),
+                    }],
+                });
+
+                Group {
+                    count: command.instance_count,
+                    bind_group,
+                }
+            })
+            .collect();
+        self.buffers = Some(Buffers {
+            visible,
+            draws,
+            empty,
+            bind_groups,
+        });
+    }
+
+    ///Culls the groups against the depth of the previous frame, in the `depth` attachment, using
+    ///the instance buffers of the `frame` in flight
+    ///
+    ///`view_projection` is the matrix the groups are drawn with in this frame, the culling is
+    ///skipped if there is no previous frame to cull against. The culled groups must be drawn with
+    ///[`Self::draw_buffers`].
+    pub(crate) fn cull(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        depth: &wgpu::TextureView,
+        view_projection: Mat4x4,
+        frame: usize,
+    ) {
+        self.active = false;
+        if !self.enabled {
+            return;
+        }
+
+        let resolution = *RESOLUTION.read().unwrap();
+        let v = viewport::current();
+        let (width, height) = (resolution.width.max(1), resolution.height.max(1));
+        let current = Frame {
+            view_projection,
+            viewport: [
+                v.x / width as f32,
+                v.y / height as f32,
+                v.width / width as f32,
+                v.height / height as f32,
+            ],
+            size: (width, height),
+            samples: msaa::sample_count(),
+            reversed: depth::is_reversed(),
+        };
+
+        //The depth of extensions rendering at a different resolution is not kept at the same size
+        let previous = if viewport::scale() == (1.0, 1.0) {
+            self.previous.replace(current)
+        } else {
+            self.previous.take()
+        };
+        //The depth attachment is recreated when any of these change
+        let Some(previous) = previous.filter(|p| {
+            viewport::scale() == (1.0, 1.0)
+                && p.size == current.size
+                && p.samples == current.samples
+                && p.reversed == current.reversed
+        }) else {
+            return;
+        };
+        if self.groups.is_empty() {
+            return;
+        }
+
+        let device = DEVICE.get().unwrap();
+        let state = self.state.get_or_insert_with(State::new);
+
+        //The first level is half the size of the attachment
+        let size = (width.div_ceil(2), height.div_ceil(2));
+        if self.pyramid.as_ref().is_none_or(|p| p.size != size) {
+            self.pyramid = Some(Pyramid::new(state, size));
+        }
+        let pyramid = self.pyramid.as_ref().unwrap();
+        let buffers = self.buffers.as_ref().unwrap();
+
+        {
+            let mut belt = STAGING_BELT.get().unwrap().write().unwrap();
+            belt.write_buffer(
+                encoder,
+                &state.reversed,
+                0,
+                NonZeroU64::new(4).unwrap(),
+                device,
+            )
+            .copy_from_slice(bytemuck::bytes_of(&u32::from(current.reversed)));
+            belt.write_buffer(
+                encoder,
+                &state.parameters,
+                0,
+                NonZeroU64::new(std::mem::size_of::<Parameters>() as u64).unwrap(),
+                device,
+            )
+            .copy_from_slice(bytemuck::bytes_of(&Parameters {
+                view_projection: previous.view_projection,
+                viewport: previous.viewport,
+                size: [size.0, size.1],
+                levels: pyramid.levels,
+                reversed: u32::from(current.reversed),
+            }));
+        }
+
+        //The culling only counts the visible instances
+        encoder.copy_buffer_to_buffer(&buffers.empty, 0, &buffers.draws, 0, buffers.draws.size());
+
+        let (copy, layout) = if current.samples > 1 {
+            (
+                &state.copy_depth_multisampled,
+                &state.copy_multisampled_layout,
+            )
+        } else {
+            (&state.copy_depth, &state.copy_layout)
+        };
+        let copy_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Depth pyramid bind group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: state.reversed.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: if current.samples > 1 { 2 } else { 1 },
+                    resource: wgpu::BindingResource::TextureView(depth),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(&pyramid.views[0]),
+                },
+            ],
+        });
+
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Occlusion culling pass"),
+            timestamp_writes: None,
+        });
+
+        pass.set_pipeline(copy);
+        pass.set_bind_group(0, &copy_bind_group, &[]);
+        let (x, y) = workgroups(size, 0);
+        pass.dispatch_workgroups(x, y, 1);
+
+        pass.set_pipeline(&state.downsample);
+        for (level, bind_group) in pyramid.downsample.iter().enumerate() {
+            pass.set_bind_group(0, bind_group, &[]);
+            let (x, y) = workgroups(size, level as u32 + 1);
+            pass.dispatch_workgroups(x, y, 1);
+        }
+
+        pass.set_pipeline(&state.cull);
+        pass.set_bind_group(0, &pyramid.cull, &[]);
+        pass.set_bind_group(1, &buffers.bind_groups[frame], &[]);
+        for g in &self.groups {
+            pass.set_bind_group(2, &g.bind_group, &[]);
+            pass.dispatch_workgroups(g.count.div_ceil(WORKGROUP_SIZE), 1, 1);
+        }
+        drop(pass);
+
+        self.active = true;
+    }
+
+    ///Returns the buffer of the visible instances and the buffer of the draw commands of the
+    ///groups, `None` if the groups were not culled this frame
+    pub(crate) fn draw_buffers(&self) -> Option<(&wgpu::Buffer, &wgpu::Buffer)> {
+        if !self.active {
+            return None;
+        }
+        self.buffers.as_ref().map(|b| (&b.visible, &b.draws))
+    }
+}
+
+//Number of workgroups covering the level of a pyramid with the first level of the `size`
+fn workgroups(size: (u32, u32), level: u32) -> (u32, u32) {
+    let (width, height) = ((size.0 >> level).max(1), (size.1 >> level).max(1));
+    (
+        width.div_ceil(PYRAMID_WORKGROUP_SIZE),
+        height.div_ceil(PYRAMID_WORKGROUP_SIZE),
+    )
+}
+
+impl Pyramid {
+    fn new(state: &State, size: (u32, u32)) -> Self {
+        let device = DEVICE.get().unwrap();
+        let levels = u32::BITS - size.0.max(size.1).leading_zeros();
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Depth pyramid"),
+            size: wgpu::Extent3d {
+                width: size.0,
+                height: size.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: levels,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R32Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::STORAGE_BINDING,
+            view_formats: &[],
+        });
+
+        let views = (0..levels)
+            .map(|level| {
+                texture.create_view(&wgpu::TextureViewDescriptor {
+                    label: Some("Depth pyramid level"),
+                    base_mip_level: level,
+                    mip_level_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let downsample = views
+            .windows(2)
+            .map(|views| {
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Depth pyramid downsample bind group"),
+                    layout: &state.downsample_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: state.reversed.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 3,
+                            resource: wgpu::BindingResource::TextureView(&views[0]),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 4,
+                            resource: wgpu::BindingResource::TextureView(&views[1]),
+                        },
+                    ],
+                })
+            })
+            .collect();
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let cull = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Occlusion culling bind group"),
+            layout: &state.parameters_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: state.parameters.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+            ],
+        });
+
+        Self {
+            size,
+            levels,
+            views,
+            downsample,
+            cull,
+        }
+    }
+}
+
+impl State {
+    fn new() -> Self {
+        let device = DEVICE.get().unwrap();
+
+        let uniform = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let storage = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let texture = |binding, sample_type, multisampled| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Texture {
+                sample_type,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled,
+            },
+            count: None,
+        };
+        let destination = wgpu::BindGroupLayoutEntry {
+            binding: 4,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::StorageTexture {
+                access: wgpu::StorageTextureAccess::WriteOnly,
+                format: wgpu::TextureFormat::R32Float,
+                view_dimension: wgpu::TextureViewDimension::D2,
+            },
+            count: None,
+        };
+        let layout = |label, entries: &[wgpu::BindGroupLayoutEntry]| {
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some(label),
+                entries,
+            })
+        };
+        let pyramid_value = wgpu::TextureSampleType::Float { filterable: false };
+
+        let copy_layout = layout(
+            "Depth pyramid copy bind group layout",
+            &[
+                uniform(0),
+                texture(1, wgpu::TextureSampleType::Depth, false),
+                destination,
+            ],
+        );
+        let copy_multisampled_layout = layout(
+            "Depth pyramid multisampled copy bind group layout",
+            &[
+                uniform(0),
+                texture(2, wgpu::TextureSampleType::Depth, true),
+                destination,
+            ],
+        );
+        let downsample_layout = layout(
+            "Depth pyramid downsample bind group layout",
+            &[uniform(0), texture(3, pyramid_value, false), destination],
+        );
+        let parameters_layout = layout(
+            "Occlusion culling bind group layout",
+            &[uniform(0), texture(1, pyramid_value, false)],
+        );
+        let buffers_layout = layout(
+            "Occlusion culling buffers bind group layout",
+            &[storage(0, true), storage(1, false), storage(2, false)],
+        );
+        let group_layout = layout("Occlusion culling group bind group layout", &[uniform(0)]);
+
+        let hiz = shader::compile("hiz.wgsl", include_str!("../shaders/hiz.wgsl"))
+            .expect("Failed to compile the depth pyramid shader");
+        let occlusion =
+            shader::compile("occlusion.wgsl", include_str!("../shaders/occlusion.wgsl"))
+                .expect("Failed to compile the occlusion culling shader");
+
+        let pipeline = |label, layouts: &[&wgpu::BindGroupLayout], module, entry_point| {
+            let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(label),
+                bind_group_layouts: layouts,
+                push_constant_ranges: &[],
+            });
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(label),
+                layout: Some(&layout),
+                module,
+                entry_point,
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            })
+        };
+
+        let buffer = |label, size| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        };
+
+        Self {
+            copy_depth: pipeline("Depth pyramid copy", &[&copy_layout], &hiz, "copy_depth"),
+            copy_depth_multisampled: pipeline(
+                "Depth pyramid multisampled copy",
+                &[&copy_multisampled_layout],
+                &hiz,
+                "copy_depth_multisampled",
+            ),
+            downsample: pipeline(
+                "Depth pyramid downsample",
+                &[&downsample_layout],
+                &hiz,
+                "downsample",
+            ),
+            cull: pipeline(
+                "Occlusion culling",
+                &[&parameters_layout, &buffers_layout, &group_layout],
+                &occlusion,
+                "main",
+            ),
+            copy_layout,
+            copy_multisampled_layout,
+            downsample_layout,
+            parameters_layout,
+            buffers_layout,
+            group_layout,
+            reversed: buffer("Depth pyramid parameters", 16),
+            parameters: buffer(
+                "Occlusion culling parameters",
+                std::mem::size_of::<Parameters>() as u64,
+            ),
+        }
+    }
+}
@@ -0,0 +1,368 @@
+//! Effects of the post processing stack, see [`super::push_effect`]
+use bytemuck::{Pod, Zeroable};
+
+use crate::{
+    rendering::fullscreen::{self, FullscreenPipeline},
+    DEVICE, FORMAT, QUEUE,
+};
+
+///Effect applied to the final image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Effect {
+    ///Makes the bright parts of the image glow
+    Bloom {
+        ///Brightness above which the colors glow, from 0 to 1
+        threshold: f32,
+        ///Multiplier of the glow
+        intensity: f32,
+    },
+    ///Fast approximate anti-aliasing, smooths the edges in the image
+    Fxaa,
+    ///Darkens the edges of the image
+    Vignette {
+        ///How much the corners are darkened, from 0 to 1
+        intensity: f32,
+        ///Distance from the center where the darkening starts, 0 is the center and 1 the corners
+        radius: f32,
+        ///Distance over which the darkening fades in
+        smoothness: f32,
+    },
+    ///Splits the color channels towards the edges of the image, like a cheap lens
+    ChromaticAberration {
+        ///Offset of the red and the blue channels in the corners, in pixels
+        strength: f32,
+    },
+}
+
+impl Effect {
+    ///Creates a bloom effect with the threshold of 0.8 and the intensity of 1
+    #[must_use]
+    pub const fn bloom() -> Self {
+        Self::Bloom {
+            threshold: 0.8,
+            intensity: 1.0,
+        }
+    }
+
+    ///Creates a vignette darkening the corners by half
+    #[must_use]
+    pub const fn vignette() -> Self {
+        Self::Vignette {
+            intensity: 0.5,
+            radius: 0.5,
+            smoothness: 0.5,
+        }
+    }
+
+    ///Creates a chromatic aberration with the offset of 2 pixels
+    #[must_use]
+    pub const fn chromatic_aberration() -> Self {
+        Self::ChromaticAberration { strength: 2.0 }
+    }
+
+    fn settings(&self) -> [f32; 4] {
+        match *self {
+            Self::Bloom {
+                threshold,
+                intensity,
+            } => [threshold, intensity, 0.0, 0.0],
+            Self::Fxaa => [0.0; 4],
+            Self::Vignette {
+                intensity,
+                radius,
+                smoothness,
+            } => [intensity, radius, smoothness, 0.0],
+            Self::ChromaticAberration { strength } => [strength, 0.0, 0.0, 0.0],
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Parameters {
+    //Depends on the effect
+    settings: [f32; 4],
+    //Size of a pixel of the half resolution textures and of the image in uv
+    texel: [f32; 4],
+}
+
+//Textures the effects render into
+struct Targets {
+    //Ping-pong textures at the resolution of the image
+    full: [wgpu::Texture; 2],
+    //Blurred bright parts for the bloom
+    half: [wgpu::Texture; 2],
+}
+
+pub(super) struct EffectsState {
+    layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    fxaa: FullscreenPipeline,
+    vignette: FullscreenPipeline,
+    chromatic_aberration: FullscreenPipeline,
+    //Bright pass with the horizontal blur, vertical blur, composite
+    bloom: [FullscreenPipeline; 3],
+    //One for every effect in the stack, they are all written before the frame is submitted
+    uniforms: Vec<wgpu::Buffer>,
+    targets: Option<Targets>,
+}
+
+impl EffectsState {
+    pub(super) fn new() -> Self {
+        let device = DEVICE.get().unwrap();
+
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Post effect bind group layout"),
+            entries: &[
+                texture_entry(0),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                texture_entry(3),
+            ],
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Post effect sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let pipeline = |label: &str, shader: &wgpu::ShaderModule, entry_point: &str| {
+            FullscreenPipeline::new_with_entry_point(
+                label,
+                shader,
+                entry_point,
+                &[&layout],
+                wgpu::ColorTargetState {
+                    format: *FORMAT.get().unwrap(),
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                },
+                wgpu::MultisampleState::default(),
+            )
+        };
+
+        let fxaa = fullscreen::compile("fxaa.wgsl", include_str!("../../shaders/fxaa.wgsl"))
+            .expect("Failed to compile the FXAA shader");
+        let vignette =
+            fullscreen::compile("vignette.wgsl", include_str!("../../shaders/vignette.wgsl"))
+                .expect("Failed to compile the vignette shader");
+        let chromatic_aberration = fullscreen::compile(
+            "chromatic_aberration.wgsl",
+            include_str!("../../shaders/chromatic_aberration.wgsl"),
+        )
+        .expect("Failed to compile the chromatic aberration shader");
+        let bloom = fullscreen::compile("bloom.wgsl", include_str!("../../shaders/bloom.wgsl"))
+            .expect("Failed to compile the bloom shader");
+
+        Self {
+            fxaa: pipeline("FXAA pipeline", &fxaa, "fragment"),
+            vignette: pipeline("Vignette pipeline", &vignette, "fragment"),
+            chromatic_aberration: pipeline(
+                "Chromatic aberration pipeline",
+                &chromatic_aberration,
+                "fragment",
+            ),
+            bloom: [
+                pipeline("Bloom bright pipeline", &bloom, "bright_horizontal"),
+                pipeline("Bloom blur pipeline", &bloom, "vertical"),
+                pipeline("Bloom composite pipeline", &bloom, "composite"),
+            ],
+            layout,
+            sampler,
+            uniforms: Vec::new(),
+            targets: None,
+        }
+    }
+
+    fn create_texture(label: &str, width: u32, height: u32) -> wgpu::Texture {
+        DEVICE
+            .get()
+            .unwrap()
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width: width.max(1),
+                    height: height.max(1),
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: *FORMAT.get().unwrap(),
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+    }
+
+    fn bind_group(
+        &self,
+        input: &wgpu::TextureView,
+        uniform: &wgpu::Buffer,
+        blurred: Option<&wgpu::TextureView>,
+    ) -> wgpu::BindGroup {
+        DEVICE
+            .get()
+            .unwrap()
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Post effect bind group"),
+                layout: &self.layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(input),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: uniform.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: wgpu::BindingResource::TextureView(blurred.unwrap_or(input)),
+                    },
+                ],
+            })
+    }
+
+    ///Applies the `effects` to the `input`, returns the view of the texture containing the
+    ///result, or `None` if there are no effects
+    pub(super) fn apply(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        effects: &[Effect],
+        input: &wgpu::Texture,
+    ) -> Option<wgpu::TextureView> {
+        if effects.is_empty() {
+            return None;
+        }
+        let device = DEVICE.get().unwrap();
+        let queue = QUEUE.get().unwrap();
+        let (width, height) = (input.width(), input.height());
+
+        let outdated = self
+            .targets
+            .as_ref()
+            .is_none_or(|t| t.full[0].width() != width || t.full[0].height() != height);
+        if outdated {
+            let half = |label| Self::create_texture(label, width / 2, height / 2);
+            self.targets = Some(Targets {
+                full: [
+                    Self::create_texture("Post effect ping", width, height),
+                    Self::create_texture("Post effect pong", width, height),
+                ],
+                half: [half("Bloom bright"), half("Bloom blur")],
+            });
+        }
+        while self.uniforms.len() < effects.len() {
+            self.uniforms
+                .push(device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Post effect parameters"),
+                    size: std::mem::size_of::<Parameters>() as u64,
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }));
+        }
+
+        let targets = self.targets.as_ref().unwrap();
+        let view = |t: &wgpu::Texture| t.create_view(&wgpu::TextureViewDescriptor::default());
+        let full = [view(&targets.full[0]), view(&targets.full[1])];
+        let half = [view(&targets.half[0]), view(&targets.half[1])];
+        let half_size = targets.half[0].size();
+
+        let input = view(input);
+        //Index of the ping-pong texture containing the result of the previous effect
+        let mut source = None;
+        for (i, (effect, uniform)) in effects.iter().zip(&self.uniforms).enumerate() {
+            queue.write_buffer(
+                uniform,
+                0,
+                bytemuck::bytes_of(&Parameters {
+                    settings: effect.settings(),
+                    texel: [
+                        1.0 / half_size.width as f32,
+                        1.0 / half_size.height as f32,
+                        1.0 / width as f32,
+                        1.0 / height as f32,
+                    ],
+                }),
+            );
+
+            let input = source.map_or(&input, |s: usize| &full[s]);
+            let output = &full[i % 2];
+            let bind_group = self.bind_group(input, uniform, None);
+            let load = wgpu::LoadOp::Load;
+            match effect {
+                Effect::Fxaa => {
+                    self.fxaa
+                        .draw(encoder, "FXAA pass", output, &[&bind_group], load);
+                }
+                Effect::Vignette { .. } => {
+                    self.vignette
+                        .draw(encoder, "Vignette pass", output, &[&bind_group], load);
+                }
+                Effect::ChromaticAberration { .. } => {
+                    self.chromatic_aberration.draw(
+                        encoder,
+                        "Chromatic aberration pass",
+                        output,
+                        &[&bind_group],
+                        load,
+                    );
+                }
+                Effect::Bloom { .. } => {
+                    self.bloom[0].draw(
+                        encoder,
+                        "Bloom bright pass",
+                        &half[0],
+                        &[&bind_group],
+                        load,
+                    );
+                    let blur = self.bind_group(&half[0], uniform, None);
+                    self.bloom[1].draw(encoder, "Bloom blur pass", &half[1], &[&blur], load);
+                    let composite = self.bind_group(input, uniform, Some(&half[1]));
+                    self.bloom[2].draw(
+                        encoder,
+                        "Bloom composite pass",
+                        output,
+                        &[&composite],
+                        load,
+                    );
+                }
+            }
+            source = Some(i % 2);
+        }
+
+        let [ping, pong] = full;
+        Some(if source == Some(0) { ping } else { pong })
+    }
+}
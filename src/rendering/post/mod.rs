@@ -0,0 +1,526 @@
+//! Post processing applied to the final image
+//!
+//! When any of the filters is enabled, the extensions render into an intermediate texture, which
+//! is then processed into the window in a final pass. Otherwise the extensions render straight
+//! into the window and post processing has no cost.
+//!
+//!```
+//! use lunar_engine::rendering::post::{self, ColorDeficiency, ColorFilter};
+//!
+//! post::set_color_filter(Some(ColorFilter::compensate(ColorDeficiency::Deuteranopia)));
+//!```
+//!
+//! Effects like bloom or FXAA are pushed onto a stack, and applied in the order they were pushed,
+//! after all the extensions and the tonemapping, see [`push_effect`].
+//!
+//!```
+//! use lunar_engine::rendering::post::{self, Effect};
+//!
+//! post::push_effect(Effect::bloom());
+//! post::push_effect(Effect::Fxaa);
+//! post::push_effect(Effect::vignette());
+//!```
+//!
+//! The brightness and the gamma calibration of the player are applied last, see
+//! [`DisplaySettings`]
+use std::{cell::RefCell, sync::RwLock};
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::{assets::shader, math::Mat3x3, DEVICE, FORMAT, QUEUE};
+
+use self::effects::EffectsState;
+
+mod effects;
+
+pub use effects::Effect;
+
+static COLOR_FILTER: RwLock<Option<ColorFilter>> = RwLock::new(None);
+static EFFECTS: RwLock<Vec<Effect>> = RwLock::new(Vec::new());
+static DISPLAY: RwLock<DisplaySettings> = RwLock::new(DisplaySettings::new());
+
+thread_local! {
+    static STATE: RefCell<Option<PostState>> = const { RefCell::new(None) };
+}
+
+///Type of color vision deficiency
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDeficiency {
+    ///Missing red cones
+    Protanopia,
+    ///Missing green cones
+    Deuteranopia,
+    ///Missing blue cones
+    Tritanopia,
+}
+
+///What the color filter does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterMode {
+    ///Shows the image as it is seen with the deficiency, for testing the readability of the game
+    Simulate,
+    ///Shifts the colors that can't be distinguished with the deficiency into the ones that can
+    Compensate,
+}
+
+///Color vision deficiency filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorFilter {
+    ///Deficiency the filter is for
+    pub deficiency: ColorDeficiency,
+    ///What the filter does
+    pub mode: FilterMode,
+}
+
+impl ColorFilter {
+    ///Creates a filter simulating the deficiency
+    #[must_use]
+    pub const fn simulate(deficiency: ColorDeficiency) -> Self {
+        Self {
+            deficiency,
+            mode: FilterMode::Simulate,
+        }
+    }
+
+    ///Creates a filter compensating for the deficiency
+    #[must_use]
+    pub const fn compensate(deficiency: ColorDeficiency) -> Self {
+        Self {
+            deficiency,
+            mode: FilterMode::Compensate,
+        }
+    }
+
+    ///Returns the matrix the filter applies to linear rgb colors
+    #[must_use]
+    pub fn matrix(&self) -> Mat3x3 {
+        //Machado et al. 2009, full severity
+        let simulation = match self.deficiency {
+            ColorDeficiency::Protanopia => Mat3x3::new(
+                0.152_286, 1.052_583, -0.204_868, 0.114_503, 0.786_281, 0.099_216, -0.003_882,
+                -0.048_116, 1.051_998,
+            ),
+            ColorDeficiency::Deuteranopia => Mat3x3::new(
+                0.367_322, 0.860_646, -0.227_968, 0.280_085, 0.672_501, 0.047_413, -0.011_820,
+                0.042_940, 0.968_881,
+            ),
+            ColorDeficiency::Tritanopia => Mat3x3::new(
+                1.255_528, -0.076_749, -0.178_779, -0.078_411, 0.930_809, 0.147_602, 0.004_733,
+                0.691_367, 0.303_900,
+            ),
+        };
+
+        match self.mode {
+            FilterMode::Simulate => simulation,
+            FilterMode::Compensate => {
+                //Daltonization, the difference between the original and the simulated color is
+                //moved into the channels that can be seen
+                let shift = match self.deficiency {
+                    ColorDeficiency::Protanopia | ColorDeficiency::Deuteranopia => {
+                        Mat3x3::new(0.0, 0.0, 0.0, 0.7, 1.0, 0.0, 0.7, 0.0, 1.0)
+                    }
+                    ColorDeficiency::Tritanopia => {
+                        Mat3x3::new(1.0, 0.0, 0.7, 0.0, 1.0, 0.7, 0.0, 0.0, 0.0)
+                    }
+                };
+                Mat3x3::identity() - shift * (simulation - Mat3x3::identity())
+            }
+        }
+    }
+}
+
+///Sets the color vision deficiency filter, `None` disables it
+pub fn set_color_filter(filter: Option<ColorFilter>) {
+    *COLOR_FILTER.write().unwrap() = filter;
+}
+
+///Returns the color vision deficiency filter
+#[must_use]
+pub fn get_color_filter() -> Option<ColorFilter> {
+    *COLOR_FILTER.read().unwrap()
+}
+
+///Brightness and gamma calibration of the display
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplaySettings {
+    ///Multiplier of the final colors, 1 leaves the image unchanged
+    pub brightness: f32,
+    ///Gamma correction, values above 1 brighten the dark parts of the image, 1 leaves the image
+    ///unchanged
+    pub gamma: f32,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DisplaySettings {
+    ///Creates settings that leave the image unchanged
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            brightness: 1.0,
+            gamma: 1.0,
+        }
+    }
+
+    ///Returns `true` if the settings leave the image unchanged
+    #[must_use]
+    pub fn is_neutral(&self) -> bool {
+        (self.brightness - 1.0).abs() < f32::EPSILON && (self.gamma - 1.0).abs() < f32::EPSILON
+    }
+
+    ///Returns the settings in the format read by [`DisplaySettings::parse`]
+    #[must_use]
+    pub fn to_config(&self) -> String {
+        format!("brightness = {}\ngamma = {}\n", self.brightness, self.gamma)
+    }
+
+    ///Parses settings stored as `key = value` lines, missing keys keep their default values
+    ///
+    ///# Errors
+    ///Returns an error message if a line is not a `key = value` pair, the key is unknown or the
+    ///value is not a positive number
+    pub fn parse(config: &str) -> Result<Self, String> {
+        let mut settings = Self::new();
+
+        for line in config.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("Expected a key = value pair, found {line}"));
+            };
+            let value = value
+                .trim()
+                .parse::<f32>()
+                .ok()
+                .filter(|v| *v > 0.0)
+                .ok_or_else(|| format!("Invalid value of {}", key.trim()))?;
+
+            match key.trim() {
+                "brightness" => settings.brightness = value,
+                "gamma" => settings.gamma = value,
+                k => return Err(format!("Unknown key {k}")),
+            }
+        }
+
+        Ok(settings)
+    }
+
+    ///Loads the settings from a file
+    ///
+    ///# Errors
+    ///Returns an error if reading the file fails or the file is not valid, see
+    ///[`DisplaySettings::parse`]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &std::path::Path) -> std::io::Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    ///Saves the settings into a file
+    ///
+    ///# Errors
+    ///Returns an error if writing the file fails
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: &std::path::Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_config())
+    }
+}
+
+///Sets the brightness and gamma calibration
+///
+///# Panics
+///Panics if the brightness or the gamma is not positive
+pub fn set_display_settings(settings: DisplaySettings) {
+    assert!(
+        settings.brightness > 0.0 && settings.gamma > 0.0,
+        "Brightness and gamma must be positive"
+    );
+    *DISPLAY.write().unwrap() = settings;
+}
+
+///Returns the brightness and gamma calibration
+#[must_use]
+pub fn get_display_settings() -> DisplaySettings {
+    *DISPLAY.read().unwrap()
+}
+
+///Pushes an effect onto the top of the stack, it is applied after the ones already on the stack
+pub fn push_effect(effect: Effect) {
+    EFFECTS.write().unwrap().push(effect);
+}
+
+///Removes the effect at the top of the stack
+pub fn pop_effect() -> Option<Effect> {
+    EFFECTS.write().unwrap().pop()
+}
+
+///Removes all the effects from the stack
+pub fn clear_effects() {
+    EFFECTS.write().unwrap().clear();
+}
+
+///Replaces the effects on the stack, the first one is applied first
+pub fn set_effects(effects: Vec<Effect>) {
+    *EFFECTS.write().unwrap() = effects;
+}
+
+///Returns the effects on the stack, in the order they are applied
+#[must_use]
+pub fn get_effects() -> Vec<Effect> {
+    EFFECTS.read().unwrap().clone()
+}
+
+///Returns `true` if any post processing is enabled
+#[must_use]
+pub fn is_active() -> bool {
+    get_color_filter().is_some()
+        || !get_display_settings().is_neutral()
+        || !EFFECTS.read().unwrap().is_empty()
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Parameters {
+    //Rows of the color matrix
+    matrix: [[f32; 4]; 3],
+    //Brightness, gamma
+    display: [f32; 4],
+}
+
+impl Parameters {
+    fn current() -> Self {
+        let m = get_color_filter().map_or_else(Mat3x3::identity, |f| f.matrix());
+        let display = get_display_settings();
+        Self {
+            matrix: [
+                [m.m00, m.m01, m.m02, 0.0],
+                [m.m10, m.m11, m.m12, 0.0],
+                [m.m20, m.m21, m.m22, 0.0],
+            ],
+            display: [display.brightness, display.gamma, 0.0, 0.0],
+        }
+    }
+}
+
+struct PostState {
+    pipeline: wgpu::RenderPipeline,
+    layout: wgpu::BindGroupLayout,
+    uniform: wgpu::Buffer,
+    //Intermediate texture and the bind group using it
+    target: Option<(wgpu::Texture, wgpu::BindGroup)>,
+    //Created when the first effect is pushed
+    effects: Option<EffectsState>,
+}
+
+///Releases the intermediate textures and the pipelines of the effects
+pub(crate) fn release() {
+    STATE.set(None);
+}
+
+///Returns the view of the intermediate texture the extensions should render into, or `None` if
+///post processing is disabled
+pub(crate) fn intermediate(width: u32, height: u32) -> Option<wgpu::TextureView> {
+    if !is_active() {
+        return None;
+    }
+
+    STATE.with_borrow_mut(|s| {
+        let state = s.get_or_insert_with(PostState::new);
+
+        let outdated = state
+            .target
+            .as_ref()
+            .is_none_or(|(t, _)| t.width() != width || t.height() != height);
+        if outdated {
+            state.target = Some(state.create_target(width, height));
+        }
+
+        Some(
+            state
+                .target
+                .as_ref()
+                .unwrap()
+                .0
+                .create_view(&wgpu::TextureViewDescriptor::default()),
+        )
+    })
+}
+
+///Processes the intermediate texture into the `output`
+pub(crate) fn apply(encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView) {
+    STATE.with_borrow_mut(|s| {
+        let Some(state) = s else {
+            return;
+        };
+        let Some((input, bind_group)) = &state.target else {
+            return;
+        };
+
+        let effects = get_effects();
+        let processed = if effects.is_empty() {
+            None
+        } else {
+            state
+                .effects
+                .get_or_insert_with(EffectsState::new)
+                .apply(encoder, &effects, input)
+                .map(|view| state.bind_group(&view))
+        };
+        let bind_group = processed.as_ref().unwrap_or(bind_group);
+
+        QUEUE.get().unwrap().write_buffer(
+            &state.uniform,
+            0,
+            bytemuck::bytes_of(&Parameters::current()),
+        );
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Post processing pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&state.pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    });
+}
+
+impl PostState {
+    fn new() -> Self {
+        let device = DEVICE.get().unwrap();
+
+        let shader = shader::compile("post.wgsl", include_str!("../../shaders/post.wgsl"))
+            .expect("Failed to compile the post processing shader");
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Post processing bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post processing pipeline layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = crate::errors::scoped("Post processing pipeline", || {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Post processing pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vertex",
+                    buffers: &[],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fragment",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: *FORMAT.get().unwrap(),
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                }),
+                multiview: None,
+            })
+        });
+
+        let uniform = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Post processing parameters"),
+            size: std::mem::size_of::<Parameters>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            pipeline,
+            layout,
+            uniform,
+            target: None,
+            effects: None,
+        }
+    }
+
+    fn create_target(&self, width: u32, height: u32) -> (wgpu::Texture, wgpu::BindGroup) {
+        let device = DEVICE.get().unwrap();
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Post processing input"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: *FORMAT.get().unwrap(),
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let bind_group =
+            self.bind_group(&texture.create_view(&wgpu::TextureViewDescriptor::default()));
+
+        (texture, bind_group)
+    }
+
+    fn bind_group(&self, input: &wgpu::TextureView) -> wgpu::BindGroup {
+        DEVICE
+            .get()
+            .unwrap()
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Post processing bind group"),
+                layout: &self.layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(input),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: self.uniform.as_entire_binding(),
+                    },
+                ],
+            })
+    }
+}
@@ -0,0 +1,261 @@
+//! Gpu time spent in the rendering extensions
+//!
+//! When enabled, timestamps are written before and after the [`render`] of every extension and
+//! read back once the gpu is done with the frame, checked at the start of the following frames,
+//! so profiling never stalls the frame and the timings lag a few frames behind.
+//!
+//! Needs the timestamp query features, which are requested if the gpu has them, without them
+//! [`frame_gpu_timings`] always returns `None`.
+//!
+//!```no_run
+//! use lunar_engine::rendering::{self, profiling};
+//!
+//! profiling::set_enabled(true);
+//! //A few frames later
+//! for timing in rendering::frame_gpu_timings().unwrap_or_default() {
+//!     println!("{}: {:?}", timing.name, timing.duration);
+//! }
+//!```
+//!
+//! [`render`]: super::extensions::RenderingExtension::render
+use std::{
+    cell::RefCell,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use crate::{DEVICE, QUEUE};
+
+///Features needed for the timestamps
+pub(crate) const FEATURES: wgpu::Features =
+    wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS);
+
+///Maximum number of extensions timed in a frame, the rest are not timed
+pub const MAX_EXTENSIONS: usize = 128;
+
+//Size of a resolved timestamp
+const TIMESTAMP_SIZE: u64 = std::mem::size_of::<u64>() as u64;
+
+static SUPPORTED: AtomicBool = AtomicBool::new(false);
+static ENABLED: AtomicBool = AtomicBool::new(false);
+//Timings of the last frame read back
+static LAST: Mutex<Option<Vec<ExtensionTiming>>> = Mutex::new(None);
+
+///Gpu time spent in the [`render`](super::extensions::RenderingExtension::render) of an extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtensionTiming {
+    ///Name of the extension
+    pub name: String,
+    ///Time between the start of the first and the end of the last command the extension recorded
+    pub duration: Duration,
+}
+
+//Queries and buffers, created when profiling is first used
+struct Queries {
+    set: wgpu::QuerySet,
+    resolve: wgpu::Buffer,
+    readback: wgpu::Buffer,
+    //Names of the extensions timed in the frame
+    names: Vec<String>,
+    state: State,
+}
+
+enum State {
+    Idle,
+    Recording,
+    Mapping(Arc<AtomicBool>),
+}
+
+thread_local! {
+    static QUERIES: RefCell<Option<Queries>> = const { RefCell::new(None) };
+}
+
+pub(crate) fn set_supported(supported: bool) {
+    SUPPORTED.store(supported, Ordering::Relaxed);
+}
+
+///Returns whether or not the gpu supports timestamp queries
+#[must_use]
+pub fn is_supported() -> bool {
+    SUPPORTED.load(Ordering::Relaxed)
+}
+
+///Enables or disables the profiling of the extensions, it's disabled by default
+pub fn set_enabled(enabled: bool) {
+    if enabled && !is_supported() {
+        log::warn!("Timestamp queries not supported, the extensions can't be profiled");
+    }
+    ENABLED.store(enabled, Ordering::Relaxed);
+    if !enabled {
+        LAST.lock().unwrap().take();
+    }
+}
+
+///Returns whether or not the extensions are profiled
+#[must_use]
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+///Returns the gpu time spent in each extension in the order they ran, for the last frame the gpu
+///finished, or `None` if profiling is disabled, not supported or no frame finished yet
+#[must_use]
+pub fn frame_gpu_timings() -> Option<Vec<ExtensionTiming>> {
+    if !is_enabled() || !is_supported() {
+        return None;
+    }
+    LAST.lock().unwrap().clone()
+}
+
+///Starts timing the extensions of a frame, unless the previous one is still being read back
+pub(super) fn begin_frame() {
+    if !is_enabled() || !is_supported() {
+        return;
+    }
+    QUERIES.with_borrow_mut(|q| {
+        let q = q.get_or_insert_with(create_queries);
+        if matches!(q.state, State::Idle) {
+            q.names.clear();
+            q.state = State::Recording;
+        }
+    });
+}
+
+///Writes the timestamp before the extension records its commands
+pub(super) fn begin_extension(encoder: &mut wgpu::CommandEncoder, name: &str) {
+    QUERIES.with_borrow_mut(|q| {
+        let Some(q) = q.as_mut().filter(|q| matches!(q.state, State::Recording)) else {
+            return;
+        };
+        if q.names.len() < MAX_EXTENSIONS {
+            encoder.write_timestamp(&q.set, q.names.len() as u32 * 2);
+            q.names.push(name.to_owned());
+        }
+    });
+}
+
+///Writes the timestamp after the extension recorded its commands
+pub(super) fn end_extension(encoder: &mut wgpu::CommandEncoder) {
+    QUERIES.with_borrow_mut(|q| {
+        let Some(q) = q.as_mut().filter(|q| matches!(q.state, State::Recording)) else {
+            return;
+        };
+        if let Some(i) = q.names.len().checked_sub(1) {
+            encoder.write_timestamp(&q.set, i as u32 * 2 + 1);
+        }
+    });
+}
+
+///Copies the timestamps of the frame into the readback buffer
+pub(super) fn resolve(encoder: &mut wgpu::CommandEncoder) {
+    QUERIES.with_borrow_mut(|q| {
+        let Some(q) = q.as_mut().filter(|q| matches!(q.state, State::Recording)) else {
+            return;
+        };
+        if q.names.is_empty() {
+            q.state = State::Idle;
+            return;
+        }
+        let count = q.names.len() as u32 * 2;
+        encoder.resolve_query_set(&q.set, 0..count, &q.resolve, 0);
+        encoder.copy_buffer_to_buffer(
+            &q.resolve,
+            0,
+            &q.readback,
+            0,
+            u64::from(count) * TIMESTAMP_SIZE,
+        );
+    });
+}
+
+///Starts reading the timestamps back, must be called after the frame was submitted
+pub(super) fn submitted() {
+    QUERIES.with_borrow_mut(|q| {
+        let Some(q) = q.as_mut().filter(|q| matches!(q.state, State::Recording)) else {
+            return;
+        };
+        let mapped = Arc::new(AtomicBool::new(false));
+        let flag = mapped.clone();
+        q.readback
+            .slice(..q.names.len() as u64 * 2 * TIMESTAMP_SIZE)
+            .map_async(wgpu::MapMode::Read, move |result| match result {
+                Ok(()) => flag.store(true, Ordering::Release),
+                Err(e) => log::error!("Failed to map the timestamp buffer: {e}"),
+            });
+        q.state = State::Mapping(mapped);
+    });
+}
+
+///Stores the timings of the frame the gpu finished, without waiting for it
+pub(super) fn poll() {
+    QUERIES.with_borrow_mut(|q| {
+        let Some(q) = q.as_mut() else {
+            return;
+        };
+        let State::Mapping(mapped) = &q.state else {
+            return;
+        };
+        DEVICE.get().unwrap().poll(wgpu::Maintain::Poll);
+        if !mapped.load(Ordering::Acquire) {
+            return;
+        }
+
+        let size = q.names.len() as u64 * 2 * TIMESTAMP_SIZE;
+        let timestamps =
+            bytemuck::cast_slice::<u8, u64>(&q.readback.slice(..size).get_mapped_range()).to_vec();
+        q.readback.unmap();
+        q.state = State::Idle;
+
+        //Seconds per tick
+        let period = f64::from(QUEUE.get().unwrap().get_timestamp_period()) / 1e9;
+        let timings = q
+            .names
+            .drain(..)
+            .zip(timestamps.chunks_exact(2))
+            .map(|(name, t)| ExtensionTiming {
+                name,
+                duration: Duration::from_secs_f64(t[1].saturating_sub(t[0]) as f64 * period),
+            })
+            .collect();
+        if is_enabled() {
+            *LAST.lock().unwrap() = Some(timings);
+        }
+    });
+}
+
+fn create_queries() -> Queries {
+    let device = DEVICE.get().unwrap();
+    let count = MAX_EXTENSIONS as u32 * 2;
+    let size = u64::from(count) * TIMESTAMP_SIZE;
+
+    Queries {
+        set: device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("Extension timestamps"),
+            ty: wgpu::QueryType::Timestamp,
+            count,
+        }),
+        resolve: device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Extension timestamps resolve"),
+            size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        }),
+        readback: device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Extension timestamps readback"),
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        }),
+        names: Vec::new(),
+        state: State::Idle,
+    }
+}
+
+///Drops the queries and the timings
+pub(crate) fn release() {
+    QUERIES.take();
+    LAST.lock().unwrap().take();
+}
@@ -0,0 +1,261 @@
+//! Capturing of the rendered frames
+//!
+//! A screenshot is copied from the surface after all the extensions ran and read back once the
+//! GPU is done with it, checked at the start of the following frames, so requesting one never
+//! stalls the frame.
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use crate::{DEVICE, QUEUE};
+
+///Function receiving the width, the height and the RGBA8 pixels of the screenshot, starting from
+///the top left corner
+pub type Callback = Box<dyn FnOnce(u32, u32, Vec<u8>) + Send>;
+
+//Whether the surface can be copied from
+static SUPPORTED: AtomicBool = AtomicBool::new(false);
+//Requests waiting for the next frame
+static REQUESTS: Mutex<Vec<Callback>> = Mutex::new(Vec::new());
+//Frames being copied
+static PENDING: Mutex<Vec<Pending>> = Mutex::new(Vec::new());
+
+struct Pending {
+    buffer: wgpu::Buffer,
+    mapped: Arc<AtomicBool>,
+    width: u32,
+    height: u32,
+    bytes_per_row: u64,
+    format: wgpu::TextureFormat,
+    callbacks: Vec<Callback>,
+}
+
+pub(crate) fn set_supported(supported: bool) {
+    SUPPORTED.store(supported, Ordering::Relaxed);
+}
+
+///Returns whether or not the surface supports taking screenshots
+#[must_use]
+pub fn is_supported() -> bool {
+    SUPPORTED.load(Ordering::Relaxed)
+}
+
+///Saves the next rendered frame into a png file at `path`
+///
+///The file is written a few frames later, once the GPU finished copying the frame
+pub fn request_screenshot(path: impl Into<PathBuf>) {
+    let path = path.into();
+    request_screenshot_with(Box::new(move |width, height, pixels| {
+        if let Some(directory) = path.parent() {
+            _ = std::fs::create_dir_all(directory);
+        }
+        match std::fs::write(&path, encode_png(width, height, &pixels)) {
+            Ok(()) => log::info!("Saved a screenshot to {}", path.display()),
+            Err(e) => log::error!("Failed to save a screenshot to {}: {e}", path.display()),
+        }
+    }));
+}
+
+///Calls the `callback` with the pixels of the next rendered frame
+///
+///The callback is called at the start of one of the following frames, once the GPU finished
+///copying the frame
+pub fn request_screenshot_with(callback: Callback) {
+    if !is_supported() {
+        log::warn!("Screenshot feature not supported!");
+        return;
+    }
+    REQUESTS.lock().unwrap().push(callback);
+}
+
+///Copies the `texture` into a buffer if there are any requests, must be called after all the
+///commands rendering into it were submitted
+pub(super) fn capture(texture: &wgpu::Texture) {
+    let callbacks = std::mem::take(&mut *REQUESTS.lock().unwrap());
+    if callbacks.is_empty() {
+        return;
+    }
+    let device = DEVICE.get().unwrap();
+    let format = texture.format();
+    if !matches!(
+        format,
+        wgpu::TextureFormat::Rgba8Unorm
+            | wgpu::TextureFormat::Rgba8UnormSrgb
+            | wgpu::TextureFormat::Bgra8Unorm
+            | wgpu::TextureFormat::Bgra8UnormSrgb
+    ) {
+        log::error!("Screenshots of the {format:?} surface are not supported");
+        return;
+    }
+    let (width, height) = (texture.width(), texture.height());
+    let bytes_per_row = crate::helpers::calculate_bpr(width, format);
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Screenshot buffer"),
+        size: bytes_per_row * u64::from(height),
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Screenshot encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row as u32),
+                rows_per_image: Some(height),
+            },
+        },
+        texture.size(),
+    );
+    QUEUE.get().unwrap().submit(Some(encoder.finish()));
+
+    let mapped = Arc::new(AtomicBool::new(false));
+    let flag = mapped.clone();
+    buffer
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, move |result| match result {
+            Ok(()) => flag.store(true, Ordering::Release),
+            Err(e) => log::error!("Failed to map the screenshot buffer: {e}"),
+        });
+
+    PENDING.lock().unwrap().push(Pending {
+        buffer,
+        mapped,
+        width,
+        height,
+        bytes_per_row,
+        format,
+        callbacks,
+    });
+}
+
+///Calls the callbacks of the screenshots the GPU finished copying, without waiting for the rest
+pub(super) fn poll() {
+    let mut pending = PENDING.lock().unwrap();
+    if pending.is_empty() {
+        return;
+    }
+    DEVICE.get().unwrap().poll(wgpu::Maintain::Poll);
+
+    let (done, waiting) = std::mem::take(&mut *pending)
+        .into_iter()
+        .partition::<Vec<_>, _>(|p| p.mapped.load(Ordering::Acquire));
+    *pending = waiting;
+    drop(pending);
+
+    for p in done {
+        let mut pixels = unpad(
+            &p.buffer.slice(..).get_mapped_range(),
+            p.width,
+            p.height,
+            p.bytes_per_row,
+        );
+        p.buffer.unmap();
+        if matches!(
+            p.format,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        ) {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        let mut callbacks = p.callbacks;
+        let last = callbacks.pop();
+        for callback in callbacks {
+            callback(p.width, p.height, pixels.clone());
+        }
+        if let Some(callback) = last {
+            callback(p.width, p.height, pixels);
+        }
+    }
+}
+
+///Removes the padding at the end of the rows of 4 byte pixels, required by the copy alignment
+pub(crate) fn unpad(data: &[u8], width: u32, height: u32, bytes_per_row: u64) -> Vec<u8> {
+    let row = width as usize * 4;
+    data.chunks(bytes_per_row as usize)
+        .take(height as usize)
+        .flat_map(|r| &r[..row])
+        .copied()
+        .collect()
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = u32::MAX;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + u32::from(*byte)) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+///Encodes RGBA8 pixels into an uncompressed png
+pub(crate) fn encode_png(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+    fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+        png.extend((data.len() as u32).to_be_bytes());
+        let start = png.len();
+        png.extend(kind);
+        png.extend(data);
+        let crc = crc32(&png[start..]);
+        png.extend(crc.to_be_bytes());
+    }
+
+    //Every row starts with the filter type, none
+    let raw = pixels
+        .chunks(width as usize * 4)
+        .flat_map(|r| std::iter::once(0).chain(r.iter().copied()))
+        .collect::<Vec<_>>();
+
+    //Zlib stream of stored deflate blocks
+    let mut data = vec![0x78, 0x01];
+    let blocks = raw.chunks(u16::MAX as usize);
+    let count = blocks.len();
+    for (i, block) in blocks.enumerate() {
+        let len = block.len() as u16;
+        data.push(u8::from(i + 1 == count));
+        data.extend(len.to_le_bytes());
+        data.extend((!len).to_le_bytes());
+        data.extend(block);
+    }
+    if count == 0 {
+        data.extend([1, 0, 0, 0xFF, 0xFF]);
+    }
+    data.extend(adler32(&raw).to_be_bytes());
+
+    let mut header = Vec::with_capacity(13);
+    header.extend(width.to_be_bytes());
+    header.extend(height.to_be_bytes());
+    //8 bit depth, RGBA, deflate, no filter, no interlace
+    header.extend([8, 6, 0, 0, 0]);
+
+    let mut png = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+    chunk(&mut png, b"IHDR", &header);
+    chunk(&mut png, b"IDAT", &data);
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
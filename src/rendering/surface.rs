@@ -0,0 +1,243 @@
+//! Format of the frame buffer
+//!
+//! By default the engine picks one of the formats the surface supports. A specific format can be
+//! requested with [`set_format`] before the window is created, if the surface doesn't support it
+//! the default one is used instead.
+//!
+//! Requesting [`HDR_FORMAT`] outputs linear colors in an extended range on platforms supporting
+//! it, values above 1 are displayed brighter than white on HDR monitors. The
+//! [`Tonemap`](super::extensions::Tonemap) extension then only applies the exposure, custom
+//! extensions can check [`is_hdr_output`].
+//!
+//!```no_run
+//! use lunar_engine::rendering::surface;
+//!
+//! surface::set_format(Some(surface::HDR_FORMAT)).unwrap();
+//!```
+//!
+//! # Transparency
+//! The window can be created with per-pixel transparency with [`set_transparent`], the alpha of
+//! the frame buffer then blends the image with the desktop, for overlays and desktop widgets. The
+//! parts without any meshes are see-through if the background is transparent, see
+//! [`super::background`].
+//!
+//!```no_run
+//! use lunar_engine::{rendering::{self, surface}, structures::Color};
+//!
+//! surface::set_transparent(true);
+//! rendering::set_clear_color(Some(Color::new(0.0, 0.0, 0.0, 0.0)));
+//!```
+//!
+//! The compositing mode is picked from the modes supported by the surface, preferring
+//! premultiplied alpha, or can be requested with [`set_alpha_mode`]. With premultiplied alpha the
+//! clear colors are premultiplied by the engine, materials blending into the frame buffer have to
+//! output premultiplied colors themselves, see [`is_premultiplied`].
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    RwLock,
+};
+
+///Format of the frame buffer for HDR output, linear colors with values above 1 being brighter than
+///white
+pub const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+static REQUESTED: RwLock<Option<wgpu::TextureFormat>> = RwLock::new(None);
+static TRANSPARENT: AtomicBool = AtomicBool::new(false);
+static REQUESTED_ALPHA: RwLock<Option<wgpu::CompositeAlphaMode>> = RwLock::new(None);
+//Mode the surface was configured with
+static ALPHA_MODE: RwLock<Option<wgpu::CompositeAlphaMode>> = RwLock::new(None);
+
+///The format is not supported by the surface
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedSurfaceFormat {
+    ///Requested format
+    pub requested: wgpu::TextureFormat,
+    ///Formats supported by the surface
+    pub supported: Vec<wgpu::TextureFormat>,
+}
+
+impl std::fmt::Display for UnsupportedSurfaceFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Surface format {:?} is not supported, supported formats are {:?}",
+            self.requested, self.supported
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedSurfaceFormat {}
+
+///The compositing mode is not supported by the surface
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedAlphaMode {
+    ///Requested mode
+    pub requested: wgpu::CompositeAlphaMode,
+    ///Modes supported by the surface
+    pub supported: Vec<wgpu::CompositeAlphaMode>,
+}
+
+impl std::fmt::Display for UnsupportedAlphaMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Alpha mode {:?} is not supported, supported modes are {:?}",
+            self.requested, self.supported
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedAlphaMode {}
+
+///Requests the format of the frame buffer, `None` lets the engine pick one
+///
+///Has to be called before the window is created, the format can't be changed afterwards
+///
+///# Errors
+///Returns an error if the gpu is already initialized and the surface doesn't support the format,
+///see [`super::capabilities::Capabilities::surface_formats`]
+pub fn set_format(format: Option<wgpu::TextureFormat>) -> Result<(), UnsupportedSurfaceFormat> {
+    if let (Some(requested), Some(capabilities)) = (format, super::capabilities()) {
+        if !capabilities.surface_formats.contains(&requested) {
+            return Err(UnsupportedSurfaceFormat {
+                requested,
+                supported: capabilities.surface_formats.clone(),
+            });
+        }
+    }
+    *REQUESTED.write().unwrap() = format;
+    Ok(())
+}
+
+///Returns the requested format of the frame buffer
+#[must_use]
+pub fn requested_format() -> Option<wgpu::TextureFormat> {
+    *REQUESTED.read().unwrap()
+}
+
+///Returns `true` if the frame buffer uses [`HDR_FORMAT`]
+#[must_use]
+pub fn is_hdr_output() -> bool {
+    crate::FORMAT.get() == Some(&HDR_FORMAT)
+}
+
+///Picks the format of the frame buffer from the formats supported by the surface, the requested
+///one if it's supported, the last one otherwise
+pub(crate) fn pick(supported: &[wgpu::TextureFormat]) -> Option<wgpu::TextureFormat> {
+    match requested_format() {
+        Some(format) if supported.contains(&format) => Some(format),
+        Some(format) => {
+            log::warn!(
+                "{}, using the default surface format",
+                UnsupportedSurfaceFormat {
+                    requested: format,
+                    supported: supported.to_vec(),
+                }
+            );
+            supported.last().copied()
+        }
+        None => supported.last().copied(),
+    }
+}
+
+///Creates the window with per-pixel transparency, the frame buffer is blended with the desktop
+///
+///Has to be called before the window is created. Not every platform supports it, see
+///[`super::capabilities::Capabilities::transparency`]
+pub fn set_transparent(transparent: bool) {
+    TRANSPARENT.store(transparent, Ordering::Relaxed);
+}
+
+///Returns `true` if the window is created with per-pixel transparency
+#[must_use]
+pub fn is_transparent() -> bool {
+    TRANSPARENT.load(Ordering::Relaxed)
+}
+
+///Requests the mode of compositing the frame buffer with the desktop, `None` lets the engine pick
+///one based on [`set_transparent`]
+///
+///Has to be called before the window is created
+///
+///# Errors
+///Returns an error if the gpu is already initialized and the surface doesn't support the mode,
+///see [`super::capabilities::Capabilities::alpha_modes`]
+pub fn set_alpha_mode(mode: Option<wgpu::CompositeAlphaMode>) -> Result<(), UnsupportedAlphaMode> {
+    if let (Some(requested), Some(capabilities)) = (mode, super::capabilities()) {
+        if !capabilities.alpha_modes.contains(&requested) {
+            return Err(UnsupportedAlphaMode {
+                requested,
+                supported: capabilities.alpha_modes.clone(),
+            });
+        }
+    }
+    *REQUESTED_ALPHA.write().unwrap() = mode;
+    Ok(())
+}
+
+///Returns the mode the frame buffer is composited with, `None` before the window is created
+#[must_use]
+pub fn alpha_mode() -> Option<wgpu::CompositeAlphaMode> {
+    *ALPHA_MODE.read().unwrap()
+}
+
+///Returns `true` if the frame buffer is expected to contain premultiplied colors
+#[must_use]
+pub fn is_premultiplied() -> bool {
+    alpha_mode() == Some(wgpu::CompositeAlphaMode::PreMultiplied)
+}
+
+///Picks the compositing mode from the modes supported by the surface, the requested one if it's
+///supported. Otherwise transparent windows prefer premultiplied alpha, opaque ones ignore the
+///alpha
+pub(crate) fn pick_alpha_mode(supported: &[wgpu::CompositeAlphaMode]) -> wgpu::CompositeAlphaMode {
+    use wgpu::CompositeAlphaMode as Mode;
+
+    let requested = *REQUESTED_ALPHA.read().unwrap();
+    if let Some(mode) = requested {
+        if supported.contains(&mode) {
+            *ALPHA_MODE.write().unwrap() = Some(mode);
+            return mode;
+        }
+        log::warn!(
+            "{}, picking one instead",
+            UnsupportedAlphaMode {
+                requested: mode,
+                supported: supported.to_vec(),
+            }
+        );
+    }
+
+    let preferred: &[Mode] = if is_transparent() {
+        &[Mode::PreMultiplied, Mode::PostMultiplied, Mode::Inherit]
+    } else {
+        &[Mode::Opaque]
+    };
+    let mode = preferred
+        .iter()
+        .find(|m| supported.contains(m))
+        .copied()
+        .unwrap_or_else(|| {
+            if is_transparent() {
+                log::warn!("The surface does not support transparency");
+            }
+            supported.first().copied().unwrap_or(Mode::Auto)
+        });
+
+    *ALPHA_MODE.write().unwrap() = Some(mode);
+    mode
+}
+
+///Premultiplies the color if the frame buffer expects premultiplied colors
+pub(crate) fn output_color(color: crate::structures::Color) -> crate::structures::Color {
+    if is_premultiplied() {
+        crate::structures::Color::new(
+            color.r * color.a,
+            color.g * color.a,
+            color.b * color.a,
+            color.a,
+        )
+    } else {
+        color
+    }
+}
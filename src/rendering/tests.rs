@@ -0,0 +1,2104 @@
+use std::sync::Mutex;
+
+use crate::{
+    asset_managment::AssetStore,
+    assets::{materials::ColorUnlit, Mesh},
+    components::{camera::MainCamera, mesh::Mesh as MeshComponent, transform::Transform},
+    ecs::{Component, EntityBuilder, World},
+    math::{Vec2, Vec3},
+    structures::Color,
+    test_utils::{compare_golden, generate_headless},
+};
+
+use super::{
+    depth::{self, DepthRange},
+    extensions, hdr, msaa, render_to_image, surface, Background, RenderingExtension,
+};
+
+const WIDTH: u32 = 128;
+const HEIGHT: u32 = 128;
+const TOLERANCE: u8 = 2;
+
+//Rendering into an image changes the global resolution
+static LOCK: Mutex<()> = Mutex::new(());
+
+///Creates a scene with a camera, a single box and a row of instanced boxes
+fn scene() -> (World, AssetStore) {
+    let mut world = World::new();
+    let mut assets = AssetStore::new();
+
+    let mesh = assets.register(Mesh::new_box(Vec3::new(1.0, 1.0, 1.0)));
+    let red = assets.register(ColorUnlit::new(Color::rgb(1.0, 0.0, 0.0)));
+    let green = assets.register(ColorUnlit::new(Color::rgb(0.0, 1.0, 0.0)));
+    assets.intialize_all().unwrap();
+
+    world.add_entity(
+        EntityBuilder::new()
+            .create_component(|| Transform {
+                position: Vec3::new(0.0, 0.0, -10.0),
+                ..Default::default()
+            })
+            .create_component(MainCamera::mew)
+            .create()
+            .unwrap(),
+    );
+
+    world.add_entity(
+        EntityBuilder::new()
+            .create_component(|| Transform {
+                position: Vec3::new(0.0, 1.5, 0.0),
+                rotation: Vec3::new(30.0, 45.0, 0.0),
+                ..Default::default()
+            })
+            .create_component(|| MeshComponent::new(mesh, red))
+            .create()
+            .unwrap(),
+    );
+
+    for i in -2..=2 {
+        world.add_entity(
+            EntityBuilder::new()
+                .create_component(|| Transform {
+                    position: Vec3::new(i as f32 * 1.5, -1.5, 0.0),
+                    ..Default::default()
+                })
+                .create_component(|| MeshComponent::new(mesh, green))
+                .create()
+                .unwrap(),
+        );
+    }
+
+    //Out of view, must not affect the image
+    world.add_entity(
+        EntityBuilder::new()
+            .create_component(|| Transform {
+                position: Vec3::new(0.0, 0.0, -20.0),
+                ..Default::default()
+            })
+            .create_component(|| MeshComponent::new(mesh, green))
+            .create()
+            .unwrap(),
+    );
+
+    (world, assets)
+}
+
+///Renders the scene with the extension and compares it with the reference `name`
+fn golden(name: &str, extension: &mut dyn RenderingExtension) {
+    golden_with_depth(name, &mut [extension], DepthRange::Standard);
+}
+
+///Renders the scene with the extensions and the depth range and compares it with the reference
+///`name`
+fn golden_with_depth(
+    name: &str,
+    extensions: &mut [&mut dyn RenderingExtension],
+    range: DepthRange,
+) {
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+    //Must be set before the materials are created
+    depth::set_depth_range(range);
+
+    let (world, assets) = scene();
+
+    //Render twice to also test the cached path of the extensions
+    for _ in 0..2 {
+        let image = render_to_image(&world, &assets, extensions, WIDTH, HEIGHT);
+        compare_golden(name, &image, WIDTH, HEIGHT, TOLERANCE);
+    }
+    depth::set_depth_range(DepthRange::Standard);
+}
+
+#[test]
+fn golden_base() {
+    golden(
+        "base",
+        &mut extensions::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0)),
+    );
+}
+
+#[test]
+fn background_test() {
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let (world, assets) = scene();
+    let mut extension = extensions::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    let mut corner = |background| {
+        world.get_all_components::<MainCamera>().unwrap()[0]
+            .borrow_mut()
+            .background = background;
+        let image = render_to_image(&world, &assets, &mut [&mut extension], WIDTH, HEIGHT);
+        [image[0], image[1], image[2], image[3]]
+    };
+
+    assert_eq!(corner(Background::Inherit), [0, 0, 255, 255]);
+    super::set_clear_color(Some(Color::rgb(0.0, 1.0, 0.0)));
+    assert_eq!(corner(Background::Inherit), [0, 255, 0, 255]);
+    assert_eq!(
+        corner(Background::Color(Color::rgb(1.0, 0.0, 0.0))),
+        [255, 0, 0, 255]
+    );
+    assert_eq!(corner(Background::Transparent), [0, 0, 0, 0]);
+    super::set_clear_color(None);
+}
+
+#[test]
+fn base_material_swap() {
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let (world, assets) = scene();
+    let mut extension = extensions::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    _ = render_to_image(&world, &assets, &mut [&mut extension], WIDTH, HEIGHT);
+
+    let meshes = world.get_all_components::<MeshComponent>().unwrap();
+    let red = meshes[0].borrow().get_material_id().unwrap();
+    let green = meshes[1].borrow().get_material_id().unwrap();
+    //Empties the bucket of the red material and creates it again
+    meshes[0].borrow_mut().set_material(green);
+    meshes[1].borrow_mut().set_material(red);
+    meshes[2].borrow_mut().set_material(red);
+
+    //Render twice to also write the next set of buffers
+    let mut swapped = Vec::new();
+    for _ in 0..2 {
+        swapped = render_to_image(&world, &assets, &mut [&mut extension], WIDTH, HEIGHT);
+    }
+
+    //Must match the buffers built from scratch
+    let mut fresh = extensions::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    let expected = render_to_image(&world, &assets, &mut [&mut fresh], WIDTH, HEIGHT);
+    assert!(swapped == expected);
+}
+
+///Creates a scene with a camera, a skinned triangle and a box, both using the same lit material
+fn skinned_scene(animator: bool) -> (World, AssetStore) {
+    use crate::{
+        assets::materials::Lit,
+        components::animator::Animator,
+        import::gltf::{test_json, TEST_BUFFER},
+    };
+
+    let json = test_json(&format!(
+        r#"{{"byteLength": 264, "uri": "data:application/octet-stream;base64,{TEST_BUFFER}"}}"#
+    ));
+    let mut world = World::new();
+    let mut assets = AssetStore::new();
+    let triangle = assets.register(Mesh::new_from_static_gltf(
+        Box::leak(json.into_boxed_str()).as_bytes(),
+    ));
+    let cube = assets.register(Mesh::new_box(Vec3::new(1.0, 1.0, 1.0)));
+    let material = assets.register(Lit::new(Color::rgb(1.0, 1.0, 1.0)));
+    assets.intialize_all().unwrap();
+
+    let armature = assets
+        .get_by_id::<Mesh>(triangle)
+        .unwrap()
+        .borrow()
+        .get_armature()
+        .unwrap();
+
+    world.add_entity(
+        EntityBuilder::new()
+            .create_component(|| Transform {
+                position: Vec3::new(0.0, 0.0, -5.0),
+                ..Default::default()
+            })
+            .create_component(MainCamera::mew)
+            .create()
+            .unwrap(),
+    );
+    let mut entity = EntityBuilder::new()
+        .create_component(|| Transform {
+            //Facing the camera
+            rotation: Vec3::new(0.0, 180.0, 0.0),
+            ..Default::default()
+        })
+        .create_component(|| MeshComponent::new(triangle, material));
+    if animator {
+        entity = entity.create_component(|| Animator::new(armature));
+    }
+    world.add_entity(entity.create().unwrap());
+    world.add_entity(
+        EntityBuilder::new()
+            .create_component(|| Transform {
+                position: Vec3::new(-2.0, 0.0, 0.0),
+                ..Default::default()
+            })
+            .create_component(|| MeshComponent::new(cube, material))
+            .create()
+            .unwrap(),
+    );
+
+    (world, assets)
+}
+
+#[test]
+fn base_skinned_mesh() {
+    use crate::{components::animator::Animator, validation};
+
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let missing_animator = |world: &World, assets: &AssetStore| {
+        validation::validate(world, assets)
+            .iter()
+            .any(|w| matches!(w, validation::Warning::MissingAnimator { .. }))
+    };
+
+    let (world, assets) = skinned_scene(true);
+    assert!(!missing_animator(&world, &assets));
+
+    let mut extension = extensions::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    let rest = render_to_image(&world, &assets, &mut [&mut extension], WIDTH, HEIGHT);
+    //Something was drawn
+    assert!(rest.chunks_exact(4).any(|p| p[2] < 128));
+
+    //Without an animator the triangle is drawn in its bind pose with the instanced meshes
+    let (static_world, static_assets) = skinned_scene(false);
+    let mut fresh = extensions::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    let bind_pose = render_to_image(
+        &static_world,
+        &static_assets,
+        &mut [&mut fresh],
+        WIDTH,
+        HEIGHT,
+    );
+    assert!(rest == bind_pose);
+    assert!(missing_animator(&static_world, &static_assets));
+
+    let clip = assets
+        .get_by_id::<Mesh>(
+            world.get_all_components::<MeshComponent>().unwrap()[0]
+                .borrow()
+                .get_mesh_id()
+                .unwrap(),
+        )
+        .unwrap()
+        .borrow()
+        .get_animation("bend")
+        .unwrap();
+    let animators = world.get_all_components::<Animator>().unwrap();
+    animators[0].borrow_mut().play(clip);
+    animators[0].borrow_mut().seek(1.0);
+    let bent = render_to_image(&world, &assets, &mut [&mut extension], WIDTH, HEIGHT);
+    assert!(rest != bent);
+
+    //The culling extension draws the skinned meshes the same way
+    let mut culling =
+        extensions::frustum_culling::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    let culled = render_to_image(&world, &assets, &mut [&mut culling], WIDTH, HEIGHT);
+    assert!(bent == culled);
+    assert_eq!(culling.get_stats().groups, 2);
+}
+
+#[test]
+fn debug_lines() {
+    use super::debug;
+
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let (world, assets) = scene();
+    let mut base = extensions::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    let mut lines = extensions::DebugLines::new(1);
+
+    //In front of the boxes
+    debug::line(
+        Vec3::new(-10.0, 0.0, -5.0),
+        Vec3::new(10.0, 0.0, -5.0),
+        Color::rgb(1.0, 1.0, 0.0),
+    );
+    debug::wire_sphere(Vec3::new(0.0, 0.0, 0.0), 1.0, Color::rgb(1.0, 1.0, 0.0));
+    assert_eq!(debug::line_count(), 1 + 3 * 32);
+
+    let yellow = |image: &[u8]| {
+        image
+            .chunks_exact(4)
+            .any(|p| p[0] > 200 && p[1] > 200 && p[2] < 50)
+    };
+    let image = render_to_image(&world, &assets, &mut [&mut base, &mut lines], WIDTH, HEIGHT);
+    assert!(yellow(&image));
+    //Cleared at the end of the frame
+    assert_eq!(debug::line_count(), 0);
+
+    let image = render_to_image(&world, &assets, &mut [&mut base, &mut lines], WIDTH, HEIGHT);
+    assert!(!yellow(&image));
+}
+
+#[test]
+fn debug_view() {
+    use extensions::RenderMode;
+
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let (world, assets) = scene();
+    let mut base = extensions::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    let mut view = extensions::DebugView::new(1, RenderMode::Wireframe);
+
+    //Drawn over the boxes
+    let image = render_to_image(&world, &assets, &mut [&mut base, &mut view], WIDTH, HEIGHT);
+    assert!(image
+        .chunks_exact(4)
+        .any(|p| p[0] > 200 && p[1] > 200 && p[2] > 200));
+
+    //Replace the image
+    view.priority = 0;
+    view.mode = RenderMode::Normals;
+    let normals = render_to_image(&world, &assets, &mut [&mut view], WIDTH, HEIGHT);
+    assert!(normals.chunks_exact(4).any(|p| p[0] > 50 && p[1] > 50));
+
+    view.mode = RenderMode::Depth;
+    let depth = render_to_image(&world, &assets, &mut [&mut view], WIDTH, HEIGHT);
+    assert!(depth != normals);
+    //Grayscale
+    assert!(depth.chunks_exact(4).all(|p| p[0] == p[1] && p[1] == p[2]));
+    assert!(depth.chunks_exact(4).any(|p| p[0] > 0));
+
+    view.mode = RenderMode::Overdraw;
+    let overdraw = render_to_image(&world, &assets, &mut [&mut view], WIDTH, HEIGHT);
+    assert!(overdraw.chunks_exact(4).any(|p| p[0] > 0));
+}
+
+#[test]
+fn golden_frustum_culling() {
+    let events = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let mut extension =
+        extensions::frustum_culling::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    let sink = events.clone();
+    extension.set_event_sink(move |e| sink.borrow_mut().push(*e));
+
+    //Culling must not change the output
+    golden("base", &mut extension);
+
+    let stats = extension.get_stats();
+    assert_eq!(stats.visible + stats.culled, stats.total);
+    assert!(!stats.rebuilt);
+    //Every group is drawn with at most one draw call
+    assert!(stats.draw_calls > 0 && stats.draw_calls <= stats.groups);
+
+    //Buffers are only built in the first frame
+    let events = events.borrow();
+    assert_eq!(events.len(), 3);
+    assert!(matches!(
+        events[0],
+        extensions::frustum_culling::CullingEvent::BuffersRebuilt { instances, .. } if instances == stats.visible
+    ));
+    assert!(matches!(
+        events[1],
+        extensions::frustum_culling::CullingEvent::Frame(s) if s.rebuilt
+    ));
+    assert_eq!(
+        events[2],
+        extensions::frustum_culling::CullingEvent::Frame(stats)
+    );
+}
+
+#[test]
+fn frustum_culling_partial_meshes() {
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let (mut world, assets) = scene();
+    //Without any assets, with an unregistered mesh and no material, and with unregistered assets
+    for mesh in [
+        MeshComponent::default(),
+        {
+            let mut m = MeshComponent::default();
+            m.set_mesh(12345);
+            m
+        },
+        MeshComponent::new(12345, 12345),
+    ] {
+        world.add_entity(
+            EntityBuilder::new()
+                .add_component::<Transform>()
+                .create_component(|| mesh)
+                .create()
+                .unwrap(),
+        );
+    }
+
+    let mut extension =
+        extensions::frustum_culling::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    //The broken meshes must be skipped, without changing the output
+    for _ in 0..2 {
+        let image = render_to_image(&world, &assets, &mut [&mut extension], WIDTH, HEIGHT);
+        compare_golden("base", &image, WIDTH, HEIGHT, TOLERANCE);
+    }
+
+    let stats = extension.get_stats();
+    assert_eq!(stats.total, 10);
+    assert_eq!(stats.visible + stats.culled, stats.total);
+    assert!(stats.visible <= 6);
+}
+
+#[test]
+fn frustum_culling_distance() {
+    use crate::components::camera::ProjectionType;
+
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let (world, assets) = scene();
+    let meshes = world.get_all_components::<MeshComponent>().unwrap();
+    let mut extension =
+        extensions::frustum_culling::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    let visible = |extension: &mut extensions::frustum_culling::Base| {
+        _ = render_to_image(&world, &assets, &mut [&mut *extension], WIDTH, HEIGHT);
+        extension.get_stats().visible
+    };
+    let all = visible(&mut extension);
+
+    //All the boxes are about 10 units away
+    extension.max_draw_distance = Some(5.0);
+    assert_eq!(visible(&mut extension), 0);
+    meshes[1].borrow_mut().set_max_draw_distance(Some(20.0));
+    assert_eq!(visible(&mut extension), 1);
+
+    //Each box covers about a tenth of the screen
+    extension.max_draw_distance = None;
+    extension.min_screen_size = 0.5;
+    assert_eq!(visible(&mut extension), 0);
+    meshes[1].borrow_mut().set_min_screen_size(Some(0.05));
+    assert_eq!(visible(&mut extension), 1);
+    extension.min_screen_size = 0.05;
+    assert_eq!(visible(&mut extension), all);
+
+    let perspective = ProjectionType::Perspective {
+        fov: std::f32::consts::FRAC_PI_2,
+    };
+    let screen_size = extensions::frustum_culling::screen_size;
+    assert!((screen_size(1.0, 4.0, &perspective) - 0.25).abs() < 0.0001);
+    assert!(screen_size(1.0, 0.5, &perspective).is_infinite());
+    assert!(
+        (screen_size(1.0, 4.0, &ProjectionType::Orthographic { size: 2.0 }) - 0.5).abs() < 0.0001
+    );
+}
+
+#[test]
+fn frustum_culling_bounds() {
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let (mut world, mut assets) = scene();
+    let mut extension =
+        extensions::frustum_culling::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    _ = render_to_image(&world, &assets, &mut [&mut extension], WIDTH, HEIGHT);
+    let stats = extension.get_stats();
+
+    //A tall thin pole far to the side, its bounding sphere reaches into the view but its box
+    //does not
+    let pole = assets.register(Mesh::new_box(Vec3::new(0.1, 60.0, 0.1)));
+    let material = assets.register(ColorUnlit::new(Color::rgb(1.0, 1.0, 1.0)));
+    assets.intialize_all().unwrap();
+    world.add_entity(
+        EntityBuilder::new()
+            .create_component(|| Transform {
+                position: Vec3::new(30.0, 0.0, -5.0),
+                ..Default::default()
+            })
+            .create_component(|| MeshComponent::new(pole, material))
+            .create()
+            .unwrap(),
+    );
+
+    _ = render_to_image(&world, &assets, &mut [&mut extension], WIDTH, HEIGHT);
+    assert_eq!(extension.get_stats().total, stats.total + 1);
+    assert_eq!(extension.get_stats().visible, stats.visible);
+
+    //Moving the pole into the view updates the bounding volume hierarchy
+    world
+        .get_all_components::<Transform>()
+        .unwrap()
+        .last()
+        .unwrap()
+        .borrow_mut()
+        .position = Vec3::new(0.0, 0.0, -5.0);
+    _ = render_to_image(&world, &assets, &mut [&mut extension], WIDTH, HEIGHT);
+    assert_eq!(extension.get_stats().visible, stats.visible + 1);
+    assert!(extension.get_stats().frustum_tests > 0);
+}
+
+#[test]
+fn golden_occlusion_culling() {
+    let mut extension =
+        extensions::frustum_culling::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    extension.occlusion_culling = true;
+
+    //The culling runs from the second frame on, it must not change the output
+    golden("base", &mut extension);
+}
+
+#[test]
+fn golden_reversed_z() {
+    //The depth range must not change the output
+    golden_with_depth(
+        "base",
+        &mut [&mut extensions::Base::new_with_color(
+            0,
+            Color::rgb(0.0, 0.0, 1.0),
+        )],
+        DepthRange::Reversed,
+    );
+}
+
+#[test]
+fn golden_layered() {
+    //Only the first extension clears the attachments, the second one draws on top
+    let mut first = extensions::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    let mut second =
+        extensions::frustum_culling::Base::new_with_color(1, Color::rgb(0.0, 1.0, 0.0));
+    golden_with_depth("base", &mut [&mut first, &mut second], DepthRange::Standard);
+}
+
+#[test]
+fn golden_scaled() {
+    //The second extension renders at half of the resolution and is blended over the first one
+    let mut first = extensions::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    let mut second = extensions::Base::new(1);
+    second.resolution = super::graph::AttachmentSize::Scaled(0.5);
+    golden_with_depth(
+        "scaled",
+        &mut [&mut first, &mut second],
+        DepthRange::Standard,
+    );
+}
+
+#[test]
+fn golden_skybox() {
+    use crate::assets::Texture;
+
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let (world, mut assets) = scene();
+    let panorama = assets.register(Texture::new_png(std::path::Path::new(
+        "assets/test-data/blahaj.png",
+    )));
+    assets.intialize_by_type::<Texture>().unwrap();
+
+    //The sky is only visible behind the boxes
+    let mut base = extensions::Base::new(0);
+    let mut skybox =
+        extensions::Skybox::new(1, extensions::SkyboxSource::Equirectangular(panorama));
+    for _ in 0..2 {
+        let image = render_to_image(
+            &world,
+            &assets,
+            &mut [&mut base, &mut skybox],
+            WIDTH,
+            HEIGHT,
+        );
+        compare_golden("skybox", &image, WIDTH, HEIGHT, TOLERANCE);
+    }
+}
+
+#[test]
+fn golden_render_target() {
+    use crate::assets::{materials::TextureUnlit, Texture};
+    use crate::components::camera::Camera;
+
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let (mut world, mut assets) = scene();
+    let target = assets.register(Texture::render_target(64, 64));
+    let screen = assets.register(TextureUnlit::new(target));
+    let mesh = assets.register(Mesh::new_box(Vec3::new(1.0, 1.0, 0.1)));
+    assets.intialize_all().unwrap();
+
+    //Looks at the boxes from behind, shown on a screen in the top left corner
+    world.add_entity(
+        EntityBuilder::new()
+            .create_component(|| Transform {
+                position: Vec3::new(0.0, 0.0, 10.0),
+                rotation: Vec3::new(0.0, 180.0, 0.0),
+                ..Default::default()
+            })
+            .create_component(|| {
+                let mut camera = Camera::default();
+                camera.target = Some(target);
+                camera.background = Background::Color(Color::white());
+                camera
+            })
+            .create()
+            .unwrap(),
+    );
+    world.add_entity(
+        EntityBuilder::new()
+            .create_component(|| Transform {
+                position: Vec3::new(-3.0, 3.0, -2.0),
+                scale: Vec3::new(2.0, 2.0, 1.0),
+                ..Default::default()
+            })
+            .create_component(|| MeshComponent::new(mesh, screen))
+            .create()
+            .unwrap(),
+    );
+
+    let mut targets = extensions::RenderTargets::new(0);
+    let mut base = extensions::Base::new_with_color(1, Color::rgb(0.0, 0.0, 1.0));
+    for _ in 0..2 {
+        let image = render_to_image(
+            &world,
+            &assets,
+            &mut [&mut targets, &mut base],
+            WIDTH,
+            HEIGHT,
+        );
+        compare_golden("render_target", &image, WIDTH, HEIGHT, TOLERANCE);
+    }
+}
+
+#[test]
+fn golden_sprites() {
+    use crate::assets::Texture;
+    use crate::components::sprite::Sprite;
+
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let (mut world, mut assets) = scene();
+    let texture = assets.register(Texture::new_png(std::path::Path::new(
+        "assets/test-data/blahaj.png",
+    )));
+    assets.intialize_by_type::<Texture>().unwrap();
+
+    //A whole texture behind a tinted quarter of it, in front of the boxes
+    let mut quarter = Sprite::from_region(texture, Vec2::new(0.5, 0.5), Vec2::new(0.5, 0.5));
+    quarter.color = Color::rgb(1.0, 0.5, 0.5);
+    quarter.order = 1;
+    for (x, sprite) in [(-1.0, Sprite::new(texture)), (0.5, quarter)] {
+        world.add_entity(
+            EntityBuilder::new()
+                .create_component(move || Transform {
+                    position: Vec3::new(x, 0.0, -5.0),
+                    scale: Vec3::new(3.0, 3.0, 1.0),
+                    ..Default::default()
+                })
+                .create_component(move || sprite)
+                .create()
+                .unwrap(),
+        );
+    }
+
+    let mut base = extensions::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    let mut sprites = extensions::SpriteRenderer::new(1);
+    for _ in 0..2 {
+        let image = render_to_image(
+            &world,
+            &assets,
+            &mut [&mut base, &mut sprites],
+            WIDTH,
+            HEIGHT,
+        );
+        compare_golden("sprites", &image, WIDTH, HEIGHT, TOLERANCE);
+    }
+}
+
+#[test]
+fn sprite_batching_test() {
+    use super::extensions::sprites::batch_sprites;
+
+    //Sorted by the order, then batched by the texture
+    let mut sprites = [
+        (1, 7, 'a'),
+        (0, 3, 'b'),
+        (0, 7, 'c'),
+        (1, 7, 'd'),
+        (0, 3, 'e'),
+    ];
+    let batches = batch_sprites(&mut sprites);
+    assert_eq!(sprites.map(|s| s.2), ['b', 'e', 'c', 'a', 'd']);
+    assert_eq!(batches, vec![(3, 0..2), (7, 2..5)]);
+}
+
+#[test]
+fn golden_msaa() {
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let (world, assets) = scene();
+    let mut base = extensions::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+
+    msaa::set_sample_count(4).unwrap();
+    for _ in 0..2 {
+        let image = render_to_image(&world, &assets, &mut [&mut base], WIDTH, HEIGHT);
+        compare_golden("msaa", &image, WIDTH, HEIGHT, TOLERANCE);
+    }
+
+    //Switching back at runtime recreates the attachments and the pipelines
+    msaa::set_sample_count(1).unwrap();
+    let image = render_to_image(&world, &assets, &mut [&mut base], WIDTH, HEIGHT);
+    compare_golden("base", &image, WIDTH, HEIGHT, TOLERANCE);
+}
+
+#[test]
+fn msaa_unsupported_sample_count() {
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let error = msaa::set_sample_count(3).unwrap_err();
+    assert_eq!(error.requested, 3);
+    assert!(error.supported.contains(&1));
+    assert_eq!(msaa::sample_count(), 1);
+}
+
+#[test]
+fn surface_unsupported_format() {
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    //There's no surface when rendering headless
+    let error = surface::set_format(Some(surface::HDR_FORMAT)).unwrap_err();
+    assert_eq!(error.requested, surface::HDR_FORMAT);
+    assert_eq!(surface::requested_format(), None);
+
+    let formats = [
+        wgpu::TextureFormat::Bgra8UnormSrgb,
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+    ];
+    assert_eq!(
+        surface::pick(&formats),
+        Some(wgpu::TextureFormat::Rgba8UnormSrgb)
+    );
+    assert!(!surface::is_hdr_output());
+}
+
+#[test]
+fn surface_alpha_mode() {
+    use wgpu::CompositeAlphaMode as Mode;
+
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    //There's no surface when rendering headless
+    let error = surface::set_alpha_mode(Some(Mode::PreMultiplied)).unwrap_err();
+    assert_eq!(error.requested, Mode::PreMultiplied);
+
+    let modes = [Mode::Opaque, Mode::PostMultiplied, Mode::PreMultiplied];
+    assert_eq!(surface::pick_alpha_mode(&modes), Mode::Opaque);
+
+    surface::set_transparent(true);
+    assert_eq!(surface::pick_alpha_mode(&modes), Mode::PreMultiplied);
+    assert_eq!(surface::pick_alpha_mode(&modes[..2]), Mode::PostMultiplied);
+    assert_eq!(surface::pick_alpha_mode(&[Mode::Opaque]), Mode::Opaque);
+
+    _ = surface::pick_alpha_mode(&modes);
+    assert!(surface::is_premultiplied());
+    assert_eq!(
+        Background::Color(Color::new(1.0, 0.5, 0.0, 0.5)).clear_color(Color::black()),
+        Color::new(0.5, 0.25, 0.0, 0.5)
+    );
+
+    surface::set_transparent(false);
+    _ = surface::pick_alpha_mode(&modes);
+    assert!(!surface::is_premultiplied());
+}
+
+#[test]
+fn golden_static_batch() {
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let (mut world, mut assets) = scene();
+    for m in world.get_all_components::<MeshComponent>().unwrap() {
+        m.borrow_mut().set_static(true);
+    }
+
+    let batch = super::bake_static_batch(&mut world, &mut assets).unwrap();
+    //Only the green boxes share a material
+    assert_eq!(batch.meshes.len(), 1);
+    assert_eq!(batch.entities.len(), 1);
+    assert_eq!(batch.merged, 6);
+    let visible = world
+        .get_all_components::<MeshComponent>()
+        .unwrap()
+        .iter()
+        .filter(|m| m.borrow().get_visible())
+        .count();
+    assert_eq!(visible, 2);
+
+    //Merging must not change the output
+    let mut base = extensions::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    let image = render_to_image(&world, &assets, &mut [&mut base], WIDTH, HEIGHT);
+    compare_golden("base", &image, WIDTH, HEIGHT, TOLERANCE);
+}
+
+#[test]
+fn lod_selection() {
+    use crate::components::lod::{LodGroup, LodLevel};
+
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let mut world = World::new();
+    let mut assets = AssetStore::new();
+    let detailed = assets.register(Mesh::new_box(Vec3::new(1.0, 1.0, 1.0)));
+    let simple = assets.register(Mesh::new_box(Vec3::new(0.5, 0.5, 0.5)));
+    let material = assets.register(ColorUnlit::new(Color::rgb(1.0, 0.0, 0.0)));
+    assets.intialize_all().unwrap();
+
+    world.add_entity(
+        EntityBuilder::new()
+            .create_component(|| Transform {
+                position: Vec3::new(0.0, 0.0, -10.0),
+                ..Default::default()
+            })
+            .create_component(MainCamera::mew)
+            .create()
+            .unwrap(),
+    );
+    world.add_entity(
+        EntityBuilder::new()
+            .create_component(Transform::mew)
+            .create_component(|| MeshComponent::new(detailed, material))
+            .create_component(|| {
+                LodGroup::new(vec![
+                    LodLevel::new(simple, 0.0),
+                    LodLevel::new(detailed, 0.5),
+                ])
+            })
+            .create()
+            .unwrap(),
+    );
+    let mesh = world.get_all_components::<MeshComponent>().unwrap()[0].clone();
+    let lod = world.get_all_components::<LodGroup>().unwrap()[0].clone();
+
+    //Far from the camera the simple mesh is used
+    let mut base = extensions::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    let far = render_to_image(&world, &assets, &mut [&mut base], WIDTH, HEIGHT);
+    assert_eq!(lod.borrow().get_current_level(), Some(1));
+    assert_eq!(mesh.borrow().get_mesh_id(), Some(simple));
+
+    //Close to it the detailed one
+    mesh.borrow().get_transform().borrow_mut().position = Vec3::new(0.0, 0.0, -8.0);
+    let mut culling =
+        extensions::frustum_culling::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    let close = render_to_image(&world, &assets, &mut [&mut culling], WIDTH, HEIGHT);
+    assert_eq!(lod.borrow().get_current_level(), Some(0));
+    assert_eq!(mesh.borrow().get_mesh_id(), Some(detailed));
+    assert!(far != close);
+}
+
+#[test]
+fn golden_depth_config() {
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    //Not a depth format
+    let error = depth::set_config(
+        wgpu::TextureFormat::Rgba8Unorm,
+        wgpu::TextureUsages::RENDER_ATTACHMENT,
+    )
+    .unwrap_err();
+    assert_eq!(error.format, wgpu::TextureFormat::Rgba8Unorm);
+    assert!(!error.supported.is_empty());
+    assert_eq!(depth::format(), depth::DEFAULT_FORMAT);
+    assert_eq!(depth::usage(), depth::DEFAULT_USAGE);
+
+    //Materials must be created after the format is set
+    depth::set_config(
+        wgpu::TextureFormat::Depth24PlusStencil8,
+        wgpu::TextureUsages::empty(),
+    )
+    .unwrap();
+    assert!(depth::has_stencil());
+    assert_eq!(depth::usage(), wgpu::TextureUsages::RENDER_ATTACHMENT);
+
+    let (world, assets) = scene();
+    let mut base = extensions::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    let image = render_to_image(&world, &assets, &mut [&mut base], WIDTH, HEIGHT);
+    depth::set_config(depth::DEFAULT_FORMAT, depth::DEFAULT_USAGE).unwrap();
+    compare_golden("base", &image, WIDTH, HEIGHT, TOLERANCE);
+}
+
+#[test]
+fn golden_hdr() {
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let (world, assets) = scene();
+    //The background is brighter than the frame buffer can show
+    let mut base = extensions::Base::new_with_color(0, Color::rgb(4.0, 1.0, 0.25));
+    let mut tonemap = extensions::Tonemap::new(1, extensions::TonemapOperator::Reinhard);
+
+    hdr::set_enabled(true);
+    for _ in 0..2 {
+        let image = render_to_image(
+            &world,
+            &assets,
+            &mut [&mut base, &mut tonemap],
+            WIDTH,
+            HEIGHT,
+        );
+        compare_golden("hdr", &image, WIDTH, HEIGHT, TOLERANCE);
+    }
+
+    //Without a tonemapping extension the colors are clamped
+    let mut base = extensions::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    let image = render_to_image(&world, &assets, &mut [&mut base], WIDTH, HEIGHT);
+    hdr::set_enabled(false);
+    compare_golden("base", &image, WIDTH, HEIGHT, TOLERANCE);
+}
+
+#[test]
+fn golden_canvas() {
+    use super::extensions::{Canvas, LineJoin};
+
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let (world, assets) = scene();
+    let mut base = extensions::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    let mut canvas = Canvas::new(1);
+
+    canvas.rect(
+        Vec2::new(8.0, 8.0),
+        Vec2::new(48.0, 24.0),
+        6.0,
+        Color::new(1.0, 1.0, 1.0, 0.5),
+    );
+    canvas.circle(Vec2::new(88.0, 20.0), 12.0, Color::red());
+    for (i, join) in [LineJoin::Miter, LineJoin::Bevel, LineJoin::Round]
+        .into_iter()
+        .enumerate()
+    {
+        let x = 40.0f32.mul_add(i as f32, 8.0);
+        canvas.polyline(
+            &[
+                Vec2::new(x, 80.0),
+                Vec2::new(x + 16.0, 48.0),
+                Vec2::new(x + 32.0, 80.0),
+            ],
+            6.0,
+            join,
+            Color::green(),
+        );
+    }
+
+    let image = render_to_image(
+        &world,
+        &assets,
+        &mut [&mut base, &mut canvas],
+        WIDTH,
+        HEIGHT,
+    );
+    compare_golden("canvas", &image, WIDTH, HEIGHT, TOLERANCE);
+    assert!(canvas.is_empty());
+}
+
+#[test]
+fn sdf_font_layout_test() {
+    use super::extensions::SdfFont;
+
+    let font = SdfFont::grid(0, 16, 6, ' ', 0.5);
+    let a = font.glyph('A').unwrap();
+    //'A' is the 33rd glyph, second column of the third row
+    assert_eq!(a.uv.0, Vec2::new(1.0 / 16.0, 2.0 / 6.0));
+    assert_eq!(a.uv.1, Vec2::new(2.0 / 16.0, 3.0 / 6.0));
+    assert!(font.glyph('\u{80}').is_none());
+
+    let layout = font.layout("AB\nC", Vec2::new(10.0, 20.0), 16.0);
+    let positions = layout.iter().map(|l| l.0).collect::<Vec<_>>();
+    assert_eq!(
+        positions,
+        [
+            Vec2::new(10.0, 20.0),
+            Vec2::new(18.0, 20.0),
+            Vec2::new(10.0, 36.0)
+        ]
+    );
+    assert_eq!(layout[0].1, Vec2::new(8.0, 16.0));
+    assert_eq!(font.measure("AB\nC", 16.0), Vec2::new(16.0, 32.0));
+
+    let mut font = font;
+    font.add_kerning('A', 'V', -0.25);
+    let layout = font.layout("AVA", Vec2::new(0.0, 0.0), 16.0);
+    assert_eq!(layout[1].0, Vec2::new(4.0, 0.0));
+    assert_eq!(layout[2].0, Vec2::new(12.0, 0.0));
+    assert_eq!(font.measure("AVA", 16.0), Vec2::new(20.0, 16.0));
+}
+
+#[test]
+fn bindless_texture_index_test() {
+    use super::bindless::{texture_index, MAX_TEXTURES};
+
+    assert_eq!(texture_index(10), Some(0));
+    assert_eq!(texture_index(11), Some(1));
+    //Textures are added only once
+    assert_eq!(texture_index(10), Some(0));
+
+    for i in 2..MAX_TEXTURES {
+        assert_eq!(texture_index(100 + u128::from(i)), Some(i));
+    }
+    //The array is full
+    assert_eq!(texture_index(12), None);
+    assert_eq!(texture_index(11), Some(1));
+}
+
+#[test]
+fn viewport_test() {
+    use super::viewport::calculate;
+
+    //Pillarbox
+    let v = calculate(200.0, 100.0, Some(1.0));
+    assert_eq!((v.x, v.y, v.width, v.height), (50.0, 0.0, 100.0, 100.0));
+    assert_eq!(v.bars(200, 100), [(0, 0, 50, 100), (150, 0, 50, 100)]);
+
+    //Letterbox
+    let v = calculate(100.0, 200.0, Some(1.0));
+    assert_eq!((v.x, v.y, v.width, v.height), (0.0, 50.0, 100.0, 100.0));
+    assert_eq!(v.bars(100, 200), [(0, 0, 100, 50), (0, 150, 100, 50)]);
+
+    //Whole window
+    let v = calculate(200.0, 100.0, None);
+    assert_eq!((v.x, v.y, v.width, v.height), (0.0, 0.0, 200.0, 100.0));
+
+    //Cursor remapping
+    let v = calculate(200.0, 100.0, Some(1.0));
+    assert_eq!(
+        v.to_viewport(Vec2::new(60.0, 10.0)),
+        Some(Vec2::new(10.0, 10.0))
+    );
+    assert_eq!(v.to_viewport(Vec2::new(10.0, 10.0)), None);
+    assert_eq!(v.to_viewport(Vec2::new(160.0, 10.0)), None);
+
+    //Camera rectangles, clamped to the viewport
+    let r = v.sub(super::viewport::Rect::new(0.5, 0.0, 0.5, 0.25));
+    assert_eq!((r.x, r.y, r.width, r.height), (100.0, 0.0, 50.0, 25.0));
+    let r = v.sub(super::viewport::Rect::new(0.75, -1.0, 1.0, 2.0));
+    assert_eq!((r.x, r.y, r.width, r.height), (125.0, 0.0, 25.0, 100.0));
+}
+
+#[test]
+fn golden_split_screen() {
+    use super::viewport::Rect;
+    use crate::components::camera::{Camera, CameraClear};
+
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let (mut world, assets) = scene();
+    //Left and right halves, and a picture-in-picture drawn over the right one
+    for (rect, position, priority, clear) in [
+        (
+            Rect::new(0.0, 0.0, 0.5, 1.0),
+            Vec3::new(0.0, 0.0, -10.0),
+            0,
+            CameraClear::Color,
+        ),
+        (
+            Rect::new(0.5, 0.0, 0.5, 1.0),
+            Vec3::new(0.0, 0.0, -6.0),
+            0,
+            CameraClear::Color,
+        ),
+        (
+            Rect::new(0.6, 0.1, 0.3, 0.3),
+            Vec3::new(0.0, 0.0, -20.0),
+            1,
+            CameraClear::Depth,
+        ),
+    ] {
+        world.add_entity(
+            EntityBuilder::new()
+                .create_component(|| Transform {
+                    position,
+                    ..Default::default()
+                })
+                .create_component(|| {
+                    let mut camera = Camera::default();
+                    camera.rect = rect;
+                    camera.priority = priority;
+                    camera.clear = clear;
+                    camera.background = Background::Color(Color::rgb(0.0, 0.0, 1.0));
+                    camera
+                })
+                .create()
+                .unwrap(),
+        );
+    }
+
+    let mut cameras = extensions::Cameras::new(0);
+    for _ in 0..2 {
+        let image = render_to_image(&world, &assets, &mut [&mut cameras], WIDTH, HEIGHT);
+        compare_golden("split_screen", &image, WIDTH, HEIGHT, TOLERANCE);
+    }
+}
+
+#[test]
+fn golden_post_effects() {
+    use super::post::{self, Effect};
+
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let (world, assets) = scene();
+    let mut base = extensions::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+
+    post::push_effect(Effect::Bloom {
+        threshold: 0.5,
+        intensity: 1.0,
+    });
+    post::push_effect(Effect::Fxaa);
+    post::push_effect(Effect::chromatic_aberration());
+    post::push_effect(Effect::vignette());
+    assert_eq!(post::get_effects().len(), 4);
+    assert!(post::is_active());
+
+    let image = render_to_image(&world, &assets, &mut [&mut base], WIDTH, HEIGHT);
+    compare_golden("post_effects", &image, WIDTH, HEIGHT, TOLERANCE);
+
+    assert_eq!(post::pop_effect(), Some(Effect::vignette()));
+    post::clear_effects();
+    assert_eq!(post::pop_effect(), None);
+    assert!(!post::is_active());
+
+    //Without effects the image is unchanged
+    let image = render_to_image(&world, &assets, &mut [&mut base], WIDTH, HEIGHT);
+    compare_golden("base", &image, WIDTH, HEIGHT, TOLERANCE);
+}
+
+#[test]
+fn golden_minimap() {
+    use super::extensions::Minimap;
+    use crate::math::ApproxEq;
+
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let (world, assets) = scene();
+    let mut base = extensions::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    let mut minimap = Minimap::new(1, 48);
+    minimap.position = Vec2::new(72.0, 8.0);
+    minimap.extent = 10.0;
+    minimap.circular = true;
+
+    //The center is in the middle of the map, corners are outside of the circle
+    let center = minimap.world_to_map(Vec3::new(0.0, 0.0, 0.0)).unwrap();
+    assert!(center.approx_eq(&Vec2::new(96.0, 32.0), 0.001));
+    assert!(minimap.world_to_map(Vec3::new(4.9, 0.0, 4.9)).is_none());
+    assert!(minimap.world_to_map(Vec3::new(6.0, 0.0, 0.0)).is_none());
+    minimap.marker(Vec3::new(0.0, 0.0, 0.0), 3.0, Color::white());
+
+    let image = render_to_image(
+        &world,
+        &assets,
+        &mut [&mut base, &mut minimap],
+        WIDTH,
+        HEIGHT,
+    );
+    compare_golden("minimap", &image, WIDTH, HEIGHT, TOLERANCE);
+}
+
+#[test]
+fn color_filter_test() {
+    use super::post::{ColorDeficiency, ColorFilter};
+    use crate::{assert_approx_eq, math::Mat3x3};
+
+    for deficiency in [
+        ColorDeficiency::Protanopia,
+        ColorDeficiency::Deuteranopia,
+        ColorDeficiency::Tritanopia,
+    ] {
+        //Grays are not affected
+        for filter in [
+            ColorFilter::simulate(deficiency),
+            ColorFilter::compensate(deficiency),
+        ] {
+            let gray = Vec3::new(0.5, 0.5, 0.5);
+            assert_approx_eq!(filter.matrix() * gray, gray, 0.001);
+        }
+
+        //Compensation changes colors
+        let red = Vec3::new(1.0, 0.0, 0.0);
+        assert_ne!(
+            ColorFilter::compensate(deficiency).matrix(),
+            Mat3x3::identity()
+        );
+        assert_ne!(ColorFilter::simulate(deficiency).matrix() * red, red);
+    }
+}
+
+#[test]
+fn display_settings_test() {
+    use super::post::DisplaySettings;
+
+    assert!(DisplaySettings::default().is_neutral());
+
+    let settings = DisplaySettings {
+        brightness: 1.25,
+        gamma: 2.2,
+    };
+    assert!(!settings.is_neutral());
+    assert_eq!(DisplaySettings::parse(&settings.to_config()), Ok(settings));
+
+    //Missing keys keep the defaults
+    assert_eq!(
+        DisplaySettings::parse("# Display\ngamma = 1.8\n"),
+        Ok(DisplaySettings {
+            brightness: 1.0,
+            gamma: 1.8
+        })
+    );
+
+    assert!(DisplaySettings::parse("gamma = -1").is_err());
+    assert!(DisplaySettings::parse("contrast = 1").is_err());
+    assert!(DisplaySettings::parse("gamma").is_err());
+}
+
+#[test]
+fn capabilities_test() {
+    crate::test_utils::generate_gpu();
+
+    let capabilities = super::capabilities().unwrap();
+    assert!(capabilities.msaa_levels.contains(&1));
+    assert!(capabilities.max_msaa() >= 1);
+    assert!(capabilities.max_texture_size > 0);
+
+    let info = crate::build_info();
+    assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+}
+
+#[test]
+fn render_graph_order_test() {
+    use super::graph::{resolve_order, AttachmentSize, CycleError, PassInfo};
+
+    let pass = |priority, reads: &[&'static str], writes: &[&'static str]| PassInfo {
+        name: String::new(),
+        priority,
+        reads: reads.to_vec(),
+        writes: writes.to_vec(),
+    };
+
+    //Without dependencies the passes are ordered by priority
+    let passes = [
+        pass(2, &[], &["color"]),
+        pass(0, &[], &["color", "depth_stencil"]),
+        pass(1, &[], &["color"]),
+    ];
+    assert_eq!(resolve_order(&passes), Ok(vec![1, 2, 0]));
+
+    //Shadows are rendered before the passes reading them, regardless of the priorities
+    let passes = [
+        pass(0, &["shadow"], &["color", "depth_stencil"]),
+        pass(10, &[], &["shadow"]),
+        pass(5, &[], &["color"]),
+    ];
+    assert_eq!(resolve_order(&passes), Ok(vec![1, 0, 2]));
+
+    //Post processing reads the color after everything writing to it
+    let passes = [
+        pass(100, &[], &["color"]),
+        pass(0, &["color"], &["post"]),
+        pass(1, &["post"], &["color_out"]),
+    ];
+    assert_eq!(resolve_order(&passes), Ok(vec![0, 1, 2]));
+
+    //Equal priorities keep the order the passes are passed in
+    let passes = [
+        pass(1, &[], &["color"]),
+        pass(0, &[], &["color"]),
+        pass(1, &[], &["color"]),
+        pass(1, &[], &["ui"]),
+    ];
+    assert_eq!(resolve_order(&passes), Ok(vec![1, 0, 2, 3]));
+
+    let passes = [pass(0, &["a"], &["b"]), pass(1, &["b"], &["a"])];
+    assert_eq!(
+        resolve_order(&passes),
+        Err(CycleError { passes: vec![0, 1] })
+    );
+
+    assert_eq!(AttachmentSize::Full.resolve(640, 480), (640, 480));
+    assert_eq!(AttachmentSize::Scaled(0.5).resolve(640, 480), (320, 240));
+    assert_eq!(AttachmentSize::Fixed(0, 2048).resolve(640, 480), (1, 2048));
+}
+
+#[test]
+fn frame_graph_export_test() {
+    use super::graph::{AttachmentInfo, FrameGraph, PassInfo};
+    use crate::import::json::{self, Value};
+
+    let graph = FrameGraph {
+        attachments: vec![AttachmentInfo {
+            name: "color",
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            width: 640,
+            height: 480,
+        }],
+        passes: vec![
+            PassInfo {
+                name: "Clear".into(),
+                priority: 0,
+                reads: Vec::new(),
+                writes: vec!["color"],
+            },
+            PassInfo {
+                name: r#"My "quoted" \ pass"#.into(),
+                priority: 5,
+                reads: vec!["color"],
+                writes: vec!["color"],
+            },
+        ],
+    };
+
+    let dot = graph.to_dot();
+    assert!(dot.starts_with("digraph frame {"));
+    assert!(dot.contains(r#"pass1 [shape=ellipse, label="My \"quoted\" \\ pass\npriority 5"];"#));
+    assert!(dot.contains("pass0 -> pass1 [style=dashed];"));
+    assert!(dot.contains("\"color\" -> pass1;"));
+    assert!(dot.contains("pass1 -> \"color\";"));
+
+    let json = json::parse(&graph.to_json()).unwrap();
+    let attachments = json.get("attachments").and_then(Value::as_array).unwrap();
+    assert_eq!(attachments.len(), 1);
+    assert_eq!(
+        attachments[0].get("name").and_then(Value::as_str),
+        Some("color")
+    );
+    assert_eq!(
+        attachments[0].get("width").and_then(Value::as_usize),
+        Some(640)
+    );
+
+    let passes = json.get("passes").and_then(Value::as_array).unwrap();
+    assert_eq!(passes.len(), 2);
+    assert_eq!(
+        passes[1].get("name").and_then(Value::as_str),
+        Some(r#"My "quoted" \ pass"#)
+    );
+    assert_eq!(passes[1].get("priority").and_then(Value::as_usize), Some(5));
+    assert_eq!(
+        passes[1].get("reads").and_then(Value::as_array),
+        Some([Value::String("color".into())].as_slice())
+    );
+}
+
+#[test]
+fn extension_dependencies_test() {
+    use super::graph::{check_dependencies, DependencyError};
+
+    struct Dummy(&'static [&'static str], &'static [&'static str]);
+    impl RenderingExtension for Dummy {
+        fn render(
+            &mut self,
+            _: &mut wgpu::CommandEncoder,
+            _: &World,
+            _: &AssetStore,
+            _: &extensions::AttachmentData,
+        ) {
+        }
+
+        fn get_priority(&self) -> u32 {
+            0
+        }
+
+        fn get_name(&self) -> &'static str {
+            "Dummy"
+        }
+
+        fn get_provided(&self) -> &'static [&'static str] {
+            self.0
+        }
+
+        fn get_required(&self) -> &'static [&'static str] {
+            self.1
+        }
+    }
+
+    let mut hdr = Dummy(&["hdr"], &[]);
+    let mut bloom = Dummy(&["bloom"], &["hdr"]);
+    let mut lens_flare = Dummy(&[], &["bloom"]);
+    assert_eq!(
+        check_dependencies(&[&mut hdr, &mut bloom, &mut lens_flare]),
+        Ok(())
+    );
+
+    //Missing requirements propagate to the extensions depending on them
+    assert_eq!(
+        check_dependencies(&[&mut lens_flare, &mut bloom]),
+        Err(vec![
+            DependencyError {
+                index: 0,
+                name: "Dummy",
+                missing: vec!["bloom"],
+            },
+            DependencyError {
+                index: 1,
+                name: "Dummy",
+                missing: vec!["hdr"],
+            },
+        ])
+    );
+}
+
+#[test]
+fn light_matrix_test() {
+    use super::lighting::light_matrix;
+    use crate::{
+        assert_approx_eq,
+        math::{Vec4, Vector},
+    };
+
+    for direction in [Vec3::new(0.3, -1.0, 0.5), Vec3::new(0.0, -1.0, 0.0)] {
+        let center = Vec3::new(1.0, 2.0, 3.0);
+        let matrix = light_matrix(direction, center, 10.0, 20.0).transpose();
+        let project = |v: Vec3| matrix.transform(Vec4::new(v.x, v.y, v.z, 1.0));
+
+        //The center is in the middle of the shadow map
+        assert_approx_eq!(project(center), Vec4::new(0.0, 0.0, 0.5, 1.0), 0.001);
+
+        //The depth goes from 0 towards the light to 1 away from it
+        let direction = direction.normalized();
+        assert_approx_eq!(project(center - direction * 10.0).z, 0.0, 0.001);
+        assert_approx_eq!(project(center + direction * 10.0).z, 1.0, 0.001);
+    }
+}
+
+#[test]
+fn golden_shadows() {
+    use crate::{assets::materials::Lit, components::light::DirectionalLight};
+
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let mut world = World::new();
+    let mut assets = AssetStore::new();
+
+    let mesh = assets.register(Mesh::new_box(Vec3::new(1.0, 1.0, 1.0)));
+    let floor = assets.register(Mesh::new_box(Vec3::new(10.0, 0.1, 10.0)));
+    let material = assets.register(Lit::new(Color::white()));
+    assets.intialize_all().unwrap();
+
+    world.add_entity(
+        EntityBuilder::new()
+            .create_component(|| Transform {
+                position: Vec3::new(0.0, 3.0, -8.0),
+                rotation: Vec3::new(20.0, 0.0, 0.0),
+                ..Default::default()
+            })
+            .create_component(MainCamera::mew)
+            .create()
+            .unwrap(),
+    );
+
+    world.add_entity(
+        EntityBuilder::new()
+            .create_component(|| Transform {
+                rotation: Vec3::new(60.0, 30.0, 0.0),
+                ..Default::default()
+            })
+            .create_component(|| DirectionalLight::new(Color::white(), 1.0))
+            .create()
+            .unwrap(),
+    );
+
+    for (mesh, position) in [
+        (floor, Vec3::new(0.0, -0.55, 0.0)),
+        (mesh, Vec3::new(0.0, 0.5, 0.0)),
+    ] {
+        world.add_entity(
+            EntityBuilder::new()
+                .create_component(|| Transform {
+                    position,
+                    ..Default::default()
+                })
+                .create_component(|| MeshComponent::new(mesh, material))
+                .create()
+                .unwrap(),
+        );
+    }
+
+    let mut shadows = extensions::shadows::ShadowMap::new(0, 512);
+    let mut base = extensions::Base::new_with_color(1, Color::rgb(0.0, 0.0, 1.0));
+    for _ in 0..2 {
+        let image = render_to_image(
+            &world,
+            &assets,
+            &mut [&mut base, &mut shadows],
+            WIDTH,
+            HEIGHT,
+        );
+        compare_golden("shadows", &image, WIDTH, HEIGHT, TOLERANCE);
+    }
+}
+
+#[test]
+fn golden_pbr() {
+    use crate::{
+        assets::materials::{Pbr, PbrParameters},
+        components::light::{DirectionalLight, PointLight},
+    };
+
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let mut world = World::new();
+    let mut assets = AssetStore::new();
+
+    let mesh = assets.register(Mesh::new_box(Vec3::new(1.0, 1.0, 1.0)));
+    //Dielectric to metal from left to right, smooth to rough from bottom to top
+    let mut materials = Vec::new();
+    for roughness in [0.2, 0.8] {
+        for metallic in [0.0, 0.5, 1.0] {
+            materials.push(assets.register(Pbr::new(PbrParameters {
+                albedo: Color::rgb(1.0, 0.5, 0.2),
+                metallic,
+                roughness,
+                ..Default::default()
+            })));
+        }
+    }
+    assets.intialize_all().unwrap();
+
+    world.add_entity(
+        EntityBuilder::new()
+            .create_component(|| Transform {
+                position: Vec3::new(0.0, 0.0, -8.0),
+                ..Default::default()
+            })
+            .create_component(MainCamera::mew)
+            .create()
+            .unwrap(),
+    );
+
+    world.add_entity(
+        EntityBuilder::new()
+            .create_component(|| Transform {
+                rotation: Vec3::new(30.0, -30.0, 0.0),
+                ..Default::default()
+            })
+            .create_component(|| DirectionalLight::new(Color::white(), 2.0))
+            .create()
+            .unwrap(),
+    );
+
+    world.add_entity(
+        EntityBuilder::new()
+            .create_component(|| Transform {
+                position: Vec3::new(0.0, 0.0, -2.0),
+                ..Default::default()
+            })
+            .create_component(|| PointLight::new(Color::white(), 4.0, 5.0))
+            .create()
+            .unwrap(),
+    );
+
+    for (i, material) in materials.into_iter().enumerate() {
+        world.add_entity(
+            EntityBuilder::new()
+                .create_component(|| Transform {
+                    position: Vec3::new(
+                        (i % 3) as f32 * 1.5 - 1.5,
+                        (i / 3) as f32 * 1.5 - 0.75,
+                        0.0,
+                    ),
+                    rotation: Vec3::new(20.0, 30.0, 0.0),
+                    ..Default::default()
+                })
+                .create_component(|| MeshComponent::new(mesh, material))
+                .create()
+                .unwrap(),
+        );
+    }
+
+    let mut culling = extensions::clustered::LightCulling::new(0);
+    let mut base = extensions::Base::new_with_color(1, Color::black());
+    let image = render_to_image(
+        &world,
+        &assets,
+        &mut [&mut base, &mut culling],
+        WIDTH,
+        HEIGHT,
+    );
+    compare_golden("pbr", &image, WIDTH, HEIGHT, TOLERANCE);
+}
+
+#[test]
+fn light_clusters_test() {
+    use super::{
+        extensions::clustered::{assign_lights, ClusterCamera},
+        lighting::{CLUSTERS, MAX_LIGHTS_PER_CLUSTER},
+    };
+    use crate::math::Mat4x4;
+
+    let position = Vec3::new(0.0, 0.0, -10.0);
+    let forward = Vec3::new(0.0, 0.0, 1.0);
+    let camera = ClusterCamera {
+        view_projection: Mat4x4::look_at_matrix(
+            position,
+            Vec3::new(0.0, 1.0, 0.0),
+            position + forward,
+        ) * Mat4x4::perspercive_projection(
+            std::f32::consts::FRAC_PI_3,
+            1.0,
+            0.1,
+            100.0,
+        ),
+        position,
+        forward,
+        near: 0.1,
+        far: 100.0,
+    };
+    let count = |c: u32| (c & 0xff) as usize;
+
+    //In front of the camera, behind it and outside of the view
+    let lights = [
+        (Vec3::new(0.0, 0.0, 0.0), 1.0),
+        (Vec3::new(0.0, 0.0, -20.0), 1.0),
+        (Vec3::new(50.0, 0.0, 0.0), 1.0),
+    ];
+    let (clusters, indices, stats) = assign_lights(&camera, &lights, 4096);
+    assert_eq!(
+        clusters.len(),
+        (CLUSTERS[0] * CLUSTERS[1] * CLUSTERS[2]) as usize
+    );
+    assert_eq!(stats.visible, 1);
+    assert_eq!(stats.dropped, 0);
+    assert!(!indices.is_empty() && indices.iter().all(|i| *i == 0));
+    assert_eq!(
+        clusters.iter().map(|c| count(*c)).sum::<usize>(),
+        indices.len()
+    );
+
+    //The cluster in the middle of the screen, 10 units away from the camera
+    let middle = ((10 * CLUSTERS[1] + 4) * CLUSTERS[0] + 8) as usize;
+    assert_eq!(count(clusters[middle]), 1);
+    assert_eq!(indices[(clusters[middle] >> 8) as usize], 0);
+
+    //Too many lights in the same place
+    let lights = vec![(Vec3::new(0.0, 0.0, 0.0), 1.0); MAX_LIGHTS_PER_CLUSTER + 10];
+    let (clusters, _, stats) = assign_lights(&camera, &lights, usize::MAX);
+    assert!(stats.dropped > 0);
+    assert_eq!(count(clusters[middle]), MAX_LIGHTS_PER_CLUSTER);
+
+    let (_, indices, stats) = assign_lights(&camera, &lights, 10);
+    assert_eq!(indices.len(), 10);
+    assert_eq!(stats.assignments, 10);
+}
+
+#[test]
+fn screenshot_encoding_test() {
+    //Rows of 3 pixels padded to 16 bytes
+    let padded = (0..4u8)
+        .flat_map(|row| (0..16).map(move |i| if i < 12 { row * 16 + i } else { 255 }))
+        .collect::<Vec<_>>();
+    let pixels = super::screenshot::unpad(&padded, 3, 4, 16);
+    assert_eq!(pixels.len(), 3 * 4 * 4);
+    assert!(pixels.iter().all(|p| *p != 255));
+    assert_eq!(pixels[12], 16);
+
+    let png = super::screenshot::encode_png(3, 4, &pixels);
+    let image = lunar_png::read_png(&mut png.into_iter()).unwrap();
+    assert_eq!((image.width, image.height), (3, 4));
+    assert_eq!(image.img_type, lunar_png::ImageType::Rgba8);
+    assert_eq!(image.data, pixels);
+}
+
+#[test]
+fn compute_pass_test() {
+    use super::compute::{self, ComputePass};
+
+    crate::test_utils::generate_gpu();
+    let pass = ComputePass::new(
+        "double.wgsl",
+        "@group(0) @binding(0) var<storage, read_write> data: array<u32>;
+
+         @compute @workgroup_size(64)
+         fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+             if id.x < arrayLength(&data) {
+                 data[id.x] *= 2u;
+             }
+         }",
+        "main",
+    )
+    .unwrap();
+
+    let data = (0..100).collect::<Vec<u32>>();
+    let buffer = compute::storage_buffer("Data", &data);
+    let bind_group = pass.create_bind_group(0, &[buffer.as_entire_binding()]);
+    assert_eq!(compute::workgroups(100, 64), 2);
+
+    let mut encoder = crate::DEVICE
+        .get()
+        .unwrap()
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    pass.dispatch(&mut encoder, &[&bind_group], [compute::workgroups(100, 64), 1, 1]);
+    crate::QUEUE.get().unwrap().submit(Some(encoder.finish()));
+
+    let doubled = compute::read_buffer::<u32>(&buffer);
+    assert_eq!(doubled, data.iter().map(|i| i * 2).collect::<Vec<_>>());
+
+    //Queued dispatches are recorded in the next encoder
+    pass.queue(&[bind_group], [2, 1, 1]);
+    let mut encoder = crate::DEVICE
+        .get()
+        .unwrap()
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    compute::record_queued(&mut encoder);
+    crate::QUEUE.get().unwrap().submit(Some(encoder.finish()));
+    assert_eq!(compute::read_buffer::<u32>(&buffer)[10], 40);
+
+    assert!(ComputePass::new("invalid.wgsl", "fn main() {}", "main").is_err());
+}
+
+#[test]
+fn profiling_unsupported() {
+    use super::profiling;
+
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    //The test device is created without the timestamp features
+    assert!(!profiling::is_supported());
+    assert_eq!(super::frame_gpu_timings(), None);
+
+    //Enabling it anyway must not change the rendering
+    profiling::set_enabled(true);
+    let (world, assets) = scene();
+    let mut extension = extensions::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    for _ in 0..2 {
+        let image = render_to_image(&world, &assets, &mut [&mut extension], WIDTH, HEIGHT);
+        compare_golden("base", &image, WIDTH, HEIGHT, TOLERANCE);
+    }
+    assert_eq!(super::frame_gpu_timings(), None);
+    profiling::set_enabled(false);
+}
+
+#[test]
+fn error_scopes_across_threads() {
+    use crate::errors;
+
+    crate::test_utils::generate_gpu();
+    let buffer = |label: &str, size: u64| {
+        errors::try_scoped(label, || {
+            crate::DEVICE
+                .get()
+                .unwrap()
+                .create_buffer(&wgpu::BufferDescriptor {
+                    label: Some(label),
+                    size,
+                    usage: wgpu::BufferUsages::COPY_DST,
+                    //Unaligned sizes can't be mapped at creation
+                    mapped_at_creation: true,
+                })
+        })
+    };
+
+    //The errors of one thread must not end up in the scopes of the other
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            for _ in 0..50 {
+                assert!(buffer("Valid", 16).is_ok());
+            }
+        });
+        s.spawn(|| {
+            for _ in 0..50 {
+                let Err(errors::Error::Validation { label, .. }) = buffer("Invalid", 3) else {
+                    panic!("Expected a validation error");
+                };
+                assert_eq!(label, "Invalid");
+            }
+        });
+    });
+    _ = errors::take_errors();
+}
+
+#[test]
+fn draw_data_test() {
+    use super::draw_data::{self, DrawData, DrawDataBinding};
+
+    //More draws than the initial capacity of the uniform buffer
+    const DRAWS: u32 = 100;
+    const SIZE: u32 = 128;
+
+    crate::test_utils::generate_gpu();
+    let device = crate::DEVICE.get().unwrap();
+
+    let data = (0..DRAWS)
+        .map(|i| DrawData {
+            object_id: i + 1,
+            flags: i * 2,
+            user: [i * 3, i * 4],
+        })
+        .collect::<Vec<_>>();
+
+    let mut bindings = vec![DrawDataBinding::with_fallback(true)];
+    if draw_data::push_constants_supported() {
+        bindings.push(DrawDataBinding::new());
+        assert!(bindings[1].bind_group_layout().is_none());
+    }
+
+    for mut binding in bindings {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Draw data test"),
+            source: wgpu::ShaderSource::Wgsl(
+                format!(
+                    "{}
+                     @vertex
+                     fn vertex(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {{
+                         let uv = vec2<f32>(f32((i << 1u) & 2u), f32(i & 2u));
+                         return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+                     }}
+
+                     @fragment
+                     fn fragment() -> @location(0) vec4<u32> {{
+                         return vec4<u32>(draw_data.object_id, draw_data.flags, draw_data.user);
+                     }}",
+                    binding.wgsl_declaration(0)
+                )
+                .into(),
+            ),
+        });
+
+        let layouts = binding.bind_group_layout().into_iter().collect::<Vec<_>>();
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &layouts,
+            push_constant_ranges: binding.push_constant_ranges(),
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vertex",
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fragment",
+                targets: &[Some(wgpu::TextureFormat::Rgba32Uint.into())],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            multiview: None,
+        });
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width: SIZE,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba32Uint,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: u64::from(SIZE) * 16,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        binding.prepare(&data);
+
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&pipeline);
+            //Every draw covers its own pixel
+            for i in 0..DRAWS {
+                pass.set_scissor_rect(i, 0, 1, 1);
+                binding.set(&mut pass, 0, i as usize);
+                pass.draw(0..3, 0..1);
+            }
+        }
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(SIZE * 16),
+                    rows_per_image: None,
+                },
+            },
+            texture.size(),
+        );
+        crate::QUEUE.get().unwrap().submit(Some(encoder.finish()));
+
+        let pixels = super::compute::read_buffer::<DrawData>(&buffer);
+        assert_eq!(pixels[..DRAWS as usize], data);
+        assert_eq!(pixels[DRAWS as usize], DrawData::default());
+    }
+}
+
+///Renders frames with the picking extension until the pick of the pixel is resolved
+fn pick(
+    world: &World,
+    assets: &AssetStore,
+    picking: &mut extensions::picking::Picking,
+    pixel: usize,
+) -> Option<crate::ecs::UUID> {
+    let (x, y) = (pixel as u32 % WIDTH, pixel as u32 / WIDTH);
+    _ = extensions::picking::pick_pixel(x, y);
+    //Copied in the first frame, mapped in the second and resolved in the third
+    for _ in 0..3 {
+        _ = render_to_image(world, assets, &mut [&mut *picking], WIDTH, HEIGHT);
+    }
+    extensions::picking::pick_pixel(x, y)
+}
+
+#[test]
+fn picking_test() {
+    use crate::components::animator::Animator;
+
+    let _lock = LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    generate_headless();
+
+    let position = |world: &World, id| {
+        world
+            .get_entity_by_id(id)
+            .unwrap()
+            .borrow()
+            .get_component::<Transform>()
+            .unwrap()
+            .borrow()
+            .position
+    };
+
+    let (world, assets) = scene();
+    let mut base = extensions::Base::new_with_color(0, Color::rgb(0.0, 0.0, 1.0));
+    let image = render_to_image(&world, &assets, &mut [&mut base], WIDTH, HEIGHT);
+    let pixels = |f: fn(&[u8]) -> bool| {
+        image
+            .chunks_exact(4)
+            .enumerate()
+            .filter(move |(_, p)| f(p))
+            .map(|(i, _)| i)
+    };
+    let red = pixels(|p| p[0] > 128 && p[1] < 128).next().unwrap();
+    let left = pixels(|p| p[1] > 128 && p[0] < 128)
+        .min_by_key(|i| i % WIDTH as usize)
+        .unwrap();
+    let right = pixels(|p| p[1] > 128 && p[0] < 128)
+        .max_by_key(|i| i % WIDTH as usize)
+        .unwrap();
+
+    let mut picking = extensions::picking::Picking::default();
+    let id = pick(&world, &assets, &mut picking, red).unwrap();
+    assert_eq!(position(&world, id), Vec3::new(0.0, 1.5, 0.0));
+
+    //Instances of the same mesh have their own ids, the outermost boxes of the row are picked
+    let first = position(&world, pick(&world, &assets, &mut picking, left).unwrap());
+    let last = position(&world, pick(&world, &assets, &mut picking, right).unwrap());
+    assert_eq!(first, Vec3::new(first.x, -1.5, 0.0));
+    assert_eq!(first.x.abs(), 3.0);
+    assert_eq!(last, Vec3::new(-first.x, -1.5, 0.0));
+
+    //Skinned meshes are picked in their animated pose
+    let (world, assets) = skinned_scene(true);
+    let rest = render_to_image(&world, &assets, &mut [&mut base], WIDTH, HEIGHT);
+    let animators = world.get_all_components::<Animator>().unwrap();
+    let clip = assets
+        .get_by_id::<Mesh>(
+            world.get_all_components::<MeshComponent>().unwrap()[0]
+                .borrow()
+                .get_mesh_id()
+                .unwrap(),
+        )
+        .unwrap()
+        .borrow()
+        .get_animation("bend")
+        .unwrap();
+    animators[0].borrow_mut().play(clip);
+    animators[0].borrow_mut().seek(1.0);
+    let bent = render_to_image(&world, &assets, &mut [&mut base], WIDTH, HEIGHT);
+
+    let moved = rest
+        .chunks_exact(4)
+        .zip(bent.chunks_exact(4))
+        .position(|(r, b)| r[2] > 128 && b[2] < 128)
+        .expect("The animation must move the triangle");
+    let triangle = world.get_all_entities_with_component::<Animator>().unwrap()[0]
+        .borrow()
+        .get_id();
+    let mut picking = extensions::picking::Picking::default();
+    assert_eq!(pick(&world, &assets, &mut picking, moved), Some(triangle));
+}
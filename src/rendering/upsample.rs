@@ -0,0 +1,144 @@
+//! Composites the attachments of the extensions rendering at a different resolution, see
+//! [`super::graph`]
+use std::cell::RefCell;
+
+use crate::DEVICE;
+
+use super::{
+    fullscreen::{self, FullscreenPipeline},
+    hdr, msaa,
+};
+
+struct UpsampleState {
+    pipeline: FullscreenPipeline,
+    layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    //Color attachment the pipeline was created for
+    targets: (u32, wgpu::TextureFormat),
+}
+
+thread_local! {
+    static STATE: RefCell<Option<UpsampleState>> = const { RefCell::new(None) };
+}
+
+///Upsamples the `input` and blends it over the `output`
+pub(crate) fn composite(
+    encoder: &mut wgpu::CommandEncoder,
+    input: &wgpu::TextureView,
+    output: &wgpu::TextureView,
+) {
+    STATE.with_borrow_mut(|s| {
+        if s.as_ref()
+            .is_none_or(|s| s.targets != super::color_targets())
+        {
+            *s = Some(UpsampleState::new());
+        }
+        let state = s.as_ref().unwrap();
+
+        let bind_group = DEVICE
+            .get()
+            .unwrap()
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Upsample bind group"),
+                layout: &state.layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(input),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&state.sampler),
+                    },
+                ],
+            });
+
+        state.pipeline.draw(
+            encoder,
+            "Upsample pass",
+            output,
+            &[&bind_group],
+            wgpu::LoadOp::Load,
+        );
+    });
+}
+
+///Releases the upsampling pipeline
+pub(crate) fn release() {
+    STATE.set(None);
+}
+
+///Clears the `view` to transparent black
+pub(crate) fn clear(encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Scaled clear pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+}
+
+impl UpsampleState {
+    fn new() -> Self {
+        let device = DEVICE.get().unwrap();
+
+        let shader = fullscreen::compile("upsample.wgsl", include_str!("../shaders/upsample.wgsl"))
+            .expect("Failed to compile the upsample shader");
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Upsample bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline = FullscreenPipeline::new(
+            "Upsample pipeline",
+            &shader,
+            &[&layout],
+            wgpu::ColorTargetState {
+                format: hdr::color_format(),
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            },
+            msaa::multisample_state(),
+        );
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Upsample sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            pipeline,
+            layout,
+            sampler,
+            targets: super::color_targets(),
+        }
+    }
+}
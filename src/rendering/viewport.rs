@@ -0,0 +1,316 @@
+//! Fixed aspect ratio rendering
+//!
+//! When a fixed aspect ratio is set, the scene is rendered into the largest centered rectangle of
+//! the window with that aspect ratio, the rest of the window is covered by black bars. Built-in
+//! extensions and cameras use the viewport automatically, custom extensions should call
+//! [`apply`] on their render passes and position their UI inside of [`current`].
+//!
+//! The cursor position relative to the viewport is returned by
+//! [`crate::input::viewport_cursor_position`].
+use std::{
+    cell::{Cell, RefCell},
+    sync::RwLock,
+};
+
+use crate::{assets::shader, math::Vec2, DEVICE, FORMAT, RESOLUTION};
+
+static ASPECT: RwLock<Option<f32>> = RwLock::new(None);
+
+thread_local! {
+    static PIPELINE: RefCell<Option<wgpu::RenderPipeline>> = const { RefCell::new(None) };
+    //Size of the attachments of the current pass relative to the frame buffer
+    static SCALE: Cell<(f32, f32)> = const { Cell::new((1.0, 1.0)) };
+}
+
+///Area of the window the scene is rendered into, in pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    ///Distance from the left edge of the window
+    pub x: f32,
+    ///Distance from the top edge of the window
+    pub y: f32,
+    ///Width of the viewport
+    pub width: f32,
+    ///Height of the viewport
+    pub height: f32,
+}
+
+///Part of the viewport, in fractions of its size
+///
+///Used for rendering multiple cameras into the same frame, see
+///[`Camera::rect`](crate::components::camera::Camera::rect)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    ///Distance from the left edge of the viewport
+    pub x: f32,
+    ///Distance from the top edge of the viewport
+    pub y: f32,
+    ///Width of the rectangle
+    pub width: f32,
+    ///Height of the rectangle
+    pub height: f32,
+}
+
+impl Default for Rect {
+    fn default() -> Self {
+        Self::FULL
+    }
+}
+
+impl Rect {
+    ///The whole viewport
+    pub const FULL: Self = Self::new(0.0, 0.0, 1.0, 1.0);
+
+    ///Creates a new rectangle
+    #[must_use]
+    pub const fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+impl Viewport {
+    ///Returns the aspect ratio of the viewport
+    #[must_use]
+    pub fn aspect(&self) -> f32 {
+        self.width / self.height
+    }
+
+    ///Returns the part of the viewport covered by the `rect`, clamped to the viewport
+    #[must_use]
+    pub fn sub(&self, rect: Rect) -> Self {
+        let left = rect.x.clamp(0.0, 1.0);
+        let top = rect.y.clamp(0.0, 1.0);
+        let right = (rect.x + rect.width).clamp(left, 1.0);
+        let bottom = (rect.y + rect.height).clamp(top, 1.0);
+        Self {
+            x: self.width.mul_add(left, self.x),
+            y: self.height.mul_add(top, self.y),
+            width: self.width * (right - left),
+            height: self.height * (bottom - top),
+        }
+    }
+
+    ///Converts a position in the window to a position in the viewport, returns `None` if the
+    ///position is outside of the viewport, i.e. on the bars
+    #[must_use]
+    pub fn to_viewport(&self, position: Vec2) -> Option<Vec2> {
+        let p = Vec2::new(position.x - self.x, position.y - self.y);
+        (p.x >= 0.0 && p.y >= 0.0 && p.x < self.width && p.y < self.height).then_some(p)
+    }
+
+    ///Returns the rectangles of the bars in pixels as x, y, width, height
+    pub(crate) fn bars(&self, width: u32, height: u32) -> [(u32, u32, u32, u32); 2] {
+        let x = self.x as u32;
+        let y = self.y as u32;
+        if x > 0 {
+            //Pillarbox
+            let right = (self.x + self.width) as u32;
+            [(0, 0, x, height), (right, 0, width - right, height)]
+        } else {
+            //Letterbox
+            let bottom = (self.y + self.height) as u32;
+            [(0, 0, width, y), (0, bottom, width, height - bottom)]
+        }
+    }
+}
+
+///Sets the aspect ratio, width divided by height, the scene is rendered with
+///
+///`None` uses the whole window
+///
+///# Panics
+///Panics if the aspect ratio is not positive
+pub fn set_fixed_aspect(aspect: Option<f32>) {
+    if let Some(a) = aspect {
+        assert!(a > 0.0, "Aspect ratio must be positive");
+    }
+    *ASPECT.write().unwrap() = aspect;
+}
+
+///Returns the fixed aspect ratio, or `None` if the whole window is used
+#[must_use]
+pub fn get_fixed_aspect() -> Option<f32> {
+    *ASPECT.read().unwrap()
+}
+
+///Returns the viewport for the current resolution
+#[must_use]
+pub fn current() -> Viewport {
+    let resolution = *RESOLUTION.read().unwrap();
+    calculate(
+        resolution.width as f32,
+        resolution.height as f32,
+        get_fixed_aspect(),
+    )
+}
+
+///Returns the aspect ratio used for the projection of the cameras
+#[must_use]
+pub fn aspect() -> f32 {
+    current().aspect()
+}
+
+///Calculates the largest centered viewport with the aspect ratio that fits into the window
+pub(crate) fn calculate(width: f32, height: f32, aspect: Option<f32>) -> Viewport {
+    let full = Viewport {
+        x: 0.0,
+        y: 0.0,
+        width,
+        height,
+    };
+    let Some(aspect) = aspect else {
+        return full;
+    };
+    if width <= 0.0 || height <= 0.0 {
+        return full;
+    }
+
+    if width / height > aspect {
+        let w = (height * aspect).round();
+        Viewport {
+            x: ((width - w) / 2.0).floor(),
+            y: 0.0,
+            width: w,
+            height,
+        }
+    } else {
+        let h = (width / aspect).round();
+        Viewport {
+            x: 0.0,
+            y: ((height - h) / 2.0).floor(),
+            width,
+            height: h,
+        }
+    }
+}
+
+///Restricts the render pass to the viewport
+///
+///Takes the resolution of the extension into account, see
+///[`crate::rendering::extensions::RenderingExtension::get_resolution`]
+pub fn apply(render_pass: &mut wgpu::RenderPass<'_>) {
+    let v = current();
+    let (x, y) = SCALE.get();
+    render_pass.set_viewport(v.x * x, v.y * y, v.width * x, v.height * y, 0.0, 1.0);
+}
+
+///Restricts the render pass to the `rect` of the viewport, drawing outside of it is discarded
+pub fn apply_rect(render_pass: &mut wgpu::RenderPass<'_>, rect: Rect) {
+    let v = current().sub(rect);
+    let (x, y) = SCALE.get();
+    let resolution = *RESOLUTION.read().unwrap();
+    let (width, height) = (
+        (resolution.width as f32 * x) as u32,
+        (resolution.height as f32 * y) as u32,
+    );
+
+    render_pass.set_viewport(v.x * x, v.y * y, v.width * x, v.height * y, 0.0, 1.0);
+    let left = ((v.x * x) as u32).min(width);
+    let top = ((v.y * y) as u32).min(height);
+    render_pass.set_scissor_rect(
+        left,
+        top,
+        (((v.x + v.width) * x).ceil() as u32).min(width) - left,
+        (((v.y + v.height) * y).ceil() as u32).min(height) - top,
+    );
+}
+
+///Sets the size of the attachments of the following passes relative to the frame buffer
+pub(crate) fn set_scale(x: f32, y: f32) {
+    SCALE.set((x, y));
+}
+
+///Returns the size of the attachments of the current pass relative to the frame buffer
+pub(crate) fn scale() -> (f32, f32) {
+    SCALE.get()
+}
+
+///Covers the parts of the attachment outside of the viewport with black bars
+pub(crate) fn draw_bars(encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+    if get_fixed_aspect().is_none() {
+        return;
+    }
+    let resolution = *RESOLUTION.read().unwrap();
+    let viewport = current();
+    if viewport.width as u32 == resolution.width && viewport.height as u32 == resolution.height {
+        return;
+    }
+
+    PIPELINE.with_borrow_mut(|p| {
+        let pipeline = p.get_or_insert_with(create_pipeline);
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Letterbox pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(pipeline);
+
+        for (x, y, w, h) in viewport.bars(resolution.width, resolution.height) {
+            if w == 0 || h == 0 {
+                continue;
+            }
+            render_pass.set_scissor_rect(x, y, w, h);
+            render_pass.draw(0..3, 0..1);
+        }
+    });
+}
+
+///Releases the pipeline drawing the bars
+pub(crate) fn release() {
+    PIPELINE.set(None);
+}
+
+fn create_pipeline() -> wgpu::RenderPipeline {
+    let device = DEVICE.get().unwrap();
+
+    let shader = shader::compile("letterbox.wgsl", include_str!("../shaders/letterbox.wgsl"))
+        .expect("Failed to compile the letterbox shader");
+
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Letterbox pipeline layout"),
+        bind_group_layouts: &[],
+        push_constant_ranges: &[],
+    });
+
+    crate::errors::scoped("Letterbox pipeline", || {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Letterbox pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vertex",
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fragment",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: *FORMAT.get().unwrap(),
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            multiview: None,
+        })
+    })
+}
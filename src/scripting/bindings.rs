@@ -0,0 +1,233 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::{Rc, Weak},
+};
+
+use rhai::{Dynamic, Engine, EvalAltResult, FLOAT, INT};
+use winit::{event::MouseButton, keyboard::KeyCode};
+
+use crate::{
+    components::{metadata::Value, transform::Transform, Metadata},
+    ecs::{Entity, World},
+    input::{self, KeyState},
+    math::{Vec2, Vec3, Vector},
+    time,
+};
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+thread_local! {
+    //World the scripts are currently run for, only set during `scripting::run`
+    static WORLD: Cell<*const World> = const { Cell::new(std::ptr::null()) };
+}
+
+///Makes the `world` accessible to the scripts until the guard is dropped
+pub(super) struct WorldGuard;
+
+impl WorldGuard {
+    pub(super) fn new(world: &World) -> Self {
+        WORLD.with(|w| w.set(world));
+        Self
+    }
+}
+
+impl Drop for WorldGuard {
+    fn drop(&mut self) {
+        WORLD.with(|w| w.set(std::ptr::null()));
+    }
+}
+
+fn with_world<T>(f: impl FnOnce(&World) -> T) -> ScriptResult<T> {
+    let world = WORLD.with(Cell::get);
+    if world.is_null() {
+        return Err("The world is only accessible while the scripts are running".into());
+    }
+    //The guard clears the pointer before the world can be dropped
+    Ok(f(unsafe { &*world }))
+}
+
+///Entity as seen by the scripts
+#[derive(Clone)]
+pub(super) struct ScriptEntity(pub(super) Weak<RefCell<Entity>>);
+
+impl ScriptEntity {
+    fn entity(&self) -> ScriptResult<Rc<RefCell<Entity>>> {
+        self.0
+            .upgrade()
+            .ok_or_else(|| "The entity no longer exists".into())
+    }
+
+    fn id(&mut self) -> ScriptResult<INT> {
+        Ok(self.entity()?.borrow().get_id() as INT)
+    }
+
+    fn transform(&self, f: impl FnOnce(&mut Transform)) -> ScriptResult<()> {
+        let transform = self
+            .entity()?
+            .borrow()
+            .get_component::<Transform>()
+            .ok_or("The entity has no transform")?;
+        f(&mut transform.borrow_mut());
+        Ok(())
+    }
+
+    fn get_meta(&mut self, key: &str) -> ScriptResult<Dynamic> {
+        let Some(metadata) = self.entity()?.borrow().get_component::<Metadata>() else {
+            return Ok(Dynamic::UNIT);
+        };
+        let metadata = metadata.borrow();
+        Ok(match metadata.get(key) {
+            Some(Value::Bool(v)) => Dynamic::from(*v),
+            Some(Value::Int(v)) => Dynamic::from(*v),
+            Some(Value::Float(v)) => Dynamic::from(*v as FLOAT),
+            Some(Value::String(v)) => Dynamic::from(v.clone()),
+            None => Dynamic::UNIT,
+        })
+    }
+
+    fn set_meta(&mut self, key: &str, value: Dynamic) -> ScriptResult<()> {
+        let metadata = self
+            .entity()?
+            .borrow()
+            .get_component::<Metadata>()
+            .ok_or("The entity has no metadata")?;
+        let value = if let Ok(v) = value.as_bool() {
+            Value::Bool(v)
+        } else if let Ok(v) = value.as_int() {
+            Value::Int(v)
+        } else if let Ok(v) = value.as_float() {
+            Value::Float(v.into())
+        } else if value.is_string() {
+            Value::String(value.into_string()?)
+        } else {
+            return Err(format!("Metadata can not store {}", value.type_name()).into());
+        };
+        metadata.borrow_mut().set(key, value);
+        Ok(())
+    }
+}
+
+fn entity_by_id(world: &World, id: INT) -> Dynamic {
+    world
+        .get_entity_by_id(id as u64)
+        .map_or(Dynamic::UNIT, |e| {
+            Dynamic::from(ScriptEntity(Rc::downgrade(&e)))
+        })
+}
+
+///Returns the key with the same name as the variant of [`KeyCode`]
+fn key_code(name: &str) -> ScriptResult<KeyCode> {
+    input::key_from_name(name).ok_or_else(|| format!("Unknown key {name}").into())
+}
+
+fn mouse_button(name: &str) -> ScriptResult<MouseButton> {
+    match name {
+        "Left" => Ok(MouseButton::Left),
+        "Right" => Ok(MouseButton::Right),
+        "Middle" => Ok(MouseButton::Middle),
+        _ => Err(format!("Unknown mouse button {name}").into()),
+    }
+}
+
+///Creates an engine with all the bindings registered
+#[allow(clippy::too_many_lines)]
+pub(super) fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.on_print(|s| log::info!(target: "script", "{s}"));
+    engine.on_debug(|s, _, _| log::debug!(target: "script", "{s}"));
+
+    engine
+        .register_type_with_name::<Vec3>("Vec3")
+        .register_fn("vec3", Vec3::new)
+        .register_get_set("x", |v: &mut Vec3| v.x, |v: &mut Vec3, x: FLOAT| v.x = x)
+        .register_get_set("y", |v: &mut Vec3| v.y, |v: &mut Vec3, y: FLOAT| v.y = y)
+        .register_get_set("z", |v: &mut Vec3| v.z, |v: &mut Vec3, z: FLOAT| v.z = z)
+        .register_fn("+", |a: Vec3, b: Vec3| a + b)
+        .register_fn("-", |a: Vec3, b: Vec3| a - b)
+        .register_fn("*", |a: Vec3, b: FLOAT| a * b)
+        .register_fn("length", |v: &mut Vec3| v.length())
+        .register_fn("normalize", |v: &mut Vec3| v.normalized())
+        .register_fn("dot", |a: Vec3, b: Vec3| a.dot_product(&b))
+        .register_fn("to_string", |v: &mut Vec3| v.to_string())
+        .register_fn("to_debug", |v: &mut Vec3| format!("{v:?}"));
+
+    engine
+        .register_type_with_name::<Vec2>("Vec2")
+        .register_fn("vec2", Vec2::new)
+        .register_get_set("x", |v: &mut Vec2| v.x, |v: &mut Vec2, x: FLOAT| v.x = x)
+        .register_get_set("y", |v: &mut Vec2| v.y, |v: &mut Vec2, y: FLOAT| v.y = y)
+        .register_fn("to_string", |v: &mut Vec2| v.to_string())
+        .register_fn("to_debug", |v: &mut Vec2| format!("{v:?}"));
+
+    //Entity
+    engine
+        .register_type_with_name::<ScriptEntity>("Entity")
+        .register_get("id", ScriptEntity::id)
+        .register_get("alive", |e: &mut ScriptEntity| e.0.strong_count() > 0)
+        .register_get_set(
+            "position",
+            |e: &mut ScriptEntity| {
+                let mut v = Vec3::default();
+                e.transform(|t| v = t.position).map(|()| v)
+            },
+            |e: &mut ScriptEntity, v: Vec3| e.transform(|t| t.position = v),
+        )
+        .register_get_set(
+            "rotation",
+            |e: &mut ScriptEntity| {
+                let mut v = Vec3::default();
+                e.transform(|t| v = t.rotation).map(|()| v)
+            },
+            |e: &mut ScriptEntity, v: Vec3| e.transform(|t| t.rotation = v),
+        )
+        .register_get_set(
+            "scale",
+            |e: &mut ScriptEntity| {
+                let mut v = Vec3::default();
+                e.transform(|t| v = t.scale).map(|()| v)
+            },
+            |e: &mut ScriptEntity, v: Vec3| e.transform(|t| t.scale = v),
+        )
+        .register_fn("get_meta", ScriptEntity::get_meta)
+        .register_fn("set_meta", ScriptEntity::set_meta);
+
+    //World
+    engine
+        .register_fn("entity_count", || {
+            with_world(|w| w.get_entity_count() as INT)
+        })
+        .register_fn("get_entity", |id: INT| with_world(|w| entity_by_id(w, id)))
+        .register_fn("entities", || {
+            with_world(|w| {
+                w.get_entity_ids()
+                    .into_iter()
+                    .map(|id| entity_by_id(w, id as INT))
+                    .collect::<rhai::Array>()
+            })
+        });
+
+    //Input
+    engine
+        .register_fn("key_down", |key: &str| {
+            key_code(key).map(|k| input::key(k) == KeyState::Down)
+        })
+        .register_fn("key_pressed", |key: &str| {
+            key_code(key).map(|k| matches!(input::key(k), KeyState::Down | KeyState::Pressed))
+        })
+        .register_fn("key_up", |key: &str| {
+            key_code(key).map(|k| input::key(k) == KeyState::Up)
+        })
+        .register_fn("mouse_pressed", |button: &str| {
+            mouse_button(button)
+                .map(|b| matches!(input::mouse_btn(b), KeyState::Down | KeyState::Pressed))
+        })
+        .register_fn("cursor_position", input::cursor_position)
+        .register_fn("cursor_delta", input::cursor_delta);
+
+    //Time
+    engine
+        .register_fn("delta_time", time::delta_time)
+        .register_fn("elapsed_time", time::elapsed_time);
+
+    engine
+}
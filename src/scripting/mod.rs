@@ -0,0 +1,248 @@
+//! Gameplay scripts written in [Rhai](https://rhai.rs), requires the `scripting` feature
+//!
+//! A script is attached to an entity with [`ScriptComponent`] and run by calling [`run`] every
+//! frame, after [`World::update`]. The script may define the following functions, both optional,
+//! taking the entity the script is attached to
+//! - `start(entity)`, called once when the script is loaded
+//! - `update(entity)`, called every frame
+//!
+//! State that persists between the calls is stored in the fields of `this`, which is an object
+//! map private to each script. The state is reset when the script is reloaded.
+//!
+//! # Bindings
+//! - `vec3(x, y, z)` and `vec2(x, y)`, with `x`, `y`, `z` properties, `+`, `-`, `*` by a number,
+//!   `length()`, `normalize()` and `dot(other)`
+//! - Entity properties `id`, `alive`, `position`, `rotation` and `scale`, the last three access
+//!   the [`Transform`](crate::components::transform::Transform) of the entity
+//! - Entity methods `get_meta(key)` and `set_meta(key, value)`, accessing the
+//!   [`Metadata`](crate::components::Metadata) of the entity
+//! - World functions `entity_count()`, `entities()` and `get_entity(id)`, the last one returns
+//!   `()` if the entity doesn't exist
+//! - Time functions `delta_time()` and `elapsed_time()`
+//! - Input functions `key_down(key)`, `key_pressed(key)` and `key_up(key)`, where the key is the
+//!   name of the [`KeyCode`](winit::keyboard::KeyCode) variant, for example `"KeyW"`,
+//!   `mouse_pressed(button)` with `"Left"`, `"Right"` or `"Middle"`, `cursor_position()` and
+//!   `cursor_delta()`
+//! - `print` and `debug` write to the log
+//!
+//! A script that fails to compile or run is logged and disabled until it's reloaded.
+//!
+//! # Examples
+//!```
+//!# use lunar_engine::{components::transform::Transform, ecs::{EntityBuilder, World}};
+//!use lunar_engine::scripting::{self, ScriptComponent};
+//!
+//!let mut world = World::new();
+//!world.add_entity(
+//!    EntityBuilder::new()
+//!        .add_component::<Transform>()
+//!        .create_component(|| {
+//!            ScriptComponent::new(
+//!                r#"
+//!                fn start(entity) {
+//!                    this.speed = 2.0;
+//!                }
+//!
+//!                fn update(entity) {
+//!                    entity.position += vec3(0.0, this.speed * delta_time(), 0.0);
+//!                }
+//!                "#,
+//!            )
+//!        })
+//!        .create()
+//!        .unwrap(),
+//!);
+//!
+//!//Every frame
+//!world.update();
+//!scripting::run(&world);
+//!```
+use std::{
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::SystemTime,
+};
+
+use lunar_engine_derive::as_any;
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, Scope, AST};
+
+use crate::ecs::{Component, World};
+
+use bindings::{ScriptEntity, WorldGuard};
+
+mod bindings;
+#[cfg(test)]
+mod tests;
+
+thread_local! {
+    static ENGINE: Engine = bindings::engine();
+}
+
+///Component running a script on its entity, see [`self`]
+pub struct ScriptComponent {
+    source: String,
+    //File the script was loaded from and its modification time
+    file: Option<(PathBuf, Option<SystemTime>)>,
+    ast: Option<AST>,
+    state: Dynamic,
+    error: Option<String>,
+}
+
+impl Component for ScriptComponent {
+    #[as_any]
+
+    fn mew() -> Self
+    where
+        Self: Sized,
+    {
+        Self::new("")
+    }
+}
+
+impl ScriptComponent {
+    ///Creates a component running the script
+    #[must_use]
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            file: None,
+            ast: None,
+            state: Dynamic::from_map(rhai::Map::new()),
+            error: None,
+        }
+    }
+
+    ///Creates a component running the script from the file, the script is reloaded when the file
+    ///changes
+    ///
+    ///# Errors
+    ///Returns an error if the file can't be read
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, std::io::Error> {
+        let path = path.as_ref().to_path_buf();
+        let modified = modified(&path);
+        let mut script = Self::new(std::fs::read_to_string(&path)?);
+        script.file = Some((path, modified));
+        Ok(script)
+    }
+
+    ///Replaces the script, it's loaded again before the next update
+    pub fn set_source(&mut self, source: impl Into<String>) {
+        self.source = source.into();
+        self.unload();
+    }
+
+    ///Returns the source of the script
+    #[must_use]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    ///Returns the error that disabled the script, if any
+    #[must_use]
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    fn unload(&mut self) {
+        self.ast = None;
+        self.state = Dynamic::from_map(rhai::Map::new());
+        self.error = None;
+    }
+
+    //Rereads the file of the script if it changed
+    fn reload_file(&mut self) {
+        let Some((path, loaded)) = &mut self.file else {
+            return;
+        };
+        let modified = modified(path);
+        if modified.is_none() || modified == *loaded {
+            return;
+        }
+        *loaded = modified;
+
+        match std::fs::read_to_string(&*path) {
+            Ok(source) => {
+                log::info!("Reloaded script {}", path.display());
+                self.set_source(source);
+            }
+            Err(e) => log::error!("Failed to reload script {}: {e}", path.display()),
+        }
+    }
+
+    fn run(&mut self, engine: &Engine, entity: &ScriptEntity) {
+        self.reload_file();
+        if self.error.is_some() {
+            return;
+        }
+
+        let result = if let Some(ast) = &self.ast {
+            call(engine, ast, &mut self.state, "update", entity)
+        } else {
+            match engine.compile(&self.source) {
+                Ok(ast) => {
+                    let ast = self.ast.insert(ast);
+                    call(engine, ast, &mut self.state, "start", entity)
+                        .and_then(|()| call(engine, ast, &mut self.state, "update", entity))
+                }
+                Err(e) => Err(e.into()),
+            }
+        };
+
+        if let Err(e) = result {
+            let name = self
+                .file
+                .as_ref()
+                .map_or_else(|| "script".to_owned(), |(p, _)| p.display().to_string());
+            log::error!("Error in {name}, the script is disabled until it's reloaded: {e}");
+            self.error = Some(e.to_string());
+        }
+    }
+}
+
+//Calls the function of the script if it's defined
+fn call(
+    engine: &Engine,
+    ast: &AST,
+    state: &mut Dynamic,
+    name: &str,
+    entity: &ScriptEntity,
+) -> Result<(), Box<EvalAltResult>> {
+    if !ast
+        .iter_functions()
+        .any(|f| f.name == name && f.params.len() == 1)
+    {
+        return Ok(());
+    }
+
+    let options = CallFnOptions::new().eval_ast(false).bind_this_ptr(state);
+    engine
+        .call_fn_with_options::<Dynamic>(options, &mut Scope::new(), ast, name, (entity.clone(),))
+        .map(drop)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+///Runs the scripts of all the entities of the world, should be called every frame after
+///[`World::update`]
+pub fn run(world: &World) {
+    let Some(entities) = world.get_all_entities_with_component::<ScriptComponent>() else {
+        return;
+    };
+    let _guard = WorldGuard::new(world);
+    let _span = crate::timeline::span_with_category("Scripts", "engine");
+
+    ENGINE.with(|engine| {
+        for entity in entities {
+            //The entity must not stay borrowed, so that the script can access it
+            let script = entity.borrow().get_component::<ScriptComponent>();
+            let Some(script) = script else {
+                continue;
+            };
+            script
+                .borrow_mut()
+                .run(engine, &ScriptEntity(Rc::downgrade(&entity)));
+        }
+    });
+}
@@ -0,0 +1,83 @@
+use crate::{
+    components::{transform::Transform, Metadata},
+    ecs::{EntityBuilder, World},
+    math::Vec3,
+};
+
+use super::{run, ScriptComponent};
+
+fn world_with_script(source: &str) -> World {
+    let mut world = World::new();
+    let source = source.to_owned();
+    world.add_entity(
+        EntityBuilder::new()
+            .add_component::<Transform>()
+            .add_component::<Metadata>()
+            .create_component(move || ScriptComponent::new(source))
+            .create()
+            .unwrap(),
+    );
+    world
+}
+
+#[test]
+fn script_update_test() {
+    let world = world_with_script(
+        r"
+        fn update(entity) {
+            entity.position = entity.position + vec3(1.0, 0.0, 0.0);
+        }
+        ",
+    );
+
+    run(&world);
+    run(&world);
+
+    let transform = world.get_all_components::<Transform>().unwrap()[0].clone();
+    assert_eq!(transform.borrow().position, Vec3::new(2.0, 0.0, 0.0));
+}
+
+#[test]
+fn script_state_test() {
+    let world = world_with_script(
+        r#"
+        fn start(entity) {
+            this.frames = 0;
+        }
+
+        fn update(entity) {
+            this.frames += 1;
+            entity.set_meta("frames", this.frames);
+            entity.set_meta("count", entity_count());
+        }
+        "#,
+    );
+
+    run(&world);
+    run(&world);
+    run(&world);
+
+    let metadata = world.get_all_components::<Metadata>().unwrap()[0].clone();
+    assert_eq!(metadata.borrow().get_int("frames"), Some(3));
+    assert_eq!(metadata.borrow().get_int("count"), Some(1));
+}
+
+#[test]
+fn script_error_test() {
+    let world = world_with_script(
+        r"
+        fn update(entity) {
+            entity.position = 5;
+        }
+        ",
+    );
+
+    run(&world);
+
+    let script = world.get_all_components::<ScriptComponent>().unwrap()[0].clone();
+    assert!(script.borrow().error().is_some());
+
+    script.borrow_mut().set_source("fn update(entity) {}");
+    run(&world);
+    assert!(script.borrow().error().is_none());
+}
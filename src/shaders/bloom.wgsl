@@ -0,0 +1,57 @@
+struct Parameters {
+  //Threshold, intensity
+  settings: vec4<f32>,
+  //Size of a pixel of the blurred image and of the input in uv
+  texel: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var input: texture_2d<f32>;
+@group(0) @binding(1)
+var input_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> parameters: Parameters;
+//Blurred bright parts, only read by the composite pass
+@group(0) @binding(3)
+var blurred: texture_2d<f32>;
+
+//Gaussian kernel, the center and one side
+const WEIGHTS = array<f32, 5>(0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+fn bright(uv: vec2<f32>) -> vec3<f32> {
+    let color = textureSampleLevel(input, input_sampler, uv, 0.0).rgb;
+    let brightness = max(color.r, max(color.g, color.b));
+    return color * max(brightness - parameters.settings.x, 0.0) / max(brightness, 0.0001);
+}
+
+//Extracts the parts above the threshold and blurs them horizontally at half resolution
+@fragment
+fn bright_horizontal(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let step = vec2<f32>(parameters.texel.x, 0.0);
+    var weights = WEIGHTS;
+    var sum = bright(in.uv) * weights[0];
+    for (var i = 1; i < 5; i++) {
+        sum += (bright(in.uv + step * f32(i)) + bright(in.uv - step * f32(i))) * weights[i];
+    }
+    return vec4<f32>(sum, 1.0);
+}
+
+@fragment
+fn vertical(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let step = vec2<f32>(0.0, parameters.texel.y);
+    var weights = WEIGHTS;
+    var sum = textureSampleLevel(input, input_sampler, in.uv, 0.0).rgb * weights[0];
+    for (var i = 1; i < 5; i++) {
+        sum += (textureSampleLevel(input, input_sampler, in.uv + step * f32(i), 0.0).rgb
+            + textureSampleLevel(input, input_sampler, in.uv - step * f32(i), 0.0).rgb) * weights[i];
+    }
+    return vec4<f32>(sum, 1.0);
+}
+
+//Adds the blurred bright parts to the image
+@fragment
+fn composite(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let color = textureSampleLevel(input, input_sampler, in.uv, 0.0);
+    let glow = textureSampleLevel(blurred, input_sampler, in.uv, 0.0).rgb;
+    return vec4<f32>(color.rgb + glow * parameters.settings.y, color.a);
+}
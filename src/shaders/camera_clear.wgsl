@@ -0,0 +1,20 @@
+struct Clear {
+    color: vec4<f32>,
+    //Depth clear value in x
+    depth: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> clear: Clear;
+
+//Single triangle covering the whole viewport
+@vertex
+fn vertex(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, clear.depth.x, 1.0);
+}
+
+@fragment
+fn fragment() -> @location(0) vec4<f32> {
+    return clear.color;
+}
@@ -0,0 +1,128 @@
+struct Globals {
+  //Size of the viewport in pixels
+  size: vec4<f32>,
+}
+
+struct Shape {
+  //Boxes and glyphs: center, half size, triangles: first and second corner
+  @location(0) rect: vec4<f32>,
+  //Boxes and glyphs: direction of the x axis, corner radius, triangles: third corner, last is
+  //the kind
+  @location(1) params: vec4<f32>,
+  @location(2) color: vec4<f32>,
+  //Top left and bottom right corners of the glyph in the atlas
+  @location(3) uv: vec4<f32>,
+}
+
+struct Output {
+  @builtin(position) position: vec4<f32>,
+  //Position in the viewport in pixels
+  @location(0) pixel: vec2<f32>,
+  //Position relative to the center of the box in pixels
+  @location(1) local: vec2<f32>,
+  @location(2) uv: vec2<f32>,
+  @location(3) @interpolate(flat) color: vec4<f32>,
+  @location(4) @interpolate(flat) rect: vec4<f32>,
+  @location(5) @interpolate(flat) params: vec4<f32>,
+}
+
+const BOX: u32 = 0u;
+const GLYPH: u32 = 1u;
+const TRIANGLE: u32 = 2u;
+
+@group(0) @binding(0)
+var<uniform> globals: Globals;
+@group(1) @binding(0)
+var atlas: texture_2d<f32>;
+@group(1) @binding(1)
+var atlas_sampler: sampler;
+
+@vertex
+fn vertex(@builtin(vertex_index) index: u32, shape: Shape) -> Output {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(-1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    let corner = corners[index];
+    let kind = u32(shape.params.w);
+
+    var pixel: vec2<f32>;
+    var local = vec2<f32>(0.0);
+    if kind == TRIANGLE {
+        var points = array<vec2<f32>, 3>(shape.rect.xy, shape.rect.zw, shape.params.xy);
+        //The rest of the vertices are degenerate
+        let point = points[min(index, 2u)];
+        //Room for the antialiasing
+        let center = (points[0] + points[1] + points[2]) / 3.0;
+        let offset = point - center;
+        pixel = point + offset / max(length(offset), 0.0001) * 2.0;
+    } else {
+        //Glyphs are antialiased inside of their quad
+        let margin = select(1.0, 0.0, kind == GLYPH);
+        local = corner * (shape.rect.zw + margin);
+        let axis = shape.params.xy;
+        pixel = shape.rect.xy + axis * local.x + vec2<f32>(-axis.y, axis.x) * local.y;
+    }
+
+    var out: Output;
+    out.position = vec4<f32>(
+        pixel.x / globals.size.x * 2.0 - 1.0,
+        1.0 - pixel.y / globals.size.y * 2.0,
+        0.0,
+        1.0
+    );
+    out.pixel = pixel;
+    out.local = local;
+    out.uv = mix(shape.uv.xy, shape.uv.zw, corner * 0.5 + 0.5);
+    out.color = shape.color;
+    out.rect = shape.rect;
+    out.params = shape.params;
+    return out;
+}
+
+//Signed distance from the point to the line going through a and b, positive on the right
+fn edge(p: vec2<f32>, a: vec2<f32>, b: vec2<f32>) -> f32 {
+    let d = normalize(b - a);
+    let r = p - a;
+    return r.x * d.y - r.y * d.x;
+}
+
+@fragment
+fn fragment(in: Output) -> @location(0) vec4<f32> {
+    //Sampled outside of the branches, derivatives need uniform control flow, the atlas is stored
+    //flipped
+    let field = textureSample(atlas, atlas_sampler, vec2<f32>(in.uv.x, 1.0 - in.uv.y)).r;
+    let width = max(fwidth(field) * 0.5, 0.0001);
+
+    var coverage: f32;
+    switch u32(in.params.w) {
+        case GLYPH: {
+            //0.5 is the edge of the glyph
+            coverage = smoothstep(0.5 - width, 0.5 + width, field);
+        }
+        case TRIANGLE: {
+            let a = in.rect.xy;
+            let b = in.rect.zw;
+            let c = in.params.xy;
+            //The inside is on the same side of all the edges
+            let side = sign(edge(c, a, b));
+            let inside = min(
+                min(side * edge(in.pixel, a, b), side * edge(in.pixel, b, c)),
+                side * edge(in.pixel, c, a)
+            );
+            coverage = clamp(0.5 + inside, 0.0, 1.0);
+        }
+        case BOX, default: {
+            let radius = in.params.z;
+            let q = abs(in.local) - in.rect.zw + radius;
+            let d = length(max(q, vec2<f32>(0.0))) + min(max(q.x, q.y), 0.0) - radius;
+            coverage = clamp(0.5 - d, 0.0, 1.0);
+        }
+    }
+
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
+}
@@ -0,0 +1,23 @@
+struct Parameters {
+  //Offset of the red and blue channels in the corners in pixels
+  settings: vec4<f32>,
+  //Size of a pixel in uv
+  texel: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var input: texture_2d<f32>;
+@group(0) @binding(1)
+var input_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> parameters: Parameters;
+
+@fragment
+fn fragment(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    //Grows towards the edges
+    let offset = (in.uv - 0.5) * 2.0 * parameters.settings.x * parameters.texel.xy;
+    let color = textureSampleLevel(input, input_sampler, in.uv, 0.0);
+    let r = textureSampleLevel(input, input_sampler, in.uv + offset, 0.0).r;
+    let b = textureSampleLevel(input, input_sampler, in.uv - offset, 0.0).b;
+    return vec4<f32>(r, color.g, b, color.a);
+}
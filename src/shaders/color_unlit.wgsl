@@ -1,7 +1,14 @@
 @group(1)@binding(0)
 var<uniform> color: vec4<f32>;
 
+// Every output of the vertex shader must be consumed
 @fragment
-fn main(@location(0) uvs: vec2<f32>, @location(1) normal: vec3<f32>) -> @location(0) vec4<f32> {
+fn main(
+    @location(0) uvs: vec2<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) world_position: vec3<f32>,
+    @location(3) @interpolate(flat) receive_shadows: f32,
+    @location(4) @interpolate(flat) texture_index: u32,
+) -> @location(0) vec4<f32> {
     return color;
 }
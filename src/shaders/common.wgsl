@@ -0,0 +1,28 @@
+// Declarations shared by the engine shaders and the shader materials, `#include "common.wgsl"`
+
+struct VertexInput {
+    @location(0) position: vec4<f32>,
+    @location(1) uvs: vec2<f32>,
+    @location(2) normal: vec3<f32>,
+    @location(3) trans_0: vec4<f32>,
+    @location(4) trans_1: vec4<f32>,
+    @location(5) trans_2: vec4<f32>,
+    @location(6) trans_3: vec4<f32>,
+    @location(7) normal_0: vec3<f32>,
+    @location(8) normal_1: vec3<f32>,
+    @location(9) normal_2: vec3<f32>,
+    @location(11) receive_shadows: f32,
+    @location(12) texture_index: u32,
+}
+
+// Output of the vertex shader, taken as the input of the fragment shader
+struct VertexOutput {
+    @location(0) tex_coord: vec2<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) world_position: vec3<f32>,
+    @location(3) @interpolate(flat) receive_shadows: f32,
+    @location(4) @interpolate(flat) texture_index: u32,
+    @builtin(position) position: vec4<f32>
+}
+
+@group(0) @binding(0) var<uniform> camera: mat4x4<f32>;
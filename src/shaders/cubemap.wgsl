@@ -0,0 +1,58 @@
+const PI: f32 = 3.14159265;
+
+struct Face {
+  //Index of the face, size of the face in pixels
+  face: vec4<u32>,
+}
+
+@group(0) @binding(0)
+var<uniform> face: Face;
+@group(0) @binding(1)
+var input: texture_2d<f32>;
+@group(0) @binding(2)
+var input_sampler: sampler;
+
+//Single triangle covering the whole face
+@vertex
+fn vertex(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+//Textures are flipped when loaded, so the first row is the bottom of the image
+fn flip(uv: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(uv.x, 1.0 - uv.y);
+}
+
+//Copies a texture into the face
+@fragment
+fn copy_face(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let uv = position.xy / f32(face.face.y);
+    return textureSample(input, input_sampler, flip(uv));
+}
+
+//Projects an equirectangular texture onto the face
+@fragment
+fn equirectangular(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let st = position.xy / f32(face.face.y) * 2.0 - 1.0;
+    let s = st.x;
+    let t = st.y;
+
+    var direction: vec3<f32>;
+    switch face.face.x {
+        case 0u: { direction = vec3<f32>(1.0, -t, -s); }
+        case 1u: { direction = vec3<f32>(-1.0, -t, s); }
+        case 2u: { direction = vec3<f32>(s, 1.0, t); }
+        case 3u: { direction = vec3<f32>(s, -1.0, -t); }
+        case 4u: { direction = vec3<f32>(s, -t, 1.0); }
+        default: { direction = vec3<f32>(-s, -t, -1.0); }
+    }
+    direction = normalize(direction);
+
+    let uv = vec2<f32>(
+        atan2(direction.z, direction.x) / (2.0 * PI) + 0.5,
+        acos(clamp(direction.y, -1.0, 1.0)) / PI,
+    );
+    //Sampled without mips, so the seam of the texture doesn't select a wrong mip
+    return textureSampleLevel(input, input_sampler, flip(uv), 0.0);
+}
@@ -0,0 +1,66 @@
+struct Output {
+  @builtin(position) position: vec4<f32>,
+  @location(0) normal: vec3<f32>,
+  @location(1) world_position: vec3<f32>,
+}
+
+struct Parameters {
+  //Color of the wireframe
+  color: vec4<f32>,
+  //Position of the camera and the distance shown as black in the depth mode
+  eye: vec4<f32>,
+  //Distance shown as white in the depth mode, the rest is padding
+  range: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: mat4x4<f32>;
+
+@group(1) @binding(0)
+var<uniform> parameters: Parameters;
+
+@vertex
+fn vertex(
+    @location(0) position: vec4<f32>,
+    @location(2) normal: vec3<f32>,
+    @location(3) trans_0: vec4<f32>,
+    @location(4) trans_1: vec4<f32>,
+    @location(5) trans_2: vec4<f32>,
+    @location(6) trans_3: vec4<f32>,
+    @location(7) normal_0: vec3<f32>,
+    @location(8) normal_1: vec3<f32>,
+    @location(9) normal_2: vec3<f32>,
+) -> Output {
+    let world = mat4x4<f32>(trans_0, trans_1, trans_2, trans_3);
+    let normal_mat = mat3x3<f32>(normal_0, normal_1, normal_2);
+    let world_position = world * position;
+
+    var out: Output;
+    out.position = camera * world_position;
+    out.world_position = world_position.xyz;
+    out.normal = normalize(normal_mat * normal);
+    return out;
+}
+
+@fragment
+fn wireframe(in: Output) -> @location(0) vec4<f32> {
+    return parameters.color;
+}
+
+@fragment
+fn normals(in: Output) -> @location(0) vec4<f32> {
+    return vec4<f32>(normalize(in.normal) * 0.5 + 0.5, 1.0);
+}
+
+@fragment
+fn depth(in: Output) -> @location(0) vec4<f32> {
+    let distance = length(in.world_position - parameters.eye.xyz);
+    let value = saturate((distance - parameters.eye.w) / (parameters.range.x - parameters.eye.w));
+    return vec4<f32>(vec3<f32>(value), 1.0);
+}
+
+@fragment
+fn overdraw(in: Output) -> @location(0) vec4<f32> {
+    //Added together for every layer
+    return vec4<f32>(0.1, 0.05, 0.025, 1.0);
+}
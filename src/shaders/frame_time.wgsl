@@ -0,0 +1,34 @@
+struct BarOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) color: vec4<f32>,
+}
+
+@vertex
+fn vertex(
+    @builtin(vertex_index) index: u32,
+    //min x, min y, max x, max y in clip space
+    @location(0) rect: vec4<f32>,
+    @location(1) color: vec4<f32>,
+) -> BarOutput {
+    //2 triangles
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    let corner = corners[index];
+
+    var res: BarOutput;
+    res.position = vec4<f32>(mix(rect.xy, rect.zw, corner), 0.0, 1.0);
+    res.color = color;
+
+    return res;
+}
+
+@fragment
+fn fragment(in: BarOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
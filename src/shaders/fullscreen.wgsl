@@ -0,0 +1,15 @@
+struct FullscreenOutput {
+  @builtin(position) position: vec4<f32>,
+  //Top left corner is 0, 0
+  @location(0) uv: vec2<f32>,
+}
+
+//Single triangle covering the whole screen
+@vertex
+fn vertex(@builtin(vertex_index) index: u32) -> FullscreenOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: FullscreenOutput;
+    out.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv = vec2<f32>(uv.x, 1.0 - uv.y);
+    return out;
+}
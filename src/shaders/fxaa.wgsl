@@ -0,0 +1,56 @@
+struct Parameters {
+  settings: vec4<f32>,
+  //Size of a pixel in uv
+  texel: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var input: texture_2d<f32>;
+@group(0) @binding(1)
+var input_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> parameters: Parameters;
+
+const SPAN_MAX: f32 = 8.0;
+const REDUCE_MUL: f32 = 0.125;
+const REDUCE_MIN: f32 = 0.0078125;
+
+fn luma(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.299, 0.587, 0.114));
+}
+
+fn sample(uv: vec2<f32>) -> vec4<f32> {
+    return textureSampleLevel(input, input_sampler, uv, 0.0);
+}
+
+//Fast approximate anti-aliasing, blurs along the edges found from the luma of the neighbours
+@fragment
+fn fragment(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let texel = parameters.texel.xy;
+    let color = sample(in.uv);
+
+    let nw = luma(sample(in.uv + vec2<f32>(-1.0, -1.0) * texel).rgb);
+    let ne = luma(sample(in.uv + vec2<f32>(1.0, -1.0) * texel).rgb);
+    let sw = luma(sample(in.uv + vec2<f32>(-1.0, 1.0) * texel).rgb);
+    let se = luma(sample(in.uv + vec2<f32>(1.0, 1.0) * texel).rgb);
+    let m = luma(color.rgb);
+
+    let luma_min = min(m, min(min(nw, ne), min(sw, se)));
+    let luma_max = max(m, max(max(nw, ne), max(sw, se)));
+
+    //Perpendicular to the gradient of the luma
+    var direction = vec2<f32>(-((nw + ne) - (sw + se)), (nw + sw) - (ne + se));
+    let reduce = max((nw + ne + sw + se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    let scale = 1.0 / (min(abs(direction.x), abs(direction.y)) + reduce);
+    direction = clamp(direction * scale, vec2<f32>(-SPAN_MAX), vec2<f32>(SPAN_MAX)) * texel;
+
+    let a = 0.5 * (sample(in.uv + direction * (1.0 / 3.0 - 0.5)).rgb
+        + sample(in.uv + direction * (2.0 / 3.0 - 0.5)).rgb);
+    let b = a * 0.5 + 0.25 * (sample(in.uv - direction * 0.5).rgb
+        + sample(in.uv + direction * 0.5).rgb);
+
+    //The wider blur crossed another edge
+    let luma_b = luma(b);
+    let rgb = select(b, a, luma_b < luma_min || luma_b > luma_max);
+    return vec4<f32>(rgb, color.a);
+}
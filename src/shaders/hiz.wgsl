@@ -0,0 +1,88 @@
+// Builds the hierarchical depth pyramid used for the occlusion culling, every texel of a level
+// stores the farthest depth of the texels of the level below it that it covers
+
+// 1 if the depth is reversed, so that the farthest depth is the smallest one
+@group(0) @binding(0) var<uniform> reversed: u32;
+@group(0) @binding(1) var depth: texture_depth_2d;
+@group(0) @binding(2) var depth_multisampled: texture_depth_multisampled_2d;
+@group(0) @binding(3) var source: texture_2d<f32>;
+@group(0) @binding(4) var destination: texture_storage_2d<r32float, write>;
+
+fn farthest(a: f32, b: f32) -> f32 {
+    return select(max(a, b), min(a, b), reversed == 1u);
+}
+
+// Depth nothing can be in front of
+fn nearest_depth() -> f32 {
+    return select(0.0, 1.0, reversed == 1u);
+}
+
+// New depth attachments are zero initialized, with the standard depth range nothing can be
+// occluded by texels at the near plane, so they are treated as empty
+fn attachment_depth(value: f32) -> f32 {
+    return select(value, 1.0, reversed == 0u && value == 0.0);
+}
+
+// Range of the source texels covered by the destination texel, the last texel of a level also
+// covers the last row and column of an odd sized source
+fn covered(coords: vec2<u32>, size: vec2<u32>, source_size: vec2<u32>) -> vec4<u32> {
+    let start = coords * 2u;
+    let end = select(min(start + 2u, source_size), source_size, coords == size - 1u);
+    return vec4<u32>(start, end);
+}
+
+@compute @workgroup_size(8, 8)
+fn copy_depth(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(destination);
+    if any(id.xy >= size) {
+        return;
+    }
+    let range = covered(id.xy, size, textureDimensions(depth));
+
+    var result = nearest_depth();
+    for (var y = range.y; y < range.w; y++) {
+        for (var x = range.x; x < range.z; x++) {
+            let sample = textureLoad(depth, vec2<u32>(x, y), 0);
+            result = farthest(result, attachment_depth(sample));
+        }
+    }
+    textureStore(destination, id.xy, vec4<f32>(result, 0.0, 0.0, 0.0));
+}
+
+@compute @workgroup_size(8, 8)
+fn copy_depth_multisampled(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(destination);
+    if any(id.xy >= size) {
+        return;
+    }
+    let range = covered(id.xy, size, textureDimensions(depth_multisampled));
+    let samples = textureNumSamples(depth_multisampled);
+
+    var result = nearest_depth();
+    for (var y = range.y; y < range.w; y++) {
+        for (var x = range.x; x < range.z; x++) {
+            for (var s = 0u; s < samples; s++) {
+                let sample = textureLoad(depth_multisampled, vec2<u32>(x, y), i32(s));
+                result = farthest(result, attachment_depth(sample));
+            }
+        }
+    }
+    textureStore(destination, id.xy, vec4<f32>(result, 0.0, 0.0, 0.0));
+}
+
+@compute @workgroup_size(8, 8)
+fn downsample(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(destination);
+    if any(id.xy >= size) {
+        return;
+    }
+    let range = covered(id.xy, size, textureDimensions(source));
+
+    var result = nearest_depth();
+    for (var y = range.y; y < range.w; y++) {
+        for (var x = range.x; x < range.z; x++) {
+            result = farthest(result, textureLoad(source, vec2<u32>(x, y), 0).r);
+        }
+    }
+    textureStore(destination, id.xy, vec4<f32>(result, 0.0, 0.0, 0.0));
+}
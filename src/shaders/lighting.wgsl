@@ -0,0 +1,150 @@
+//Lighting shared by the lit materials, see `lighting.rs`
+//Declares group 2, the material shader is appended after this file
+
+struct Clusters {
+  //View projection matrix of the camera
+  view_projection: mat4x4<f32>,
+  //Position of the camera, near plane
+  camera_position: vec4<f32>,
+  //Forward direction of the camera, far plane
+  camera_forward: vec4<f32>,
+  //Number of clusters along every axis, 0 if the clusters are not used
+  grid: vec4<u32>,
+}
+
+struct LocalLight {
+  //Position, range
+  position: vec4<f32>,
+  //Color multiplied by the intensity, 1 for spot lights
+  color: vec4<f32>,
+  //Direction of the spot light, cosine of the outer angle
+  direction: vec4<f32>,
+  //Cosine of the inner angle of the spot light
+  cone: vec4<f32>,
+}
+
+struct Light {
+  //View projection matrix of the light
+  matrix: mat4x4<f32>,
+  //Direction towards the light, shadow bias
+  direction: vec4<f32>,
+  //Color multiplied by the intensity, 1 if the shadow map is used
+  color: vec4<f32>,
+  ambient: vec4<f32>,
+  //Position of the main camera
+  camera: vec4<f32>,
+  clusters: Clusters,
+}
+
+//Light arriving at a surface
+struct Incoming {
+  //Direction towards the light
+  direction: vec3<f32>,
+  //Color of the light, attenuated by the distance
+  radiance: vec3<f32>,
+}
+
+@group(2)@binding(0)
+var<uniform> light: Light;
+@group(2)@binding(1)
+var shadow_map: texture_depth_2d;
+@group(2)@binding(2)
+var shadow_sampler: sampler_comparison;
+
+//Returns how much of the directional light reaches the position, using a 3x3 PCF filter
+fn shadow(world_position: vec3<f32>) -> f32 {
+    if light.color.w == 0.0 {
+        return 1.0;
+    }
+
+    let position = light.matrix * vec4<f32>(world_position, 1.0);
+    let uv = vec2<f32>(position.x * 0.5 + 0.5, 0.5 - position.y * 0.5);
+    let depth = position.z - light.direction.w;
+
+    //Outside of the shadow map
+    if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) || depth > 1.0 {
+        return 1.0;
+    }
+
+    let texel = 1.0 / vec2<f32>(textureDimensions(shadow_map));
+    var lit = 0.0;
+    for (var x = -1; x <= 1; x++) {
+        for (var y = -1; y <= 1; y++) {
+            let offset = vec2<f32>(f32(x), f32(y)) * texel;
+            lit += textureSampleCompareLevel(shadow_map, shadow_sampler, uv + offset, depth);
+        }
+    }
+    return lit / 9.0;
+}
+
+//Returns the light of the directional light arriving at the position, shadows are only applied
+//if `receive_shadows` is not 0
+fn directional_light(world_position: vec3<f32>, receive_shadows: f32) -> Incoming {
+    var lit = 1.0;
+    if receive_shadows != 0.0 {
+        lit = shadow(world_position);
+    }
+    return Incoming(light.direction.xyz, light.color.rgb * lit);
+}
+
+//Returns the index of the cluster containing the position
+fn cluster(world_position: vec3<f32>) -> u32 {
+    let grid = light.clusters.grid;
+
+    let clip = light.clusters.view_projection * vec4<f32>(world_position, 1.0);
+    let tile = clamp(
+        (clip.xy / clip.w * 0.5 + 0.5) * vec2<f32>(grid.xy),
+        vec2<f32>(0.0),
+        vec2<f32>(grid.xy - 1u),
+    );
+
+    //Slices are distributed exponentially between the near and the far plane
+    let near = light.clusters.camera_position.w;
+    let far = light.clusters.camera_forward.w;
+    let depth = dot(
+        world_position - light.clusters.camera_position.xyz,
+        light.clusters.camera_forward.xyz,
+    );
+    let slice = clamp(
+        log(max(depth, near) / near) / log(far / near) * f32(grid.z),
+        0.0,
+        f32(grid.z - 1u),
+    );
+
+    return (u32(slice) * grid.y + u32(tile.y)) * grid.x + u32(tile.x);
+}
+
+//Returns the offset of the first light of the cluster containing the position, and the number of
+//lights in it
+fn cluster_lights(world_position: vec3<f32>) -> vec2<u32> {
+    if light.clusters.grid.x == 0u {
+        return vec2<u32>(0u);
+    }
+
+    let index = cluster(world_position);
+    let entry = clusters[index / 4u][index % 4u];
+    return vec2<u32>(entry >> 8u, entry & 0xffu);
+}
+
+//Returns the light of the point or spot light at the `index` of the light indices arriving at the
+//position
+fn local_light(index: u32, world_position: vec3<f32>) -> Incoming {
+    let source = local_lights[light_indices[index / 4u][index % 4u]];
+
+    let to_light = source.position.xyz - world_position;
+    let distance = length(to_light);
+    let direction = to_light / max(distance, 0.0001);
+
+    //Inverse square falloff, smoothly reaching 0 at the range
+    let window = saturate(1.0 - pow(distance / source.position.w, 4.0));
+    var attenuation = window * window / max(distance * distance, 0.01);
+    if source.color.w == 1.0 {
+        attenuation *= smoothstep(
+            source.direction.w,
+            source.cone.x,
+            dot(-direction, source.direction.xyz),
+        );
+    }
+
+    return Incoming(direction, source.color.rgb * attenuation);
+}
@@ -0,0 +1,8 @@
+//Buffers of the point and spot lights, see `lighting.rs`
+@group(2)@binding(3)
+var<storage, read> local_lights: array<LocalLight>;
+//Offset of the first light index shifted by 8 bits, number of lights in the lowest 8 bits
+@group(2)@binding(4)
+var<storage, read> clusters: array<vec4<u32>>;
+@group(2)@binding(5)
+var<storage, read> light_indices: array<vec4<u32>>;
@@ -0,0 +1,9 @@
+//Buffers of the point and spot lights, used when storage buffers are not available
+//The sizes must match the capacities in `lighting.rs`
+@group(2)@binding(3)
+var<uniform> local_lights: array<LocalLight, 64>;
+//Offset of the first light index shifted by 8 bits, number of lights in the lowest 8 bits
+@group(2)@binding(4)
+var<uniform> clusters: array<vec4<u32>, 512>;
+@group(2)@binding(5)
+var<uniform> light_indices: array<vec4<u32>, 1024>;
@@ -0,0 +1,24 @@
+@group(1)@binding(0)
+var<uniform> color: vec4<f32>;
+
+@fragment
+fn main(
+    @location(0) uvs: vec2<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) world_position: vec3<f32>,
+    @location(3) @interpolate(flat) receive_shadows: f32,
+    @location(4) @interpolate(flat) texture_index: u32,
+) -> @location(0) vec4<f32> {
+    let n = normalize(normal);
+
+    let sun = directional_light(world_position, receive_shadows);
+    var diffuse = sun.radiance * max(dot(n, sun.direction), 0.0);
+
+    let lights = cluster_lights(world_position);
+    for (var i = 0u; i < lights.y; i++) {
+        let incoming = local_light(lights.x + i, world_position);
+        diffuse += incoming.radiance * max(dot(n, incoming.direction), 0.0);
+    }
+
+    return vec4<f32>(color.rgb * (light.ambient.rgb + diffuse), color.a);
+}
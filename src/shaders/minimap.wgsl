@@ -0,0 +1,53 @@
+struct Parameters {
+  //Top left corner and size of the minimap in pixels
+  rect: vec4<f32>,
+  //Size of the viewport, whether or not the minimap is circular
+  settings: vec4<f32>,
+}
+
+struct Output {
+  @builtin(position) position: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var map: texture_2d<f32>;
+@group(0) @binding(1)
+var map_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> parameters: Parameters;
+
+@vertex
+fn vertex(@builtin(vertex_index) index: u32) -> Output {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    let uv = corners[index];
+    let pixel = parameters.rect.xy + uv * parameters.rect.zw;
+
+    var out: Output;
+    out.position = vec4<f32>(
+        pixel.x / parameters.settings.x * 2.0 - 1.0,
+        1.0 - pixel.y / parameters.settings.y * 2.0,
+        0.0,
+        1.0
+    );
+    out.uv = uv;
+    return out;
+}
+
+@fragment
+fn fragment(in: Output) -> @location(0) vec4<f32> {
+    let color = textureSample(map, map_sampler, in.uv);
+
+    //Distance from the edge of the circle in pixels, positive inside
+    let radius = parameters.rect.z * 0.5;
+    let inside = radius - length(in.uv - 0.5) * parameters.rect.z;
+    let coverage = select(1.0, clamp(inside + 0.5, 0.0, 1.0), parameters.settings.z > 0.5);
+    return vec4<f32>(color.rgb, coverage);
+}
@@ -0,0 +1,147 @@
+// Copies the instances that are not hidden behind the depth of the previous frame into the
+// instance buffer used for drawing, counting them in the draw commands of their groups
+
+struct Parameters {
+    // View projection of the previous frame
+    view_projection: mat4x4<f32>,
+    // Viewport of the previous frame, in fractions of the attachment
+    viewport: vec4<f32>,
+    // Size of the first level of the pyramid
+    size: vec2<u32>,
+    levels: u32,
+    // 1 if the depth is reversed
+    reversed: u32,
+}
+
+struct Group {
+    bounds_min: vec3<f32>,
+    // First instance of the group in the shared instance buffer
+    first: u32,
+    bounds_max: vec3<f32>,
+    count: u32,
+    // Index of the draw command of the group
+    index: u32,
+}
+
+struct DrawIndexedIndirect {
+    index_count: u32,
+    instance_count: atomic<u32>,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+}
+
+// Size of the instance data in words
+const INSTANCE_SIZE: u32 = 27u;
+
+@group(0) @binding(0) var<uniform> parameters: Parameters;
+@group(0) @binding(1) var pyramid: texture_2d<f32>;
+
+// The instance data is not aligned to 16 bytes, so it's read as words
+@group(1) @binding(0) var<storage, read> instances: array<u32>;
+@group(1) @binding(1) var<storage, read_write> visible: array<u32>;
+@group(1) @binding(2) var<storage, read_write> draws: array<DrawIndexedIndirect>;
+
+@group(2) @binding(0) var<uniform> group: Group;
+
+fn load_column(base: u32) -> vec4<f32> {
+    return vec4<f32>(
+        bitcast<f32>(instances[base]),
+        bitcast<f32>(instances[base + 1u]),
+        bitcast<f32>(instances[base + 2u]),
+        bitcast<f32>(instances[base + 3u]),
+    );
+}
+
+fn farthest(a: f32, b: f32) -> f32 {
+    return select(max(a, b), min(a, b), parameters.reversed == 1u);
+}
+
+fn is_visible(index: u32) -> bool {
+    let base = index * INSTANCE_SIZE;
+    let transform = mat4x4<f32>(
+        load_column(base),
+        load_column(base + 4u),
+        load_column(base + 8u),
+        load_column(base + 12u),
+    );
+    let matrix = parameters.view_projection * transform;
+
+    var ndc_min = vec3<f32>(1e30);
+    var ndc_max = vec3<f32>(-1e30);
+    for (var i = 0u; i < 8u; i++) {
+        let corner = select(
+            group.bounds_min,
+            group.bounds_max,
+            vec3<bool>((i & 1u) != 0u, (i & 2u) != 0u, (i & 4u) != 0u)
+        );
+        let clip = matrix * vec4<f32>(corner, 1.0);
+        // Crossing the near plane, the projected box is unbounded
+        if clip.w <= 0.0 {
+            return true;
+        }
+        let ndc = clip.xyz / clip.w;
+        ndc_min = min(ndc_min, ndc);
+        ndc_max = max(ndc_max, ndc);
+    }
+
+    // Outside of the previous view, nothing is known about it
+    if any(ndc_max.xy < vec2<f32>(-1.0)) || any(ndc_min.xy > vec2<f32>(1.0)) {
+        return true;
+    }
+
+    // Rectangle covered in the attachment, the y axis of the uvs points down
+    let viewport = parameters.viewport;
+    let uv_min = clamp(
+        viewport.xy + vec2<f32>(ndc_min.x * 0.5 + 0.5, 0.5 - ndc_max.y * 0.5) * viewport.zw,
+        vec2<f32>(0.0),
+        vec2<f32>(1.0)
+    );
+    let uv_max = clamp(
+        viewport.xy + vec2<f32>(ndc_max.x * 0.5 + 0.5, 0.5 - ndc_min.y * 0.5) * viewport.zw,
+        vec2<f32>(0.0),
+        vec2<f32>(1.0)
+    );
+
+    // Level at which the rectangle covers at most 2x2 texels
+    let extent = (uv_max - uv_min) * vec2<f32>(parameters.size);
+    let level = min(
+        u32(ceil(log2(max(max(extent.x, extent.y), 1.0)))),
+        parameters.levels - 1u
+    );
+    let size = max(parameters.size >> vec2<u32>(level), vec2<u32>(1u));
+    let texel_min = min(vec2<u32>(uv_min * vec2<f32>(size)), size - 1u);
+    let texel_max = min(vec2<u32>(uv_max * vec2<f32>(size)), size - 1u);
+
+    let depth = farthest(
+        farthest(
+            textureLoad(pyramid, texel_min, i32(level)).r,
+            textureLoad(pyramid, vec2<u32>(texel_max.x, texel_min.y), i32(level)).r
+        ),
+        farthest(
+            textureLoad(pyramid, vec2<u32>(texel_min.x, texel_max.y), i32(level)).r,
+            textureLoad(pyramid, texel_max, i32(level)).r
+        )
+    );
+
+    // Visible if the nearest point of the box is in front of the farthest occluder
+    if parameters.reversed == 1u {
+        return ndc_max.z >= depth;
+    }
+    return ndc_min.z <= depth;
+}
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= group.count || !is_visible(group.first + index) {
+        return;
+    }
+
+    let slot = atomicAdd(&draws[group.index].instance_count, 1u);
+    let source = (group.first + index) * INSTANCE_SIZE;
+    let destination = (group.first + slot) * INSTANCE_SIZE;
+    for (var i = 0u; i < INSTANCE_SIZE; i++) {
+        visible[destination + i] = instances[source + i];
+    }
+}
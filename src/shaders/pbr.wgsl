@@ -0,0 +1,133 @@
+const PI: f32 = 3.14159265;
+
+struct Parameters {
+  //Albedo color, multiplied with the albedo map
+  albedo: vec4<f32>,
+  //Emissive color, multiplied with the emissive map
+  emissive: vec4<f32>,
+  //Metallic, roughness, normal scale, occlusion strength
+  factors: vec4<f32>,
+}
+
+@group(1)@binding(0)
+var<uniform> parameters: Parameters;
+@group(1)@binding(1)
+var albedo_map: texture_2d<f32>;
+@group(1)@binding(2)
+var normal_map: texture_2d<f32>;
+//Roughness in the green channel, metallic in the blue channel
+@group(1)@binding(3)
+var metallic_roughness_map: texture_2d<f32>;
+@group(1)@binding(4)
+var emissive_map: texture_2d<f32>;
+//Occlusion in the red channel
+@group(1)@binding(5)
+var occlusion_map: texture_2d<f32>;
+@group(1)@binding(6)
+var map_sampler: sampler;
+
+//Textures are stored without the srgb conversion
+fn to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
+
+//Applies the normal map using a tangent frame built from the screen space derivatives, so the
+//meshes don't need tangents
+fn perturb_normal(normal: vec3<f32>, world_position: vec3<f32>, uvs: vec2<f32>) -> vec3<f32> {
+    let dp1 = dpdx(world_position);
+    let dp2 = dpdy(world_position);
+    let duv1 = dpdx(uvs);
+    let duv2 = dpdy(uvs);
+
+    let dp2_perpendicular = cross(dp2, normal);
+    let dp1_perpendicular = cross(normal, dp1);
+    let tangent = dp2_perpendicular * duv1.x + dp1_perpendicular * duv2.x;
+    let bitangent = dp2_perpendicular * duv1.y + dp1_perpendicular * duv2.y;
+    let scale = inverseSqrt(max(max(dot(tangent, tangent), dot(bitangent, bitangent)), 1e-12));
+
+    var sampled = textureSample(normal_map, map_sampler, uvs).xyz * 2.0 - 1.0;
+    sampled = vec3<f32>(sampled.xy * parameters.factors.z, sampled.z);
+    return normalize(mat3x3<f32>(tangent * scale, bitangent * scale, normal) * sampled);
+}
+
+//Trowbridge-Reitz GGX normal distribution
+fn distribution(n_h: f32, alpha: f32) -> f32 {
+    let alpha2 = alpha * alpha;
+    let d = n_h * n_h * (alpha2 - 1.0) + 1.0;
+    return alpha2 / (PI * d * d);
+}
+
+//Smith-Schlick geometry term
+fn geometry(n_v: f32, n_l: f32, roughness: f32) -> f32 {
+    let k = (roughness + 1.0) * (roughness + 1.0) / 8.0;
+    return n_v / (n_v * (1.0 - k) + k) * n_l / (n_l * (1.0 - k) + k);
+}
+
+fn fresnel(cos_theta: f32, f0: vec3<f32>) -> vec3<f32> {
+    return f0 + (1.0 - f0) * pow(1.0 - cos_theta, 5.0);
+}
+
+//Returns the light reflected towards the viewer
+fn shade(
+    incoming: Incoming,
+    n: vec3<f32>,
+    v: vec3<f32>,
+    albedo: vec3<f32>,
+    metallic: f32,
+    roughness: f32,
+) -> vec3<f32> {
+    let l = incoming.direction;
+    let n_l = max(dot(n, l), 0.0);
+    if n_l == 0.0 {
+        return vec3<f32>(0.0);
+    }
+
+    let h = normalize(l + v);
+    let n_v = max(dot(n, v), 0.0001);
+    let f0 = mix(vec3<f32>(0.04), albedo, metallic);
+
+    let f = fresnel(max(dot(h, v), 0.0), f0);
+    let specular = distribution(max(dot(n, h), 0.0), roughness * roughness)
+        * geometry(n_v, n_l, roughness) * f / (4.0 * n_v * n_l);
+    let diffuse = (1.0 - f) * (1.0 - metallic) * albedo / PI;
+
+    return (diffuse + specular) * incoming.radiance * n_l;
+}
+
+@fragment
+fn main(
+    @location(0) uvs: vec2<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) world_position: vec3<f32>,
+    @location(3) @interpolate(flat) receive_shadows: f32,
+    @location(4) @interpolate(flat) texture_index: u32,
+) -> @location(0) vec4<f32> {
+    let n = perturb_normal(normalize(normal), world_position, uvs);
+    let v = normalize(light.camera.xyz - world_position);
+
+    let albedo_sample = textureSample(albedo_map, map_sampler, uvs);
+    let albedo = parameters.albedo.rgb * to_linear(albedo_sample.rgb);
+    let metallic_roughness = textureSample(metallic_roughness_map, map_sampler, uvs);
+    let metallic = saturate(parameters.factors.x * metallic_roughness.b);
+    let roughness = clamp(parameters.factors.y * metallic_roughness.g, 0.045, 1.0);
+    let occlusion = mix(
+        1.0,
+        textureSample(occlusion_map, map_sampler, uvs).r,
+        parameters.factors.w,
+    );
+    let emissive = parameters.emissive.rgb
+        * to_linear(textureSample(emissive_map, map_sampler, uvs).rgb);
+
+    let sun = directional_light(world_position, receive_shadows);
+    var color = shade(sun, n, v, albedo, metallic, roughness);
+
+    let lights = cluster_lights(world_position);
+    for (var i = 0u; i < lights.y; i++) {
+        color += shade(local_light(lights.x + i, world_position), n, v, albedo, metallic, roughness);
+    }
+
+    color += light.ambient.rgb * albedo * occlusion + emissive;
+    return vec4<f32>(color, parameters.albedo.a * albedo_sample.a);
+}
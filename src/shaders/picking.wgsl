@@ -0,0 +1,58 @@
+//Picking shader, transforms the vertices by the joints of the armature before the transformation
+//of the mesh if SKINNED is defined
+#include "draw_data.wgsl"
+
+struct PickOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) @interpolate(flat) id: u32,
+}
+
+@group(0) @binding(0) var<uniform> camera: mat4x4<f32>;
+
+#ifdef SKINNED
+//Must match MAX_JOINTS
+@group(1) @binding(0) var<uniform> joints: array<mat4x4<f32>, 128>;
+#endif
+
+@vertex
+fn vertex(
+    @builtin(instance_index) instance: u32,
+    @location(0) position: vec4<f32>,
+    @location(3) trans_0: vec4<f32>,
+    @location(4) trans_1: vec4<f32>,
+    @location(5) trans_2: vec4<f32>,
+    @location(6) trans_3: vec4<f32>,
+#ifdef SKINNED
+    @location(13) joint_indices: vec4<u32>,
+    @location(14) weights: vec4<f32>,
+#endif
+) -> PickOutput {
+    let trans_mat = mat4x4<f32>(
+        trans_0,
+        trans_1,
+        trans_2,
+        trans_3,
+    );
+
+#ifdef SKINNED
+    let indices = min(joint_indices, vec4<u32>(127u));
+    let skin = joints[indices.x] * weights.x
+        + joints[indices.y] * weights.y
+        + joints[indices.z] * weights.z
+        + joints[indices.w] * weights.w;
+    let world_position = trans_mat * skin * position;
+#else
+    let world_position = trans_mat * position;
+#endif
+
+    var res: PickOutput;
+    res.position = camera * world_position;
+    res.id = draw_data.object_id + instance;
+
+    return res;
+}
+
+@fragment
+fn fragment(in: PickOutput) -> @location(0) u32 {
+    return in.id;
+}
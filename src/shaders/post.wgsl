@@ -0,0 +1,33 @@
+struct Parameters {
+  //Rows of the color matrix
+  r: vec4<f32>,
+  g: vec4<f32>,
+  b: vec4<f32>,
+  //Brightness, gamma
+  display: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var input: texture_2d<f32>;
+@group(0) @binding(1)
+var<uniform> parameters: Parameters;
+
+//Single triangle covering the whole screen
+@vertex
+fn vertex(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn fragment(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let color = textureLoad(input, vec2<i32>(position.xy), 0);
+    let rgb = vec3<f32>(
+        dot(parameters.r.xyz, color.rgb),
+        dot(parameters.g.xyz, color.rgb),
+        dot(parameters.b.xyz, color.rgb),
+    );
+    let calibrated = pow(clamp(rgb, vec3<f32>(0.0), vec3<f32>(1.0)), vec3<f32>(1.0 / parameters.display.y))
+        * parameters.display.x;
+    return vec4<f32>(clamp(calibrated, vec3<f32>(0.0), vec3<f32>(1.0)), color.a);
+}
@@ -0,0 +1,10 @@
+@group(0) @binding(0)
+var input: texture_2d<f32>;
+@group(0) @binding(1)
+var input_sampler: sampler;
+
+//Textures are stored starting from the bottom row
+@fragment
+fn fragment(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    return textureSample(input, input_sampler, vec2<f32>(in.uv.x, 1.0 - in.uv.y));
+}
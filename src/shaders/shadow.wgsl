@@ -0,0 +1,19 @@
+@group(0) @binding(0) var<uniform> light: mat4x4<f32>;
+
+@vertex
+fn main(
+    @location(0) position: vec4<f32>,
+    @location(3) trans_0: vec4<f32>,
+    @location(4) trans_1: vec4<f32>,
+    @location(5) trans_2: vec4<f32>,
+    @location(6) trans_3: vec4<f32>,
+) -> @builtin(position) vec4<f32> {
+    let trans_mat = mat4x4<f32>(
+        trans_0,
+        trans_1,
+        trans_2,
+        trans_3,
+    );
+
+    return light * (trans_mat * position);
+}
@@ -0,0 +1,39 @@
+struct Parameters {
+  //Inverse of the view projection matrix of the camera
+  inverse: mat4x4<f32>,
+  //Depth of the far plane, depth of the near plane
+  depth: vec4<f32>,
+}
+
+struct Output {
+  @builtin(position) position: vec4<f32>,
+  @location(0) ndc: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> parameters: Parameters;
+@group(0) @binding(1)
+var sky: texture_cube<f32>;
+@group(0) @binding(2)
+var sky_sampler: sampler;
+
+//Single triangle covering the whole screen, placed on the far plane
+@vertex
+fn vertex(@builtin(vertex_index) index: u32) -> Output {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: Output;
+    out.ndc = uv * 2.0 - 1.0;
+    out.position = vec4<f32>(out.ndc, parameters.depth.x, 1.0);
+    return out;
+}
+
+@fragment
+fn fragment(in: Output) -> @location(0) vec4<f32> {
+    //Direction of the view ray, works for both perspective and orthographic projections and
+    //doesn't depend on the position of the camera
+    let near = parameters.inverse * vec4<f32>(in.ndc, parameters.depth.y, 1.0);
+    let middle = parameters.inverse * vec4<f32>(in.ndc, 0.5, 1.0);
+    let direction = middle.xyz / middle.w - near.xyz / near.w;
+
+    return vec4<f32>(textureSample(sky, sky_sampler, direction).rgb, 1.0);
+}
@@ -0,0 +1,53 @@
+struct Output {
+  @builtin(position) position: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+  @location(1) color: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: mat4x4<f32>;
+@group(1) @binding(0)
+var sprite_texture: texture_2d<f32>;
+@group(1) @binding(1)
+var sprite_sampler: sampler;
+
+@vertex
+fn vertex(
+    @builtin(vertex_index) index: u32,
+    @location(0) trans_0: vec4<f32>,
+    @location(1) trans_1: vec4<f32>,
+    @location(2) trans_2: vec4<f32>,
+    @location(3) trans_3: vec4<f32>,
+    //Offset and size of the region of the texture
+    @location(4) region: vec4<f32>,
+    @location(5) color: vec4<f32>,
+) -> Output {
+    //Two triangles, corners from 0 to 1 with y pointing down
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    let corner = corners[index];
+    let trans_mat = mat4x4<f32>(trans_0, trans_1, trans_2, trans_3);
+
+    var out: Output;
+    out.position = camera * trans_mat * vec4<f32>(corner.x - 0.5, 0.5 - corner.y, 0.0, 1.0);
+    out.uv = region.xy + corner * region.zw;
+    out.color = color;
+    return out;
+}
+
+@fragment
+fn fragment(in: Output) -> @location(0) vec4<f32> {
+    //Textures are stored flipped
+    let color = textureSample(sprite_texture, sprite_sampler, vec2<f32>(in.uv.x, 1.0 - in.uv.y))
+        * in.color;
+    if color.a <= 0.0 {
+        discard;
+    }
+    return color;
+}
@@ -3,8 +3,15 @@ var texture: texture_2d<f32>;
 @group(1)@binding(1)
 var tex_sampler: sampler;
 
+// Every output of the vertex shader must be consumed
 @fragment
-fn main(@location(0) uvs: vec2<f32>, @location(1) normals: vec3<f32>) -> @location(0) vec4<f32> {
+fn main(
+    @location(0) uvs: vec2<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) world_position: vec3<f32>,
+    @location(3) @interpolate(flat) receive_shadows: f32,
+    @location(4) @interpolate(flat) texture_index: u32,
+) -> @location(0) vec4<f32> {
     let col = textureSample(texture, tex_sampler, uvs);
     return col;
 }
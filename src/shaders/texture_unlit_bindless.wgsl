@@ -0,0 +1,17 @@
+//Must match `rendering::bindless::MAX_TEXTURES`
+@group(1)@binding(0)
+var textures: binding_array<texture_2d<f32>, 256>;
+@group(1)@binding(1)
+var tex_sampler: sampler;
+
+@fragment
+fn main(
+    @location(0) uvs: vec2<f32>,
+    @location(1) normals: vec3<f32>,
+    @location(2) world_position: vec3<f32>,
+    @location(3) @interpolate(flat) receive_shadows: f32,
+    @location(4) @interpolate(flat) texture_index: u32,
+) -> @location(0) vec4<f32> {
+    let col = textureSample(textures[texture_index], tex_sampler, uvs);
+    return col;
+}
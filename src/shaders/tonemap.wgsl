@@ -0,0 +1,43 @@
+struct Parameters {
+  //Exposure, operator, extended range output
+  settings: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var input: texture_2d<f32>;
+@group(0) @binding(1)
+var<uniform> parameters: Parameters;
+
+//Narkowicz 2015, fit of the ACES filmic curve
+fn aces(x: vec3<f32>) -> vec3<f32> {
+    return (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14);
+}
+
+fn reinhard(x: vec3<f32>) -> vec3<f32> {
+    return x / (1.0 + x);
+}
+
+@fragment
+fn fragment(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let color = textureLoad(input, vec2<i32>(in.position.xy), 0);
+    let exposed = max(color.rgb * parameters.settings.x, vec3<f32>(0.0));
+
+    //HDR displays show the colors above 1
+    if parameters.settings.z > 0.5 {
+        return vec4<f32>(exposed, clamp(color.a, 0.0, 1.0));
+    }
+
+    var mapped: vec3<f32>;
+    switch u32(parameters.settings.y) {
+        case 0u: {
+            mapped = aces(exposed);
+        }
+        case 1u: {
+            mapped = reinhard(exposed);
+        }
+        default: {
+            mapped = exposed;
+        }
+    }
+    return vec4<f32>(clamp(mapped, vec3<f32>(0.0), vec3<f32>(1.0)), clamp(color.a, 0.0, 1.0));
+}
@@ -1,42 +1,53 @@
-// struct transformations {
-//   world: mat4x4<f32>,
-//   view: mat4x4<f32>,
-//   projection: mat4x4<f32>
-// }
+//Vertex shader of the engine materials, transforms the vertices by the joints of the armature
+//before the transformation of the mesh if SKINNED is defined
+#include "common.wgsl"
 
-struct ColorOutput {
-  @location(0) tex_coord: vec2<f32>,
-  @location(1) normal: vec3<f32>,
-  @builtin(position) position: vec4<f32>
-}
-
-@group(0) @binding(0) var<uniform> camera: mat4x4<f32>;
+#ifdef SKINNED
+//Must match MAX_JOINTS
+@group(3) @binding(0) var<uniform> joints: array<mat4x4<f32>, 128>;
+#endif
 
 @vertex
 fn main(
-    @location(0) position: vec4<f32>,
-    @location(1) uvs: vec2<f32>,
-    @location(2) normal: vec3<f32>,
-    @location(3) trans_0: vec4<f32>,
-    @location(4) trans_1: vec4<f32>,
-    @location(5) trans_2: vec4<f32>,
-    @location(6) trans_3: vec4<f32>,
-) -> ColorOutput {
-    // let mat = trans_mat.projection * trans_mat.view * trans_mat.world ;
-
+    in: VertexInput,
+#ifdef SKINNED
+    @location(13) joint_indices: vec4<u32>,
+    @location(14) weights: vec4<f32>,
+#endif
+) -> VertexOutput {
     let trans_mat = mat4x4<f32>(
-        trans_0,
-        trans_1,
-        trans_2,
-        trans_3,
+        in.trans_0,
+        in.trans_1,
+        in.trans_2,
+        in.trans_3,
     );
-    var o = trans_mat * position;
+    var normal_mat = mat3x3<f32>(
+        in.normal_0,
+        in.normal_1,
+        in.normal_2,
+    );
+
+#ifdef SKINNED
+    let indices = min(joint_indices, vec4<u32>(127u));
+    let skin = joints[indices.x] * weights.x
+        + joints[indices.y] * weights.y
+        + joints[indices.z] * weights.z
+        + joints[indices.w] * weights.w;
+    var o = trans_mat * skin * in.position;
+    normal_mat = normal_mat * mat3x3<f32>(skin[0].xyz, skin[1].xyz, skin[2].xyz);
+#else
+    var o = trans_mat * in.position;
+#endif
+    let world_position = o.xyz;
     o = camera * o;
 
-    var res: ColorOutput;
+    var res: VertexOutput;
     res.position = o;
-    res.tex_coord = uvs;
-    res.normal = normal;
+    res.world_position = world_position;
+    res.tex_coord = in.uvs;
+    res.normal = normalize(normal_mat * in.normal);
+    res.receive_shadows = in.receive_shadows;
+    res.texture_index = in.texture_index;
 
     return res;
 }
@@ -0,0 +1,23 @@
+struct Parameters {
+  //Intensity, radius, smoothness
+  settings: vec4<f32>,
+  //Size of a pixel in uv
+  texel: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var input: texture_2d<f32>;
+@group(0) @binding(1)
+var input_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> parameters: Parameters;
+
+@fragment
+fn fragment(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let color = textureSampleLevel(input, input_sampler, in.uv, 0.0);
+    //0 in the center, 1 in the corners
+    let distance = length(in.uv - 0.5) * sqrt(2.0);
+    let radius = parameters.settings.y;
+    let darkening = smoothstep(radius, radius + parameters.settings.z, distance) * parameters.settings.x;
+    return vec4<f32>(color.rgb * (1.0 - darkening), color.a);
+}
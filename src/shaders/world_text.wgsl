@@ -0,0 +1,37 @@
+struct Output {
+  @builtin(position) position: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+  @location(1) color: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: mat4x4<f32>;
+@group(1) @binding(0)
+var atlas: texture_2d<f32>;
+@group(1) @binding(1)
+var atlas_sampler: sampler;
+
+@vertex
+fn vertex(
+    @location(0) position: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+) -> Output {
+    var out: Output;
+    out.position = camera * vec4<f32>(position, 1.0);
+    out.uv = uv;
+    out.color = color;
+    return out;
+}
+
+@fragment
+fn fragment(in: Output) -> @location(0) vec4<f32> {
+    //The atlas is stored flipped, 0.5 is the edge of the glyph
+    let field = textureSample(atlas, atlas_sampler, vec2<f32>(in.uv.x, 1.0 - in.uv.y)).r;
+    let width = max(fwidth(field) * 0.5, 0.0001);
+    let coverage = smoothstep(0.5 - width, 0.5 + width, field);
+    if coverage <= 0.0 {
+        discard;
+    }
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
+}
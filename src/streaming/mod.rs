@@ -0,0 +1,319 @@
+//! Streaming of large worlds
+//!
+//! The world is split into square chunks on the XZ plane. Chunks around the observer are
+//! generated by a [`ChunkGenerator`] and added to the world, while chunks that are too far away
+//! are removed from it.
+//!
+//! The expensive part of the chunk creation, [`ChunkGenerator::generate`], is performed on
+//! background threads, only the creation of the entities happens on the main thread. On the web
+//! target chunks are generated synchronously.
+//!
+//!```no_run
+//! # use lunar_engine::{asset_managment::AssetStore, ecs::{Entity, EntityBuilder, World}};
+//! # use lunar_engine::components::transform::Transform;
+//! # use lunar_engine::math::Vec3;
+//! use lunar_engine::streaming::{ChunkCoord, ChunkGenerator, Streamer};
+//!
+//! struct Terrain;
+//!
+//! impl ChunkGenerator for Terrain {
+//!     type Data = Vec<Vec3>;
+//!
+//!     fn generate(&self, coord: ChunkCoord) -> Self::Data {
+//!         vec![coord.origin(16.0)]
+//!     }
+//!
+//!     fn build(&self, _: ChunkCoord, data: Self::Data, _: &mut AssetStore) -> Vec<Entity> {
+//!         data.into_iter()
+//!             .map(|position| {
+//!                 EntityBuilder::new()
+//!                     .create_component(|| Transform {
+//!                         position,
+//!                         ..Default::default()
+//!                     })
+//!                     .create()
+//!                     .unwrap()
+//!             })
+//!             .collect()
+//!     }
+//! }
+//!
+//! # let mut world = World::new();
+//! # let mut assets = AssetStore::new();
+//! let mut streamer = Streamer::new(Terrain, 16.0, 4, 5);
+//! //Every frame
+//! streamer.update(Vec3::new(0.0, 0.0, 0.0), &mut world, &mut assets);
+//!```
+use std::sync::{mpsc, Arc};
+
+use vec_key_value_pair::map::VecMap;
+
+use crate::{
+    asset_managment::AssetStore,
+    ecs::{Entity, World, UUID},
+    math::Vec3,
+};
+
+#[cfg(test)]
+mod tests;
+
+///Coordinates of a chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ChunkCoord {
+    ///X coordinate of the chunk
+    pub x: i32,
+    ///Z coordinate of the chunk
+    pub z: i32,
+}
+
+impl ChunkCoord {
+    ///Creates new chunk coordinates
+    #[must_use]
+    pub const fn new(x: i32, z: i32) -> Self {
+        Self { x, z }
+    }
+
+    ///Returns the coordinates of the chunk that contains the `position`
+    #[must_use]
+    pub fn from_position(position: Vec3, chunk_size: f32) -> Self {
+        Self {
+            x: (position.x / chunk_size).floor() as i32,
+            z: (position.z / chunk_size).floor() as i32,
+        }
+    }
+
+    ///Returns the distance to the `other` chunk in chunks, measured along the axis with the
+    ///largest difference
+    #[must_use]
+    pub const fn distance(self, other: Self) -> u32 {
+        let x = self.x.abs_diff(other.x);
+        let z = self.z.abs_diff(other.z);
+        if x > z {
+            x
+        } else {
+            z
+        }
+    }
+
+    ///Returns the corner of the chunk with the smallest coordinates
+    #[must_use]
+    pub fn origin(self, chunk_size: f32) -> Vec3 {
+        Vec3::new(self.x as f32 * chunk_size, 0.0, self.z as f32 * chunk_size)
+    }
+}
+
+///Generates the content of chunks
+pub trait ChunkGenerator: Send + Sync + 'static {
+    ///Data produced by the generator, that is used to create the entities of the chunk
+    type Data: Send + 'static;
+
+    ///Generates the data of the chunk
+    ///
+    ///Called on a background thread, all the expensive work, such as procedural generation or
+    ///reading files, should be done here
+    fn generate(&self, coord: ChunkCoord) -> Self::Data;
+
+    ///Creates the entities of the chunk from the generated data
+    ///
+    ///Called on the main thread, assets needed by the chunk may be registered in `assets`
+    fn build(&self, coord: ChunkCoord, data: Self::Data, assets: &mut AssetStore) -> Vec<Entity>;
+
+    ///Called after all the entities of the chunk were removed from the world
+    #[allow(unused_variables)]
+    fn unload(&self, coord: ChunkCoord, assets: &mut AssetStore) {}
+}
+
+///Loads and unloads the chunks around an observer
+pub struct Streamer<G: ChunkGenerator> {
+    generator: Arc<G>,
+    chunk_size: f32,
+    load_distance: u32,
+    unload_distance: u32,
+    //Ids of the entities of every loaded chunk
+    loaded: VecMap<ChunkCoord, Vec<UUID>>,
+    //Chunks that are being generated
+    pending: Vec<ChunkCoord>,
+    //Incremented by `unload_all`, chunks requested before it are discarded
+    generation: u64,
+    sender: mpsc::Sender<(u64, ChunkCoord, G::Data)>,
+    receiver: mpsc::Receiver<(u64, ChunkCoord, G::Data)>,
+}
+
+impl<G: ChunkGenerator> Streamer<G> {
+    ///Creates a new streamer
+    ///
+    ///Chunks closer than `load_distance` chunks to the observer are loaded, chunks further than
+    ///`unload_distance` chunks are unloaded. Keeping `unload_distance` larger than the
+    ///`load_distance` prevents chunks from being reloaded when the observer moves back and forth
+    ///across a chunk border
+    ///
+    ///# Panics
+    ///Panics if `chunk_size` is not positive or `unload_distance` is smaller than `load_distance`
+    #[must_use]
+    pub fn new(generator: G, chunk_size: f32, load_distance: u32, unload_distance: u32) -> Self {
+        assert!(chunk_size > 0.0, "Chunk size must be positive");
+        assert!(
+            unload_distance >= load_distance,
+            "Unload distance must not be smaller than the load distance"
+        );
+
+        let (sender, receiver) = mpsc::channel();
+
+        Self {
+            generator: Arc::new(generator),
+            chunk_size,
+            load_distance,
+            unload_distance,
+            loaded: VecMap::new(),
+            pending: Vec::new(),
+            generation: 0,
+            sender,
+            receiver,
+        }
+    }
+
+    ///Returns the generator used by the streamer
+    #[must_use]
+    pub fn generator(&self) -> &G {
+        &self.generator
+    }
+
+    ///Returns the size of a chunk
+    #[must_use]
+    pub const fn chunk_size(&self) -> f32 {
+        self.chunk_size
+    }
+
+    ///Returns `true` if the chunk is loaded
+    #[must_use]
+    pub fn is_loaded(&self, coord: ChunkCoord) -> bool {
+        self.loaded.contains_key(&coord)
+    }
+
+    ///Returns the coordinates of all the loaded chunks
+    pub fn loaded_chunks(&self) -> impl Iterator<Item = ChunkCoord> + '_ {
+        self.loaded.keys().copied()
+    }
+
+    ///Returns the number of chunks that are currently being generated
+    #[must_use]
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    ///Loads the chunks around the `position` and unloads the ones that are too far from it
+    ///
+    ///Chunks are generated in the background and are added to the world by one of the following
+    ///calls after their generation is finished. All the chunks around the `position` are loaded
+    ///once [`Streamer::pending`] returns 0
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn update(&mut self, position: Vec3, world: &mut World, assets: &mut AssetStore) {
+        let center = ChunkCoord::from_position(position, self.chunk_size);
+
+        //Add the generated chunks to the world
+        while let Ok((generation, coord, data)) = self.receiver.try_recv() {
+            //Requested before the chunks were unloaded
+            if generation != self.generation {
+                continue;
+            }
+            self.pending.retain(|c| *c != coord);
+
+            //The observer moved away while the chunk was being generated
+            if coord.distance(center) > self.unload_distance {
+                continue;
+            }
+
+            let entities = self.generator.build(coord, data, assets);
+            let ids = entities
+                .into_iter()
+                .map(|e| {
+                    let id = e.get_id();
+                    world.add_entity(e);
+                    id
+                })
+                .collect();
+
+            self.loaded.insert(coord, ids);
+        }
+
+        //Unload the chunks that are too far
+        let far = self
+            .loaded
+            .keys()
+            .filter(|c| c.distance(center) > self.unload_distance)
+            .copied()
+            .collect::<Vec<_>>();
+        for c in far {
+            self.unload(c, world, assets);
+        }
+
+        //Request the missing chunks, closest ones first
+        let distance = self.load_distance as i32;
+        let mut missing = (-distance..=distance)
+            .flat_map(|x| (-distance..=distance).map(move |z| (x, z)))
+            .map(|(x, z)| ChunkCoord::new(center.x + x, center.z + z))
+            .filter(|c| !self.loaded.contains_key(c) && !self.pending.contains(c))
+            .collect::<Vec<_>>();
+        missing.sort_by_key(|c| c.distance(center));
+
+        //Limit the number of chunks generated at the same time
+        let available = max_pending().saturating_sub(self.pending.len());
+        for c in missing.into_iter().take(available) {
+            self.request(c);
+        }
+    }
+
+    ///Unloads all the chunks
+    ///
+    ///Chunks that are still being generated are discarded once finished
+    pub fn unload_all(&mut self, world: &mut World, assets: &mut AssetStore) {
+        self.generation += 1;
+        self.pending.clear();
+
+        let chunks = self.loaded.keys().copied().collect::<Vec<_>>();
+        for c in chunks {
+            self.unload(c, world, assets);
+        }
+    }
+
+    ///Removes the entities of the chunk from the world
+    fn unload(&mut self, coord: ChunkCoord, world: &mut World, assets: &mut AssetStore) {
+        let Some(ids) = self.loaded.remove(&coord) else {
+            return;
+        };
+
+        for id in ids {
+            //The entity may have already been removed by the user
+            _ = world.remove_entity_by_id(id);
+        }
+
+        self.generator.unload(coord, assets);
+    }
+
+    ///Starts generation of the chunk
+    fn request(&mut self, coord: ChunkCoord) {
+        self.pending.push(coord);
+
+        let generator = self.generator.clone();
+        let sender = self.sender.clone();
+        let generation = self.generation;
+
+        let generate = move || {
+            //The streamer was dropped, nobody is waiting for the chunk anymore
+            _ = sender.send((generation, coord, generator.generate(coord)));
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::spawn(generate);
+        #[cfg(target_arch = "wasm32")]
+        generate();
+    }
+}
+
+///Returns the maximum number of chunks that may be generated at the same time
+fn max_pending() -> usize {
+    #[cfg(not(target_arch = "wasm32"))]
+    return std::thread::available_parallelism().map_or(4, std::num::NonZeroUsize::get);
+    #[cfg(target_arch = "wasm32")]
+    return usize::MAX;
+}
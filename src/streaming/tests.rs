@@ -0,0 +1,157 @@
+use std::{
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use crate::{
+    asset_managment::AssetStore,
+    components::transform::Transform,
+    ecs::{Entity, EntityBuilder, World},
+    math::Vec3,
+};
+
+use super::{ChunkCoord, ChunkGenerator, Streamer};
+
+struct Generator;
+
+impl ChunkGenerator for Generator {
+    type Data = Vec3;
+
+    fn generate(&self, coord: ChunkCoord) -> Self::Data {
+        coord.origin(10.0)
+    }
+
+    fn build(&self, _: ChunkCoord, data: Self::Data, _: &mut AssetStore) -> Vec<Entity> {
+        vec![EntityBuilder::new()
+            .create_component(|| Transform {
+                position: data,
+                ..Default::default()
+            })
+            .create()
+            .unwrap()]
+    }
+}
+
+//Doesn't finish the generation until opened
+#[derive(Default)]
+struct Gated {
+    open: AtomicBool,
+    generated: AtomicUsize,
+}
+
+impl ChunkGenerator for Gated {
+    type Data = Vec3;
+
+    fn generate(&self, coord: ChunkCoord) -> Self::Data {
+        while !self.open.load(Ordering::Acquire) {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        self.generated.fetch_add(1, Ordering::AcqRel);
+        coord.origin(10.0)
+    }
+
+    fn build(&self, coord: ChunkCoord, data: Self::Data, assets: &mut AssetStore) -> Vec<Entity> {
+        Generator.build(coord, data, assets)
+    }
+}
+
+fn update_until_done(
+    streamer: &mut Streamer<Generator>,
+    position: Vec3,
+    world: &mut World,
+    assets: &mut AssetStore,
+) {
+    streamer.update(position, world, assets);
+    while streamer.pending() != 0 {
+        std::thread::sleep(Duration::from_millis(1));
+        streamer.update(position, world, assets);
+    }
+}
+
+#[test]
+fn chunk_coord_test() {
+    assert_eq!(
+        ChunkCoord::from_position(Vec3::new(5.0, 100.0, -5.0), 10.0),
+        ChunkCoord::new(0, -1)
+    );
+    assert_eq!(
+        ChunkCoord::from_position(Vec3::new(-10.0, 0.0, 25.0), 10.0),
+        ChunkCoord::new(-1, 2)
+    );
+    assert_eq!(ChunkCoord::new(0, 0).distance(ChunkCoord::new(-3, 2)), 3);
+}
+
+#[test]
+fn streaming_test() {
+    let mut world = World::new();
+    let mut assets = AssetStore::new();
+    let mut streamer = Streamer::new(Generator, 10.0, 1, 2);
+
+    update_until_done(
+        &mut streamer,
+        Vec3::new(5.0, 0.0, 5.0),
+        &mut world,
+        &mut assets,
+    );
+    assert_eq!(streamer.loaded_chunks().count(), 9);
+    assert_eq!(world.get_entity_count(), 9);
+
+    //Chunks within the unload distance are kept
+    update_until_done(
+        &mut streamer,
+        Vec3::new(15.0, 0.0, 5.0),
+        &mut world,
+        &mut assets,
+    );
+    assert_eq!(streamer.loaded_chunks().count(), 12);
+    assert!(streamer.is_loaded(ChunkCoord::new(-1, 0)));
+
+    update_until_done(
+        &mut streamer,
+        Vec3::new(105.0, 0.0, 5.0),
+        &mut world,
+        &mut assets,
+    );
+    assert_eq!(streamer.loaded_chunks().count(), 9);
+    assert_eq!(world.get_entity_count(), 9);
+    assert!(!streamer.is_loaded(ChunkCoord::new(0, 0)));
+    assert!(streamer.is_loaded(ChunkCoord::new(11, 1)));
+
+    let positions = world
+        .get_all_components::<Transform>()
+        .unwrap()
+        .iter()
+        .map(|t| ChunkCoord::from_position(t.borrow().position, 10.0))
+        .collect::<Vec<_>>();
+    assert!(positions.iter().all(|c| streamer.is_loaded(*c)));
+
+    streamer.unload_all(&mut world, &mut assets);
+    assert_eq!(world.get_entity_count(), 0);
+}
+
+#[test]
+fn unload_all_pending_test() {
+    let mut world = World::new();
+    let mut assets = AssetStore::new();
+    let mut streamer = Streamer::new(Gated::default(), 10.0, 1, 2);
+    let position = Vec3::new(5.0, 0.0, 5.0);
+
+    streamer.update(position, &mut world, &mut assets);
+    let requested = streamer.pending();
+    assert_ne!(requested, 0);
+
+    streamer.unload_all(&mut world, &mut assets);
+    assert_eq!(streamer.pending(), 0);
+
+    streamer.generator().open.store(true, Ordering::Release);
+    while streamer.generator().generated.load(Ordering::Acquire) != requested {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    //Give the threads time to send the chunks
+    std::thread::sleep(Duration::from_millis(10));
+
+    //The chunks requested before the unload are not added
+    streamer.update(position, &mut world, &mut assets);
+    assert_eq!(world.get_entity_count(), 0);
+    assert_eq!(streamer.loaded_chunks().count(), 0);
+}
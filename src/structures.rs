@@ -2,7 +2,7 @@
 
 use bytemuck::{Pod, Zeroable};
 
-use crate::math::{Vec2, Vec3, Vec4};
+use crate::math::{Mat3x3, Mat4x4, Vec2, Vec3, Vec4};
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Pod, Zeroable)]
@@ -18,6 +18,55 @@ pub struct Vertex {
 ///Indecies of a mesh
 pub type Index = u32;
 
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Pod, Zeroable)]
+///Joints influencing a vertex of a skinned mesh, stored in a separate vertex buffer
+pub struct SkinVertex {
+    ///Indices of the joints in the armature
+    pub joints: [u32; 4],
+    ///Weights of the joints, should add up to 1
+    pub weights: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Pod, Zeroable)]
+///Per instance data of a rendered mesh
+///
+///Both of the matrices are stored in the column major order, as expected by the shaders
+pub struct InstanceData {
+    ///Transformation matrix of the mesh
+    pub transform: Mat4x4,
+    ///Normal matrix of the mesh, see [`Mat3x3::normal_matrix`]
+    pub normal: Mat3x3,
+    ///1 if the mesh receives shadows, 0 otherwise
+    pub receive_shadows: f32,
+    ///Index of the texture of the material in the shared texture array, see
+    ///[`crate::rendering::bindless`]
+    pub texture_index: u32,
+}
+
+//Must match the vertex buffer layout
+const _: () = {
+    use std::mem::{align_of, offset_of, size_of};
+
+    assert!(size_of::<Vertex>() == 36);
+    assert!(align_of::<Vertex>() == 4);
+    assert!(offset_of!(Vertex, coords) == 0);
+    assert!(offset_of!(Vertex, texture) == 16);
+    assert!(offset_of!(Vertex, normal) == 24);
+
+    assert!(size_of::<SkinVertex>() == 32);
+    assert!(offset_of!(SkinVertex, weights) == 16);
+
+    assert!(size_of::<InstanceData>() == 108);
+    assert!(offset_of!(InstanceData, normal) == 64);
+    assert!(offset_of!(InstanceData, receive_shadows) == 100);
+    assert!(offset_of!(InstanceData, texture_index) == 104);
+
+    assert!(size_of::<Color>() == 16);
+    assert!(size_of::<Pixel>() == 4);
+};
+
 #[repr(C)]
 #[derive(Clone, Debug, Default, PartialEq, PartialOrd)]
 ///Mesh data
@@ -26,6 +75,8 @@ pub struct Mesh {
     pub vertices: Vec<Vertex>,
     ///Indecies of the mesh
     pub indices: Vec<Index>,
+    ///Joints of every vertex, empty if the mesh is not skinned
+    pub skin: Vec<SkinVertex>,
 }
 
 #[repr(C)]
@@ -54,7 +105,8 @@ pub struct Image {
 
 ///Color represented using 4 values from 0 to 1
 #[repr(C)]
-#[derive(Default, Clone, Copy, bytemuck::Zeroable, bytemuck::Pod, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, bytemuck::Zeroable, bytemuck::Pod, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Color {
     ///Value of the red channel
     pub r: f32,
@@ -0,0 +1,71 @@
+//! Scaffolding of new projects
+//!
+//! Generates a minimal game project with a state struct, an asset folder and a scene containing a
+//! camera, a directional light and a cube, that can be run with `cargo run` right away.
+//!
+//!```no_run
+//! lunar_engine::template::generate(std::path::Path::new("my-game"), "my-game").unwrap();
+//!```
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+};
+
+#[cfg(test)]
+mod tests;
+
+const MAIN: &str = include_str!("main.rs.in");
+const MANIFEST: &str = include_str!("Cargo.toml.in");
+const GITIGNORE: &str = "/target\n";
+
+///Generates a new project called `name` in the `directory`
+///
+///The directory is created if it does not exist
+///
+///# Errors
+///Returns an error if the name is not a valid package name, if the directory is not empty or if
+///writing any of the files fails
+pub fn generate(directory: &Path, name: &str) -> io::Result<()> {
+    if !valid_name(name) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("{name} is not a valid package name"),
+        ));
+    }
+
+    if directory.exists() && fs::read_dir(directory)?.next().is_some() {
+        return Err(io::Error::new(
+            ErrorKind::AlreadyExists,
+            format!("{} is not empty", directory.display()),
+        ));
+    }
+
+    fs::create_dir_all(directory.join("src"))?;
+    fs::create_dir_all(directory.join("assets"))?;
+
+    fs::write(directory.join("Cargo.toml"), manifest(name))?;
+    fs::write(directory.join("src").join("main.rs"), MAIN)?;
+    fs::write(directory.join(".gitignore"), GITIGNORE)?;
+    //Keep the empty asset folder in version control
+    fs::write(directory.join("assets").join(".gitkeep"), "")?;
+
+    Ok(())
+}
+
+///Returns the manifest of the project
+fn manifest(name: &str) -> String {
+    MANIFEST
+        .replace("{name}", name)
+        .replace("{version}", env!("CARGO_PKG_VERSION"))
+}
+
+///Returns `true` if the name can be used as a package name
+fn valid_name(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
@@ -0,0 +1,33 @@
+use std::fs;
+
+use super::{generate, valid_name};
+
+#[test]
+fn valid_name_test() {
+    assert!(valid_name("my-game"));
+    assert!(valid_name("_game2"));
+    assert!(!valid_name(""));
+    assert!(!valid_name("2game"));
+    assert!(!valid_name("my game"));
+}
+
+#[test]
+fn generate_test() {
+    let directory = std::env::temp_dir().join(format!(
+        "lunar-engine-template-{}",
+        crate::determinism::random::<u64>()
+    ));
+
+    generate(&directory, "my-game").unwrap();
+
+    let manifest = fs::read_to_string(directory.join("Cargo.toml")).unwrap();
+    assert!(manifest.contains("name = \"my-game\""));
+    assert!(manifest.contains(env!("CARGO_PKG_VERSION")));
+    assert!(directory.join("src/main.rs").exists());
+    assert!(directory.join("assets").is_dir());
+
+    //The directory is no longer empty
+    assert!(generate(&directory, "my-game").is_err());
+
+    fs::remove_dir_all(directory).unwrap();
+}
@@ -9,8 +9,37 @@ async fn gen_gpu_async(instance: &wgpu::Instance) -> (wgpu::Device, wgpu::Queue)
         .await
         .expect("Unable to get an adapter");
 
+    crate::rendering::capabilities::initialize(
+        &adapter,
+        &wgpu::Limits::default(),
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+        &wgpu::SurfaceCapabilities::default(),
+    );
+
+    //Requested like in the engine, so the push constants of the draw data are tested too
+    let push_constants = adapter.features().contains(wgpu::Features::PUSH_CONSTANTS);
+    crate::rendering::draw_data::set_push_constants_supported(push_constants);
+
     adapter
-        .request_device(&wgpu::DeviceDescriptor::default(), None)
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                required_features: if push_constants {
+                    wgpu::Features::PUSH_CONSTANTS
+                } else {
+                    wgpu::Features::empty()
+                },
+                required_limits: wgpu::Limits {
+                    max_push_constant_size: if push_constants {
+                        std::mem::size_of::<crate::rendering::draw_data::DrawData>() as u32
+                    } else {
+                        0
+                    },
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            None,
+        )
         .await
         .expect("Can not get device and queue")
 }
@@ -33,3 +62,113 @@ pub(crate) fn generate_gpu() {
         _ = crate::DEVICE.set(WgpuWrapper::new(device));
     }
 }
+
+///Generates all the necessary data for rendering without a window
+pub(crate) fn generate_headless() {
+    generate_gpu();
+
+    _ = crate::FORMAT.set(wgpu::TextureFormat::Rgba8UnormSrgb);
+    let belt = wgpu::util::StagingBelt::new(2048);
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        _ = crate::STAGING_BELT.set(std::sync::RwLock::new(belt));
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        _ = crate::STAGING_BELT.set(std::sync::RwLock::new(crate::wrappers::WgpuWrapper::new(
+            belt,
+        )));
+    }
+}
+
+const GOLDEN_DIRECTORY: &str = "assets/test-data/golden";
+//Rendered images of the failed comparisons, kept out of the repository
+const ACTUAL_DIRECTORY: &str = "target/golden";
+
+///Compares the RGBA8 image with the stored reference image `name`
+///
+///Channels may differ by at most `tolerance`, up to 0.1% of the pixels may exceed it to allow for
+///differences in rasterization between gpus. If the reference does not exist, or the
+///`UPDATE_GOLDEN` environment variable is set, the image is stored as the new reference.
+///
+///References are stored as PAM images, on failure the rendered image is stored in
+///`target/golden` with the `.actual.pam` extension
+pub(crate) fn compare_golden(name: &str, image: &[u8], width: u32, height: u32, tolerance: u8) {
+    let directory = std::path::Path::new(GOLDEN_DIRECTORY);
+    let path = directory.join(format!("{name}.pam"));
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() || !path.exists() {
+        log::warn!("Storing a new reference image {}", path.display());
+        std::fs::create_dir_all(directory).unwrap();
+        std::fs::write(&path, encode_pam(image, width, height)).unwrap();
+        return;
+    }
+
+    let actual_directory = std::path::Path::new(ACTUAL_DIRECTORY);
+    let actual = actual_directory.join(format!("{name}.actual.pam"));
+    let fail = |message: String| {
+        std::fs::create_dir_all(actual_directory).unwrap();
+        std::fs::write(&actual, encode_pam(image, width, height)).unwrap();
+        panic!(
+            "{message}, the rendered image was stored in {}",
+            actual.display()
+        );
+    };
+
+    let reference = std::fs::read(&path).unwrap();
+    let (ref_width, ref_height, reference) =
+        decode_pam(&reference).expect("Invalid reference image");
+
+    if (ref_width, ref_height) != (width, height) {
+        fail(format!(
+            "Image size {width}x{height} does not match the reference size {ref_width}x{ref_height}"
+        ));
+    }
+
+    let different = image
+        .chunks_exact(4)
+        .zip(reference.chunks_exact(4))
+        .filter(|(a, b)| {
+            a.iter()
+                .zip(b.iter())
+                .any(|(a, b)| a.abs_diff(*b) > tolerance)
+        })
+        .count();
+
+    if different * 1000 > (width * height) as usize {
+        fail(format!(
+            "{different} pixels differ from the reference image {}",
+            path.display()
+        ));
+    }
+    _ = std::fs::remove_file(actual);
+}
+
+fn encode_pam(image: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let mut o = format!(
+        "P7\nWIDTH {width}\nHEIGHT {height}\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n"
+    )
+    .into_bytes();
+    o.extend_from_slice(image);
+    o
+}
+
+fn decode_pam(data: &[u8]) -> Option<(u32, u32, &[u8])> {
+    const END: &[u8] = b"ENDHDR\n";
+    let end = data.windows(END.len()).position(|w| w == END)? + END.len();
+    let header = std::str::from_utf8(&data[..end]).ok()?;
+
+    let value = |key: &str| {
+        header
+            .lines()
+            .find_map(|l| l.strip_prefix(key))
+            .and_then(|v| v.trim().parse::<u32>().ok())
+    };
+    let (width, height) = (value("WIDTH ")?, value("HEIGHT ")?);
+    if value("DEPTH ")? != 4 || value("MAXVAL ")? != 255 {
+        return None;
+    }
+
+    let pixels = &data[end..];
+    (pixels.len() == (width * height * 4) as usize).then_some((width, height, pixels))
+}
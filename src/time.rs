@@ -0,0 +1,93 @@
+//! Frame timing
+//!
+//! Time is measured using a monotonic clock, [`std::time::Instant`] on native and
+//! `performance.now()` on the web, and accumulated as integer nanoseconds, so it doesn't drift
+//! or jump with changes of the system clock.
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+//Starting value of 10ms, used before the first frame is finished
+static DELTA: AtomicU64 = AtomicU64::new(10_000_000);
+static ELAPSED: AtomicU64 = AtomicU64::new(0);
+static FRAME: AtomicU64 = AtomicU64::new(0);
+//Fixed delta time in nanoseconds, 0 if the measured time is used
+static FIXED_DELTA: AtomicU64 = AtomicU64::new(0);
+
+///Returns the current time of the monotonic clock in nanoseconds
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn now() -> u64 {
+    use std::{sync::OnceLock, time::Instant};
+
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_nanos() as u64
+}
+
+///Returns the current time of the monotonic clock in nanoseconds
+#[cfg(target_arch = "wasm32")]
+#[allow(clippy::cast_sign_loss)]
+pub(crate) fn now() -> u64 {
+    let ms = web_sys::window()
+        .and_then(|w| w.performance())
+        .map_or(0.0, |p| p.now());
+    (ms * 1_000_000.0) as u64
+}
+
+///Ends the current frame, that started at `frame_start`, returning the start of the next one
+pub(crate) fn end_frame(frame_start: u64) -> u64 {
+    let now = now();
+    let delta = match FIXED_DELTA.load(Ordering::Relaxed) {
+        0 => now.saturating_sub(frame_start),
+        fixed => fixed,
+    };
+
+    DELTA.store(delta, Ordering::Relaxed);
+    ELAPSED.fetch_add(delta, Ordering::Relaxed);
+    FRAME.fetch_add(1, Ordering::Relaxed);
+
+    now
+}
+
+///Returns time between frames in seconds
+#[must_use]
+pub fn delta_time() -> f32 {
+    delta().as_secs_f32()
+}
+
+///Returns time between frames
+#[must_use]
+pub fn delta() -> Duration {
+    match FIXED_DELTA.load(Ordering::Relaxed) {
+        0 => Duration::from_nanos(DELTA.load(Ordering::Relaxed)),
+        fixed => Duration::from_nanos(fixed),
+    }
+}
+
+///Makes every frame last exactly `delta`, regardless of the measured time, see
+///[`crate::determinism`]
+pub(crate) fn set_fixed_delta(delta: Option<Duration>) {
+    let nanos = delta.map_or(0, |d| (d.as_nanos() as u64).max(1));
+    FIXED_DELTA.store(nanos, Ordering::Relaxed);
+}
+
+///Returns the sum of all frame times, i.e. the time since the first frame, in seconds
+///
+///For long running applications, the precision of the returned value decreases, prefer
+///[`elapsed`] for measuring long periods of time
+#[must_use]
+pub fn elapsed_time() -> f32 {
+    elapsed().as_secs_f32()
+}
+
+///Returns the sum of all frame times, i.e. the time since the first frame
+#[must_use]
+pub fn elapsed() -> Duration {
+    Duration::from_nanos(ELAPSED.load(Ordering::Relaxed))
+}
+
+///Returns the number of frames rendered so far
+#[must_use]
+pub fn frame() -> u64 {
+    FRAME.load(Ordering::Relaxed)
+}
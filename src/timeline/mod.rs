@@ -0,0 +1,242 @@
+//! Recording of the events of frames into a timeline, for finding stutters
+//!
+//! The recording is started on demand with [`capture_frames`], and covers the given number of
+//! whole frames. Every frame is recorded as a span, containing the spans of the input processing,
+//! the game loop, the update of the world, each of the rendering extensions and the presentation
+//! of the frame. Custom spans can be added with [`span`]. Spans measure the time spent on the cpu,
+//! the spans of the extensions cover recording their commands, not their execution on the gpu.
+//! Initialization of assets is recorded with a span per type and per asset.
+//!
+//! The captured [`Trace`] is exported in the Chrome trace event format with [`Trace::to_json`],
+//! which can be opened in `chrome://tracing` or [Perfetto](https://ui.perfetto.dev). On native
+//! targets [`capture_frames_to_file`] writes the trace into a file once it's captured.
+//!
+//! Spans are only recorded while a capture is in progress, otherwise creating them is a single
+//! atomic load.
+//!
+//! # Examples
+//!```
+//!use lunar_engine::timeline;
+//!
+//!timeline::capture_frames(120);
+//!
+//!//In the game loop
+//!{
+//!    let _span = timeline::span("Physics");
+//!    //...
+//!}
+//!
+//!//Once the frames are rendered
+//!if let Some(trace) = timeline::take_capture() {
+//!    println!("{}", trace.to_json());
+//!}
+//!```
+use std::{
+    borrow::Cow,
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Mutex, RwLock,
+    },
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+use crate::time;
+
+#[cfg(test)]
+mod tests;
+
+//Number of frames requested to be captured, the capture starts at the beginning of the next frame
+static REQUESTED: AtomicU32 = AtomicU32::new(0);
+//Number of frames left in the current capture
+static REMAINING: AtomicU32 = AtomicU32::new(0);
+static EVENTS: Mutex<Vec<Event>> = Mutex::new(Vec::new());
+static CAPTURED: RwLock<Option<Trace>> = RwLock::new(None);
+#[cfg(not(target_arch = "wasm32"))]
+static DESTINATION: Mutex<Option<PathBuf>> = Mutex::new(None);
+static THREADS: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    //Id of the thread in the trace, in the order the threads recorded their first span
+    static THREAD: u64 = THREADS.fetch_add(1, Ordering::Relaxed) + 1;
+}
+
+///Span recorded in a [`Trace`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    ///Name of the span
+    pub name: Cow<'static, str>,
+    ///Category of the span, one of `"frame"`, `"engine"`, `"extension"` or `"user"`
+    pub category: &'static str,
+    ///Start of the span in nanoseconds, since the start of the application
+    pub start: u64,
+    ///Duration of the span in nanoseconds
+    pub duration: u64,
+    ///Id of the thread the span was recorded on
+    pub thread: u64,
+    ///Number of the frame the span was recorded in, see [`time::frame`]
+    pub frame: u64,
+}
+
+///Events recorded during a capture, see [`self`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trace {
+    ///Recorded spans, in the order they ended in
+    pub events: Vec<Event>,
+}
+
+impl Trace {
+    ///Returns the trace in the Chrome trace event format
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let events = self
+            .events
+            .iter()
+            .map(|e| {
+                format!(
+                    "{{\"name\":\"{}\",\"cat\":\"{}\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":1,\"tid\":{},\"args\":{{\"frame\":{}}}}}",
+                    escape(&e.name),
+                    e.category,
+                    e.start as f64 / 1000.0,
+                    e.duration as f64 / 1000.0,
+                    e.thread,
+                    e.frame
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        format!("{{\"traceEvents\":[{events}],\"displayTimeUnit\":\"ms\"}}")
+    }
+
+    ///Returns the number of frames in the trace
+    #[must_use]
+    pub fn frames(&self) -> usize {
+        self.events.iter().filter(|e| e.category == "frame").count()
+    }
+}
+
+///Escapes quotes and backslashes
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+///Span of time that is recorded when it's dropped, see [`span`]
+#[must_use = "The span ends when it's dropped"]
+pub struct Span {
+    //Name, category and start of the span, `None` if nothing is being captured
+    inner: Option<(Cow<'static, str>, &'static str, u64)>,
+    //Whether or not the span covers a whole frame
+    frame: bool,
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let Some((name, category, start)) = self.inner.take() else {
+            return;
+        };
+        let event = Event {
+            name,
+            category,
+            start,
+            duration: time::now().saturating_sub(start),
+            thread: THREAD.with(|t| *t),
+            frame: time::frame(),
+        };
+        EVENTS.lock().unwrap().push(event);
+
+        if self.frame && REMAINING.fetch_sub(1, Ordering::Relaxed) == 1 {
+            finish();
+        }
+    }
+}
+
+///Starts a span with the given name, that ends when the returned value is dropped
+///
+///The span is only recorded if a capture is in progress
+pub fn span(name: impl Into<Cow<'static, str>>) -> Span {
+    span_with_category(name, "user")
+}
+
+pub(crate) fn span_with_category(
+    name: impl Into<Cow<'static, str>>,
+    category: &'static str,
+) -> Span {
+    Span {
+        inner: is_capturing().then(|| (name.into(), category, time::now())),
+        frame: false,
+    }
+}
+
+///Starts the span of a frame, starting the requested capture
+pub(crate) fn frame() -> Span {
+    let requested = REQUESTED.swap(0, Ordering::Relaxed);
+    if requested > 0 && !is_capturing() {
+        EVENTS.lock().unwrap().clear();
+        REMAINING.store(requested, Ordering::Relaxed);
+        log::info!("Capturing the timeline of {requested} frames");
+    }
+
+    let mut span = span_with_category("Frame", "frame");
+    span.frame = span.inner.is_some();
+    span
+}
+
+///Captures the timeline of the next `frames` frames, the trace is returned by [`take_capture`]
+///once they are rendered
+///
+///Does nothing if a capture is already in progress
+pub fn capture_frames(frames: u32) {
+    #[cfg(not(target_arch = "wasm32"))]
+    if !is_capturing() {
+        *DESTINATION.lock().unwrap() = None;
+    }
+    REQUESTED.store(frames.max(1), Ordering::Relaxed);
+}
+
+///Captures the timeline of the next `frames` frames and writes it into the file at `path`, in the
+///format of [`Trace::to_json`]
+///
+///Does nothing if a capture is already in progress
+#[cfg(not(target_arch = "wasm32"))]
+pub fn capture_frames_to_file(frames: u32, path: impl Into<PathBuf>) {
+    if is_capturing() {
+        return;
+    }
+    *DESTINATION.lock().unwrap() = Some(path.into());
+    REQUESTED.store(frames.max(1), Ordering::Relaxed);
+}
+
+///Returns `true` if the timeline is being captured
+#[must_use]
+pub fn is_capturing() -> bool {
+    REMAINING.load(Ordering::Relaxed) > 0
+}
+
+///Returns the last captured trace, if a capture finished since the last call
+#[must_use]
+pub fn take_capture() -> Option<Trace> {
+    CAPTURED.write().unwrap().take()
+}
+
+///Ends the capture, storing the trace or writing it into the requested file
+fn finish() {
+    let trace = Trace {
+        events: std::mem::take(&mut *EVENTS.lock().unwrap()),
+    };
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let destination = DESTINATION.lock().unwrap().take();
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(path) = destination {
+        match std::fs::write(&path, trace.to_json()) {
+            Ok(()) => log::info!("Wrote the timeline to {}", path.display()),
+            Err(e) => log::error!("Failed to write the timeline to {}: {e}", path.display()),
+        }
+        return;
+    }
+
+    log::info!("Captured the timeline of {} frames", trace.frames());
+    *CAPTURED.write().unwrap() = Some(trace);
+}
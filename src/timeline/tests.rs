@@ -0,0 +1,66 @@
+use crate::{asset_managment::AssetStore, assets::ShaderInclude};
+
+use super::{capture_frames, capture_frames_to_file, frame, is_capturing, span, take_capture};
+
+#[test]
+fn timeline_capture_test() {
+    capture_frames(2);
+    assert!(!is_capturing());
+
+    for _ in 0..3 {
+        let _frame = frame();
+        let _span = span("Work");
+    }
+    assert!(!is_capturing());
+
+    let trace = take_capture().unwrap();
+    assert_eq!(trace.frames(), 2);
+    let work = trace
+        .events
+        .iter()
+        .filter(|e| e.name == "Work")
+        .collect::<Vec<_>>();
+    assert_eq!(work.len(), 2);
+    assert!(work.iter().all(|e| e.category == "user"));
+
+    //Spans are nested in their frames
+    let frames = trace.events.iter().filter(|e| e.category == "frame");
+    for (frame, work) in frames.zip(work) {
+        assert!(frame.start <= work.start);
+        assert!(frame.start + frame.duration >= work.start + work.duration);
+    }
+
+    let json = trace.to_json();
+    assert!(json.starts_with("{\"traceEvents\":["));
+    assert!(json.contains("{\"name\":\"Work\",\"cat\":\"user\",\"ph\":\"X\""));
+    assert!(take_capture().is_none());
+
+    //Written into a file instead
+    let path = std::env::temp_dir().join(format!("lunar-timeline-{}.json", std::process::id()));
+    capture_frames_to_file(1, &path);
+    drop(frame());
+    assert!(take_capture().is_none());
+    let written = std::fs::read_to_string(&path).unwrap();
+    _ = std::fs::remove_file(&path);
+    assert!(written.contains("\"name\":\"Frame\",\"cat\":\"frame\""));
+
+    //Initialization of the assets is recorded per type and per asset
+    capture_frames(1);
+    let id = {
+        let _frame = frame();
+        let mut assets = AssetStore::new();
+        let id = assets.register(ShaderInclude::new("a.wgsl", ""));
+        assets.register(ShaderInclude::new("b.wgsl", ""));
+        assets.intialize_all().unwrap();
+        id
+    };
+    let trace = take_capture().unwrap();
+    let type_name = std::any::type_name::<ShaderInclude>();
+    let assets = trace
+        .events
+        .iter()
+        .filter(|e| e.category == "asset")
+        .collect::<Vec<_>>();
+    assert_eq!(assets.iter().filter(|e| e.name == type_name).count(), 1);
+    assert!(assets.iter().any(|e| e.name == format!("{type_name} {id}")));
+}
@@ -0,0 +1,347 @@
+//! Opt-in validation of common mistakes
+//!
+//! When enabled, the world is checked every frame before rendering, every problem found is
+//! reported as a warning once per entity, instead of the renderer panicking or producing garbage.
+//!
+//!```
+//! lunar_engine::validation::set_enabled(true);
+//!```
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        RwLock,
+    },
+};
+
+use crate::{
+    asset_managment::{self, AssetStore},
+    assets::{self, skeleton::ClipError, Material},
+    components::{animator::Animator, camera::MainCamera, mesh::Mesh, transform::Transform},
+    ecs::{self, World},
+    math::Vector,
+};
+
+#[cfg(test)]
+mod tests;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+//Reported warnings by the entity they're about, forgotten when the entity is removed
+static REPORTED: RwLock<Option<HashMap<ecs::UUID, HashSet<Warning>>>> = RwLock::new(None);
+
+///Enables or disables validation
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+///Returns `true` if validation is enabled
+#[must_use]
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+///Problems found by the validation
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Warning {
+    ///A mesh component doesn't have a mesh asset assigned
+    MissingMesh {
+        ///Id of the entity
+        entity: ecs::UUID,
+    },
+    ///A mesh component doesn't have a material asset assigned
+    MissingMaterial {
+        ///Id of the entity
+        entity: ecs::UUID,
+    },
+    ///A mesh component references an asset that is not registered in the asset store
+    UnknownAsset {
+        ///Id of the entity
+        entity: ecs::UUID,
+        ///Id of the asset
+        asset: asset_managment::UUID,
+    },
+    ///A camera is on an entity without a transform
+    CameraWithoutTransform {
+        ///Id of the entity
+        entity: ecs::UUID,
+    },
+    ///Position, rotation or scale of a transform contains NaN or infinity
+    NonFiniteTransform {
+        ///Id of the entity
+        entity: ecs::UUID,
+    },
+    ///Scale of a transform has a component equal to 0
+    ZeroScale {
+        ///Id of the entity
+        entity: ecs::UUID,
+    },
+    ///A skinned mesh is on an entity without an animator
+    MissingAnimator {
+        ///Id of the entity
+        entity: ecs::UUID,
+    },
+    ///A skinned mesh uses a material that does not support skinning
+    SkinningUnsupported {
+        ///Id of the entity
+        entity: ecs::UUID,
+    },
+    ///A mesh or an animator references a skeleton that is not registered in the asset store
+    UnknownSkeleton {
+        ///Id of the entity
+        entity: ecs::UUID,
+        ///Id of the skeleton
+        skeleton: asset_managment::UUID,
+    },
+    ///A skinned mesh and its animator use different skeletons
+    SkeletonMismatch {
+        ///Id of the entity
+        entity: ecs::UUID,
+    },
+    ///The clip played by an animator does not target its skeleton
+    InvalidClip {
+        ///Id of the entity
+        entity: ecs::UUID,
+        ///The problem with the clip
+        error: ClipError,
+    },
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingMesh { entity } => write!(
+                f,
+                "Entity {entity} has a mesh component without a mesh, use `Mesh::set_mesh` to assign one, it will not be rendered"
+            ),
+            Self::MissingMaterial { entity } => write!(
+                f,
+                "Entity {entity} has a mesh component without a material, use `Mesh::set_material` to assign one, it will not be rendered"
+            ),
+            Self::UnknownAsset { entity, asset } => write!(
+                f,
+                "Entity {entity} references asset {asset}, which is not registered in the asset store"
+            ),
+            Self::CameraWithoutTransform { entity } => write!(
+                f,
+                "Entity {entity} has a camera, but no transform, add a `Transform` before the camera"
+            ),
+            Self::NonFiniteTransform { entity } => write!(
+                f,
+                "Transform of entity {entity} contains NaN or infinite values"
+            ),
+            Self::ZeroScale { entity } => write!(
+                f,
+                "Transform of entity {entity} has a scale of 0, it's matrix can not be inverted"
+            ),
+            Self::MissingAnimator { entity } => write!(
+                f,
+                "Entity {entity} has a skinned mesh, but no `Animator`, it will be rendered in its bind pose"
+            ),
+            Self::SkinningUnsupported { entity } => write!(
+                f,
+                "Entity {entity} has a skinned mesh, but its material does not support skinning, it will be rendered in its bind pose"
+            ),
+            Self::UnknownSkeleton { entity, skeleton } => write!(
+                f,
+                "Entity {entity} references skeleton {skeleton}, which is not registered in the asset store"
+            ),
+            Self::SkeletonMismatch { entity } => write!(
+                f,
+                "Entity {entity} has a skinned mesh and an `Animator` using different skeletons"
+            ),
+            Self::InvalidClip { entity, error } => write!(
+                f,
+                "The animation clip played on entity {entity} is invalid: {error}"
+            ),
+        }
+    }
+}
+
+impl Warning {
+    ///Returns the id of the entity the warning is about
+    #[must_use]
+    pub const fn get_entity(&self) -> ecs::UUID {
+        match self {
+            Self::MissingMesh { entity }
+            | Self::MissingMaterial { entity }
+            | Self::UnknownAsset { entity, .. }
+            | Self::CameraWithoutTransform { entity }
+            | Self::NonFiniteTransform { entity }
+            | Self::ZeroScale { entity }
+            | Self::MissingAnimator { entity }
+            | Self::SkinningUnsupported { entity }
+            | Self::UnknownSkeleton { entity, .. }
+            | Self::SkeletonMismatch { entity }
+            | Self::InvalidClip { entity, .. } => *entity,
+        }
+    }
+}
+
+///Checks the world for common mistakes, returning all the problems found
+#[must_use]
+pub fn validate(world: &World, assets: &AssetStore) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+
+    for e in world
+        .get_all_entities_with_component::<Mesh>()
+        .unwrap_or_default()
+    {
+        let e = e.borrow();
+        let entity = e.get_id();
+        let mesh = e.get_component::<Mesh>().unwrap();
+        let mesh = mesh.borrow();
+
+        for (id, warning) in [
+            (mesh.get_mesh_id(), Warning::MissingMesh { entity }),
+            (mesh.get_material_id(), Warning::MissingMaterial { entity }),
+        ] {
+            match id {
+                Some(asset) if !assets.contains(asset) => {
+                    warnings.push(Warning::UnknownAsset { entity, asset });
+                }
+                Some(_) => {}
+                None => warnings.push(warning),
+            }
+        }
+
+        //Only known once the mesh is loaded
+        let (skinned, skeleton) = mesh
+            .get_mesh_id()
+            .and_then(|m| assets.get_by_id::<assets::Mesh>(m).ok())
+            .map_or((false, None), |m| {
+                let m = m.borrow();
+                (m.is_skinned(), m.get_skeleton())
+            });
+        if let Some(skeleton) = skeleton {
+            if !assets.contains_type::<assets::Skeleton>(skeleton) {
+                warnings.push(Warning::UnknownSkeleton { entity, skeleton });
+            }
+            if e.get_component::<Animator>()
+                .and_then(|a| a.borrow().get_skeleton())
+                .is_some_and(|s| s != skeleton)
+            {
+                warnings.push(Warning::SkeletonMismatch { entity });
+            }
+        }
+        if skinned {
+            if !e.has_component::<Animator>() {
+                warnings.push(Warning::MissingAnimator { entity });
+            }
+            if mesh
+                .get_material_id()
+                .and_then(|m| assets.get_by_id::<Material>(m).ok())
+                .is_some_and(|m| !m.borrow().supports_skinning())
+            {
+                warnings.push(Warning::SkinningUnsupported { entity });
+            }
+        }
+    }
+
+    for e in world
+        .get_all_entities_with_component::<Animator>()
+        .unwrap_or_default()
+    {
+        let e = e.borrow();
+        let entity = e.get_id();
+        let animator = e.get_component::<Animator>().unwrap();
+        let animator = animator.borrow();
+        let Some(skeleton) = animator.get_skeleton() else {
+            continue;
+        };
+
+        if !assets.contains_type::<assets::Skeleton>(skeleton) {
+            let warning = Warning::UnknownSkeleton { entity, skeleton };
+            //Already reported for the mesh
+            if !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+            continue;
+        }
+        let Some(clip) = animator.get_clip() else {
+            continue;
+        };
+        if let Ok(skeleton) = assets.get_by_id::<assets::Skeleton>(skeleton) {
+            if let Err(error) = skeleton.borrow().validate_clip(clip) {
+                warnings.push(Warning::InvalidClip { entity, error });
+            }
+        }
+    }
+
+    for e in world
+        .get_all_entities_with_component::<MainCamera>()
+        .unwrap_or_default()
+    {
+        let e = e.borrow();
+        if !e.has_component::<Transform>() {
+            warnings.push(Warning::CameraWithoutTransform { entity: e.get_id() });
+        }
+    }
+
+    for e in world
+        .get_all_entities_with_component::<Transform>()
+        .unwrap_or_default()
+    {
+        let e = e.borrow();
+        let entity = e.get_id();
+        let t = e.get_component::<Transform>().unwrap();
+        let t = t.borrow();
+
+        if !(t.position.is_finite() && t.rotation.is_finite() && t.scale.is_finite()) {
+            warnings.push(Warning::NonFiniteTransform { entity });
+        }
+        if t.scale.x == 0.0 || t.scale.y == 0.0 || t.scale.z == 0.0 {
+            warnings.push(Warning::ZeroScale { entity });
+        }
+    }
+
+    warnings
+}
+
+///Returns the (mesh, material) ids of the mesh component, or the reason it can not be rendered
+pub(crate) fn renderable(mesh: &Mesh, assets: &AssetStore) -> Result<(u128, u128), Warning> {
+    let entity = mesh.get_entity_id().unwrap_or_default();
+    let check = |id: Option<asset_managment::UUID>, missing: Warning| match id {
+        Some(asset) if !assets.contains(asset) => Err(Warning::UnknownAsset { entity, asset }),
+        Some(asset) => Ok(asset),
+        None => Err(missing),
+    };
+    Ok((
+        check(mesh.get_mesh_id(), Warning::MissingMesh { entity })?,
+        check(mesh.get_material_id(), Warning::MissingMaterial { entity })?,
+    ))
+}
+
+///Reports a warning, if it was not reported before
+///
+///Returns `true` if the warning was reported
+pub(crate) fn warn_once(warning: Warning) -> bool {
+    let mut reported = REPORTED.write().unwrap();
+    let reported = reported
+        .get_or_insert_with(HashMap::new)
+        .entry(warning.get_entity())
+        .or_default();
+    if reported.contains(&warning) {
+        return false;
+    }
+    log::warn!("{warning}");
+    reported.insert(warning);
+    true
+}
+
+///Forgets the warnings reported about the entity, called when it's removed from the world
+pub(crate) fn forget_entity(entity: ecs::UUID) {
+    if let Some(reported) = REPORTED.write().unwrap().as_mut() {
+        reported.remove(&entity);
+    }
+}
+
+///Validates the world if validation is enabled
+pub(crate) fn validate_frame(world: &World, assets: &AssetStore) {
+    if !enabled() {
+        return;
+    }
+
+    for w in validate(world, assets) {
+        warn_once(w);
+    }
+}
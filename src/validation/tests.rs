@@ -0,0 +1,206 @@
+use crate::{
+    asset_managment::AssetStore,
+    components::{mesh::Mesh, transform::Transform},
+    ecs::{EntityBuilder, World},
+    math::Vec3,
+};
+
+use super::*;
+
+#[test]
+fn test_validate_mesh() {
+    let mut world = World::new();
+    let assets = AssetStore::new();
+
+    let e = world.add_entity(
+        EntityBuilder::new()
+            .add_component::<Transform>()
+            .add_component::<Mesh>()
+            .create()
+            .unwrap(),
+    );
+    let entity = e.upgrade().unwrap().borrow().get_id();
+
+    let warnings = validate(&world, &assets);
+    assert!(warnings.contains(&Warning::MissingMesh { entity }));
+    assert!(warnings.contains(&Warning::MissingMaterial { entity }));
+
+    let e = e.upgrade().unwrap();
+    e.borrow()
+        .get_component::<Mesh>()
+        .unwrap()
+        .borrow_mut()
+        .set_mesh(10);
+
+    let warnings = validate(&world, &assets);
+    assert!(warnings.contains(&Warning::UnknownAsset { entity, asset: 10 }));
+    assert!(!warnings.contains(&Warning::MissingMesh { entity }));
+}
+
+#[test]
+fn test_validate_transform() {
+    let mut world = World::new();
+    let assets = AssetStore::new();
+
+    let e = world.add_entity(
+        EntityBuilder::new()
+            .create_component(|| Transform {
+                position: Vec3::new(f32::NAN, 0.0, 0.0),
+                scale: Vec3::new(1.0, 0.0, 1.0),
+                ..Default::default()
+            })
+            .create()
+            .unwrap(),
+    );
+    let entity = e.upgrade().unwrap().borrow().get_id();
+
+    let warnings = validate(&world, &assets);
+    assert_eq!(
+        warnings,
+        vec![
+            Warning::NonFiniteTransform { entity },
+            Warning::ZeroScale { entity }
+        ]
+    );
+}
+
+#[test]
+fn test_warn_once() {
+    let w = Warning::ZeroScale { entity: 1234 };
+    assert!(warn_once(w.clone()));
+    assert!(!warn_once(w));
+}
+
+#[test]
+fn test_warn_once_removed_entity() {
+    let mut world = World::new();
+    let entity = world
+        .add_entity(EntityBuilder::new().create().unwrap())
+        .upgrade()
+        .unwrap()
+        .borrow()
+        .get_id();
+
+    let w = Warning::ZeroScale { entity };
+    assert_eq!(w.get_entity(), entity);
+    assert!(warn_once(w.clone()));
+    assert!(!warn_once(w.clone()));
+
+    //Removing the entity forgets its warnings
+    world.remove_entity_by_id(entity).unwrap();
+    assert!(warn_once(w));
+}
+
+#[test]
+fn test_renderable() {
+    let mut world = World::new();
+    let assets = AssetStore::new();
+
+    let e = world.add_entity(
+        EntityBuilder::new()
+            .add_component::<Transform>()
+            .add_component::<Mesh>()
+            .create()
+            .unwrap(),
+    );
+    let e = e.upgrade().unwrap();
+    let entity = e.borrow().get_id();
+    let mesh = e.borrow().get_component::<Mesh>().unwrap();
+
+    assert_eq!(mesh.borrow().get_entity_id(), Some(entity));
+    assert_eq!(
+        renderable(&mesh.borrow(), &assets),
+        Err(Warning::MissingMesh { entity })
+    );
+
+    mesh.borrow_mut().set_mesh(10);
+    assert_eq!(
+        renderable(&mesh.borrow(), &assets),
+        Err(Warning::UnknownAsset { entity, asset: 10 })
+    );
+}
+
+#[test]
+fn test_validate_skinned() {
+    use crate::{
+        assets::{self, materials::ColorUnlit},
+        structures::Color,
+    };
+
+    //Validation initializes the mesh to read its joints
+    crate::test_utils::generate_gpu();
+    let mut world = World::new();
+    let mut assets = AssetStore::new();
+    let mesh = assets.register(assets::Mesh::new_box(Vec3::new(1.0, 1.0, 1.0)));
+    let material = assets.register(ColorUnlit::new(Color::white()));
+
+    let e = world.add_entity(
+        EntityBuilder::new()
+            .add_component::<Transform>()
+            .add_existing_component(Mesh::new(mesh, material))
+            .create()
+            .unwrap(),
+    );
+    let entity = e.upgrade().unwrap().borrow().get_id();
+
+    //Meshes without joints need neither an animator nor a material supporting skinning
+    let warnings = validate(&world, &assets);
+    assert!(!warnings.contains(&Warning::MissingAnimator { entity }));
+    assert!(!warnings.contains(&Warning::SkinningUnsupported { entity }));
+}
+
+#[test]
+fn test_validate_skeleton() {
+    use std::sync::Arc;
+
+    use crate::{
+        assets::{skeleton::ClipError, Skeleton},
+        components::animator::{
+            AnimationClip, Animator, Armature, Channel, ChannelValues, Interpolation,
+        },
+    };
+
+    let mut world = World::new();
+    let mut assets = AssetStore::new();
+    let skeleton = assets.register(Skeleton::new("skeleton", Arc::new(Armature::default())));
+    let animator = {
+        let skeleton = assets.get_by_id::<Skeleton>(skeleton).unwrap();
+        let skeleton = skeleton.borrow();
+        Animator::from_skeleton(&skeleton)
+    };
+
+    let e = world.add_entity(
+        EntityBuilder::new()
+            .add_existing_component(animator)
+            .create()
+            .unwrap(),
+    );
+    let e = e.upgrade().unwrap();
+    let entity = e.borrow().get_id();
+    assert!(validate(&world, &assets).is_empty());
+
+    //The armature has no joints to animate
+    let clip = AnimationClip::new(
+        "clip".to_owned(),
+        vec![Channel {
+            joint: 0,
+            times: vec![0.0],
+            values: ChannelValues::Scale(vec![Vec3::new(1.0, 1.0, 1.0)]),
+            interpolation: Interpolation::Step,
+        }],
+    );
+    let animator = e.borrow().get_component::<Animator>().unwrap();
+    animator.borrow_mut().play(Arc::new(clip));
+    assert_eq!(
+        validate(&world, &assets),
+        vec![Warning::InvalidClip {
+            entity,
+            error: ClipError::Unbound
+        }]
+    );
+
+    animator
+        .borrow_mut()
+        .set_armature(Arc::new(Armature::default()));
+    assert!(validate(&world, &assets).is_empty());
+}
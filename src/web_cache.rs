@@ -0,0 +1,210 @@
+//! Persistent cache for the web target, backed by IndexedDB
+//!
+//! Browsers only allow asynchronous access to IndexedDB, so the results of all operations are
+//! passed to callbacks, which are called from the browser event loop.
+//!
+//! Every entry is stored along with a version string, reading an entry with a different version
+//! removes it and returns `None`. Bumping the version of the assets therefore invalidates all of
+//! the bytes cached by the previous version of the application.
+//!
+//! ```ignore
+//! use lunar_engine::web_cache::{self, Store};
+//!
+//! web_cache::open(|cache| {
+//!     let cache = cache.unwrap();
+//!     cache.put(Store::Assets, "models/box.obj", "1.0", &bytes);
+//!     cache.get(Store::Assets, "models/box.obj", "1.0", |bytes| {
+//!         //Some(bytes) if the entry exists and has the same version
+//!     });
+//! });
+//! ```
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{js_sys, IdbDatabase, IdbObjectStore, IdbRequest, IdbTransactionMode};
+
+///Name of the database used by the engine
+const DATABASE: &str = "lunar-engine";
+///Version of the database layout, must be increased when adding stores
+const DATABASE_VERSION: u32 = 1;
+
+///Object store of the cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Store {
+    ///Bytes of fetched assets
+    Assets,
+    ///Saved data of the application
+    Saves,
+}
+
+impl Store {
+    const ALL: [Self; 2] = [Self::Assets, Self::Saves];
+
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Assets => "assets",
+            Self::Saves => "saves",
+        }
+    }
+}
+
+///Opened cache database
+#[derive(Debug, Clone)]
+pub struct Cache {
+    database: IdbDatabase,
+}
+
+///Calls `f` once the request finishes, with the result of the request
+fn on_finish(request: &IdbRequest, f: impl FnOnce(Result<JsValue, String>) + 'static) {
+    let success = request.clone();
+    let error = request.clone();
+    let f = std::rc::Rc::new(std::cell::Cell::new(Some(f)));
+    let f_error = f.clone();
+
+    let on_success = Closure::once_into_js(move || {
+        if let Some(f) = f.take() {
+            f(success.result().map_err(|e| format!("{e:?}")));
+        }
+    });
+    let on_error = Closure::once_into_js(move || {
+        if let Some(f) = f_error.take() {
+            let message = error
+                .error()
+                .ok()
+                .flatten()
+                .map_or_else(|| "Unknown error".to_owned(), |e| e.message());
+            f(Err(message));
+        }
+    });
+
+    request.set_onsuccess(Some(on_success.unchecked_ref()));
+    request.set_onerror(Some(on_error.unchecked_ref()));
+}
+
+///Opens the cache database, creating it if it does not exist
+///
+///`callback` receives an error if IndexedDB is not available, for example in private browsing
+///windows of some browsers
+pub fn open(callback: impl FnOnce(Result<Cache, String>) + 'static) {
+    let factory = match web_sys::window().map(|w| w.indexed_db()) {
+        Some(Ok(Some(f))) => f,
+        Some(Err(e)) => return callback(Err(format!("{e:?}"))),
+        _ => return callback(Err("IndexedDB is not available".to_owned())),
+    };
+
+    let request = match factory.open_with_u32(DATABASE, DATABASE_VERSION) {
+        Ok(r) => r,
+        Err(e) => return callback(Err(format!("{e:?}"))),
+    };
+
+    //Creates the stores of a new or outdated database
+    let upgrade = request.clone();
+    let on_upgrade = Closure::once_into_js(move || {
+        let Ok(database) = upgrade.result() else {
+            return;
+        };
+        let database = database.unchecked_into::<IdbDatabase>();
+        let existing = database.object_store_names();
+
+        for store in Store::ALL {
+            if !existing.contains(store.name()) {
+                if let Err(e) = database.create_object_store(store.name()) {
+                    log::error!("Failed to create the {} cache store: {e:?}", store.name());
+                }
+            }
+        }
+    });
+    request.set_onupgradeneeded(Some(on_upgrade.unchecked_ref()));
+
+    on_finish(&request, move |r| {
+        callback(r.map(|d| Cache {
+            database: d.unchecked_into(),
+        }));
+    });
+}
+
+impl Cache {
+    fn store(&self, store: Store, mode: IdbTransactionMode) -> Result<IdbObjectStore, String> {
+        self.database
+            .transaction_with_str_and_mode(store.name(), mode)
+            .and_then(|t| t.object_store(store.name()))
+            .map_err(|e| format!("{e:?}"))
+    }
+
+    ///Reads the bytes of the entry `key`, `callback` receives `None` if the entry does not exist,
+    ///could not be read or was stored with a different `version`
+    ///
+    ///Entries with a different version are removed
+    pub fn get(
+        &self,
+        store: Store,
+        key: &str,
+        version: &str,
+        callback: impl FnOnce(Option<Vec<u8>>) + 'static,
+    ) {
+        let request = match self
+            .store(store, IdbTransactionMode::Readonly)
+            .and_then(|s| s.get(&key.into()).map_err(|e| format!("{e:?}")))
+        {
+            Ok(r) => r,
+            Err(e) => {
+                log::warn!("Failed to read {key} from the cache: {e}");
+                return callback(None);
+            }
+        };
+
+        let cache = self.clone();
+        let key = key.to_owned();
+        let version = version.to_owned();
+        on_finish(&request, move |r| {
+            let entry = match r {
+                Ok(e) if e.is_undefined() => return callback(None),
+                Ok(e) => e.unchecked_into::<js_sys::Array>(),
+                Err(e) => {
+                    log::warn!("Failed to read {key} from the cache: {e}");
+                    return callback(None);
+                }
+            };
+
+            if entry.get(0).as_string().as_deref() != Some(version.as_str()) {
+                log::debug!("Removing outdated cache entry {key}");
+                cache.remove(store, &key);
+                return callback(None);
+            }
+            callback(Some(js_sys::Uint8Array::new(&entry.get(1)).to_vec()));
+        });
+    }
+
+    ///Writes the `bytes` into the entry `key`, replacing the previous entry
+    pub fn put(&self, store: Store, key: &str, version: &str, bytes: &[u8]) {
+        let entry = js_sys::Array::of2(&version.into(), &js_sys::Uint8Array::from(bytes).into());
+
+        if let Err(e) = self
+            .store(store, IdbTransactionMode::Readwrite)
+            .and_then(|s| {
+                s.put_with_key(&entry, &key.into())
+                    .map_err(|e| format!("{e:?}"))
+            })
+        {
+            log::warn!("Failed to write {key} into the cache: {e}");
+        }
+    }
+
+    ///Removes the entry `key`
+    pub fn remove(&self, store: Store, key: &str) {
+        if let Err(e) = self
+            .store(store, IdbTransactionMode::Readwrite)
+            .and_then(|s| s.delete(&key.into()).map_err(|e| format!("{e:?}")))
+        {
+            log::warn!("Failed to remove {key} from the cache: {e}");
+        }
+    }
+
+    ///Removes all entries of the `store`
+    pub fn clear(&self, store: Store) {
+        if let Err(e) = self
+            .store(store, IdbTransactionMode::Readwrite)
+            .and_then(|s| s.clear().map_err(|e| format!("{e:?}")))
+        {
+            log::warn!("Failed to clear the {} cache: {e}", store.name());
+        }
+    }
+}
@@ -0,0 +1,286 @@
+//! The window and the gpu it renders with
+//!
+//! See [`monitor`] for the monitors and fullscreen
+#![allow(clippy::too_many_lines)]
+use std::sync::RwLock;
+
+use wgpu::{util::StagingBelt, Backends, Surface, SurfaceConfiguration, Texture};
+use winit::window::Window;
+
+use crate::{DEPTH, DEVICE, FORMAT, QUEUE, RESOLUTION, STAGING_BELT, SURFACE};
+
+pub mod monitor;
+#[cfg(test)]
+mod tests;
+
+pub use monitor::{
+    current_monitor, fullscreen, monitors, primary_monitor, scale_factor, set_fullscreen,
+    take_monitor_events, Fullscreen, Monitor, MonitorEvent, VideoMode,
+};
+
+//Kept for the report of the resources that are still alive after the shutdown
+#[cfg(not(target_arch = "wasm32"))]
+static INSTANCE: std::sync::OnceLock<wgpu::Instance> = std::sync::OnceLock::new();
+
+pub(crate) fn initialize_gpu(window: &Window) -> (Surface<'_>, SurfaceConfiguration, Texture) {
+    let mut size = window.inner_size();
+    size.width = size.width.max(1);
+    size.height = size.width.max(1);
+    log::debug!("Window size is {size:?}");
+    *RESOLUTION.write().unwrap() = size;
+
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: Backends::GL | Backends::VULKAN,
+        ..Default::default()
+    });
+
+    let surface = instance
+        .create_surface(window)
+        .expect("Failed to createate surface");
+
+    log::debug!("Created surface");
+
+    let adapter: wgpu::Adapter = futures::executor::block_on(req_adapter(
+        &instance,
+        &wgpu::RequestAdapterOptions {
+            compatible_surface: Some(&surface),
+            ..Default::default()
+        },
+    ))
+    .expect("Failed to get an adapter");
+
+    log::debug!("Acquired an adapter");
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        _ = INSTANCE.set(instance);
+    }
+
+    #[cfg(feature = "webgl")]
+    let limits = wgpu::Limits::downlevel_webgl2_defaults();
+
+    #[cfg(not(feature = "webgl"))]
+    let limits = wgpu::Limits::default();
+
+    //Push constants are not available on the web
+    #[cfg(not(target_arch = "wasm32"))]
+    let push_constants = adapter.features().contains(wgpu::Features::PUSH_CONSTANTS);
+    #[cfg(target_arch = "wasm32")]
+    let push_constants = false;
+    crate::rendering::draw_data::set_push_constants_supported(push_constants);
+
+    //Binding arrays are not available on the web
+    #[cfg(not(target_arch = "wasm32"))]
+    let bindless = crate::rendering::bindless::adapter_supported(&adapter);
+    #[cfg(target_arch = "wasm32")]
+    let bindless = false;
+    crate::rendering::bindless::set_supported(bindless);
+
+    //Only used for debug views, so it's optional
+    let wireframe = adapter
+        .features()
+        .contains(wgpu::Features::POLYGON_MODE_LINE);
+
+    //Without them the instance groups are drawn directly
+    let indirect = adapter.features() & crate::rendering::indirect::FEATURES;
+
+    //Only used for profiling, so it's optional
+    let timestamps = adapter
+        .features()
+        .contains(crate::rendering::profiling::FEATURES);
+    crate::rendering::profiling::set_supported(timestamps);
+
+    let (device, queue): (wgpu::Device, wgpu::Queue) = {
+        let r = futures::executor::block_on(req_device(
+            &adapter,
+            &wgpu::DeviceDescriptor {
+                required_features: if push_constants {
+                    wgpu::Features::PUSH_CONSTANTS
+                } else {
+                    wgpu::Features::empty()
+                } | if bindless {
+                    crate::rendering::bindless::FEATURES
+                } else {
+                    wgpu::Features::empty()
+                } | if wireframe {
+                    wgpu::Features::POLYGON_MODE_LINE
+                } else {
+                    wgpu::Features::empty()
+                } | if timestamps {
+                    crate::rendering::profiling::FEATURES
+                } else {
+                    wgpu::Features::empty()
+                } | indirect,
+                required_limits: wgpu::Limits {
+                    max_sampled_textures_per_shader_stage: if bindless {
+                        crate::rendering::bindless::MAX_TEXTURES
+                    } else {
+                        limits.max_sampled_textures_per_shader_stage
+                    },
+                    max_push_constant_size: if push_constants {
+                        std::mem::size_of::<crate::rendering::draw_data::DrawData>() as u32
+                    } else {
+                        limits.max_push_constant_size
+                    },
+                    ..limits
+                },
+                ..Default::default()
+            },
+        ));
+        if let Err(e) = r {
+            log::error!("Error while getting device {e}");
+            panic!();
+        }
+        r.unwrap()
+    };
+    log::debug!("Created device and queue");
+
+    crate::errors::set_uncaptured_error_handler(&device);
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        DEVICE
+            .set(crate::wrappers::WgpuWrapper::new(device))
+            .unwrap();
+        QUEUE.set(crate::wrappers::WgpuWrapper::new(queue)).unwrap();
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        DEVICE.set(device).unwrap();
+        QUEUE.set(queue).unwrap();
+    }
+
+    let device = DEVICE.get().unwrap();
+
+    let capabilities = surface.get_capabilities(&adapter);
+    let format =
+        crate::rendering::surface::pick(&capabilities.formats).expect("Did not have last format");
+
+    log::debug!("Picked a format");
+
+    FORMAT.set(format).unwrap();
+    crate::rendering::capabilities::initialize(
+        &adapter,
+        &device.limits(),
+        format,
+        &capabilities,
+    );
+    crate::rendering::msaa::validate();
+    crate::rendering::depth::validate();
+    assert!(
+        capabilities.usages & wgpu::TextureUsages::RENDER_ATTACHMENT
+            == wgpu::TextureUsages::RENDER_ATTACHMENT,
+        "Rendering not supported... What shitty ancient piece of shit are you fucking using wtf?"
+    );
+
+    let surface_config = wgpu::SurfaceConfiguration {
+        usage: if capabilities.usages & wgpu::TextureUsages::COPY_SRC
+            == wgpu::TextureUsages::COPY_SRC
+        {
+            crate::rendering::screenshot::set_supported(true);
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC
+        } else {
+            log::warn!("Screenshot feature not supported!");
+            wgpu::TextureUsages::RENDER_ATTACHMENT
+        },
+        format,
+        width: size.width,
+        height: size.height,
+        present_mode: wgpu::PresentMode::AutoNoVsync,
+        view_formats: vec![format],
+        alpha_mode: crate::rendering::surface::pick_alpha_mode(&capabilities.alpha_modes),
+        desired_maximum_frame_latency: 2,
+    };
+    surface.configure(device, &surface_config);
+
+    log::debug!("Configured the surface");
+
+    let desc = get_depth_descriptor(size.width, size.height);
+    let depth_stencil = device.create_texture(&desc);
+
+    log::debug!("Created depth texture");
+
+    let belt = StagingBelt::new(2048);
+
+    log::debug!("Created staging belt");
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        STAGING_BELT
+            .set(RwLock::new(crate::wrappers::WgpuWrapper::new(belt)))
+            .unwrap();
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        STAGING_BELT.set(RwLock::new(belt)).unwrap();
+    }
+
+    super::input::initialize();
+
+    (surface, surface_config, depth_stencil)
+}
+
+#[allow(clippy::future_not_send)]
+async fn req_adapter<'a, 'b>(
+    instance: &wgpu::Instance,
+    options: &wgpu::RequestAdapterOptions<'a, 'b>,
+) -> Option<wgpu::Adapter> {
+    instance.request_adapter(options).await
+}
+
+#[allow(clippy::future_not_send)]
+async fn req_device<'a>(
+    adapter: &wgpu::Adapter,
+    descriptor: &wgpu::DeviceDescriptor<'a>,
+) -> Result<(wgpu::Device, wgpu::Queue), wgpu::RequestDeviceError> {
+    adapter.request_device(descriptor, None).await
+}
+
+///Waits for the gpu to finish its work and destroys the resources created with the device, followed
+///by the device itself
+pub(crate) fn shutdown_gpu() {
+    let device = DEVICE.get().unwrap();
+    device.poll(wgpu::Maintain::Wait);
+
+    //The staging belt keeps its buffers until it's dropped
+    #[cfg(target_arch = "wasm32")]
+    {
+        *STAGING_BELT.get().unwrap().write().unwrap() =
+            crate::wrappers::WgpuWrapper::new(StagingBelt::new(0));
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        *STAGING_BELT.get().unwrap().write().unwrap() = StagingBelt::new(0);
+    }
+
+    *DEPTH.get().unwrap().write().unwrap() = None;
+    *SURFACE.get().unwrap().write().unwrap() = None;
+
+    //Resources still referenced elsewhere are unusable from now on, on the web this also releases
+    //the device, which would otherwise stay alive with the page
+    device.destroy();
+    log::debug!("Destroyed the device");
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(report) = INSTANCE.get().and_then(wgpu::Instance::generate_report) {
+        log::debug!("Gpu resources alive after the shutdown: {report:#?}");
+    }
+}
+
+pub(crate) fn get_depth_descriptor<'a>(width: u32, height: u32) -> wgpu::TextureDescriptor<'a> {
+    wgpu::TextureDescriptor {
+        label: Some("Depth stencil"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: crate::rendering::msaa::sample_count(),
+        dimension: wgpu::TextureDimension::D2,
+        format: crate::rendering::depth::format(),
+        usage: crate::rendering::depth::usage(),
+        view_formats: &[],
+    }
+}
@@ -0,0 +1,275 @@
+//! Monitors and fullscreen
+//!
+//! The monitors are only known once the window is created, before that [`monitors`] returns an
+//! empty list. Fullscreen set before the window is created is applied when it's created, which is
+//! only useful for [`Fullscreen::Borderless`] on the current monitor.
+//!
+//!```no_run
+//! use lunar_engine::windowing::{self, Fullscreen};
+//!
+//! for monitor in windowing::monitors() {
+//!     println!("{} {:?}", monitor.get_name(), monitor.get_refresh_rate());
+//! }
+//!
+//! //Exclusive fullscreen in the highest resolution of the primary monitor
+//! if let Some(mode) = windowing::primary_monitor().and_then(|m| m.best_video_mode()) {
+//!     windowing::set_fullscreen(Fullscreen::Exclusive(mode));
+//! }
+//!```
+//!
+//! On the web fullscreen can only be entered during user input, see
+//! `platform::web::request_fullscreen`.
+use std::sync::Mutex;
+
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    monitor::{MonitorHandle, VideoModeHandle},
+};
+
+use crate::WINDOW;
+
+///A monitor connected to the system
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Monitor {
+    handle: MonitorHandle,
+}
+
+impl Monitor {
+    ///Returns the name of the monitor, empty if it's unknown
+    #[must_use]
+    pub fn get_name(&self) -> String {
+        self.handle.name().unwrap_or_default()
+    }
+
+    ///Returns the current resolution of the monitor
+    #[must_use]
+    pub fn get_resolution(&self) -> PhysicalSize<u32> {
+        self.handle.size()
+    }
+
+    ///Returns the position of the top left corner of the monitor on the desktop
+    #[must_use]
+    pub fn get_position(&self) -> PhysicalPosition<i32> {
+        self.handle.position()
+    }
+
+    ///Returns the current refresh rate of the monitor in hertz, `None` if it's unknown
+    #[must_use]
+    pub fn get_refresh_rate(&self) -> Option<f32> {
+        self.handle.refresh_rate_millihertz().map(|r| r as f32 / 1000.0)
+    }
+
+    ///Returns the scale factor of the monitor, the number of physical pixels per logical pixel
+    #[must_use]
+    pub fn get_scale_factor(&self) -> f64 {
+        self.handle.scale_factor()
+    }
+
+    ///Returns the video modes supported by the monitor, for [`Fullscreen::Exclusive`]
+    #[must_use]
+    pub fn get_video_modes(&self) -> Vec<VideoMode> {
+        self.handle
+            .video_modes()
+            .map(|handle| VideoMode { handle })
+            .collect()
+    }
+
+    ///Returns the video mode with the highest resolution, then refresh rate and then bit depth
+    #[must_use]
+    pub fn best_video_mode(&self) -> Option<VideoMode> {
+        self.get_video_modes().into_iter().max_by_key(|m| {
+            let size = m.handle.size();
+            (
+                u64::from(size.width) * u64::from(size.height),
+                m.handle.refresh_rate_millihertz(),
+                m.handle.bit_depth(),
+            )
+        })
+    }
+
+    ///Returns `true` if it's the primary monitor of the system
+    #[must_use]
+    pub fn is_primary(&self) -> bool {
+        primary_monitor().is_some_and(|m| m == *self)
+    }
+}
+
+///Resolution, refresh rate and bit depth a monitor can be switched to in exclusive fullscreen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VideoMode {
+    handle: VideoModeHandle,
+}
+
+impl VideoMode {
+    ///Returns the resolution
+    #[must_use]
+    pub fn get_resolution(&self) -> PhysicalSize<u32> {
+        self.handle.size()
+    }
+
+    ///Returns the refresh rate in hertz
+    #[must_use]
+    pub fn get_refresh_rate(&self) -> f32 {
+        self.handle.refresh_rate_millihertz() as f32 / 1000.0
+    }
+
+    ///Returns the number of bits per pixel
+    #[must_use]
+    pub fn get_bit_depth(&self) -> u16 {
+        self.handle.bit_depth()
+    }
+
+    ///Returns the monitor of the video mode
+    #[must_use]
+    pub fn get_monitor(&self) -> Monitor {
+        Monitor {
+            handle: self.handle.monitor(),
+        }
+    }
+}
+
+///Fullscreen state of the window
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Fullscreen {
+    ///Not fullscreen
+    #[default]
+    Windowed,
+    ///Borderless window covering the monitor, `None` for the monitor the window is on
+    Borderless(Option<Monitor>),
+    ///Exclusive fullscreen, switching the monitor of the video mode to it
+    Exclusive(VideoMode),
+}
+
+impl Fullscreen {
+    fn to_winit(&self) -> Option<winit::window::Fullscreen> {
+        match self {
+            Self::Windowed => None,
+            Self::Borderless(monitor) => Some(winit::window::Fullscreen::Borderless(
+                monitor.as_ref().map(|m| m.handle.clone()),
+            )),
+            Self::Exclusive(mode) => {
+                Some(winit::window::Fullscreen::Exclusive(mode.handle.clone()))
+            }
+        }
+    }
+
+    fn from_winit(fullscreen: Option<winit::window::Fullscreen>) -> Self {
+        match fullscreen {
+            None => Self::Windowed,
+            Some(winit::window::Fullscreen::Borderless(handle)) => {
+                Self::Borderless(handle.map(|handle| Monitor { handle }))
+            }
+            Some(winit::window::Fullscreen::Exclusive(handle)) => {
+                Self::Exclusive(VideoMode { handle })
+            }
+        }
+    }
+}
+
+///Change of the monitor the window is on, see [`take_monitor_events`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum MonitorEvent {
+    ///The window moved to another monitor
+    Changed(Monitor),
+    ///The scale factor of the window changed, the window is resized to match it right after
+    ScaleFactorChanged(f64),
+}
+
+//Fullscreen requested before the window was created
+static PENDING: Mutex<Option<Fullscreen>> = Mutex::new(None);
+//Monitor the window was last seen on
+static CURRENT: Mutex<Option<Monitor>> = Mutex::new(None);
+static EVENTS: Mutex<Vec<MonitorEvent>> = Mutex::new(Vec::new());
+
+///Returns the monitors connected to the system
+#[must_use]
+pub fn monitors() -> Vec<Monitor> {
+    WINDOW.get().map_or_else(Vec::new, |w| {
+        w.available_monitors()
+            .map(|handle| Monitor { handle })
+            .collect()
+    })
+}
+
+///Returns the primary monitor, `None` if it can't be determined
+#[must_use]
+pub fn primary_monitor() -> Option<Monitor> {
+    WINDOW.get()?.primary_monitor().map(|handle| Monitor { handle })
+}
+
+///Returns the monitor the window is on
+#[must_use]
+pub fn current_monitor() -> Option<Monitor> {
+    WINDOW.get()?.current_monitor().map(|handle| Monitor { handle })
+}
+
+///Returns the scale factor of the window, 1 before it's created
+#[must_use]
+pub fn scale_factor() -> f64 {
+    WINDOW.get().map_or(1.0, winit::window::Window::scale_factor)
+}
+
+///Sets the fullscreen state of the window
+pub fn set_fullscreen(fullscreen: Fullscreen) {
+    let Some(window) = WINDOW.get() else {
+        *PENDING.lock().unwrap() = Some(fullscreen);
+        return;
+    };
+    log::debug!("Setting fullscreen to {fullscreen:?}");
+    window.set_fullscreen(fullscreen.to_winit());
+}
+
+///Returns the fullscreen state of the window
+#[must_use]
+pub fn fullscreen() -> Fullscreen {
+    WINDOW.get().map_or_else(
+        || PENDING.lock().unwrap().clone().unwrap_or_default(),
+        |w| Fullscreen::from_winit(w.fullscreen()),
+    )
+}
+
+///Returns all the monitor events since the last call, clearing the queue
+#[must_use]
+pub fn take_monitor_events() -> Vec<MonitorEvent> {
+    std::mem::take(&mut *EVENTS.lock().unwrap())
+}
+
+///Returns the fullscreen the window is created with
+pub(crate) fn initial_fullscreen() -> Option<winit::window::Fullscreen> {
+    PENDING.lock().unwrap().take().and_then(|f| f.to_winit())
+}
+
+///Remembers the monitor the window was created on
+pub(crate) fn initialize() {
+    *CURRENT.lock().unwrap() = current_monitor();
+}
+
+///Emits the monitor events for the event of the window
+pub(crate) fn process_event(event: &winit::event::WindowEvent) {
+    match event {
+        winit::event::WindowEvent::Moved(_) => check_monitor(),
+        winit::event::WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+            check_monitor();
+            log::debug!("Scale factor changed to {scale_factor}");
+            EVENTS
+                .lock()
+                .unwrap()
+                .push(MonitorEvent::ScaleFactorChanged(*scale_factor));
+        }
+        _ => {}
+    }
+}
+
+fn check_monitor() {
+    let current = current_monitor();
+    let mut previous = CURRENT.lock().unwrap();
+    if *previous == current {
+        return;
+    }
+    previous.clone_from(&current);
+
+    if let Some(monitor) = current {
+        log::debug!("Window moved to the monitor {}", monitor.get_name());
+        EVENTS.lock().unwrap().push(MonitorEvent::Changed(monitor));
+    }
+}
@@ -0,0 +1,19 @@
+use super::{monitor, Fullscreen};
+
+#[test]
+fn fullscreen_before_window() {
+    //There's no window in the tests
+    assert!(super::monitors().is_empty());
+    assert!(super::primary_monitor().is_none());
+    assert!((super::scale_factor() - 1.0).abs() < f64::EPSILON);
+
+    super::set_fullscreen(Fullscreen::Borderless(None));
+    assert_eq!(super::fullscreen(), Fullscreen::Borderless(None));
+    assert_eq!(
+        monitor::initial_fullscreen(),
+        Some(winit::window::Fullscreen::Borderless(None))
+    );
+    //Applied to the window when it's created
+    assert_eq!(super::fullscreen(), Fullscreen::Windowed);
+    assert!(monitor::initial_fullscreen().is_none());
+}